@@ -0,0 +1,3 @@
+#![no_main]
+
+sy_fuzz::fuzz_message!(sy::server::protocol::FileData);
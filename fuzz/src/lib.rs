@@ -0,0 +1,38 @@
+//! Shared driver for the server protocol fuzz targets
+//!
+//! Each target feeds raw payload bytes (everything after the 5-byte frame
+//! header) to one message's `from_reader`. Decoding must never panic, and
+//! whatever decodes must re-encode to a frame that decodes to the same bytes.
+
+pub use libfuzzer_sys;
+
+pub fn block_on<F: std::future::Future>(f: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap()
+        .block_on(f)
+}
+
+/// Define a fuzz target for one message type
+#[macro_export]
+macro_rules! fuzz_message {
+    ($ty:ty) => {
+        $crate::libfuzzer_sys::fuzz_target!(|data: &[u8]| {
+            let Ok(msg) = $crate::block_on(<$ty>::from_reader(&mut std::io::Cursor::new(data)))
+            else {
+                return;
+            };
+            let mut first = Vec::new();
+            $crate::block_on(msg.write(&mut first)).unwrap();
+            let len = u32::from_be_bytes(first[..4].try_into().unwrap()) as usize;
+            assert_eq!(len, first.len() - 5, "frame length");
+
+            let again =
+                $crate::block_on(<$ty>::from_reader(&mut std::io::Cursor::new(&first[5..])))
+                    .expect("re-encoded message decodes");
+            let mut second = Vec::new();
+            $crate::block_on(again.write(&mut second)).unwrap();
+            assert_eq!(first, second, "encoding is stable");
+        });
+    };
+}
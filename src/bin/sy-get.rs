@@ -1,6 +1,6 @@
 //! sy-get - Download files from remote backends to local
 //!
-//! This tool downloads files and directories from SSH, S3, and GCS backends to local filesystem,
+//! This tool downloads files and directories from SSH, S3, GCS, and Azure backends to local filesystem,
 //! similar to `rclone copy {remote} {local}`.
 //!
 //! For SSH sources, this uses sy's server protocol (spawning `sy --server` on remote)
@@ -22,19 +22,28 @@ use tracing_subscriber::{fmt, EnvFilter};
 #[cfg(feature = "ssh")]
 use sy::ssh::config::{parse_ssh_config, SshConfig};
 #[cfg(feature = "ssh")]
+use sy::transport::sftp::SftpTransport;
+#[cfg(feature = "ssh")]
 use sy::transport::ssh::SshTransport;
 
 #[cfg(feature = "s3")]
 use sy::transport::s3::S3Transport;
 
+#[cfg(feature = "azure")]
+use sy::transport::azure::AzureTransport;
 #[cfg(feature = "gcs")]
 use sy::transport::gcs::GcsTransport;
+#[cfg(feature = "webdav")]
+use sy::transport::webdav::WebDavTransport;
 
 // Server protocol for high-performance SSH transfers
 #[cfg(feature = "ssh")]
-use sy::server::protocol::Action;
+use sy::server::confine::Confinement;
 #[cfg(feature = "ssh")]
-use sy::transport::server::ServerSession;
+use sy::server::layout;
+#[cfg(feature = "ssh")]
+use sy::server::protocol::Action;
+use sy::transport::server::{PullSession, ServerSession, SessionOptions};
 
 fn parse_sync_path(s: &str) -> Result<SyncPath, String> {
     Ok(SyncPath::parse(s))
@@ -42,7 +51,7 @@ fn parse_sync_path(s: &str) -> Result<SyncPath, String> {
 
 #[derive(Parser, Debug)]
 #[command(name = "sy-get")]
-#[command(about = "Download files from remote storage (S3, GCS, SSH)", long_about = None)]
+#[command(about = "Download files from remote storage (S3, GCS, Azure, SSH)", long_about = None)]
 #[command(version)]
 #[command(after_help = "EXAMPLES:
     # Download a single file from S3
@@ -68,7 +77,7 @@ fn parse_sync_path(s: &str) -> Result<SyncPath, String> {
 
 For more information: https://github.com/nijaru/sy")]
 struct Cli {
-    /// Remote source path (SSH, S3, GCS)
+    /// Remote source path (SSH, S3, GCS, Azure)
     /// Examples: user@host:/path, s3://bucket/path, gs://bucket/path
     #[arg(value_parser = parse_sync_path)]
     pub source: SyncPath,
@@ -439,6 +448,8 @@ async fn download_via_server_protocol(
     if !dest.exists() {
         std::fs::create_dir_all(dest)?;
     }
+    // Paths come from the server; keep every write beneath dest
+    let confine = Confinement::new(dest);
 
     // Scan local destination for comparison (to skip unchanged files)
     let local_entries: HashMap<String, (u64, i64)> = if dest.exists() {
@@ -475,7 +486,7 @@ async fn download_via_server_protocol(
     };
 
     // Connect to server in PULL mode
-    let mut session = ServerSession::connect_ssh_pull(config, source)
+    let mut session = ServerSession::connect_ssh_pull(config, source, SessionOptions::default())
         .await
         .context(
             "Failed to connect to sy --server on remote. Is 'sy' installed on the remote host?",
@@ -501,7 +512,13 @@ async fn download_via_server_protocol(
             }
         }
 
-        let full_path = dest.join(dir_path);
+        let full_path = match confine.resolve(dir_path) {
+            Ok(p) => p,
+            Err(e) => {
+                failed_dirs.push((dir_path.clone(), e.to_string()));
+                continue;
+            }
+        };
         if cli.dry_run {
             if !cli.quiet && cli.verbose > 0 {
                 println!("Would create directory: {}", full_path.display());
@@ -529,6 +546,7 @@ async fn download_via_server_protocol(
 
     let mut decisions = Vec::with_capacity(file_list.entries.len());
     let mut files_to_receive: Vec<(u32, String, u64)> = Vec::new();
+    let mut hardlinks: Vec<(u32, u32)> = Vec::new();
 
     for (idx, entry) in file_list.entries.iter().enumerate() {
         let entry_path = std::path::Path::new(&entry.path);
@@ -538,6 +556,9 @@ async fn download_via_server_protocol(
             decisions.push(sy::server::protocol::Decision {
                 index: idx as u32,
                 action: Action::Skip,
+                resume_from: 0,
+                digest: sy::integrity::ChecksumType::None,
+                basis: None,
             });
             continue;
         }
@@ -548,13 +569,22 @@ async fn download_via_server_protocol(
                 decisions.push(sy::server::protocol::Decision {
                     index: idx as u32,
                     action: Action::Skip,
+                    resume_from: 0,
+                    digest: sy::integrity::ChecksumType::None,
+                    basis: None,
                 });
                 continue;
             }
         }
 
         // Check if file needs download
-        let action = if let Some((local_size, local_mtime)) = local_entries.get(&entry.path) {
+        let action = if let Err(e) = confine.resolve(&entry.path) {
+            result.failed.push(FailedDownload {
+                path: entry.path.clone(),
+                error: e.to_string(),
+            });
+            Action::Skip
+        } else if let Some((local_size, local_mtime)) = local_entries.get(&entry.path) {
             if *local_size == entry.size && *local_mtime >= entry.mtime {
                 Action::Skip
             } else {
@@ -564,15 +594,26 @@ async fn download_via_server_protocol(
             Action::Create
         };
 
+        // Hard links carry no data; they are copied from their leader below
         if action != Action::Skip {
-            files_to_receive.push((idx as u32, entry.path.clone(), entry.size));
+            match entry.hardlink_to {
+                Some(leader) => hardlinks.push((idx as u32, leader)),
+                None => files_to_receive.push((idx as u32, entry.path.clone(), entry.size)),
+            }
         } else if !cli.quiet && cli.verbose > 1 {
             println!("Skipped (up-to-date): {}", entry.path);
         }
 
         decisions.push(sy::server::protocol::Decision {
             index: idx as u32,
-            action,
+            action: if entry.is_hardlink() {
+                Action::Skip
+            } else {
+                action
+            },
+            resume_from: 0,
+            digest: sy::integrity::ChecksumType::None,
+            basis: None,
         });
     }
 
@@ -600,6 +641,9 @@ async fn download_via_server_protocol(
             .map(|idx| sy::server::protocol::Decision {
                 index: idx as u32,
                 action: Action::Skip,
+                resume_from: 0,
+                digest: sy::integrity::ChecksumType::None,
+                basis: None,
             })
             .collect();
         session.send_file_list_ack(skip_decisions).await?;
@@ -611,28 +655,33 @@ async fn download_via_server_protocol(
 
     // Step 3: Receive files (pipelined - receive all, then send all ACKs)
     let mut files_received: Vec<(u32, String, u8)> = Vec::new(); // (idx, path, status)
+    let mut symlinks_next = false;
 
-    for (idx, rel_path, _expected_size) in &files_to_receive {
+    for (idx, rel_path, _) in &files_to_receive {
         let file_data = match session.read_file_data().await? {
             Some(data) => data,
-            None => break, // Server sent symlinks instead
+            None => {
+                // Server sent symlinks instead
+                symlinks_next = true;
+                break;
+            }
         };
 
-        let full_path = dest.join(rel_path);
-
-        // Ensure parent directory exists
-        if let Some(parent) = full_path.parent() {
-            std::fs::create_dir_all(parent)?;
-        }
-
         // Write file
-        let file_size = file_data.data.len() as u64;
-        match std::fs::write(&full_path, &file_data.data) {
-            Ok(_) => {
-                result.downloaded_bytes += file_size;
+        match session
+            .receive_file(
+                &confine,
+                rel_path,
+                &file_list.entries[*idx as usize],
+                file_data,
+            )
+            .await?
+        {
+            Ok(received) => {
+                result.downloaded_bytes += received.bytes;
                 result.downloaded_files += 1;
                 if !cli.quiet && cli.verbose > 0 {
-                    println!("Downloaded: {} ({} bytes)", rel_path, file_size);
+                    println!("Downloaded: {} ({} bytes)", rel_path, received.bytes);
                 }
                 files_received.push((*idx, rel_path.clone(), 0)); // Status OK
             }
@@ -642,7 +691,7 @@ async fn download_via_server_protocol(
                     error: e.to_string(),
                 });
                 if !cli.quiet {
-                    tracing::warn!("Failed to write {}: {}", full_path.display(), e);
+                    tracing::warn!("Failed to write {}: {}", rel_path, e);
                 }
                 files_received.push((*idx, rel_path.clone(), 2)); // STATUS_WRITE_ERROR
             }
@@ -651,19 +700,51 @@ async fn download_via_server_protocol(
 
     // Send all FILE_DONE responses at once (pipelined)
     for (idx, _path, status) in &files_received {
-        session.send_file_done(*idx, *status).await?;
+        session.send_file_done(*idx, *status, Vec::new()).await?;
+    }
+
+    // Copy hard links from the files they link to
+    for (idx, leader) in &hardlinks {
+        let (Some(entry), Some(leader)) = (
+            file_list.entries.get(*idx as usize),
+            file_list.entries.get(*leader as usize),
+        ) else {
+            continue;
+        };
+        let linked = confine.resolve(&leader.path).and_then(|leader| {
+            let path = confine.resolve(&entry.path)?;
+            Ok(layout::link_file(&leader, &path, false)?)
+        });
+        match linked {
+            Ok(()) => result.downloaded_files += 1,
+            Err(e) => result.failed.push(FailedDownload {
+                path: entry.path.clone(),
+                error: e.to_string(),
+            }),
+        }
     }
 
     // Step 4: Handle symlinks (if any)
-    // Note: Server might send symlink batch after files
-    match session.read_symlink_batch_body().await {
+    // Note: Server sends the symlink batch after files, or just ends
+    let symlink_batch = if symlinks_next {
+        session.read_symlink_batch_body().await
+    } else {
+        session.read_symlink_batch().await
+    };
+    match symlink_batch {
         Ok(symlink_batch) => {
             tracing::debug!("Received {} symlinks", symlink_batch.entries.len());
             let mut created = 0u32;
             let mut failed: Vec<(String, String)> = Vec::new();
 
             for entry in &symlink_batch.entries {
-                let link_path = dest.join(&entry.path);
+                let link_path = match confine.resolve(&entry.path) {
+                    Ok(p) => p,
+                    Err(e) => {
+                        failed.push((entry.path.clone(), e.to_string()));
+                        continue;
+                    }
+                };
 
                 // Remove existing if present
                 if link_path.exists() || link_path.symlink_metadata().is_ok() {
@@ -720,7 +801,7 @@ async fn main() -> Result<()> {
     // Validate source is remote
     if cli.source.is_local() {
         anyhow::bail!(
-            "Source must be a remote path (SSH, S3, or GCS). Use sy or cp for local copies."
+            "Source must be a remote path (SSH, S3, GCS, or Azure). Use sy or cp for local copies."
         );
     }
 
@@ -784,6 +865,37 @@ async fn main() -> Result<()> {
                 "SSH support not enabled. Reinstall with: cargo install sy --features ssh"
             );
         }
+        #[cfg(feature = "ssh")]
+        SyncPath::Sftp {
+            host, user, port, ..
+        } => {
+            let mut config = parse_ssh_config(host).unwrap_or_else(|_| SshConfig::new(host));
+            if let Some(user) = user {
+                config.user = user.clone();
+            }
+            if let Some(port) = port {
+                config.port = *port;
+            }
+
+            let ssh =
+                SshTransport::with_retry_config(&config, cli.jobs.max(1), RetryConfig::default())
+                    .await
+                    .context("Failed to create SSH transport")?;
+            let transport = Arc::new(SftpTransport::new(ssh));
+
+            transport
+                .prepare_for_transfer(1000)
+                .await
+                .context("Failed to expand SSH connection pool")?;
+
+            download_from_transport(transport, cli.source.path(), dest, &cli, &path_filter).await?
+        }
+        #[cfg(not(feature = "ssh"))]
+        SyncPath::Sftp { .. } => {
+            anyhow::bail!(
+                "SSH support not enabled. Reinstall with: cargo install sy --features ssh"
+            );
+        }
         #[cfg(feature = "s3")]
         SyncPath::S3 {
             bucket,
@@ -836,7 +948,45 @@ async fn main() -> Result<()> {
                 "GCS support not enabled. Reinstall with: cargo install sy --features gcs"
             );
         }
-        SyncPath::Daemon { .. } => {
+        #[cfg(feature = "azure")]
+        SyncPath::Azure {
+            container,
+            key,
+            account,
+            ..
+        } => {
+            let transport = Arc::new(
+                AzureTransport::new(container.clone(), key.clone(), account.clone())
+                    .await
+                    .context("Failed to create Azure transport")?,
+            );
+
+            download_from_transport(transport, cli.source.path(), dest, &cli, &path_filter).await?
+        }
+        #[cfg(not(feature = "azure"))]
+        SyncPath::Azure { .. } => {
+            anyhow::bail!(
+                "Azure support not enabled. Reinstall with: cargo install sy --features azure"
+            );
+        }
+        #[cfg(feature = "webdav")]
+        SyncPath::WebDav {
+            host, user, secure, ..
+        } => {
+            let transport = Arc::new(
+                WebDavTransport::new(host.clone(), user.clone(), *secure)
+                    .context("Failed to create WebDAV transport")?,
+            );
+
+            download_from_transport(transport, cli.source.path(), dest, &cli, &path_filter).await?
+        }
+        #[cfg(not(feature = "webdav"))]
+        SyncPath::WebDav { .. } => {
+            anyhow::bail!(
+                "WebDAV support not enabled. Reinstall with: cargo install sy --features webdav"
+            );
+        }
+        SyncPath::Daemon { .. } | SyncPath::TcpDaemon { .. } => {
             anyhow::bail!(
                 "Daemon paths are not supported for download. Use SSH paths directly: user@host:/path"
             );
@@ -1,7 +1,7 @@
 //! sy-ls - List directory contents across all supported transports
 //!
 //! This tool provides efficient directory listing similar to `ls` or `rclone lsjson`,
-//! working with local paths, SSH, S3, GCS, and other supported transports.
+//! working with local paths, SSH, S3, GCS, Azure, and other supported transports.
 
 use anyhow::{Context, Result};
 use clap::Parser;
@@ -15,6 +15,8 @@ use tracing_subscriber::{fmt, EnvFilter};
 #[cfg(feature = "ssh")]
 use sy::ssh::config::{parse_ssh_config, SshConfig};
 #[cfg(feature = "ssh")]
+use sy::transport::sftp::SftpTransport;
+#[cfg(feature = "ssh")]
 use sy::transport::ssh::SshTransport;
 
 #[cfg(feature = "s3")]
@@ -23,13 +25,19 @@ use sy::transport::s3::S3Transport;
 #[cfg(feature = "gcs")]
 use sy::transport::gcs::GcsTransport;
 
+#[cfg(feature = "azure")]
+use sy::transport::azure::AzureTransport;
+
+#[cfg(feature = "webdav")]
+use sy::transport::webdav::WebDavTransport;
+
 fn parse_sync_path(s: &str) -> Result<SyncPath, String> {
     Ok(SyncPath::parse(s))
 }
 
 #[derive(Parser, Debug)]
 #[command(name = "sy-ls")]
-#[command(about = "List directory contents (works with local, SSH, S3, GCS)", long_about = None)]
+#[command(about = "List directory contents (works with local, SSH, S3, GCS, Azure)", long_about = None)]
 #[command(version)]
 #[command(after_help = "EXAMPLES:
     # List local directory
@@ -50,6 +58,9 @@ fn parse_sync_path(s: &str) -> Result<SyncPath, String> {
     # List GCS bucket
     sy-ls gs://bucket/path
 
+    # List Azure container
+    sy-ls az://container/path?account=mystorage
+
     # List only files (no directories)
     sy-ls /path --files-only
 
@@ -58,8 +69,8 @@ fn parse_sync_path(s: &str) -> Result<SyncPath, String> {
 
 For more information: https://github.com/nijaru/sy")]
 struct Cli {
-    /// Path to list (local, SSH, S3, GCS, etc.)
-    /// Examples: /path, user@host:/path, s3://bucket/path, gs://bucket/path
+    /// Path to list (local, SSH, S3, GCS, Azure, etc.)
+    /// Examples: /path, user@host:/path, s3://bucket/path, gs://bucket/path, az://container/path
     #[arg(value_parser = parse_sync_path)]
     pub path: SyncPath,
 
@@ -190,6 +201,33 @@ async fn main() -> Result<()> {
                 "SSH support not enabled. Reinstall with: cargo install sy --features ssh"
             );
         }
+        #[cfg(feature = "ssh")]
+        SyncPath::Sftp {
+            host, user, port, ..
+        } => {
+            let mut config = parse_ssh_config(host).unwrap_or_else(|_| SshConfig::new(host));
+            if let Some(user) = user {
+                config.user = user.clone();
+            }
+            if let Some(port) = port {
+                config.port = *port;
+            }
+
+            let ssh = SshTransport::with_retry_config(&config, 1, RetryConfig::default())
+                .await
+                .context("Failed to create SSH transport")?;
+            let transport = SftpTransport::new(ssh);
+
+            list_directory(&transport, cli.path.path(), &list_opts)
+                .await
+                .context("Failed to list remote directory via SFTP")?
+        }
+        #[cfg(not(feature = "ssh"))]
+        SyncPath::Sftp { .. } => {
+            anyhow::bail!(
+                "SSH support not enabled. Reinstall with: cargo install sy --features ssh"
+            );
+        }
         #[cfg(feature = "s3")]
         SyncPath::S3 {
             bucket,
@@ -287,7 +325,69 @@ async fn main() -> Result<()> {
                 "GCS support not enabled. Reinstall with: cargo install sy --features gcs"
             );
         }
-        SyncPath::Daemon { .. } => {
+        #[cfg(feature = "azure")]
+        SyncPath::Azure {
+            container,
+            key,
+            account,
+            ..
+        } => {
+            use sy::transport::{AzureConfig, CloudClientOptions};
+
+            // Use optimized settings for listing (longer timeouts, more connections)
+            let client_options = CloudClientOptions {
+                pool_max_idle_per_host: 50,
+                pool_idle_timeout_secs: 60,
+                connect_timeout_secs: 5,
+                request_timeout_secs: 120, // Generous timeout for large listings
+                max_retries: 1,
+                retry_timeout_secs: 30,
+                allow_http: false,
+            };
+
+            let config = AzureConfig {
+                client_options: Some(client_options),
+                ..Default::default()
+            };
+
+            let transport = AzureTransport::with_config(
+                container.clone(),
+                key.clone(),
+                account.clone(),
+                Some(config),
+                50, // More connections for parallel listing
+            )
+            .await
+            .context("Failed to create Azure transport")?;
+
+            list_directory(&transport, cli.path.path(), &list_opts)
+                .await
+                .context("Failed to list Azure container")?
+        }
+        #[cfg(not(feature = "azure"))]
+        SyncPath::Azure { .. } => {
+            anyhow::bail!(
+                "Azure support not enabled. Reinstall with: cargo install sy --features azure"
+            );
+        }
+        #[cfg(feature = "webdav")]
+        SyncPath::WebDav {
+            host, user, secure, ..
+        } => {
+            let transport = WebDavTransport::new(host.clone(), user.clone(), *secure)
+                .context("Failed to create WebDAV transport")?;
+
+            list_directory(&transport, cli.path.path(), &list_opts)
+                .await
+                .context("Failed to list WebDAV collection")?
+        }
+        #[cfg(not(feature = "webdav"))]
+        SyncPath::WebDav { .. } => {
+            anyhow::bail!(
+                "WebDAV support not enabled. Reinstall with: cargo install sy --features webdav"
+            );
+        }
+        SyncPath::Daemon { .. } | SyncPath::TcpDaemon { .. } => {
             anyhow::bail!("Daemon paths are not supported for listing. Use SSH paths directly: user@host:/path");
         }
     };
@@ -1,6 +1,6 @@
 //! sy-put - Upload files from local to remote backends
 //!
-//! This tool uploads files and directories from local filesystem to SSH, S3, and GCS backends,
+//! This tool uploads files and directories from local filesystem to SSH, S3, GCS, and Azure backends,
 //! similar to `rclone copy {local} {remote}`.
 //!
 //! For SSH destinations, this uses sy's server protocol (spawning `sy --server` on remote)
@@ -22,13 +22,19 @@ use tracing_subscriber::{fmt, EnvFilter};
 #[cfg(feature = "ssh")]
 use sy::ssh::config::{parse_ssh_config, SshConfig};
 #[cfg(feature = "ssh")]
+use sy::transport::sftp::SftpTransport;
+#[cfg(feature = "ssh")]
 use sy::transport::ssh::SshTransport;
 
 #[cfg(feature = "s3")]
 use sy::transport::s3::S3Transport;
 
+#[cfg(feature = "azure")]
+use sy::transport::azure::AzureTransport;
 #[cfg(feature = "gcs")]
 use sy::transport::gcs::GcsTransport;
+#[cfg(feature = "webdav")]
+use sy::transport::webdav::WebDavTransport;
 
 // Server protocol for high-performance SSH transfers
 #[cfg(feature = "ssh")]
@@ -36,7 +42,7 @@ use sy::compress::{compress, is_compressed_extension, Compression};
 #[cfg(feature = "ssh")]
 use sy::server::protocol::{FileListEntry, DATA_FLAG_COMPRESSED};
 #[cfg(feature = "ssh")]
-use sy::transport::server::ServerSession;
+use sy::transport::server::{ServerSession, SessionOptions};
 
 fn parse_sync_path(s: &str) -> Result<SyncPath, String> {
     Ok(SyncPath::parse(s))
@@ -44,7 +50,7 @@ fn parse_sync_path(s: &str) -> Result<SyncPath, String> {
 
 #[derive(Parser, Debug)]
 #[command(name = "sy-put")]
-#[command(about = "Upload files to remote storage (S3, GCS, SSH)", long_about = None)]
+#[command(about = "Upload files to remote storage (S3, GCS, Azure, SSH)", long_about = None)]
 #[command(version)]
 #[command(after_help = "EXAMPLES:
     # Upload a single file to S3
@@ -74,7 +80,7 @@ struct Cli {
     #[arg()]
     pub source: String,
 
-    /// Remote destination path (SSH, S3, GCS)
+    /// Remote destination path (SSH, S3, GCS, Azure)
     /// Examples: user@host:/path, s3://bucket/path, gs://bucket/path
     #[arg(value_parser = parse_sync_path)]
     pub destination: SyncPath,
@@ -473,11 +479,15 @@ async fn upload_via_server_protocol(
         }
 
         // Connect to server
-        let mut session = ServerSession::connect_ssh(config, dest.parent().unwrap_or(dest))
-            .await
-            .context(
-                "Failed to connect to sy --server on remote. Is 'sy' installed on the remote host?",
-            )?;
+        let mut session = ServerSession::connect_ssh(
+            config,
+            dest.parent().unwrap_or(dest),
+            SessionOptions::default(),
+        )
+        .await
+        .context(
+            "Failed to connect to sy --server on remote. Is 'sy' installed on the remote host?",
+        )?;
 
         // Send file list with single file
         let entry = FileListEntry {
@@ -487,6 +497,8 @@ async fn upload_via_server_protocol(
             mode: 0o644,
             flags: 0,
             symlink_target: None,
+            hardlink_to: None,
+            meta: None,
         };
         session.send_file_list(vec![entry]).await?;
         let ack = session.read_ack().await?;
@@ -629,9 +641,11 @@ async fn upload_via_server_protocol(
     }
 
     // Connect to server
-    let mut session = ServerSession::connect_ssh(config, dest).await.context(
-        "Failed to connect to sy --server on remote. Is 'sy' installed on the remote host?",
-    )?;
+    let mut session = ServerSession::connect_ssh(config, dest, SessionOptions::default())
+        .await
+        .context(
+            "Failed to connect to sy --server on remote. Is 'sy' installed on the remote host?",
+        )?;
 
     // Step 1: Create directories (batched)
     if !directories.is_empty() {
@@ -656,6 +670,8 @@ async fn upload_via_server_protocol(
             mode: 0o644,
             flags: 0,
             symlink_target: None,
+            hardlink_to: None,
+            meta: None,
         })
         .collect();
 
@@ -783,7 +799,7 @@ async fn main() -> Result<()> {
     // Validate destination is remote
     if cli.destination.is_local() {
         anyhow::bail!(
-            "Destination must be a remote path (SSH, S3, or GCS). Use sy or cp for local copies."
+            "Destination must be a remote path (SSH, S3, GCS, or Azure). Use sy or cp for local copies."
         );
     }
 
@@ -866,6 +882,44 @@ async fn main() -> Result<()> {
                 "SSH support not enabled. Reinstall with: cargo install sy --features ssh"
             );
         }
+        #[cfg(feature = "ssh")]
+        SyncPath::Sftp {
+            host, user, port, ..
+        } => {
+            let mut config = parse_ssh_config(host).unwrap_or_else(|_| SshConfig::new(host));
+            if let Some(user) = user {
+                config.user = user.clone();
+            }
+            if let Some(port) = port {
+                config.port = *port;
+            }
+
+            let ssh =
+                SshTransport::with_retry_config(&config, cli.jobs.max(1), RetryConfig::default())
+                    .await
+                    .context("Failed to create SSH transport")?;
+            let transport = Arc::new(SftpTransport::new(ssh));
+
+            transport
+                .prepare_for_transfer(1000)
+                .await
+                .context("Failed to expand SSH connection pool")?;
+
+            upload_to_transport(
+                transport,
+                source,
+                cli.destination.path(),
+                &cli,
+                &path_filter,
+            )
+            .await?
+        }
+        #[cfg(not(feature = "ssh"))]
+        SyncPath::Sftp { .. } => {
+            anyhow::bail!(
+                "SSH support not enabled. Reinstall with: cargo install sy --features ssh"
+            );
+        }
         #[cfg(feature = "s3")]
         SyncPath::S3 {
             bucket,
@@ -932,7 +986,59 @@ async fn main() -> Result<()> {
                 "GCS support not enabled. Reinstall with: cargo install sy --features gcs"
             );
         }
-        SyncPath::Daemon { .. } => {
+        #[cfg(feature = "azure")]
+        SyncPath::Azure {
+            container,
+            key,
+            account,
+            ..
+        } => {
+            let transport = Arc::new(
+                AzureTransport::new(container.clone(), key.clone(), account.clone())
+                    .await
+                    .context("Failed to create Azure transport")?,
+            );
+
+            upload_to_transport(
+                transport,
+                source,
+                cli.destination.path(),
+                &cli,
+                &path_filter,
+            )
+            .await?
+        }
+        #[cfg(not(feature = "azure"))]
+        SyncPath::Azure { .. } => {
+            anyhow::bail!(
+                "Azure support not enabled. Reinstall with: cargo install sy --features azure"
+            );
+        }
+        #[cfg(feature = "webdav")]
+        SyncPath::WebDav {
+            host, user, secure, ..
+        } => {
+            let transport = Arc::new(
+                WebDavTransport::new(host.clone(), user.clone(), *secure)
+                    .context("Failed to create WebDAV transport")?,
+            );
+
+            upload_to_transport(
+                transport,
+                source,
+                cli.destination.path(),
+                &cli,
+                &path_filter,
+            )
+            .await?
+        }
+        #[cfg(not(feature = "webdav"))]
+        SyncPath::WebDav { .. } => {
+            anyhow::bail!(
+                "WebDAV support not enabled. Reinstall with: cargo install sy --features webdav"
+            );
+        }
+        SyncPath::Daemon { .. } | SyncPath::TcpDaemon { .. } => {
             anyhow::bail!(
                 "Daemon paths are not supported for upload. Use SSH paths directly: user@host:/path"
             );
@@ -1,6 +1,6 @@
 //! sy-rm - Remove files/directories across all supported transports
 //!
-//! This tool removes files and directories from local, SSH, S3, and GCS backends,
+//! This tool removes files and directories from local, SSH, S3, GCS, and Azure backends,
 //! similar to `rclone delete` / `rclone purge`.
 
 use anyhow::{Context, Result};
@@ -17,13 +17,19 @@ use tracing_subscriber::{fmt, EnvFilter};
 #[cfg(feature = "ssh")]
 use sy::ssh::config::{parse_ssh_config, SshConfig};
 #[cfg(feature = "ssh")]
+use sy::transport::sftp::SftpTransport;
+#[cfg(feature = "ssh")]
 use sy::transport::ssh::SshTransport;
 
 #[cfg(feature = "s3")]
 use sy::transport::s3::S3Transport;
 
+#[cfg(feature = "azure")]
+use sy::transport::azure::AzureTransport;
 #[cfg(feature = "gcs")]
 use sy::transport::gcs::GcsTransport;
+#[cfg(feature = "webdav")]
+use sy::transport::webdav::WebDavTransport;
 
 fn parse_sync_path(s: &str) -> Result<SyncPath, String> {
     Ok(SyncPath::parse(s))
@@ -31,7 +37,7 @@ fn parse_sync_path(s: &str) -> Result<SyncPath, String> {
 
 #[derive(Parser, Debug)]
 #[command(name = "sy-rm")]
-#[command(about = "Remove files/directories (works with local, SSH, S3, GCS)", long_about = None)]
+#[command(about = "Remove files/directories (works with local, SSH, S3, GCS, Azure)", long_about = None)]
 #[command(version)]
 #[command(after_help = "EXAMPLES:
     # Remove a single file
@@ -64,7 +70,7 @@ fn parse_sync_path(s: &str) -> Result<SyncPath, String> {
 
 For more information: https://github.com/nijaru/sy")]
 struct Cli {
-    /// Path to remove (local, SSH, S3, GCS, etc.)
+    /// Path to remove (local, SSH, S3, GCS, Azure, etc.)
     /// Examples: /path, user@host:/path, s3://bucket/path, gs://bucket/path
     #[arg(value_parser = parse_sync_path)]
     pub path: SyncPath,
@@ -391,6 +397,31 @@ async fn main() -> Result<()> {
                 "SSH support not enabled. Reinstall with: cargo install sy --features ssh"
             );
         }
+        #[cfg(feature = "ssh")]
+        SyncPath::Sftp {
+            host, user, port, ..
+        } => {
+            let mut config = parse_ssh_config(host).unwrap_or_else(|_| SshConfig::new(host));
+            if let Some(user) = user {
+                config.user = user.clone();
+            }
+            if let Some(port) = port {
+                config.port = *port;
+            }
+
+            let ssh = SshTransport::with_retry_config(&config, 1, RetryConfig::default())
+                .await
+                .context("Failed to create SSH transport")?;
+            let transport = SftpTransport::new(ssh);
+
+            remove_with_transport(&transport, cli.path.path(), &cli, &path_filter).await?
+        }
+        #[cfg(not(feature = "ssh"))]
+        SyncPath::Sftp { .. } => {
+            anyhow::bail!(
+                "SSH support not enabled. Reinstall with: cargo install sy --features ssh"
+            );
+        }
         #[cfg(feature = "s3")]
         SyncPath::S3 {
             bucket,
@@ -439,7 +470,41 @@ async fn main() -> Result<()> {
                 "GCS support not enabled. Reinstall with: cargo install sy --features gcs"
             );
         }
-        SyncPath::Daemon { .. } => {
+        #[cfg(feature = "azure")]
+        SyncPath::Azure {
+            container,
+            key,
+            account,
+            ..
+        } => {
+            let transport = AzureTransport::new(container.clone(), key.clone(), account.clone())
+                .await
+                .context("Failed to create Azure transport")?;
+
+            remove_with_transport(&transport, cli.path.path(), &cli, &path_filter).await?
+        }
+        #[cfg(not(feature = "azure"))]
+        SyncPath::Azure { .. } => {
+            anyhow::bail!(
+                "Azure support not enabled. Reinstall with: cargo install sy --features azure"
+            );
+        }
+        #[cfg(feature = "webdav")]
+        SyncPath::WebDav {
+            host, user, secure, ..
+        } => {
+            let transport = WebDavTransport::new(host.clone(), user.clone(), *secure)
+                .context("Failed to create WebDAV transport")?;
+
+            remove_with_transport(&transport, cli.path.path(), &cli, &path_filter).await?
+        }
+        #[cfg(not(feature = "webdav"))]
+        SyncPath::WebDav { .. } => {
+            anyhow::bail!(
+                "WebDAV support not enabled. Reinstall with: cargo install sy --features webdav"
+            );
+        }
+        SyncPath::Daemon { .. } | SyncPath::TcpDaemon { .. } => {
             anyhow::bail!(
                 "Daemon paths are not supported for removal. Use SSH paths directly: user@host:/path"
             );
@@ -4,11 +4,13 @@
 
 #[cfg(test)]
 use crate::bisync::state::Side;
-use crate::bisync::state::{StateMap, SyncState};
+use crate::bisync::state::{StateMap, SyncState, TombstoneMap};
 use crate::error::Result;
+use crate::sync::moves::{match_candidates, MoveCandidate};
 use crate::sync::scanner::FileEntry;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 /// Type of change detected
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -21,6 +23,13 @@ pub enum ChangeType {
     DeletedFromSource, // Was in prior, now only in dest
     DeletedFromDest,   // Was in prior, now only in source
 
+    // Moves (delete + create on one side with matching content)
+    MovedInSource { from: PathBuf }, // Source renamed `from` → path, dest still at `from`
+    MovedInDest { from: PathBuf },   // Dest renamed `from` → path, source still at `from`
+
+    // Both sides changed to identical content (checksum confirmed)
+    Identical, // No transfer needed, only state refresh
+
     // Conflicts (both sides changed)
     ModifiedBoth,         // Both changed since prior sync
     CreateCreateConflict, // New in both sides (different content)
@@ -36,11 +45,41 @@ pub struct Change {
     pub dest_entry: Option<FileEntry>,
 }
 
+/// Content hashes per side, keyed by relative path (for --bisync-checksum)
+#[derive(Debug, Clone, Default)]
+pub struct ContentHashes {
+    pub source: HashMap<PathBuf, u64>,
+    pub dest: HashMap<PathBuf, u64>,
+}
+
+/// Hashes of one path on each side
+#[derive(Debug, Clone, Copy, Default)]
+struct PathHashes {
+    source: Option<u64>,
+    dest: Option<u64>,
+}
+
 /// Classify all changes between source, dest, and prior state
+#[allow(dead_code)] // Public API (benches), the engine classifies with hashes
 pub fn classify_changes(
     source_files: &[FileEntry],
     dest_files: &[FileEntry],
     prior_state: &StateMap,
+) -> Result<Vec<Change>> {
+    classify_changes_with_hashes(source_files, dest_files, prior_state, None)
+}
+
+/// Classify changes, using content hashes when available
+///
+/// With hashes, a modification is confirmed or ruled out by comparing
+/// against the checksum recorded at the last sync instead of trusting
+/// mtime and size, and both-sides changes with equal content become
+/// `ChangeType::Identical` instead of conflicts.
+pub fn classify_changes_with_hashes(
+    source_files: &[FileEntry],
+    dest_files: &[FileEntry],
+    prior_state: &StateMap,
+    hashes: Option<&ContentHashes>,
 ) -> Result<Vec<Change>> {
     // Build lookups by relative path
     let mut source_map: HashMap<PathBuf, &FileEntry> = HashMap::with_capacity(source_files.len());
@@ -67,6 +106,12 @@ pub fn classify_changes(
         let source_entry = source_map.get(&path).copied();
         let dest_entry = dest_map.get(&path).copied();
         let prior = prior_state.get(&path);
+        let path_hashes = hashes
+            .map(|h| PathHashes {
+                source: h.source.get(&path).copied(),
+                dest: h.dest.get(&path).copied(),
+            })
+            .unwrap_or_default();
 
         if let Some(change) = classify_single_path(
             &path,
@@ -74,6 +119,7 @@ pub fn classify_changes(
             dest_entry,
             prior.and_then(|(s, _)| s.as_ref()),
             prior.and_then(|(_, d)| d.as_ref()),
+            path_hashes,
         )? {
             changes.push(change);
         }
@@ -82,6 +128,156 @@ pub fn classify_changes(
     Ok(changes)
 }
 
+/// Reclassify stale copies of deleted files using tombstones
+///
+/// Without prior state, a file present on only one side looks new. If a
+/// tombstone recorded this exact copy (same size and mtime) before the
+/// deletion, the copy is a leftover: propagate the deletion to it instead
+/// of copying it back to the other side.
+pub fn apply_tombstones(changes: Vec<Change>, tombstones: &TombstoneMap) -> Vec<Change> {
+    if tombstones.is_empty() {
+        return changes;
+    }
+
+    changes
+        .into_iter()
+        .map(|mut change| {
+            if let Some(tombstone) = tombstones.get(&change.path) {
+                match change.change_type {
+                    ChangeType::NewInSource
+                        if change
+                            .source_entry
+                            .as_ref()
+                            .is_some_and(|e| tombstone.covers(e)) =>
+                    {
+                        change.change_type = ChangeType::DeletedFromDest;
+                    }
+                    ChangeType::NewInDest
+                        if change
+                            .dest_entry
+                            .as_ref()
+                            .is_some_and(|e| tombstone.covers(e)) =>
+                    {
+                        change.change_type = ChangeType::DeletedFromSource;
+                    }
+                    _ => {}
+                }
+            }
+            change
+        })
+        .collect()
+}
+
+/// Rewrite delete + create pairs on the same side as moves
+///
+/// A file that vanished from path A and appeared at path B on one side is a
+/// move when B matches A's recorded state: same inode, or same size and
+/// exact mtime (renames preserve both). The other side still holds an
+/// unchanged copy at A, so it only needs a rename instead of a full copy.
+pub fn detect_moves(changes: Vec<Change>, prior_state: &StateMap) -> Vec<Change> {
+    let mut changes = changes;
+    for side_is_source in [true, false] {
+        changes = pair_moves_on_side(changes, prior_state, side_is_source);
+    }
+    changes
+}
+
+fn pair_moves_on_side(
+    changes: Vec<Change>,
+    prior_state: &StateMap,
+    side_is_source: bool,
+) -> Vec<Change> {
+    let (deleted_type, new_type) = if side_is_source {
+        (ChangeType::DeletedFromSource, ChangeType::NewInSource)
+    } else {
+        (ChangeType::DeletedFromDest, ChangeType::NewInDest)
+    };
+
+    let mut removed_indices = Vec::new();
+    let mut removed = Vec::new();
+    let mut added_indices = Vec::new();
+    let mut added = Vec::new();
+
+    for (i, change) in changes.iter().enumerate() {
+        if change.change_type == deleted_type {
+            let prior = prior_state.get(&change.path).and_then(|(s, d)| {
+                if side_is_source {
+                    s.as_ref()
+                } else {
+                    d.as_ref()
+                }
+            });
+            if let Some(prior) = prior {
+                removed_indices.push(i);
+                removed.push(MoveCandidate {
+                    path: change.path.clone(),
+                    size: prior.size,
+                    modified: prior.mtime,
+                    inode: prior.inode,
+                });
+            }
+        } else if change.change_type == new_type {
+            let entry = if side_is_source {
+                change.source_entry.as_ref()
+            } else {
+                change.dest_entry.as_ref()
+            };
+            if let Some(entry) = entry.filter(|e| !e.is_symlink) {
+                added_indices.push(i);
+                added.push(MoveCandidate::from_entry_with_inode(entry));
+            }
+        }
+    }
+
+    if removed.is_empty() || added.is_empty() {
+        return changes;
+    }
+
+    let matches = match_candidates(&removed, &added, Duration::ZERO);
+    if matches.is_empty() {
+        return changes;
+    }
+
+    let mut consumed = HashSet::new();
+    let mut moves = Vec::with_capacity(matches.len());
+    for m in matches {
+        let deleted = &changes[removed_indices[m.removed]];
+        let created = &changes[added_indices[m.added]];
+        let from = deleted.path.clone();
+        moves.push(Change {
+            path: created.path.clone(),
+            change_type: if side_is_source {
+                ChangeType::MovedInSource { from }
+            } else {
+                ChangeType::MovedInDest { from }
+            },
+            // The moved side's entry comes from the create, the untouched
+            // side's entry (still at the old path) from the delete
+            source_entry: if side_is_source {
+                created.source_entry.clone()
+            } else {
+                deleted.source_entry.clone()
+            },
+            dest_entry: if side_is_source {
+                deleted.dest_entry.clone()
+            } else {
+                created.dest_entry.clone()
+            },
+        });
+        consumed.insert(removed_indices[m.removed]);
+        consumed.insert(added_indices[m.added]);
+    }
+
+    let mut result: Vec<Change> = changes
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| !consumed.contains(i))
+        .map(|(_, c)| c)
+        .collect();
+    result.extend(moves);
+    result
+}
+
 /// Classify a single path
 fn classify_single_path(
     path: &Path,
@@ -89,6 +285,7 @@ fn classify_single_path(
     dest_entry: Option<&FileEntry>,
     prior_source: Option<&SyncState>,
     prior_dest: Option<&SyncState>,
+    hashes: PathHashes,
 ) -> Result<Option<Change>> {
     // Skip directories (we only sync files)
     if source_entry.is_some_and(|e| e.is_dir) || dest_entry.is_some_and(|e| e.is_dir) {
@@ -98,9 +295,9 @@ fn classify_single_path(
     let change_type = match (source_entry, dest_entry, prior_source, prior_dest) {
         // Both exist now, neither existed before (new in both)
         (Some(s), Some(d), None, None) => {
-            if content_equal(s, d)? {
+            if content_equal(s, d, hashes)? {
                 // Same file created on both sides, no conflict
-                return Ok(None);
+                return identical(path, s, d, hashes);
             } else {
                 ChangeType::CreateCreateConflict
             }
@@ -114,17 +311,17 @@ fn classify_single_path(
 
         // Both exist now, both existed before (check modifications)
         (Some(s), Some(d), Some(ps), Some(pd)) => {
-            let source_modified = is_modified(s, ps);
-            let dest_modified = is_modified(d, pd);
+            let source_modified = is_modified(s, ps, hashes.source);
+            let dest_modified = is_modified(d, pd, hashes.dest);
 
             match (source_modified, dest_modified) {
                 (false, false) => return Ok(None), // No changes
                 (true, false) => ChangeType::ModifiedInSource,
                 (false, true) => ChangeType::ModifiedInDest,
                 (true, true) => {
-                    if content_equal(s, d)? {
+                    if content_equal(s, d, hashes)? {
                         // Both changed to same content
-                        return Ok(None);
+                        return identical(path, s, d, hashes);
                     } else {
                         ChangeType::ModifiedBoth
                     }
@@ -134,7 +331,7 @@ fn classify_single_path(
 
         // Source deleted, dest unchanged
         (None, Some(d), Some(_ps), Some(pd)) => {
-            if is_modified(d, pd) {
+            if is_modified(d, pd, hashes.dest) {
                 // Dest modified while source deleted
                 ChangeType::ModifyDeleteConflict
             } else {
@@ -144,7 +341,7 @@ fn classify_single_path(
 
         // Dest deleted, source unchanged
         (Some(s), None, Some(ps), Some(_pd)) => {
-            if is_modified(s, ps) {
+            if is_modified(s, ps, hashes.source) {
                 // Source modified while dest deleted
                 ChangeType::ModifyDeleteConflict
             } else {
@@ -164,10 +361,10 @@ fn classify_single_path(
         // Both exist now, only source existed before
         (Some(s), Some(d), Some(ps), None) => {
             // Source may have changed, dest is new
-            if is_modified(s, ps) && !content_equal(s, d)? {
+            if is_modified(s, ps, hashes.source) && !content_equal(s, d, hashes)? {
                 ChangeType::CreateCreateConflict
-            } else if content_equal(s, d)? {
-                return Ok(None);
+            } else if content_equal(s, d, hashes)? {
+                return identical(path, s, d, hashes);
             } else {
                 ChangeType::NewInDest
             }
@@ -176,10 +373,10 @@ fn classify_single_path(
         // Both exist now, only dest existed before
         (Some(s), Some(d), None, Some(pd)) => {
             // Dest may have changed, source is new
-            if is_modified(d, pd) && !content_equal(s, d)? {
+            if is_modified(d, pd, hashes.dest) && !content_equal(s, d, hashes)? {
                 ChangeType::CreateCreateConflict
-            } else if content_equal(s, d)? {
-                return Ok(None);
+            } else if content_equal(s, d, hashes)? {
+                return identical(path, s, d, hashes);
             } else {
                 ChangeType::NewInSource
             }
@@ -212,33 +409,62 @@ fn classify_single_path(
 }
 
 /// Check if file was modified compared to prior state
-fn is_modified(entry: &FileEntry, prior: &SyncState) -> bool {
+fn is_modified(entry: &FileEntry, prior: &SyncState, hash: Option<u64>) -> bool {
     // Size change = definitely modified
     if entry.size != prior.size {
         return true;
     }
 
+    // Checksum known on both ends = authoritative (catches edits that
+    // kept the mtime, ignores touches that did not change content)
+    if let (Some(current), Some(recorded)) = (hash, prior.checksum) {
+        return current != recorded;
+    }
+
     // Mtime change = likely modified
     entry.modified > prior.mtime
 }
 
 /// Check if two files have equal content
-fn content_equal(source: &FileEntry, dest: &FileEntry) -> Result<bool> {
+fn content_equal(source: &FileEntry, dest: &FileEntry, hashes: PathHashes) -> Result<bool> {
     // Fast path: size mismatch
     if source.size != dest.size {
         return Ok(false);
     }
 
-    // For now, assume equal if sizes match
-    // In future: compare checksums if available
+    if let (Some(s), Some(d)) = (hashes.source, hashes.dest) {
+        return Ok(s == d);
+    }
+
+    // Without checksums, assume equal if sizes match
     // This is conservative (may miss some conflicts) but safe
 
     Ok(true)
 }
 
+/// Both sides hold the same content: record it when checksums prove it
+fn identical(
+    path: &Path,
+    source: &FileEntry,
+    dest: &FileEntry,
+    hashes: PathHashes,
+) -> Result<Option<Change>> {
+    if hashes.source.is_none() || hashes.dest.is_none() {
+        return Ok(None);
+    }
+
+    Ok(Some(Change {
+        path: path.to_path_buf(),
+        change_type: ChangeType::Identical,
+        source_entry: Some(source.clone()),
+        dest_entry: Some(dest.clone()),
+    }))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::bisync::state::Tombstone;
     use std::sync::Arc;
     use std::time::{Duration, SystemTime};
 
@@ -269,6 +495,7 @@ mod tests {
             size,
             checksum: None,
             last_sync: SystemTime::now(),
+            inode: None,
         }
     }
 
@@ -442,6 +669,249 @@ mod tests {
         assert_eq!(changes[0].change_type, ChangeType::CreateCreateConflict);
     }
 
+    fn prior_both(path: &str, size: u64, mtime_secs_ago: u64) -> (PathBuf, PriorPair) {
+        (
+            PathBuf::from(path),
+            (
+                Some(make_sync_state(path, size, mtime_secs_ago, Side::Source)),
+                Some(make_sync_state(path, size, mtime_secs_ago, Side::Dest)),
+            ),
+        )
+    }
+
+    type PriorPair = (Option<SyncState>, Option<SyncState>);
+
+    #[test]
+    fn test_detect_move_in_source() {
+        let moved = make_file_entry("new/file.bin", 100, 60);
+        let mut old_dest = make_file_entry("old/file.bin", 100, 60);
+        old_dest.modified = moved.modified;
+        let source = vec![moved.clone()];
+        let dest = vec![old_dest.clone()];
+
+        let mut prior: StateMap = HashMap::new();
+        let (path, mut states) = prior_both("old/file.bin", 100, 60);
+        states.0.as_mut().unwrap().mtime = moved.modified;
+        states.1.as_mut().unwrap().mtime = moved.modified;
+        prior.insert(path, states);
+
+        let changes = classify_changes(&source, &dest, &prior).unwrap();
+        assert_eq!(changes.len(), 2);
+
+        let changes = detect_moves(changes, &prior);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].path, PathBuf::from("new/file.bin"));
+        assert_eq!(
+            changes[0].change_type,
+            ChangeType::MovedInSource {
+                from: PathBuf::from("old/file.bin")
+            }
+        );
+        assert_eq!(
+            *changes[0].dest_entry.as_ref().unwrap().relative_path,
+            PathBuf::from("old/file.bin")
+        );
+    }
+
+    #[test]
+    fn test_detect_move_by_inode_in_dest() {
+        let source_old = make_file_entry("a.txt", 100, 60);
+        let mut dest_new = make_file_entry("b.txt", 100, 0); // mtime differs
+        dest_new.inode = Some(99);
+
+        let mut prior: StateMap = HashMap::new();
+        let (path, mut states) = prior_both("a.txt", 100, 60);
+        states.0.as_mut().unwrap().mtime = source_old.modified;
+        states.1.as_mut().unwrap().inode = Some(99);
+        prior.insert(path, states);
+
+        let changes = classify_changes(&[source_old], &[dest_new], &prior).unwrap();
+        let changes = detect_moves(changes, &prior);
+
+        assert_eq!(changes.len(), 1);
+        assert_eq!(
+            changes[0].change_type,
+            ChangeType::MovedInDest {
+                from: PathBuf::from("a.txt")
+            }
+        );
+    }
+
+    #[test]
+    fn test_no_move_when_content_differs() {
+        let source = vec![make_file_entry("new.txt", 200, 0)];
+        let dest = vec![make_file_entry("old.txt", 100, 60)];
+        let mut prior: StateMap = HashMap::new();
+        let (path, mut states) = prior_both("old.txt", 100, 60);
+        states.1.as_mut().unwrap().mtime = dest[0].modified;
+        prior.insert(path, states);
+
+        let changes = classify_changes(&source, &dest, &prior).unwrap();
+        let changes = detect_moves(changes, &prior);
+
+        let change_types: Vec<_> = changes.iter().map(|c| c.change_type.clone()).collect();
+        assert!(change_types.contains(&ChangeType::NewInSource));
+        assert!(change_types.contains(&ChangeType::DeletedFromSource));
+    }
+
+    fn hashes(path: &str, source: Option<u64>, dest: Option<u64>) -> ContentHashes {
+        let mut hashes = ContentHashes::default();
+        if let Some(h) = source {
+            hashes.source.insert(PathBuf::from(path), h);
+        }
+        if let Some(h) = dest {
+            hashes.dest.insert(PathBuf::from(path), h);
+        }
+        hashes
+    }
+
+    #[test]
+    fn test_checksum_catches_edit_with_preserved_mtime() {
+        let source = make_file_entry("file.txt", 100, 60);
+        let dest = make_file_entry("file.txt", 100, 60);
+
+        let mut prior_source = make_sync_state("file.txt", 100, 60, Side::Source);
+        prior_source.mtime = source.modified;
+        prior_source.checksum = Some(1);
+        let mut prior_dest = make_sync_state("file.txt", 100, 60, Side::Dest);
+        prior_dest.mtime = dest.modified;
+        prior_dest.checksum = Some(1);
+
+        let mut prior: StateMap = HashMap::new();
+        prior.insert(
+            PathBuf::from("file.txt"),
+            (Some(prior_source), Some(prior_dest)),
+        );
+
+        // mtime + size say unchanged
+        let changes = classify_changes(
+            std::slice::from_ref(&source),
+            std::slice::from_ref(&dest),
+            &prior,
+        )
+        .unwrap();
+        assert!(changes.is_empty());
+
+        // Content hash says the source was edited
+        let h = hashes("file.txt", Some(2), Some(1));
+        let changes = classify_changes_with_hashes(&[source], &[dest], &prior, Some(&h)).unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].change_type, ChangeType::ModifiedInSource);
+    }
+
+    #[test]
+    fn test_checksum_rules_out_touch() {
+        let source = make_file_entry("file.txt", 100, 0); // newer mtime
+        let dest = make_file_entry("file.txt", 100, 60);
+
+        let mut prior_source = make_sync_state("file.txt", 100, 60, Side::Source);
+        prior_source.checksum = Some(1);
+        let mut prior_dest = make_sync_state("file.txt", 100, 60, Side::Dest);
+        prior_dest.mtime = dest.modified;
+        prior_dest.checksum = Some(1);
+
+        let mut prior: StateMap = HashMap::new();
+        prior.insert(
+            PathBuf::from("file.txt"),
+            (Some(prior_source), Some(prior_dest)),
+        );
+
+        let h = hashes("file.txt", Some(1), Some(1));
+        let changes = classify_changes_with_hashes(&[source], &[dest], &prior, Some(&h)).unwrap();
+        assert!(changes.is_empty());
+    }
+
+    #[test]
+    fn test_checksum_resolves_identical_conflict() {
+        let source = make_file_entry("file.txt", 100, 0);
+        let dest = make_file_entry("file.txt", 100, 0);
+
+        let mut prior: StateMap = HashMap::new();
+        prior.insert(
+            PathBuf::from("file.txt"),
+            (
+                Some(make_sync_state("file.txt", 100, 60, Side::Source)),
+                Some(make_sync_state("file.txt", 100, 60, Side::Dest)),
+            ),
+        );
+
+        // Different content: real conflict
+        let h = hashes("file.txt", Some(5), Some(6));
+        let changes = classify_changes_with_hashes(
+            std::slice::from_ref(&source),
+            std::slice::from_ref(&dest),
+            &prior,
+            Some(&h),
+        )
+        .unwrap();
+        assert_eq!(changes[0].change_type, ChangeType::ModifiedBoth);
+
+        // Same content: nothing to transfer
+        let h = hashes("file.txt", Some(5), Some(5));
+        let changes = classify_changes_with_hashes(&[source], &[dest], &prior, Some(&h)).unwrap();
+        assert_eq!(changes[0].change_type, ChangeType::Identical);
+    }
+
+    /// Tombstone recording `entry` as it was when deleted
+    fn tombstone(entry: &FileEntry, deleted_secs_ago: u64) -> (PathBuf, Tombstone) {
+        let path = entry.relative_path.to_path_buf();
+        (
+            path.clone(),
+            Tombstone {
+                path,
+                deleted_on: Side::Source,
+                deleted_at: SystemTime::now() - Duration::from_secs(deleted_secs_ago),
+                size: entry.size,
+                mtime: entry.modified,
+                trashed: false,
+            },
+        )
+    }
+
+    #[test]
+    fn test_tombstone_stops_resurrection() {
+        // Stale replica still has a copy older than the deletion
+        let dest = vec![make_file_entry("gone.txt", 100, 3600)];
+        let tombstones: TombstoneMap = [tombstone(&dest[0], 60)].into_iter().collect();
+
+        let changes = classify_changes(&[], &dest, &HashMap::new()).unwrap();
+        assert_eq!(changes[0].change_type, ChangeType::NewInDest);
+
+        let changes = apply_tombstones(changes, &tombstones);
+        assert_eq!(changes[0].change_type, ChangeType::DeletedFromSource);
+    }
+
+    #[test]
+    fn test_tombstone_ignores_newer_file() {
+        // Created again after the deletion: genuinely new
+        let deleted = make_file_entry("gone.txt", 100, 3600);
+        let source = vec![make_file_entry("gone.txt", 100, 0)];
+        let tombstones: TombstoneMap = [tombstone(&deleted, 60)].into_iter().collect();
+
+        let changes = classify_changes(&source, &[], &HashMap::new()).unwrap();
+        let changes = apply_tombstones(changes, &tombstones);
+        assert_eq!(changes[0].change_type, ChangeType::NewInSource);
+    }
+
+    #[test]
+    fn test_tombstone_ignores_restored_file_with_old_mtime() {
+        // A different file put back with a preserved, older mtime (cp -p)
+        let deleted = make_file_entry("gone.txt", 100, 3600);
+        let mut restored = make_file_entry("gone.txt", 250, 7200);
+        let tombstones: TombstoneMap = [tombstone(&deleted, 60)].into_iter().collect();
+
+        let changes =
+            classify_changes(std::slice::from_ref(&restored), &[], &HashMap::new()).unwrap();
+        let changes = apply_tombstones(changes, &tombstones);
+        assert_eq!(changes[0].change_type, ChangeType::NewInSource);
+
+        // Same size is not enough either
+        restored.size = 100;
+        let changes = classify_changes(&[restored], &[], &HashMap::new()).unwrap();
+        let changes = apply_tombstones(changes, &tombstones);
+        assert_eq!(changes[0].change_type, ChangeType::NewInSource);
+    }
+
     #[test]
     fn test_multiple_changes() {
         let source = vec![
@@ -3,14 +3,20 @@
 // Orchestrates the complete bidirectional sync workflow
 
 use crate::bisync::{
-    classify_changes, conflict_filename, resolve_changes, BisyncStateDb, Change, ChangeType,
-    ConflictResolution, ResolvedChanges, Side, SyncAction, SyncState,
+    apply_tombstones, classify_changes_with_hashes, conflict_filename, detect_moves,
+    resolve_changes, BisyncStateDb, Change, ChangeType, ConflictResolution, ContentHashes,
+    ResolvedChanges, Side, SyncAction, SyncState, Tombstone, TRASH_DIR,
 };
 use crate::error::{Result, SyncError};
+use crate::integrity::{Checksum, ChecksumType, IntegrityVerifier};
+use crate::sync::checksumdb::ChecksumDatabase;
+use crate::sync::scanner::FileEntry;
 use crate::transport::Transport;
+use futures::stream::{self, StreamExt};
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 
 /// Options for bidirectional sync
 #[derive(Debug, Clone)]
@@ -20,6 +26,10 @@ pub struct BisyncOptions {
     pub dry_run: bool,
     pub clear_state: bool,
     pub force_resync: bool, // Ignore corrupt state and rebuild from scratch
+    pub detect_moves: bool, // Propagate renames instead of copy + delete
+    pub trash: bool,        // Move propagated deletions to .sy-trash instead of removing
+    pub tombstone_retention: Duration, // How long deletions are remembered
+    pub checksum: bool,     // Compare content hashes instead of mtime + size
 }
 
 impl Default for BisyncOptions {
@@ -30,6 +40,10 @@ impl Default for BisyncOptions {
             dry_run: false,
             clear_state: false,
             force_resync: false,
+            detect_moves: false,
+            trash: false,
+            tombstone_retention: Duration::from_secs(30 * 24 * 60 * 60),
+            checksum: false,
         }
     }
 }
@@ -41,6 +55,9 @@ pub struct BisyncStats {
     pub files_synced_to_source: usize,
     pub files_deleted_from_source: usize,
     pub files_deleted_from_dest: usize,
+    pub files_moved: usize,
+    pub files_identical: usize, // Changed on both sides to the same content
+    pub tombstones_expired: usize,
     pub conflicts_resolved: usize,
     pub conflicts_renamed: usize,
     pub bytes_transferred: u64,
@@ -73,6 +90,7 @@ pub struct BisyncResult {
 }
 
 /// Bidirectional sync engine
+#[derive(Clone)]
 pub struct BisyncEngine {
     source_transport: Arc<dyn Transport>,
     dest_transport: Arc<dyn Transport>,
@@ -93,10 +111,21 @@ impl BisyncEngine {
         dest: &Path,
         opts: BisyncOptions,
     ) -> Result<BisyncResult> {
-        let start = std::time::Instant::now();
+        let mut session = self.open_session(source, dest, opts)?;
+        session.sync_once().await
+    }
 
+    /// Lock a sync pair and open its state for repeated passes
+    ///
+    /// `clear_state` and `force_resync` take effect once, here.
+    pub fn open_session(
+        &self,
+        source: &Path,
+        dest: &Path,
+        opts: BisyncOptions,
+    ) -> Result<BisyncSession> {
         // 0. Acquire lock to prevent concurrent syncs to same pair
-        let _lock = crate::bisync::SyncLock::acquire(source, dest)?;
+        let lock = crate::bisync::SyncLock::acquire(source, dest)?;
 
         // 1. Open state database
         let mut state_db = BisyncStateDb::open(source, dest, opts.force_resync)?;
@@ -105,15 +134,95 @@ impl BisyncEngine {
             state_db.clear_all()?;
         }
 
+        let checksum_cache = if opts.checksum {
+            let cache = ChecksumDatabase::open_at(&state_db.checksum_cache_dir())?;
+            if opts.clear_state {
+                cache.clear()?;
+            }
+            Some(cache)
+        } else {
+            None
+        };
+
+        Ok(BisyncSession {
+            engine: self.clone(),
+            source: source.to_path_buf(),
+            dest: dest.to_path_buf(),
+            opts,
+            state_db,
+            checksum_cache,
+            _lock: lock,
+        })
+    }
+
+    /// One sync pass against an already opened state database
+    async fn run_pass(
+        &self,
+        state_db: &mut BisyncStateDb,
+        checksum_cache: Option<&ChecksumDatabase>,
+        source: &Path,
+        dest: &Path,
+        opts: &BisyncOptions,
+    ) -> Result<BisyncResult> {
+        let start = std::time::Instant::now();
+
         // 2. Load prior state
         let prior_state = state_db.load_all()?;
 
-        // 3. Scan both sides using transports
-        let source_files = self.source_transport.scan(source).await?;
-        let dest_files = self.dest_transport.scan(dest).await?;
+        // 3. Scan both sides using transports (trash is never synced)
+        let source_files = without_trash(self.source_transport.scan(source).await?);
+        let dest_files = without_trash(self.dest_transport.scan(dest).await?);
+
+        // 3b. Expire old tombstones and forget ones whose file was restored
+        let mut tombstones_expired = 0;
+        if !opts.dry_run {
+            let expired = state_db.prune_stale(opts.tombstone_retention)?;
+            tombstones_expired = expired.len();
+            self.purge_trash(source, dest, &expired).await;
+            self.forget_restored(state_db, source, dest, &source_files, &dest_files)
+                .await?;
+        }
+
+        // 3c. Hash both sides for content-based classification
+        let hashes = if let Some(cache) = checksum_cache {
+            let hashes = ContentHashes {
+                source: hash_side(
+                    &self.source_transport,
+                    source,
+                    Side::Source,
+                    &source_files,
+                    cache,
+                )
+                .await?,
+                dest: hash_side(&self.dest_transport, dest, Side::Dest, &dest_files, cache).await?,
+            };
+
+            // Drop cache entries for files (or inodes) that no longer exist
+            let live_keys: HashSet<PathBuf> = source_files
+                .iter()
+                .map(|e| checksum_cache_key(Side::Source, e))
+                .chain(dest_files.iter().map(|e| checksum_cache_key(Side::Dest, e)))
+                .collect();
+            cache.prune(&live_keys)?;
+
+            Some(hashes)
+        } else {
+            None
+        };
 
         // 4. Classify changes
-        let changes = classify_changes(&source_files, &dest_files, &prior_state)?;
+        let mut changes = classify_changes_with_hashes(
+            &source_files,
+            &dest_files,
+            &prior_state,
+            hashes.as_ref(),
+        )?;
+        changes = apply_tombstones(changes, state_db.tombstones());
+
+        // 4b. Pair deletes with creates on the same side as moves
+        if opts.detect_moves {
+            changes = detect_moves(changes, &prior_state);
+        }
 
         // 5. Check deletion limit
         check_deletion_limit(&changes, opts.max_delete_percent)?;
@@ -130,6 +239,8 @@ impl BisyncEngine {
         }
 
         // 8. Execute sync actions (or dry run)
+        let deleted_at = SystemTime::now();
+        let trash_stamp = opts.trash.then_some(deleted_at);
         let (stats, errors) = if opts.dry_run {
             // Dry run - just report what would happen
             let stats = simulate_actions(&resolved);
@@ -142,11 +253,18 @@ impl BisyncEngine {
                 source,
                 dest,
                 &resolved,
+                trash_stamp,
             )
             .await?;
 
             // 9. Update state database
-            update_state(&mut state_db, &resolved)?;
+            update_state(state_db, &resolved, hashes.as_ref(), deleted_at, opts.trash)?;
+
+            // Remember inodes so renames can be recognized next time
+            if opts.detect_moves {
+                state_db.refresh_inodes(Side::Source, &source_files)?;
+                state_db.refresh_inodes(Side::Dest, &dest_files)?;
+            }
 
             (stats, errors)
         };
@@ -154,6 +272,7 @@ impl BisyncEngine {
         let duration_ms = start.elapsed().as_millis();
         let final_stats = BisyncStats {
             duration_ms,
+            tombstones_expired,
             ..stats
         };
 
@@ -163,6 +282,195 @@ impl BisyncEngine {
             errors,
         })
     }
+
+    /// Remove trashed copies belonging to expired tombstones
+    ///
+    /// Every deletion propagated in one run shares a `.sy-trash/<stamp>`
+    /// directory, so whole stamp directories are removed at once.
+    async fn purge_trash(&self, source: &Path, dest: &Path, expired: &[Tombstone]) {
+        let mut stamp_dirs = HashSet::new();
+        for tombstone in expired.iter().filter(|t| t.trashed) {
+            let (transport, root) = match tombstone.propagated_to() {
+                Side::Source => (&self.source_transport, source),
+                Side::Dest => (&self.dest_transport, dest),
+            };
+            let dir = root.join(Tombstone::trash_dir_for(tombstone.deleted_at));
+            if stamp_dirs.insert(dir.clone()) {
+                if let Err(e) = transport.remove(&dir, true).await {
+                    tracing::warn!("Failed to purge trash {}: {}", dir.display(), e);
+                }
+            }
+        }
+    }
+
+    /// Drop tombstones for files that were restored out of the trash
+    ///
+    /// A restored file predates its deletion, so its tombstone would otherwise
+    /// make the next sync delete it again.
+    async fn forget_restored(
+        &self,
+        state_db: &mut BisyncStateDb,
+        source: &Path,
+        dest: &Path,
+        source_files: &[FileEntry],
+        dest_files: &[FileEntry],
+    ) -> Result<()> {
+        let source_paths: HashSet<&Path> = source_files
+            .iter()
+            .map(|e| e.relative_path.as_path())
+            .collect();
+        let dest_paths: HashSet<&Path> = dest_files
+            .iter()
+            .map(|e| e.relative_path.as_path())
+            .collect();
+
+        let candidates: Vec<Tombstone> = state_db
+            .tombstones()
+            .values()
+            .filter(|t| t.trashed)
+            .filter(|t| match t.propagated_to() {
+                Side::Source => source_paths.contains(t.path.as_path()),
+                Side::Dest => dest_paths.contains(t.path.as_path()),
+            })
+            .cloned()
+            .collect();
+
+        for tombstone in candidates {
+            let (transport, root) = match tombstone.propagated_to() {
+                Side::Source => (&self.source_transport, source),
+                Side::Dest => (&self.dest_transport, dest),
+            };
+            if !transport.exists(&root.join(tombstone.trash_path())).await? {
+                state_db.clear_tombstone(&tombstone.path)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Number of files hashed concurrently per side
+const HASH_CONCURRENCY: usize = 8;
+
+/// Hash every regular file on one side, reusing cached checksums
+async fn hash_side(
+    transport: &Arc<dyn Transport>,
+    root: &Path,
+    side: Side,
+    entries: &[FileEntry],
+    cache: &ChecksumDatabase,
+) -> Result<HashMap<PathBuf, u64>> {
+    let verifier = IntegrityVerifier::new(ChecksumType::Fast, false);
+
+    let results: Vec<Result<Option<(PathBuf, u64)>>> =
+        stream::iter(entries.iter().filter(|e| !e.is_dir && !e.is_symlink))
+            .map(|entry| {
+                let verifier = &verifier;
+                async move {
+                    let relative = (*entry.relative_path).clone();
+                    let key = checksum_cache_key(side, entry);
+
+                    let checksum =
+                        match cache.get_checksum(&key, entry.modified, entry.size, "fast")? {
+                            Some(cached) => cached,
+                            None => {
+                                let computed = transport
+                                    .compute_checksum(&root.join(&relative), verifier)
+                                    .await?;
+                                cache.store_checksum(
+                                    &key,
+                                    entry.modified,
+                                    entry.size,
+                                    &computed,
+                                )?;
+                                computed
+                            }
+                        };
+
+                    Ok(checksum_to_u64(&checksum).map(|hash| (relative, hash)))
+                }
+            })
+            .buffer_unordered(HASH_CONCURRENCY)
+            .collect()
+            .await;
+
+    let mut hashes = HashMap::with_capacity(results.len());
+    for result in results {
+        if let Some((path, hash)) = result? {
+            hashes.insert(path, hash);
+        }
+    }
+    Ok(hashes)
+}
+
+/// Key of an entry in the bisync checksum cache
+///
+/// Prefixed with the side name so a local and a remote root sharing the
+/// same path never collide. The inode is part of the key when known, so a
+/// file replaced by a rename (how most mtime-preserving tools write) misses
+/// the cache even with unchanged size and mtime. `--clear-bisync-state`
+/// drops the cache for in-place edits that kept both.
+fn checksum_cache_key(side: Side, entry: &FileEntry) -> PathBuf {
+    match entry.inode {
+        Some(inode) => PathBuf::from(format!("{}@{}", side.as_str(), inode)),
+        None => PathBuf::from(side.as_str()),
+    }
+    .join(&*entry.relative_path)
+}
+
+/// Fold a fast (xxHash3) checksum into the u64 stored in `SyncState`
+fn checksum_to_u64(checksum: &Checksum) -> Option<u64> {
+    match checksum {
+        Checksum::Fast(bytes) => bytes.as_slice().try_into().ok().map(u64::from_le_bytes),
+        _ => None,
+    }
+}
+
+/// Drop entries inside the trash directory from a scan
+fn without_trash(entries: Vec<FileEntry>) -> Vec<FileEntry> {
+    entries
+        .into_iter()
+        .filter(|e| !e.relative_path.starts_with(TRASH_DIR))
+        .collect()
+}
+
+/// A locked sync pair whose state stays open between passes
+///
+/// Watch mode runs one pass per batch of changes without re-acquiring the
+/// lock or reloading the state file each time.
+pub struct BisyncSession {
+    engine: BisyncEngine,
+    source: PathBuf,
+    dest: PathBuf,
+    opts: BisyncOptions,
+    state_db: BisyncStateDb,
+    checksum_cache: Option<ChecksumDatabase>,
+    _lock: crate::bisync::SyncLock,
+}
+
+impl BisyncSession {
+    /// Run one bidirectional pass
+    pub async fn sync_once(&mut self) -> Result<BisyncResult> {
+        self.engine
+            .run_pass(
+                &mut self.state_db,
+                self.checksum_cache.as_ref(),
+                &self.source,
+                &self.dest,
+                &self.opts,
+            )
+            .await
+    }
+
+    #[allow(dead_code)] // Used by watch mode
+    pub fn source(&self) -> &Path {
+        &self.source
+    }
+
+    #[allow(dead_code)] // Used by watch mode
+    pub fn dest(&self) -> &Path {
+        &self.dest
+    }
 }
 
 /// Check if deletion limit would be exceeded
@@ -252,6 +560,12 @@ fn simulate_actions(resolved: &ResolvedChanges) -> BisyncStats {
             SyncAction::DeleteFromDest(_) => {
                 stats.files_deleted_from_dest += 1;
             }
+            SyncAction::MoveInSource { .. } | SyncAction::MoveInDest { .. } => {
+                stats.files_moved += 1;
+            }
+            SyncAction::RecordIdentical { .. } => {
+                stats.files_identical += 1;
+            }
             SyncAction::RenameConflict { source, dest, .. } => {
                 stats.files_synced_to_source += 1;
                 stats.files_synced_to_dest += 1;
@@ -273,6 +587,7 @@ async fn execute_actions(
     source_root: &Path,
     dest_root: &Path,
     resolved: &ResolvedChanges,
+    trash_stamp: Option<SystemTime>,
 ) -> Result<(BisyncStats, Vec<String>)> {
     let mut stats = BisyncStats::default();
     let mut errors = Vec::new();
@@ -284,6 +599,7 @@ async fn execute_actions(
             source_root,
             dest_root,
             action,
+            trash_stamp,
         )
         .await;
 
@@ -294,6 +610,10 @@ async fn execute_actions(
                     SyncAction::CopyToDest(_) => stats.files_synced_to_dest += 1,
                     SyncAction::DeleteFromSource(_) => stats.files_deleted_from_source += 1,
                     SyncAction::DeleteFromDest(_) => stats.files_deleted_from_dest += 1,
+                    SyncAction::MoveInSource { .. } | SyncAction::MoveInDest { .. } => {
+                        stats.files_moved += 1
+                    }
+                    SyncAction::RecordIdentical { .. } => stats.files_identical += 1,
                     SyncAction::RenameConflict { .. } => {
                         stats.files_synced_to_source += 1;
                         stats.files_synced_to_dest += 1;
@@ -320,6 +640,7 @@ async fn execute_single_action(
     source_root: &Path,
     dest_root: &Path,
     action: &SyncAction,
+    trash_stamp: Option<SystemTime>,
 ) -> Result<u64> {
     match action {
         SyncAction::CopyToSource(entry) => {
@@ -335,13 +656,27 @@ async fn execute_single_action(
             copy_file_across_transports(source_transport, dest_transport, &src, &dst).await
         }
         SyncAction::DeleteFromSource(path) => {
-            let target = source_root.join(path);
-            source_transport.remove(&target, false).await?;
+            delete_or_trash(source_transport, source_root, path, trash_stamp).await?;
             Ok(0)
         }
         SyncAction::DeleteFromDest(path) => {
-            let target = dest_root.join(path);
-            dest_transport.remove(&target, false).await?;
+            delete_or_trash(dest_transport, dest_root, path, trash_stamp).await?;
+            Ok(0)
+        }
+        SyncAction::MoveInSource { from, entry } => {
+            let from_path = source_root.join(from);
+            let to_path = source_root.join(&*entry.relative_path);
+            source_transport.rename(&from_path, &to_path).await?;
+            Ok(0)
+        }
+        SyncAction::MoveInDest { from, entry } => {
+            let from_path = dest_root.join(from);
+            let to_path = dest_root.join(&*entry.relative_path);
+            dest_transport.rename(&from_path, &to_path).await?;
+            Ok(0)
+        }
+        SyncAction::RecordIdentical { .. } => {
+            // Content already matches, nothing to transfer
             Ok(0)
         }
         SyncAction::RenameConflict {
@@ -376,6 +711,23 @@ async fn execute_single_action(
     }
 }
 
+/// Remove a file, or move it under `.sy-trash/<stamp>/` when trashing
+async fn delete_or_trash(
+    transport: &Arc<dyn Transport>,
+    root: &Path,
+    path: &Path,
+    trash_stamp: Option<SystemTime>,
+) -> Result<()> {
+    let target = root.join(path);
+    match trash_stamp {
+        Some(stamp) => {
+            let trashed = root.join(Tombstone::trash_dir_for(stamp)).join(path);
+            transport.rename(&target, &trashed).await
+        }
+        None => transport.remove(&target, false).await,
+    }
+}
+
 /// Copy a file across transports (e.g., local to SSH, or SSH to local)
 async fn copy_file_across_transports(
     from_transport: &Arc<dyn Transport>,
@@ -394,21 +746,38 @@ async fn copy_file_across_transports(
 }
 
 /// Update state database after sync
-fn update_state(state_db: &mut BisyncStateDb, resolved: &ResolvedChanges) -> Result<()> {
+///
+/// Propagated deletions leave a tombstone stamped with `deleted_at`.
+/// With content hashes, the hash of the copied side is recorded for both.
+fn update_state(
+    state_db: &mut BisyncStateDb,
+    resolved: &ResolvedChanges,
+    hashes: Option<&ContentHashes>,
+    deleted_at: SystemTime,
+    trashed: bool,
+) -> Result<()> {
     let now = SystemTime::now();
+    let hash_of = |side: Side, path: &Path| {
+        hashes.and_then(|h| match side {
+            Side::Source => h.source.get(path).copied(),
+            Side::Dest => h.dest.get(path).copied(),
+        })
+    };
 
     for action in &resolved.actions {
         match action {
             SyncAction::CopyToSource(entry) => {
                 // File now exists on both sides with same content
                 // Store state for both source and dest
+                let checksum = hash_of(Side::Dest, &entry.relative_path);
                 let source_state = SyncState {
                     path: (*entry.relative_path).clone(),
                     side: Side::Source,
                     mtime: entry.modified,
                     size: entry.size,
-                    checksum: None,
+                    checksum,
                     last_sync: now,
+                    inode: None,
                 };
                 state_db.store(&source_state)?;
 
@@ -417,21 +786,24 @@ fn update_state(state_db: &mut BisyncStateDb, resolved: &ResolvedChanges) -> Res
                     side: Side::Dest,
                     mtime: entry.modified,
                     size: entry.size,
-                    checksum: None,
+                    checksum,
                     last_sync: now,
+                    inode: None,
                 };
                 state_db.store(&dest_state)?;
             }
             SyncAction::CopyToDest(entry) => {
                 // File now exists on both sides with same content
                 // Store state for both source and dest
+                let checksum = hash_of(Side::Source, &entry.relative_path);
                 let source_state = SyncState {
                     path: (*entry.relative_path).clone(),
                     side: Side::Source,
                     mtime: entry.modified,
                     size: entry.size,
-                    checksum: None,
+                    checksum,
                     last_sync: now,
+                    inode: None,
                 };
                 state_db.store(&source_state)?;
 
@@ -440,16 +812,53 @@ fn update_state(state_db: &mut BisyncStateDb, resolved: &ResolvedChanges) -> Res
                     side: Side::Dest,
                     mtime: entry.modified,
                     size: entry.size,
-                    checksum: None,
+                    checksum,
                     last_sync: now,
+                    inode: None,
                 };
                 state_db.store(&dest_state)?;
             }
             SyncAction::DeleteFromSource(path) => {
-                state_db.delete(path)?;
+                // Deleted on dest, propagated to source
+                state_db.record_deletion(path, Side::Dest, deleted_at, trashed)?;
             }
             SyncAction::DeleteFromDest(path) => {
-                state_db.delete(path)?;
+                // Deleted on source, propagated to dest
+                state_db.record_deletion(path, Side::Source, deleted_at, trashed)?;
+            }
+            SyncAction::MoveInSource { from, entry } | SyncAction::MoveInDest { from, entry } => {
+                // Content unchanged, only the path moved on both sides
+                let moved_side = match action {
+                    SyncAction::MoveInSource { .. } => Side::Dest,
+                    _ => Side::Source,
+                };
+                let checksum = hash_of(moved_side, &entry.relative_path);
+                state_db.delete(from)?;
+                for side in [Side::Source, Side::Dest] {
+                    state_db.store(&SyncState {
+                        path: (*entry.relative_path).clone(),
+                        side,
+                        mtime: entry.modified,
+                        size: entry.size,
+                        checksum,
+                        last_sync: now,
+                        inode: None,
+                    })?;
+                }
+            }
+            SyncAction::RecordIdentical { source, dest } => {
+                // Same content on both sides, keep each side's own metadata
+                for (side, entry) in [(Side::Source, source), (Side::Dest, dest)] {
+                    state_db.store(&SyncState {
+                        path: (*entry.relative_path).clone(),
+                        side,
+                        mtime: entry.modified,
+                        size: entry.size,
+                        checksum: hash_of(side, &entry.relative_path),
+                        last_sync: now,
+                        inode: None,
+                    })?;
+                }
             }
             SyncAction::RenameConflict { source, dest, .. } => {
                 // Both files kept with new names - update state
@@ -460,6 +869,7 @@ fn update_state(state_db: &mut BisyncStateDb, resolved: &ResolvedChanges) -> Res
                     size: source.size,
                     checksum: None,
                     last_sync: now,
+                    inode: None,
                 };
                 state_db.store(&source_state)?;
 
@@ -470,6 +880,7 @@ fn update_state(state_db: &mut BisyncStateDb, resolved: &ResolvedChanges) -> Res
                     size: dest.size,
                     checksum: None,
                     last_sync: now,
+                    inode: None,
                 };
                 state_db.store(&dest_state)?;
             }
@@ -8,7 +8,13 @@ pub mod lock;
 pub mod resolver;
 pub mod state;
 
-pub use classifier::{classify_changes, Change, ChangeType};
+#[allow(unused_imports)] // Public API (benches), the engine classifies with hashes
+pub use classifier::classify_changes;
+pub use classifier::{
+    apply_tombstones, classify_changes_with_hashes, detect_moves, Change, ChangeType, ContentHashes,
+};
+#[allow(unused_imports)] // Used by watch mode
+pub use engine::BisyncSession;
 pub use engine::{BisyncEngine, BisyncOptions};
 #[allow(unused_imports)]
 pub(crate) use engine::{BisyncResult, BisyncStats, ConflictInfo};
@@ -16,4 +22,4 @@ pub use lock::SyncLock;
 pub use resolver::{
     conflict_filename, resolve_changes, ConflictResolution, ResolvedChanges, SyncAction,
 };
-pub use state::{BisyncStateDb, Side, SyncState};
+pub use state::{BisyncStateDb, Side, SyncState, Tombstone, TRASH_DIR};
@@ -39,6 +39,18 @@ pub enum SyncAction {
     CopyToDest(FileEntry),     // Copy source → dest
     DeleteFromSource(PathBuf), // Delete file from source
     DeleteFromDest(PathBuf),   // Delete file from dest
+    MoveInSource {
+        from: PathBuf,    // Old path on source
+        entry: FileEntry, // Dest entry at the new path
+    },
+    MoveInDest {
+        from: PathBuf,    // Old path on dest
+        entry: FileEntry, // Source entry at the new path
+    },
+    RecordIdentical {
+        source: FileEntry, // Already equal on both sides,
+        dest: FileEntry,   // only the sync state is refreshed
+    },
     RenameConflict {
         source: FileEntry,
         dest: FileEntry,
@@ -92,6 +104,24 @@ pub fn resolve_changes(
             ChangeType::DeletedFromDest => {
                 actions.push(SyncAction::DeleteFromSource(change.path.clone()));
             }
+            ChangeType::MovedInSource { from } => {
+                if let Some(source) = change.source_entry {
+                    actions.push(SyncAction::MoveInDest {
+                        from,
+                        entry: source,
+                    });
+                }
+            }
+            ChangeType::MovedInDest { from } => {
+                if let Some(dest) = change.dest_entry {
+                    actions.push(SyncAction::MoveInSource { from, entry: dest });
+                }
+            }
+            ChangeType::Identical => {
+                if let (Some(source), Some(dest)) = (change.source_entry, change.dest_entry) {
+                    actions.push(SyncAction::RecordIdentical { source, dest });
+                }
+            }
 
             // Conflicts - apply resolution strategy
             ChangeType::ModifiedBoth
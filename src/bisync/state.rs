@@ -4,11 +4,15 @@
 // Uses text-based format for persistent state storage in ~/.cache/sy/bisync/
 
 use crate::error::Result;
+use crate::sync::scanner::FileEntry;
 use std::collections::HashMap;
 use std::fs;
 use std::io::{BufRead, BufReader, Write};
 use std::path::{Path, PathBuf};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Directory (relative to each sync root) holding files removed by bisync
+pub const TRASH_DIR: &str = ".sy-trash";
 
 /// Sync state for a single file
 #[derive(Debug, Clone, PartialEq)]
@@ -19,6 +23,59 @@ pub struct SyncState {
     pub size: u64,
     pub checksum: Option<u64>,
     pub last_sync: SystemTime,
+    /// Inode at last sync (used for move detection on the same side)
+    pub inode: Option<u64>,
+}
+
+/// Record of a path whose deletion was propagated to both sides
+///
+/// Kept for a retention period so a replica that comes back with a stale
+/// copy is told the file was deleted, rather than resurrecting it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tombstone {
+    pub path: PathBuf,
+    /// Side where the user deleted the file
+    pub deleted_on: Side,
+    /// When the deletion was propagated
+    pub deleted_at: SystemTime,
+    /// Size and mtime of the file when it was last in sync
+    pub size: u64,
+    pub mtime: SystemTime,
+    /// Whether the propagated copy was moved to the trash instead of removed
+    pub trashed: bool,
+}
+
+impl Tombstone {
+    /// Side that held the copy removed by propagation (and its trash)
+    pub fn propagated_to(&self) -> Side {
+        match self.deleted_on {
+            Side::Source => Side::Dest,
+            Side::Dest => Side::Source,
+        }
+    }
+
+    /// Location of the trashed copy, relative to the `propagated_to` root
+    pub fn trash_path(&self) -> PathBuf {
+        Self::trash_dir_for(self.deleted_at).join(&self.path)
+    }
+
+    /// Trash directory for deletions propagated at `deleted_at`
+    /// (`.sy-trash/<unix seconds>`, relative to the sync root)
+    pub fn trash_dir_for(deleted_at: SystemTime) -> PathBuf {
+        let secs = deleted_at
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        PathBuf::from(TRASH_DIR).join(secs.to_string())
+    }
+
+    /// Whether an entry reappearing at this path is a stale pre-deletion copy
+    ///
+    /// It must match the recorded size and exact mtime: a new file restored
+    /// with an old mtime (`cp -p`, tar, `rsync -t`) is not the deleted one.
+    pub fn covers(&self, entry: &FileEntry) -> bool {
+        entry.size == self.size && entry.modified == self.mtime && entry.modified <= self.deleted_at
+    }
 }
 
 /// Which side of the sync (source or destination)
@@ -32,8 +89,11 @@ pub enum Side {
 /// Maps path to (source_state, dest_state) tuple
 pub type StateMap = HashMap<PathBuf, (Option<SyncState>, Option<SyncState>)>;
 
+/// Tombstones keyed by path
+pub type TombstoneMap = HashMap<PathBuf, Tombstone>;
+
 impl Side {
-    fn as_str(&self) -> &'static str {
+    pub fn as_str(&self) -> &'static str {
         match self {
             Side::Source => "source",
             Side::Dest => "dest",
@@ -56,11 +116,25 @@ pub struct BisyncStateDb {
     dest_path: PathBuf,
     // In-memory cache for faster lookups
     states: StateMap,
+    tombstones: TombstoneMap,
 }
 
 impl BisyncStateDb {
     /// Format version
-    const FORMAT_VERSION: &'static str = "v2";
+    const FORMAT_VERSION: &'static str = "v4";
+
+    /// Header line that introduces the v3 layout (adds the inode field)
+    const V3_HEADER: &'static str = "# sy bisync v3";
+
+    /// Header line for v4 (v3 layout plus tombstone records)
+    const V4_HEADER: &'static str = "# sy bisync v4";
+
+    /// Prefix of tombstone records
+    const TOMBSTONE_TAG: &'static str = "tombstone";
+
+    fn has_inode_field(header: &str) -> bool {
+        header.starts_with(Self::V3_HEADER) || header.starts_with(Self::V4_HEADER)
+    }
 
     /// Generate unique hash for source+dest pair
     fn generate_sync_pair_hash(source: &Path, dest: &Path) -> String {
@@ -104,12 +178,12 @@ impl BisyncStateDb {
             fs::remove_file(&state_file)?;
         }
 
-        let states = if state_file.exists() {
+        let (states, tombstones) = if state_file.exists() {
             // Validate before loading to catch corruption early
             Self::validate_state_file(&state_file)?;
             Self::load_from_file(&state_file)?
         } else {
-            HashMap::new()
+            (HashMap::new(), HashMap::new())
         };
 
         Ok(Self {
@@ -117,6 +191,7 @@ impl BisyncStateDb {
             source_path: source.to_path_buf(),
             dest_path: dest.to_path_buf(),
             states,
+            tombstones,
         })
     }
 
@@ -174,24 +249,39 @@ impl BisyncStateDb {
         }
 
         // Count non-comment lines for basic sanity check
+        // (tombstones are validated when parsed)
         let data_lines: Vec<&String> = lines
             .iter()
             .filter(|line| {
                 let trimmed = line.trim();
-                !trimmed.is_empty() && !trimmed.starts_with('#')
+                !trimmed.is_empty()
+                    && !trimmed.starts_with('#')
+                    && !trimmed.starts_with(Self::TOMBSTONE_TAG)
             })
             .collect();
 
+        let is_v3 = lines.iter().any(|line| Self::has_inode_field(line.trim()));
+
         // If there are data lines, validate structure of first few
         if !data_lines.is_empty() {
             for (idx, line) in data_lines.iter().take(5).enumerate() {
-                let parts: Vec<&str> = line.splitn(6, ' ').collect();
-                if parts.len() != 5 && parts.len() != 6 {
+                let parts: Vec<&str> = if is_v3 {
+                    line.splitn(7, ' ').collect()
+                } else {
+                    line.splitn(6, ' ').collect()
+                };
+                let valid_count = if is_v3 {
+                    parts.len() == 7
+                } else {
+                    parts.len() == 5 || parts.len() == 6
+                };
+                if !valid_count {
                     return Err(crate::error::SyncError::StateCorruption {
                         path: path.to_path_buf(),
                         reason: format!(
-                            "Invalid field count at line {}: expected 5 or 6 fields, got {}",
+                            "Invalid field count at line {}: expected {} fields, got {}",
                             idx + 1,
+                            if is_v3 { "7" } else { "5 or 6" },
                             parts.len()
                         ),
                     });
@@ -245,17 +335,31 @@ impl BisyncStateDb {
                         ),
                     });
                 }
+
+                // Validate inode format (decimal or '-', v3 only)
+                if is_v3 && parts[5] != "-" && parts[5].parse::<u64>().is_err() {
+                    return Err(crate::error::SyncError::StateCorruption {
+                        path: path.to_path_buf(),
+                        reason: format!(
+                            "Invalid inode '{}' at line {}: must be a number or '-'",
+                            parts[5],
+                            idx + 1
+                        ),
+                    });
+                }
             }
         }
 
         Ok(())
     }
 
-    /// Load state from file
-    fn load_from_file(path: &Path) -> Result<StateMap> {
+    /// Load state and tombstones from file
+    fn load_from_file(path: &Path) -> Result<(StateMap, TombstoneMap)> {
         let file = fs::File::open(path)?;
         let reader = BufReader::new(file);
         let mut states: StateMap = HashMap::new();
+        let mut tombstones: TombstoneMap = HashMap::new();
+        let mut is_v3 = false;
 
         for (line_num, line) in reader.lines().enumerate() {
             let line = line?;
@@ -263,11 +367,37 @@ impl BisyncStateDb {
 
             // Skip comments and blank lines
             if line.is_empty() || line.starts_with('#') {
+                if Self::has_inode_field(line) {
+                    is_v3 = true;
+                }
+                continue;
+            }
+
+            if line.starts_with(Self::TOMBSTONE_TAG) {
+                let tombstone = Self::parse_tombstone(line, line_num + 1)?;
+                tombstones.insert(tombstone.path.clone(), tombstone);
                 continue;
             }
 
-            // Parse: <side> <mtime_ns> <size> <checksum> <last_sync_ns> <path>
-            let parts: Vec<&str> = line.splitn(6, ' ').collect();
+            // Parse: <side> <mtime_ns> <size> <checksum> <last_sync_ns> [<inode>] <path>
+            let mut parts: Vec<&str> = if is_v3 {
+                line.splitn(7, ' ').collect()
+            } else {
+                line.splitn(6, ' ').collect()
+            };
+
+            // v3 adds the inode field before the path
+            let inode_str = if is_v3 && parts.len() == 7 {
+                Some(parts.remove(5))
+            } else if is_v3 {
+                return Err(crate::error::SyncError::Config(format!(
+                    "Malformed state file line {}: expected 7 fields, got {}",
+                    line_num + 1,
+                    parts.len()
+                )));
+            } else {
+                None
+            };
 
             // Support both v1 (5 parts) and v2 (6 parts) formats
             let (side_str, mtime_str, size_str, checksum_str, last_sync_str, path_str) =
@@ -329,6 +459,17 @@ impl BisyncStateDb {
                 ))
             })?;
 
+            let inode: Option<u64> = match inode_str {
+                None | Some("-") => None,
+                Some(s) => Some(s.parse().map_err(|_| {
+                    crate::error::SyncError::Config(format!(
+                        "Invalid inode '{}' on line {}",
+                        s,
+                        line_num + 1
+                    ))
+                })?),
+            };
+
             // Unquote and unescape path
             let path_unescaped = if path_str.starts_with('"') && path_str.ends_with('"') {
                 Self::unescape_path(&path_str[1..path_str.len() - 1])
@@ -350,6 +491,7 @@ impl BisyncStateDb {
                 size,
                 checksum,
                 last_sync,
+                inode,
             };
 
             let entry = states.entry(path).or_insert((None, None));
@@ -359,7 +501,54 @@ impl BisyncStateDb {
             }
         }
 
-        Ok(states)
+        Ok((states, tombstones))
+    }
+
+    /// Parse: tombstone <deleted_at_ns> <deleted_on> <size> <mtime_ns> <trash|-> <path>
+    fn parse_tombstone(line: &str, line_num: usize) -> Result<Tombstone> {
+        let invalid = |what: &str, value: &str| {
+            crate::error::SyncError::Config(format!(
+                "Invalid tombstone {} '{}' on line {}",
+                what, value, line_num
+            ))
+        };
+
+        let parts: Vec<&str> = line.splitn(7, ' ').collect();
+        if parts.len() != 7 {
+            return Err(crate::error::SyncError::Config(format!(
+                "Malformed tombstone on line {}: expected 7 fields, got {}",
+                line_num,
+                parts.len()
+            )));
+        }
+
+        let deleted_at_ns: i64 = parts[1]
+            .parse()
+            .map_err(|_| invalid("timestamp", parts[1]))?;
+        let deleted_on = Side::from_str(parts[2]).ok_or_else(|| invalid("side", parts[2]))?;
+        let size: u64 = parts[3].parse().map_err(|_| invalid("size", parts[3]))?;
+        let mtime_ns: i64 = parts[4].parse().map_err(|_| invalid("mtime", parts[4]))?;
+        let trashed = match parts[5] {
+            "trash" => true,
+            "-" => false,
+            other => return Err(invalid("trash flag", other)),
+        };
+
+        let path_str = parts[6];
+        let path = if path_str.starts_with('"') && path_str.ends_with('"') && path_str.len() >= 2 {
+            Self::unescape_path(&path_str[1..path_str.len() - 1])
+        } else {
+            path_str.to_string()
+        };
+
+        Ok(Tombstone {
+            path: PathBuf::from(path),
+            deleted_on,
+            deleted_at: UNIX_EPOCH + Duration::from_nanos(deleted_at_ns.max(0) as u64),
+            size,
+            mtime: UNIX_EPOCH + Duration::from_nanos(mtime_ns.max(0) as u64),
+            trashed,
+        })
     }
 
     /// Save all state to file (atomic write)
@@ -393,6 +582,22 @@ impl BisyncStateDb {
                     self.write_state(&mut file, state)?;
                 }
             }
+
+            let mut tombstones: Vec<_> = self.tombstones.values().collect();
+            tombstones.sort_by(|a, b| a.path.cmp(&b.path));
+            for tombstone in tombstones {
+                writeln!(
+                    file,
+                    "{} {} {} {} {} {} {}",
+                    Self::TOMBSTONE_TAG,
+                    Self::system_time_to_nanos(tombstone.deleted_at),
+                    tombstone.deleted_on.as_str(),
+                    tombstone.size,
+                    Self::system_time_to_nanos(tombstone.mtime),
+                    if tombstone.trashed { "trash" } else { "-" },
+                    Self::escape_path(&tombstone.path.to_string_lossy())
+                )?;
+            }
         }
 
         // Atomic rename
@@ -442,17 +647,23 @@ impl BisyncStateDb {
             "-".to_string()
         };
 
+        let inode_str = state
+            .inode
+            .map(|ino| ino.to_string())
+            .unwrap_or_else(|| "-".to_string());
+
         let path_str = state.path.to_string_lossy();
         let path_escaped = Self::escape_path(&path_str);
 
         writeln!(
             file,
-            "{} {} {} {} {} {}",
+            "{} {} {} {} {} {} {}",
             state.side.as_str(),
             mtime_ns,
             state.size,
             checksum_str,
             last_sync_ns,
+            inode_str,
             path_escaped
         )?;
 
@@ -469,6 +680,8 @@ impl BisyncStateDb {
             Side::Source => entry.0 = Some(state.clone()),
             Side::Dest => entry.1 = Some(state.clone()),
         }
+        // The path is live again
+        self.tombstones.remove(&state.path);
         self.save_to_file()?;
         Ok(())
     }
@@ -498,20 +711,128 @@ impl BisyncStateDb {
         Ok(())
     }
 
+    /// Delete state for a file and leave a tombstone behind
+    ///
+    /// `deleted_on` is the side where the user removed the file; `trashed`
+    /// records whether the other side's copy went to the trash.
+    pub fn record_deletion(
+        &mut self,
+        path: &Path,
+        deleted_on: Side,
+        deleted_at: SystemTime,
+        trashed: bool,
+    ) -> Result<()> {
+        let prior = self.states.remove(path);
+        let (size, mtime) = prior
+            .and_then(|(s, d)| s.or(d))
+            .map(|state| (state.size, state.mtime))
+            .unwrap_or((0, UNIX_EPOCH));
+
+        self.tombstones.insert(
+            path.to_path_buf(),
+            Tombstone {
+                path: path.to_path_buf(),
+                deleted_on,
+                deleted_at,
+                size,
+                mtime,
+                trashed,
+            },
+        );
+        self.save_to_file()?;
+        Ok(())
+    }
+
+    /// All live tombstones
+    pub fn tombstones(&self) -> &TombstoneMap {
+        &self.tombstones
+    }
+
+    /// Forget a tombstone (e.g. the file was restored from trash)
+    pub fn clear_tombstone(&mut self, path: &Path) -> Result<()> {
+        if self.tombstones.remove(path).is_some() {
+            self.save_to_file()?;
+        }
+        Ok(())
+    }
+
+    /// Record current inodes for unchanged files on one side
+    ///
+    /// Only entries whose size and mtime still match the stored state are
+    /// touched. Keeps inode history fresh for move detection without
+    /// rewriting the state file when nothing changed.
+    pub fn refresh_inodes(&mut self, side: Side, entries: &[FileEntry]) -> Result<()> {
+        let mut changed = false;
+
+        for entry in entries {
+            let Some(inode) = entry.inode else {
+                continue;
+            };
+            let Some((source_state, dest_state)) = self.states.get_mut(&*entry.relative_path)
+            else {
+                continue;
+            };
+            let state = match side {
+                Side::Source => source_state.as_mut(),
+                Side::Dest => dest_state.as_mut(),
+            };
+            if let Some(state) = state {
+                if state.size == entry.size
+                    && state.mtime == entry.modified
+                    && state.inode != Some(inode)
+                {
+                    state.inode = Some(inode);
+                    changed = true;
+                }
+            }
+        }
+
+        if changed {
+            self.save_to_file()?;
+        }
+        Ok(())
+    }
+
     /// Clear all state (for --clear-bisync-state)
     pub fn clear_all(&mut self) -> Result<()> {
         self.states.clear();
+        self.tombstones.clear();
         self.save_to_file()?;
         Ok(())
     }
 
-    /// Prune deleted files (files not in recent syncs)
-    #[allow(dead_code)] // Future feature: automatic state pruning
-    pub fn prune_stale(&mut self, keep_syncs: usize) -> Result<usize> {
-        // Not implemented yet - will add in follow-up
-        // For now, just return 0 (no pruning)
-        let _ = keep_syncs;
-        Ok(0)
+    /// Expire tombstones older than `retention`
+    ///
+    /// Returns the expired tombstones so callers can purge their trashed
+    /// copies. A zero retention expires everything.
+    pub fn prune_stale(&mut self, retention: Duration) -> Result<Vec<Tombstone>> {
+        let now = SystemTime::now();
+        let expired_paths: Vec<PathBuf> = self
+            .tombstones
+            .values()
+            .filter(|t| {
+                now.duration_since(t.deleted_at)
+                    .map(|age| age >= retention)
+                    .unwrap_or(false)
+            })
+            .map(|t| t.path.clone())
+            .collect();
+
+        if expired_paths.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let expired = expired_paths
+            .iter()
+            .filter_map(|p| self.tombstones.remove(p))
+            .collect();
+        self.save_to_file()?;
+        Ok(expired)
+    }
+
+    /// Directory for this sync pair's content checksum cache
+    pub fn checksum_cache_dir(&self) -> PathBuf {
+        self.state_file.with_extension("checksums")
     }
 
     /// Get sync pair hash (for logging/debugging)
@@ -640,6 +961,7 @@ mod tests {
             size: 1024,
             checksum: Some(0x123456789abcdef0),
             last_sync: SystemTime::now(),
+            inode: None,
         };
 
         db.store(&state).unwrap();
@@ -663,6 +985,7 @@ mod tests {
             size: 1024,
             checksum: Some(0x111),
             last_sync: SystemTime::now(),
+            inode: None,
         };
 
         let dest_state = SyncState {
@@ -672,6 +995,7 @@ mod tests {
             size: 2048,
             checksum: Some(0x222),
             last_sync: SystemTime::now(),
+            inode: None,
         };
 
         db.store(&source_state).unwrap();
@@ -699,6 +1023,7 @@ mod tests {
                 size: 100,
                 checksum: None,
                 last_sync: SystemTime::now(),
+                inode: None,
             },
             SyncState {
                 path: PathBuf::from("file1.txt"),
@@ -707,6 +1032,7 @@ mod tests {
                 size: 100,
                 checksum: None,
                 last_sync: SystemTime::now(),
+                inode: None,
             },
             SyncState {
                 path: PathBuf::from("file2.txt"),
@@ -715,6 +1041,7 @@ mod tests {
                 size: 200,
                 checksum: None,
                 last_sync: SystemTime::now(),
+                inode: None,
             },
         ];
 
@@ -746,6 +1073,7 @@ mod tests {
             size: 1024,
             checksum: None,
             last_sync: SystemTime::now(),
+            inode: None,
         };
 
         db.store(&state).unwrap();
@@ -768,6 +1096,7 @@ mod tests {
                 size: 1024,
                 checksum: None,
                 last_sync: SystemTime::now(),
+                inode: None,
             };
             db.store(&state).unwrap();
         }
@@ -849,6 +1178,7 @@ mod tests {
             size: 1024,
             checksum: Some(0xdeadbeef),
             last_sync: SystemTime::now(),
+            inode: None,
         };
 
         // Store and retrieve
@@ -876,6 +1206,7 @@ mod tests {
             size: 1024,
             checksum: None,
             last_sync: now, // But synced just now
+            inode: None,
         };
 
         db.store(&state).unwrap();
@@ -909,7 +1240,7 @@ mod tests {
         writeln!(file, "source 1730000000000000000 1024 abc123 test.txt").unwrap();
 
         // Load v1 file
-        let states = BisyncStateDb::load_from_file(&state_file).unwrap();
+        let (states, _) = BisyncStateDb::load_from_file(&state_file).unwrap();
 
         assert_eq!(states.len(), 1);
         let (source_state, _) = states.get(&PathBuf::from("test.txt")).unwrap();
@@ -919,6 +1250,134 @@ mod tests {
         assert_eq!(state.mtime, state.last_sync);
     }
 
+    #[test]
+    fn test_v2_backward_compatibility() {
+        use std::io::Write;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let state_file = temp_dir.path().join("test.lst");
+
+        let mut file = std::fs::File::create(&state_file).unwrap();
+        writeln!(file, "# sy bisync v2").unwrap();
+        writeln!(
+            file,
+            "dest 1730000000000000000 1024 - 1730000000000000000 \"dir/file with spaces.txt\""
+        )
+        .unwrap();
+
+        BisyncStateDb::validate_state_file(&state_file).unwrap();
+        let (states, _) = BisyncStateDb::load_from_file(&state_file).unwrap();
+
+        let (_, dest_state) = states
+            .get(&PathBuf::from("dir/file with spaces.txt"))
+            .unwrap();
+        assert_eq!(dest_state.as_ref().unwrap().inode, None);
+    }
+
+    #[test]
+    #[serial]
+    fn test_inode_round_trip() {
+        let (mut db, _temp) = temp_db();
+
+        let state = SyncState {
+            path: PathBuf::from("moved file.txt"),
+            side: Side::Dest,
+            mtime: SystemTime::now(),
+            size: 2048,
+            checksum: None,
+            last_sync: SystemTime::now(),
+            inode: Some(424242),
+        };
+        db.store(&state).unwrap();
+
+        BisyncStateDb::validate_state_file(&db.state_file).unwrap();
+        let (states, _) = BisyncStateDb::load_from_file(&db.state_file).unwrap();
+        let (_, dest_state) = states.get(&state.path).unwrap();
+        assert_eq!(dest_state.as_ref().unwrap().inode, Some(424242));
+    }
+
+    #[test]
+    #[serial]
+    fn test_tombstone_round_trip() {
+        let (mut db, _temp) = temp_db();
+        let path = PathBuf::from("gone file.txt");
+
+        for side in [Side::Source, Side::Dest] {
+            db.store(&SyncState {
+                path: path.clone(),
+                side,
+                mtime: UNIX_EPOCH + Duration::from_secs(1_000),
+                size: 77,
+                checksum: None,
+                last_sync: SystemTime::now(),
+                inode: None,
+            })
+            .unwrap();
+        }
+
+        db.record_deletion(&path, Side::Source, SystemTime::now(), true)
+            .unwrap();
+        assert!(db.get(&path, Side::Dest).unwrap().is_none());
+
+        BisyncStateDb::validate_state_file(&db.state_file).unwrap();
+        let (states, tombstones) = BisyncStateDb::load_from_file(&db.state_file).unwrap();
+        assert!(states.is_empty());
+
+        let tombstone = tombstones.get(&path).unwrap();
+        assert_eq!(tombstone.deleted_on, Side::Source);
+        assert_eq!(tombstone.propagated_to(), Side::Dest);
+        assert_eq!(tombstone.size, 77);
+        assert_eq!(tombstone.mtime, UNIX_EPOCH + Duration::from_secs(1_000));
+        assert!(tombstone.trashed);
+        assert!(tombstone.trash_path().starts_with(TRASH_DIR));
+        assert!(tombstone.trash_path().ends_with("gone file.txt"));
+    }
+
+    #[test]
+    #[serial]
+    fn test_store_clears_tombstone() {
+        let (mut db, _temp) = temp_db();
+        let path = PathBuf::from("back.txt");
+
+        db.record_deletion(&path, Side::Dest, SystemTime::now(), false)
+            .unwrap();
+        assert!(db.tombstones().contains_key(&path));
+
+        db.store(&SyncState {
+            path: path.clone(),
+            side: Side::Source,
+            mtime: SystemTime::now(),
+            size: 1,
+            checksum: None,
+            last_sync: SystemTime::now(),
+            inode: None,
+        })
+        .unwrap();
+        assert!(!db.tombstones().contains_key(&path));
+    }
+
+    #[test]
+    #[serial]
+    fn test_prune_stale_expires_tombstones() {
+        let (mut db, _temp) = temp_db();
+        db.record_deletion(Path::new("a.txt"), Side::Source, SystemTime::now(), false)
+            .unwrap();
+
+        // Fresh tombstone survives a long retention
+        let expired = db.prune_stale(Duration::from_secs(3600)).unwrap();
+        assert!(expired.is_empty());
+        assert_eq!(db.tombstones().len(), 1);
+
+        // Zero retention expires everything
+        let expired = db.prune_stale(Duration::ZERO).unwrap();
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].path, PathBuf::from("a.txt"));
+        assert!(db.tombstones().is_empty());
+
+        let (_, tombstones) = BisyncStateDb::load_from_file(&db.state_file).unwrap();
+        assert!(tombstones.is_empty());
+    }
+
     #[test]
     fn test_parse_error_handling() {
         use std::io::Write;
@@ -2,6 +2,7 @@ use crate::path::SyncPath;
 use clap::{Parser, ValueEnum};
 
 // Import integrity types for verification modes
+use crate::delta::{BlockSize, DeltaAlgo};
 use crate::integrity::ChecksumType;
 
 // Import compression types for detection modes
@@ -167,6 +168,8 @@ pub struct Cli {
     pub delete_threshold: u8,
 
     /// Move deleted files to trash instead of permanent deletion
+    /// In bidirectional sync, propagated deletions go to .sy-trash/ on the
+    /// affected side and are purged when their tombstone expires
     #[arg(long)]
     pub trash: bool,
 
@@ -294,6 +297,25 @@ pub struct Cli {
     #[arg(long)]
     pub prune_checksum_db: bool,
 
+    /// Delta algorithm for updating existing files in server/daemon mode
+    /// - block: Fixed-size blocks with a rolling checksum (rsync-style, default)
+    /// - cdc: Content-defined chunks; survives insertions and deletions
+    ///   (with --checksum-db the receiver caches its chunk lists)
+    #[arg(long, value_enum, default_value = "block")]
+    pub delta_algo: DeltaAlgo,
+
+    /// Block size for delta sync: auto, adaptive, or a size (e.g. 8KB)
+    /// - auto: square root of the file size (512 bytes - 128KB, default)
+    /// - adaptive: auto, scaled per file by how much it changed last time
+    /// - SIZE: the same size for every file (e.g. a database's page size)
+    #[arg(long, default_value = "auto", value_name = "SIZE")]
+    pub block_size: BlockSize,
+
+    /// Include matched and literal bytes of each delta in JSON update events
+    /// (local and SFTP syncs; server and daemon sessions don't emit them)
+    #[arg(long)]
+    pub delta_stats: bool,
+
     /// Verify file integrity after write using xxHash3 checksums
     ///
     /// By default, sy trusts the OS like rsync does. Enable this flag
@@ -302,6 +324,7 @@ pub struct Cli {
     pub verify: bool,
 
     /// Enable compression for network transfers (auto-detects based on file type)
+    /// Server and daemon sessions then compress their whole stream
     #[arg(short = 'z', long)]
     pub compress: bool,
 
@@ -411,9 +434,16 @@ pub struct Cli {
     pub json: bool,
 
     /// Watch mode - continuously monitor source for changes
+    /// (with --bidirectional, both sides are watched; remote sides are polled)
     #[arg(short = 'w', long)]
     pub watch: bool,
 
+    /// Poll the source every N seconds instead of using file system events
+    /// (for NFS/SMB/FUSE sources; used automatically when one is detected;
+    /// with --bidirectional, both sides are polled)
+    #[arg(long, value_name = "SECONDS")]
+    pub watch_poll: Option<u64>,
+
     /// Disable hook execution (skip pre-sync and post-sync hooks)
     #[arg(long)]
     pub no_hooks: bool,
@@ -460,6 +490,45 @@ pub struct Cli {
     #[arg(long)]
     pub force_resync: bool,
 
+    /// Detect renamed and moved files and apply them as renames
+    /// Matches deleted and new files by inode or size + mtime (confirmed
+    /// by checksum), avoiding a full re-copy of moved data. One-way syncs
+    /// need --delete and an engine transfer (local, sftp:// or cloud), not
+    /// a server or daemon session
+    #[arg(long)]
+    pub detect_moves: bool,
+
+    /// Delta-encode new files against similar destination files
+    /// A new file is matched with a similarly named file in the same
+    /// destination directory (e.g. app-1.3.tar next to app-1.2.tar)
+    #[arg(short = 'y', long)]
+    pub fuzzy: bool,
+
+    /// Record this sync's changes in FILE, then apply them
+    /// The batch can be replayed with --read-batch on mirrors whose
+    /// destination matches this one (nothing is applied with --dry-run).
+    /// Batches carry permissions and mtimes, not xattrs, ACLs or ownership
+    #[arg(long, value_name = "FILE")]
+    pub write_batch: Option<std::path::PathBuf>,
+
+    /// Apply a batch written by --write-batch to the given destination
+    /// Usage: sy --read-batch FILE /mirror/path
+    #[arg(long, value_name = "FILE")]
+    pub read_batch: Option<std::path::PathBuf>,
+
+    /// Days to remember deletions in bidirectional sync (default: 30)
+    /// A stale copy of a deleted file is deleted instead of resurrected
+    /// while its tombstone is kept. Set to 0 to expire them on the next sync
+    #[arg(long, default_value = "30")]
+    pub tombstone_retention: u64,
+
+    /// Use content checksums to detect changes in bidirectional sync
+    /// Catches edits that preserved mtime and resolves "conflicts" where
+    /// both sides already hold identical content. Checksums are cached per
+    /// size, mtime and inode; --clear-bisync-state rehashes everything
+    #[arg(long)]
+    pub bisync_checksum: bool,
+
     /// Maximum retry attempts for network operations (default: 3, 0 = no retries)
     #[arg(long, default_value = "3")]
     pub retry: u32,
@@ -475,6 +544,13 @@ pub struct Cli {
     #[arg(long, hide = true)]
     pub server: bool,
 
+    /// Remote shell used to start `sy --server` (like rsync's -e/--rsh)
+    /// Runs as `<command> [-l user] <host> sy --server <path>`; put `%h`
+    /// where the host belongs if it isn't the last argument
+    /// Examples: -e "ssh -p 2222", -e "docker exec -i", -e "kubectl exec -i %h --"
+    #[arg(short = 'e', long)]
+    pub rsh: Option<String>,
+
     // === Daemon mode ===
     /// Run as a persistent daemon server listening on a Unix socket.
     /// This eliminates cold-start overhead (~2s) for repeated syncs.
@@ -492,6 +568,19 @@ pub struct Cli {
     #[arg(long, default_value = "~/.sy/daemon.sock")]
     pub socket: String,
 
+    /// Serve only the named modules from this TOML file (with --daemon)
+    /// Clients then address daemon:module/sub/path or sy://host/module/sub/path
+    #[arg(long, value_name = "FILE")]
+    pub daemon_config: Option<std::path::PathBuf>,
+
+    /// Refuse incoming symlinks that are absolute or point above the root
+    /// Applies when receiving with --server or --daemon (daemon modules set
+    /// this per module with `safe_links`, which defaults to on), and to
+    /// server-protocol transfers: pushes pass it on to `sy --server`, pulls
+    /// apply it locally
+    #[arg(long)]
+    pub safe_links: bool,
+
     /// Connect to a running daemon via Unix socket instead of spawning SSH.
     /// Provide the path to a Unix socket (local or forwarded via SSH).
     ///
@@ -515,6 +604,29 @@ pub struct Cli {
     #[arg(long)]
     pub daemon_auto: bool,
 
+    /// Listen for daemon clients on a TCP address instead of the Unix socket
+    /// Clients connect with sy://host[:port]/path and must present a token
+    /// from --auth-tokens (set SY_DAEMON_TOKEN=name:secret on the client)
+    /// Example: sy --daemon --listen 0.0.0.0:8730 --auth-tokens ~/.sy/tokens
+    #[arg(long, value_name = "ADDR")]
+    pub listen: Option<String>,
+
+    /// Token file for --listen: one `name:secret` per line, `#` comments allowed
+    #[arg(long, value_name = "FILE")]
+    pub auth_tokens: Option<std::path::PathBuf>,
+
+    /// PEM certificate chain served by the TCP daemon (enables TLS)
+    #[arg(long, value_name = "FILE", requires = "tls_key")]
+    pub tls_cert: Option<std::path::PathBuf>,
+
+    /// PEM private key for --tls-cert
+    #[arg(long, value_name = "FILE", requires = "tls_cert")]
+    pub tls_key: Option<std::path::PathBuf>,
+
+    /// PEM CA certificate used to verify a TLS daemon (sy:// paths)
+    #[arg(long, value_name = "FILE")]
+    pub tls_ca: Option<std::path::PathBuf>,
+
     // === rsync compatibility flags (hidden, no-op) ===
     /// Recursive (no-op: sy is always recursive, for rsync compatibility)
     #[arg(short = 'r', hide = true)]
@@ -549,6 +661,101 @@ impl Cli {
             );
         }
 
+        if self.delta_stats && !self.json {
+            anyhow::bail!("--delta-stats requires --json");
+        }
+
+        // One-way move detection pairs the engine's deletions with new files
+        if self.detect_moves && !self.bidirectional {
+            if !self.delete {
+                anyhow::bail!("--detect-moves requires --delete (or --bidirectional)");
+            }
+            let source_local = self.source.as_ref().is_some_and(|p| p.is_local());
+            let dest_local = self.destination.as_ref().is_some_and(|p| p.is_local());
+            let ssh = !self.watch
+                && (source_local && self.destination.as_ref().is_some_and(|p| p.is_remote())
+                    || dest_local && self.source.as_ref().is_some_and(|p| p.is_remote()));
+            let daemon = [&self.source, &self.destination]
+                .into_iter()
+                .flatten()
+                .any(|p| p.is_tcp_daemon());
+            if ssh || daemon || self.rsh.is_some() || self.use_daemon.is_some() || self.daemon_auto
+            {
+                anyhow::bail!(
+                    "--detect-moves is not supported by server or daemon sessions, which never delete; use an sftp:// path or --bidirectional"
+                );
+            }
+        }
+
+        // Validate poll interval
+        if let Some(interval) = self.watch_poll {
+            if !self.watch {
+                anyhow::bail!("--watch-poll requires --watch");
+            }
+            if interval == 0 {
+                anyhow::bail!("--watch-poll interval must be at least 1 second");
+            }
+        }
+
+        // Validate remote shell
+        if let Some(rsh) = &self.rsh {
+            crate::transport::server::RemoteShell::parse(rsh)?;
+            let source_remote = self.source.as_ref().is_some_and(|p| p.is_remote());
+            let dest_remote = self.destination.as_ref().is_some_and(|p| p.is_remote());
+            if source_remote == dest_remote {
+                anyhow::bail!(
+                    "--rsh requires exactly one remote (host:path) source or destination"
+                );
+            }
+            if self.watch && source_remote {
+                anyhow::bail!("--rsh with --watch requires a local source");
+            }
+            if self.bidirectional || self.use_daemon.is_some() || self.daemon_auto {
+                anyhow::bail!(
+                    "--rsh cannot be used with --bidirectional, --use-daemon or --daemon-auto"
+                );
+            }
+        }
+
+        if self.daemon_config.is_some() && !self.daemon {
+            anyhow::bail!("--daemon-config requires --daemon");
+        }
+
+        if self.safe_links && self.daemon_config.is_some() {
+            anyhow::bail!("--safe-links is set per module in --daemon-config (safe_links = true)");
+        }
+
+        // Validate TCP daemon listener
+        if self.listen.is_some() {
+            if !self.daemon {
+                anyhow::bail!("--listen requires --daemon");
+            }
+            if self.auth_tokens.is_none() {
+                anyhow::bail!("--listen requires --auth-tokens");
+            }
+        } else if self.tls_cert.is_some() || self.auth_tokens.is_some() {
+            anyhow::bail!("--tls-cert and --auth-tokens only apply with --listen");
+        }
+
+        // TCP daemon paths sync between a local directory and the daemon
+        let source_tcp = self.source.as_ref().is_some_and(|p| p.is_tcp_daemon());
+        let dest_tcp = self.destination.as_ref().is_some_and(|p| p.is_tcp_daemon());
+        if source_tcp || dest_tcp {
+            let other_local = if source_tcp {
+                self.destination.as_ref().is_some_and(|p| p.is_local())
+            } else {
+                self.source.as_ref().is_some_and(|p| p.is_local())
+            };
+            if !other_local {
+                anyhow::bail!("sy:// daemon paths must be synced with a local directory");
+            }
+            if self.watch || self.bidirectional || self.use_daemon.is_some() {
+                anyhow::bail!(
+                    "sy:// daemon paths cannot be used with --watch, --bidirectional or --use-daemon"
+                );
+            }
+        }
+
         // --verify-only conflicts with modification flags
         if self.verify_only {
             if self.delete {
@@ -588,9 +795,6 @@ impl Cli {
                     "--bidirectional cannot be used with --verify-only (conflicts with sync logic)"
                 );
             }
-            if self.watch {
-                anyhow::bail!("--bidirectional with --watch is not yet supported (deferred to future version)");
-            }
 
             // Bidirectional sync doesn't support S3 or GCS paths
             let source_is_s3 = self.source.as_ref().is_some_and(|p| p.is_s3());
@@ -604,6 +808,46 @@ impl Cli {
             }
         }
 
+        // Batch files are written and replayed between local directories
+        if self.write_batch.is_some() || self.read_batch.is_some() {
+            if self.write_batch.is_some() && self.read_batch.is_some() {
+                anyhow::bail!("--write-batch and --read-batch cannot be used together");
+            }
+            if self.watch
+                || self.bidirectional
+                || self.verify_only
+                || self.use_daemon.is_some()
+                || self.daemon_auto
+            {
+                anyhow::bail!(
+                    "--write-batch and --read-batch cannot be used with --watch, --bidirectional, --verify-only, --use-daemon or --daemon-auto"
+                );
+            }
+            let paths = [&self.source, &self.destination];
+            if paths.into_iter().flatten().any(|p| !p.is_local()) {
+                anyhow::bail!("--write-batch and --read-batch only support local paths");
+            }
+            // Batches carry permissions and mtimes only; mirrors would miss the rest
+            if self.preserve_xattrs
+                || self.preserve_acls
+                || self.preserve_flags
+                || self.preserve_hardlinks
+                || self.should_preserve_owner()
+                || self.should_preserve_group()
+            {
+                anyhow::bail!(
+                    "Batch files don't record xattrs, ACLs, file flags, hard links or ownership; \
+                     drop -X, -A, -F, -H, -o and -g (-a implies -o and -g)"
+                );
+            }
+        }
+        if self.read_batch.is_some() {
+            if self.source.is_some() || self.destination.is_none() {
+                anyhow::bail!("--read-batch takes a single destination path");
+            }
+            return Ok(());
+        }
+
         // --list-profiles, --show-profile, --server, and --daemon don't need source/destination
         if self.list_profiles || self.show_profile.is_some() || self.server || self.daemon {
             return Ok(());
@@ -687,6 +931,39 @@ impl Cli {
             .is_some_and(|s| s.is_local() && s.path().is_file())
     }
 
+    /// First flag set that only the sync engine honors, if any
+    ///
+    /// Server and daemon sessions push every scanned file and compare by
+    /// size and mtime; they don't delete, filter, throttle, or report
+    /// per-file delta stats.
+    #[cfg(feature = "watch")]
+    pub fn engine_only_flag(&self) -> Option<&'static str> {
+        let flags = [
+            (self.delete, "--delete"),
+            (!self.exclude.is_empty(), "--exclude"),
+            (!self.include.is_empty(), "--include"),
+            (!self.filter.is_empty(), "--filter"),
+            (self.include_from.is_some(), "--include-from"),
+            (self.exclude_from.is_some(), "--exclude-from"),
+            (!self.ignore_template.is_empty(), "--ignore-template"),
+            (self.gitignore, "--gitignore"),
+            (self.exclude_vcs, "--exclude-vcs"),
+            (self.min_size.is_some(), "--min-size"),
+            (self.max_size.is_some(), "--max-size"),
+            (self.bwlimit.is_some(), "--bwlimit"),
+            (self.ignore_times, "--ignore-times"),
+            (self.size_only, "--size-only"),
+            (self.checksum, "--checksum"),
+            (self.update, "--update"),
+            (self.ignore_existing, "--ignore-existing"),
+            (self.delta_stats, "--delta-stats"),
+        ];
+        flags
+            .into_iter()
+            .find(|(set, _)| *set)
+            .map(|(_, flag)| flag)
+    }
+
     pub fn log_level(&self) -> tracing::Level {
         if self.quiet || self.json {
             return tracing::Level::ERROR;
@@ -712,17 +989,44 @@ impl Cli {
     }
 
     /// Check if group should be preserved (archive mode or explicit flag)
-    #[allow(dead_code)] // Public API for group preservation (planned feature)
     pub fn should_preserve_group(&self) -> bool {
         self.archive || self.preserve_group
     }
 
     /// Check if owner should be preserved (archive mode or explicit flag)
-    #[allow(dead_code)] // Public API for owner preservation (planned feature)
     pub fn should_preserve_owner(&self) -> bool {
         self.archive || self.preserve_owner
     }
 
+    /// Metadata the server and daemon protocols should carry (-o, -g, -X, -A, -F)
+    pub fn meta_options(&self) -> crate::server::meta::MetaOptions {
+        crate::server::meta::MetaOptions {
+            owner: self.should_preserve_owner(),
+            group: self.should_preserve_group(),
+            xattrs: self.preserve_xattrs,
+            acls: self.preserve_acls,
+            flags: self.preserve_flags,
+        }
+    }
+
+    /// How server and daemon transfers keep hard links (-H), delta-encode,
+    /// verify (--verify), and what their sessions ask for (-z, --safe-links)
+    pub fn transfer_options(&self) -> crate::sync::server_mode::TransferOptions {
+        crate::sync::server_mode::TransferOptions {
+            hardlinks: self.preserve_hardlinks,
+            verify: self.verification_mode().checksum_type(),
+            delta: self.delta_algo,
+            block_size: self.block_size,
+            chunk_cache: self.checksum_db,
+            fuzzy: self.fuzzy,
+            session: crate::transport::server::SessionOptions {
+                compress: self.compress
+                    && self.compression_detection != CompressionDetection::Never,
+                safe_links: self.safe_links,
+            },
+        }
+    }
+
     /// Check if device files should be preserved (archive mode or explicit flag)
     #[allow(dead_code)] // Public API for device preservation (planned feature)
     pub fn should_preserve_devices(&self) -> bool {
@@ -813,6 +1117,7 @@ mod tests {
             json: false,
             stream: false,
             watch: false,
+            watch_poll: None,
             no_hooks: false,
             abort_on_hook_failure: false,
             profile: None,
@@ -823,21 +1128,38 @@ mod tests {
             max_delete: 50,
             clear_bisync_state: false,
             force_resync: false,
+            detect_moves: false,
+            fuzzy: false,
+            write_batch: None,
+            read_batch: None,
+            tombstone_retention: 30,
+            bisync_checksum: false,
             use_cache: false,
             clear_cache: false,
             checksum_db: false,
             clear_checksum_db: false,
             prune_checksum_db: false,
+            delta_algo: DeltaAlgo::Block,
+            block_size: BlockSize::Auto,
+            delta_stats: false,
             retry: 3,
             retry_delay: 1,
             resume_only: false,
             clear_resume_state: false,
             recursive: false,
             server: false,
+            rsh: None,
             daemon: false,
             socket: "~/.sy/daemon.sock".to_string(),
+            daemon_config: None,
+            safe_links: false,
             use_daemon: None,
             daemon_auto: false,
+            listen: None,
+            auth_tokens: None,
+            tls_cert: None,
+            tls_key: None,
+            tls_ca: None,
         };
         assert!(cli.validate().is_ok());
     }
@@ -905,6 +1227,7 @@ mod tests {
             json: false,
             stream: false,
             watch: false,
+            watch_poll: None,
             no_hooks: false,
             abort_on_hook_failure: false,
             profile: None,
@@ -915,21 +1238,38 @@ mod tests {
             max_delete: 50,
             clear_bisync_state: false,
             force_resync: false,
+            detect_moves: false,
+            fuzzy: false,
+            write_batch: None,
+            read_batch: None,
+            tombstone_retention: 30,
+            bisync_checksum: false,
             use_cache: false,
             clear_cache: false,
             checksum_db: false,
             clear_checksum_db: false,
             prune_checksum_db: false,
+            delta_algo: DeltaAlgo::Block,
+            block_size: BlockSize::Auto,
+            delta_stats: false,
             retry: 3,
             retry_delay: 1,
             resume_only: false,
             clear_resume_state: false,
             recursive: false,
             server: false,
+            rsh: None,
             daemon: false,
             socket: "~/.sy/daemon.sock".to_string(),
+            daemon_config: None,
+            safe_links: false,
             use_daemon: None,
             daemon_auto: false,
+            listen: None,
+            auth_tokens: None,
+            tls_cert: None,
+            tls_key: None,
+            tls_ca: None,
         };
         let result = cli.validate();
         assert!(result.is_err());
@@ -1001,6 +1341,7 @@ mod tests {
             json: false,
             stream: false,
             watch: false,
+            watch_poll: None,
             no_hooks: false,
             abort_on_hook_failure: false,
             profile: None,
@@ -1011,11 +1352,20 @@ mod tests {
             max_delete: 50,
             clear_bisync_state: false,
             force_resync: false,
+            detect_moves: false,
+            fuzzy: false,
+            write_batch: None,
+            read_batch: None,
+            tombstone_retention: 30,
+            bisync_checksum: false,
             use_cache: false,
             clear_cache: false,
             checksum_db: false,
             clear_checksum_db: false,
             prune_checksum_db: false,
+            delta_algo: DeltaAlgo::Block,
+            block_size: BlockSize::Auto,
+            delta_stats: false,
             min_size: None,
             max_size: None,
             retry: 3,
@@ -1024,10 +1374,18 @@ mod tests {
             clear_resume_state: false,
             recursive: false,
             server: false,
+            rsh: None,
             daemon: false,
             socket: "~/.sy/daemon.sock".to_string(),
+            daemon_config: None,
+            safe_links: false,
             use_daemon: None,
             daemon_auto: false,
+            listen: None,
+            auth_tokens: None,
+            tls_cert: None,
+            tls_key: None,
+            tls_ca: None,
         };
         // Single file sync is now supported
         assert!(cli.validate().is_ok());
@@ -1098,6 +1456,7 @@ mod tests {
             json: false,
             stream: false,
             watch: false,
+            watch_poll: None,
             no_hooks: false,
             abort_on_hook_failure: false,
             profile: None,
@@ -1108,11 +1467,20 @@ mod tests {
             max_delete: 50,
             clear_bisync_state: false,
             force_resync: false,
+            detect_moves: false,
+            fuzzy: false,
+            write_batch: None,
+            read_batch: None,
+            tombstone_retention: 30,
+            bisync_checksum: false,
             use_cache: false,
             clear_cache: false,
             checksum_db: false,
             clear_checksum_db: false,
             prune_checksum_db: false,
+            delta_algo: DeltaAlgo::Block,
+            block_size: BlockSize::Auto,
+            delta_stats: false,
             min_size: None,
             max_size: None,
             retry: 3,
@@ -1121,10 +1489,18 @@ mod tests {
             clear_resume_state: false,
             recursive: false,
             server: false,
+            rsh: None,
             daemon: false,
             socket: "~/.sy/daemon.sock".to_string(),
+            daemon_config: None,
+            safe_links: false,
             use_daemon: None,
             daemon_auto: false,
+            listen: None,
+            auth_tokens: None,
+            tls_cert: None,
+            tls_key: None,
+            tls_ca: None,
         };
         assert!(cli.validate().is_ok());
     }
@@ -1190,6 +1566,7 @@ mod tests {
             json: false,
             stream: false,
             watch: false,
+            watch_poll: None,
             no_hooks: false,
             abort_on_hook_failure: false,
             profile: None,
@@ -1200,11 +1577,20 @@ mod tests {
             max_delete: 50,
             clear_bisync_state: false,
             force_resync: false,
+            detect_moves: false,
+            fuzzy: false,
+            write_batch: None,
+            read_batch: None,
+            tombstone_retention: 30,
+            bisync_checksum: false,
             use_cache: false,
             clear_cache: false,
             checksum_db: false,
             clear_checksum_db: false,
             prune_checksum_db: false,
+            delta_algo: DeltaAlgo::Block,
+            block_size: BlockSize::Auto,
+            delta_stats: false,
             min_size: None,
             max_size: None,
             retry: 3,
@@ -1213,10 +1599,18 @@ mod tests {
             clear_resume_state: false,
             recursive: false,
             server: false,
+            rsh: None,
             daemon: false,
             socket: "~/.sy/daemon.sock".to_string(),
+            daemon_config: None,
+            safe_links: false,
             use_daemon: None,
             daemon_auto: false,
+            listen: None,
+            auth_tokens: None,
+            tls_cert: None,
+            tls_key: None,
+            tls_ca: None,
         };
         assert_eq!(cli.log_level(), tracing::Level::ERROR);
     }
@@ -1282,6 +1676,7 @@ mod tests {
             json: false,
             stream: false,
             watch: false,
+            watch_poll: None,
             no_hooks: false,
             abort_on_hook_failure: false,
             profile: None,
@@ -1292,11 +1687,20 @@ mod tests {
             max_delete: 50,
             clear_bisync_state: false,
             force_resync: false,
+            detect_moves: false,
+            fuzzy: false,
+            write_batch: None,
+            read_batch: None,
+            tombstone_retention: 30,
+            bisync_checksum: false,
             use_cache: false,
             clear_cache: false,
             checksum_db: false,
             clear_checksum_db: false,
             prune_checksum_db: false,
+            delta_algo: DeltaAlgo::Block,
+            block_size: BlockSize::Auto,
+            delta_stats: false,
             min_size: None,
             max_size: None,
             retry: 3,
@@ -1305,10 +1709,18 @@ mod tests {
             clear_resume_state: false,
             recursive: false,
             server: false,
+            rsh: None,
             daemon: false,
             socket: "~/.sy/daemon.sock".to_string(),
+            daemon_config: None,
+            safe_links: false,
             use_daemon: None,
             daemon_auto: false,
+            listen: None,
+            auth_tokens: None,
+            tls_cert: None,
+            tls_key: None,
+            tls_ca: None,
         };
         assert_eq!(cli.log_level(), tracing::Level::INFO);
     }
@@ -1374,6 +1786,7 @@ mod tests {
             json: false,
             stream: false,
             watch: false,
+            watch_poll: None,
             no_hooks: false,
             abort_on_hook_failure: false,
             profile: None,
@@ -1384,11 +1797,20 @@ mod tests {
             max_delete: 50,
             clear_bisync_state: false,
             force_resync: false,
+            detect_moves: false,
+            fuzzy: false,
+            write_batch: None,
+            read_batch: None,
+            tombstone_retention: 30,
+            bisync_checksum: false,
             use_cache: false,
             clear_cache: false,
             checksum_db: false,
             clear_checksum_db: false,
             prune_checksum_db: false,
+            delta_algo: DeltaAlgo::Block,
+            block_size: BlockSize::Auto,
+            delta_stats: false,
             min_size: None,
             max_size: None,
             retry: 3,
@@ -1397,10 +1819,18 @@ mod tests {
             clear_resume_state: false,
             recursive: false,
             server: false,
+            rsh: None,
             daemon: false,
             socket: "~/.sy/daemon.sock".to_string(),
+            daemon_config: None,
+            safe_links: false,
             use_daemon: None,
             daemon_auto: false,
+            listen: None,
+            auth_tokens: None,
+            tls_cert: None,
+            tls_key: None,
+            tls_ca: None,
         };
         assert_eq!(cli.log_level(), tracing::Level::DEBUG);
     }
@@ -1466,6 +1896,7 @@ mod tests {
             json: false,
             stream: false,
             watch: false,
+            watch_poll: None,
             no_hooks: false,
             abort_on_hook_failure: false,
             profile: None,
@@ -1476,11 +1907,20 @@ mod tests {
             max_delete: 50,
             clear_bisync_state: false,
             force_resync: false,
+            detect_moves: false,
+            fuzzy: false,
+            write_batch: None,
+            read_batch: None,
+            tombstone_retention: 30,
+            bisync_checksum: false,
             use_cache: false,
             clear_cache: false,
             checksum_db: false,
             clear_checksum_db: false,
             prune_checksum_db: false,
+            delta_algo: DeltaAlgo::Block,
+            block_size: BlockSize::Auto,
+            delta_stats: false,
             min_size: None,
             max_size: None,
             retry: 3,
@@ -1489,10 +1929,18 @@ mod tests {
             clear_resume_state: false,
             recursive: false,
             server: false,
+            rsh: None,
             daemon: false,
             socket: "~/.sy/daemon.sock".to_string(),
+            daemon_config: None,
+            safe_links: false,
             use_daemon: None,
             daemon_auto: false,
+            listen: None,
+            auth_tokens: None,
+            tls_cert: None,
+            tls_key: None,
+            tls_ca: None,
         };
         assert_eq!(cli.log_level(), tracing::Level::TRACE);
     }
@@ -1577,6 +2025,7 @@ mod tests {
             json: false,
             stream: false,
             watch: false,
+            watch_poll: None,
             no_hooks: false,
             abort_on_hook_failure: false,
             profile: None,
@@ -1587,11 +2036,20 @@ mod tests {
             max_delete: 50,
             clear_bisync_state: false,
             force_resync: false,
+            detect_moves: false,
+            fuzzy: false,
+            write_batch: None,
+            read_batch: None,
+            tombstone_retention: 30,
+            bisync_checksum: false,
             use_cache: false,
             clear_cache: false,
             checksum_db: false,
             clear_checksum_db: false,
             prune_checksum_db: false,
+            delta_algo: DeltaAlgo::Block,
+            block_size: BlockSize::Auto,
+            delta_stats: false,
             min_size: Some(1024 * 1024), // 1MB
             max_size: Some(500 * 1024),  // 500KB (smaller than min)
             retry: 3,
@@ -1600,10 +2058,18 @@ mod tests {
             clear_resume_state: false,
             recursive: false,
             server: false,
+            rsh: None,
             daemon: false,
             socket: "~/.sy/daemon.sock".to_string(),
+            daemon_config: None,
+            safe_links: false,
             use_daemon: None,
             daemon_auto: false,
+            listen: None,
+            auth_tokens: None,
+            tls_cert: None,
+            tls_key: None,
+            tls_ca: None,
         };
 
         let result = cli.validate();
@@ -1672,6 +2138,7 @@ mod tests {
             json: false,
             stream: false,
             watch: false,
+            watch_poll: None,
             no_hooks: false,
             abort_on_hook_failure: false,
             profile: None,
@@ -1682,11 +2149,20 @@ mod tests {
             max_delete: 50,
             clear_bisync_state: false,
             force_resync: false,
+            detect_moves: false,
+            fuzzy: false,
+            write_batch: None,
+            read_batch: None,
+            tombstone_retention: 30,
+            bisync_checksum: false,
             use_cache: false,
             clear_cache: false,
             checksum_db: false,
             clear_checksum_db: false,
             prune_checksum_db: false,
+            delta_algo: DeltaAlgo::Block,
+            block_size: BlockSize::Auto,
+            delta_stats: false,
             min_size: None,
             max_size: None,
             retry: 3,
@@ -1695,10 +2171,18 @@ mod tests {
             clear_resume_state: false,
             recursive: false,
             server: false,
+            rsh: None,
             daemon: false,
             socket: "~/.sy/daemon.sock".to_string(),
+            daemon_config: None,
+            safe_links: false,
             use_daemon: None,
             daemon_auto: false,
+            listen: None,
+            auth_tokens: None,
+            tls_cert: None,
+            tls_key: None,
+            tls_ca: None,
         };
         assert_eq!(cli.verification_mode(), VerificationMode::None);
     }
@@ -1764,6 +2248,7 @@ mod tests {
             json: false,
             stream: false,
             watch: false,
+            watch_poll: None,
             no_hooks: false,
             abort_on_hook_failure: false,
             profile: None,
@@ -1774,11 +2259,20 @@ mod tests {
             max_delete: 50,
             clear_bisync_state: false,
             force_resync: false,
+            detect_moves: false,
+            fuzzy: false,
+            write_batch: None,
+            read_batch: None,
+            tombstone_retention: 30,
+            bisync_checksum: false,
             use_cache: false,
             clear_cache: false,
             checksum_db: false,
             clear_checksum_db: false,
             prune_checksum_db: false,
+            delta_algo: DeltaAlgo::Block,
+            block_size: BlockSize::Auto,
+            delta_stats: false,
             min_size: None,
             max_size: None,
             retry: 3,
@@ -1787,10 +2281,18 @@ mod tests {
             clear_resume_state: false,
             recursive: false,
             server: false,
+            rsh: None,
             daemon: false,
             socket: "~/.sy/daemon.sock".to_string(),
+            daemon_config: None,
+            safe_links: false,
             use_daemon: None,
             daemon_auto: false,
+            listen: None,
+            auth_tokens: None,
+            tls_cert: None,
+            tls_key: None,
+            tls_ca: None,
         };
         // verify flag should override mode to Verify
         assert_eq!(cli.verification_mode(), VerificationMode::Verify);
@@ -1869,6 +2371,7 @@ mod tests {
             json: false,
             stream: false,
             watch: false,
+            watch_poll: None,
             no_hooks: false,
             abort_on_hook_failure: false,
             profile: None,
@@ -1879,11 +2382,20 @@ mod tests {
             max_delete: 50,
             clear_bisync_state: false,
             force_resync: false,
+            detect_moves: false,
+            fuzzy: false,
+            write_batch: None,
+            read_batch: None,
+            tombstone_retention: 30,
+            bisync_checksum: false,
             use_cache: false,
             clear_cache: false,
             checksum_db: false,
             clear_checksum_db: false,
             prune_checksum_db: false,
+            delta_algo: DeltaAlgo::Block,
+            block_size: BlockSize::Auto,
+            delta_stats: false,
             min_size: None,
             max_size: None,
             retry: 3,
@@ -1892,10 +2404,18 @@ mod tests {
             clear_resume_state: false,
             recursive: false,
             server: false,
+            rsh: None,
             daemon: false,
             socket: "~/.sy/daemon.sock".to_string(),
+            daemon_config: None,
+            safe_links: false,
             use_daemon: None,
             daemon_auto: false,
+            listen: None,
+            auth_tokens: None,
+            tls_cert: None,
+            tls_key: None,
+            tls_ca: None,
         };
         assert_eq!(cli.symlink_mode(), SymlinkMode::Preserve);
     }
@@ -1961,6 +2481,7 @@ mod tests {
             json: false,
             stream: false,
             watch: false,
+            watch_poll: None,
             no_hooks: false,
             abort_on_hook_failure: false,
             profile: None,
@@ -1971,11 +2492,20 @@ mod tests {
             max_delete: 50,
             clear_bisync_state: false,
             force_resync: false,
+            detect_moves: false,
+            fuzzy: false,
+            write_batch: None,
+            read_batch: None,
+            tombstone_retention: 30,
+            bisync_checksum: false,
             use_cache: false,
             clear_cache: false,
             checksum_db: false,
             clear_checksum_db: false,
             prune_checksum_db: false,
+            delta_algo: DeltaAlgo::Block,
+            block_size: BlockSize::Auto,
+            delta_stats: false,
             min_size: None,
             max_size: None,
             retry: 3,
@@ -1984,10 +2514,18 @@ mod tests {
             clear_resume_state: false,
             recursive: false,
             server: false,
+            rsh: None,
             daemon: false,
             socket: "~/.sy/daemon.sock".to_string(),
+            daemon_config: None,
+            safe_links: false,
             use_daemon: None,
             daemon_auto: false,
+            listen: None,
+            auth_tokens: None,
+            tls_cert: None,
+            tls_key: None,
+            tls_ca: None,
         };
         assert_eq!(cli.symlink_mode(), SymlinkMode::Follow);
     }
@@ -2053,6 +2591,7 @@ mod tests {
             json: false,
             stream: false,
             watch: false,
+            watch_poll: None,
             no_hooks: false,
             abort_on_hook_failure: false,
             profile: None,
@@ -2063,11 +2602,20 @@ mod tests {
             max_delete: 50,
             clear_bisync_state: false,
             force_resync: false,
+            detect_moves: false,
+            fuzzy: false,
+            write_batch: None,
+            read_batch: None,
+            tombstone_retention: 30,
+            bisync_checksum: false,
             use_cache: false,
             clear_cache: false,
             checksum_db: false,
             clear_checksum_db: false,
             prune_checksum_db: false,
+            delta_algo: DeltaAlgo::Block,
+            block_size: BlockSize::Auto,
+            delta_stats: false,
             min_size: None,
             max_size: None,
             retry: 3,
@@ -2076,10 +2624,18 @@ mod tests {
             clear_resume_state: false,
             recursive: false,
             server: false,
+            rsh: None,
             daemon: false,
             socket: "~/.sy/daemon.sock".to_string(),
+            daemon_config: None,
+            safe_links: false,
             use_daemon: None,
             daemon_auto: false,
+            listen: None,
+            auth_tokens: None,
+            tls_cert: None,
+            tls_key: None,
+            tls_ca: None,
         };
         assert_eq!(cli.symlink_mode(), SymlinkMode::Skip);
     }
@@ -2145,6 +2701,7 @@ mod tests {
             json: false,
             stream: false,
             watch: false,
+            watch_poll: None,
             no_hooks: false,
             abort_on_hook_failure: false,
             profile: None,
@@ -2155,11 +2712,20 @@ mod tests {
             max_delete: 50,
             clear_bisync_state: false,
             force_resync: false,
+            detect_moves: false,
+            fuzzy: false,
+            write_batch: None,
+            read_batch: None,
+            tombstone_retention: 30,
+            bisync_checksum: false,
             use_cache: false,
             clear_cache: false,
             checksum_db: false,
             clear_checksum_db: false,
             prune_checksum_db: false,
+            delta_algo: DeltaAlgo::Block,
+            block_size: BlockSize::Auto,
+            delta_stats: false,
             min_size: None,
             max_size: None,
             retry: 3,
@@ -2168,10 +2734,18 @@ mod tests {
             clear_resume_state: false,
             recursive: false,
             server: false,
+            rsh: None,
             daemon: false,
             socket: "~/.sy/daemon.sock".to_string(),
+            daemon_config: None,
+            safe_links: false,
             use_daemon: None,
             daemon_auto: false,
+            listen: None,
+            auth_tokens: None,
+            tls_cert: None,
+            tls_key: None,
+            tls_ca: None,
         };
 
         // Archive mode should enable all these flags
@@ -2244,6 +2818,7 @@ mod tests {
             json: false,
             stream: false,
             watch: false,
+            watch_poll: None,
             no_hooks: false,
             abort_on_hook_failure: false,
             profile: None,
@@ -2254,11 +2829,20 @@ mod tests {
             max_delete: 50,
             clear_bisync_state: false,
             force_resync: false,
+            detect_moves: false,
+            fuzzy: false,
+            write_batch: None,
+            read_batch: None,
+            tombstone_retention: 30,
+            bisync_checksum: false,
             use_cache: false,
             clear_cache: false,
             checksum_db: false,
             clear_checksum_db: false,
             prune_checksum_db: false,
+            delta_algo: DeltaAlgo::Block,
+            block_size: BlockSize::Auto,
+            delta_stats: false,
             min_size: None,
             max_size: None,
             retry: 3,
@@ -2267,10 +2851,18 @@ mod tests {
             clear_resume_state: false,
             recursive: false,
             server: false,
+            rsh: None,
             daemon: false,
             socket: "~/.sy/daemon.sock".to_string(),
+            daemon_config: None,
+            safe_links: false,
             use_daemon: None,
             daemon_auto: false,
+            listen: None,
+            auth_tokens: None,
+            tls_cert: None,
+            tls_key: None,
+            tls_ca: None,
         };
 
         // Only permissions should be enabled
@@ -2342,6 +2934,7 @@ mod tests {
             json: false,
             stream: false,
             watch: false,
+            watch_poll: None,
             no_hooks: false,
             abort_on_hook_failure: false,
             profile: None,
@@ -2352,11 +2945,20 @@ mod tests {
             max_delete: 50,
             clear_bisync_state: false,
             force_resync: false,
+            detect_moves: false,
+            fuzzy: false,
+            write_batch: None,
+            read_batch: None,
+            tombstone_retention: 30,
+            bisync_checksum: false,
             use_cache: false,
             clear_cache: false,
             checksum_db: false,
             clear_checksum_db: false,
             prune_checksum_db: false,
+            delta_algo: DeltaAlgo::Block,
+            block_size: BlockSize::Auto,
+            delta_stats: false,
             min_size: None,
             max_size: None,
             retry: 3,
@@ -2365,10 +2967,18 @@ mod tests {
             clear_resume_state: false,
             recursive: false,
             server: false,
+            rsh: None,
             daemon: false,
             socket: "~/.sy/daemon.sock".to_string(),
+            daemon_config: None,
+            safe_links: false,
             use_daemon: None,
             daemon_auto: false,
+            listen: None,
+            auth_tokens: None,
+            tls_cert: None,
+            tls_key: None,
+            tls_ca: None,
         };
 
         // All should be enabled (archive mode OR individual flags)
@@ -2441,6 +3051,7 @@ mod tests {
             json: false,
             stream: false,
             watch: false,
+            watch_poll: None,
             no_hooks: false,
             abort_on_hook_failure: false,
             profile: None,
@@ -2451,11 +3062,20 @@ mod tests {
             max_delete: 50,
             clear_bisync_state: false,
             force_resync: false,
+            detect_moves: false,
+            fuzzy: false,
+            write_batch: None,
+            read_batch: None,
+            tombstone_retention: 30,
+            bisync_checksum: false,
             use_cache: false,
             clear_cache: false,
             checksum_db: false,
             clear_checksum_db: false,
             prune_checksum_db: false,
+            delta_algo: DeltaAlgo::Block,
+            block_size: BlockSize::Auto,
+            delta_stats: false,
             min_size: None,
             max_size: None,
             retry: 3,
@@ -2464,10 +3084,18 @@ mod tests {
             clear_resume_state: false,
             recursive: false,
             server: false,
+            rsh: None,
             daemon: false,
             socket: "~/.sy/daemon.sock".to_string(),
+            daemon_config: None,
+            safe_links: false,
             use_daemon: None,
             daemon_auto: false,
+            listen: None,
+            auth_tokens: None,
+            tls_cert: None,
+            tls_key: None,
+            tls_ca: None,
         };
 
         let result = cli.validate();
@@ -2540,6 +3168,7 @@ mod tests {
             json: false,
             stream: false,
             watch: false,
+            watch_poll: None,
             no_hooks: false,
             abort_on_hook_failure: false,
             profile: None,
@@ -2550,11 +3179,20 @@ mod tests {
             max_delete: 50,
             clear_bisync_state: false,
             force_resync: false,
+            detect_moves: false,
+            fuzzy: false,
+            write_batch: None,
+            read_batch: None,
+            tombstone_retention: 30,
+            bisync_checksum: false,
             use_cache: false,
             clear_cache: false,
             checksum_db: false,
             clear_checksum_db: false,
             prune_checksum_db: false,
+            delta_algo: DeltaAlgo::Block,
+            block_size: BlockSize::Auto,
+            delta_stats: false,
             min_size: None,
             max_size: None,
             retry: 3,
@@ -2563,10 +3201,18 @@ mod tests {
             clear_resume_state: false,
             recursive: false,
             server: false,
+            rsh: None,
             daemon: false,
             socket: "~/.sy/daemon.sock".to_string(),
+            daemon_config: None,
+            safe_links: false,
             use_daemon: None,
             daemon_auto: false,
+            listen: None,
+            auth_tokens: None,
+            tls_cert: None,
+            tls_key: None,
+            tls_ca: None,
         };
 
         // Should be valid - only one comparison flag
@@ -2636,6 +3282,7 @@ mod tests {
             json: false,
             stream: false,
             watch: false,
+            watch_poll: None,
             no_hooks: false,
             abort_on_hook_failure: false,
             profile: None,
@@ -2646,11 +3293,20 @@ mod tests {
             max_delete: 50,
             clear_bisync_state: false,
             force_resync: false,
+            detect_moves: false,
+            fuzzy: false,
+            write_batch: None,
+            read_batch: None,
+            tombstone_retention: 30,
+            bisync_checksum: false,
             use_cache: false,
             clear_cache: false,
             checksum_db: false,
             clear_checksum_db: false,
             prune_checksum_db: false,
+            delta_algo: DeltaAlgo::Block,
+            block_size: BlockSize::Auto,
+            delta_stats: false,
             min_size: None,
             max_size: None,
             retry: 3,
@@ -2659,10 +3315,18 @@ mod tests {
             clear_resume_state: false,
             recursive: false,
             server: false,
+            rsh: None,
             daemon: false,
             socket: "~/.sy/daemon.sock".to_string(),
+            daemon_config: None,
+            safe_links: false,
             use_daemon: None,
             daemon_auto: false,
+            listen: None,
+            auth_tokens: None,
+            tls_cert: None,
+            tls_key: None,
+            tls_ca: None,
         };
 
         // Should be valid - only one comparison flag
@@ -2775,6 +3439,7 @@ mod tests {
             json: false,
             stream: false,
             watch: false,
+            watch_poll: None,
             no_hooks: false,
             abort_on_hook_failure: false,
             profile: None,
@@ -2785,11 +3450,20 @@ mod tests {
             max_delete: 50,
             clear_bisync_state: false,
             force_resync: false,
+            detect_moves: false,
+            fuzzy: false,
+            write_batch: None,
+            read_batch: None,
+            tombstone_retention: 30,
+            bisync_checksum: false,
             use_cache: false,
             clear_cache: false,
             checksum_db: false,
             clear_checksum_db: false,
             prune_checksum_db: false,
+            delta_algo: DeltaAlgo::Block,
+            block_size: BlockSize::Auto,
+            delta_stats: false,
             min_size: None,
             max_size: None,
             retry: 3,
@@ -2798,10 +3472,18 @@ mod tests {
             clear_resume_state: false,
             recursive: false,
             server: false,
+            rsh: None,
             daemon: false,
             socket: "~/.sy/daemon.sock".to_string(),
+            daemon_config: None,
+            safe_links: false,
             use_daemon: None,
             daemon_auto: false,
+            listen: None,
+            auth_tokens: None,
+            tls_cert: None,
+            tls_key: None,
+            tls_ca: None,
         }
     }
 }
@@ -0,0 +1,316 @@
+//! Block size policy for delta sync (--block-size)
+//!
+//! Every rolling-checksum delta (local basis files, SSH, server and daemon
+//! push, batch files) picks its block size here. `auto` scales with the
+//! file size; a fixed size suits data with a known page size (databases,
+//! VM images); `adaptive` starts from `auto` and adjusts per file using how
+//! much of that file changed in earlier syncs.
+//!
+//! Smaller blocks cost more checksums but keep the literal data around a
+//! small edit small; larger blocks are cheaper when most of a file changes
+//! anyway.
+
+use super::calculate_block_size;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Smallest block size accepted
+pub const MIN_BLOCK_SIZE: usize = 512;
+
+/// Largest block size accepted
+pub const MAX_BLOCK_SIZE: usize = 1024 * 1024;
+
+/// Files remembered in the change history (oldest are dropped first)
+const MAX_HISTORY_ENTRIES: usize = 10_000;
+
+/// How delta sync sizes its blocks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BlockSize {
+    /// Square root of the file size (rsync's heuristic)
+    #[default]
+    Auto,
+    /// The same size for every file
+    Fixed(usize),
+    /// `Auto`, scaled by the file's change history
+    Adaptive,
+}
+
+impl BlockSize {
+    /// Block size for a file, given the fraction of it that changed last time
+    pub fn for_file(self, file_size: u64, change_ratio: Option<f64>) -> usize {
+        match self {
+            BlockSize::Auto => calculate_block_size(file_size),
+            BlockSize::Fixed(size) => size,
+            BlockSize::Adaptive => adaptive_block_size(file_size, change_ratio),
+        }
+    }
+}
+
+impl FromStr for BlockSize {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "auto" => Ok(BlockSize::Auto),
+            "adaptive" => Ok(BlockSize::Adaptive),
+            _ => {
+                let size = crate::cli::parse_size(s)
+                    .map_err(|_| format!("expected auto, adaptive or a size, got '{}'", s))?;
+                if !(MIN_BLOCK_SIZE as u64..=MAX_BLOCK_SIZE as u64).contains(&size) {
+                    return Err(format!(
+                        "block size must be between {} bytes and {} KB",
+                        MIN_BLOCK_SIZE,
+                        MAX_BLOCK_SIZE / 1024
+                    ));
+                }
+                Ok(BlockSize::Fixed(size as usize))
+            }
+        }
+    }
+}
+
+/// Scale the `auto` size by how much of the file changed before
+///
+/// Files that change in a few places get finer blocks; files that mostly
+/// change get coarser ones. Without history this is the `auto` size.
+pub fn adaptive_block_size(file_size: u64, change_ratio: Option<f64>) -> usize {
+    let base = calculate_block_size(file_size);
+    let size = match change_ratio {
+        Some(ratio) if ratio < 0.05 => base / 4,
+        Some(ratio) if ratio < 0.25 => base / 2,
+        Some(ratio) if ratio > 0.5 => base * 2,
+        _ => base,
+    };
+    size.clamp(MIN_BLOCK_SIZE, 128 * 1024)
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct HistoryEntry {
+    /// Smoothed fraction of the file that changed per sync (0.0 - 1.0)
+    ratio: f64,
+    /// Unix time of the last update
+    updated: u64,
+}
+
+/// How much each source file changed in earlier syncs
+///
+/// Kept in `~/.cache/sy/change-history.json` and written back when dropped.
+/// Ratios come from `estimate_change_ratio` sampling on local copies and
+/// from the literal share of each delta elsewhere.
+#[derive(Debug)]
+pub struct ChangeHistory {
+    path: Option<PathBuf>,
+    entries: Mutex<HashMap<String, HistoryEntry>>,
+    dirty: Mutex<bool>,
+}
+
+impl ChangeHistory {
+    /// Load the history from the user's cache directory
+    pub fn load() -> Self {
+        let path = dirs::cache_dir().map(|dir| dir.join("sy").join("change-history.json"));
+        match path {
+            Some(path) => Self::load_from(path),
+            None => Self::in_memory(),
+        }
+    }
+
+    /// Load the history from `path` (missing or unreadable files start empty)
+    pub fn load_from(path: PathBuf) -> Self {
+        let entries = std::fs::read(&path)
+            .ok()
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .unwrap_or_default();
+        Self {
+            path: Some(path),
+            entries: Mutex::new(entries),
+            dirty: Mutex::new(false),
+        }
+    }
+
+    /// A history that isn't saved
+    pub fn in_memory() -> Self {
+        Self {
+            path: None,
+            entries: Mutex::new(HashMap::new()),
+            dirty: Mutex::new(false),
+        }
+    }
+
+    /// Smoothed change ratio of a file, if it was synced before
+    pub fn ratio(&self, file: &Path) -> Option<f64> {
+        let entries = self.entries.lock().unwrap();
+        entries.get(&history_key(file)).map(|e| e.ratio)
+    }
+
+    /// Record how much of a file changed in this sync
+    pub fn record(&self, file: &Path, ratio: f64) {
+        let ratio = ratio.clamp(0.0, 1.0);
+        let updated = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries
+            .entry(history_key(file))
+            .or_insert(HistoryEntry { ratio, updated });
+        // Weight the latest sync and the past equally
+        entry.ratio = (entry.ratio + ratio) / 2.0;
+        entry.updated = updated;
+        *self.dirty.lock().unwrap() = true;
+    }
+
+    /// Write the history back, keeping the most recently updated files
+    pub fn save(&self) -> std::io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() > MAX_HISTORY_ENTRIES {
+            let mut by_age: Vec<u64> = entries.values().map(|e| e.updated).collect();
+            by_age.sort_unstable_by(|a, b| b.cmp(a));
+            let cutoff = by_age[MAX_HISTORY_ENTRIES - 1];
+            entries.retain(|_, e| e.updated >= cutoff);
+        }
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let data = serde_json::to_vec(&*entries)?;
+        let temp = path.with_extension("json.tmp");
+        std::fs::write(&temp, data)?;
+        std::fs::rename(&temp, path)?;
+        *self.dirty.lock().unwrap() = false;
+        Ok(())
+    }
+}
+
+impl Drop for ChangeHistory {
+    fn drop(&mut self) {
+        if *self.dirty.lock().unwrap() {
+            if let Err(e) = self.save() {
+                tracing::debug!("Failed to save change history: {}", e);
+            }
+        }
+    }
+}
+
+/// Files are remembered by absolute path
+fn history_key(file: &Path) -> String {
+    std::path::absolute(file)
+        .unwrap_or_else(|_| file.to_path_buf())
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// A block size policy plus the history `Adaptive` consults
+#[derive(Debug, Clone, Default)]
+pub struct BlockSizePolicy {
+    mode: BlockSize,
+    history: Option<Arc<ChangeHistory>>,
+}
+
+impl BlockSizePolicy {
+    /// Policy for `mode`; `Adaptive` loads the saved change history
+    pub fn new(mode: BlockSize) -> Self {
+        let history = (mode == BlockSize::Adaptive).then(|| Arc::new(ChangeHistory::load()));
+        Self { mode, history }
+    }
+
+    /// Policy with an explicit history (tests, embedders)
+    #[allow(dead_code)] // Public API
+    pub fn with_history(mode: BlockSize, history: Arc<ChangeHistory>) -> Self {
+        Self {
+            mode,
+            history: Some(history),
+        }
+    }
+
+    pub fn mode(&self) -> BlockSize {
+        self.mode
+    }
+
+    /// Block size for delta-encoding `source` (a file of `file_size` bytes)
+    pub fn block_size(&self, source: &Path, file_size: u64) -> usize {
+        let ratio = self.history.as_ref().and_then(|h| h.ratio(source));
+        self.mode.for_file(file_size, ratio)
+    }
+
+    /// Record the fraction of `source` that changed
+    pub fn record(&self, source: &Path, change_ratio: f64) {
+        if let Some(history) = &self.history {
+            history.record(source, change_ratio);
+        }
+    }
+
+    /// Record a finished delta: literal bytes out of the whole file
+    pub fn record_delta(&self, source: &Path, literal_bytes: u64, file_size: u64) {
+        if file_size > 0 {
+            self.record(source, literal_bytes as f64 / file_size as f64);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_block_size() {
+        assert_eq!("auto".parse::<BlockSize>(), Ok(BlockSize::Auto));
+        assert_eq!("Adaptive".parse::<BlockSize>(), Ok(BlockSize::Adaptive));
+        assert_eq!("8K".parse::<BlockSize>(), Ok(BlockSize::Fixed(8192)));
+        assert_eq!("4096".parse::<BlockSize>(), Ok(BlockSize::Fixed(4096)));
+        assert!("100".parse::<BlockSize>().is_err());
+        assert!("2MB".parse::<BlockSize>().is_err());
+        assert!("huge".parse::<BlockSize>().is_err());
+    }
+
+    #[test]
+    fn test_adaptive_follows_history() {
+        let size = 100_000_000; // auto: 10000
+        assert_eq!(adaptive_block_size(size, None), 10_000);
+        assert_eq!(adaptive_block_size(size, Some(0.01)), 2_500);
+        assert_eq!(adaptive_block_size(size, Some(0.1)), 5_000);
+        assert_eq!(adaptive_block_size(size, Some(0.3)), 10_000);
+        assert_eq!(adaptive_block_size(size, Some(0.9)), 20_000);
+        // Never below the minimum
+        assert_eq!(adaptive_block_size(1024, Some(0.0)), MIN_BLOCK_SIZE);
+    }
+
+    #[test]
+    fn test_history_persists_smoothed_ratios() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("history.json");
+        let file = dir.path().join("db.sqlite");
+
+        let history = ChangeHistory::load_from(path.clone());
+        assert_eq!(history.ratio(&file), None);
+        history.record(&file, 0.2);
+        history.record(&file, 0.0);
+        assert_eq!(history.ratio(&file), Some(0.1));
+        drop(history);
+
+        let reloaded = ChangeHistory::load_from(path);
+        assert_eq!(reloaded.ratio(&file), Some(0.1));
+    }
+
+    #[test]
+    fn test_policy_uses_history_only_when_adaptive() {
+        let history = Arc::new(ChangeHistory::in_memory());
+        let file = Path::new("/data/app.db");
+        history.record(file, 0.01);
+
+        let adaptive = BlockSizePolicy::with_history(BlockSize::Adaptive, history.clone());
+        let auto = BlockSizePolicy::with_history(BlockSize::Auto, history);
+        assert_eq!(adaptive.block_size(file, 100_000_000), 2_500);
+        assert_eq!(auto.block_size(file, 100_000_000), 10_000);
+        assert_eq!(
+            BlockSizePolicy::new(BlockSize::Fixed(4096)).block_size(file, 100_000_000),
+            4096
+        );
+    }
+}
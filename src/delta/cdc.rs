@@ -0,0 +1,336 @@
+//! Content-defined chunking (FastCDC) for delta sync
+//!
+//! Chunk boundaries are cut where a Gear rolling hash of the content hits a
+//! mask, so an insertion or deletion only disturbs the chunks around it and
+//! the rest of the file realigns. Both sides chunk their copy the same way;
+//! the sender copies every chunk the receiver already has and sends the rest
+//! as literals. There is no per-byte Adler32 probe as in the fixed-block
+//! delta, and a chunk list depends only on the file's content, so receivers
+//! can cache it (see `ChecksumDatabase::get_chunks`).
+//!
+//! Cut points follow FastCDC's normalized chunking: no cut before `min`, a
+//! harder mask up to `avg`, an easier one after it, and a forced cut at `max`.
+
+use super::{Delta, DeltaOp};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+/// Smallest and largest average chunk size (rounded to a power of two)
+pub const MIN_AVG_SIZE: u32 = 256;
+pub const MAX_AVG_SIZE: u32 = 1 << 20;
+
+/// Longest literal run sent as one Data op
+const LITERAL_FLUSH: usize = 1 << 20;
+
+/// Gear table: one pseudo-random u64 per byte value
+///
+/// SplitMix64 from a fixed seed; chunks only match across hosts that use
+/// the same table, so it must never change.
+const GEAR: [u64; 256] = gear_table();
+
+const fn gear_table() -> [u64; 256] {
+    let mut table = [0u64; 256];
+    let mut state: u64 = 0x5359_5F46_4153_5443;
+    let mut i = 0;
+    while i < 256 {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        table[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    table
+}
+
+/// Average chunk size for a file: small files get finer chunks, large
+/// archives coarser ones to keep chunk lists short
+pub fn avg_chunk_size(file_size: u64) -> u32 {
+    if file_size < 64 * 1024 * 1024 {
+        8 * 1024
+    } else if file_size < 1024 * 1024 * 1024 {
+        16 * 1024
+    } else {
+        64 * 1024
+    }
+}
+
+/// Chunk size bounds and cut masks derived from an average size
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkParams {
+    pub min: usize,
+    pub avg: usize,
+    pub max: usize,
+    mask_small: u64,
+    mask_large: u64,
+}
+
+impl ChunkParams {
+    /// Parameters for a target average size, clamped to
+    /// `MIN_AVG_SIZE..=MAX_AVG_SIZE` and rounded up to a power of two
+    pub fn new(avg: u32) -> Self {
+        let avg = avg.clamp(MIN_AVG_SIZE, MAX_AVG_SIZE).next_power_of_two();
+        let bits = avg.trailing_zeros();
+        Self {
+            min: avg as usize / 4,
+            avg: avg as usize,
+            max: avg as usize * 8,
+            mask_small: high_bits(bits + 2),
+            mask_large: high_bits(bits - 2),
+        }
+    }
+}
+
+/// A mask of the top `n` bits; the Gear hash mixes new bytes in from the bottom
+fn high_bits(n: u32) -> u64 {
+    !0u64 << (64 - n)
+}
+
+/// One content-defined chunk of a file
+///
+/// `strong` and `tag` are the low 64 and next 32 bits of the chunk's
+/// xxh3-128; in a CHECKSUM_RESP they travel as the block's `strong` and
+/// `weak` fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Chunk {
+    pub offset: u64,
+    pub size: u32,
+    pub strong: u64,
+    pub tag: u32,
+}
+
+impl Chunk {
+    fn of(offset: u64, data: &[u8]) -> Self {
+        let hash = xxhash_rust::xxh3::xxh3_128(data);
+        Self {
+            offset,
+            size: data.len() as u32,
+            strong: hash as u64,
+            tag: (hash >> 64) as u32,
+        }
+    }
+
+    fn key(&self) -> (u64, u32, u32) {
+        (self.strong, self.tag, self.size)
+    }
+}
+
+/// Length of the chunk starting at `data[0]`
+///
+/// `data` holds at least `max` bytes unless the file ends sooner.
+fn cut_point(data: &[u8], params: &ChunkParams) -> usize {
+    let len = data.len().min(params.max);
+    if len <= params.min {
+        return len;
+    }
+    let normal = params.avg.min(len);
+    let mut hash = 0u64;
+    let mut i = params.min;
+    while i < normal {
+        hash = (hash << 1).wrapping_add(GEAR[data[i] as usize]);
+        if hash & params.mask_small == 0 {
+            return i + 1;
+        }
+        i += 1;
+    }
+    while i < len {
+        hash = (hash << 1).wrapping_add(GEAR[data[i] as usize]);
+        if hash & params.mask_large == 0 {
+            return i + 1;
+        }
+        i += 1;
+    }
+    len
+}
+
+/// Walk a reader chunk by chunk, calling `f` with each chunk's offset and bytes
+fn for_each_chunk<R: Read>(
+    mut reader: R,
+    params: &ChunkParams,
+    mut f: impl FnMut(u64, &[u8]),
+) -> io::Result<()> {
+    let mut buf = vec![0u8; params.max * 4];
+    let (mut start, mut end) = (0, 0);
+    let mut eof = false;
+    let mut offset = 0u64;
+    loop {
+        // Keep a whole `max` ahead of the cut point until the file ends
+        if !eof && end - start < params.max {
+            buf.copy_within(start..end, 0);
+            end -= start;
+            start = 0;
+            while end < buf.len() {
+                match reader.read(&mut buf[end..]) {
+                    Ok(0) => {
+                        eof = true;
+                        break;
+                    }
+                    Ok(n) => end += n,
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+        if start == end {
+            return Ok(());
+        }
+        let len = cut_point(&buf[start..end], params);
+        f(offset, &buf[start..start + len]);
+        offset += len as u64;
+        start += len;
+    }
+}
+
+/// Split a file's contents into content-defined chunks
+pub fn chunk_file<R: Read>(reader: R, params: ChunkParams) -> io::Result<Vec<Chunk>> {
+    let mut chunks = Vec::new();
+    for_each_chunk(reader, &params, |offset, data| {
+        chunks.push(Chunk::of(offset, data))
+    })?;
+    Ok(chunks)
+}
+
+/// Generate delta operations against the receiver's chunk list
+///
+/// Source chunks the receiver already has (same hash and size, anywhere in
+/// its file) become Copy ops, with runs of adjacent ones merged; everything
+/// else is sent as literal data.
+pub fn generate_delta(
+    source_path: &Path,
+    dest_chunks: &[Chunk],
+    params: ChunkParams,
+) -> io::Result<Delta> {
+    let known: HashMap<_, u64> = dest_chunks.iter().map(|c| (c.key(), c.offset)).collect();
+
+    let source_file = File::open(source_path)?;
+    let source_size = source_file.metadata()?.len();
+
+    let mut ops = Vec::new();
+    let mut literal = Vec::new();
+    for_each_chunk(source_file, &params, |_, data| {
+        let Some(&at) = known.get(&Chunk::of(0, data).key()) else {
+            literal.extend_from_slice(data);
+            if literal.len() >= LITERAL_FLUSH {
+                ops.push(DeltaOp::Data(std::mem::take(&mut literal)));
+            }
+            return;
+        };
+        if !literal.is_empty() {
+            ops.push(DeltaOp::Data(std::mem::take(&mut literal)));
+        }
+        match ops.last_mut() {
+            Some(DeltaOp::Copy { offset, size })
+                if *offset + *size as u64 == at && *size + data.len() <= u32::MAX as usize =>
+            {
+                *size += data.len();
+            }
+            _ => ops.push(DeltaOp::Copy {
+                offset: at,
+                size: data.len(),
+            }),
+        }
+    })?;
+    if !literal.is_empty() {
+        ops.push(DeltaOp::Data(literal));
+    }
+
+    Ok(Delta {
+        ops,
+        source_size,
+        block_size: params.avg,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::delta::apply_delta;
+    use tempfile::TempDir;
+
+    /// Deterministic incompressible bytes
+    fn noise(len: usize, seed: u64) -> Vec<u8> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_chunk_sizes_stay_in_bounds() {
+        let params = ChunkParams::new(4096);
+        assert_eq!((params.min, params.avg, params.max), (1024, 4096, 32768));
+
+        let data = noise(1 << 20, 1);
+        let chunks = chunk_file(&data[..], params).unwrap();
+        let (last, rest) = chunks.split_last().unwrap();
+        assert!(rest
+            .iter()
+            .all(|c| (params.min..=params.max).contains(&(c.size as usize))));
+        assert_eq!(last.offset + last.size as u64, data.len() as u64);
+
+        // Roughly the requested average
+        let avg = data.len() / chunks.len();
+        assert!((2048..=8192).contains(&avg), "average chunk size {}", avg);
+
+        assert!(chunk_file(&[][..], params).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_insertion_only_disturbs_nearby_chunks() {
+        let params = ChunkParams::new(4096);
+        let old = noise(512 * 1024, 2);
+        let mut new = old.clone();
+        new.splice(100_000..100_000, noise(777, 3));
+
+        let before = chunk_file(&old[..], params).unwrap();
+        let after = chunk_file(&new[..], params).unwrap();
+        let known: std::collections::HashSet<_> = before.iter().map(Chunk::key).collect();
+        let changed = after.iter().filter(|c| !known.contains(&c.key())).count();
+        assert!(
+            changed <= 3,
+            "{} of {} chunks changed",
+            changed,
+            after.len()
+        );
+    }
+
+    #[test]
+    fn test_delta_reconstructs_shifted_file() {
+        let dir = TempDir::new().unwrap();
+        let old_path = dir.path().join("old");
+        let new_path = dir.path().join("new");
+        let out_path = dir.path().join("out");
+
+        let old = noise(300 * 1024, 4);
+        let mut new = noise(5000, 5);
+        new.extend_from_slice(&old[..150 * 1024]);
+        new.extend_from_slice(&old[160 * 1024..]);
+        std::fs::write(&old_path, &old).unwrap();
+        std::fs::write(&new_path, &new).unwrap();
+
+        let params = ChunkParams::new(2048);
+        let dest = chunk_file(File::open(&old_path).unwrap(), params).unwrap();
+        let delta = generate_delta(&new_path, &dest, params).unwrap();
+
+        let literal: usize = delta
+            .ops
+            .iter()
+            .map(|op| match op {
+                DeltaOp::Data(data) => data.len(),
+                DeltaOp::Copy { .. } => 0,
+            })
+            .sum();
+        assert!(literal < 32 * 1024, "{} literal bytes", literal);
+
+        apply_delta(&old_path, &delta, &out_path).unwrap();
+        assert_eq!(std::fs::read(&out_path).unwrap(), new);
+    }
+}
@@ -1,4 +1,6 @@
 pub mod applier;
+pub mod blocksize;
+pub mod cdc;
 pub mod checksum;
 pub mod generator;
 pub mod ratio;
@@ -7,6 +9,7 @@ pub mod rolling;
 // Delta sync functions for remote sync (not used for local sync which uses block comparison)
 #[allow(unused_imports)]
 pub use applier::apply_delta;
+pub use blocksize::{BlockSize, BlockSizePolicy};
 #[allow(unused_imports)]
 pub use checksum::{compute_checksums, BlockChecksum};
 #[allow(unused_imports)]
@@ -15,7 +18,18 @@ pub use generator::{generate_delta, generate_delta_streaming, Delta, DeltaOp};
 pub use ratio::{estimate_change_ratio, ChangeRatioResult};
 pub use rolling::Adler32;
 
-/// Default block size calculation: sqrt(filesize)
+/// How delta sync finds the parts of a file the receiver already has
+/// (--delta-algo)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum DeltaAlgo {
+    /// Fixed blocks matched with a rolling checksum (rsync's algorithm)
+    #[default]
+    Block,
+    /// Content-defined chunks (FastCDC); robust to shifted data
+    Cdc,
+}
+
+/// Default block size (--block-size=auto): sqrt(filesize)
 /// Capped between 512 bytes and 128KB
 pub fn calculate_block_size(file_size: u64) -> usize {
     let size = (file_size as f64).sqrt() as usize;
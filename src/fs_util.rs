@@ -40,10 +40,16 @@ use std::path::Path;
 /// ```
 #[cfg(target_os = "macos")]
 pub fn supports_cow_reflinks(path: &Path) -> bool {
+    // APFS type name is "apfs"
+    fs_type_name(path).as_deref() == Some("apfs")
+}
+
+/// Filesystem type name of the filesystem containing `path` (e.g. "apfs", "nfs")
+#[cfg(target_os = "macos")]
+fn fs_type_name(path: &Path) -> Option<String> {
     use std::ffi::CString;
     use std::os::unix::ffi::OsStrExt;
 
-    // On macOS, check if filesystem is APFS using statfs
     #[repr(C)]
     struct statfs {
         f_bsize: u32,
@@ -68,26 +74,18 @@ pub fn supports_cow_reflinks(path: &Path) -> bool {
         fn statfs(path: *const libc::c_char, buf: *mut statfs) -> libc::c_int;
     }
 
-    let path_bytes = path.as_os_str().as_bytes();
-    let path_c = match CString::new(path_bytes) {
-        Ok(p) => p,
-        Err(_) => return false,
-    };
+    let path_c = CString::new(path.as_os_str().as_bytes()).ok()?;
 
     unsafe {
         let mut stat: std::mem::MaybeUninit<statfs> = std::mem::MaybeUninit::uninit();
-        if statfs(path_c.as_ptr(), stat.as_mut_ptr()) == 0 {
-            let stat = stat.assume_init();
-            // APFS type name is "apfs"
-            let fs_type = std::str::from_utf8(&stat.f_fstypename)
-                .ok()
-                .and_then(|s| s.split('\0').next())
-                .unwrap_or("");
-
-            fs_type == "apfs"
-        } else {
-            false
+        if statfs(path_c.as_ptr(), stat.as_mut_ptr()) != 0 {
+            return None;
         }
+        let stat = stat.assume_init();
+        std::str::from_utf8(&stat.f_fstypename)
+            .ok()
+            .and_then(|s| s.split('\0').next())
+            .map(str::to_string)
     }
 }
 
@@ -123,6 +121,70 @@ pub fn supports_cow_reflinks(_path: &Path) -> bool {
     false
 }
 
+/// Check if a path lives on a network or FUSE filesystem
+///
+/// File system events are unreliable there: inotify/FSEvents only see
+/// changes made through the local kernel, not those made by other NFS or
+/// SMB clients. Watch mode polls such sources instead.
+///
+/// Detected filesystems:
+/// - Linux: NFS, SMB/CIFS, FUSE, Ceph, AFS, Coda, 9P (by `statfs` magic number)
+/// - macOS: nfs, smbfs, afpfs, webdav and FUSE mounts (by type name)
+/// - Other platforms: never (returns false)
+#[cfg(target_os = "linux")]
+#[allow(dead_code)] // Used by watch mode
+pub fn is_network_filesystem(path: &Path) -> bool {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let path_c = match CString::new(path.as_os_str().as_bytes()) {
+        Ok(p) => p,
+        Err(_) => return false,
+    };
+
+    unsafe {
+        let mut stat: std::mem::MaybeUninit<libc::statfs> = std::mem::MaybeUninit::uninit();
+        if libc::statfs(path_c.as_ptr(), stat.as_mut_ptr()) == 0 {
+            let stat = stat.assume_init();
+            // NFS=0x6969, SMB=0x517B, CIFS=0xFF534D42, SMB2=0xFE534D42,
+            // FUSE=0x65735546, CEPH=0x00C36400, AFS=0x5346414F,
+            // CODA=0x73757245, 9P=0x01021997
+            matches!(
+                stat.f_type as u32,
+                0x6969
+                    | 0x517B
+                    | 0xFF534D42
+                    | 0xFE534D42
+                    | 0x65735546
+                    | 0x00C36400
+                    | 0x5346414F
+                    | 0x73757245
+                    | 0x01021997
+            )
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+#[allow(dead_code)] // Used by watch mode
+pub fn is_network_filesystem(path: &Path) -> bool {
+    match fs_type_name(path) {
+        Some(name) => {
+            matches!(name.as_str(), "nfs" | "smbfs" | "afpfs" | "webdav" | "cifs")
+                || name.contains("fuse")
+        }
+        None => false,
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+#[allow(dead_code)] // Used by watch mode
+pub fn is_network_filesystem(_path: &Path) -> bool {
+    false
+}
+
 /// Check if two paths are on the same filesystem
 ///
 /// COW reflinks only work within the same filesystem.
@@ -479,6 +541,12 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_is_network_filesystem_nonexistent_path() {
+        // Unknown filesystems are treated as local (keep using events)
+        assert!(!is_network_filesystem(Path::new("/nonexistent/path")));
+    }
+
     #[test]
     #[cfg(unix)]
     fn test_same_filesystem_nonexistent_paths() {
@@ -1,4 +1,5 @@
 use crate::error::Result;
+use std::io::Read;
 use std::path::Path;
 
 mod blake3;
@@ -8,9 +9,10 @@ pub use self::blake3::Blake3Hasher;
 pub use self::xxhash3::XxHash3Hasher;
 
 /// Type of checksum to compute
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub enum ChecksumType {
     /// No checksum verification (trust TCP)
+    #[default]
     None,
 
     /// Fast non-cryptographic checksum (xxHash3)
@@ -127,6 +129,38 @@ impl IntegrityVerifier {
         }
     }
 
+    /// Compute checksum for a stream, reading it in 1MB chunks
+    pub fn compute_reader_checksum(&self, reader: &mut impl Read) -> Result<Checksum> {
+        let mut buffer = vec![0u8; 1024 * 1024];
+        match self.checksum_type {
+            ChecksumType::None => Ok(Checksum::None),
+            ChecksumType::Fast => {
+                let mut hasher = XxHash3Hasher::new_hasher();
+                loop {
+                    let n = reader.read(&mut buffer)?;
+                    if n == 0 {
+                        break;
+                    }
+                    hasher.update(&buffer[..n]);
+                }
+                Ok(Checksum::Fast(hasher.digest().to_le_bytes().to_vec()))
+            }
+            ChecksumType::Cryptographic => {
+                let mut hasher = Blake3Hasher::new_hasher();
+                loop {
+                    let n = reader.read(&mut buffer)?;
+                    if n == 0 {
+                        break;
+                    }
+                    hasher.update(&buffer[..n]);
+                }
+                Ok(Checksum::Cryptographic(
+                    hasher.finalize().as_bytes().to_vec(),
+                ))
+            }
+        }
+    }
+
     /// Verify that source and destination files match
     pub fn verify_transfer(&self, source: &Path, dest: &Path) -> Result<bool> {
         let source_sum = self.compute_file_checksum(source)?;
@@ -296,6 +330,25 @@ mod tests {
         assert!(!verifier.verify_transfer(&source_path, &dest_path).unwrap());
     }
 
+    #[test]
+    fn test_reader_checksum_matches_data_checksum() {
+        // Larger than one read chunk
+        let data: Vec<u8> = (0..3 * 1024 * 1024 + 17).map(|i| (i % 251) as u8).collect();
+        for checksum_type in [
+            ChecksumType::None,
+            ChecksumType::Fast,
+            ChecksumType::Cryptographic,
+        ] {
+            let verifier = IntegrityVerifier::new(checksum_type, false);
+            assert_eq!(
+                verifier
+                    .compute_reader_checksum(&mut data.as_slice())
+                    .unwrap(),
+                verifier.compute_data_checksum(&data).unwrap()
+            );
+        }
+    }
+
     #[test]
     fn test_verify_block_disabled() {
         let verifier = IntegrityVerifier::new(ChecksumType::Cryptographic, false);
@@ -31,10 +31,15 @@ use hooks::{HookContext, HookExecutor, HookType};
 use path::SyncPath;
 use std::path::PathBuf;
 #[cfg(feature = "watch")]
-use sync::watch::WatchMode;
+use sync::watch::{BisyncWatchMode, WatchMode};
 use sync::SyncEngine;
 use tracing_subscriber::{fmt, EnvFilter};
 use transport::router::TransportRouter;
+use transport::Transport as _;
+
+/// How often `--watch --bidirectional` re-scans when a side is remote
+#[cfg(feature = "watch")]
+const BISYNC_WATCH_POLL_SECS: u64 = 10;
 
 /// Compute effective destination path based on rsync trailing slash semantics
 ///
@@ -105,14 +110,45 @@ async fn main() -> Result<()> {
         return sy::server::run_server().await;
     }
 
-    // Daemon mode - persistent server listening on Unix socket
+    // Daemon mode - persistent server listening on Unix socket (or TCP with --listen)
     if cli.daemon {
+        cli.validate()?;
         let root_path = cli
             .source
             .as_ref()
             .map(|s| s.path().to_path_buf())
             .unwrap_or_else(|| std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")));
-        return sy::server::daemon::run_daemon(&cli.socket, &root_path).await;
+        let modules = match &cli.daemon_config {
+            Some(path) => Some(sy::server::modules::ModuleTable::load(path)?),
+            None => None,
+        };
+        if let Some(listen) = &cli.listen {
+            let auth_tokens = cli
+                .auth_tokens
+                .clone()
+                .ok_or_else(|| anyhow::anyhow!("--listen requires --auth-tokens"))?;
+            let config = sy::server::tcp::TcpListenConfig {
+                listen: listen.clone(),
+                auth_tokens,
+                tls_cert: cli.tls_cert.clone(),
+                tls_key: cli.tls_key.clone(),
+                handshake_timeout: sy::server::tcp::DEFAULT_HANDSHAKE_TIMEOUT,
+            };
+            return match modules {
+                Some(modules) => {
+                    sy::server::daemon::run_tcp_daemon_with_modules(&config, modules).await
+                }
+                None => {
+                    sy::server::daemon::run_tcp_daemon(&config, &root_path, cli.safe_links).await
+                }
+            };
+        }
+        return match modules {
+            Some(modules) => {
+                sy::server::daemon::run_daemon_with_modules(&cli.socket, modules).await
+            }
+            None => sy::server::daemon::run_daemon(&cli.socket, &root_path, cli.safe_links).await,
+        };
     }
 
     // Merge profile with CLI args if --profile is set
@@ -187,9 +223,27 @@ async fn main() -> Result<()> {
         .compact()
         .init();
 
+    // --read-batch FILE DEST: the only path given is the destination
+    if cli.read_batch.is_some() && cli.destination.is_none() {
+        cli.destination = cli.source.take();
+    }
+
     // Validate arguments
     cli.validate()?;
 
+    // Create hook executor (unless disabled)
+    let hook_executor = if cli.no_hooks {
+        None
+    } else {
+        HookExecutor::new()
+            .ok()
+            .map(|e| e.with_abort_on_failure(cli.abort_on_hook_failure))
+    };
+
+    if let (Some(batch), Some(destination)) = (&cli.read_batch, &cli.destination) {
+        return apply_batch(&cli, batch, destination.path(), hook_executor.as_ref());
+    }
+
     // After validation, source and destination must be present
     let source = cli
         .source
@@ -200,15 +254,6 @@ async fn main() -> Result<()> {
         .as_ref()
         .expect("destination required after validation");
 
-    // Create hook executor (unless disabled)
-    let hook_executor = if cli.no_hooks {
-        None
-    } else {
-        HookExecutor::new()
-            .ok()
-            .map(|e| e.with_abort_on_failure(cli.abort_on_hook_failure))
-    };
-
     // Clean state files if requested
     if cli.clean_state {
         use sync::resume::ResumeState;
@@ -245,8 +290,24 @@ async fn main() -> Result<()> {
     let verify_on_write = verification_mode.verify_blocks();
 
     // Handle daemon mode early - before creating transport router
-    // Daemon mode uses Unix socket forwarding for fast repeated syncs
-    if let Some(ref socket_path) = cli.use_daemon {
+    // Daemon mode uses Unix socket forwarding for fast repeated syncs.
+    // Pushing in watch mode keeps its own daemon session open (see below).
+    // sy://host/path reaches a daemon's TCP listener directly.
+    let daemon_watch = cli.watch && !cli.bidirectional && source.is_local();
+    let tcp_daemon = match (&source, &destination) {
+        (SyncPath::TcpDaemon { host, port, .. }, _)
+        | (_, SyncPath::TcpDaemon { host, port, .. }) => Some((host.clone(), *port)),
+        _ => None,
+    };
+    if cli.safe_links && source.is_local() && (cli.use_daemon.is_some() || tcp_daemon.is_some()) {
+        anyhow::bail!(
+            "--safe-links can't be passed to a running daemon; start it with --safe-links (or set safe_links in its module)"
+        );
+    }
+    if cli.delta_stats && (cli.use_daemon.is_some() || tcp_daemon.is_some() || cli.rsh.is_some()) {
+        anyhow::bail!("{}", DELTA_STATS_ENGINE_ONLY);
+    }
+    if cli.use_daemon.is_some() && !daemon_watch || tcp_daemon.is_some() {
         #[cfg(unix)]
         {
             if !cli.quiet && !cli.json {
@@ -256,18 +317,57 @@ async fn main() -> Result<()> {
             }
 
             // Determine direction based on SyncPath types
-            let stats = if source.is_local() {
+            let stats = match (tcp_daemon, &cli.use_daemon) {
+                (Some((host, port)), _) => {
+                    let endpoint = server::tcp::TcpEndpoint {
+                        host,
+                        port,
+                        tls_ca: cli.tls_ca.clone(),
+                        token: server::tcp::AuthToken::from_env()?,
+                    };
+                    if source.is_local() {
+                        sync::daemon_mode::sync_tcp_daemon_mode(
+                            source.path(),
+                            &endpoint,
+                            destination.path(),
+                            cli.meta_options(),
+                            cli.transfer_options(),
+                        )
+                        .await?
+                    } else {
+                        sync::daemon_mode::sync_pull_tcp_daemon_mode(
+                            &endpoint,
+                            source.path(),
+                            destination.path(),
+                            cli.meta_options(),
+                            cli.transfer_options(),
+                        )
+                        .await?
+                    }
+                }
                 // Local -> Daemon: push mode
-                sync::daemon_mode::sync_daemon_mode(source.path(), socket_path, destination.path())
+                (None, Some(socket_path)) if source.is_local() => {
+                    sync::daemon_mode::sync_daemon_mode(
+                        source.path(),
+                        socket_path,
+                        destination.path(),
+                        cli.meta_options(),
+                        cli.transfer_options(),
+                    )
                     .await?
-            } else {
+                }
                 // Daemon -> Local: pull mode
-                sync::daemon_mode::sync_pull_daemon_mode(
-                    socket_path,
-                    source.path(),
-                    destination.path(),
-                )
-                .await?
+                (None, Some(socket_path)) => {
+                    sync::daemon_mode::sync_pull_daemon_mode(
+                        socket_path,
+                        source.path(),
+                        destination.path(),
+                        cli.meta_options(),
+                        cli.transfer_options(),
+                    )
+                    .await?
+                }
+                (None, None) => unreachable!("daemon sync without a socket or sy:// path"),
             };
 
             // Print summary
@@ -298,6 +398,7 @@ async fn main() -> Result<()> {
                     "  Duration:          {}",
                     format_duration(stats.duration).cyan()
                 );
+                print_stream_stats(&stats);
             }
 
             return Ok(());
@@ -308,6 +409,127 @@ async fn main() -> Result<()> {
         }
     }
 
+    // Watch mode pushing to a server or daemon holds one session open for
+    // the whole watch instead of reconnecting on every pass. The session
+    // can't delete or filter; over SSH those flags fall back to the engine.
+    #[cfg(feature = "watch")]
+    let session_watch = cli.watch
+        && !cli.bidirectional
+        && source.is_local()
+        && (cli.use_daemon.is_some() || destination.is_remote());
+    #[cfg(feature = "watch")]
+    if session_watch && (cli.use_daemon.is_some() || cli.rsh.is_some()) {
+        if let Some(flag) = cli.engine_only_flag() {
+            anyhow::bail!(
+                "{} is not supported with --watch over a daemon or --rsh session",
+                flag
+            );
+        }
+    }
+    #[cfg(feature = "watch")]
+    if session_watch && cli.engine_only_flag().is_none() {
+        let target = match &cli.use_daemon {
+            #[cfg(unix)]
+            Some(socket_path) => sync::server_mode::SessionTarget::Daemon {
+                socket_path: socket_path.clone(),
+                remote_path: destination.path().to_path_buf(),
+            },
+            #[cfg(not(unix))]
+            Some(_) => anyhow::bail!("Daemon mode is only supported on Unix-like systems"),
+            None => match &cli.rsh {
+                Some(rsh) => sync::server_mode::SessionTarget::Rsh {
+                    dest: destination.clone(),
+                    rsh: transport::server::RemoteShell::parse(rsh)?,
+                },
+                None => sync::server_mode::SessionTarget::Server {
+                    dest: destination.clone(),
+                    ssh_config: None,
+                },
+            },
+        };
+
+        let watch_mode = WatchMode::<TransportRouter>::with_session(
+            sync::server_mode::PersistentSession::new(target, cli.dry_run)
+                .with_meta(cli.meta_options())
+                .with_transfer(cli.transfer_options()),
+            source.path().to_path_buf(),
+            destination.path().to_path_buf(),
+            std::time::Duration::from_millis(500), // 500ms debounce
+        )
+        .with_poll_interval(cli.watch_poll.map(std::time::Duration::from_secs));
+
+        watch_mode.watch().await?;
+        return Ok(()); // Watch mode handles its own output
+    }
+
+    // Handle --rsh: start `sy --server` through a user-supplied remote shell
+    // (docker exec, kubectl exec, ssh with custom args). This never opens an
+    // SSH session of its own, so it runs before the transport router.
+    if let Some(rsh) = &cli.rsh {
+        let rsh = transport::server::RemoteShell::parse(rsh)?;
+
+        if !cli.quiet && !cli.json {
+            println!("Mode: Server protocol via remote shell\n");
+        }
+
+        let stats = if destination.is_remote() {
+            sync::server_mode::sync_server_mode_with_rsh(
+                source.path(),
+                destination,
+                cli.dry_run,
+                cli.meta_options(),
+                cli.transfer_options(),
+                None,
+                &rsh,
+            )
+            .await?
+        } else {
+            sync::server_mode::sync_pull_server_mode_with_rsh(
+                source,
+                destination.path(),
+                cli.dry_run,
+                cli.meta_options(),
+                cli.transfer_options(),
+                None,
+                &rsh,
+            )
+            .await?
+        };
+
+        // Print summary
+        if !cli.quiet && !cli.json {
+            println!("\n{}\n", "✓ Sync complete".green().bold());
+            println!(
+                "  Files scanned:     {}",
+                stats.files_scanned.to_string().blue()
+            );
+            println!(
+                "  Files created:     {}",
+                stats.files_created.to_string().green()
+            );
+            println!(
+                "  Files updated:     {}",
+                stats.files_updated.to_string().yellow()
+            );
+            println!(
+                "  Files skipped:     {}",
+                stats.files_skipped.to_string().bright_black()
+            );
+            println!();
+            println!(
+                "  Bytes transferred: {}",
+                format_bytes(stats.bytes_transferred).cyan()
+            );
+            println!(
+                "  Duration:          {}",
+                format_duration(stats.duration).cyan()
+            );
+            print_stream_stats(&stats);
+        }
+
+        return Ok(());
+    }
+
     // Handle --daemon-auto: automatically set up daemon for SSH destinations
     if cli.daemon_auto && destination.is_remote() {
         #[cfg(unix)]
@@ -346,6 +568,8 @@ async fn main() -> Result<()> {
                 source.path(),
                 &daemon_result.socket_path,
                 destination.path(),
+                cli.meta_options(),
+                cli.transfer_options(),
             )
             .await?;
 
@@ -377,6 +601,7 @@ async fn main() -> Result<()> {
                     "  Duration:          {}",
                     format_duration(stats.duration).cyan()
                 );
+                print_stream_stats(&stats);
                 println!(
                     "\n  Tip: Connection persists for 10min. Subsequent syncs will be faster."
                 );
@@ -394,6 +619,9 @@ async fn main() -> Result<()> {
     let retry_config =
         retry::RetryConfig::new(cli.retry, std::time::Duration::from_secs(cli.retry_delay));
 
+    // Adaptive sizing shares one change history between transports and batches
+    let block_sizes = delta::BlockSizePolicy::new(cli.block_size);
+
     // Create transport router based on source and destination
     // Use worker count for SSH connection pool size to enable true parallel transfers
     let transport = TransportRouter::new(
@@ -405,7 +633,24 @@ async fn main() -> Result<()> {
         retry_config,
     )
     .await?
-    .with_scan_options(cli.scan_options());
+    .with_scan_options(cli.scan_options())
+    .with_block_size(block_sizes.clone());
+
+    // Hosts that refuse command execution can't run `sy --server`
+    let sftp_only = transport.is_sftp_only();
+
+    // Only sy's own receivers enforce --safe-links; the engine copies symlinks as-is
+    let server_protocol = !cli.bidirectional
+        && !cli.watch
+        && !sftp_only
+        && (source.is_local() && destination.is_remote()
+            || source.is_remote() && destination.is_local());
+    if cli.safe_links && !server_protocol {
+        anyhow::bail!("--safe-links needs a server-protocol transfer (SSH, --rsh or a daemon)");
+    }
+    if cli.delta_stats && server_protocol {
+        anyhow::bail!("{}", DELTA_STATS_ENGINE_ONLY);
+    }
 
     // Get symlink mode
     let symlink_mode = cli.symlink_mode();
@@ -585,7 +830,30 @@ Or install from local source with: cargo install --path . --features acl"#
         cli.prune_checksum_db,
         destination.is_remote(),
         cli.perf,
-    );
+    )
+    .with_move_detection(cli.detect_moves)
+    .with_fuzzy(cli.fuzzy)
+    .with_delta_stats(cli.delta_stats);
+
+    // Record the changes in a batch file, then apply that batch to the
+    // destination like --read-batch would, instead of planning them again
+    if let Some(ref batch) = cli.write_batch {
+        if !source.path().is_dir() {
+            anyhow::bail!("--write-batch requires a source directory");
+        }
+        let effective_dest = compute_destination_path(source, destination);
+        let tasks = engine.plan(source.path(), &effective_dest).await?;
+        let written =
+            sync::batch::write_batch(batch, source.path(), &effective_dest, &tasks, &block_sizes)?;
+        if !cli.quiet && !cli.json {
+            println!("Wrote batch {}\n", batch.display());
+            print_batch_stats(&written);
+        }
+        if cli.dry_run {
+            return Ok(());
+        }
+        return apply_batch(&cli, batch, &effective_dest, hook_executor.as_ref());
+    }
 
     // Execute pre-sync hook
     if let Some(ref executor) = hook_executor {
@@ -706,6 +974,55 @@ Or install from local source with: cargo install --path . --features acl"#
     if cli.watch {
         #[cfg(feature = "watch")]
         {
+            if cli.bidirectional {
+                let (source_transport, dest_transport) =
+                    bisync_transports(&cli, source, destination, checksum_type, verify_on_write)
+                        .await?;
+                let effective_dest = compute_destination_path(source, destination);
+                let session = bisync::BisyncEngine::new(source_transport, dest_transport)
+                    .open_session(source.path(), &effective_dest, bisync_options(&cli)?)?;
+
+                // Local sides get file watchers; remote sides, local sides on
+                // network filesystems, and everything under --watch-poll are polled
+                let mut local_roots = Vec::new();
+                if source.is_local() {
+                    local_roots.push(source.path().to_path_buf());
+                }
+                if destination.is_local() {
+                    std::fs::create_dir_all(&effective_dest)?;
+                    local_roots.push(effective_dest.clone());
+                }
+                let watched_roots: Vec<_> = local_roots
+                    .into_iter()
+                    .filter(|root| {
+                        if cli.watch_poll.is_some() {
+                            return false;
+                        }
+                        let network = fs_util::is_network_filesystem(root);
+                        if network {
+                            println!(
+                                "ℹ️  {} is on a network filesystem, polling for changes instead of watching",
+                                root.display()
+                            );
+                        }
+                        !network
+                    })
+                    .collect();
+                let poll_interval = (watched_roots.len() < 2).then(|| {
+                    std::time::Duration::from_secs(cli.watch_poll.unwrap_or(BISYNC_WATCH_POLL_SECS))
+                });
+
+                let mut watch_mode = BisyncWatchMode::new(
+                    session,
+                    watched_roots,
+                    poll_interval,
+                    std::time::Duration::from_millis(500), // 500ms debounce
+                );
+
+                watch_mode.watch().await?;
+                return Ok(());
+            }
+
             if !source.is_local() {
                 anyhow::bail!("Watch mode currently only supports local sources.");
             }
@@ -716,7 +1033,8 @@ Or install from local source with: cargo install --path . --features acl"#
                 source.path().to_path_buf(),
                 destination.path().to_path_buf(),
                 std::time::Duration::from_millis(500), // 500ms debounce
-            );
+            )
+            .with_poll_interval(cli.watch_poll.map(std::time::Duration::from_secs));
 
             watch_mode.watch().await?;
             return Ok(()); // Watch mode handles its own output
@@ -740,118 +1058,11 @@ Or install from local source with: cargo install --path . --features acl"#
             println!("{} ↔ {}\n", source, destination);
         }
 
-        // Create transports for source and destination
-        let (source_transport, dest_transport): (
-            std::sync::Arc<dyn transport::Transport>,
-            std::sync::Arc<dyn transport::Transport>,
-        ) = match (&source, &destination) {
-            (crate::path::SyncPath::Local { .. }, crate::path::SyncPath::Local { .. }) => {
-                // Both local
-                let verifier = integrity::IntegrityVerifier::new(checksum_type, verify_on_write);
-                let local_source = std::sync::Arc::new(
-                    transport::local::LocalTransport::with_verifier(verifier.clone()),
-                );
-                let local_dest =
-                    std::sync::Arc::new(transport::local::LocalTransport::with_verifier(verifier));
-                (local_source, local_dest)
-            }
-            (
-                crate::path::SyncPath::Local { .. },
-                crate::path::SyncPath::Remote { host, user, .. },
-            ) => {
-                // Local → Remote
-                let config = if let Some(user) = user {
-                    ssh::config::SshConfig {
-                        hostname: host.clone(),
-                        user: user.clone(),
-                        ..Default::default()
-                    }
-                } else {
-                    ssh::config::parse_ssh_config(host)?
-                };
-                let verifier = integrity::IntegrityVerifier::new(checksum_type, verify_on_write);
-                let local =
-                    std::sync::Arc::new(transport::local::LocalTransport::with_verifier(verifier));
-                let remote = std::sync::Arc::new(
-                    transport::ssh::SshTransport::with_pool_size(&config, cli.parallel).await?,
-                );
-                (local, remote)
-            }
-            (
-                crate::path::SyncPath::Remote { host, user, .. },
-                crate::path::SyncPath::Local { .. },
-            ) => {
-                // Remote → Local
-                let config = if let Some(user) = user {
-                    ssh::config::SshConfig {
-                        hostname: host.clone(),
-                        user: user.clone(),
-                        ..Default::default()
-                    }
-                } else {
-                    ssh::config::parse_ssh_config(host)?
-                };
-                let verifier = integrity::IntegrityVerifier::new(checksum_type, verify_on_write);
-                let remote = std::sync::Arc::new(
-                    transport::ssh::SshTransport::with_pool_size(&config, cli.parallel).await?,
-                );
-                let local =
-                    std::sync::Arc::new(transport::local::LocalTransport::with_verifier(verifier));
-                (remote, local)
-            }
-            (
-                crate::path::SyncPath::Remote {
-                    host: host1,
-                    user: user1,
-                    ..
-                },
-                crate::path::SyncPath::Remote {
-                    host: host2,
-                    user: user2,
-                    ..
-                },
-            ) => {
-                // Remote → Remote
-                let config1 = if let Some(user) = user1 {
-                    ssh::config::SshConfig {
-                        hostname: host1.clone(),
-                        user: user.clone(),
-                        ..Default::default()
-                    }
-                } else {
-                    ssh::config::parse_ssh_config(host1)?
-                };
-                let config2 = if let Some(user) = user2 {
-                    ssh::config::SshConfig {
-                        hostname: host2.clone(),
-                        user: user.clone(),
-                        ..Default::default()
-                    }
-                } else {
-                    ssh::config::parse_ssh_config(host2)?
-                };
-                let remote1 = std::sync::Arc::new(
-                    transport::ssh::SshTransport::with_pool_size(&config1, cli.parallel).await?,
-                );
-                let remote2 = std::sync::Arc::new(
-                    transport::ssh::SshTransport::with_pool_size(&config2, cli.parallel).await?,
-                );
-                (remote1, remote2)
-            }
-            _ => {
-                anyhow::bail!("Bidirectional sync does not support S3 paths");
-            }
-        };
+        let (source_transport, dest_transport) =
+            bisync_transports(&cli, source, destination, checksum_type, verify_on_write).await?;
 
         let bisync_engine = bisync::BisyncEngine::new(source_transport, dest_transport);
-        let bisync_opts = bisync::BisyncOptions {
-            conflict_resolution: bisync::ConflictResolution::from_str(&cli.conflict_resolve)
-                .ok_or_else(|| anyhow::anyhow!("Invalid conflict resolution strategy"))?,
-            max_delete_percent: cli.max_delete,
-            dry_run: cli.dry_run,
-            clear_state: cli.clear_bisync_state,
-            force_resync: cli.force_resync,
-        };
+        let bisync_opts = bisync_options(&cli)?;
 
         // Compute effective destination path based on trailing slash semantics
         let effective_dest = compute_destination_path(source, destination);
@@ -869,6 +1080,20 @@ Or install from local source with: cargo install --path . --features acl"#
             println!();
         }
 
+        if bisync_result.stats.files_identical > 0 && !cli.quiet && !cli.json {
+            println!(
+                "{} files changed on both sides to identical content\n",
+                bisync_result.stats.files_identical
+            );
+        }
+
+        if bisync_result.stats.tombstones_expired > 0 && !cli.quiet && !cli.json {
+            println!(
+                "Expired {} deletion records older than {} days\n",
+                bisync_result.stats.tombstones_expired, cli.tombstone_retention
+            );
+        }
+
         // Convert BisyncStats to SyncStats for compatibility
         sync::SyncStats {
             files_scanned: (bisync_result.stats.files_synced_to_source
@@ -877,12 +1102,15 @@ Or install from local source with: cargo install --path . --features acl"#
             files_updated: bisync_result.stats.files_synced_to_source as u64,
             files_deleted: bisync_result.stats.files_deleted_from_source
                 + bisync_result.stats.files_deleted_from_dest,
+            files_moved: bisync_result.stats.files_moved,
             files_skipped: 0,
             bytes_transferred: bisync_result.stats.bytes_transferred,
             files_delta_synced: 0,
             delta_bytes_saved: 0,
             files_compressed: 0,
             compression_bytes_saved: 0,
+            stream_bytes_raw: 0,
+            stream_bytes_wire: 0,
             files_verified: 0,
             verification_failures: 0,
             duration: std::time::Duration::from_millis(bisync_result.stats.duration_ms as u64),
@@ -902,19 +1130,34 @@ Or install from local source with: cargo install --path . --features acl"#
                 .collect(),
             dry_run_details: None,
         }
-    } else if source.is_local() && destination.is_remote() {
+    } else if source.is_local() && destination.is_remote() && !sftp_only {
         // Use server mode for local → remote SSH (faster than SFTP)
         if !cli.quiet && !cli.json {
             println!("Mode: Server protocol (push)\n");
         }
-        sync::server_mode::sync_server_mode(source.path(), destination, cli.dry_run, None).await?
-    } else if source.is_remote() && destination.is_local() {
+        sync::server_mode::sync_server_mode(
+            source.path(),
+            destination,
+            cli.dry_run,
+            cli.meta_options(),
+            cli.transfer_options(),
+            None,
+        )
+        .await?
+    } else if source.is_remote() && destination.is_local() && !sftp_only {
         // Use server mode for remote → local SSH (faster than SFTP)
         if !cli.quiet && !cli.json {
             println!("Mode: Server protocol (pull)\n");
         }
-        sync::server_mode::sync_pull_server_mode(source, destination.path(), cli.dry_run, None)
-            .await?
+        sync::server_mode::sync_pull_server_mode(
+            source,
+            destination.path(),
+            cli.dry_run,
+            cli.meta_options(),
+            cli.transfer_options(),
+            None,
+        )
+        .await?
     } else if cli.is_single_file() {
         if !cli.quiet && !cli.json {
             println!("Mode: Single file sync\n");
@@ -927,6 +1170,10 @@ Or install from local source with: cargo install --path . --features acl"#
         // Compute effective destination path based on trailing slash semantics
         let effective_dest = compute_destination_path(source, destination);
 
+        if sftp_only && !cli.quiet && !cli.json {
+            println!("Mode: SFTP-only (remote refuses command execution)\n");
+        }
+
         if cli.stream {
             if !cli.quiet && !cli.json {
                 println!("Mode: Streaming sync (experimental)\n");
@@ -995,6 +1242,12 @@ Or install from local source with: cargo install --path . --features acl"#
                     stats.files_deleted.to_string().red()
                 );
             }
+            if stats.files_moved > 0 {
+                println!(
+                    "  Would move:        {}",
+                    stats.files_moved.to_string().yellow()
+                );
+            }
 
             // Dry-run byte statistics
             if stats.bytes_would_add > 0
@@ -1059,6 +1312,12 @@ Or install from local source with: cargo install --path . --features acl"#
                     stats.files_deleted.to_string().bright_black()
                 );
             }
+            if stats.files_moved > 0 {
+                println!(
+                    "  Files moved:       {}",
+                    stats.files_moved.to_string().yellow()
+                );
+            }
         }
 
         // Transfer stats
@@ -1105,6 +1364,9 @@ Or install from local source with: cargo install --path . --features acl"#
             );
         }
 
+        // Stream compression stats (server/daemon connections)
+        print_stream_stats(&stats);
+
         // Verification stats (if enabled)
         if verification_mode == cli::VerificationMode::Verify {
             println!();
@@ -1135,6 +1397,249 @@ Or install from local source with: cargo install --path . --features acl"#
     Ok(())
 }
 
+/// Create transports for both sides of a bidirectional sync
+async fn bisync_transports(
+    cli: &Cli,
+    source: &SyncPath,
+    destination: &SyncPath,
+    checksum_type: integrity::ChecksumType,
+    verify_on_write: bool,
+) -> Result<(
+    std::sync::Arc<dyn transport::Transport>,
+    std::sync::Arc<dyn transport::Transport>,
+)> {
+    let transports: (
+        std::sync::Arc<dyn transport::Transport>,
+        std::sync::Arc<dyn transport::Transport>,
+    ) = match (source, destination) {
+        (crate::path::SyncPath::Local { .. }, crate::path::SyncPath::Local { .. }) => {
+            // Both local
+            let verifier = integrity::IntegrityVerifier::new(checksum_type, verify_on_write);
+            let local_source = std::sync::Arc::new(
+                transport::local::LocalTransport::with_verifier(verifier.clone()),
+            );
+            let local_dest =
+                std::sync::Arc::new(transport::local::LocalTransport::with_verifier(verifier));
+            (local_source, local_dest)
+        }
+        (crate::path::SyncPath::Local { .. }, crate::path::SyncPath::Remote { host, user, .. }) => {
+            // Local → Remote
+            let config = if let Some(user) = user {
+                ssh::config::SshConfig {
+                    hostname: host.clone(),
+                    user: user.clone(),
+                    ..Default::default()
+                }
+            } else {
+                ssh::config::parse_ssh_config(host)?
+            };
+            let verifier = integrity::IntegrityVerifier::new(checksum_type, verify_on_write);
+            let local =
+                std::sync::Arc::new(transport::local::LocalTransport::with_verifier(verifier));
+            let remote = std::sync::Arc::new(
+                transport::ssh::SshTransport::with_pool_size(&config, cli.parallel).await?,
+            );
+            (local, remote)
+        }
+        (crate::path::SyncPath::Remote { host, user, .. }, crate::path::SyncPath::Local { .. }) => {
+            // Remote → Local
+            let config = if let Some(user) = user {
+                ssh::config::SshConfig {
+                    hostname: host.clone(),
+                    user: user.clone(),
+                    ..Default::default()
+                }
+            } else {
+                ssh::config::parse_ssh_config(host)?
+            };
+            let verifier = integrity::IntegrityVerifier::new(checksum_type, verify_on_write);
+            let remote = std::sync::Arc::new(
+                transport::ssh::SshTransport::with_pool_size(&config, cli.parallel).await?,
+            );
+            let local =
+                std::sync::Arc::new(transport::local::LocalTransport::with_verifier(verifier));
+            (remote, local)
+        }
+        (
+            crate::path::SyncPath::Remote {
+                host: host1,
+                user: user1,
+                ..
+            },
+            crate::path::SyncPath::Remote {
+                host: host2,
+                user: user2,
+                ..
+            },
+        ) => {
+            // Remote → Remote
+            let config1 = if let Some(user) = user1 {
+                ssh::config::SshConfig {
+                    hostname: host1.clone(),
+                    user: user.clone(),
+                    ..Default::default()
+                }
+            } else {
+                ssh::config::parse_ssh_config(host1)?
+            };
+            let config2 = if let Some(user) = user2 {
+                ssh::config::SshConfig {
+                    hostname: host2.clone(),
+                    user: user.clone(),
+                    ..Default::default()
+                }
+            } else {
+                ssh::config::parse_ssh_config(host2)?
+            };
+            let remote1 = std::sync::Arc::new(
+                transport::ssh::SshTransport::with_pool_size(&config1, cli.parallel).await?,
+            );
+            let remote2 = std::sync::Arc::new(
+                transport::ssh::SshTransport::with_pool_size(&config2, cli.parallel).await?,
+            );
+            (remote1, remote2)
+        }
+        _ => {
+            anyhow::bail!("Bidirectional sync does not support S3 paths");
+        }
+    };
+    Ok(transports)
+}
+
+/// Build bisync options from the command line
+fn bisync_options(cli: &Cli) -> Result<bisync::BisyncOptions> {
+    let retention_secs = cli
+        .tombstone_retention
+        .checked_mul(24 * 60 * 60)
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "--tombstone-retention {} days is too large",
+                cli.tombstone_retention
+            )
+        })?;
+    Ok(bisync::BisyncOptions {
+        conflict_resolution: bisync::ConflictResolution::from_str(&cli.conflict_resolve)
+            .ok_or_else(|| anyhow::anyhow!("Invalid conflict resolution strategy"))?,
+        max_delete_percent: cli.max_delete,
+        dry_run: cli.dry_run,
+        clear_state: cli.clear_bisync_state,
+        force_resync: cli.force_resync,
+        detect_moves: cli.detect_moves,
+        trash: cli.trash,
+        tombstone_retention: std::time::Duration::from_secs(retention_secs),
+        checksum: cli.bisync_checksum,
+    })
+}
+
+/// Apply a batch file (--read-batch, or the one --write-batch just wrote),
+/// running hooks around it
+fn apply_batch(
+    cli: &Cli,
+    batch: &std::path::Path,
+    destination: &std::path::Path,
+    hook_executor: Option<&HookExecutor>,
+) -> Result<()> {
+    let mut context = HookContext {
+        source: batch.display().to_string(),
+        destination: destination.display().to_string(),
+        files_scanned: 0,
+        files_created: 0,
+        files_updated: 0,
+        files_deleted: 0,
+        files_skipped: 0,
+        bytes_transferred: 0,
+        duration_secs: 0,
+        dry_run: false,
+    };
+    if let Some(executor) = hook_executor {
+        if let Err(e) = executor.execute(HookType::PreSync, &context) {
+            tracing::error!("Pre-sync hook failed: {}", e);
+            return Err(e.into());
+        }
+    }
+
+    let start = std::time::Instant::now();
+    let stats = sync::batch::read_batch(batch, destination)?;
+    let duration = start.elapsed();
+
+    if cli.json {
+        sync::output::SyncEvent::Summary {
+            files_created: stats.files as usize,
+            files_updated: 0,
+            files_skipped: stats.files_current as usize,
+            files_deleted: stats.deletions as usize,
+            files_moved: 0,
+            bytes_transferred: stats.literal_bytes,
+            duration_secs: duration.as_secs_f64(),
+            files_verified: 0,
+            verification_failures: 0,
+        }
+        .emit();
+    } else if !cli.quiet {
+        println!("sy v{}", env!("CARGO_PKG_VERSION"));
+        println!(
+            "Applied batch {} → {}\n",
+            batch.display(),
+            destination.display()
+        );
+        print_batch_stats(&stats);
+    }
+
+    if let Some(executor) = hook_executor {
+        context.files_created = stats.files as usize;
+        context.files_deleted = stats.deletions as usize;
+        context.files_skipped = stats.files_current as usize;
+        context.bytes_transferred = stats.literal_bytes;
+        context.duration_secs = duration.as_secs();
+        if let Err(e) = executor.execute(HookType::PostSync, &context) {
+            tracing::error!("Post-sync hook failed: {}", e);
+        }
+    }
+    Ok(())
+}
+
+/// Server and daemon sessions emit no per-file JSON events
+const DELTA_STATS_ENGINE_ONLY: &str =
+    "--delta-stats is only reported by local and SFTP syncs, not by server or daemon sessions";
+
+/// Stream compression ratio for server and daemon connections
+fn print_stream_stats(stats: &sync::SyncStats) {
+    if stats.stream_bytes_wire > 0 && stats.stream_bytes_raw > stats.stream_bytes_wire {
+        println!();
+        println!(
+            "  {}          {} → {} ({:.1}x)",
+            "Stream:".bright_cyan(),
+            format_bytes(stats.stream_bytes_raw).bright_cyan(),
+            format_bytes(stats.stream_bytes_wire).bright_cyan(),
+            stats.stream_bytes_raw as f64 / stats.stream_bytes_wire as f64
+        );
+    }
+}
+
+fn print_batch_stats(stats: &sync::batch::BatchStats) {
+    println!("  Files:             {}", stats.files.to_string().green());
+    if stats.files_current > 0 {
+        println!(
+            "  Already current:   {}",
+            stats.files_current.to_string().bright_black()
+        );
+    }
+    println!("  Directories:       {}", stats.dirs.to_string().blue());
+    if stats.symlinks > 0 {
+        println!("  Symlinks:          {}", stats.symlinks.to_string().blue());
+    }
+    println!("  Deleted:           {}", stats.deletions.to_string().red());
+    println!();
+    println!(
+        "  Literal data:      {}",
+        format_bytes(stats.literal_bytes).cyan()
+    );
+    println!(
+        "  Matched data:      {}",
+        format_bytes(stats.matched_bytes).cyan()
+    );
+}
+
 fn format_bytes(bytes: u64) -> String {
     const KB: u64 = 1024;
     const MB: u64 = KB * 1024;
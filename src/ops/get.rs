@@ -20,8 +20,12 @@ use crate::ssh::config::SshConfig;
 #[cfg(feature = "ssh")]
 use crate::transport::server::ServerSession;
 
+#[cfg(any(feature = "ssh", unix))]
+use crate::server::{confine::Confinement, layout};
 #[cfg(unix)]
 use crate::transport::server::DaemonSession;
+#[cfg(any(feature = "ssh", unix))]
+use crate::transport::server::{PullSession, SessionOptions};
 
 /// Options for download operations
 #[derive(Debug, Clone, Default)]
@@ -362,6 +366,8 @@ pub async fn download_via_server_protocol(
     } else if !dest.exists() {
         std::fs::create_dir_all(dest)?;
     }
+    // Paths come from the remote side; keep every write beneath dest
+    let confine = Confinement::new(dest);
 
     // Scan local destination for comparison (to skip unchanged files)
     let local_entries: HashMap<String, (u64, i64)> = if dest.exists() {
@@ -398,7 +404,7 @@ pub async fn download_via_server_protocol(
     };
 
     // Connect to server in PULL mode
-    let mut session = ServerSession::connect_ssh_pull(config, source)
+    let mut session = ServerSession::connect_ssh_pull(config, source, SessionOptions::default())
         .await
         .context(
             "Failed to connect to sy --server on remote. Is 'sy' installed on the remote host?",
@@ -421,7 +427,13 @@ pub async fn download_via_server_protocol(
             }
         }
 
-        let full_path = dest.join(dir_path);
+        let full_path = match confine.resolve(dir_path) {
+            Ok(p) => p,
+            Err(e) => {
+                failed_dirs.push((dir_path.clone(), e.to_string()));
+                continue;
+            }
+        };
         if options.dry_run {
             result.created_dirs += 1;
         } else {
@@ -440,6 +452,7 @@ pub async fn download_via_server_protocol(
 
     let mut decisions = Vec::with_capacity(file_list.entries.len());
     let mut files_to_receive: Vec<(u32, String, u64)> = Vec::new();
+    let mut hardlinks: Vec<(u32, u32)> = Vec::new();
 
     for (idx, entry) in file_list.entries.iter().enumerate() {
         let entry_path = std::path::Path::new(&entry.path);
@@ -448,6 +461,9 @@ pub async fn download_via_server_protocol(
             decisions.push(crate::server::protocol::Decision {
                 index: idx as u32,
                 action: Action::Skip,
+                resume_from: 0,
+                digest: crate::integrity::ChecksumType::None,
+                basis: None,
             });
             continue;
         }
@@ -457,12 +473,21 @@ pub async fn download_via_server_protocol(
                 decisions.push(crate::server::protocol::Decision {
                     index: idx as u32,
                     action: Action::Skip,
+                    resume_from: 0,
+                    digest: crate::integrity::ChecksumType::None,
+                    basis: None,
                 });
                 continue;
             }
         }
 
-        let action = if let Some((local_size, local_mtime)) = local_entries.get(&entry.path) {
+        let action = if let Err(e) = confine.resolve(&entry.path) {
+            result.failed.push(FailedDownload {
+                path: entry.path.clone(),
+                error: e.to_string(),
+            });
+            Action::Skip
+        } else if let Some((local_size, local_mtime)) = local_entries.get(&entry.path) {
             if *local_size == entry.size && *local_mtime >= entry.mtime {
                 result.skipped_files += 1;
                 Action::Skip
@@ -473,13 +498,24 @@ pub async fn download_via_server_protocol(
             Action::Create
         };
 
+        // Hard links carry no data; they are copied from their leader below
         if action != Action::Skip {
-            files_to_receive.push((idx as u32, entry.path.clone(), entry.size));
+            match entry.hardlink_to {
+                Some(leader) => hardlinks.push((idx as u32, leader)),
+                None => files_to_receive.push((idx as u32, entry.path.clone(), entry.size)),
+            }
         }
 
         decisions.push(crate::server::protocol::Decision {
             index: idx as u32,
-            action,
+            action: if entry.is_hardlink() {
+                Action::Skip
+            } else {
+                action
+            },
+            resume_from: 0,
+            digest: crate::integrity::ChecksumType::None,
+            basis: None,
         });
     }
 
@@ -491,6 +527,9 @@ pub async fn download_via_server_protocol(
             .map(|idx| crate::server::protocol::Decision {
                 index: idx as u32,
                 action: Action::Skip,
+                resume_from: 0,
+                digest: crate::integrity::ChecksumType::None,
+                basis: None,
             })
             .collect();
         session.send_file_list_ack(skip_decisions).await?;
@@ -502,30 +541,43 @@ pub async fn download_via_server_protocol(
 
     // Step 3: Receive files
     let mut files_received: Vec<(u32, String, u8)> = Vec::new();
+    let mut symlinks_next = false;
     let single_file_dest = dest_is_file && files_to_receive.len() == 1;
-
-    for (idx, rel_path, _expected_size) in &files_to_receive {
+    // For single file downloads to a file path, write directly to dest
+    let single_file = dest.file_name().filter(|_| single_file_dest).map(|name| {
+        let parent = dest.parent().filter(|p| !p.as_os_str().is_empty());
+        (
+            Confinement::new(parent.unwrap_or(Path::new("."))),
+            name.to_string_lossy().into_owned(),
+        )
+    });
+
+    for (idx, rel_path, _) in &files_to_receive {
         let file_data = match session.read_file_data().await? {
             Some(data) => data,
-            None => break,
+            None => {
+                symlinks_next = true;
+                break;
+            }
         };
 
-        // For single file downloads to a file path, write directly to dest
-        // Otherwise, join the relative path to the destination directory
-        let full_path = if single_file_dest {
-            dest.to_path_buf()
-        } else {
-            dest.join(rel_path)
+        // Otherwise, the relative path goes beneath the destination directory
+        let (target, target_path) = match &single_file {
+            Some((parent, name)) => (parent, name.as_str()),
+            None => (&confine, rel_path.as_str()),
         };
 
-        if let Some(parent) = full_path.parent() {
-            std::fs::create_dir_all(parent)?;
-        }
-
-        let file_size = file_data.data.len() as u64;
-        match std::fs::write(&full_path, &file_data.data) {
-            Ok(_) => {
-                result.downloaded_bytes += file_size;
+        match session
+            .receive_file(
+                target,
+                target_path,
+                &file_list.entries[*idx as usize],
+                file_data,
+            )
+            .await?
+        {
+            Ok(received) => {
+                result.downloaded_bytes += received.bytes;
                 result.downloaded_files += 1;
                 files_received.push((*idx, rel_path.clone(), 0));
             }
@@ -540,17 +592,49 @@ pub async fn download_via_server_protocol(
     }
 
     for (idx, _path, status) in &files_received {
-        session.send_file_done(*idx, *status).await?;
+        session.send_file_done(*idx, *status, Vec::new()).await?;
     }
 
-    // Step 4: Handle symlinks
-    match session.read_symlink_batch_body().await {
+    // Copy hard links from the files they link to
+    for (idx, leader) in &hardlinks {
+        let (Some(entry), Some(leader)) = (
+            file_list.entries.get(*idx as usize),
+            file_list.entries.get(*leader as usize),
+        ) else {
+            continue;
+        };
+        let linked = confine.resolve(&leader.path).and_then(|leader| {
+            let path = confine.resolve(&entry.path)?;
+            Ok(layout::link_file(&leader, &path, false)?)
+        });
+        match linked {
+            Ok(()) => result.downloaded_files += 1,
+            Err(e) => result.failed.push(FailedDownload {
+                path: entry.path.clone(),
+                error: e.to_string(),
+            }),
+        }
+    }
+
+    // Step 4: Handle symlinks (if any; the remote side just ends otherwise)
+    let symlink_batch = if symlinks_next {
+        session.read_symlink_batch_body().await
+    } else {
+        session.read_symlink_batch().await
+    };
+    match symlink_batch {
         Ok(symlink_batch) => {
             let mut created = 0u32;
             let mut failed: Vec<(String, String)> = Vec::new();
 
             for entry in &symlink_batch.entries {
-                let link_path = dest.join(&entry.path);
+                let link_path = match confine.resolve(&entry.path) {
+                    Ok(p) => p,
+                    Err(e) => {
+                        failed.push((entry.path.clone(), e.to_string()));
+                        continue;
+                    }
+                };
 
                 if link_path.exists() || link_path.symlink_metadata().is_ok() {
                     let _ = std::fs::remove_file(&link_path);
@@ -627,6 +711,8 @@ pub async fn download_via_daemon(
     } else if !dest.exists() {
         std::fs::create_dir_all(dest)?;
     }
+    // Paths come from the remote side; keep every write beneath dest
+    let confine = Confinement::new(dest);
 
     // Scan local destination for comparison (to skip unchanged files)
     let local_entries: HashMap<String, (u64, i64)> = if dest.exists() {
@@ -663,7 +749,7 @@ pub async fn download_via_daemon(
     };
 
     // Connect to daemon in PULL mode
-    let mut session = DaemonSession::connect_pull(socket_path, source)
+    let mut session = DaemonSession::connect_pull(socket_path, source, SessionOptions::default())
         .await
         .context("Failed to connect to daemon")?;
 
@@ -684,7 +770,13 @@ pub async fn download_via_daemon(
             }
         }
 
-        let full_path = dest.join(dir_path);
+        let full_path = match confine.resolve(dir_path) {
+            Ok(p) => p,
+            Err(e) => {
+                failed_dirs.push((dir_path.clone(), e.to_string()));
+                continue;
+            }
+        };
         if options.dry_run {
             result.created_dirs += 1;
         } else {
@@ -703,6 +795,7 @@ pub async fn download_via_daemon(
 
     let mut decisions = Vec::with_capacity(file_list.entries.len());
     let mut files_to_receive: Vec<(u32, String, u64)> = Vec::new();
+    let mut hardlinks: Vec<(u32, u32)> = Vec::new();
 
     for (idx, entry) in file_list.entries.iter().enumerate() {
         let entry_path = std::path::Path::new(&entry.path);
@@ -711,6 +804,9 @@ pub async fn download_via_daemon(
             decisions.push(crate::server::protocol::Decision {
                 index: idx as u32,
                 action: Action::Skip,
+                resume_from: 0,
+                digest: crate::integrity::ChecksumType::None,
+                basis: None,
             });
             continue;
         }
@@ -720,12 +816,21 @@ pub async fn download_via_daemon(
                 decisions.push(crate::server::protocol::Decision {
                     index: idx as u32,
                     action: Action::Skip,
+                    resume_from: 0,
+                    digest: crate::integrity::ChecksumType::None,
+                    basis: None,
                 });
                 continue;
             }
         }
 
-        let action = if let Some((local_size, local_mtime)) = local_entries.get(&entry.path) {
+        let action = if let Err(e) = confine.resolve(&entry.path) {
+            result.failed.push(FailedDownload {
+                path: entry.path.clone(),
+                error: e.to_string(),
+            });
+            Action::Skip
+        } else if let Some((local_size, local_mtime)) = local_entries.get(&entry.path) {
             if *local_size == entry.size && *local_mtime >= entry.mtime {
                 result.skipped_files += 1;
                 Action::Skip
@@ -736,13 +841,24 @@ pub async fn download_via_daemon(
             Action::Create
         };
 
+        // Hard links carry no data; they are copied from their leader below
         if action != Action::Skip {
-            files_to_receive.push((idx as u32, entry.path.clone(), entry.size));
+            match entry.hardlink_to {
+                Some(leader) => hardlinks.push((idx as u32, leader)),
+                None => files_to_receive.push((idx as u32, entry.path.clone(), entry.size)),
+            }
         }
 
         decisions.push(crate::server::protocol::Decision {
             index: idx as u32,
-            action,
+            action: if entry.is_hardlink() {
+                Action::Skip
+            } else {
+                action
+            },
+            resume_from: 0,
+            digest: crate::integrity::ChecksumType::None,
+            basis: None,
         });
     }
 
@@ -754,6 +870,9 @@ pub async fn download_via_daemon(
             .map(|idx| crate::server::protocol::Decision {
                 index: idx as u32,
                 action: Action::Skip,
+                resume_from: 0,
+                digest: crate::integrity::ChecksumType::None,
+                basis: None,
             })
             .collect();
         session.send_file_list_ack(skip_decisions).await?;
@@ -765,30 +884,43 @@ pub async fn download_via_daemon(
 
     // Step 3: Receive files
     let mut files_received: Vec<(u32, String, u8)> = Vec::new();
+    let mut symlinks_next = false;
     let single_file_dest = dest_is_file && files_to_receive.len() == 1;
-
-    for (idx, rel_path, _expected_size) in &files_to_receive {
+    // For single file downloads to a file path, write directly to dest
+    let single_file = dest.file_name().filter(|_| single_file_dest).map(|name| {
+        let parent = dest.parent().filter(|p| !p.as_os_str().is_empty());
+        (
+            Confinement::new(parent.unwrap_or(Path::new("."))),
+            name.to_string_lossy().into_owned(),
+        )
+    });
+
+    for (idx, rel_path, _) in &files_to_receive {
         let file_data = match session.read_file_data().await? {
             Some(data) => data,
-            None => break,
+            None => {
+                symlinks_next = true;
+                break;
+            }
         };
 
-        // For single file downloads to a file path, write directly to dest
-        // Otherwise, join the relative path to the destination directory
-        let full_path = if single_file_dest {
-            dest.to_path_buf()
-        } else {
-            dest.join(rel_path)
+        // Otherwise, the relative path goes beneath the destination directory
+        let (target, target_path) = match &single_file {
+            Some((parent, name)) => (parent, name.as_str()),
+            None => (&confine, rel_path.as_str()),
         };
 
-        if let Some(parent) = full_path.parent() {
-            std::fs::create_dir_all(parent)?;
-        }
-
-        let file_size = file_data.data.len() as u64;
-        match std::fs::write(&full_path, &file_data.data) {
-            Ok(_) => {
-                result.downloaded_bytes += file_size;
+        match session
+            .receive_file(
+                target,
+                target_path,
+                &file_list.entries[*idx as usize],
+                file_data,
+            )
+            .await?
+        {
+            Ok(received) => {
+                result.downloaded_bytes += received.bytes;
                 result.downloaded_files += 1;
                 files_received.push((*idx, rel_path.clone(), 0));
             }
@@ -803,17 +935,49 @@ pub async fn download_via_daemon(
     }
 
     for (idx, _path, status) in &files_received {
-        session.send_file_done(*idx, *status).await?;
+        session.send_file_done(*idx, *status, Vec::new()).await?;
     }
 
-    // Step 4: Handle symlinks
-    match session.read_symlink_batch_body().await {
+    // Copy hard links from the files they link to
+    for (idx, leader) in &hardlinks {
+        let (Some(entry), Some(leader)) = (
+            file_list.entries.get(*idx as usize),
+            file_list.entries.get(*leader as usize),
+        ) else {
+            continue;
+        };
+        let linked = confine.resolve(&leader.path).and_then(|leader| {
+            let path = confine.resolve(&entry.path)?;
+            Ok(layout::link_file(&leader, &path, false)?)
+        });
+        match linked {
+            Ok(()) => result.downloaded_files += 1,
+            Err(e) => result.failed.push(FailedDownload {
+                path: entry.path.clone(),
+                error: e.to_string(),
+            }),
+        }
+    }
+
+    // Step 4: Handle symlinks (if any; the remote side just ends otherwise)
+    let symlink_batch = if symlinks_next {
+        session.read_symlink_batch_body().await
+    } else {
+        session.read_symlink_batch().await
+    };
+    match symlink_batch {
         Ok(symlink_batch) => {
             let mut created = 0u32;
             let mut failed: Vec<(String, String)> = Vec::new();
 
             for entry in &symlink_batch.entries {
-                let link_path = dest.join(&entry.path);
+                let link_path = match confine.resolve(&entry.path) {
+                    Ok(p) => p,
+                    Err(e) => {
+                        failed.push((entry.path.clone(), e.to_string()));
+                        continue;
+                    }
+                };
 
                 if link_path.exists() || link_path.symlink_metadata().is_ok() {
                     let _ = std::fs::remove_file(&link_path);
@@ -1054,6 +1218,46 @@ pub async fn download(
             );
         }
 
+        #[cfg(feature = "ssh")]
+        SyncPath::Sftp {
+            host, user, port, ..
+        } => {
+            use crate::retry::RetryConfig;
+            use crate::ssh::config::parse_ssh_config;
+            use crate::transport::ssh::SshTransport;
+
+            // sftp:// paths carry their own user and port overrides
+            let mut config =
+                parse_ssh_config(host).unwrap_or_else(|_| crate::ssh::config::SshConfig::new(host));
+            if let Some(user) = user {
+                config.user = user.clone();
+            }
+            if let Some(port) = port {
+                config.port = *port;
+            }
+
+            let pool_size = options.parallel.max(1);
+            let transport = Arc::new(
+                SshTransport::with_retry_config(&config, pool_size, RetryConfig::default())
+                    .await
+                    .context("Failed to create SSH transport")?,
+            );
+
+            transport
+                .prepare_for_transfer(1000)
+                .await
+                .context("Failed to expand SSH connection pool")?;
+
+            download_sftp(transport, source.path(), dest, options, &filter).await
+        }
+
+        #[cfg(not(feature = "ssh"))]
+        SyncPath::Sftp { .. } => {
+            anyhow::bail!(
+                "SSH support not enabled. Reinstall with: cargo install sy --features ssh"
+            );
+        }
+
         #[cfg(feature = "s3")]
         SyncPath::S3 {
             bucket,
@@ -1114,6 +1318,52 @@ pub async fn download(
             );
         }
 
+        #[cfg(feature = "azure")]
+        SyncPath::Azure {
+            container,
+            key,
+            account,
+            ..
+        } => {
+            use crate::transport::azure::AzureTransport;
+
+            let transport = Arc::new(
+                AzureTransport::new(container.clone(), key.clone(), account.clone())
+                    .await
+                    .context("Failed to create Azure transport")?,
+            );
+
+            download_from_transport(transport, source.path(), dest, options, &filter).await
+        }
+
+        #[cfg(not(feature = "azure"))]
+        SyncPath::Azure { .. } => {
+            anyhow::bail!(
+                "Azure support not enabled. Reinstall with: cargo install sy --features azure"
+            );
+        }
+
+        #[cfg(feature = "webdav")]
+        SyncPath::WebDav {
+            host, user, secure, ..
+        } => {
+            use crate::transport::webdav::WebDavTransport;
+
+            let transport = Arc::new(
+                WebDavTransport::new(host.clone(), user.clone(), *secure)
+                    .context("Failed to create WebDAV transport")?,
+            );
+
+            download_from_transport(transport, source.path(), dest, options, &filter).await
+        }
+
+        #[cfg(not(feature = "webdav"))]
+        SyncPath::WebDav { .. } => {
+            anyhow::bail!(
+                "WebDAV support not enabled. Reinstall with: cargo install sy --features webdav"
+            );
+        }
+
         #[cfg(unix)]
         SyncPath::Daemon { path, .. } => {
             let socket_path = options.daemon_socket.as_ref().ok_or_else(|| {
@@ -1129,5 +1379,9 @@ pub async fn download(
         SyncPath::Daemon { .. } => {
             anyhow::bail!("Daemon mode is only supported on Unix systems");
         }
+
+        SyncPath::TcpDaemon { .. } => {
+            anyhow::bail!("TCP daemon paths (sy://) are only supported by the sy command");
+        }
     }
 }
@@ -24,6 +24,8 @@ use crate::transport::server::ServerSession;
 
 #[cfg(unix)]
 use crate::transport::server::DaemonSession;
+#[cfg(any(feature = "ssh", unix))]
+use crate::transport::server::SessionOptions;
 
 /// Options for upload operations
 #[derive(Debug, Clone, Default)]
@@ -399,11 +401,15 @@ pub async fn upload_via_server_protocol(
             return Ok(result);
         }
 
-        let mut session = ServerSession::connect_ssh(config, &server_root)
-            .await
-            .context(
-                "Failed to connect to sy --server on remote. Is 'sy' installed on the remote host?",
-            )?;
+        let mut session = ServerSession::connect_ssh(
+            config,
+            &server_root,
+            SessionOptions::default(),
+        )
+        .await
+        .context(
+            "Failed to connect to sy --server on remote. Is 'sy' installed on the remote host?",
+        )?;
 
         let entry = FileListEntry {
             path: rel_path.clone(),
@@ -412,6 +418,8 @@ pub async fn upload_via_server_protocol(
             mode: 0o644,
             flags: 0,
             symlink_target: None,
+            hardlink_to: None,
+            meta: None,
         };
         session.send_file_list(vec![entry]).await?;
         let ack = session.read_ack().await?;
@@ -520,9 +528,11 @@ pub async fn upload_via_server_protocol(
     }
 
     // Connect to server
-    let mut session = ServerSession::connect_ssh(config, dest).await.context(
-        "Failed to connect to sy --server on remote. Is 'sy' installed on the remote host?",
-    )?;
+    let mut session = ServerSession::connect_ssh(config, dest, SessionOptions::default())
+        .await
+        .context(
+            "Failed to connect to sy --server on remote. Is 'sy' installed on the remote host?",
+        )?;
 
     // Step 1: Create directories
     if !directories.is_empty() {
@@ -541,6 +551,8 @@ pub async fn upload_via_server_protocol(
             mode: 0o644,
             flags: 0,
             symlink_target: None,
+            hardlink_to: None,
+            meta: None,
         })
         .collect();
 
@@ -666,7 +678,7 @@ pub async fn upload_via_daemon(
         // 1. A directory path (e.g., /remote/) - file goes into that directory
         // 2. A file path (e.g., /remote/newname.txt) - file gets renamed
         // We use dest directly as the root for the daemon connection
-        let mut session = DaemonSession::connect(socket_path, dest)
+        let mut session = DaemonSession::connect(socket_path, dest, SessionOptions::default())
             .await
             .context("Failed to connect to daemon")?;
 
@@ -677,6 +689,8 @@ pub async fn upload_via_daemon(
             mode: 0o644,
             flags: 0,
             symlink_target: None,
+            hardlink_to: None,
+            meta: None,
         };
         session.send_file_list(vec![entry]).await?;
         let ack = session.read_ack().await?;
@@ -785,7 +799,7 @@ pub async fn upload_via_daemon(
     }
 
     // Connect to daemon
-    let mut session = DaemonSession::connect(socket_path, dest)
+    let mut session = DaemonSession::connect(socket_path, dest, SessionOptions::default())
         .await
         .context("Failed to connect to daemon")?;
 
@@ -806,6 +820,8 @@ pub async fn upload_via_daemon(
             mode: 0o644,
             flags: 0,
             symlink_target: None,
+            hardlink_to: None,
+            meta: None,
         })
         .collect();
 
@@ -945,6 +961,45 @@ pub async fn upload(
             );
         }
 
+        #[cfg(feature = "ssh")]
+        SyncPath::Sftp {
+            host, user, port, ..
+        } => {
+            use crate::ssh::config::parse_ssh_config;
+            use crate::transport::sftp::SftpTransport;
+            use crate::transport::ssh::SshTransport;
+
+            // sftp:// paths carry their own user and port overrides
+            let mut config =
+                parse_ssh_config(host).unwrap_or_else(|_| crate::ssh::config::SshConfig::new(host));
+            if let Some(user) = user {
+                config.user = user.clone();
+            }
+            if let Some(port) = port {
+                config.port = *port;
+            }
+
+            let pool_size = options.parallel.max(1);
+            let ssh = SshTransport::with_retry_config(&config, pool_size, RetryConfig::default())
+                .await
+                .context("Failed to create SSH transport")?;
+            let transport = Arc::new(SftpTransport::new(ssh));
+
+            transport
+                .prepare_for_transfer(1000)
+                .await
+                .context("Failed to expand SSH connection pool")?;
+
+            upload_to_transport(transport, source, dest.path(), options, &filter).await
+        }
+
+        #[cfg(not(feature = "ssh"))]
+        SyncPath::Sftp { .. } => {
+            anyhow::bail!(
+                "SSH support not enabled. Reinstall with: cargo install sy --features ssh"
+            );
+        }
+
         #[cfg(feature = "s3")]
         SyncPath::S3 {
             bucket,
@@ -1005,6 +1060,52 @@ pub async fn upload(
             );
         }
 
+        #[cfg(feature = "azure")]
+        SyncPath::Azure {
+            container,
+            key,
+            account,
+            ..
+        } => {
+            use crate::transport::azure::AzureTransport;
+
+            let transport = Arc::new(
+                AzureTransport::new(container.clone(), key.clone(), account.clone())
+                    .await
+                    .context("Failed to create Azure transport")?,
+            );
+
+            upload_to_transport(transport, source, dest.path(), options, &filter).await
+        }
+
+        #[cfg(not(feature = "azure"))]
+        SyncPath::Azure { .. } => {
+            anyhow::bail!(
+                "Azure support not enabled. Reinstall with: cargo install sy --features azure"
+            );
+        }
+
+        #[cfg(feature = "webdav")]
+        SyncPath::WebDav {
+            host, user, secure, ..
+        } => {
+            use crate::transport::webdav::WebDavTransport;
+
+            let transport = Arc::new(
+                WebDavTransport::new(host.clone(), user.clone(), *secure)
+                    .context("Failed to create WebDAV transport")?,
+            );
+
+            upload_to_transport(transport, source, dest.path(), options, &filter).await
+        }
+
+        #[cfg(not(feature = "webdav"))]
+        SyncPath::WebDav { .. } => {
+            anyhow::bail!(
+                "WebDAV support not enabled. Reinstall with: cargo install sy --features webdav"
+            );
+        }
+
         #[cfg(unix)]
         SyncPath::Daemon { path, .. } => {
             let socket_path = options.daemon_socket.as_ref().ok_or_else(|| {
@@ -1020,5 +1121,9 @@ pub async fn upload(
         SyncPath::Daemon { .. } => {
             anyhow::bail!("Daemon mode is only supported on Unix systems");
         }
+
+        SyncPath::TcpDaemon { .. } => {
+            anyhow::bail!("TCP daemon paths (sy://) are only supported by the sy command");
+        }
     }
 }
@@ -407,6 +407,37 @@ pub async fn remove(target: &SyncPath, options: &RemoveOptions) -> Result<Remove
             );
         }
 
+        #[cfg(feature = "ssh")]
+        SyncPath::Sftp {
+            host, user, port, ..
+        } => {
+            use crate::ssh::config::parse_ssh_config;
+            use crate::transport::ssh::SshTransport;
+
+            // sftp:// paths carry their own user and port overrides
+            let mut config =
+                parse_ssh_config(host).unwrap_or_else(|_| crate::ssh::config::SshConfig::new(host));
+            if let Some(user) = user {
+                config.user = user.clone();
+            }
+            if let Some(port) = port {
+                config.port = *port;
+            }
+
+            let transport = SshTransport::with_retry_config(&config, 1, RetryConfig::default())
+                .await
+                .context("Failed to create SSH transport")?;
+
+            remove_sftp(&transport, target.path(), options, &filter).await
+        }
+
+        #[cfg(not(feature = "ssh"))]
+        SyncPath::Sftp { .. } => {
+            anyhow::bail!(
+                "SSH support not enabled. Reinstall with: cargo install sy --features ssh"
+            );
+        }
+
         #[cfg(feature = "s3")]
         SyncPath::S3 {
             bucket,
@@ -463,6 +494,48 @@ pub async fn remove(target: &SyncPath, options: &RemoveOptions) -> Result<Remove
             );
         }
 
+        #[cfg(feature = "azure")]
+        SyncPath::Azure {
+            container,
+            key,
+            account,
+            ..
+        } => {
+            use crate::transport::azure::AzureTransport;
+
+            let transport = AzureTransport::new(container.clone(), key.clone(), account.clone())
+                .await
+                .context("Failed to create Azure transport")?;
+
+            remove_with_transport(&transport, target.path(), options, &filter).await
+        }
+
+        #[cfg(not(feature = "azure"))]
+        SyncPath::Azure { .. } => {
+            anyhow::bail!(
+                "Azure support not enabled. Reinstall with: cargo install sy --features azure"
+            );
+        }
+
+        #[cfg(feature = "webdav")]
+        SyncPath::WebDav {
+            host, user, secure, ..
+        } => {
+            use crate::transport::webdav::WebDavTransport;
+
+            let transport = WebDavTransport::new(host.clone(), user.clone(), *secure)
+                .context("Failed to create WebDAV transport")?;
+
+            remove_with_transport(&transport, target.path(), options, &filter).await
+        }
+
+        #[cfg(not(feature = "webdav"))]
+        SyncPath::WebDav { .. } => {
+            anyhow::bail!(
+                "WebDAV support not enabled. Reinstall with: cargo install sy --features webdav"
+            );
+        }
+
         #[cfg(unix)]
         SyncPath::Daemon { .. } => {
             // Note: Daemon protocol doesn't currently support deletion operations.
@@ -477,5 +550,9 @@ pub async fn remove(target: &SyncPath, options: &RemoveOptions) -> Result<Remove
         SyncPath::Daemon { .. } => {
             anyhow::bail!("Daemon mode is only supported on Unix systems");
         }
+
+        SyncPath::TcpDaemon { .. } => {
+            anyhow::bail!("TCP daemon paths (sy://) are only supported by the sy command");
+        }
     }
 }
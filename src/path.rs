@@ -1,6 +1,6 @@
 use std::path::{Path, PathBuf};
 
-/// Represents a sync path that can be either local, remote (SSH), S3, GCS, or daemon
+/// Represents a sync path that can be either local, remote (SSH), SFTP, S3, GCS, Azure, WebDAV, or daemon (socket or TCP)
 #[derive(Debug, Clone, PartialEq)]
 pub enum SyncPath {
     Local {
@@ -13,6 +13,15 @@ pub enum SyncPath {
         path: PathBuf,
         has_trailing_slash: bool,
     },
+    /// SFTP-only path - for hosts that allow SFTP but not command execution
+    /// Format: sftp://[user@]host[:port]/path
+    Sftp {
+        host: String,
+        user: Option<String>,
+        port: Option<u16>,
+        path: PathBuf,
+        has_trailing_slash: bool,
+    },
     S3 {
         bucket: String,
         key: String,
@@ -27,6 +36,21 @@ pub enum SyncPath {
         service_account_path: Option<String>,
         has_trailing_slash: bool,
     },
+    Azure {
+        container: String,
+        key: String,
+        account: Option<String>,
+        has_trailing_slash: bool,
+    },
+    /// WebDAV path (Nextcloud, ownCloud, ...)
+    /// Format: dav://[user@]host[:port]/path (HTTP) or davs://... (HTTPS)
+    WebDav {
+        host: String,
+        user: Option<String>,
+        path: PathBuf,
+        secure: bool,
+        has_trailing_slash: bool,
+    },
     /// Daemon path - connects via Unix socket instead of SSH
     /// Format: daemon:/path/on/remote
     /// Requires --use-daemon <socket> to specify the socket path
@@ -34,6 +58,14 @@ pub enum SyncPath {
         path: PathBuf,
         has_trailing_slash: bool,
     },
+    /// Daemon reached over TCP - started with `sy --daemon --listen`
+    /// Format: sy://host[:port]/path (default port 8730)
+    TcpDaemon {
+        host: String,
+        port: u16,
+        path: PathBuf,
+        has_trailing_slash: bool,
+    },
 }
 
 impl SyncPath {
@@ -42,24 +74,29 @@ impl SyncPath {
     /// Supported formats:
     /// - Local: `/path/to/dir`, `./relative/path`, `relative/path`
     /// - Remote: `user@host:/path`, `host:/path`
+    /// - SFTP: `sftp://user@host/path`, `sftp://host:2222/path`, `sftp://host/~/relative-to-home`
     /// - S3: `s3://bucket/key/path`, `s3://bucket/key?region=us-west-2`, `s3://bucket/key?endpoint=https://...`
     /// - GCS: `gs://bucket/key/path`, `gs://bucket/key?project=my-project`, `gs://bucket/key?service_account=/path/to/key.json`
+    /// - Azure: `az://container/prefix`, `az://container/prefix?account=mystorageaccount`
+    /// - WebDAV: `davs://user@cloud.example.com/remote.php/dav/files/user/dir`, `dav://host:8080/dir`
+    /// - TCP daemon: `sy://host/path`, `sy://host:9000/path`, `sy://host/~/relative-to-home`
     ///
     /// Trailing slash semantics (rsync-compatible):
     /// - `/path/to/dir` (no slash): Copy directory itself to destination
     /// - `/path/to/dir/` (with slash): Copy directory contents to destination
     pub fn parse(s: &str) -> Self {
         // Detect trailing slash (before parsing)
-        // For S3/GCS paths with query parameters, check the path portion before '?'
-        let has_trailing_slash = if s.starts_with("s3://") || s.starts_with("gs://") {
-            if let Some(q_pos) = s.find('?') {
-                s[..q_pos].ends_with('/')
+        // For S3/GCS/Azure paths with query parameters, check the path portion before '?'
+        let has_trailing_slash =
+            if s.starts_with("s3://") || s.starts_with("gs://") || s.starts_with("az://") {
+                if let Some(q_pos) = s.find('?') {
+                    s[..q_pos].ends_with('/')
+                } else {
+                    s.ends_with('/')
+                }
             } else {
-                s.ends_with('/')
-            }
-        } else {
-            s.ends_with('/') || s.ends_with('\\')
-        };
+                s.ends_with('/') || s.ends_with('\\')
+            };
 
         // Check for GCS URL format
         if let Some(remainder) = s.strip_prefix("gs://") {
@@ -110,6 +147,35 @@ impl SyncPath {
             }
         }
 
+        // Check for Azure Blob Storage URL format
+        if let Some(remainder) = s.strip_prefix("az://") {
+            // Split on ? to separate path from query params
+            let (path_part, query_part) = match remainder.split_once('?') {
+                Some((path_part, query)) => (path_part, Some(query)),
+                None => (remainder, None),
+            };
+
+            // Split path into container and key (no key = container root)
+            let (container, key) = path_part.split_once('/').unwrap_or((path_part, ""));
+
+            // Parse query parameters (account)
+            let mut account = None;
+            if let Some(query) = query_part {
+                for param in query.split('&') {
+                    if let Some(("account", v)) = param.split_once('=') {
+                        account = Some(v.to_string());
+                    }
+                }
+            }
+
+            return SyncPath::Azure {
+                container: container.to_string(),
+                key: key.to_string(),
+                account,
+                has_trailing_slash,
+            };
+        }
+
         // Check for S3 URL format
         if let Some(remainder) = s.strip_prefix("s3://") {
             // Split on ? to separate path from query params
@@ -159,6 +225,96 @@ impl SyncPath {
             }
         }
 
+        // Check for SFTP URL format
+        if let Some(remainder) = s.strip_prefix("sftp://") {
+            // Split authority from path (no path = server root)
+            let (authority, path) = match remainder.find('/') {
+                Some(slash_pos) => (&remainder[..slash_pos], &remainder[slash_pos..]),
+                None => (remainder, "/"),
+            };
+
+            // sftp://host/~/dir is relative to the login directory
+            let path = path.strip_prefix("/~/").unwrap_or(path);
+
+            // Parse user@host[:port]
+            let (user, host_port) = match authority.rsplit_once('@') {
+                Some((user, host)) => (Some(user.to_string()), host),
+                None => (None, authority),
+            };
+            let (host, port) = match host_port
+                .rsplit_once(':')
+                .and_then(|(host, port)| port.parse().ok().map(|port| (host, port)))
+            {
+                Some((host, port)) => (host, Some(port)),
+                None => (host_port, None),
+            };
+
+            return SyncPath::Sftp {
+                host: host.to_string(),
+                user,
+                port,
+                path: PathBuf::from(path),
+                has_trailing_slash,
+            };
+        }
+
+        // Check for WebDAV URL format (dav:// = HTTP, davs:// = HTTPS)
+        let webdav = s
+            .strip_prefix("davs://")
+            .map(|r| (r, true))
+            .or_else(|| s.strip_prefix("dav://").map(|r| (r, false)));
+        if let Some((remainder, secure)) = webdav {
+            // Split authority from path (no path = server root)
+            let (authority, path) = match remainder.find('/') {
+                Some(slash_pos) => (&remainder[..slash_pos], &remainder[slash_pos..]),
+                None => (remainder, "/"),
+            };
+
+            // Parse user@host or just host
+            let (user, host) = match authority.rsplit_once('@') {
+                Some((user, host)) => (Some(user.to_string()), host),
+                None => (None, authority),
+            };
+
+            return SyncPath::WebDav {
+                host: host.to_string(),
+                user,
+                path: PathBuf::from(path),
+                secure,
+                has_trailing_slash,
+            };
+        }
+
+        // Check for TCP daemon URL format
+        if let Some(remainder) = s.strip_prefix("sy://") {
+            // Split authority from path (no path = daemon's default root)
+            let (authority, path) = match remainder.find('/') {
+                Some(slash_pos) => (&remainder[..slash_pos], &remainder[slash_pos..]),
+                None => (remainder, "/"),
+            };
+
+            // sy://host/~/dir is relative to the daemon user's home
+            let path = match path.strip_prefix("/~/") {
+                Some(rest) => format!("~/{}", rest),
+                None => path.to_string(),
+            };
+
+            let (host, port) = match authority
+                .rsplit_once(':')
+                .and_then(|(host, port)| port.parse().ok().map(|port| (host, port)))
+            {
+                Some((host, port)) => (host, port),
+                None => (authority, crate::server::tcp::DEFAULT_PORT),
+            };
+
+            return SyncPath::TcpDaemon {
+                host: host.to_string(),
+                port,
+                path: PathBuf::from(path),
+                has_trailing_slash,
+            };
+        }
+
         // Check for daemon path format (daemon:/path)
         if let Some(remainder) = s.strip_prefix("daemon:") {
             return SyncPath::Daemon {
@@ -219,9 +375,13 @@ impl SyncPath {
         match self {
             SyncPath::Local { path, .. } => path,
             SyncPath::Remote { path, .. } => path,
+            SyncPath::Sftp { path, .. } => path,
             SyncPath::S3 { key, .. } => Path::new(key),
             SyncPath::Gcs { key, .. } => Path::new(key),
+            SyncPath::Azure { key, .. } => Path::new(key),
+            SyncPath::WebDav { path, .. } => path,
             SyncPath::Daemon { path, .. } => path,
+            SyncPath::TcpDaemon { path, .. } => path,
         }
     }
 
@@ -238,15 +398,27 @@ impl SyncPath {
             SyncPath::Remote {
                 has_trailing_slash, ..
             } => *has_trailing_slash,
+            SyncPath::Sftp {
+                has_trailing_slash, ..
+            } => *has_trailing_slash,
             SyncPath::S3 {
                 has_trailing_slash, ..
             } => *has_trailing_slash,
             SyncPath::Gcs {
                 has_trailing_slash, ..
             } => *has_trailing_slash,
+            SyncPath::Azure {
+                has_trailing_slash, ..
+            } => *has_trailing_slash,
+            SyncPath::WebDav {
+                has_trailing_slash, ..
+            } => *has_trailing_slash,
             SyncPath::Daemon {
                 has_trailing_slash, ..
             } => *has_trailing_slash,
+            SyncPath::TcpDaemon {
+                has_trailing_slash, ..
+            } => *has_trailing_slash,
         }
     }
 
@@ -261,6 +433,12 @@ impl SyncPath {
         matches!(self, SyncPath::Local { .. })
     }
 
+    /// Check if this is an SFTP-only path
+    #[allow(dead_code)] // Public API for SFTP path detection
+    pub fn is_sftp(&self) -> bool {
+        matches!(self, SyncPath::Sftp { .. })
+    }
+
     /// Check if this is an S3 path
     #[allow(dead_code)] // Public API for S3 path detection
     pub fn is_s3(&self) -> bool {
@@ -273,11 +451,29 @@ impl SyncPath {
         matches!(self, SyncPath::Gcs { .. })
     }
 
+    /// Check if this is an Azure Blob Storage path
+    #[allow(dead_code)] // Public API for Azure path detection
+    pub fn is_azure(&self) -> bool {
+        matches!(self, SyncPath::Azure { .. })
+    }
+
+    /// Check if this is a WebDAV path
+    #[allow(dead_code)] // Public API for WebDAV path detection
+    pub fn is_webdav(&self) -> bool {
+        matches!(self, SyncPath::WebDav { .. })
+    }
+
     /// Check if this is a daemon path (requires --use-daemon socket)
     #[allow(dead_code)] // Public API for daemon path detection
     pub fn is_daemon(&self) -> bool {
         matches!(self, SyncPath::Daemon { .. })
     }
+
+    /// Check if this is a TCP daemon path (sy://host/path)
+    #[allow(dead_code)] // Public API for TCP daemon path detection
+    pub fn is_tcp_daemon(&self) -> bool {
+        matches!(self, SyncPath::TcpDaemon { .. })
+    }
 }
 
 impl std::fmt::Display for SyncPath {
@@ -293,6 +489,26 @@ impl std::fmt::Display for SyncPath {
                     write!(f, "{}:{}", host, path.display())
                 }
             }
+            SyncPath::Sftp {
+                host,
+                user,
+                port,
+                path,
+                ..
+            } => {
+                write!(f, "sftp://")?;
+                if let Some(u) = user {
+                    write!(f, "{}@", u)?;
+                }
+                write!(f, "{}", host)?;
+                if let Some(p) = port {
+                    write!(f, ":{}", p)?;
+                }
+                if path.is_relative() {
+                    write!(f, "/~/")?;
+                }
+                write!(f, "{}", path.display())
+            }
             SyncPath::S3 {
                 bucket,
                 key,
@@ -333,7 +549,44 @@ impl std::fmt::Display for SyncPath {
                 }
                 Ok(())
             }
+            SyncPath::Azure {
+                container,
+                key,
+                account,
+                ..
+            } => {
+                write!(f, "az://{}/{}", container, key)?;
+                if let Some(a) = account {
+                    write!(f, "?account={}", a)?;
+                }
+                Ok(())
+            }
+            SyncPath::WebDav {
+                host,
+                user,
+                path,
+                secure,
+                ..
+            } => {
+                write!(f, "{}://", if *secure { "davs" } else { "dav" })?;
+                if let Some(u) = user {
+                    write!(f, "{}@", u)?;
+                }
+                write!(f, "{}{}", host, path.display())
+            }
             SyncPath::Daemon { path, .. } => write!(f, "daemon:{}", path.display()),
+            SyncPath::TcpDaemon {
+                host, port, path, ..
+            } => {
+                write!(f, "sy://{}", host)?;
+                if *port != crate::server::tcp::DEFAULT_PORT {
+                    write!(f, ":{}", port)?;
+                }
+                match path.to_str().and_then(|p| p.strip_prefix("~/")) {
+                    Some(rest) => write!(f, "/~/{}", rest),
+                    None => write!(f, "{}", path.display()),
+                }
+            }
         }
     }
 }
@@ -724,6 +977,196 @@ mod tests {
         );
     }
 
+    // =========================================================================
+    // Azure path tests
+    // =========================================================================
+
+    #[test]
+    fn test_parse_azure_path() {
+        let path = SyncPath::parse("az://my-container/backups/2024");
+        assert!(path.is_azure());
+        match path {
+            SyncPath::Azure {
+                container,
+                key,
+                account,
+                has_trailing_slash,
+            } => {
+                assert_eq!(container, "my-container");
+                assert_eq!(key, "backups/2024");
+                assert_eq!(account, None);
+                assert!(!has_trailing_slash);
+            }
+            _ => panic!("Expected Azure path"),
+        }
+    }
+
+    #[test]
+    fn test_parse_azure_with_account() {
+        let path = SyncPath::parse("az://my-container/backups/?account=mystorage");
+        match path {
+            SyncPath::Azure {
+                container,
+                key,
+                account,
+                has_trailing_slash,
+            } => {
+                assert_eq!(container, "my-container");
+                assert_eq!(key, "backups/");
+                assert_eq!(account, Some("mystorage".to_string()));
+                assert!(has_trailing_slash);
+            }
+            _ => panic!("Expected Azure path"),
+        }
+    }
+
+    #[test]
+    fn test_parse_azure_container_only() {
+        let path = SyncPath::parse("az://my-container?account=mystorage");
+        match path {
+            SyncPath::Azure {
+                container,
+                key,
+                account,
+                ..
+            } => {
+                assert_eq!(container, "my-container");
+                assert_eq!(key, "");
+                assert_eq!(account, Some("mystorage".to_string()));
+            }
+            _ => panic!("Expected Azure path"),
+        }
+    }
+
+    #[test]
+    fn test_display_azure() {
+        let path = SyncPath::parse("az://my-container/file.txt?account=mystorage");
+        assert_eq!(
+            path.to_string(),
+            "az://my-container/file.txt?account=mystorage"
+        );
+    }
+
+    // =========================================================================
+    // SFTP path tests
+    // =========================================================================
+
+    #[test]
+    fn test_parse_sftp_path() {
+        let path = SyncPath::parse("sftp://backup@nas.local/volume1/backups/");
+        match path {
+            SyncPath::Sftp {
+                host,
+                user,
+                port,
+                path,
+                has_trailing_slash,
+            } => {
+                assert_eq!(host, "nas.local");
+                assert_eq!(user, Some("backup".to_string()));
+                assert_eq!(port, None);
+                assert_eq!(path, PathBuf::from("/volume1/backups/"));
+                assert!(has_trailing_slash);
+            }
+            _ => panic!("Expected SFTP path"),
+        }
+    }
+
+    #[test]
+    fn test_parse_sftp_with_port_and_home() {
+        let path = SyncPath::parse("sftp://nas.local:2222/~/uploads");
+        match &path {
+            SyncPath::Sftp {
+                host,
+                user,
+                port,
+                path,
+                ..
+            } => {
+                assert_eq!(host, "nas.local");
+                assert_eq!(*user, None);
+                assert_eq!(*port, Some(2222));
+                assert_eq!(*path, PathBuf::from("uploads"));
+            }
+            _ => panic!("Expected SFTP path"),
+        }
+        assert!(path.is_sftp());
+        assert!(!path.is_remote());
+    }
+
+    #[test]
+    fn test_display_sftp() {
+        let path = SyncPath::parse("sftp://backup@nas.local:2222/srv/data");
+        assert_eq!(path.to_string(), "sftp://backup@nas.local:2222/srv/data");
+        let path = SyncPath::parse("sftp://nas.local/~/uploads");
+        assert_eq!(path.to_string(), "sftp://nas.local/~/uploads");
+    }
+
+    // =========================================================================
+    // WebDAV path tests
+    // =========================================================================
+
+    #[test]
+    fn test_parse_webdav_https_with_user() {
+        let path = SyncPath::parse("davs://alice@cloud.example.com/remote.php/dav/files/alice/");
+        match path {
+            SyncPath::WebDav {
+                host,
+                user,
+                path,
+                secure,
+                has_trailing_slash,
+            } => {
+                assert_eq!(host, "cloud.example.com");
+                assert_eq!(user, Some("alice".to_string()));
+                assert_eq!(path, PathBuf::from("/remote.php/dav/files/alice/"));
+                assert!(secure);
+                assert!(has_trailing_slash);
+            }
+            _ => panic!("Expected WebDAV path"),
+        }
+    }
+
+    #[test]
+    fn test_parse_webdav_http_with_port() {
+        let path = SyncPath::parse("dav://localhost:8080/artifacts");
+        match path {
+            SyncPath::WebDav {
+                host,
+                user,
+                path,
+                secure,
+                has_trailing_slash,
+            } => {
+                assert_eq!(host, "localhost:8080");
+                assert_eq!(user, None);
+                assert_eq!(path, PathBuf::from("/artifacts"));
+                assert!(!secure);
+                assert!(!has_trailing_slash);
+            }
+            _ => panic!("Expected WebDAV path"),
+        }
+        assert!(!SyncPath::parse("dav://localhost:8080/artifacts").is_remote());
+    }
+
+    #[test]
+    fn test_parse_webdav_host_only() {
+        let path = SyncPath::parse("davs://cloud.example.com");
+        assert!(path.is_webdav());
+        assert_eq!(path.path(), Path::new("/"));
+    }
+
+    #[test]
+    fn test_display_webdav() {
+        let path = SyncPath::parse("davs://alice@cloud.example.com/files/builds");
+        assert_eq!(
+            path.to_string(),
+            "davs://alice@cloud.example.com/files/builds"
+        );
+        let path = SyncPath::parse("dav://localhost:8080/dir");
+        assert_eq!(path.to_string(), "dav://localhost:8080/dir");
+    }
+
     // =========================================================================
     // Daemon path tests
     // =========================================================================
@@ -768,4 +1211,40 @@ mod tests {
         assert!(!path.is_s3());
         assert!(path.is_daemon());
     }
+
+    #[test]
+    fn test_parse_tcp_daemon_path() {
+        let path = SyncPath::parse("sy://backup.example.com/srv/data/");
+        assert!(path.is_tcp_daemon());
+        assert!(!path.is_daemon());
+        match &path {
+            SyncPath::TcpDaemon { host, port, .. } => {
+                assert_eq!(host, "backup.example.com");
+                assert_eq!(*port, 8730);
+            }
+            _ => panic!("Expected TcpDaemon path"),
+        }
+        assert_eq!(path.path(), Path::new("/srv/data/"));
+        assert!(path.has_trailing_slash());
+    }
+
+    #[test]
+    fn test_parse_tcp_daemon_path_with_port_and_home() {
+        let path = SyncPath::parse("sy://10.0.0.5:9000/~/backup");
+        match &path {
+            SyncPath::TcpDaemon { host, port, .. } => {
+                assert_eq!(host, "10.0.0.5");
+                assert_eq!(*port, 9000);
+            }
+            _ => panic!("Expected TcpDaemon path"),
+        }
+        assert_eq!(path.path(), Path::new("~/backup"));
+    }
+
+    #[test]
+    fn test_display_tcp_daemon_path() {
+        for url in ["sy://host/srv/data", "sy://host:9000/~/backup"] {
+            assert_eq!(SyncPath::parse(url).to_string(), url);
+        }
+    }
 }
@@ -0,0 +1,282 @@
+//! Keep server-side writes inside the session root
+//!
+//! Every path in the protocol comes from the peer, so the receiver treats it
+//! as hostile: it must be relative, must not contain `..`, and must not reach
+//! outside the root through a symlink that already exists on disk. On Linux
+//! files are opened with `openat2(RESOLVE_BENEATH)` so the kernel enforces
+//! this; elsewhere (or on kernels without openat2) the parent directories are
+//! canonicalized and the final component is opened with `O_NOFOLLOW`.
+
+use anyhow::{anyhow, Result};
+use std::fs::{File, OpenOptions};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Component, Path, PathBuf};
+
+/// Validate a protocol path: relative, no `..`, no NUL bytes
+///
+/// `.` components are dropped; the cleaned relative path is returned.
+pub fn check_relative(path: &str) -> Result<PathBuf> {
+    if path.is_empty() {
+        return Err(anyhow!("empty path"));
+    }
+    if path.contains('\0') {
+        return Err(anyhow!("path contains a NUL byte"));
+    }
+
+    let mut clean = PathBuf::new();
+    for component in Path::new(path).components() {
+        match component {
+            Component::Normal(part) => clean.push(part),
+            Component::CurDir => {}
+            Component::ParentDir => return Err(anyhow!("path escapes the root: {}", path)),
+            Component::RootDir | Component::Prefix(_) => {
+                return Err(anyhow!("absolute path not allowed: {}", path))
+            }
+        }
+    }
+
+    if clean.as_os_str().is_empty() {
+        return Err(anyhow!("path names the root itself: {}", path));
+    }
+    Ok(clean)
+}
+
+/// Check a symlink target against the `--safe-links` policy
+///
+/// A target is safe when it is relative and, resolved lexically from the
+/// directory holding the link, never climbs above the root.
+pub fn symlink_is_safe(link: &Path, target: &str) -> bool {
+    let target = Path::new(target);
+    if target.is_absolute() {
+        return false;
+    }
+
+    // Depth of the directory containing the link, relative to the root
+    let mut depth = link
+        .parent()
+        .map(|p| {
+            p.components()
+                .filter(|c| matches!(c, Component::Normal(_)))
+                .count()
+        })
+        .unwrap_or(0) as i64;
+
+    for component in target.components() {
+        match component {
+            Component::Normal(_) => depth += 1,
+            Component::CurDir => {}
+            Component::ParentDir => {
+                depth -= 1;
+                if depth < 0 {
+                    return false;
+                }
+            }
+            Component::RootDir | Component::Prefix(_) => return false,
+        }
+    }
+    true
+}
+
+/// Resolves and opens protocol paths without leaving `root`
+#[derive(Debug, Clone)]
+pub struct Confinement {
+    root: PathBuf,
+}
+
+impl Confinement {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Map a protocol path to an absolute path under the root
+    ///
+    /// Fails if the path is malformed or if an existing parent directory
+    /// (after following symlinks) lies outside the root. The final component
+    /// itself is not followed; callers open it with `O_NOFOLLOW` or replace it.
+    pub fn resolve(&self, path: &str) -> Result<PathBuf> {
+        let rel = check_relative(path)?;
+        let full = self.root.join(&rel);
+
+        // Missing root: nothing on disk can redirect us yet
+        let Ok(root) = self.root.canonicalize() else {
+            return Ok(full);
+        };
+
+        let mut ancestor = full.parent();
+        while let Some(dir) = ancestor {
+            if let Ok(real) = dir.canonicalize() {
+                if !real.starts_with(&root) {
+                    return Err(anyhow!("path escapes the root through a symlink: {}", path));
+                }
+                break;
+            }
+            ancestor = dir.parent();
+        }
+        Ok(full)
+    }
+
+    /// Open a file for writing (creating it if needed) beneath the root
+    ///
+    /// Never follows a symlink at the final component, and on Linux the
+    /// kernel also refuses any intermediate component that leaves the root.
+    pub fn create_file(&self, path: &str, truncate: bool) -> Result<File> {
+        let mut flags = libc::O_WRONLY | libc::O_CREAT;
+        if truncate {
+            flags |= libc::O_TRUNC;
+        }
+        self.open_with(path, flags)
+    }
+
+    /// Open an existing regular file for reading beneath the root
+    pub fn open_file(&self, path: &str) -> Result<File> {
+        self.open_with(path, libc::O_RDONLY)
+    }
+
+    fn open_with(&self, path: &str, flags: libc::c_int) -> Result<File> {
+        let full = self.resolve(path)?;
+
+        #[cfg(target_os = "linux")]
+        if let Some(file) = self.openat2(path, flags)? {
+            return Ok(file);
+        }
+
+        let mut options = OpenOptions::new();
+        options
+            .read(flags & libc::O_ACCMODE == libc::O_RDONLY)
+            .write(flags & libc::O_WRONLY != 0)
+            .create(flags & libc::O_CREAT != 0)
+            .truncate(flags & libc::O_TRUNC != 0)
+            .mode(0o666)
+            .custom_flags(libc::O_NOFOLLOW);
+        Ok(options.open(full)?)
+    }
+
+    /// Open via openat2(RESOLVE_BENEATH); None when the kernel lacks it
+    #[cfg(target_os = "linux")]
+    fn openat2(&self, path: &str, flags: libc::c_int) -> Result<Option<File>> {
+        use std::ffi::CString;
+        use std::os::unix::ffi::OsStrExt;
+        use std::os::unix::io::{FromRawFd, OwnedFd};
+
+        let rel = check_relative(path)?;
+        let root = CString::new(self.root.as_os_str().as_bytes())?;
+        let rel = CString::new(rel.as_os_str().as_bytes())?;
+
+        // SAFETY: root is a valid NUL-terminated string
+        let dirfd = unsafe {
+            libc::open(
+                root.as_ptr(),
+                libc::O_PATH | libc::O_DIRECTORY | libc::O_CLOEXEC,
+            )
+        };
+        if dirfd < 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        // SAFETY: dirfd was just opened and is owned here
+        let dirfd = unsafe { OwnedFd::from_raw_fd(dirfd) };
+
+        // SAFETY: open_how is plain data; all-zero is a valid value
+        let mut how: libc::open_how = unsafe { std::mem::zeroed() };
+        how.flags = (flags | libc::O_NOFOLLOW | libc::O_CLOEXEC) as u64;
+        // The kernel rejects a mode without O_CREAT/O_TMPFILE
+        how.mode = if flags & libc::O_CREAT != 0 { 0o666 } else { 0 };
+        how.resolve = libc::RESOLVE_BENEATH | libc::RESOLVE_NO_MAGICLINKS;
+        // SAFETY: all pointers are valid for the duration of the call
+        let fd = unsafe {
+            use std::os::unix::io::AsRawFd;
+            libc::syscall(
+                libc::SYS_openat2,
+                dirfd.as_raw_fd(),
+                rel.as_ptr(),
+                &how as *const libc::open_how,
+                std::mem::size_of::<libc::open_how>(),
+            )
+        };
+        if fd < 0 {
+            let err = std::io::Error::last_os_error();
+            return match err.raw_os_error() {
+                // Old kernel or seccomp filter: fall back to the portable path
+                Some(libc::ENOSYS) | Some(libc::EPERM) => Ok(None),
+                Some(libc::EXDEV) => Err(anyhow!("path escapes the root: {}", path)),
+                _ => Err(err.into()),
+            };
+        }
+        // SAFETY: openat2 returned a new descriptor that we now own
+        Ok(Some(unsafe { File::from_raw_fd(fd as libc::c_int) }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_check_relative_rejects_escapes() {
+        assert!(check_relative("../etc/passwd").is_err());
+        assert!(check_relative("a/../../b").is_err());
+        assert!(check_relative("a/..").is_err());
+        assert!(check_relative("/etc/passwd").is_err());
+        assert!(check_relative("").is_err());
+        assert!(check_relative(".").is_err());
+        assert!(check_relative("a\0b").is_err());
+        assert_eq!(check_relative("./a/./b").unwrap(), PathBuf::from("a/b"));
+        assert_eq!(check_relative("a..b/c").unwrap(), PathBuf::from("a..b/c"));
+    }
+
+    #[test]
+    fn test_symlink_is_safe() {
+        assert!(symlink_is_safe(Path::new("link"), "target"));
+        assert!(symlink_is_safe(Path::new("a/b/link"), "../../c"));
+        assert!(symlink_is_safe(Path::new("a/link"), "./x/../y"));
+        assert!(!symlink_is_safe(Path::new("link"), "../outside"));
+        assert!(!symlink_is_safe(Path::new("a/link"), "x/../../../y"));
+        assert!(!symlink_is_safe(Path::new("a/link"), "/etc/passwd"));
+    }
+
+    #[test]
+    fn test_create_file_refuses_symlinked_parent() {
+        let root = TempDir::new().unwrap();
+        let outside = TempDir::new().unwrap();
+        std::os::unix::fs::symlink(outside.path(), root.path().join("escape")).unwrap();
+
+        let confine = Confinement::new(root.path());
+        assert!(confine.create_file("escape/owned", true).is_err());
+        assert!(!outside.path().join("owned").exists());
+
+        assert!(confine.create_file("inside", true).is_ok());
+        assert!(root.path().join("inside").exists());
+    }
+
+    #[test]
+    fn test_create_file_refuses_symlinked_final_component() {
+        let root = TempDir::new().unwrap();
+        let outside = TempDir::new().unwrap();
+        let victim = outside.path().join("victim");
+        std::fs::write(&victim, b"original").unwrap();
+        std::os::unix::fs::symlink(&victim, root.path().join("file")).unwrap();
+
+        let confine = Confinement::new(root.path());
+        assert!(confine.create_file("file", true).is_err());
+        assert!(confine.open_file("file").is_err());
+        assert_eq!(std::fs::read(&victim).unwrap(), b"original");
+    }
+
+    #[test]
+    fn test_open_file_reads_regular_file() {
+        use std::io::Read;
+
+        let root = TempDir::new().unwrap();
+        std::fs::create_dir(root.path().join("dir")).unwrap();
+        std::fs::write(root.path().join("dir/file"), b"basis").unwrap();
+
+        let confine = Confinement::new(root.path());
+        let mut data = Vec::new();
+        confine
+            .open_file("dir/file")
+            .unwrap()
+            .read_to_end(&mut data)
+            .unwrap();
+        assert_eq!(data, b"basis");
+    }
+}
@@ -20,10 +20,27 @@
 //! sy --use-daemon /tmp/sy-local.sock /local/path daemon:/remote/path
 //! ```
 //!
+//! ## Or listen on TCP (token auth, optional TLS):
+//! ```bash
+//! sy --daemon --listen 0.0.0.0:8730 --auth-tokens /etc/sy/tokens \
+//!     --tls-cert /etc/sy/cert.pem --tls-key /etc/sy/key.pem /srv/sync
+//!
+//! SY_DAEMON_TOKEN=ci:secret sy --tls-ca /etc/sy/ca.pem /local/path sy://host:8730/remote/path
+//! ```
+//!
+//! ## Or serve named modules only:
+//! ```bash
+//! sy --daemon --daemon-config /etc/sy/daemon.toml --socket ~/.sy/daemon.sock
+//! sy --use-daemon /tmp/sy.sock /local/path daemon:backups/host1
+//! ```
+//! See [`super::modules`] for the config format.
+//!
 //! # Protocol
 //!
-//! The daemon uses the same protocol as `sy --server`, with one addition:
+//! The daemon uses the same protocol as `sy --server`, with two additions:
+//! - TCP connections answer a token challenge after HELLO (see [`super::tcp`])
 //! - After HELLO, client sends SET_ROOT to specify the working directory
+//!   (`module/sub/path` when modules are configured)
 //! - Paths in subsequent messages are relative to this root
 
 #![cfg(unix)]
@@ -33,25 +50,29 @@ use std::sync::Arc;
 
 use anyhow::{Context, Result};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::{UnixListener, UnixStream};
+use tokio::net::{TcpListener, UnixListener};
 use tokio::sync::mpsc;
-use tracing::{error, info, warn};
+use tracing::{debug, error, info, warn};
 
 use super::handler::{compute_checksum_response, ServerHandler};
+use super::meta::{self, MetaOptions};
+use super::modules::{user_name, ModuleFilter, ModuleTable};
+use super::partial;
 use super::protocol::{
-    ChecksumReq, ChecksumResp, DeltaData, ErrorMessage, Hello, MessageType, MkdirBatch,
-    SymlinkBatch, PROTOCOL_VERSION,
+    ChecksumReq, ChecksumResp, DeltaData, ErrorMessage, Frame, Hello, MessageType, MkdirBatch,
+    Negotiated, ProtocolError, SymlinkBatch, CAP_FILE_CHECKSUM, CAP_RESUME, CAP_STREAM_ZSTD,
 };
+use super::stream::{StreamReader, StreamWriter};
+use super::tcp::{authenticate_client, TcpListenConfig, TokenStore};
 use crate::sync::scanner::{self, ScanOptions};
 
 /// Message types specific to daemon mode
 pub const MSG_SET_ROOT: u8 = 0x30;
 pub const MSG_SET_ROOT_ACK: u8 = 0x31;
-pub const MSG_PING: u8 = 0x32;
-pub const MSG_PONG: u8 = 0x33;
+pub use super::protocol::{MSG_PING, MSG_PONG};
 
 /// Expand tilde (~) in paths to the user's home directory.
-fn expand_tilde(path: &Path) -> PathBuf {
+pub(super) fn expand_tilde(path: &Path) -> PathBuf {
     let path_str = path.to_string_lossy();
 
     if path_str == "~" {
@@ -72,14 +93,61 @@ fn expand_socket_path(socket_path: &str) -> PathBuf {
     expand_tilde(Path::new(socket_path))
 }
 
+/// How a client's SET_ROOT path is turned into a directory
+#[derive(Clone)]
+enum Roots {
+    /// Any path the client names (the default root is only logged)
+    Open { root: PathBuf, safe_links: bool },
+    /// Only `module/sub/path` inside a configured module
+    Modules(Arc<ModuleTable>),
+}
+
+impl Roots {
+    fn log(&self) {
+        match self {
+            Roots::Open { root, .. } => info!("Default root: {}", root.display()),
+            Roots::Modules(modules) => {
+                for (name, root, comment) in modules.describe() {
+                    info!(
+                        "Module '{}': {}{}",
+                        name,
+                        root.display(),
+                        comment.map(|c| format!(" ({})", c)).unwrap_or_default()
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Directory a client session works in, with any module restrictions
+struct SessionRoot {
+    path: PathBuf,
+    filter: Option<ModuleFilter>,
+    owner: (Option<u32>, Option<u32>),
+    safe_links: bool,
+    /// Keeps the module's connection slot for the life of the session
+    _module: Option<super::modules::ModuleSession>,
+}
+
 /// Run the daemon server
 ///
 /// # Arguments
 /// * `socket_path` - Path to the Unix socket (supports ~ expansion)
 /// * `default_root` - Default root path for file operations
-pub async fn run_daemon(socket_path: &str, default_root: &Path) -> Result<()> {
+/// * `safe_links` - Refuse incoming symlinks that point outside the session root
+pub async fn run_daemon(socket_path: &str, default_root: &Path, safe_links: bool) -> Result<()> {
+    let root = expand_tilde(default_root);
+    serve_unix(socket_path, Roots::Open { root, safe_links }).await
+}
+
+/// Run the daemon server, serving only the given modules
+pub async fn run_daemon_with_modules(socket_path: &str, modules: ModuleTable) -> Result<()> {
+    serve_unix(socket_path, Roots::Modules(Arc::new(modules))).await
+}
+
+async fn serve_unix(socket_path: &str, roots: Roots) -> Result<()> {
     let socket_path = expand_socket_path(socket_path);
-    let default_root = expand_tilde(default_root);
 
     // Ensure socket directory exists
     if let Some(parent) = socket_path.parent() {
@@ -105,29 +173,9 @@ pub async fn run_daemon(socket_path: &str, default_root: &Path) -> Result<()> {
         .with_context(|| format!("Failed to bind socket: {}", socket_path.display()))?;
 
     info!("Daemon listening on {}", socket_path.display());
-    info!("Default root: {}", default_root.display());
+    roots.log();
 
-    // Set up signal handler for graceful shutdown
-    let (shutdown_tx, mut shutdown_rx) = mpsc::channel::<()>(1);
-
-    // Handle SIGTERM and SIGINT
-    let shutdown_tx_clone = shutdown_tx.clone();
-    tokio::spawn(async move {
-        let mut sigterm = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
-            .expect("Failed to register SIGTERM handler");
-        let mut sigint = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::interrupt())
-            .expect("Failed to register SIGINT handler");
-
-        tokio::select! {
-            _ = sigterm.recv() => {
-                info!("Received SIGTERM, shutting down...");
-            }
-            _ = sigint.recv() => {
-                info!("Received SIGINT, shutting down...");
-            }
-        }
-        let _ = shutdown_tx_clone.send(()).await;
-    });
+    let mut shutdown_rx = shutdown_signal();
 
     // Track active connections
     let active_connections = Arc::new(tokio::sync::Semaphore::new(100)); // Max 100 concurrent connections
@@ -145,9 +193,14 @@ pub async fn run_daemon(socket_path: &str, default_root: &Path) -> Result<()> {
                             }
                         };
 
-                        let default_root = default_root.clone();
+                        // Module access rules name local users
+                        let peer_user = stream.peer_cred().ok().map(|cred| {
+                            user_name(cred.uid()).unwrap_or_else(|| cred.uid().to_string())
+                        });
+                        let roots = roots.clone();
                         tokio::spawn(async move {
-                            if let Err(e) = handle_client(stream, default_root).await {
+                            let (reader, writer) = stream.into_split();
+                            if let Err(e) = handle_client(reader, writer, roots, None, peer_user, None).await {
                                 // Don't log EOF as error - it's normal when client disconnects
                                 if !e.to_string().contains("unexpected eof") {
                                     error!("Client error: {}", e);
@@ -175,66 +228,295 @@ pub async fn run_daemon(socket_path: &str, default_root: &Path) -> Result<()> {
     Ok(())
 }
 
-/// Handle a single client connection
-async fn handle_client(stream: UnixStream, default_root: PathBuf) -> Result<()> {
-    let (mut reader, mut writer) = stream.into_split();
+/// Run the daemon on a TCP port
+///
+/// Every connection must pass token authentication before it can set a
+/// root; with a certificate and key configured the stream is TLS-encrypted.
+pub async fn run_tcp_daemon(
+    config: &TcpListenConfig,
+    default_root: &Path,
+    safe_links: bool,
+) -> Result<()> {
+    let root = expand_tilde(default_root);
+    serve_tcp(config, Roots::Open { root, safe_links }).await
+}
 
-    // Handshake
-    let _len = reader.read_u32().await?;
-    let type_byte = reader.read_u8().await?;
+/// Run the daemon on a TCP port, serving only the given modules
+pub async fn run_tcp_daemon_with_modules(
+    config: &TcpListenConfig,
+    modules: ModuleTable,
+) -> Result<()> {
+    serve_tcp(config, Roots::Modules(Arc::new(modules))).await
+}
 
-    if type_byte != MessageType::Hello as u8 {
-        let err = ErrorMessage {
-            code: 1,
-            message: format!("Expected HELLO (0x01), got 0x{:02X}", type_byte),
-        };
-        err.write(&mut writer).await?;
-        return Ok(());
+async fn serve_tcp(config: &TcpListenConfig, roots: Roots) -> Result<()> {
+    let tokens = Arc::new(TokenStore::load(&config.auth_tokens)?);
+
+    #[cfg(feature = "tls")]
+    let tls = match (&config.tls_cert, &config.tls_key) {
+        (Some(cert), Some(key)) => Some(super::tcp::tls_acceptor(cert, key)?),
+        (None, None) => None,
+        _ => anyhow::bail!("--tls-cert and --tls-key must be given together"),
+    };
+    #[cfg(not(feature = "tls"))]
+    if config.tls_cert.is_some() || config.tls_key.is_some() {
+        anyhow::bail!("TLS support not enabled. Reinstall with: cargo install sy --features tls");
     }
 
-    let hello = Hello::read(&mut reader).await?;
+    let listener = TcpListener::bind(&config.listen)
+        .await
+        .with_context(|| format!("Failed to bind {}", config.listen))?;
+
+    #[cfg(feature = "tls")]
+    let encrypted = tls.is_some();
+    #[cfg(not(feature = "tls"))]
+    let encrypted = false;
+
+    info!(
+        "Daemon listening on {} ({}, {} token(s))",
+        listener.local_addr()?,
+        if encrypted { "TLS" } else { "plaintext" },
+        tokens.len()
+    );
+    if !encrypted {
+        warn!("TCP listener without TLS: file data is not encrypted");
+    }
+    roots.log();
 
-    if hello.version != PROTOCOL_VERSION {
-        let err = ErrorMessage {
-            code: 1,
-            message: format!(
-                "Version mismatch: client {}, server {}",
-                hello.version, PROTOCOL_VERSION
-            ),
-        };
-        err.write(&mut writer).await?;
-        return Ok(());
+    let mut shutdown_rx = shutdown_signal();
+    let active_connections = Arc::new(tokio::sync::Semaphore::new(100));
+
+    loop {
+        tokio::select! {
+            accept_result = listener.accept() => {
+                let (stream, addr) = match accept_result {
+                    Ok(accepted) => accepted,
+                    Err(e) => {
+                        error!("Accept error: {}", e);
+                        continue;
+                    }
+                };
+                let permit = match active_connections.clone().try_acquire_owned() {
+                    Ok(p) => p,
+                    Err(_) => {
+                        warn!("Max connections reached, rejecting {}", addr);
+                        continue;
+                    }
+                };
+                let _ = stream.set_nodelay(true);
+
+                let roots = roots.clone();
+                let tokens = Arc::clone(&tokens);
+                #[cfg(feature = "tls")]
+                let tls = tls.clone();
+                let deadline = tokio::time::Instant::now() + config.handshake_timeout;
+                tokio::spawn(async move {
+                    #[cfg(feature = "tls")]
+                    let result = match tls {
+                        Some(acceptor) => match tokio::time::timeout_at(
+                            deadline,
+                            acceptor.accept(stream),
+                        )
+                        .await
+                        {
+                            Err(_) => Err(anyhow::anyhow!("TLS handshake timed out")),
+                            Ok(Ok(stream)) => {
+                                let (reader, writer) = tokio::io::split(stream);
+                                handle_client(reader, writer, roots, Some(tokens), None, Some(deadline)).await
+                            }
+                            Ok(Err(e)) => Err(anyhow::anyhow!("TLS handshake failed: {}", e)),
+                        },
+                        None => {
+                            let (reader, writer) = stream.into_split();
+                            handle_client(reader, writer, roots, Some(tokens), None, Some(deadline)).await
+                        }
+                    };
+                    #[cfg(not(feature = "tls"))]
+                    let result = {
+                        let (reader, writer) = stream.into_split();
+                        handle_client(reader, writer, roots, Some(tokens), None, Some(deadline)).await
+                    };
+
+                    if let Err(e) = result {
+                        if !e.to_string().contains("unexpected eof") {
+                            error!("Client {} error: {}", addr, e);
+                        }
+                    }
+                    drop(permit);
+                });
+            }
+            _ = shutdown_rx.recv() => {
+                info!("Shutting down daemon...");
+                break;
+            }
+        }
     }
 
-    // Send HELLO response
-    let resp = Hello {
-        version: PROTOCOL_VERSION,
-        flags: 0,
-        capabilities: vec![],
-    };
-    resp.write(&mut writer).await?;
-    writer.flush().await?;
+    info!("Daemon stopped");
+    Ok(())
+}
+
+/// Receiver that fires once on SIGTERM or SIGINT
+fn shutdown_signal() -> mpsc::Receiver<()> {
+    let (shutdown_tx, shutdown_rx) = mpsc::channel::<()>(1);
+
+    tokio::spawn(async move {
+        let mut sigterm = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to register SIGTERM handler");
+        let mut sigint = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::interrupt())
+            .expect("Failed to register SIGINT handler");
 
-    // Wait for SET_ROOT or use default
-    let root_path = match read_set_root(&mut reader, &mut writer, &default_root).await? {
-        Some(path) => path,
-        None => default_root,
+        tokio::select! {
+            _ = sigterm.recv() => {
+                info!("Received SIGTERM, shutting down...");
+            }
+            _ = sigint.recv() => {
+                info!("Received SIGINT, shutting down...");
+            }
+        }
+        let _ = shutdown_tx.send(()).await;
+    });
+
+    shutdown_rx
+}
+
+/// Handle a single client connection
+///
+/// `tokens` is set for TCP connections, which must authenticate after HELLO;
+/// `peer_user` is the local user behind a Unix socket connection.
+async fn handle_client<R, W>(
+    reader: R,
+    writer: W,
+    roots: Roots,
+    tokens: Option<Arc<TokenStore>>,
+    mut peer_user: Option<String>,
+    handshake_deadline: Option<tokio::time::Instant>,
+) -> Result<()>
+where
+    R: AsyncReadExt + Unpin,
+    W: AsyncWriteExt + Unpin,
+{
+    let mut reader = StreamReader::new(reader);
+    let mut writer = StreamWriter::new(writer);
+
+    // Everything up to SET_ROOT runs before the client is known; on TCP
+    // listeners it must finish by the deadline, so idle sockets can't hold
+    // connection slots
+    let handshake = async {
+        // Handshake
+        let frame = Frame::read(&mut reader).await?;
+        let type_byte = frame.msg_type;
+
+        if type_byte != MessageType::Hello as u8 {
+            let err = ErrorMessage {
+                code: 1,
+                message: format!("Expected HELLO (0x01), got 0x{:02X}", type_byte),
+            };
+            err.write(&mut writer).await?;
+            return Ok(None);
+        }
+
+        let hello = Hello::read_frame(&mut reader, &frame).await?;
+
+        let negotiated = match hello.negotiate() {
+            Ok(negotiated) => negotiated,
+            Err(e) => {
+                let err = ErrorMessage {
+                    code: 1,
+                    message: format!("Version mismatch: {}", e),
+                };
+                err.write(&mut writer).await?;
+                return Ok(None);
+            }
+        };
+        debug!(
+            "Negotiated protocol v{} (features {:#x})",
+            negotiated.version, negotiated.features
+        );
+
+        // Send HELLO response
+        Hello::new(0).write(&mut writer).await?;
+        writer.flush().await?;
+
+        // TCP clients must hold a token before touching the filesystem
+        if let Some(tokens) = tokens {
+            match authenticate_client(&mut reader, &mut writer, &tokens).await? {
+                Some(name) => {
+                    info!("Client authenticated as '{}'", name);
+                    peer_user = Some(name);
+                }
+                None => {
+                    warn!("Client failed token authentication");
+                    return Ok(None);
+                }
+            }
+        }
+
+        // Wait for SET_ROOT and resolve it
+        let pull = hello.flags & super::protocol::HELLO_FLAG_PULL != 0;
+        let requested = read_set_root(&mut reader, &mut writer, &roots).await?;
+        let session_root = match resolve_root(&roots, &requested, peer_user.as_deref(), pull).await
+        {
+            Ok(root) => root,
+            Err(message) => {
+                warn!("Rejected root '{}': {}", requested, message);
+                let err = ErrorMessage { code: 2, message };
+                err.write(&mut writer).await?;
+                writer.flush().await?;
+                return Ok(None);
+            }
+        };
+        write_set_root_ack(&mut writer).await?;
+        Ok::<_, anyhow::Error>(Some((hello, negotiated, session_root)))
+    };
+    let handshake = match handshake_deadline {
+        Some(deadline) => tokio::time::timeout_at(deadline, handshake)
+            .await
+            .map_err(|_| anyhow::anyhow!("Client did not finish the handshake in time"))??,
+        None => handshake.await?,
+    };
+    let Some((hello, negotiated, session_root)) = handshake else {
+        return Ok(());
     };
+    let pull = hello.flags & super::protocol::HELLO_FLAG_PULL != 0;
+    let root_path = session_root.path.clone();
 
-    // Ensure root directory exists
-    if !root_path.exists() {
-        tokio::fs::create_dir_all(&root_path).await?;
+    // Everything after HELLO, auth and SET_ROOT may travel as one zstd stream
+    if negotiated.has(CAP_STREAM_ZSTD) {
+        reader.start_zstd()?;
+        writer.start_zstd()?;
     }
 
     info!("Client connected with root: {}", root_path.display());
 
     // Check if client requested PULL mode
-    if hello.flags & super::protocol::HELLO_FLAG_PULL != 0 {
-        return run_daemon_pull_mode(&root_path, &mut reader, &mut writer).await;
+    if pull {
+        return run_daemon_pull_mode(
+            &root_path,
+            session_root.filter.as_ref(),
+            negotiated,
+            &mut reader,
+            &mut writer,
+        )
+        .await;
     }
 
     // Handle messages using the standard handler
-    let mut handler = ServerHandler::new(root_path.clone());
+    // Clients don't choose ownership here; modules set it with uid/gid
+    let (uid, gid) = session_root.owner;
+    let mut handler = ServerHandler::new(root_path.clone())
+        .with_owner(uid, gid)
+        .with_meta(MetaOptions {
+            owner: false,
+            group: false,
+            ..MetaOptions::all()
+        })
+        .with_safe_links(session_root.safe_links)
+        .with_resume(negotiated.has(CAP_RESUME))
+        .with_checksums(negotiated.has(CAP_FILE_CHECKSUM));
+    if let Some(filter) = session_root.filter.clone() {
+        handler = handler.with_filter(filter);
+    }
 
     // Shared state for concurrent CHECKSUM_REQ handling
     let mut file_list: Option<Arc<Vec<super::protocol::FileListEntry>>> = None;
@@ -261,15 +543,17 @@ async fn handle_client(stream: UnixStream, default_root: PathBuf) -> Result<()>
 
             // Read and handle incoming messages
             len_result = reader.read_u32() => {
-                let _len = match len_result {
+                let len = match len_result {
                     Ok(len) => len,
                     Err(_) => break, // EOF or error, exit loop
                 };
-                let type_byte = reader.read_u8().await?;
+                let frame = Frame { len, msg_type: reader.read_u8().await? };
+                let type_byte = frame.msg_type;
 
                 match type_byte {
                     // PING - keepalive
                     b if b == MSG_PING => {
+                        frame.expect_empty()?;
                         writer.write_u32(0).await?;
                         writer.write_u8(MSG_PONG).await?;
                         writer.flush().await?;
@@ -278,42 +562,42 @@ async fn handle_client(stream: UnixStream, default_root: PathBuf) -> Result<()>
                     // Standard protocol messages
                     b if b == MessageType::FileList as u8 => {
                         drain_pending_checksums(&mut checksum_rx, &mut pending_checksum_count, &mut writer).await?;
-                        let list = super::protocol::FileList::read(&mut reader).await?;
+                        let list = super::protocol::FileList::read_frame(&mut reader, &frame).await?;
                         file_list = Some(Arc::new(list.entries.clone()));
                         handler.handle_file_list(list, &mut writer).await?;
                     }
 
                     b if b == MessageType::MkdirBatch as u8 => {
                         drain_pending_checksums(&mut checksum_rx, &mut pending_checksum_count, &mut writer).await?;
-                        let batch = MkdirBatch::read(&mut reader).await?;
+                        let batch = MkdirBatch::read_frame(&mut reader, &frame).await?;
                         handler.handle_mkdir_batch(batch, &mut writer).await?;
                     }
 
                     b if b == MessageType::SymlinkBatch as u8 => {
                         drain_pending_checksums(&mut checksum_rx, &mut pending_checksum_count, &mut writer).await?;
-                        let batch = SymlinkBatch::read(&mut reader).await?;
+                        let batch = SymlinkBatch::read_frame(&mut reader, &frame).await?;
                         handler.handle_symlink_batch(batch, &mut writer).await?;
                     }
 
                     b if b == MessageType::FileData as u8 => {
                         drain_pending_checksums(&mut checksum_rx, &mut pending_checksum_count, &mut writer).await?;
-                        let data = super::protocol::FileData::read(&mut reader).await?;
+                        let data = super::protocol::FileData::read_frame(&mut reader, &frame).await?;
                         handler.handle_file_data(data, &mut writer).await?;
                     }
 
                     b if b == MessageType::ChecksumReq as u8 => {
-                        let req = ChecksumReq::read(&mut reader).await?;
+                        let req = ChecksumReq::read_frame(&mut reader, &frame).await?;
 
                         if let Some(ref fl) = file_list {
                             let fl = Arc::clone(fl);
                             let rp = Arc::clone(&root_path);
-                            let index = req.index;
-                            let block_size = req.block_size as usize;
+                            let cache = handler.chunk_cache(&req);
+                            let basis = handler.fuzzy_basis(req.index);
                             let tx = checksum_tx.clone();
 
                             pending_checksum_count += 1;
                             tokio::spawn(async move {
-                                match compute_checksum_response(index, block_size, &fl, &rp).await {
+                                match compute_checksum_response(req, &fl, &rp, basis, cache).await {
                                     Ok(resp) => {
                                         let _ = tx.send(resp).await;
                                     }
@@ -329,12 +613,12 @@ async fn handle_client(stream: UnixStream, default_root: PathBuf) -> Result<()>
 
                     b if b == MessageType::DeltaData as u8 => {
                         drain_pending_checksums(&mut checksum_rx, &mut pending_checksum_count, &mut writer).await?;
-                        let delta = DeltaData::read(&mut reader).await?;
+                        let delta = DeltaData::read_frame(&mut reader, &frame).await?;
                         handler.handle_delta_data(delta, &mut writer).await?;
                     }
 
                     b if b == MessageType::Error as u8 => {
-                        let err = super::protocol::ErrorMessage::read(&mut reader).await?;
+                        let err = super::protocol::ErrorMessage::read_frame(&mut reader, &frame).await?;
                         error!("Received error from client: {}", err.message);
                         break;
                     }
@@ -362,63 +646,43 @@ async fn handle_client(stream: UnixStream, default_root: PathBuf) -> Result<()>
     Ok(())
 }
 
-/// Read SET_ROOT message if present, or return None to use default
-async fn read_set_root<R, W>(
-    reader: &mut R,
-    writer: &mut W,
-    default_root: &Path,
-) -> Result<Option<PathBuf>>
+/// Read the SET_ROOT message that must follow HELLO, returning the requested path
+async fn read_set_root<R, W>(reader: &mut R, writer: &mut W, roots: &Roots) -> Result<String>
 where
     R: AsyncReadExt + Unpin,
     W: AsyncWriteExt + Unpin,
 {
-    // Peek at next message - if it's SET_ROOT, process it
-    let _len = reader.read_u32().await?;
-    let type_byte = reader.read_u8().await?;
+    let frame = Frame::read(reader).await?;
+    let type_byte = frame.msg_type;
 
     if type_byte == MSG_SET_ROOT {
         // Read path length and path
-        let path_len = reader.read_u16().await? as usize;
-        let mut path_buf = vec![0u8; path_len];
-        reader.read_exact(&mut path_buf).await?;
-        let path_str =
-            String::from_utf8(path_buf).with_context(|| "Invalid UTF-8 in SET_ROOT path")?;
-
-        let root_path = expand_tilde(Path::new(&path_str));
-
-        // Validate path exists or can be created
-        let success = root_path.exists() || tokio::fs::create_dir_all(&root_path).await.is_ok();
-
-        // Send SET_ROOT_ACK
-        writer.write_u32(1).await?;
-        writer.write_u8(MSG_SET_ROOT_ACK).await?;
-        writer.write_u8(if success { 0 } else { 1 }).await?;
-        writer.flush().await?;
-
-        if success {
-            Ok(Some(root_path))
-        } else {
-            Err(anyhow::anyhow!("Failed to access root path: {}", path_str))
+        let mut payload = frame.payload(reader, 2 + u16::MAX as u64)?;
+        let path = async {
+            let path_len = payload.read_u16().await? as usize;
+            let mut path_buf = vec![0u8; path_len];
+            payload.read_exact(&mut path_buf).await?;
+            Ok::<_, ProtocolError>(path_buf)
         }
+        .await;
+        let path_buf = frame.finish(&payload, path)?;
+        String::from_utf8(path_buf).with_context(|| "Invalid UTF-8 in SET_ROOT path")
     } else {
-        // Not SET_ROOT - this is unexpected in daemon mode
-        // Client should send SET_ROOT first, but we'll continue with default
-        // Push back the message type for normal processing
-        // Actually, we've already consumed the length and type, so we need to handle this differently
-        // For now, send an error asking for SET_ROOT
+        // The message has already been consumed, so the session can't continue
+        let expected = match roots {
+            Roots::Open { root, .. } => format!("default root: {}", root.display()),
+            Roots::Modules(_) => "this daemon serves named modules".to_string(),
+        };
         let err = ErrorMessage {
             code: 2,
             message: format!(
-                "Expected SET_ROOT (0x30) first, got 0x{:02X}. Using default root: {}",
-                type_byte,
-                default_root.display()
+                "Expected SET_ROOT (0x30) first, got 0x{:02X} ({})",
+                type_byte, expected
             ),
         };
         err.write(writer).await?;
         writer.flush().await?;
 
-        // Return None to indicate we couldn't read SET_ROOT properly
-        // The caller will need to handle the already-read message
         Err(anyhow::anyhow!(
             "Client didn't send SET_ROOT, got 0x{:02X}. Protocol requires SET_ROOT after HELLO in daemon mode.",
             type_byte
@@ -426,6 +690,74 @@ where
     }
 }
 
+/// Turn a SET_ROOT request into the session's directory
+///
+/// The error string is sent to the client.
+async fn resolve_root(
+    roots: &Roots,
+    requested: &str,
+    user: Option<&str>,
+    pull: bool,
+) -> std::result::Result<SessionRoot, String> {
+    let session = match roots {
+        Roots::Open { safe_links, .. } => SessionRoot {
+            path: expand_tilde(Path::new(requested)),
+            filter: None,
+            owner: (None, None),
+            safe_links: *safe_links,
+            _module: None,
+        },
+        Roots::Modules(modules) => {
+            let module = modules.resolve(requested, user, pull)?;
+            info!(
+                "Client{} using module '{}'",
+                user.map(|u| format!(" '{}'", u)).unwrap_or_default(),
+                module.module
+            );
+            SessionRoot {
+                path: module.root.clone(),
+                filter: module.filter.clone(),
+                owner: (module.uid, module.gid),
+                safe_links: module.safe_links,
+                _module: Some(module),
+            }
+        }
+    };
+
+    // Validate path exists or can be created
+    if !session.path.exists() {
+        let created: Vec<PathBuf> = session
+            .path
+            .ancestors()
+            .take_while(|dir| !dir.exists())
+            .map(Path::to_path_buf)
+            .collect();
+        tokio::fs::create_dir_all(&session.path)
+            .await
+            .map_err(|e| format!("Failed to access root path {}: {}", requested, e))?;
+
+        // Directories created for a module session belong to its owner too
+        let (uid, gid) = session.owner;
+        if uid.is_some() || gid.is_some() {
+            for dir in &created {
+                if let Err(e) = std::os::unix::fs::lchown(dir, uid, gid) {
+                    warn!("Failed to chown {}: {}", dir.display(), e);
+                }
+            }
+        }
+    }
+    Ok(session)
+}
+
+/// Write a successful SET_ROOT_ACK (rejections are sent as ERROR messages)
+async fn write_set_root_ack<W: AsyncWriteExt + Unpin>(writer: &mut W) -> Result<()> {
+    writer.write_u32(1).await?;
+    writer.write_u8(MSG_SET_ROOT_ACK).await?;
+    writer.write_u8(0).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
 /// Drain all pending checksum responses from the channel
 async fn drain_pending_checksums<W: AsyncWriteExt + Unpin>(
     rx: &mut mpsc::Receiver<ChecksumResp>,
@@ -450,15 +782,25 @@ async fn drain_pending_checksums<W: AsyncWriteExt + Unpin>(
 }
 
 /// PULL mode for daemon: Server scans source and sends files to client
-async fn run_daemon_pull_mode<R, W>(root_path: &Path, reader: &mut R, writer: &mut W) -> Result<()>
+///
+/// Entries hidden by a module filter are never offered.
+async fn run_daemon_pull_mode<R, W>(
+    root_path: &Path,
+    filter: Option<&ModuleFilter>,
+    negotiated: Negotiated,
+    reader: &mut R,
+    writer: &mut W,
+) -> Result<()>
 where
     R: AsyncReadExt + Unpin,
     W: AsyncWriteExt + Unpin,
 {
     use super::protocol::{
-        FileData, FileList, FileListEntry, MkdirBatchAck, SymlinkBatch, SymlinkBatchAck,
-        SymlinkEntry,
+        FileList, MkdirBatchAck, SymlinkBatch, SymlinkBatchAck, SymlinkEntry, CAP_META_BLOCK,
     };
+    use super::{pull_file_list, send_pull_files, FileRow};
+
+    let send_meta = negotiated.has(CAP_META_BLOCK);
 
     // Scan source directory
     let scan_opts = ScanOptions::default();
@@ -470,13 +812,18 @@ where
 
     // Separate entries by type
     let mut directories: Vec<String> = Vec::new();
-    let mut files: Vec<(String, PathBuf, u64, i64, u32)> = Vec::new();
+    let mut files: Vec<FileRow> = Vec::new();
     let mut symlinks: Vec<SymlinkEntry> = Vec::new();
 
     // Check if root_path is a single file (not a directory)
     let is_single_file = root_path.is_file();
 
     for entry in entries {
+        // Partials of interrupted receives are not part of the tree
+        if partial::is_partial(&entry.path) {
+            continue;
+        }
+
         // For single file sources, don't strip prefix - use just the filename
         let rel_path_str = if is_single_file {
             // Single file: use just the filename as the relative path
@@ -496,6 +843,10 @@ where
         };
 
         if let Some(path_str) = rel_path_str {
+            if filter.is_some_and(|f| f.excludes(Path::new(&path_str), entry.is_dir)) {
+                continue;
+            }
+
             let mtime = entry
                 .modified
                 .duration_since(std::time::SystemTime::UNIX_EPOCH)
@@ -514,13 +865,12 @@ where
                     }
                 }
             } else {
-                files.push((
-                    path_str.to_string(),
-                    entry.path.to_path_buf(),
-                    entry.size,
-                    mtime,
-                    0o644,
-                ));
+                let meta = if send_meta {
+                    meta::collect(&entry, &MetaOptions::all())
+                } else {
+                    None
+                };
+                files.push(FileRow::new(path_str, &entry, mtime, meta));
             }
         }
     }
@@ -533,100 +883,36 @@ where
     writer.flush().await?;
 
     // Wait for MKDIR_BATCH_ACK
-    let _len = reader.read_u32().await?;
-    let type_byte = reader.read_u8().await?;
+    let frame = Frame::read(reader).await?;
+    let type_byte = frame.msg_type;
     if type_byte != MessageType::MkdirBatchAck as u8 {
         return Err(anyhow::anyhow!(
             "Expected MKDIR_BATCH_ACK, got 0x{:02X}",
             type_byte
         ));
     }
-    let _ack = MkdirBatchAck::read(reader).await?;
+    let _ack = MkdirBatchAck::read_frame(reader, &frame).await?;
 
     // Step 2: Send file list
-    let file_entries: Vec<FileListEntry> = files
-        .iter()
-        .map(|(rel_path, _, size, mtime, mode)| FileListEntry {
-            path: rel_path.clone(),
-            size: *size,
-            mtime: *mtime,
-            mode: *mode,
-            flags: 0,
-            symlink_target: None,
-        })
-        .collect();
-
     let file_list = FileList {
-        entries: file_entries,
+        entries: pull_file_list(&files, negotiated),
     };
     file_list.write(writer).await?;
     writer.flush().await?;
 
     // Wait for FILE_LIST_ACK
-    let _len = reader.read_u32().await?;
-    let type_byte = reader.read_u8().await?;
+    let frame = Frame::read(reader).await?;
+    let type_byte = frame.msg_type;
     if type_byte != MessageType::FileListAck as u8 {
         return Err(anyhow::anyhow!(
             "Expected FILE_LIST_ACK, got 0x{:02X}",
             type_byte
         ));
     }
-    let ack = super::protocol::FileListAck::read(reader).await?;
+    let ack = super::protocol::FileListAck::read_frame(reader, &frame).await?;
 
     // Step 3: Send files that client requested (pipelined - send all, then collect ACKs)
-    let mut files_sent: Vec<u32> = Vec::new();
-
-    for decision in &ack.decisions {
-        if decision.action == super::protocol::Action::Skip {
-            continue;
-        }
-
-        let idx = decision.index as usize;
-        if idx >= files.len() {
-            continue;
-        }
-
-        let (_, abs_path, _, _, _) = &files[idx];
-
-        // Read file data (use spawn_blocking for async compatibility)
-        let abs_path_clone = abs_path.clone();
-        let data = match tokio::task::spawn_blocking(move || std::fs::read(&abs_path_clone)).await {
-            Ok(Ok(d)) => d,
-            Ok(Err(e)) => {
-                warn!("Failed to read {}: {}", abs_path.display(), e);
-                continue;
-            }
-            Err(e) => {
-                warn!("Task join error reading {}: {}", abs_path.display(), e);
-                continue;
-            }
-        };
-
-        let file_data = FileData {
-            index: decision.index,
-            offset: 0,
-            flags: 0,
-            data,
-        };
-        file_data.write(writer).await?;
-        files_sent.push(decision.index);
-    }
-
-    // Flush once after sending all files
-    writer.flush().await?;
-
-    // Collect all FILE_DONE responses
-    for _ in &files_sent {
-        let _len = reader.read_u32().await?;
-        let type_byte = reader.read_u8().await?;
-        if type_byte != MessageType::FileDone as u8 {
-            return Err(anyhow::anyhow!(
-                "Expected FILE_DONE, got 0x{:02X}",
-                type_byte
-            ));
-        }
-        let _done = super::protocol::FileDone::read(reader).await?;
-    }
+    send_pull_files(&files, &file_list.entries, &ack, negotiated, reader, writer).await?;
 
     // Step 4: Send symlinks
     if !symlinks.is_empty() {
@@ -637,15 +923,15 @@ where
         writer.flush().await?;
 
         // Wait for SYMLINK_BATCH_ACK
-        let _len = reader.read_u32().await?;
-        let type_byte = reader.read_u8().await?;
+        let frame = Frame::read(reader).await?;
+        let type_byte = frame.msg_type;
         if type_byte != MessageType::SymlinkBatchAck as u8 {
             return Err(anyhow::anyhow!(
                 "Expected SYMLINK_BATCH_ACK, got 0x{:02X}",
                 type_byte
             ));
         }
-        let _ack = SymlinkBatchAck::read(reader).await?;
+        let _ack = SymlinkBatchAck::read_frame(reader, &frame).await?;
     }
 
     Ok(())
@@ -667,11 +953,11 @@ pub async fn write_set_root<W: AsyncWriteExt + Unpin>(writer: &mut W, path: &str
 
 /// Read SET_ROOT_ACK from daemon
 pub async fn read_set_root_ack<R: AsyncReadExt + Unpin>(reader: &mut R) -> Result<bool> {
-    let _len = reader.read_u32().await?;
-    let type_byte = reader.read_u8().await?;
+    let frame = Frame::read(reader).await?;
+    let type_byte = frame.msg_type;
 
     if type_byte == MessageType::Error as u8 {
-        let err = super::protocol::ErrorMessage::read(reader).await?;
+        let err = super::protocol::ErrorMessage::read_frame(reader, &frame).await?;
         return Err(anyhow::anyhow!("Daemon error: {}", err.message));
     }
 
@@ -682,8 +968,9 @@ pub async fn read_set_root_ack<R: AsyncReadExt + Unpin>(reader: &mut R) -> Resul
         ));
     }
 
-    let status = reader.read_u8().await?;
-    Ok(status == 0)
+    let mut payload = frame.payload(reader, 1)?;
+    let status = payload.read_u8().await.map_err(ProtocolError::from);
+    Ok(frame.finish(&payload, status)? == 0)
 }
 
 #[cfg(test)]
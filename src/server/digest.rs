@@ -0,0 +1,218 @@
+//! Whole-file digests for end-to-end verification in server mode
+//!
+//! A digest travels in a `DATA_FLAG_DIGEST` chunk after a file's data, or
+//! with its DELTA_DATA, and the receiver echoes the digest of what it wrote
+//! in FILE_DONE. Its length tells the algorithm: 8 bytes are xxh3-64
+//! (big-endian), 32 bytes BLAKE3, matching [`ChecksumType::Fast`] and
+//! [`ChecksumType::Cryptographic`].
+//!
+//! A file that fails its check is answered with `STATUS_CHECKSUM_MISMATCH`
+//! and, with `CAP_FILE_CHECKSUM`, sent again whole up to `VERIFY_RETRIES`
+//! times.
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use xxhash_rust::xxh3::Xxh3;
+
+use crate::integrity::ChecksumType;
+use crate::server::protocol::{FileDone, STATUS_CHECKSUM_MISMATCH, STATUS_OK, STATUS_WRITE_ERROR};
+
+const XXH3_LEN: usize = 8;
+const BLAKE3_LEN: usize = 32;
+
+/// Algorithm of a received digest, by its length
+pub fn kind_of(digest: &[u8]) -> Option<ChecksumType> {
+    match digest.len() {
+        XXH3_LEN => Some(ChecksumType::Fast),
+        BLAKE3_LEN => Some(ChecksumType::Cryptographic),
+        _ => None,
+    }
+}
+
+/// Incremental digest of a file's bytes, fed in order
+pub enum Hasher {
+    None,
+    Fast(Box<Xxh3>),
+    Cryptographic(Box<blake3::Hasher>),
+}
+
+impl Hasher {
+    pub fn new(kind: ChecksumType) -> Self {
+        match kind {
+            ChecksumType::None => Self::None,
+            ChecksumType::Fast => Self::Fast(Box::default()),
+            ChecksumType::Cryptographic => Self::Cryptographic(Box::default()),
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        match self {
+            Self::None => {}
+            Self::Fast(h) => h.update(data),
+            Self::Cryptographic(h) => {
+                h.update(data);
+            }
+        }
+    }
+
+    /// Feed `len` zero bytes (a hole)
+    pub fn update_zeros(&mut self, mut len: u64) {
+        let zeros = [0u8; 64 * 1024];
+        while len > 0 {
+            let n = len.min(zeros.len() as u64) as usize;
+            self.update(&zeros[..n]);
+            len -= n as u64;
+        }
+    }
+
+    /// The digest; empty for `ChecksumType::None`
+    pub fn finish(self) -> Vec<u8> {
+        match self {
+            Self::None => Vec::new(),
+            Self::Fast(h) => h.digest().to_be_bytes().to_vec(),
+            Self::Cryptographic(h) => h.finalize().as_bytes().to_vec(),
+        }
+    }
+}
+
+/// Digest of a whole file
+pub fn file_digest(path: &Path, kind: ChecksumType) -> io::Result<Vec<u8>> {
+    let mut hasher = Hasher::new(kind);
+    if matches!(hasher, Hasher::None) {
+        return Ok(Vec::new());
+    }
+    let mut file = File::open(path)?;
+    let mut buf = vec![0u8; 256 * 1024];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            return Ok(hasher.finish());
+        }
+        hasher.update(&buf[..n]);
+    }
+}
+
+/// Digest of a written file, of the same kind as `expected`
+///
+/// Fails with `InvalidData` if `expected` is no known digest.
+pub fn digest_like(path: &Path, expected: &[u8]) -> io::Result<Vec<u8>> {
+    let kind = kind_of(expected)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "malformed digest"))?;
+    file_digest(path, kind)
+}
+
+/// A written file whose digest differs from the sender's
+#[derive(Debug)]
+pub struct Mismatch(PathBuf);
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "checksum mismatch in {}", self.0.display())
+    }
+}
+
+impl std::error::Error for Mismatch {}
+
+/// The error for a file at `path` that failed its digest check
+pub fn mismatch(path: &Path) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, Mismatch(path.to_path_buf()))
+}
+
+/// Whether an error is a failed digest check (as opposed to an I/O failure)
+pub fn is_mismatch(err: &io::Error) -> bool {
+    err.get_ref().is_some_and(|e| e.is::<Mismatch>())
+}
+
+/// FILE_DONE status for a file that could not be committed
+pub fn failure_status(err: &io::Error) -> u8 {
+    if is_mismatch(err) {
+        STATUS_CHECKSUM_MISMATCH
+    } else {
+        STATUS_WRITE_ERROR
+    }
+}
+
+/// How a FILE_DONE answers a file sent with `expected` (empty: no digest)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    /// Written, and the echoed digest (if any) matches
+    Ok,
+    /// Failed its check on either side; to be sent again
+    Mismatch,
+    /// Not written for some other reason
+    Failed,
+}
+
+pub fn verdict(done: &FileDone, expected: &[u8]) -> Verdict {
+    match done.status {
+        STATUS_OK if expected.is_empty() || done.checksum.is_empty() => Verdict::Ok,
+        STATUS_OK if done.checksum == expected => Verdict::Ok,
+        STATUS_OK | STATUS_CHECKSUM_MISMATCH => Verdict::Mismatch,
+        _ => Verdict::Failed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_digests_match_streaming_with_holes() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("file");
+        let mut data = vec![0u8; 200_000];
+        data[150_000..150_005].copy_from_slice(b"hello");
+        std::fs::write(&path, &data).unwrap();
+
+        for kind in [ChecksumType::Fast, ChecksumType::Cryptographic] {
+            let mut hasher = Hasher::new(kind);
+            hasher.update_zeros(150_000);
+            hasher.update(b"hello");
+            hasher.update_zeros(49_995);
+            let streamed = hasher.finish();
+
+            assert_eq!(kind_of(&streamed), Some(kind));
+            assert_eq!(streamed, file_digest(&path, kind).unwrap());
+            assert_eq!(streamed, digest_like(&path, &streamed).unwrap());
+        }
+        assert!(file_digest(&path, ChecksumType::None).unwrap().is_empty());
+        assert!(digest_like(&path, b"bad").is_err());
+    }
+
+    #[test]
+    fn test_verdict_checks_echoed_digest() {
+        let done = |status, checksum: &[u8]| FileDone {
+            index: 0,
+            status,
+            checksum: checksum.to_vec(),
+        };
+        assert_eq!(verdict(&done(STATUS_OK, b""), b""), Verdict::Ok);
+        assert_eq!(verdict(&done(STATUS_OK, &[1; 8]), &[1; 8]), Verdict::Ok);
+        assert_eq!(
+            verdict(&done(STATUS_OK, &[2; 8]), &[1; 8]),
+            Verdict::Mismatch
+        );
+        assert_eq!(
+            verdict(&done(STATUS_CHECKSUM_MISMATCH, b""), &[1; 8]),
+            Verdict::Mismatch
+        );
+        assert_eq!(
+            verdict(&done(STATUS_WRITE_ERROR, b""), b""),
+            Verdict::Failed
+        );
+
+        assert!(is_mismatch(&mismatch(Path::new("f"))));
+        assert_eq!(
+            failure_status(&mismatch(Path::new("f"))),
+            STATUS_CHECKSUM_MISMATCH
+        );
+        assert_eq!(
+            failure_status(&io::Error::other("disk full")),
+            STATUS_WRITE_ERROR
+        );
+    }
+}
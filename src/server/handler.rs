@@ -4,39 +4,195 @@ use std::collections::HashMap;
 use std::io::Write;
 use std::os::unix::fs::PermissionsExt;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
 use tokio::fs;
 use tokio::io::{AsyncSeekExt, AsyncWrite, AsyncWriteExt};
 
 use crate::compress::{decompress, Compression};
-use crate::delta::Adler32;
+use crate::delta::cdc::{self, ChunkParams};
+use crate::delta::{Adler32, DeltaAlgo};
+use crate::integrity::ChecksumType;
+use crate::server::confine::{self, Confinement};
+use crate::server::digest;
+use crate::server::layout;
+use crate::server::meta::{self, MetaOptions};
+use crate::server::modules::ModuleFilter;
+use crate::server::partial::{self, StalePartials};
 use crate::server::protocol::{
     Action, BlockChecksum, ChecksumReq, ChecksumResp, Decision, DeltaData, DeltaOp, FileData,
     FileDone, FileList, FileListAck, FileListEntry, MkdirBatch, MkdirBatchAck, SymlinkBatch,
-    SymlinkBatchAck, DATA_FLAG_COMPRESSED, STATUS_OK, STATUS_WRITE_ERROR,
+    SymlinkBatchAck, DATA_FLAG_COMPRESSED, DATA_FLAG_DIGEST, DATA_FLAG_FINAL, DATA_FLAG_SPARSE,
+    STATUS_CHECKSUM_MISMATCH, STATUS_OK, STATUS_WRITE_ERROR,
 };
+use crate::sync::checksumdb::ChecksumDatabase;
+use crate::sync::fuzzy::BasisIndex;
 use crate::sync::scanner::{self, ScanOptions};
 
 /// Represents a file on the destination that we've scanned
 struct DestEntry {
     size: u64,
     mtime: i64,
+    is_dir: bool,
     is_symlink: bool,
     symlink_target: Option<String>,
 }
 
+/// Protocol path of the partial that `entry`'s data is written to
+fn partial_rel_path(entry: &FileListEntry) -> String {
+    partial::partial_rel(&entry.path, entry.size, entry.mtime)
+}
+
 /// Handle incoming messages on the server side
+///
+/// The destination is scanned once, on the first FILE_LIST of a session;
+/// after that `dest_map` is kept current from the files this handler writes,
+/// so long-lived sessions (watch mode) never rescan the tree.
+///
+/// Daemon modules can add a filter (excluded paths are skipped or refused)
+/// and an owner that everything written is chowned to.
+///
+/// Metadata blocks in the FILE_LIST (ownership, xattrs, ACLs, BSD flags)
+/// are applied after each file's data is committed, limited by `meta`.
+///
+/// Paths from the peer are never trusted: every write goes through a
+/// [`Confinement`], so `..`, absolute paths and existing symlinks cannot
+/// redirect it outside `root_path`. A bad entry fails on its own (failed list
+/// or `STATUS_WRITE_ERROR`) and the session carries on.
+///
+/// FILE_DATA is written to the file's partial and renamed into place once
+/// complete. With `resume` (CAP_RESUME) partials left by an interrupted
+/// session are offered back to the sender in the FILE_LIST_ACK. With
+/// `checksums` (CAP_FILE_CHECKSUM) a file may end with the sender's digest,
+/// which is checked before the rename and echoed in FILE_DONE.
+///
+/// CDC checksum requests that allow it (`--checksum-db`) keep chunk lists in
+/// the checksum database under `root_path`, opened on first use.
+///
+/// New entries flagged FLAG_FUZZY (`--fuzzy`) are offered a similar existing
+/// file as their basis in the FILE_LIST_ACK; checksum requests and deltas for
+/// them then read that file instead of the (missing) destination.
 pub struct ServerHandler {
     pub root_path: PathBuf,
+    confine: Confinement,
+    safe_links: bool,
     dest_map: HashMap<String, DestEntry>,
+    dest_scanned: bool,
     current_file_list: Vec<FileListEntry>,
+    /// Basis files offered for entries of the current file list (--fuzzy)
+    bases: HashMap<u32, String>,
+    filter: Option<ModuleFilter>,
+    owner: Option<(Option<u32>, Option<u32>)>,
+    meta: MetaOptions,
+    resume: bool,
+    checksums: bool,
+    /// Partials of older source versions, removed as files are committed
+    stale_partials: StalePartials,
+    chunk_cache: OnceLock<Option<Arc<ChecksumDatabase>>>,
 }
 
 impl ServerHandler {
     pub fn new(root_path: PathBuf) -> Self {
         Self {
+            confine: Confinement::new(&root_path),
+            safe_links: false,
             root_path,
             dest_map: HashMap::new(),
+            dest_scanned: false,
             current_file_list: Vec::new(),
+            bases: HashMap::new(),
+            filter: None,
+            owner: None,
+            meta: MetaOptions::all(),
+            resume: false,
+            checksums: false,
+            stale_partials: StalePartials::default(),
+            chunk_cache: OnceLock::new(),
+        }
+    }
+
+    /// Refuse writes to paths excluded by a daemon module's filter rules
+    pub fn with_filter(mut self, filter: ModuleFilter) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    /// Chown everything written to this uid/gid (None leaves that id unchanged)
+    pub fn with_owner(mut self, uid: Option<u32>, gid: Option<u32>) -> Self {
+        if uid.is_some() || gid.is_some() {
+            self.owner = Some((uid, gid));
+        }
+        self
+    }
+
+    /// Limit which kinds of received metadata are applied (default: all)
+    pub fn with_meta(mut self, meta: MetaOptions) -> Self {
+        self.meta = meta;
+        self
+    }
+
+    /// Refuse symlinks whose target is absolute or climbs above the root
+    pub fn with_safe_links(mut self, safe_links: bool) -> Self {
+        self.safe_links = safe_links;
+        self
+    }
+
+    /// Offer kept partials to senders and expect split files (CAP_RESUME)
+    pub fn with_resume(mut self, resume: bool) -> Self {
+        self.resume = resume;
+        self
+    }
+
+    /// Expect verified files to arrive split, ending in a digest (CAP_FILE_CHECKSUM)
+    pub fn with_checksums(mut self, checksums: bool) -> Self {
+        self.checksums = checksums;
+        self
+    }
+
+    /// Check a symlink target against the safe-links policy
+    fn unsafe_link(&self, path: &str, target: &str) -> bool {
+        self.safe_links && !confine::symlink_is_safe(Path::new(path), target)
+    }
+
+    /// Check whether a relative path is excluded by the module filter
+    fn is_filtered(&self, path: &str, is_dir: bool) -> bool {
+        self.filter
+            .as_ref()
+            .is_some_and(|f| f.excludes(Path::new(path), is_dir))
+    }
+
+    /// Apply the configured owner to a written path (without following symlinks)
+    fn apply_owner(&self, path: &Path) {
+        if let Some((uid, gid)) = self.owner {
+            if let Err(e) = std::os::unix::fs::lchown(path, uid, gid) {
+                tracing::warn!("Failed to chown {}: {}", path.display(), e);
+            }
+        }
+    }
+
+    /// Apply the entry's metadata block to a committed file
+    ///
+    /// A module owner wins over ownership sent by the client.
+    fn apply_meta(&self, path: &Path, entry: &FileListEntry) {
+        let Some(ref entry_meta) = entry.meta else {
+            return;
+        };
+        let mut opts = self.meta;
+        if self.owner.is_some() {
+            opts.owner = false;
+            opts.group = false;
+        }
+        meta::apply(path, entry_meta, &opts);
+    }
+
+    /// Permission bits to give a written file
+    ///
+    /// setuid/setgid/sticky are kept only where the client may also choose
+    /// ownership; otherwise a daemon could be made to write setuid files.
+    fn mode_of(&self, entry: &FileListEntry) -> u32 {
+        if self.meta.owner && self.owner.is_none() {
+            entry.mode
+        } else {
+            entry.mode & 0o777
         }
     }
 
@@ -51,21 +207,34 @@ impl ServerHandler {
         // Store file list for later reference
         self.current_file_list = list.entries.clone();
 
-        // Scan destination if we have entries to compare
-        if !list.entries.is_empty() {
+        // Scan destination once, if we have entries to compare
+        if !list.entries.is_empty() && !self.dest_scanned {
             self.scan_destination().await?;
+            self.dest_scanned = true;
         }
 
         // Generate decisions
         let mut decisions = Vec::with_capacity(list.entries.len());
 
         for (idx, entry) in list.entries.iter().enumerate() {
-            let action = self.decide_action(entry);
+            let action = match entry.hardlink_to {
+                Some(leader) => self.decide_link(entry, leader, idx, &decisions),
+                None => self.decide_action(entry),
+            };
+            let resume_from = if action != Action::Skip {
+                self.resume_offset(entry)
+            } else {
+                0
+            };
             decisions.push(Decision {
                 index: idx as u32,
                 action,
+                resume_from,
+                digest: ChecksumType::None,
+                basis: None,
             });
         }
+        self.offer_bases(&list.entries, &mut decisions);
 
         // Send ACK
         let ack = FileListAck { decisions };
@@ -75,6 +244,70 @@ impl ServerHandler {
         Ok(())
     }
 
+    /// Pick a basis for new entries that accept one (FLAG_FUZZY)
+    ///
+    /// Files this list writes are never offered: their checksums could be
+    /// taken before and their data copied after they change.
+    fn offer_bases(&mut self, entries: &[FileListEntry], decisions: &mut [Decision]) {
+        self.bases.clear();
+        let wanted: Vec<bool> = entries
+            .iter()
+            .zip(decisions.iter())
+            .map(|(e, d)| {
+                d.action == Action::Create
+                    && d.resume_from == 0
+                    && e.wants_basis()
+                    && !e.is_hardlink()
+            })
+            .collect();
+        if !wanted.contains(&true) {
+            return;
+        }
+
+        let mut index = BasisIndex::new();
+        for (path, dest) in &self.dest_map {
+            if !dest.is_dir && !dest.is_symlink && !self.is_filtered(path, false) {
+                index.insert(Path::new(path), dest.size);
+            }
+        }
+        let written: std::collections::HashSet<&str> = entries
+            .iter()
+            .zip(decisions.iter())
+            .filter(|(_, d)| d.action != Action::Skip)
+            .map(|(e, _)| e.path.as_str())
+            .collect();
+
+        for ((entry, decision), wanted) in entries.iter().zip(decisions.iter_mut()).zip(wanted) {
+            if !wanted {
+                continue;
+            }
+            let basis = index
+                .find(Path::new(&entry.path), entry.size)
+                .and_then(|b| b.to_str().map(String::from))
+                .filter(|b| !written.contains(b.as_str()));
+            if let Some(basis) = basis {
+                tracing::debug!("Fuzzy basis for {}: {}", entry.path, basis);
+                self.bases.insert(decision.index, basis.clone());
+                decision.basis = Some(basis);
+            }
+        }
+    }
+
+    /// Basis offered for entry `index` of the current file list (--fuzzy)
+    pub fn fuzzy_basis(&self, index: u32) -> Option<String> {
+        self.bases.get(&index).cloned()
+    }
+
+    /// Length of a partial an interrupted session left for `entry`, if resuming
+    fn resume_offset(&self, entry: &FileListEntry) -> u64 {
+        if !self.resume || entry.is_dir() || entry.is_symlink() || entry.is_hardlink() {
+            return 0;
+        }
+        self.confine
+            .resolve(&partial_rel_path(entry))
+            .map_or(0, |p| partial::resume_offset(&p, entry.size))
+    }
+
     /// Scan the destination directory and populate dest_map
     async fn scan_destination(&mut self) -> Result<()> {
         self.dest_map.clear();
@@ -114,6 +347,7 @@ impl ServerHandler {
                         DestEntry {
                             size: entry.size,
                             mtime,
+                            is_dir: entry.is_dir,
                             is_symlink: entry.is_symlink,
                             symlink_target,
                         },
@@ -125,6 +359,22 @@ impl ServerHandler {
         Ok(())
     }
 
+    /// Record a completed write of `current_file_list[index]` in `dest_map`
+    fn record_written(&mut self, index: u32) {
+        if let Some(entry) = self.current_file_list.get(index as usize) {
+            self.dest_map.insert(
+                entry.path.clone(),
+                DestEntry {
+                    size: entry.size,
+                    mtime: entry.mtime,
+                    is_dir: entry.is_dir(),
+                    is_symlink: entry.is_symlink(),
+                    symlink_target: entry.symlink_target.clone(),
+                },
+            );
+        }
+    }
+
     /// Decide what action to take for a source entry
     fn decide_action(&self, entry: &FileListEntry) -> Action {
         // Skip directories - they're handled via MKDIR_BATCH
@@ -132,6 +382,10 @@ impl ServerHandler {
             return Action::Skip;
         }
 
+        if self.is_filtered(&entry.path, false) {
+            return Action::Skip;
+        }
+
         match self.dest_map.get(&entry.path) {
             Some(dest) => {
                 // Entry exists on destination
@@ -163,6 +417,44 @@ impl ServerHandler {
         }
     }
 
+    /// Decide for a hard link to `leader`, an earlier entry of the same list
+    ///
+    /// Links are made after all data has arrived, so the link is redone
+    /// whenever the leader is rewritten, and otherwise only if the path
+    /// isn't already linked to it.
+    fn decide_link(
+        &self,
+        entry: &FileListEntry,
+        leader: u32,
+        idx: usize,
+        decisions: &[Decision],
+    ) -> Action {
+        let Some(leader_decision) = decisions
+            .get(leader as usize)
+            .filter(|_| (leader as usize) < idx)
+        else {
+            tracing::warn!("Ignoring hard link {} to entry {}", entry.path, leader);
+            return Action::Skip;
+        };
+        if self.is_filtered(&entry.path, false) {
+            return Action::Skip;
+        }
+
+        let leader_path = &self.current_file_list[leader as usize].path;
+        let up_to_date = leader_decision.action == Action::Skip
+            && layout::same_file(
+                &self.root_path.join(leader_path),
+                &self.root_path.join(&entry.path),
+            );
+        if up_to_date {
+            Action::Skip
+        } else if self.dest_map.contains_key(&entry.path) {
+            Action::Update
+        } else {
+            Action::Create
+        }
+    }
+
     /// Handle MKDIR_BATCH message: create directories
     pub async fn handle_mkdir_batch<W: AsyncWrite + Unpin>(
         &mut self,
@@ -175,9 +467,23 @@ impl ServerHandler {
         let mut failed = Vec::new();
 
         for path in batch.paths {
-            let full_path = self.root_path.join(&path);
+            if self.is_filtered(&path, true) {
+                tracing::debug!("Skipping excluded directory {}", path);
+                continue;
+            }
+            let full_path = match self.confine.resolve(&path) {
+                Ok(p) => p,
+                Err(e) => {
+                    tracing::warn!("Refusing directory {}: {}", path, e);
+                    failed.push((path, e.to_string()));
+                    continue;
+                }
+            };
             match fs::create_dir_all(&full_path).await {
-                Ok(()) => created += 1,
+                Ok(()) => {
+                    self.apply_owner(&full_path);
+                    created += 1;
+                }
                 Err(e) => {
                     tracing::warn!("Failed to create directory {}: {}", path, e);
                     failed.push((path, e.to_string()));
@@ -204,7 +510,26 @@ impl ServerHandler {
         let mut failed = Vec::new();
 
         for entry in batch.entries {
-            let full_path = self.root_path.join(&entry.path);
+            if self.is_filtered(&entry.path, false) {
+                tracing::debug!("Skipping excluded symlink {}", entry.path);
+                continue;
+            }
+            let full_path = match self.confine.resolve(&entry.path) {
+                Ok(p) => p,
+                Err(e) => {
+                    tracing::warn!("Refusing symlink {}: {}", entry.path, e);
+                    failed.push((entry.path, e.to_string()));
+                    continue;
+                }
+            };
+            if self.unsafe_link(&entry.path, &entry.target) {
+                tracing::warn!("Refusing unsafe symlink {} -> {}", entry.path, entry.target);
+                failed.push((
+                    entry.path,
+                    format!("unsafe symlink target: {}", entry.target),
+                ));
+                continue;
+            }
 
             // Ensure parent directory exists
             if let Some(parent) = full_path.parent() {
@@ -228,7 +553,20 @@ impl ServerHandler {
             // Create symlink
             #[cfg(unix)]
             match tokio::fs::symlink(&entry.target, &full_path).await {
-                Ok(()) => created += 1,
+                Ok(()) => {
+                    self.apply_owner(&full_path);
+                    created += 1;
+                    self.dest_map.insert(
+                        entry.path,
+                        DestEntry {
+                            size: 0,
+                            mtime: 0,
+                            is_dir: false,
+                            is_symlink: true,
+                            symlink_target: Some(entry.target),
+                        },
+                    );
+                }
                 Err(e) => {
                     tracing::warn!("Failed to create symlink {}: {}", entry.path, e);
                     failed.push((entry.path, e.to_string()));
@@ -275,6 +613,34 @@ impl ServerHandler {
         }
 
         let entry = &self.current_file_list[data.index as usize];
+        let refusal = if self.is_filtered(&entry.path, false) {
+            Some("path is excluded by the module filter".to_string())
+        } else if entry.is_symlink()
+            && entry
+                .symlink_target
+                .as_deref()
+                .is_some_and(|t| self.unsafe_link(&entry.path, t))
+        {
+            Some("unsafe symlink target".to_string())
+        } else {
+            self.confine
+                .resolve(&entry.path)
+                .err()
+                .map(|e| e.to_string())
+        };
+
+        if let Some(reason) = refusal {
+            tracing::warn!("Refusing write to {}: {}", entry.path, reason);
+            let done = FileDone {
+                index: data.index,
+                status: STATUS_WRITE_ERROR,
+                checksum: vec![],
+            };
+            done.write(writer).await?;
+            writer.flush().await?;
+            return Ok(());
+        }
+
         let path = self.root_path.join(&entry.path);
 
         // Ensure parent dir exists
@@ -282,6 +648,28 @@ impl ServerHandler {
             fs::create_dir_all(parent).await?;
         }
 
+        // Hard links carry no data; the leader has been written by now
+        if let Some(leader) = entry.hardlink_to {
+            let status = match self.link_to_leader(entry, leader, data.index) {
+                Ok(()) => STATUS_OK,
+                Err(e) => {
+                    tracing::warn!("Failed to link {}: {}", entry.path, e);
+                    STATUS_WRITE_ERROR
+                }
+            };
+            if status == STATUS_OK {
+                self.record_written(data.index);
+            }
+            let done = FileDone {
+                index: data.index,
+                status,
+                checksum: vec![],
+            };
+            done.write(writer).await?;
+            writer.flush().await?;
+            return Ok(());
+        }
+
         // Handle symlinks separately (should use SYMLINK_BATCH, but handle legacy)
         if entry.is_symlink() {
             if let Some(ref target) = entry.symlink_target {
@@ -294,6 +682,8 @@ impl ServerHandler {
                 #[cfg(windows)]
                 tokio::fs::symlink_file(target, &path).await?;
 
+                self.apply_owner(&path);
+                self.record_written(data.index);
                 let done = FileDone {
                     index: data.index,
                     status: STATUS_OK,
@@ -305,32 +695,37 @@ impl ServerHandler {
             }
         }
 
-        // Write regular file
-        let status = match self.write_file_data(&path, &data, entry).await {
-            Ok(complete) => {
-                if complete {
-                    // Set permissions if we have mode
-                    if entry.mode != 0 {
-                        let _ =
-                            fs::set_permissions(&path, std::fs::Permissions::from_mode(entry.mode))
-                                .await;
-                    }
-                    Some(STATUS_OK)
-                } else {
-                    None // Not complete yet, don't send FileDone
+        // Write regular file (through its partial); a resumed or verified
+        // file ends with the digest of the whole source
+        let partial_rel = partial_rel_path(entry);
+        let status = if data.flags & DATA_FLAG_DIGEST != 0 {
+            match self.verify_partial(&partial_rel, &data.data) {
+                Ok(checksum) => Some((self.commit_partial(&partial_rel, &path, entry), checksum)),
+                Err(e) => {
+                    tracing::error!("Failed to verify {}: {}", entry.path, e);
+                    Some((STATUS_CHECKSUM_MISMATCH, Vec::new()))
                 }
             }
-            Err(e) => {
-                tracing::error!("Failed to write {}: {}", entry.path, e);
-                Some(STATUS_WRITE_ERROR)
+        } else {
+            match self.write_file_data(&partial_rel, &data, entry).await {
+                Ok(true) => Some((self.commit_partial(&partial_rel, &path, entry), Vec::new())),
+                Ok(false) => None, // Not complete yet, don't send FileDone
+                Err(e) => {
+                    tracing::error!("Failed to write {}: {}", entry.path, e);
+                    Some((STATUS_WRITE_ERROR, Vec::new()))
+                }
             }
         };
 
-        if let Some(status) = status {
+        if let Some((status, checksum)) = status {
+            if status == STATUS_OK {
+                self.stale_partials.remove(&path);
+                self.record_written(data.index);
+            }
             let done = FileDone {
                 index: data.index,
                 status,
-                checksum: vec![],
+                checksum,
             };
             done.write(writer).await?;
             writer.flush().await?;
@@ -339,19 +734,73 @@ impl ServerHandler {
         Ok(())
     }
 
-    /// Write file data to disk, returns true if file is complete
+    /// Replace `entry`'s path with a hard link to the entry at `leader`
+    fn link_to_leader(&self, entry: &FileListEntry, leader: u32, index: u32) -> Result<()> {
+        let leader_entry = self
+            .current_file_list
+            .get(leader as usize)
+            .filter(|l| leader < index && !l.is_symlink() && !l.is_hardlink())
+            .ok_or_else(|| anyhow::anyhow!("invalid hard link target {}", leader))?;
+        if self.is_filtered(&leader_entry.path, false) {
+            anyhow::bail!("link target is excluded by the module filter");
+        }
+        let leader_path = self.confine.resolve(&leader_entry.path)?;
+        let path = self.confine.resolve(&entry.path)?;
+        layout::link_file(&leader_path, &path, true)?;
+        Ok(())
+    }
+
+    /// Check a partial against the sender's digest chunk, returning its own
+    fn verify_partial(&self, partial_rel: &str, digest: &[u8]) -> Result<Vec<u8>> {
+        let partial_path = self.confine.resolve(partial_rel)?;
+        Ok(partial::verify(&partial_path, digest)?)
+    }
+
+    /// Rename a complete partial into place and apply the entry's metadata
+    ///
+    /// The rename replaces whatever was at `path`, symlinks included, without
+    /// ever writing through it.
+    fn commit_partial(&self, partial_rel: &str, path: &Path, entry: &FileListEntry) -> u8 {
+        let renamed = self
+            .confine
+            .resolve(partial_rel)
+            .and_then(|partial_path| Ok(std::fs::rename(partial_path, path)?));
+        if let Err(e) = renamed {
+            tracing::error!("Failed to commit {}: {}", entry.path, e);
+            return STATUS_WRITE_ERROR;
+        }
+
+        // Set permissions if we have mode
+        if entry.mode != 0 {
+            let _ = std::fs::set_permissions(
+                path,
+                std::fs::Permissions::from_mode(self.mode_of(entry)),
+            );
+        }
+        self.apply_owner(path);
+        self.apply_meta(path, entry);
+        STATUS_OK
+    }
+
+    /// Write file data to the entry's partial, returns true if file is complete
+    ///
+    /// Split (CAP_RESUME, CAP_FILE_CHECKSUM) and sparse files end with a
+    /// `DATA_FLAG_FINAL` chunk;
+    /// otherwise the file is complete once it reaches its size.
     async fn write_file_data(
         &self,
-        path: &PathBuf,
+        partial_rel: &str,
         data: &FileData,
         entry: &FileListEntry,
     ) -> Result<bool> {
-        let mut file = fs::OpenOptions::new()
-            .write(true)
-            .create(true)
-            .truncate(data.offset == 0) // Truncate on first chunk
-            .open(path)
-            .await?;
+        // Truncate on first chunk; hole-aware files are sized up front so
+        // the ranges never written stay holes
+        let sparse = data.flags & DATA_FLAG_SPARSE != 0;
+        let file = self.confine.create_file(partial_rel, data.offset == 0)?;
+        let mut file = fs::File::from_std(file);
+        if sparse && data.offset == 0 {
+            file.set_len(entry.size).await?;
+        }
 
         if data.offset > 0 {
             file.seek(std::io::SeekFrom::Start(data.offset)).await?;
@@ -367,6 +816,10 @@ impl ServerHandler {
         file.write_all(&write_data).await?;
         file.flush().await?;
 
+        if self.resume || self.checksums || sparse {
+            return Ok(data.flags & DATA_FLAG_FINAL != 0);
+        }
+
         // Check if file is complete
         let meta = file.metadata().await?;
         Ok(meta.len() >= entry.size)
@@ -377,6 +830,25 @@ impl ServerHandler {
         &self.current_file_list
     }
 
+    /// The chunk list cache a CHECKSUM_REQ may use, if it allows one
+    ///
+    /// The database is opened on the first request that asks; if that fails
+    /// chunk lists are computed afresh for the rest of the session.
+    pub fn chunk_cache(&self, req: &ChecksumReq) -> Option<Arc<ChecksumDatabase>> {
+        if !req.cached || req.algo != DeltaAlgo::Cdc {
+            return None;
+        }
+        self.chunk_cache
+            .get_or_init(|| match ChecksumDatabase::open(&self.root_path) {
+                Ok(db) => Some(Arc::new(db)),
+                Err(e) => {
+                    tracing::warn!("Chunk lists won't be cached: {}", e);
+                    None
+                }
+            })
+            .clone()
+    }
+
     /// Handle CHECKSUM_REQ message: compute and return block checksums for delta sync
     /// Note: Does NOT flush - caller should batch flushes for better performance
     pub async fn handle_checksum_req<W: AsyncWrite + Unpin>(
@@ -384,13 +856,11 @@ impl ServerHandler {
         req: ChecksumReq,
         writer: &mut W,
     ) -> Result<()> {
-        let resp = compute_checksum_response(
-            req.index,
-            req.block_size as usize,
-            &self.current_file_list,
-            &self.root_path,
-        )
-        .await?;
+        let cache = self.chunk_cache(&req);
+        let basis = self.fuzzy_basis(req.index);
+        let resp =
+            compute_checksum_response(req, &self.current_file_list, &self.root_path, basis, cache)
+                .await?;
         resp.write(writer).await?;
         // No flush - batching handled by caller
         Ok(())
@@ -417,38 +887,61 @@ impl ServerHandler {
         }
 
         let entry = &self.current_file_list[delta.index as usize];
-        let path = self.root_path.join(&entry.path);
         let is_compressed = delta.flags & DATA_FLAG_COMPRESSED != 0;
 
-        // Apply delta in blocking task
-        let root = self.root_path.clone();
-        let status = tokio::task::spawn_blocking(move || {
-            apply_delta_ops(&path, &root, &delta.ops, is_compressed)
-        })
-        .await?;
+        // Apply delta in blocking task (never for module-excluded paths)
+        let confine = self.confine.clone();
+        let rel = entry.path.clone();
+        let basis = self.fuzzy_basis(delta.index).unwrap_or_else(|| rel.clone());
+        let status = if self.is_filtered(&entry.path, false) {
+            Err(anyhow::anyhow!("path is excluded by the module filter"))
+        } else {
+            tokio::task::spawn_blocking(move || {
+                apply_delta_ops(
+                    &confine,
+                    &rel,
+                    &basis,
+                    &delta.ops,
+                    is_compressed,
+                    &delta.digest,
+                )
+            })
+            .await?
+        };
 
+        let mut checksum = Vec::new();
         let status = match status {
-            Ok(()) => {
+            Ok(written) => {
+                checksum = written;
                 // Set permissions
                 if entry.mode != 0 {
                     let _ = fs::set_permissions(
                         &self.root_path.join(&entry.path),
-                        std::fs::Permissions::from_mode(entry.mode),
+                        std::fs::Permissions::from_mode(self.mode_of(entry)),
                     )
                     .await;
                 }
+                let path = self.root_path.join(&entry.path);
+                self.apply_owner(&path);
+                self.apply_meta(&path, entry);
                 STATUS_OK
             }
             Err(e) => {
                 tracing::error!("Delta apply failed for {}: {}", entry.path, e);
-                STATUS_WRITE_ERROR
+                match e.downcast_ref::<std::io::Error>() {
+                    Some(e) => digest::failure_status(e),
+                    None => STATUS_WRITE_ERROR,
+                }
             }
         };
+        if status == STATUS_OK {
+            self.record_written(delta.index);
+        }
 
         let done = FileDone {
             index: delta.index,
             status,
-            checksum: vec![],
+            checksum,
         };
         done.write(writer).await?;
         writer.flush().await?;
@@ -459,22 +952,54 @@ impl ServerHandler {
 
 /// Compute checksum response for concurrent handling
 /// Takes only the data needed, avoiding borrow issues
+///
+/// The basis is the entry's destination file, or the fuzzy `basis` the
+/// handler offered for it. CDC requests answer with the basis file's chunk
+/// list, read from `cache` when the file hasn't changed since it was last chunked.
 pub async fn compute_checksum_response(
-    index: u32,
-    block_size: usize,
+    req: ChecksumReq,
     file_list: &[FileListEntry],
     root_path: &std::path::Path,
+    basis: Option<String>,
+    cache: Option<Arc<ChecksumDatabase>>,
 ) -> Result<ChecksumResp> {
+    let index = req.index;
     if (index as usize) >= file_list.len() {
         return Err(anyhow::anyhow!("Invalid file index: {}", index));
     }
 
-    let entry = &file_list[index as usize];
-    let path = root_path.join(&entry.path);
+    let basis_rel = basis.unwrap_or_else(|| file_list[index as usize].path.clone());
+
+    // A basis we can't open safely (missing, a symlink, outside the root)
+    // gets no checksums, so the sender falls back to literal data
+    let basis = match Confinement::new(root_path).open_file(&basis_rel) {
+        Ok(file) => file,
+        Err(e) => {
+            tracing::debug!("No usable basis {}: {}", basis_rel, e);
+            return Ok(ChecksumResp {
+                index,
+                file_size: 0,
+                checksums: vec![],
+            });
+        }
+    };
 
     // Compute checksums in blocking task (with parallel rayon inside)
-    let checksums =
-        tokio::task::spawn_blocking(move || compute_block_checksums(&path, block_size)).await??;
+    let block_size = req.block_size as usize;
+    let checksums = match req.algo {
+        DeltaAlgo::Block => {
+            tokio::task::spawn_blocking(move || compute_block_checksums(basis, block_size))
+                .await??
+        }
+        DeltaAlgo::Cdc => {
+            let rel = PathBuf::from(&basis_rel);
+            let params = ChunkParams::new(req.block_size);
+            tokio::task::spawn_blocking(move || {
+                compute_chunk_checksums(basis, &rel, params, cache.as_deref())
+            })
+            .await??
+        }
+    };
 
     let file_size = checksums.iter().map(|c| c.size as u64).sum();
 
@@ -487,9 +1012,15 @@ pub async fn compute_checksum_response(
 
 /// Compute block checksums for a file (for delta sync)
 /// Optimized: parallel block processing using rayon
-fn compute_block_checksums(path: &PathBuf, block_size: usize) -> Result<Vec<BlockChecksum>> {
+fn compute_block_checksums(
+    mut file: std::fs::File,
+    block_size: usize,
+) -> Result<Vec<BlockChecksum>> {
+    use std::io::Read;
+
     // Read entire file into memory for parallel processing
-    let data = std::fs::read(path)?;
+    let mut data = Vec::new();
+    file.read_to_end(&mut data)?;
 
     if data.is_empty() {
         return Ok(Vec::new());
@@ -517,28 +1048,83 @@ fn compute_block_checksums(path: &PathBuf, block_size: usize) -> Result<Vec<Bloc
     Ok(checksums)
 }
 
+/// Compute the content-defined chunk list of a file (CDC delta sync)
+///
+/// Chunks travel as blocks: `weak` carries the chunk's tag bits.
+fn compute_chunk_checksums(
+    file: std::fs::File,
+    rel: &Path,
+    params: ChunkParams,
+    cache: Option<&ChecksumDatabase>,
+) -> Result<Vec<BlockChecksum>> {
+    let metadata = file.metadata()?;
+    let (mtime, size, avg) = (metadata.modified()?, metadata.len(), params.avg as u32);
+
+    let cached = cache.and_then(|db| {
+        db.get_chunks(rel, mtime, size, avg).unwrap_or_else(|e| {
+            tracing::warn!("Chunk cache lookup failed for {}: {}", rel.display(), e);
+            None
+        })
+    });
+    let chunks = match cached {
+        Some(chunks) => chunks,
+        None => {
+            let chunks = cdc::chunk_file(std::io::BufReader::new(file), params)?;
+            if let Some(db) = cache {
+                if let Err(e) = db.store_chunks(rel, mtime, size, avg, &chunks) {
+                    tracing::warn!("Failed to cache chunks for {}: {}", rel.display(), e);
+                }
+            }
+            chunks
+        }
+    };
+
+    Ok(chunks
+        .into_iter()
+        .map(|c| BlockChecksum {
+            offset: c.offset,
+            size: c.size,
+            weak: c.tag,
+            strong: c.strong,
+        })
+        .collect())
+}
+
 /// Apply delta operations to reconstruct a file
 /// Optimized: uses memory mapping for existing file, buffered writer for output
+///
+/// The basis (`basis_rel`: the destination itself, or a fuzzy basis) is
+/// opened without following symlinks, and the result is renamed over the
+/// destination, so a symlink there is replaced rather than written through.
+///
+/// With the sender's `digest` (CAP_FILE_CHECKSUM) the result is checked before
+/// the rename and its own digest returned; a mismatch leaves the destination
+/// untouched and fails with a [`digest::mismatch`].
 fn apply_delta_ops(
-    dest_path: &Path,
-    _root: &Path,
+    confine: &Confinement,
+    rel_path: &str,
+    basis_rel: &str,
     ops: &[DeltaOp],
     is_compressed: bool,
-) -> Result<()> {
+    expected: &[u8],
+) -> Result<Vec<u8>> {
     use memmap2::Mmap;
     use std::io::BufWriter;
 
+    let dest_path = confine.resolve(rel_path)?;
+
     // Create temp file for reconstruction
     let temp_path = dest_path.with_extension("sy-tmp");
+    let temp_rel = Path::new(rel_path).with_extension("sy-tmp");
 
     // Memory-map existing file for fast random access (for Copy ops)
-    let existing_file = std::fs::File::open(dest_path).ok();
+    let existing_file = confine.open_file(basis_rel).ok();
     let mmap = existing_file
         .as_ref()
         .and_then(|f| unsafe { Mmap::map(f).ok() });
 
     // Create temp file with buffered writer
-    let temp_file = std::fs::File::create(&temp_path)?;
+    let temp_file = confine.create_file(&temp_rel.to_string_lossy(), true)?;
     let mut writer = BufWriter::with_capacity(1024 * 1024, temp_file);
 
     for op in ops {
@@ -577,15 +1163,30 @@ fn apply_delta_ops(
     drop(mmap);
     drop(existing_file);
 
+    let written = if expected.is_empty() {
+        Vec::new()
+    } else {
+        match digest::digest_like(&temp_path, expected) {
+            Ok(written) if written == expected => written,
+            other => {
+                let _ = std::fs::remove_file(&temp_path);
+                other?;
+                return Err(digest::mismatch(&dest_path).into());
+            }
+        }
+    };
+
     // Atomic replace
-    std::fs::rename(&temp_path, dest_path)?;
+    std::fs::rename(&temp_path, &dest_path)?;
 
-    Ok(())
+    Ok(written)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::delta::cdc::Chunk;
+    use crate::server::protocol::FLAG_FUZZY;
     use tempfile::TempDir;
 
     #[tokio::test]
@@ -601,6 +1202,8 @@ mod tests {
                 mode: 0o644,
                 flags: 0,
                 symlink_target: None,
+                hardlink_to: None,
+                meta: None,
             }],
         };
 
@@ -679,6 +1282,8 @@ mod tests {
                 mode: 0o644,
                 flags: 0,
                 symlink_target: None,
+                hardlink_to: None,
+                meta: None,
             }],
         };
 
@@ -690,4 +1295,480 @@ mod tests {
 
         assert_eq!(ack.decisions[0].action, Action::Skip);
     }
+
+    #[tokio::test]
+    async fn test_handler_keeps_dest_map_across_file_lists() {
+        let tmp = TempDir::new().unwrap();
+        let mut handler = ServerHandler::new(tmp.path().to_path_buf());
+
+        let list = || FileList {
+            entries: vec![FileListEntry {
+                path: "test.txt".to_string(),
+                size: 5,
+                mtime: 1234567890,
+                mode: 0o644,
+                flags: 0,
+                symlink_target: None,
+                hardlink_to: None,
+                meta: None,
+            }],
+        };
+
+        let mut buf = Vec::new();
+        handler.handle_file_list(list(), &mut buf).await.unwrap();
+
+        let data = FileData {
+            index: 0,
+            offset: 0,
+            flags: 0,
+            data: b"hello".to_vec(),
+        };
+        let mut buf = Vec::new();
+        handler.handle_file_data(data, &mut buf).await.unwrap();
+
+        // A file appearing behind the session's back is not rescanned
+        std::fs::write(tmp.path().join("other.txt"), "x").unwrap();
+
+        let mut buf = Vec::new();
+        handler.handle_file_list(list(), &mut buf).await.unwrap();
+        let mut cursor = std::io::Cursor::new(&buf[5..]);
+        let ack = FileListAck::read(&mut cursor).await.unwrap();
+
+        // The written file is known from its FILE_DONE, not from a rescan
+        assert_eq!(ack.decisions[0].action, Action::Skip);
+        assert!(!handler.dest_map.contains_key("other.txt"));
+    }
+
+    #[tokio::test]
+    async fn test_handler_module_filter_refuses_excluded() {
+        let tmp = TempDir::new().unwrap();
+        let modules = crate::server::modules::ModuleTable::parse(&format!(
+            "[modules.m]\npath = \"{}\"\nfilter = [\"- secret/\", \"- *.key\"]\n",
+            tmp.path().display()
+        ))
+        .unwrap();
+        let session = modules.resolve("m", None, false).unwrap();
+        let mut handler =
+            ServerHandler::new(session.root.clone()).with_filter(session.filter.unwrap());
+
+        let list = FileList {
+            entries: ["ok.txt", "id.key"]
+                .iter()
+                .map(|path| FileListEntry {
+                    path: path.to_string(),
+                    size: 2,
+                    mtime: 1234567890,
+                    mode: 0o644,
+                    flags: 0,
+                    symlink_target: None,
+                    hardlink_to: None,
+                    meta: None,
+                })
+                .collect(),
+        };
+        let mut buf = Vec::new();
+        handler.handle_file_list(list, &mut buf).await.unwrap();
+        let mut cursor = std::io::Cursor::new(&buf[5..]);
+        let ack = FileListAck::read(&mut cursor).await.unwrap();
+        assert_eq!(ack.decisions[0].action, Action::Create);
+        assert_eq!(ack.decisions[1].action, Action::Skip);
+
+        // Data for an excluded entry is refused even if the client sends it
+        let data = FileData {
+            index: 1,
+            offset: 0,
+            flags: 0,
+            data: b"pk".to_vec(),
+        };
+        let mut buf = Vec::new();
+        handler.handle_file_data(data, &mut buf).await.unwrap();
+        let mut cursor = std::io::Cursor::new(&buf[5..]);
+        let done = FileDone::read(&mut cursor).await.unwrap();
+        assert_eq!(done.status, STATUS_WRITE_ERROR);
+        assert!(!tmp.path().join("id.key").exists());
+
+        let batch = MkdirBatch {
+            paths: vec!["secret/inner".to_string(), "public".to_string()],
+        };
+        let mut buf = Vec::new();
+        handler.handle_mkdir_batch(batch, &mut buf).await.unwrap();
+        assert!(!tmp.path().join("secret").exists());
+        assert!(tmp.path().join("public").is_dir());
+    }
+
+    #[tokio::test]
+    async fn test_checksum_response_reads_existing_basis() {
+        let tmp = TempDir::new().unwrap();
+        std::fs::create_dir(tmp.path().join("dir")).unwrap();
+        std::fs::write(tmp.path().join("dir/file.bin"), vec![7u8; 4096]).unwrap();
+
+        let entries = vec![FileListEntry {
+            path: "dir/file.bin".to_string(),
+            size: 5000,
+            mtime: 1234567890,
+            mode: 0o644,
+            flags: 0,
+            symlink_target: None,
+            hardlink_to: None,
+            meta: None,
+        }];
+        let resp = compute_checksum_response(
+            ChecksumReq::block(0, 1024),
+            &entries,
+            tmp.path(),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(resp.file_size, 4096);
+        assert_eq!(resp.checksums.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_handler_resumes_from_partial() {
+        let tmp = TempDir::new().unwrap();
+        let source = tmp.path().join("source.bin");
+        std::fs::write(&source, b"first half|second half").unwrap();
+
+        let entry = FileListEntry {
+            path: "big.bin".to_string(),
+            size: 22,
+            mtime: 1234567890,
+            mode: 0o644,
+            flags: 0,
+            symlink_target: None,
+            hardlink_to: None,
+            meta: None,
+        };
+        let dest = tmp.path().join("dest");
+        std::fs::create_dir(&dest).unwrap();
+        let partial_path = partial::partial_path(&dest.join("big.bin"), 22, 1234567890);
+        std::fs::write(&partial_path, b"first half").unwrap();
+
+        let mut handler = ServerHandler::new(dest.clone()).with_resume(true);
+        let list = FileList {
+            entries: vec![entry],
+        };
+        let mut buf = Vec::new();
+        handler.handle_file_list(list, &mut buf).await.unwrap();
+        let mut cursor = std::io::Cursor::new(&buf[5..]);
+        let ack = FileListAck::read(&mut cursor).await.unwrap();
+        assert_eq!(ack.decisions[0].action, Action::Create);
+        assert_eq!(ack.decisions[0].resume_from, 10);
+
+        // The rest of the data completes nothing until the digest arrives
+        let rest = FileData {
+            index: 0,
+            offset: 10,
+            flags: 0,
+            data: b"|second half".to_vec(),
+        };
+        let mut buf = Vec::new();
+        handler.handle_file_data(rest, &mut buf).await.unwrap();
+        assert!(buf.is_empty());
+
+        let expected = digest::file_digest(&source, ChecksumType::Fast).unwrap();
+        let digest = FileData {
+            index: 0,
+            offset: 22,
+            flags: DATA_FLAG_DIGEST | DATA_FLAG_FINAL,
+            data: expected.clone(),
+        };
+        let mut buf = Vec::new();
+        handler.handle_file_data(digest, &mut buf).await.unwrap();
+        let mut cursor = std::io::Cursor::new(&buf[5..]);
+        let done = FileDone::read(&mut cursor).await.unwrap();
+        assert_eq!(done.status, STATUS_OK);
+        assert_eq!(done.checksum, expected);
+        assert_eq!(
+            std::fs::read(dest.join("big.bin")).unwrap(),
+            b"first half|second half"
+        );
+        assert!(!partial_path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_handler_writes_long_names() {
+        let tmp = TempDir::new().unwrap();
+        let name = "n".repeat(250);
+        let entry = FileListEntry {
+            path: name.clone(),
+            size: 4,
+            mtime: 1234567890,
+            mode: 0o644,
+            flags: 0,
+            symlink_target: None,
+            hardlink_to: None,
+            meta: None,
+        };
+        let mut handler = ServerHandler::new(tmp.path().to_path_buf());
+        let list = FileList {
+            entries: vec![entry],
+        };
+        let mut buf = Vec::new();
+        handler.handle_file_list(list, &mut buf).await.unwrap();
+
+        let data = FileData {
+            index: 0,
+            offset: 0,
+            flags: 0,
+            data: b"data".to_vec(),
+        };
+        let mut buf = Vec::new();
+        handler.handle_file_data(data, &mut buf).await.unwrap();
+        let mut cursor = std::io::Cursor::new(&buf[5..]);
+        let done = FileDone::read(&mut cursor).await.unwrap();
+        assert_eq!(done.status, STATUS_OK);
+        assert_eq!(std::fs::read(tmp.path().join(&name)).unwrap(), b"data");
+    }
+
+    #[tokio::test]
+    async fn test_handler_verifies_digests_before_commit() {
+        let tmp = TempDir::new().unwrap();
+        let dest = tmp.path().join("dest");
+        std::fs::create_dir(&dest).unwrap();
+        std::fs::write(dest.join("old.txt"), b"old contents").unwrap();
+        let source = tmp.path().join("new.txt");
+        std::fs::write(&source, b"new contents").unwrap();
+        let good = digest::file_digest(&source, ChecksumType::Cryptographic).unwrap();
+
+        let entry = |path: &str| FileListEntry {
+            path: path.to_string(),
+            size: 12,
+            mtime: 1234567890,
+            mode: 0o644,
+            flags: 0,
+            symlink_target: None,
+            hardlink_to: None,
+            meta: None,
+        };
+        let mut handler = ServerHandler::new(dest.clone()).with_checksums(true);
+        let list = FileList {
+            entries: vec![entry("new.txt"), entry("bad.txt"), entry("old.txt")],
+        };
+        handler
+            .handle_file_list(list, &mut Vec::new())
+            .await
+            .unwrap();
+
+        let done_for = |buf: Vec<u8>| async move {
+            let mut cursor = std::io::Cursor::new(buf[5..].to_vec());
+            FileDone::read(&mut cursor).await.unwrap()
+        };
+        let send = |index, flags, offset, data: &[u8]| FileData {
+            index,
+            offset,
+            flags,
+            data: data.to_vec(),
+        };
+
+        // Data, then a matching digest: committed, digest echoed
+        let mut buf = Vec::new();
+        handler
+            .handle_file_data(send(0, 0, 0, b"new contents"), &mut buf)
+            .await
+            .unwrap();
+        assert!(buf.is_empty());
+        let flags = DATA_FLAG_DIGEST | DATA_FLAG_FINAL;
+        handler
+            .handle_file_data(send(0, flags, 12, &good), &mut buf)
+            .await
+            .unwrap();
+        let done = done_for(buf).await;
+        assert_eq!(done.status, STATUS_OK);
+        assert_eq!(done.checksum, good);
+        assert_eq!(
+            std::fs::read(dest.join("new.txt")).unwrap(),
+            b"new contents"
+        );
+
+        // Corrupted in transit: never renamed into place
+        let mut buf = Vec::new();
+        handler
+            .handle_file_data(send(1, 0, 0, b"NEW contents"), &mut buf)
+            .await
+            .unwrap();
+        handler
+            .handle_file_data(send(1, flags, 12, &good), &mut buf)
+            .await
+            .unwrap();
+        assert_eq!(done_for(buf).await.status, STATUS_CHECKSUM_MISMATCH);
+        assert!(!dest.join("bad.txt").exists());
+
+        // A delta that rebuilds the wrong file leaves the old one alone
+        let delta = DeltaData {
+            index: 2,
+            flags: 0,
+            ops: vec![DeltaOp::Data(b"NEW contents".to_vec())],
+            digest: good.clone(),
+        };
+        let mut buf = Vec::new();
+        handler.handle_delta_data(delta, &mut buf).await.unwrap();
+        assert_eq!(done_for(buf).await.status, STATUS_CHECKSUM_MISMATCH);
+        assert_eq!(
+            std::fs::read(dest.join("old.txt")).unwrap(),
+            b"old contents"
+        );
+
+        let delta = DeltaData {
+            index: 2,
+            flags: 0,
+            ops: vec![DeltaOp::Data(b"new contents".to_vec())],
+            digest: good.clone(),
+        };
+        let mut buf = Vec::new();
+        handler.handle_delta_data(delta, &mut buf).await.unwrap();
+        let done = done_for(buf).await;
+        assert_eq!(done.status, STATUS_OK);
+        assert_eq!(done.checksum, good);
+        assert_eq!(
+            std::fs::read(dest.join("old.txt")).unwrap(),
+            b"new contents"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_handler_cdc_checksums_use_chunk_cache() {
+        let tmp = TempDir::new().unwrap();
+        let file = tmp.path().join("data.bin");
+        let data: Vec<u8> = (0..100_000u32).map(|i| (i * 7919 % 251) as u8).collect();
+        std::fs::write(&file, &data).unwrap();
+
+        let mut handler = ServerHandler::new(tmp.path().to_path_buf());
+        let list = FileList {
+            entries: vec![FileListEntry {
+                path: "data.bin".to_string(),
+                size: 123_456,
+                mtime: 1234567890,
+                mode: 0o644,
+                flags: 0,
+                symlink_target: None,
+                hardlink_to: None,
+                meta: None,
+            }],
+        };
+        handler
+            .handle_file_list(list, &mut Vec::new())
+            .await
+            .unwrap();
+
+        let checksums = |buf: Vec<u8>| async move {
+            let mut cursor = std::io::Cursor::new(buf[5..].to_vec());
+            ChecksumResp::read(&mut cursor).await.unwrap().checksums
+        };
+        let mut req = ChecksumReq {
+            index: 0,
+            block_size: 4096,
+            algo: DeltaAlgo::Cdc,
+            cached: false,
+        };
+        let params = ChunkParams::new(req.block_size);
+
+        // Uncached: chunked afresh
+        let mut buf = Vec::new();
+        handler.handle_checksum_req(req, &mut buf).await.unwrap();
+        let fresh = cdc::chunk_file(&data[..], params).unwrap();
+        let blocks = checksums(buf).await;
+        assert_eq!(blocks.len(), fresh.len());
+        assert_eq!(blocks[0].strong, fresh[0].strong);
+
+        // Cached: a stored list for the same mtime and size is used as is
+        let metadata = std::fs::metadata(&file).unwrap();
+        let stored = Chunk {
+            offset: 0,
+            size: data.len() as u32,
+            strong: 42,
+            tag: 7,
+        };
+        ChecksumDatabase::open(tmp.path())
+            .unwrap()
+            .store_chunks(
+                Path::new("data.bin"),
+                metadata.modified().unwrap(),
+                metadata.len(),
+                params.avg as u32,
+                &[stored],
+            )
+            .unwrap();
+        req.cached = true;
+        let mut buf = Vec::new();
+        handler.handle_checksum_req(req, &mut buf).await.unwrap();
+        let blocks = checksums(buf).await;
+        assert_eq!(blocks.len(), 1);
+        assert_eq!((blocks[0].strong, blocks[0].weak), (42, 7));
+    }
+    #[tokio::test]
+    async fn test_handler_offers_fuzzy_basis() {
+        let tmp = TempDir::new().unwrap();
+        std::fs::create_dir(tmp.path().join("dist")).unwrap();
+        let old: Vec<u8> = (0..8192u32).map(|i| (i % 251) as u8).collect();
+        std::fs::write(tmp.path().join("dist/app-1.2.tar"), &old).unwrap();
+
+        let entry = |path: &str, flags| FileListEntry {
+            path: path.to_string(),
+            size: 8192,
+            mtime: 1234567890,
+            mode: 0o644,
+            flags,
+            symlink_target: None,
+            hardlink_to: None,
+            meta: None,
+        };
+        let mut handler = ServerHandler::new(tmp.path().to_path_buf());
+        let list = FileList {
+            entries: vec![
+                entry("dist/app-1.3.tar", FLAG_FUZZY),
+                entry("dist/app-1.4.tar", 0),
+            ],
+        };
+        let mut buf = Vec::new();
+        handler.handle_file_list(list, &mut buf).await.unwrap();
+        let mut cursor = std::io::Cursor::new(buf[5..].to_vec());
+        let ack = FileListAck::read(&mut cursor).await.unwrap();
+        assert_eq!(ack.decisions[0].action, Action::Create);
+        assert_eq!(ack.decisions[0].basis.as_deref(), Some("dist/app-1.2.tar"));
+        // Only entries that ask for a basis get one
+        assert_eq!(ack.decisions[1].basis, None);
+
+        // Checksums come from the basis
+        let mut buf = Vec::new();
+        handler
+            .handle_checksum_req(ChecksumReq::block(0, 4096), &mut buf)
+            .await
+            .unwrap();
+        let mut cursor = std::io::Cursor::new(buf[5..].to_vec());
+        let resp = ChecksumResp::read(&mut cursor).await.unwrap();
+        assert_eq!(resp.file_size, 8192);
+        assert_eq!(resp.checksums.len(), 2);
+
+        // Copy ops read the basis; the basis itself is left alone
+        let delta = DeltaData {
+            index: 0,
+            flags: 0,
+            ops: vec![
+                DeltaOp::Copy {
+                    offset: 0,
+                    size: 4096,
+                },
+                DeltaOp::Data(b"new tail".to_vec()),
+            ],
+            digest: Vec::new(),
+        };
+        let mut buf = Vec::new();
+        handler.handle_delta_data(delta, &mut buf).await.unwrap();
+        let mut cursor = std::io::Cursor::new(buf[5..].to_vec());
+        assert_eq!(FileDone::read(&mut cursor).await.unwrap().status, STATUS_OK);
+        let mut expected = old[..4096].to_vec();
+        expected.extend_from_slice(b"new tail");
+        assert_eq!(
+            std::fs::read(tmp.path().join("dist/app-1.3.tar")).unwrap(),
+            expected
+        );
+        assert_eq!(
+            std::fs::read(tmp.path().join("dist/app-1.2.tar")).unwrap(),
+            old
+        );
+    }
 }
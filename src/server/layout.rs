@@ -0,0 +1,433 @@
+//! Hard-link groups and sparse files in server mode
+//!
+//! With `CAP_HARDLINKS`, files sharing an inode are sent once: the first is
+//! transferred as usual and the others are FILE_LIST entries whose
+//! `hardlink_to` names it, which the receiver links (or, pulling without
+//! `-H`, copies) after all data has arrived. With `CAP_SPARSE`, files with
+//! holes go out as their data regions only, in `DATA_FLAG_SPARSE` chunks;
+//! the receiver sizes the file on the first chunk so the holes stay holes.
+//! With `CAP_RESUME` or `CAP_FILE_CHECKSUM`, files go out in `CHUNK_SIZE`
+//! pieces ending in a `DATA_FLAG_FINAL` chunk, so a cut-off transfer leaves
+//! a usable partial (see [`super::partial`]) and a digest can follow the data.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+
+use crate::compress::{compress, Compression};
+use crate::integrity::ChecksumType;
+use crate::server::digest::{self, Hasher};
+use crate::server::protocol::{
+    Negotiated, CAP_FILE_CHECKSUM, CAP_RESUME, DATA_FLAG_COMPRESSED, DATA_FLAG_DIGEST,
+    DATA_FLAG_FINAL, DATA_FLAG_SPARSE,
+};
+use crate::sparse::{detect_data_regions, DataRegion};
+
+/// Largest FILE_DATA payload when files are split
+pub const CHUNK_SIZE: u64 = 1024 * 1024;
+
+/// Whether every file's FILE_DATA ends with a `DATA_FLAG_FINAL` chunk
+pub fn final_chunks(negotiated: Negotiated) -> bool {
+    negotiated.has(CAP_RESUME) || negotiated.has(CAP_FILE_CHECKSUM)
+}
+
+/// Tracks the first file seen for each multiply-linked inode
+#[derive(Debug, Default)]
+pub struct HardlinkGroups {
+    leaders: HashMap<u64, u32>,
+}
+
+impl HardlinkGroups {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record file `index`; returns the index of an earlier file sharing its inode
+    pub fn leader(&mut self, inode: Option<u64>, nlink: u64, index: u32) -> Option<u32> {
+        let inode = inode.filter(|_| nlink > 1)?;
+        match self.leaders.get(&inode) {
+            Some(&leader) => Some(leader),
+            None => {
+                self.leaders.insert(inode, index);
+                None
+            }
+        }
+    }
+}
+
+/// Whether two paths name the same inode (without following symlinks)
+pub fn same_file(a: &Path, b: &Path) -> bool {
+    match (a.symlink_metadata(), b.symlink_metadata()) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+        _ => false,
+    }
+}
+
+/// Make `path` a hard link to `leader`, or a copy of it when `hard` is false
+///
+/// Whatever is at `path` is replaced; a path already linked to `leader` is
+/// left alone.
+pub fn link_file(leader: &Path, path: &Path, hard: bool) -> io::Result<()> {
+    if hard && same_file(leader, path) {
+        return Ok(());
+    }
+    match std::fs::remove_file(path) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+    if hard {
+        std::fs::hard_link(leader, path)
+    } else {
+        std::fs::copy(leader, path).map(|_| ())
+    }
+}
+
+/// One FILE_DATA payload: where it goes, its DATA_FLAG_* bits and bytes
+#[derive(Debug)]
+pub struct Chunk {
+    pub offset: u64,
+    pub flags: u8,
+    pub data: Vec<u8>,
+}
+
+/// How a file is cut into FILE_DATA chunks
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ChunkPlan {
+    /// Send only the data regions (CAP_SPARSE)
+    pub sparse: bool,
+    /// zstd-compress chunks that shrink
+    pub zstd: bool,
+    /// Cut into CHUNK_SIZE pieces, the last flagged FINAL ([`final_chunks`])
+    pub split: bool,
+    /// Continue a receiver's partial from here, ending with a digest chunk
+    pub resume_from: u64,
+    /// End with a digest chunk of this kind (resumes default to xxh3)
+    pub digest: ChecksumType,
+}
+
+/// Read a file as FILE_DATA chunks
+///
+/// Dense files are one chunk at offset 0 unless split. Sparse files are
+/// their data regions, flagged `DATA_FLAG_SPARSE` with the last also
+/// `DATA_FLAG_FINAL`; the first chunk always starts at offset 0, empty if
+/// the file begins with a hole, so the receiver knows to truncate. A resumed
+/// file is read densely from `resume_from`.
+///
+/// With a digest, the data is followed by a `DATA_FLAG_DIGEST | FINAL` chunk
+/// at the file's end, hashed from the chunks as read (holes as zeros), or
+/// from the whole file when resuming.
+pub fn read_chunks(path: &Path, plan: ChunkPlan) -> io::Result<Vec<Chunk>> {
+    let kind = match plan.digest {
+        ChecksumType::None if plan.resume_from > 0 => ChecksumType::Fast,
+        kind => kind,
+    };
+    let len = path.metadata()?.len();
+
+    let mut chunks = if plan.resume_from > 0 {
+        let mut file = File::open(path)?;
+        let mut chunks = Vec::new();
+        let from = plan.resume_from;
+        read_range(
+            &mut file,
+            from,
+            len.saturating_sub(from),
+            true,
+            0,
+            &mut chunks,
+        )?;
+        chunks
+    } else {
+        match plan.sparse.then(|| detect_data_regions(path)) {
+            Some(Ok(regions)) => read_regions(path, &regions, plan.split)?,
+            Some(Err(e)) => {
+                tracing::debug!(
+                    "No hole map for {} ({}), sending densely",
+                    path.display(),
+                    e
+                );
+                read_dense(path, plan.split)?
+            }
+            None => read_dense(path, plan.split)?,
+        }
+    };
+
+    if kind != ChecksumType::None {
+        let data = if plan.resume_from > 0 {
+            digest::file_digest(path, kind)?
+        } else {
+            digest_chunks(&chunks, len, kind)
+        };
+        if let Some(last) = chunks.last_mut() {
+            last.flags &= !DATA_FLAG_FINAL;
+        }
+        chunks.push(Chunk {
+            offset: len.max(plan.resume_from),
+            flags: DATA_FLAG_DIGEST | DATA_FLAG_FINAL,
+            data,
+        });
+    }
+
+    if plan.zstd {
+        let compressible = |c: &&mut Chunk| !c.data.is_empty() && c.flags & DATA_FLAG_DIGEST == 0;
+        for chunk in chunks.iter_mut().filter(compressible) {
+            if let Ok(compressed) = compress(&chunk.data, Compression::Zstd) {
+                if compressed.len() < chunk.data.len() {
+                    chunk.data = compressed;
+                    chunk.flags |= DATA_FLAG_COMPRESSED;
+                }
+            }
+        }
+    }
+    Ok(chunks)
+}
+
+/// The digest a file's chunks end with (empty if none)
+pub fn sent_digest(chunks: &[Chunk]) -> &[u8] {
+    match chunks.last() {
+        Some(last) if last.flags & DATA_FLAG_DIGEST != 0 => &last.data,
+        _ => &[],
+    }
+}
+
+/// Digest of a `len`-byte file from its (uncompressed) chunks, holes as zeros
+fn digest_chunks(chunks: &[Chunk], len: u64, kind: ChecksumType) -> Vec<u8> {
+    let mut hasher = Hasher::new(kind);
+    let mut pos = 0u64;
+    for chunk in chunks {
+        hasher.update_zeros(chunk.offset.saturating_sub(pos));
+        hasher.update(&chunk.data);
+        pos = pos.max(chunk.offset + chunk.data.len() as u64);
+    }
+    hasher.update_zeros(len.saturating_sub(pos));
+    hasher.finish()
+}
+
+fn read_dense(path: &Path, split: bool) -> io::Result<Vec<Chunk>> {
+    if !split {
+        return Ok(vec![Chunk {
+            offset: 0,
+            flags: 0,
+            data: std::fs::read(path)?,
+        }]);
+    }
+
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    let mut chunks = Vec::new();
+    read_range(&mut file, 0, len, true, 0, &mut chunks)?;
+    if let Some(last) = chunks.last_mut() {
+        last.flags |= DATA_FLAG_FINAL;
+    }
+    Ok(chunks)
+}
+
+fn read_regions(path: &Path, regions: &[DataRegion], split: bool) -> io::Result<Vec<Chunk>> {
+    let mut file = File::open(path)?;
+    let mut chunks = Vec::with_capacity(regions.len() + 1);
+
+    if regions.first().is_none_or(|r| r.offset != 0) {
+        chunks.push(Chunk {
+            offset: 0,
+            flags: DATA_FLAG_SPARSE,
+            data: Vec::new(),
+        });
+    }
+    for region in regions {
+        read_range(
+            &mut file,
+            region.offset,
+            region.length,
+            split,
+            DATA_FLAG_SPARSE,
+            &mut chunks,
+        )?;
+    }
+
+    if let Some(last) = chunks.last_mut() {
+        last.flags |= DATA_FLAG_FINAL;
+    }
+    Ok(chunks)
+}
+
+/// Append `length` bytes at `offset` as chunks (CHUNK_SIZE pieces if `split`)
+///
+/// An empty range still yields one empty chunk.
+fn read_range(
+    file: &mut File,
+    offset: u64,
+    length: u64,
+    split: bool,
+    flags: u8,
+    chunks: &mut Vec<Chunk>,
+) -> io::Result<()> {
+    file.seek(SeekFrom::Start(offset))?;
+    let piece = if split { CHUNK_SIZE } else { length.max(1) };
+    let mut done = 0u64;
+    loop {
+        let mut data = vec![0u8; piece.min(length - done) as usize];
+        file.read_exact(&mut data)?;
+        let n = data.len() as u64;
+        chunks.push(Chunk {
+            offset: offset + done,
+            flags,
+            data,
+        });
+        done += n;
+        if done >= length {
+            return Ok(());
+        }
+    }
+}
+
+/// Whether a chunk with these flags is the last of its file
+///
+/// Split (`chunked`, CAP_RESUME) and sparse files end with a
+/// `DATA_FLAG_FINAL` chunk; otherwise a file is one chunk.
+pub fn is_last_chunk(flags: u8, chunked: bool) -> bool {
+    flags & DATA_FLAG_FINAL != 0 || (!chunked && flags & DATA_FLAG_SPARSE == 0)
+}
+
+/// Write one (uncompressed) FILE_DATA chunk of a `size`-byte file
+///
+/// The caller opens `file`, truncating it for the chunk at offset 0; for
+/// `DATA_FLAG_SPARSE` chunks that one also extends it to `size`, so
+/// everything not written later is a hole.
+pub fn write_chunk(
+    file: &mut File,
+    size: u64,
+    offset: u64,
+    data: &[u8],
+    flags: u8,
+) -> io::Result<()> {
+    if flags & DATA_FLAG_SPARSE != 0 && offset == 0 {
+        file.set_len(size)?;
+    }
+    write_at(file, offset, data)
+}
+
+/// Write `data` at `offset` (no-op for the empty leading chunk)
+pub fn write_at(file: &mut File, offset: u64, data: &[u8]) -> io::Result<()> {
+    if data.is_empty() {
+        return Ok(());
+    }
+    file.seek(SeekFrom::Start(offset))?;
+    file.write_all(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::OpenOptions;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_hardlink_groups_pick_first_index() {
+        let mut groups = HardlinkGroups::new();
+        assert_eq!(groups.leader(Some(7), 2, 0), None);
+        assert_eq!(groups.leader(Some(8), 1, 1), None);
+        assert_eq!(groups.leader(Some(7), 2, 2), Some(0));
+        assert_eq!(groups.leader(None, 3, 3), None);
+    }
+
+    #[test]
+    fn test_sparse_chunks_roundtrip() {
+        let temp = TempDir::new().unwrap();
+        let src = temp.path().join("src");
+        let size = 4 * 1024 * 1024u64;
+        {
+            let mut file = File::create(&src).unwrap();
+            file.set_len(size).unwrap();
+            write_at(&mut file, 2 * 1024 * 1024, b"middle").unwrap();
+        }
+
+        let plan = ChunkPlan {
+            sparse: true,
+            ..Default::default()
+        };
+        let chunks = read_chunks(&src, plan).unwrap();
+        let dest = temp.path().join("dest");
+        for (i, chunk) in chunks.iter().enumerate() {
+            let mut file = OpenOptions::new()
+                .write(true)
+                .create(true)
+                .truncate(chunk.offset == 0)
+                .open(&dest)
+                .unwrap();
+            write_chunk(&mut file, size, chunk.offset, &chunk.data, chunk.flags).unwrap();
+            assert_eq!(is_last_chunk(chunk.flags, false), i == chunks.len() - 1);
+        }
+        assert_eq!(std::fs::read(&src).unwrap(), std::fs::read(&dest).unwrap());
+
+        // A digest follows the data, hashed with the holes as zeros
+        let plan = ChunkPlan {
+            digest: ChecksumType::Cryptographic,
+            ..plan
+        };
+        let with_digest = read_chunks(&src, plan).unwrap();
+        let (digest, data_chunks) = with_digest.split_last().unwrap();
+        assert_eq!(digest.flags, DATA_FLAG_DIGEST | DATA_FLAG_FINAL);
+        assert_eq!(
+            digest.data,
+            digest::file_digest(&src, ChecksumType::Cryptographic).unwrap()
+        );
+        assert!(data_chunks.iter().all(|c| c.flags & DATA_FLAG_FINAL == 0));
+
+        // Where the filesystem reports holes, only data regions are sent
+        if chunks.len() > 1 {
+            let sent: usize = chunks.iter().map(|c| c.data.len()).sum();
+            assert!((sent as u64) < size);
+            assert!(dest.metadata().unwrap().blocks() * 512 < size);
+        }
+    }
+
+    #[test]
+    fn test_split_and_resumed_chunks() {
+        let temp = TempDir::new().unwrap();
+        let src = temp.path().join("src");
+        let data: Vec<u8> = (0..CHUNK_SIZE * 2 + 100).map(|i| (i % 251) as u8).collect();
+        std::fs::write(&src, &data).unwrap();
+
+        let split = ChunkPlan {
+            split: true,
+            ..Default::default()
+        };
+        let chunks = read_chunks(&src, split).unwrap();
+        assert_eq!(chunks.len(), 3);
+        for (i, chunk) in chunks.iter().enumerate() {
+            assert_eq!(is_last_chunk(chunk.flags, true), i == 2);
+        }
+
+        let resumed = ChunkPlan {
+            resume_from: CHUNK_SIZE + 10,
+            ..split
+        };
+        let chunks = read_chunks(&src, resumed).unwrap();
+        let (digest, rest) = chunks.split_last().unwrap();
+        assert_eq!(rest[0].offset, CHUNK_SIZE + 10);
+        let sent: Vec<u8> = rest.iter().flat_map(|c| c.data.clone()).collect();
+        assert_eq!(sent, data[(CHUNK_SIZE + 10) as usize..]);
+        assert_eq!(digest.flags, DATA_FLAG_DIGEST | DATA_FLAG_FINAL);
+        assert_eq!(digest.offset, data.len() as u64);
+    }
+
+    #[test]
+    fn test_link_file_replaces_existing() {
+        let temp = TempDir::new().unwrap();
+        let leader = temp.path().join("leader");
+        let path = temp.path().join("path");
+        std::fs::write(&leader, b"shared").unwrap();
+        std::fs::write(&path, b"stale").unwrap();
+
+        link_file(&leader, &path, true).unwrap();
+        assert!(same_file(&leader, &path));
+        link_file(&leader, &path, true).unwrap();
+
+        std::fs::remove_file(&path).unwrap();
+        link_file(&leader, &path, false).unwrap();
+        assert!(!same_file(&leader, &path));
+        assert_eq!(std::fs::read(&path).unwrap(), b"shared");
+    }
+}
@@ -0,0 +1,257 @@
+//! Per-entry metadata (ownership, xattrs, ACLs, BSD flags) for server mode
+//!
+//! The sender fills an [`EntryMeta`] from the scanned entry, keeping only
+//! what the user asked to preserve; the receiver applies it once the file's
+//! data is committed, the same order `SshTransport` uses. Nothing here fails
+//! a transfer: metadata that can't be applied is logged and skipped.
+
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+
+use crate::server::protocol::{EntryMeta, FileListEntry};
+use crate::sync::scanner::FileEntry;
+
+/// Which kinds of metadata to send or apply (-o, -g, -X, -A, -F)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MetaOptions {
+    pub owner: bool,
+    pub group: bool,
+    pub xattrs: bool,
+    pub acls: bool,
+    pub flags: bool,
+}
+
+impl MetaOptions {
+    /// Everything; used by receivers that apply whatever the sender chose
+    pub fn all() -> Self {
+        Self {
+            owner: true,
+            group: true,
+            xattrs: true,
+            acls: true,
+            flags: true,
+        }
+    }
+
+    /// Whether any per-entry metadata block is wanted
+    pub fn any(&self) -> bool {
+        self.owner || self.group || self.xattrs || self.acls || self.flags
+    }
+}
+
+/// Permission bits of a path for FILE_LIST (0o644 if it can't be read)
+pub fn file_mode(path: &Path) -> u32 {
+    std::fs::symlink_metadata(path)
+        .map(|m| m.mode() & 0o7777)
+        .unwrap_or(0o644)
+}
+
+/// Build the metadata block for a scanned entry, or None if there is nothing to send
+pub fn collect(entry: &FileEntry, opts: &MetaOptions) -> Option<EntryMeta> {
+    if !opts.any() {
+        return None;
+    }
+
+    let mut meta = EntryMeta::default();
+    if opts.owner || opts.group {
+        if let Ok(stat) = std::fs::symlink_metadata(&*entry.path) {
+            meta.uid = opts.owner.then(|| stat.uid());
+            meta.gid = opts.group.then(|| stat.gid());
+        }
+    }
+    if opts.xattrs {
+        if let Some(ref xattrs) = entry.xattrs {
+            meta.xattrs = xattrs.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+            meta.xattrs.sort();
+        }
+    }
+    if opts.acls {
+        meta.acls = entry
+            .acls
+            .as_ref()
+            .and_then(|acls| String::from_utf8(acls.clone()).ok())
+            .filter(|text| !text.trim().is_empty());
+    }
+    if opts.flags {
+        meta.bsd_flags = entry.bsd_flags.filter(|flags| *flags != 0);
+    }
+
+    (!meta.is_empty()).then_some(meta)
+}
+
+/// Apply received metadata to a written path, limited to `opts`
+///
+/// Ownership is only changed as far as the process may: the uid only as
+/// root, the gid to any group we belong to. `security.*` and `trusted.*`
+/// xattrs grant privileges much like ownership does, so they are applied
+/// only when `opts.owner` is set. BSD flags go last, since `uchg` and
+/// friends would block every other change.
+pub fn apply(path: &Path, meta: &EntryMeta, opts: &MetaOptions) {
+    let uid = meta.uid.filter(|_| opts.owner && is_root());
+    let gid = meta.gid.filter(|_| opts.group);
+    if uid.is_some() || gid.is_some() {
+        if let Err(e) = std::os::unix::fs::lchown(path, uid, gid) {
+            tracing::debug!("Failed to chown {}: {}", path.display(), e);
+        }
+    }
+
+    if opts.xattrs {
+        for (name, value) in &meta.xattrs {
+            let privileged = name.starts_with("security.") || name.starts_with("trusted.");
+            if privileged && !opts.owner {
+                tracing::debug!("Not setting xattr {} on {}", name, path.display());
+                continue;
+            }
+            if let Err(e) = xattr::set(path, name, value) {
+                tracing::warn!("Failed to set xattr {} on {}: {}", name, path.display(), e);
+            }
+        }
+    }
+
+    if opts.acls {
+        if let Some(ref acls) = meta.acls {
+            apply_acls(path, acls);
+        }
+    }
+
+    if opts.flags {
+        if let Some(flags) = meta.bsd_flags {
+            apply_bsd_flags(path, flags);
+        }
+    }
+}
+
+/// Apply a received file's mode and metadata block (pull receivers)
+///
+/// setuid/setgid/sticky bits are kept only with `opts.owner`, as on the
+/// server side.
+pub fn apply_file(path: &Path, entry: &FileListEntry, opts: &MetaOptions) {
+    use std::os::unix::fs::PermissionsExt;
+
+    if entry.mode != 0 {
+        let mask = if opts.owner { 0o7777 } else { 0o777 };
+        let perms = std::fs::Permissions::from_mode(entry.mode & mask);
+        if let Err(e) = std::fs::set_permissions(path, perms) {
+            tracing::debug!("Failed to set permissions on {}: {}", path.display(), e);
+        }
+    }
+    if let Some(ref meta) = entry.meta {
+        apply(path, meta, opts);
+    }
+}
+
+fn is_root() -> bool {
+    // SAFETY: geteuid has no preconditions
+    unsafe { libc::geteuid() == 0 }
+}
+
+#[cfg(feature = "acl")]
+fn apply_acls(path: &Path, acls: &str) {
+    use exacl::{setfacl, AclEntry};
+    use std::str::FromStr;
+
+    let mut entries = Vec::new();
+    for line in acls.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        match AclEntry::from_str(line) {
+            Ok(entry) => entries.push(entry),
+            Err(e) => tracing::warn!(
+                "Failed to parse ACL entry '{}' for {}: {}",
+                line,
+                path.display(),
+                e
+            ),
+        }
+    }
+    if !entries.is_empty() {
+        if let Err(e) = setfacl(&[path], &entries, None) {
+            tracing::warn!("Failed to set ACLs on {}: {}", path.display(), e);
+        }
+    }
+}
+
+#[cfg(not(feature = "acl"))]
+fn apply_acls(path: &Path, _acls: &str) {
+    tracing::debug!(
+        "Built without ACL support, not setting ACLs on {}",
+        path.display()
+    );
+}
+
+#[cfg(target_os = "macos")]
+fn apply_bsd_flags(path: &Path, flags: u32) {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let Ok(c_path) = CString::new(path.as_os_str().as_bytes()) else {
+        return;
+    };
+    // SAFETY: c_path is a valid NUL-terminated string
+    if unsafe { libc::lchflags(c_path.as_ptr(), flags as _) } != 0 {
+        tracing::warn!(
+            "Failed to set BSD flags on {}: {}",
+            path.display(),
+            std::io::Error::last_os_error()
+        );
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+fn apply_bsd_flags(_path: &Path, _flags: u32) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sync::scanner::scan_entry;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_collect_respects_options() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("file");
+        std::fs::write(&path, b"data").unwrap();
+        let entry = scan_entry(temp.path(), &path).unwrap();
+
+        assert!(collect(&entry, &MetaOptions::default()).is_none());
+
+        let meta = collect(
+            &entry,
+            &MetaOptions {
+                group: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(meta.uid, None);
+        assert_eq!(meta.gid, Some(std::fs::metadata(&path).unwrap().gid()));
+    }
+
+    #[test]
+    fn test_apply_sets_user_xattrs_only_without_owner() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("file");
+        std::fs::write(&path, b"data").unwrap();
+        if xattr::set(&path, "user.sy.probe", b"1").is_err() {
+            // Filesystem without user xattrs (e.g. some tmpfs setups)
+            return;
+        }
+
+        let meta = EntryMeta {
+            xattrs: vec![
+                ("user.sy.test".to_string(), b"value".to_vec()),
+                ("trusted.sy.test".to_string(), b"nope".to_vec()),
+            ],
+            ..Default::default()
+        };
+        let opts = MetaOptions {
+            xattrs: true,
+            ..Default::default()
+        };
+        apply(&path, &meta, &opts);
+
+        assert_eq!(
+            xattr::get(&path, "user.sy.test").unwrap(),
+            Some(b"value".to_vec())
+        );
+        assert_eq!(xattr::get(&path, "trusted.sy.test").unwrap_or(None), None);
+    }
+}
@@ -2,23 +2,36 @@
 // The code appears "dead" to the compiler since it's only used at runtime
 #![allow(dead_code)]
 
+pub mod confine;
 pub mod daemon;
+pub mod digest;
 pub mod handler;
+pub mod layout;
+pub mod meta;
+pub mod modules;
+pub mod partial;
 pub mod protocol;
+pub mod stream;
+pub mod tcp;
 
 use anyhow::Result;
 use handler::{compute_checksum_response, ServerHandler};
+use meta::MetaOptions;
 use protocol::{
-    Action, ChecksumReq, ChecksumResp, DeltaData, ErrorMessage, FileData, FileList, FileListEntry,
-    Hello, MessageType, MkdirBatch, MkdirBatchAck, SymlinkBatch, SymlinkBatchAck, SymlinkEntry,
-    HELLO_FLAG_PULL, PROTOCOL_VERSION,
+    Action, ChecksumReq, ChecksumResp, DeltaData, EntryMeta, ErrorMessage, FileData, FileList,
+    FileListEntry, Frame, Hello, MessageType, MkdirBatch, MkdirBatchAck, Negotiated, SymlinkBatch,
+    SymlinkBatchAck, SymlinkEntry, CAP_FILE_CHECKSUM, CAP_HARDLINKS, CAP_META_BLOCK, CAP_RESUME,
+    CAP_SPARSE, CAP_STREAM_ZSTD, HELLO_FLAG_PULL, MSG_PING, MSG_PONG, STATUS_CHECKSUM_MISMATCH,
+    VERIFY_RETRIES,
 };
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use stream::{StreamReader, StreamWriter};
 use tokio::io::{self, AsyncReadExt, AsyncWriteExt};
 use tokio::sync::mpsc;
 
-use crate::sync::scanner::{self, ScanOptions};
+use crate::sync::scanner::{self, FileEntry, ScanOptions};
+use layout::{ChunkPlan, HardlinkGroups};
 
 /// Expand tilde (~) in paths to the user's home directory.
 fn expand_tilde(path: &Path) -> PathBuf {
@@ -38,8 +51,9 @@ fn expand_tilde(path: &Path) -> PathBuf {
 }
 
 pub async fn run_server() -> Result<()> {
-    // Parse args: sy --server <path>
+    // Parse args: sy --server [--safe-links] <path>
     let args: Vec<String> = std::env::args().collect();
+    let safe_links = args.iter().any(|a| a == "--safe-links");
     let raw_path = args
         .last()
         .map(PathBuf::from)
@@ -52,14 +66,14 @@ pub async fn run_server() -> Result<()> {
         std::fs::create_dir_all(&root_path)?;
     }
 
-    let mut stdin = io::stdin();
-    let mut stdout = io::stdout();
+    let mut stdin = StreamReader::new(io::stdin());
+    let mut stdout = StreamWriter::new(io::stdout());
 
-    let mut handler = ServerHandler::new(root_path);
+    let mut handler = ServerHandler::new(root_path).with_safe_links(safe_links);
 
     // Handshake
-    let _len = stdin.read_u32().await?;
-    let type_byte = stdin.read_u8().await?;
+    let frame = Frame::read(&mut stdin).await?;
+    let type_byte = frame.msg_type;
 
     if type_byte != MessageType::Hello as u8 {
         let err = ErrorMessage {
@@ -70,32 +84,42 @@ pub async fn run_server() -> Result<()> {
         return Ok(());
     }
 
-    let hello = Hello::read(&mut stdin).await?;
-
-    if hello.version != PROTOCOL_VERSION {
-        let err = ErrorMessage {
-            code: 1,
-            message: format!(
-                "Version mismatch: client {}, server {}",
-                hello.version, PROTOCOL_VERSION
-            ),
-        };
-        err.write(&mut stdout).await?;
-        return Ok(());
-    }
+    let hello = Hello::read_frame(&mut stdin, &frame).await?;
+
+    let negotiated = match hello.negotiate() {
+        Ok(negotiated) => negotiated,
+        Err(e) => {
+            let err = ErrorMessage {
+                code: 1,
+                message: format!("Version mismatch: {}", e),
+            };
+            err.write(&mut stdout).await?;
+            return Ok(());
+        }
+    };
+    tracing::debug!(
+        "Negotiated protocol v{} (features {:#x})",
+        negotiated.version,
+        negotiated.features
+    );
 
     // Send HELLO response
-    let resp = Hello {
-        version: PROTOCOL_VERSION,
-        flags: 0,
-        capabilities: vec![],
-    };
-    resp.write(&mut stdout).await?;
+    Hello::new(0).write(&mut stdout).await?;
     stdout.flush().await?;
 
+    // Everything after the handshake may travel as one zstd stream
+    if negotiated.has(CAP_STREAM_ZSTD) {
+        stdin.start_zstd()?;
+        stdout.start_zstd()?;
+    }
+
+    handler = handler
+        .with_resume(negotiated.has(CAP_RESUME))
+        .with_checksums(negotiated.has(CAP_FILE_CHECKSUM));
+
     // Check if client requested PULL mode (server sends files to client)
     if hello.flags & HELLO_FLAG_PULL != 0 {
-        return run_server_pull_mode(&handler.root_path, &mut stdin, &mut stdout).await;
+        return run_server_pull_mode(&handler.root_path, negotiated, &mut stdin, &mut stdout).await;
     }
 
     // Shared state for concurrent CHECKSUM_REQ handling
@@ -125,18 +149,19 @@ pub async fn run_server() -> Result<()> {
 
             // Read and handle incoming messages
             len_result = stdin.read_u32() => {
-                let _len = match len_result {
+                let len = match len_result {
                     Ok(len) => len,
                     Err(_) => break, // EOF or error, exit loop
                 };
-                let type_byte = stdin.read_u8().await?;
+                let frame = Frame { len, msg_type: stdin.read_u8().await? };
+                let type_byte = frame.msg_type;
 
                 match MessageType::from_u8(type_byte) {
                     Some(MessageType::FileList) => {
                         // Wait for all pending checksums before processing file list
                         drain_pending_checksums(&mut checksum_rx, &mut pending_checksum_count, &mut stdout).await?;
 
-                        let list = protocol::FileList::read(&mut stdin).await?;
+                        let list = protocol::FileList::read_frame(&mut stdin, &frame).await?;
                         // Store file list for concurrent checksum handling
                         file_list = Some(Arc::new(list.entries.clone()));
                         handler.handle_file_list(list, &mut stdout).await?;
@@ -144,37 +169,37 @@ pub async fn run_server() -> Result<()> {
 
                     Some(MessageType::MkdirBatch) => {
                         drain_pending_checksums(&mut checksum_rx, &mut pending_checksum_count, &mut stdout).await?;
-                        let batch = MkdirBatch::read(&mut stdin).await?;
+                        let batch = MkdirBatch::read_frame(&mut stdin, &frame).await?;
                         handler.handle_mkdir_batch(batch, &mut stdout).await?;
                     }
 
                     Some(MessageType::SymlinkBatch) => {
                         drain_pending_checksums(&mut checksum_rx, &mut pending_checksum_count, &mut stdout).await?;
-                        let batch = SymlinkBatch::read(&mut stdin).await?;
+                        let batch = SymlinkBatch::read_frame(&mut stdin, &frame).await?;
                         handler.handle_symlink_batch(batch, &mut stdout).await?;
                     }
 
                     Some(MessageType::FileData) => {
                         drain_pending_checksums(&mut checksum_rx, &mut pending_checksum_count, &mut stdout).await?;
-                        let data = protocol::FileData::read(&mut stdin).await?;
+                        let data = protocol::FileData::read_frame(&mut stdin, &frame).await?;
                         handler.handle_file_data(data, &mut stdout).await?;
                     }
 
                     Some(MessageType::ChecksumReq) => {
-                        let req = ChecksumReq::read(&mut stdin).await?;
+                        let req = ChecksumReq::read_frame(&mut stdin, &frame).await?;
 
                         // Use concurrent handling if we have file list
                         if let Some(ref fl) = file_list {
                             let fl = Arc::clone(fl);
                             let rp = Arc::clone(&root_path);
-                            let index = req.index;
-                            let block_size = req.block_size as usize;
+                            let cache = handler.chunk_cache(&req);
+                            let basis = handler.fuzzy_basis(req.index);
                             let tx = checksum_tx.clone();
 
                             // Spawn computation task - runs concurrently
                             pending_checksum_count += 1;
                             tokio::spawn(async move {
-                                match compute_checksum_response(index, block_size, &fl, &rp).await {
+                                match compute_checksum_response(req, &fl, &rp, basis, cache).await {
                                     Ok(resp) => {
                                         let _ = tx.send(resp).await;
                                     }
@@ -192,12 +217,12 @@ pub async fn run_server() -> Result<()> {
                     Some(MessageType::DeltaData) => {
                         // Wait for all pending checksums before handling delta
                         drain_pending_checksums(&mut checksum_rx, &mut pending_checksum_count, &mut stdout).await?;
-                        let delta = DeltaData::read(&mut stdin).await?;
+                        let delta = DeltaData::read_frame(&mut stdin, &frame).await?;
                         handler.handle_delta_data(delta, &mut stdout).await?;
                     }
 
                     Some(MessageType::Error) => {
-                        let err = protocol::ErrorMessage::read(&mut stdin).await?;
+                        let err = protocol::ErrorMessage::read_frame(&mut stdin, &frame).await?;
                         tracing::error!("Received error: {}", err.message);
                         return Err(anyhow::anyhow!("Remote error: {}", err.message));
                     }
@@ -205,6 +230,8 @@ pub async fn run_server() -> Result<()> {
                     Some(msg_type) => {
                         drain_pending_checksums(&mut checksum_rx, &mut pending_checksum_count, &mut stdout).await?;
                         tracing::warn!("Unhandled message type: {:?}", msg_type);
+                        let mut payload = frame.payload(&mut stdin, msg_type.max_len())?;
+                        io::copy(&mut payload, &mut io::sink()).await?;
                         let err = ErrorMessage {
                             code: 1,
                             message: format!("Unhandled message type: 0x{:02X}", type_byte),
@@ -213,6 +240,14 @@ pub async fn run_server() -> Result<()> {
                         stdout.flush().await?;
                     }
 
+                    // PING - keepalive from long-lived sessions (watch mode)
+                    None if type_byte == MSG_PING => {
+                        frame.expect_empty()?;
+                        stdout.write_u32(0).await?;
+                        stdout.write_u8(MSG_PONG).await?;
+                        stdout.flush().await?;
+                    }
+
                     None => {
                         drain_pending_checksums(&mut checksum_rx, &mut pending_checksum_count, &mut stdout).await?;
                         tracing::error!("Unknown message type: 0x{:02X}", type_byte);
@@ -260,9 +295,220 @@ async fn drain_pending_checksums<W: AsyncWriteExt + Unpin>(
     Ok(())
 }
 
+/// A file offered in PULL mode
+pub(crate) struct FileRow {
+    rel_path: String,
+    abs_path: PathBuf,
+    size: u64,
+    mtime: i64,
+    mode: u32,
+    meta: Option<EntryMeta>,
+    inode: Option<u64>,
+    nlink: u64,
+    sparse: bool,
+}
+
+impl FileRow {
+    pub(crate) fn new(
+        rel_path: String,
+        entry: &FileEntry,
+        mtime: i64,
+        meta: Option<EntryMeta>,
+    ) -> Self {
+        Self {
+            rel_path,
+            abs_path: entry.path.to_path_buf(),
+            size: entry.size,
+            mtime,
+            mode: meta::file_mode(&entry.path),
+            meta,
+            inode: entry.inode,
+            nlink: entry.nlink,
+            sparse: entry.is_sparse,
+        }
+    }
+}
+
+/// FILE_LIST entries for PULL rows; with CAP_HARDLINKS, later names of an
+/// inode become links to the first
+pub(crate) fn pull_file_list(files: &[FileRow], negotiated: Negotiated) -> Vec<FileListEntry> {
+    let mut groups = HardlinkGroups::new();
+    let can_link = negotiated.has(CAP_HARDLINKS);
+    files
+        .iter()
+        .enumerate()
+        .map(|(idx, row)| FileListEntry {
+            path: row.rel_path.clone(),
+            size: row.size,
+            mtime: row.mtime,
+            mode: row.mode,
+            flags: 0,
+            symlink_target: None,
+            hardlink_to: can_link
+                .then(|| groups.leader(row.inode, row.nlink, idx as u32))
+                .flatten(),
+            meta: row.meta.clone(),
+        })
+        .collect()
+}
+
+/// PULL step 3: send every requested file's data, then collect the FILE_DONEs
+///
+/// Hard links carry no data; the client makes them itself. Sparse files go
+/// out as their data regions when CAP_SPARSE was negotiated; with CAP_RESUME
+/// files are split, and continue from the client's partial where it has one.
+///
+/// With CAP_FILE_CHECKSUM files end with the digest the client asked for in
+/// its decision, and every file answered `STATUS_CHECKSUM_MISMATCH` is sent
+/// again whole, for up to `VERIFY_RETRIES` further rounds; the client knows
+/// the same set from the statuses it sent.
+pub(crate) async fn send_pull_files<R, W>(
+    files: &[FileRow],
+    entries: &[FileListEntry],
+    ack: &protocol::FileListAck,
+    negotiated: Negotiated,
+    reader: &mut R,
+    writer: &mut W,
+) -> Result<()>
+where
+    R: AsyncReadExt + Unpin,
+    W: AsyncWriteExt + Unpin,
+{
+    let can_retry = negotiated.has(CAP_FILE_CHECKSUM);
+    let mut pending: Vec<&protocol::Decision> = ack
+        .decisions
+        .iter()
+        .filter(|d| d.action != Action::Skip)
+        .collect();
+
+    for round in 0..=VERIFY_RETRIES {
+        if round > 0 {
+            if !can_retry || pending.is_empty() {
+                break;
+            }
+            tracing::warn!(
+                "Resending {} files that failed verification (round {})",
+                pending.len(),
+                round
+            );
+        }
+
+        let mut files_sent: Vec<&protocol::Decision> = Vec::new();
+        for decision in pending {
+            let idx = decision.index as usize;
+            let (Some(row), Some(entry)) = (files.get(idx), entries.get(idx)) else {
+                continue;
+            };
+            if entry.is_hardlink() {
+                continue;
+            }
+            // A retry starts over rather than trusting the partial again
+            let resume_from = if round == 0 { decision.resume_from } else { 0 };
+            let plan = pull_plan(row, decision, resume_from, negotiated);
+            if send_pull_file(row, decision.index, plan, writer).await? {
+                files_sent.push(decision);
+            }
+        }
+
+        // Flush once after sending all files
+        writer.flush().await?;
+
+        // Collect all FILE_DONE responses
+        pending = Vec::new();
+        for decision in files_sent {
+            let frame = Frame::read(reader).await?;
+            let type_byte = frame.msg_type;
+            if type_byte != MessageType::FileDone as u8 {
+                return Err(anyhow::anyhow!(
+                    "Expected FILE_DONE, got 0x{:02X}",
+                    type_byte
+                ));
+            }
+            let done = protocol::FileDone::read_frame(reader, &frame).await?;
+            if done.status == STATUS_CHECKSUM_MISMATCH {
+                pending.push(decision);
+            }
+        }
+    }
+
+    for decision in &pending {
+        if let Some(row) = files.get(decision.index as usize) {
+            tracing::error!("{} failed verification", row.rel_path);
+        }
+    }
+
+    Ok(())
+}
+
+/// How a PULL row is read into chunks for the client's decision
+fn pull_plan(
+    row: &FileRow,
+    decision: &protocol::Decision,
+    resume_from: u64,
+    negotiated: Negotiated,
+) -> ChunkPlan {
+    let can_resume = negotiated.has(CAP_RESUME);
+    let sparse = negotiated.has(CAP_SPARSE) && row.sparse;
+    ChunkPlan {
+        sparse,
+        zstd: false,
+        split: layout::final_chunks(negotiated),
+        // A sparse partial is already full length; send it again
+        resume_from: if can_resume && !sparse {
+            resume_from
+        } else {
+            0
+        },
+        digest: if negotiated.has(CAP_FILE_CHECKSUM) {
+            decision.digest
+        } else {
+            Default::default()
+        },
+    }
+}
+
+/// Send one file's FILE_DATA (no flush); false if it could not be read
+async fn send_pull_file<W: AsyncWriteExt + Unpin>(
+    row: &FileRow,
+    index: u32,
+    plan: ChunkPlan,
+    writer: &mut W,
+) -> Result<bool> {
+    // Read file data (use spawn_blocking for async compatibility)
+    let abs_path = row.abs_path.clone();
+    let chunks =
+        match tokio::task::spawn_blocking(move || layout::read_chunks(&abs_path, plan)).await {
+            Ok(Ok(chunks)) => chunks,
+            Ok(Err(e)) => {
+                tracing::warn!("Failed to read {}: {}", row.abs_path.display(), e);
+                return Ok(false);
+            }
+            Err(e) => {
+                tracing::warn!("Task join error reading {}: {}", row.abs_path.display(), e);
+                return Ok(false);
+            }
+        };
+
+    // Send FILE_DATA (pipelined - no flush/wait per file)
+    for chunk in chunks {
+        let file_data = FileData {
+            index,
+            offset: chunk.offset,
+            flags: chunk.flags,
+            data: chunk.data,
+        };
+        file_data.write(writer).await?;
+    }
+    Ok(true)
+}
+
 /// PULL mode: Server scans source and sends files to client
+///
+/// Entries carry a metadata block when the client negotiated CAP_META_BLOCK;
+/// the client applies the parts it was asked to preserve.
 pub async fn run_server_pull_mode<R, W>(
     root_path: &Path,
+    negotiated: Negotiated,
     stdin: &mut R,
     stdout: &mut W,
 ) -> Result<()>
@@ -270,6 +516,8 @@ where
     R: AsyncReadExt + Unpin,
     W: AsyncWriteExt + Unpin,
 {
+    let send_meta = negotiated.has(CAP_META_BLOCK);
+
     // Scan source directory
     let scan_opts = ScanOptions::default();
     let root = root_path.to_path_buf();
@@ -280,13 +528,18 @@ where
 
     // Separate entries by type
     let mut directories: Vec<String> = Vec::new();
-    let mut files: Vec<(String, PathBuf, u64, i64, u32)> = Vec::new(); // (rel_path, abs_path, size, mtime, mode)
+    let mut files: Vec<FileRow> = Vec::new();
     let mut symlinks: Vec<SymlinkEntry> = Vec::new();
 
     // Check if root_path is a single file (not a directory)
     let is_single_file = root_path.is_file();
 
     for entry in entries {
+        // Partials of interrupted receives are not part of the tree
+        if partial::is_partial(&entry.path) {
+            continue;
+        }
+
         // For single file sources, don't strip prefix - use just the filename
         let rel_path_str = if is_single_file {
             // Single file: use just the filename as the relative path
@@ -324,13 +577,12 @@ where
                     }
                 }
             } else {
-                files.push((
-                    path_str.to_string(),
-                    entry.path.to_path_buf(),
-                    entry.size,
-                    mtime,
-                    0o644,
-                ));
+                let meta = if send_meta {
+                    meta::collect(&entry, &MetaOptions::all())
+                } else {
+                    None
+                };
+                files.push(FileRow::new(path_str, &entry, mtime, meta));
             }
         }
     }
@@ -343,101 +595,36 @@ where
     stdout.flush().await?;
 
     // Wait for MKDIR_BATCH_ACK
-    let _len = stdin.read_u32().await?;
-    let type_byte = stdin.read_u8().await?;
+    let frame = Frame::read(stdin).await?;
+    let type_byte = frame.msg_type;
     if type_byte != MessageType::MkdirBatchAck as u8 {
         return Err(anyhow::anyhow!(
             "Expected MKDIR_BATCH_ACK, got 0x{:02X}",
             type_byte
         ));
     }
-    let _ack = MkdirBatchAck::read(stdin).await?;
+    let _ack = MkdirBatchAck::read_frame(stdin, &frame).await?;
 
     // Step 2: Send file list (FILE_LIST)
-    let file_entries: Vec<FileListEntry> = files
-        .iter()
-        .map(|(rel_path, _, size, mtime, mode)| FileListEntry {
-            path: rel_path.clone(),
-            size: *size,
-            mtime: *mtime,
-            mode: *mode,
-            flags: 0,
-            symlink_target: None,
-        })
-        .collect();
-
     let file_list = FileList {
-        entries: file_entries,
+        entries: pull_file_list(&files, negotiated),
     };
     file_list.write(stdout).await?;
     stdout.flush().await?;
 
     // Wait for FILE_LIST_ACK with decisions
-    let _len = stdin.read_u32().await?;
-    let type_byte = stdin.read_u8().await?;
+    let frame = Frame::read(stdin).await?;
+    let type_byte = frame.msg_type;
     if type_byte != MessageType::FileListAck as u8 {
         return Err(anyhow::anyhow!(
             "Expected FILE_LIST_ACK, got 0x{:02X}",
             type_byte
         ));
     }
-    let ack = protocol::FileListAck::read(stdin).await?;
+    let ack = protocol::FileListAck::read_frame(stdin, &frame).await?;
 
     // Step 3: Send files that client requested (pipelined - send all, then collect ACKs)
-    let mut files_sent: Vec<u32> = Vec::new();
-
-    for decision in &ack.decisions {
-        if decision.action == Action::Skip {
-            continue;
-        }
-
-        let idx = decision.index as usize;
-        if idx >= files.len() {
-            continue;
-        }
-
-        let (_, abs_path, _, _, _) = &files[idx];
-
-        // Read file data (use spawn_blocking for async compatibility)
-        let abs_path_clone = abs_path.clone();
-        let data = match tokio::task::spawn_blocking(move || std::fs::read(&abs_path_clone)).await {
-            Ok(Ok(d)) => d,
-            Ok(Err(e)) => {
-                tracing::warn!("Failed to read {}: {}", abs_path.display(), e);
-                continue;
-            }
-            Err(e) => {
-                tracing::warn!("Task join error reading {}: {}", abs_path.display(), e);
-                continue;
-            }
-        };
-
-        // Send FILE_DATA (pipelined - no flush/wait per file)
-        let file_data = FileData {
-            index: decision.index,
-            offset: 0,
-            flags: 0,
-            data,
-        };
-        file_data.write(stdout).await?;
-        files_sent.push(decision.index);
-    }
-
-    // Flush once after sending all files
-    stdout.flush().await?;
-
-    // Collect all FILE_DONE responses
-    for _ in &files_sent {
-        let _len = stdin.read_u32().await?;
-        let type_byte = stdin.read_u8().await?;
-        if type_byte != MessageType::FileDone as u8 {
-            return Err(anyhow::anyhow!(
-                "Expected FILE_DONE, got 0x{:02X}",
-                type_byte
-            ));
-        }
-        let _done = protocol::FileDone::read(stdin).await?;
-    }
+    send_pull_files(&files, &file_list.entries, &ack, negotiated, stdin, stdout).await?;
 
     // Step 4: Send symlinks (SYMLINK_BATCH)
     if !symlinks.is_empty() {
@@ -448,15 +635,15 @@ where
         stdout.flush().await?;
 
         // Wait for SYMLINK_BATCH_ACK
-        let _len = stdin.read_u32().await?;
-        let type_byte = stdin.read_u8().await?;
+        let frame = Frame::read(stdin).await?;
+        let type_byte = frame.msg_type;
         if type_byte != MessageType::SymlinkBatchAck as u8 {
             return Err(anyhow::anyhow!(
                 "Expected SYMLINK_BATCH_ACK, got 0x{:02X}",
                 type_byte
             ));
         }
-        let _ack = SymlinkBatchAck::read(stdin).await?;
+        let _ack = SymlinkBatchAck::read_frame(stdin, &frame).await?;
     }
 
     Ok(())
@@ -0,0 +1,407 @@
+//! Named daemon modules and their access control
+//!
+//! Without a config the daemon serves whatever path a client names in
+//! SET_ROOT. With `--daemon-config`, clients address `module/sub/path`
+//! instead and the daemon only serves the declared module trees:
+//!
+//! ```toml
+//! [modules.backups]
+//! path = "/srv/backups"
+//! comment = "Nightly host backups"
+//! write_only = true
+//! allow = ["ci", "alice"]     # token names (TCP) or local users (Unix socket)
+//! uid = 1001                  # chown everything written to this uid/gid
+//! gid = 1001
+//! max_connections = 4
+//! filter = ["- *.tmp", "- .cache/"]
+//!
+//! [modules.releases]
+//! path = "/srv/releases"
+//! read_only = true
+//! ```
+//!
+//! Requested paths are resolved after following symlinks; anything that
+//! lands outside the module root is rejected.
+
+#![cfg(unix)]
+
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use super::daemon::expand_tilde;
+use crate::filter::FilterEngine;
+
+/// Daemon config file: a table of named modules
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DaemonConfig {
+    #[serde(default)]
+    pub modules: BTreeMap<String, ModuleConfig>,
+}
+
+/// One `[modules.<name>]` section
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ModuleConfig {
+    /// Directory served by the module (supports ~ expansion)
+    pub path: PathBuf,
+    /// Free-form description, shown in the daemon log
+    #[serde(default)]
+    pub comment: Option<String>,
+    /// Clients may only pull from the module
+    #[serde(default)]
+    pub read_only: bool,
+    /// Clients may only push to the module
+    #[serde(default)]
+    pub write_only: bool,
+    /// Token names (TCP) or local user names (Unix socket) allowed in; empty = any client
+    #[serde(default)]
+    pub allow: Vec<String>,
+    /// Owner given to everything written through the module
+    #[serde(default)]
+    pub uid: Option<u32>,
+    /// Group given to everything written through the module
+    #[serde(default)]
+    pub gid: Option<u32>,
+    /// Maximum concurrent sessions for this module
+    #[serde(default)]
+    pub max_connections: Option<usize>,
+    /// rsync-style filter rules ("- pattern" / "+ pattern"), relative to the module root
+    #[serde(default)]
+    pub filter: Vec<String>,
+    /// Refuse incoming symlinks that point outside the module (default: true)
+    #[serde(default)]
+    pub safe_links: Option<bool>,
+}
+
+/// A module ready to serve: canonical root, compiled filter and connection slots
+#[derive(Debug)]
+struct Module {
+    config: ModuleConfig,
+    root: PathBuf,
+    filter: Option<FilterEngine>,
+    slots: Option<Arc<Semaphore>>,
+}
+
+/// All modules served by a daemon
+#[derive(Debug)]
+pub struct ModuleTable {
+    modules: BTreeMap<String, Module>,
+}
+
+/// Module filter rules, applied to paths relative to a session root
+#[derive(Debug, Clone)]
+pub struct ModuleFilter {
+    engine: FilterEngine,
+    /// Session root relative to the module root
+    prefix: PathBuf,
+}
+
+impl ModuleFilter {
+    /// Check whether a session-relative path is hidden by the module's rules
+    ///
+    /// A path is also hidden when any of its parent directories is.
+    pub fn excludes(&self, rel_path: &Path, is_dir: bool) -> bool {
+        let path = self.prefix.join(rel_path);
+        if self.engine.should_exclude(&path, is_dir) {
+            return true;
+        }
+        path.ancestors()
+            .skip(1)
+            .filter(|a| !a.as_os_str().is_empty())
+            .any(|a| self.engine.should_exclude(a, true))
+    }
+}
+
+/// A client's resolved module path, holding one of the module's connection slots
+#[derive(Debug)]
+pub struct ModuleSession {
+    pub module: String,
+    /// Directory the session reads from or writes to
+    pub root: PathBuf,
+    pub filter: Option<ModuleFilter>,
+    pub uid: Option<u32>,
+    pub gid: Option<u32>,
+    pub safe_links: bool,
+    _slot: Option<OwnedSemaphorePermit>,
+}
+
+impl ModuleTable {
+    /// Load and validate a daemon config file
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read daemon config: {}", path.display()))?;
+        Self::parse(&contents).with_context(|| format!("Invalid daemon config: {}", path.display()))
+    }
+
+    /// Parse a daemon config; every module path must already exist
+    pub fn parse(contents: &str) -> Result<Self> {
+        let config: DaemonConfig = toml::from_str(contents)?;
+        if config.modules.is_empty() {
+            anyhow::bail!("no [modules.<name>] sections defined");
+        }
+
+        let mut modules = BTreeMap::new();
+        for (name, config) in config.modules {
+            if name.is_empty() || name.contains('/') || name == "." || name == ".." {
+                anyhow::bail!("invalid module name '{}'", name);
+            }
+            if config.read_only && config.write_only {
+                anyhow::bail!("module '{}' cannot be both read_only and write_only", name);
+            }
+            if config.max_connections == Some(0) {
+                anyhow::bail!("module '{}': max_connections must be at least 1", name);
+            }
+
+            let root = expand_tilde(&config.path);
+            let root = std::fs::canonicalize(&root)
+                .with_context(|| format!("module '{}': cannot access {}", name, root.display()))?;
+            if !root.is_dir() {
+                anyhow::bail!("module '{}': {} is not a directory", name, root.display());
+            }
+
+            let filter = if config.filter.is_empty() {
+                None
+            } else {
+                let mut engine = FilterEngine::new();
+                for rule in &config.filter {
+                    engine
+                        .add_rule(rule)
+                        .with_context(|| format!("module '{}': bad filter rule", name))?;
+                }
+                Some(engine)
+            };
+            let slots = config.max_connections.map(|n| Arc::new(Semaphore::new(n)));
+
+            modules.insert(
+                name,
+                Module {
+                    config,
+                    root,
+                    filter,
+                    slots,
+                },
+            );
+        }
+
+        Ok(Self { modules })
+    }
+
+    /// Module names with their roots and comments, for the startup log
+    pub fn describe(&self) -> impl Iterator<Item = (&str, &Path, Option<&str>)> {
+        self.modules.iter().map(|(name, module)| {
+            (
+                name.as_str(),
+                module.root.as_path(),
+                module.config.comment.as_deref(),
+            )
+        })
+    }
+
+    /// Resolve a client's `module/sub/path` request
+    ///
+    /// `user` is the authenticated token name or the local user behind a
+    /// Unix socket connection; `pull` is the direction the client asked for.
+    /// The error string is sent back to the client as-is.
+    pub fn resolve(
+        &self,
+        request: &str,
+        user: Option<&str>,
+        pull: bool,
+    ) -> std::result::Result<ModuleSession, String> {
+        let request = request.trim_start_matches('/');
+        let (name, sub) = request.split_once('/').unwrap_or((request, ""));
+        let module = self
+            .modules
+            .get(name)
+            .ok_or_else(|| format!("Unknown module '{}'", name))?;
+
+        if !module.config.allow.is_empty()
+            && !user.is_some_and(|u| module.config.allow.iter().any(|a| a == u))
+        {
+            return Err(format!("Access to module '{}' denied", name));
+        }
+        if pull && module.config.write_only {
+            return Err(format!("Module '{}' is write-only", name));
+        }
+        if !pull && module.config.read_only {
+            return Err(format!("Module '{}' is read-only", name));
+        }
+
+        let sub = Path::new(sub);
+        if sub
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir))
+        {
+            return Err(format!("Path escapes module '{}': {}", name, request));
+        }
+        let root = resolve_within(&module.root, sub)
+            .ok_or_else(|| format!("Path escapes module '{}': {}", name, request))?;
+
+        let slot = match &module.slots {
+            Some(slots) => Some(
+                Arc::clone(slots)
+                    .try_acquire_owned()
+                    .map_err(|_| format!("Module '{}' is at its connection limit", name))?,
+            ),
+            None => None,
+        };
+
+        let prefix: PathBuf = sub
+            .components()
+            .filter(|c| matches!(c, Component::Normal(_)))
+            .collect();
+        Ok(ModuleSession {
+            module: name.to_string(),
+            root,
+            filter: module
+                .filter
+                .clone()
+                .map(|engine| ModuleFilter { engine, prefix }),
+            uid: module.config.uid,
+            gid: module.config.gid,
+            safe_links: module.config.safe_links.unwrap_or(true),
+            _slot: slot,
+        })
+    }
+}
+
+/// Join `sub` onto `root`, following symlinks in the part that exists
+///
+/// Returns None if the existing part resolves outside `root`. The missing
+/// tail contains only normal components, so it cannot climb back out.
+fn resolve_within(root: &Path, sub: &Path) -> Option<PathBuf> {
+    let full = root.join(sub);
+    let mut existing = full.as_path();
+    let mut missing = Vec::new();
+    while existing.symlink_metadata().is_err() {
+        missing.push(existing.file_name()?);
+        existing = existing.parent()?;
+    }
+
+    let mut resolved = std::fs::canonicalize(existing).ok()?;
+    if !resolved.starts_with(root) {
+        return None;
+    }
+    for part in missing.into_iter().rev() {
+        resolved.push(part);
+    }
+    Some(resolved)
+}
+
+/// Name of the local user with this uid, if any
+pub fn user_name(uid: u32) -> Option<String> {
+    let mut buf = vec![0 as libc::c_char; 4096];
+    let mut pwd: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut result = std::ptr::null_mut();
+    // SAFETY: all pointers are valid for the duration of the call and
+    // pw_name points into `buf`, which outlives the CStr borrow below
+    let rc = unsafe { libc::getpwuid_r(uid, &mut pwd, buf.as_mut_ptr(), buf.len(), &mut result) };
+    if rc != 0 || result.is_null() {
+        return None;
+    }
+    let name = unsafe { std::ffi::CStr::from_ptr(pwd.pw_name) };
+    name.to_str().ok().map(String::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn table(dir: &Path, extra: &str) -> ModuleTable {
+        ModuleTable::parse(&format!(
+            "[modules.data]\npath = \"{}\"\n{}",
+            dir.display(),
+            extra
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_resolve_module_subpath() {
+        let temp = TempDir::new().unwrap();
+        let root = std::fs::canonicalize(temp.path()).unwrap();
+        let modules = table(&root, "");
+
+        let session = modules.resolve("data/projects/new", None, false).unwrap();
+        assert_eq!(session.module, "data");
+        assert_eq!(session.root, root.join("projects/new"));
+
+        let session = modules.resolve("/data", None, true).unwrap();
+        assert_eq!(session.root, root);
+
+        assert!(modules.resolve("other/x", None, false).is_err());
+    }
+
+    #[test]
+    fn test_resolve_rejects_escapes() {
+        let temp = TempDir::new().unwrap();
+        let outside = TempDir::new().unwrap();
+        std::os::unix::fs::symlink(outside.path(), temp.path().join("link")).unwrap();
+        std::os::unix::fs::symlink("/etc", temp.path().join("etc")).unwrap();
+        std::os::unix::fs::symlink(".", temp.path().join("self")).unwrap();
+        let modules = table(temp.path(), "");
+
+        assert!(modules.resolve("data/../etc", None, true).is_err());
+        assert!(modules.resolve("data/link", None, true).is_err());
+        assert!(modules.resolve("data/link/new/dir", None, false).is_err());
+        assert!(modules.resolve("data/etc", None, true).is_err());
+        // Symlinks that stay inside the module are fine
+        assert!(modules.resolve("data/self/sub", None, false).is_ok());
+    }
+
+    #[test]
+    fn test_resolve_access_rules() {
+        let temp = TempDir::new().unwrap();
+        let modules = table(
+            temp.path(),
+            "read_only = true\nallow = [\"ci\"]\nmax_connections = 1\n",
+        );
+
+        assert!(modules.resolve("data", None, true).is_err());
+        assert!(modules.resolve("data", Some("bob"), true).is_err());
+        assert!(modules.resolve("data", Some("ci"), false).is_err());
+
+        let session = modules.resolve("data", Some("ci"), true).unwrap();
+        let busy = modules.resolve("data", Some("ci"), true).unwrap_err();
+        assert!(busy.contains("connection limit"));
+        drop(session);
+        assert!(modules.resolve("data", Some("ci"), true).is_ok());
+    }
+
+    #[test]
+    fn test_module_filter_is_relative_to_module_root() {
+        let temp = TempDir::new().unwrap();
+        std::fs::create_dir(temp.path().join("site")).unwrap();
+        let modules = table(temp.path(), "filter = [\"- *.tmp\", \"- site/cache\"]\n");
+
+        let session = modules.resolve("data/site", None, false).unwrap();
+        let filter = session.filter.unwrap();
+        assert!(filter.excludes(Path::new("a.tmp"), false));
+        assert!(filter.excludes(Path::new("cache"), true));
+        assert!(filter.excludes(Path::new("cache/page.html"), false));
+        assert!(!filter.excludes(Path::new("index.html"), false));
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_config() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().display();
+        assert!(ModuleTable::parse("").is_err());
+        assert!(ModuleTable::parse(&format!(
+            "[modules.m]\npath = \"{}\"\nread_only = true\nwrite_only = true\n",
+            path
+        ))
+        .is_err());
+        assert!(
+            ModuleTable::parse(&format!("[modules.m]\npath = \"{}\"\nbogus = 1\n", path)).is_err()
+        );
+        assert!(ModuleTable::parse("[modules.m]\npath = \"/nonexistent/sy-module\"\n").is_err());
+    }
+}
@@ -0,0 +1,260 @@
+//! Partial files for resumable transfers in server mode
+//!
+//! Receivers write each file to a partial next to its destination and rename
+//! it into place once complete, so an interrupted transfer never leaves a
+//! truncated file under the real name. A partial's name is keyed by the
+//! source file's size and mtime: with `CAP_RESUME`, a later run offering the
+//! same file finds it and asks the sender to continue from its length
+//! (`Decision::resume_from`). A resumed transfer ends with a
+//! `DATA_FLAG_DIGEST` chunk holding a digest of the whole source file (see
+//! [`super::digest`]), which the receiver checks before the rename; a partial
+//! that doesn't match is deleted, so the next run starts over. Partials of
+//! older source versions can't be resumed and are removed once the file is
+//! committed ([`StalePartials`]).
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+
+use xxhash_rust::xxh3::xxh3_64;
+
+use crate::server::digest;
+
+/// File name suffix of partial files
+pub const PARTIAL_SUFFIX: &str = ".sy-partial";
+
+/// Longest file name most filesystems accept, in bytes
+const NAME_MAX: usize = 255;
+
+/// Bytes a partial's name adds to the file's: the dot, `.<key>` and the suffix
+const NAME_OVERHEAD: usize = 1 + 17 + PARTIAL_SUFFIX.len();
+
+/// Where a transfer of a `size`-byte file with `mtime` to `path` is kept
+///
+/// `dir/name` becomes `dir/.name.<key>.sy-partial`; works on relative paths.
+/// A name too long to take the extra bytes is replaced by its hash.
+pub fn partial_path(path: &Path, size: u64, mtime: i64) -> PathBuf {
+    let mut identity = [0u8; 16];
+    identity[..8].copy_from_slice(&size.to_be_bytes());
+    identity[8..].copy_from_slice(&mtime.to_be_bytes());
+
+    let name = path.file_name().map_or(&[][..], |n| n.as_bytes());
+    let mut partial = b".".to_vec();
+    partial.extend_from_slice(&owner(name));
+    partial.extend_from_slice(format!(".{:016x}{}", xxh3_64(&identity), PARTIAL_SUFFIX).as_bytes());
+    path.with_file_name(OsStr::from_bytes(&partial))
+}
+
+/// What a partial of a file called `name` is named after
+fn owner(name: &[u8]) -> Cow<'_, [u8]> {
+    if name.len() + NAME_OVERHEAD > NAME_MAX {
+        Cow::Owned(format!("{:016x}", xxh3_64(name)).into_bytes())
+    } else {
+        Cow::Borrowed(name)
+    }
+}
+
+/// The owner in a partial's file name (`.<owner>.<key>.sy-partial`)
+fn owner_of(file_name: &[u8]) -> Option<&[u8]> {
+    let rest = file_name
+        .strip_prefix(b".")?
+        .strip_suffix(PARTIAL_SUFFIX.as_bytes())?;
+    let (owner, key) = rest.split_at(rest.len().checked_sub(17)?);
+    let key = key.strip_prefix(b".")?;
+    (!owner.is_empty() && key.iter().all(u8::is_ascii_hexdigit)).then_some(owner)
+}
+
+/// [`partial_path`] of a protocol path, as a protocol path
+pub fn partial_rel(path: &str, size: u64, mtime: i64) -> String {
+    partial_path(Path::new(path), size, mtime)
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Whether a path names a partial file (never offered to peers)
+pub fn is_partial(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|n| n.as_bytes().ends_with(PARTIAL_SUFFIX.as_bytes()))
+}
+
+/// Offset a transfer into `partial` can resume from (0 to start over)
+pub fn resume_offset(partial: &Path, size: u64) -> u64 {
+    match partial.symlink_metadata() {
+        Ok(meta) if meta.is_file() && meta.len() <= size => meta.len(),
+        _ => 0,
+    }
+}
+
+/// Partials of other source versions, found once per directory
+///
+/// The first commit into a directory lists it and keeps the partials found
+/// there by the file they belong to; later commits remove their own without
+/// listing the directory again. Partials this session writes are renamed
+/// into place, so they never need to be found.
+#[derive(Debug, Default)]
+pub struct StalePartials {
+    dirs: HashMap<PathBuf, HashMap<Vec<u8>, Vec<PathBuf>>>,
+}
+
+impl StalePartials {
+    /// Remove partials of `path` keyed by any size and mtime
+    ///
+    /// Called once `path` has been committed: partials of other source
+    /// versions will never be resumed.
+    pub fn remove(&mut self, path: &Path) {
+        let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+            return;
+        };
+        let found = self
+            .dirs
+            .entry(dir.to_path_buf())
+            .or_insert_with(|| find_partials(dir));
+        for partial in found
+            .remove(owner(name.as_bytes()).as_ref())
+            .unwrap_or_default()
+        {
+            let _ = std::fs::remove_file(partial);
+        }
+    }
+}
+
+/// Partials in `dir`, by the owner in their name
+fn find_partials(dir: &Path) -> HashMap<Vec<u8>, Vec<PathBuf>> {
+    let mut found: HashMap<Vec<u8>, Vec<PathBuf>> = HashMap::new();
+    let listed = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    let Ok(entries) = std::fs::read_dir(listed) else {
+        return found;
+    };
+    for entry in entries.flatten() {
+        let file_name = entry.file_name();
+        let Some(owner) = owner_of(file_name.as_bytes()) else {
+            continue;
+        };
+        if entry.file_type().is_ok_and(|t| t.is_file()) {
+            found.entry(owner.to_vec()).or_default().push(entry.path());
+        }
+    }
+    found
+}
+
+/// Check a completed partial against a `DATA_FLAG_DIGEST` chunk
+///
+/// Returns the partial's digest (to echo in FILE_DONE). A partial that
+/// doesn't match is removed and a [`digest::mismatch`] error returned.
+pub fn verify(partial: &Path, expected: &[u8]) -> io::Result<Vec<u8>> {
+    let actual = digest::digest_like(partial, expected)?;
+    if actual == expected {
+        return Ok(actual);
+    }
+    let _ = std::fs::remove_file(partial);
+    Err(digest::mismatch(partial))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integrity::ChecksumType;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_partial_path_is_keyed_by_identity() {
+        let path = Path::new("dir/big.iso");
+        let partial = partial_path(path, 100, 1_700_000_000);
+
+        assert_eq!(partial.parent(), Some(Path::new("dir")));
+        assert!(partial
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with(".big.iso."));
+        assert!(is_partial(&partial));
+        assert!(!is_partial(path));
+        assert_eq!(partial, partial_path(path, 100, 1_700_000_000));
+        assert_ne!(partial, partial_path(path, 101, 1_700_000_000));
+        assert_ne!(partial, partial_path(path, 100, 1_700_000_001));
+    }
+
+    #[test]
+    fn test_verify_removes_mismatched_partial() {
+        let temp = TempDir::new().unwrap();
+        let source = temp.path().join("source");
+        let partial = temp.path().join("partial");
+        std::fs::write(&source, b"complete contents").unwrap();
+        std::fs::write(&partial, b"complete contents").unwrap();
+        assert_eq!(resume_offset(&partial, 17), 17);
+        assert_eq!(resume_offset(&partial, 10), 0);
+
+        let expected = digest::file_digest(&source, ChecksumType::Fast).unwrap();
+        assert_eq!(verify(&partial, &expected).unwrap(), expected);
+
+        std::fs::write(&partial, b"complete c0ntents").unwrap();
+        let err = verify(&partial, &expected).unwrap_err();
+        assert!(digest::is_mismatch(&err));
+        assert!(!partial.exists());
+    }
+
+    #[test]
+    fn test_stale_partials_keep_other_names() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("big.iso");
+        let old = partial_path(&path, 100, 1_700_000_000);
+        let older = partial_path(&path, 50, 1_600_000_000);
+        let other = partial_path(&temp.path().join("big.iso.bak"), 100, 1_700_000_000);
+        for p in [&path, &old, &older, &other] {
+            std::fs::write(p, b"data").unwrap();
+        }
+
+        StalePartials::default().remove(&path);
+
+        assert!(path.exists());
+        assert!(!old.exists());
+        assert!(!older.exists());
+        assert!(other.exists());
+    }
+
+    #[test]
+    fn test_long_names_get_hashed_partials() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("x".repeat(250));
+        let partial = partial_path(&path, 100, 1_700_000_000);
+        assert!(partial.file_name().unwrap().len() <= NAME_MAX);
+        assert!(is_partial(&partial));
+        assert_ne!(partial, partial_path(&path, 101, 1_700_000_000));
+        let sibling = partial_path(&temp.path().join("y".repeat(250)), 100, 1_700_000_000);
+        assert_ne!(partial, sibling);
+
+        std::fs::write(&partial, b"data").unwrap();
+        std::fs::write(&sibling, b"data").unwrap();
+        std::fs::rename(&partial, &path).unwrap();
+        let stale = partial_path(&path, 50, 1_600_000_000);
+        std::fs::write(&stale, b"data").unwrap();
+
+        StalePartials::default().remove(&path);
+
+        assert!(path.exists());
+        assert!(!stale.exists());
+        assert!(sibling.exists());
+    }
+
+    #[test]
+    fn test_stale_partials_list_each_directory_once() {
+        let temp = TempDir::new().unwrap();
+        let first = temp.path().join("a");
+        let second = temp.path().join("b");
+        let mut stale = StalePartials::default();
+        stale.remove(&first);
+
+        // Found on the first commit only; nothing here is listed again
+        let late = partial_path(&second, 1, 1);
+        std::fs::write(&late, b"data").unwrap();
+        stale.remove(&second);
+        assert!(late.exists());
+    }
+}
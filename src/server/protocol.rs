@@ -1,50 +1,150 @@
-use anyhow::{Context, Result};
-use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use anyhow::Result;
+use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, Take};
+
+use crate::delta::DeltaAlgo;
+use crate::integrity::ChecksumType;
 
 // Protocol Constants
+// HELLO.version carries the oldest version a side speaks (older peers require
+// exactly 1 there); the newest is sent in the capability block.
 pub const PROTOCOL_VERSION: u16 = 1;
+pub const PROTOCOL_VERSION_MAX: u16 = 2;
 
 // File entry flags
 pub const FLAG_IS_DIR: u8 = 0x01;
 pub const FLAG_IS_SYMLINK: u8 = 0x02;
-pub const FLAG_IS_HARDLINK: u8 = 0x04;
+pub const FLAG_IS_HARDLINK: u8 = 0x04; // A u32 index of the entry it links to follows (needs CAP_HARDLINKS)
 pub const FLAG_HAS_XATTRS: u8 = 0x08;
+pub const FLAG_HAS_META: u8 = 0x10; // An EntryMeta block follows (needs CAP_META_BLOCK)
+pub const FLAG_FUZZY: u8 = 0x20; // Sender accepts a basis file for this entry if it's new (needs CAP_FUZZY)
 
 // Hello flags
 pub const HELLO_FLAG_PULL: u32 = 0x01; // Client wants to pull (server sends files)
 
+// Capability bits (HELLO capability block); a feature is used only when both
+// sides advertise it. Bits 0-7: compression, 8-15: delta variants,
+// 16-23: metadata kinds, 24+: operations.
+pub const CAP_COMPRESS_ZSTD: u64 = 1 << 0; // DATA_FLAG_COMPRESSED chunks
+pub const CAP_STREAM_ZSTD: u64 = 1 << 1; // Whole stream zstd-compressed after the handshake
+pub const CAP_DELTA_BLOCK: u64 = 1 << 8; // CHECKSUM_REQ / DELTA_DATA with fixed blocks
+pub const CAP_DELTA_CDC: u64 = 1 << 9; // CHECKSUM_REQ_FLAG_CDC: content-defined chunks
+pub const CAP_META_MODE: u64 = 1 << 16; // Permission bits in FILE_LIST
+pub const CAP_META_MTIME: u64 = 1 << 17; // Modification times in FILE_LIST
+pub const CAP_META_BLOCK: u64 = 1 << 18; // Ownership, xattrs, ACLs, BSD flags per entry
+pub const CAP_SYMLINKS: u64 = 1 << 24; // SYMLINK_BATCH
+pub const CAP_DELETE: u64 = 1 << 25; // DELETE_BATCH (not served yet, never advertised)
+pub const CAP_KEEPALIVE: u64 = 1 << 26; // PING / PONG
+pub const CAP_SPARSE: u64 = 1 << 27; // DATA_FLAG_SPARSE chunks (data regions only)
+pub const CAP_HARDLINKS: u64 = 1 << 28; // FLAG_IS_HARDLINK entries carry no data
+pub const CAP_RESUME: u64 = 1 << 29; // Decision::resume_from, chunked FILE_DATA ending in FINAL
+pub const CAP_FILE_CHECKSUM: u64 = 1 << 30; // Whole-file digests checked and echoed in FILE_DONE
+pub const CAP_FUZZY: u64 = 1 << 31; // FLAG_FUZZY entries, Decision::basis for new files
+
+/// Features implied by a version-1 peer that sends no capability block
+pub const CAPS_V1: u64 =
+    CAP_COMPRESS_ZSTD | CAP_DELTA_BLOCK | CAP_META_MODE | CAP_META_MTIME | CAP_SYMLINKS;
+
+/// Features this build advertises
+pub const CAPS_LOCAL: u64 = CAPS_V1
+    | CAP_STREAM_ZSTD
+    | CAP_DELTA_CDC
+    | CAP_META_BLOCK
+    | CAP_KEEPALIVE
+    | CAP_SPARSE
+    | CAP_HARDLINKS
+    | CAP_RESUME
+    | CAP_FILE_CHECKSUM
+    | CAP_FUZZY;
+
 // FileData flags
 pub const DATA_FLAG_COMPRESSED: u8 = 0x01; // Data is zstd compressed
 pub const DATA_FLAG_FINAL: u8 = 0x02; // This is the final chunk for this file
+pub const DATA_FLAG_SPARSE: u8 = 0x04; // Hole-aware chunk: offset 0 sizes the file, FINAL ends it
+pub const DATA_FLAG_DIGEST: u8 = 0x08; // Data is the whole file's digest (see server::digest)
+
+// FILE_LIST_ACK action flags
+pub const ACTION_FLAG_RESUME: u8 = 0x80; // A u64 resume offset follows (needs CAP_RESUME)
+pub const ACTION_FLAG_DIGEST_FAST: u8 = 0x40; // Send an xxh3-64 digest (needs CAP_FILE_CHECKSUM)
+pub const ACTION_FLAG_DIGEST_CRYPTO: u8 = 0x20; // Send a BLAKE3 digest (needs CAP_FILE_CHECKSUM)
+pub const ACTION_FLAG_BASIS: u8 = 0x10; // A basis path follows the resume offset (needs CAP_FUZZY)
+const ACTION_FLAGS: u8 =
+    ACTION_FLAG_RESUME | ACTION_FLAG_DIGEST_FAST | ACTION_FLAG_DIGEST_CRYPTO | ACTION_FLAG_BASIS;
+
+// CHECKSUM_REQ flags, carried in the top bits of the block size
+pub const CHECKSUM_REQ_FLAG_CDC: u32 = 1 << 31; // Chunk with FastCDC; the size is the average (needs CAP_DELTA_CDC)
+pub const CHECKSUM_REQ_FLAG_CACHE: u32 = 1 << 30; // Receiver may cache chunk lists in its checksum database
+const CHECKSUM_REQ_FLAGS: u32 = CHECKSUM_REQ_FLAG_CDC | CHECKSUM_REQ_FLAG_CACHE;
+
+/// Times a file answered with STATUS_CHECKSUM_MISMATCH is sent again
+/// (CAP_FILE_CHECKSUM); both sides count the rounds
+pub const VERIFY_RETRIES: usize = 2;
+
+// Keepalive (answered by both `sy --server` and the daemon)
+pub const MSG_PING: u8 = 0x32;
+pub const MSG_PONG: u8 = 0x33;
 
 // Delta sync thresholds
 pub const DELTA_MIN_SIZE: u64 = 64 * 1024; // 64KB - below this, full transfer is faster
 
-/// Compute optimal block size for delta sync based on file size
-/// Larger blocks = fewer checksums to compute/send but coarser granularity
-/// rsync uses similar adaptive sizing
-pub fn delta_block_size(file_size: u64) -> u32 {
-    if file_size < 1024 * 1024 {
-        // < 1MB: 2KB blocks
-        2048
-    } else if file_size < 10 * 1024 * 1024 {
-        // 1-10MB: 4KB blocks
-        4096
-    } else if file_size < 100 * 1024 * 1024 {
-        // 10-100MB: 16KB blocks
-        16384
-    } else {
-        // > 100MB: 64KB blocks
-        65536
-    }
-}
-
 // FileDone status codes
 pub const STATUS_OK: u8 = 0;
 pub const STATUS_CHECKSUM_MISMATCH: u8 = 1;
 pub const STATUS_WRITE_ERROR: u8 = 2;
 pub const STATUS_PERMISSION_DENIED: u8 = 3;
 
+// Decoding limits. Lengths and counts come from the peer, so each is checked
+// before anything is read and buffers grow only as bytes actually arrive.
+pub const MAX_CAPABILITIES_LEN: usize = 1024; // HELLO capability block
+pub const MAX_DATA_LEN: usize = 1 << 30; // FILE_DATA payload, one delta literal
+pub const MAX_DIGEST_LEN: usize = 64; // FILE_DONE echo, DELTA_DATA digest
+pub const MAX_META_LEN: usize = 4 << 20; // One EntryMeta block
+pub const MAX_ENTRIES: usize = 1 << 24; // FILE_LIST, FILE_LIST_ACK, batches and their acks
+pub const MAX_BLOCK_CHECKSUMS: usize = 1 << 24; // CHECKSUM_RESP
+pub const MAX_DELTA_OPS: usize = 1 << 24; // DELTA_DATA
+pub const MAX_BLOCK_SIZE: u32 = 16 << 20; // CHECKSUM_REQ block size
+pub const MAX_FRAME_LEN: u64 = u32::MAX as u64; // Lists and batches, bounded by their counts
+
+// Capacity reserved up front for a length or count read from the wire
+const PREALLOC_BYTES: usize = 1 << 20;
+const PREALLOC_ITEMS: usize = 4096;
+
+/// Why a message could not be decoded
+#[derive(Debug, Error)]
+pub enum ProtocolError {
+    #[error("{what} too large: {len} exceeds the limit of {max}")]
+    TooLarge {
+        what: &'static str,
+        len: u64,
+        max: u64,
+    },
+
+    #[error("Invalid {what}: {value}")]
+    InvalidValue { what: &'static str, value: u64 },
+
+    #[error("Invalid UTF-8 in {0}")]
+    InvalidUtf8(&'static str),
+
+    #[error("Unknown delta op type: {0}")]
+    UnknownDeltaOp(u8),
+
+    #[error("Message 0x{msg_type:02X} ended before its payload: frame length {len}")]
+    ShortFrame { msg_type: u8, len: u64 },
+
+    #[error("Message 0x{msg_type:02X} has {extra} bytes after its payload")]
+    TrailingBytes { msg_type: u8, extra: u64 },
+
+    #[error("Failed to read message: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+impl ProtocolError {
+    /// Whether the stream ended in the middle of a message
+    pub fn is_truncated(&self) -> bool {
+        matches!(self, Self::Io(e) if e.kind() == std::io::ErrorKind::UnexpectedEof)
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
 pub enum MessageType {
@@ -88,9 +188,120 @@ impl MessageType {
             _ => None,
         }
     }
+
+    /// Largest payload a frame of this type may announce
+    pub fn max_len(self) -> u64 {
+        match self {
+            Self::Hello => 2 + 4 + 4 + MAX_CAPABILITIES_LEN as u64,
+            Self::FileData => 4 + 8 + 1 + 4 + MAX_DATA_LEN as u64,
+            Self::FileDone => 4 + 1 + 4 + MAX_DIGEST_LEN as u64,
+            Self::ChecksumReq => 4 + 4,
+            Self::ChecksumResp => 4 + 8 + 4 + 24 * MAX_BLOCK_CHECKSUMS as u64,
+            Self::Error => 2 + 2 + u16::MAX as u64,
+            _ => MAX_FRAME_LEN,
+        }
+    }
+}
+
+/// A frame header: the payload length, then the message type
+///
+/// Payloads are read through [`Frame::payload`], so a decoder can't run past
+/// the length the peer announced, and [`Frame::finish`] refuses frames whose
+/// payload was shorter or longer than their message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Frame {
+    pub len: u32,
+    pub msg_type: u8,
+}
+
+impl Frame {
+    pub async fn read<R: AsyncRead + Unpin>(r: &mut R) -> Result<Self, ProtocolError> {
+        let len = r.read_u32().await?;
+        let msg_type = r.read_u8().await?;
+        Ok(Self { len, msg_type })
+    }
+
+    /// The payload, refused before anything is read if it's longer than `max`
+    pub fn payload<'a, R: AsyncRead + Unpin>(
+        &self,
+        r: &'a mut R,
+        max: u64,
+    ) -> Result<Take<&'a mut R>, ProtocolError> {
+        check_len(
+            "message",
+            self.len as usize,
+            max.min(MAX_FRAME_LEN) as usize,
+        )?;
+        Ok(r.take(self.len as u64))
+    }
+
+    /// Check that decoding `payload` used exactly the frame's length
+    pub fn finish<R: AsyncRead, T>(
+        &self,
+        payload: &Take<R>,
+        decoded: Result<T, ProtocolError>,
+    ) -> Result<T, ProtocolError> {
+        let extra = payload.limit();
+        match decoded {
+            Err(e) if e.is_truncated() && extra == 0 => Err(ProtocolError::ShortFrame {
+                msg_type: self.msg_type,
+                len: self.len as u64,
+            }),
+            Ok(_) if extra > 0 => Err(ProtocolError::TrailingBytes {
+                msg_type: self.msg_type,
+                extra,
+            }),
+            decoded => decoded,
+        }
+    }
+
+    /// Check that the frame carries no payload (PING, PONG)
+    pub fn expect_empty(&self) -> Result<(), ProtocolError> {
+        match self.len {
+            0 => Ok(()),
+            len => Err(ProtocolError::TrailingBytes {
+                msg_type: self.msg_type,
+                extra: len as u64,
+            }),
+        }
+    }
+}
+
+/// `read_frame` for a message type: decode a frame's payload within its length
+macro_rules! impl_read_frame {
+    ($($ty:ident => $kind:ident),* $(,)?) => {$(
+        impl $ty {
+            /// Decode the payload of `frame`, which must be exactly one message
+            pub async fn read_frame<R: AsyncRead + Unpin>(r: &mut R, frame: &Frame) -> Result<Self> {
+                let mut payload = frame.payload(r, MessageType::$kind.max_len())?;
+                let decoded = Self::from_reader(&mut payload).await;
+                Ok(frame.finish(&payload, decoded)?)
+            }
+        }
+    )*};
+}
+
+impl_read_frame! {
+    Hello => Hello,
+    FileList => FileList,
+    FileListAck => FileListAck,
+    FileData => FileData,
+    FileDone => FileDone,
+    MkdirBatch => MkdirBatch,
+    MkdirBatchAck => MkdirBatchAck,
+    SymlinkBatch => SymlinkBatch,
+    SymlinkBatchAck => SymlinkBatchAck,
+    ErrorMessage => Error,
+    ChecksumReq => ChecksumReq,
+    ChecksumResp => ChecksumResp,
+    DeltaData => DeltaData,
 }
 
 // Helper functions for serialization
+//
+// Every message decodes through `from_reader`, which reports a typed
+// ProtocolError and never allocates more than the limits above allow;
+// `read` wraps it for the session loops.
 async fn write_string<W: AsyncWrite + Unpin>(w: &mut W, s: &str) -> Result<()> {
     let bytes = s.as_bytes();
     w.write_u16(bytes.len() as u16).await?;
@@ -98,11 +309,37 @@ async fn write_string<W: AsyncWrite + Unpin>(w: &mut W, s: &str) -> Result<()> {
     Ok(())
 }
 
-async fn read_string<R: AsyncRead + Unpin>(r: &mut R) -> Result<String> {
+fn check_len(what: &'static str, len: usize, max: usize) -> Result<usize, ProtocolError> {
+    if len > max {
+        return Err(ProtocolError::TooLarge {
+            what,
+            len: len as u64,
+            max: max as u64,
+        });
+    }
+    Ok(len)
+}
+
+/// Read exactly `len` bytes without trusting `len` for the allocation
+async fn read_exact_vec<R: AsyncRead + Unpin>(
+    r: &mut R,
+    len: usize,
+) -> Result<Vec<u8>, ProtocolError> {
+    let mut buf = Vec::with_capacity(len.min(PREALLOC_BYTES));
+    r.take(len as u64).read_to_end(&mut buf).await?;
+    if buf.len() < len {
+        return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+    }
+    Ok(buf)
+}
+
+async fn read_string<R: AsyncRead + Unpin>(
+    r: &mut R,
+    what: &'static str,
+) -> Result<String, ProtocolError> {
     let len = r.read_u16().await? as usize;
-    let mut buf = vec![0u8; len];
-    r.read_exact(&mut buf).await?;
-    String::from_utf8(buf).context("Invalid UTF-8 string")
+    let buf = read_exact_vec(r, len).await?;
+    String::from_utf8(buf).map_err(|_| ProtocolError::InvalidUtf8(what))
 }
 
 async fn write_bytes<W: AsyncWrite + Unpin>(w: &mut W, b: &[u8]) -> Result<()> {
@@ -111,25 +348,151 @@ async fn write_bytes<W: AsyncWrite + Unpin>(w: &mut W, b: &[u8]) -> Result<()> {
     Ok(())
 }
 
-async fn read_bytes<R: AsyncRead + Unpin>(r: &mut R) -> Result<Vec<u8>> {
-    let len = r.read_u32().await? as usize;
-    let mut buf = vec![0u8; len];
-    r.read_exact(&mut buf).await?;
-    Ok(buf)
+async fn read_bytes<R: AsyncRead + Unpin>(
+    r: &mut R,
+    what: &'static str,
+    max: usize,
+) -> Result<Vec<u8>, ProtocolError> {
+    let len = check_len(what, r.read_u32().await? as usize, max)?;
+    read_exact_vec(r, len).await
+}
+
+/// Read a u32 element count, checked against `max`
+async fn read_count<R: AsyncRead + Unpin>(
+    r: &mut R,
+    what: &'static str,
+    max: usize,
+) -> Result<usize, ProtocolError> {
+    check_len(what, r.read_u32().await? as usize, max)
 }
 
 // ============================================================================
 // HELLO (0x01)
 // ============================================================================
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 pub struct Hello {
     pub version: u16,
     pub flags: u32,
     pub capabilities: Vec<u8>,
 }
 
+/// Protocol versions and features one side of a connection supports
+///
+/// Encoded in `Hello::capabilities` as `max_version: u16, features: u64`;
+/// the minimum version is `Hello::version`. Decoders ignore trailing bytes
+/// so later versions can append fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capabilities {
+    pub min_version: u16,
+    pub max_version: u16,
+    pub features: u64,
+}
+
+/// What both sides agreed on during the handshake
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Negotiated {
+    pub version: u16,
+    pub features: u64,
+}
+
+impl Capabilities {
+    /// Everything this build speaks
+    pub fn local() -> Self {
+        Self {
+            min_version: PROTOCOL_VERSION,
+            max_version: PROTOCOL_VERSION_MAX,
+            features: CAPS_LOCAL,
+        }
+    }
+
+    /// A peer that sent no capability block (sy before version 2)
+    pub fn legacy(version: u16) -> Self {
+        Self {
+            min_version: version,
+            max_version: version,
+            features: CAPS_V1,
+        }
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(10);
+        buf.extend_from_slice(&self.max_version.to_be_bytes());
+        buf.extend_from_slice(&self.features.to_be_bytes());
+        buf
+    }
+
+    /// Pick the highest common version and the shared features
+    pub fn negotiate(&self, peer: &Capabilities) -> Result<Negotiated> {
+        let version = self.max_version.min(peer.max_version);
+        if version < self.min_version.max(peer.min_version) {
+            anyhow::bail!(
+                "No common protocol version: we speak {}-{}, peer speaks {}-{}",
+                self.min_version,
+                self.max_version,
+                peer.min_version,
+                peer.max_version
+            );
+        }
+        Ok(Negotiated {
+            version,
+            features: self.features & peer.features,
+        })
+    }
+}
+
+impl Negotiated {
+    /// Check whether a CAP_* feature may be used on this connection
+    pub fn has(&self, cap: u64) -> bool {
+        self.features & cap == cap
+    }
+}
+
+impl Default for Negotiated {
+    /// A version-1 connection, before or without negotiation
+    fn default() -> Self {
+        Self {
+            version: PROTOCOL_VERSION,
+            features: CAPS_V1,
+        }
+    }
+}
+
 impl Hello {
+    /// Build a HELLO advertising this build's capabilities
+    pub fn new(flags: u32) -> Self {
+        Self::with_features(flags, CAPS_LOCAL)
+    }
+
+    /// Build a HELLO advertising only some of this build's features
+    pub fn with_features(flags: u32, features: u64) -> Self {
+        let caps = Capabilities {
+            features: features & CAPS_LOCAL,
+            ..Capabilities::local()
+        };
+        Self {
+            version: PROTOCOL_VERSION,
+            flags,
+            capabilities: caps.encode(),
+        }
+    }
+
+    /// Decode the sender's capabilities (legacy peers send an empty block)
+    pub fn peer_capabilities(&self) -> Capabilities {
+        match self.capabilities.get(..10) {
+            Some(block) => Capabilities {
+                min_version: self.version,
+                max_version: u16::from_be_bytes([block[0], block[1]]),
+                features: u64::from_be_bytes(block[2..10].try_into().unwrap()),
+            },
+            None => Capabilities::legacy(self.version),
+        }
+    }
+
+    /// Negotiate with the peer that sent this HELLO
+    pub fn negotiate(&self) -> Result<Negotiated> {
+        Capabilities::local().negotiate(&self.peer_capabilities())
+    }
     pub async fn write<W: AsyncWrite + Unpin>(&self, w: &mut W) -> Result<()> {
         let len = 2 + 4 + 4 + self.capabilities.len() as u32;
         w.write_u32(len).await?;
@@ -141,9 +504,13 @@ impl Hello {
     }
 
     pub async fn read<R: AsyncRead + Unpin>(r: &mut R) -> Result<Self> {
+        Ok(Self::from_reader(r).await?)
+    }
+
+    pub async fn from_reader<R: AsyncRead + Unpin>(r: &mut R) -> Result<Self, ProtocolError> {
         let version = r.read_u16().await?;
         let flags = r.read_u32().await?;
-        let capabilities = read_bytes(r).await?;
+        let capabilities = read_bytes(r, "capability block", MAX_CAPABILITIES_LEN).await?;
         Ok(Hello {
             version,
             flags,
@@ -156,7 +523,7 @@ impl Hello {
 // FILE_LIST (0x02)
 // ============================================================================
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct FileListEntry {
     pub path: String,
     pub size: u64,
@@ -164,6 +531,104 @@ pub struct FileListEntry {
     pub mode: u32,
     pub flags: u8,
     pub symlink_target: Option<String>,
+    /// Index of an earlier entry this one is a hard link to (CAP_HARDLINKS)
+    pub hardlink_to: Option<u32>,
+    /// Extra metadata; only sent when CAP_META_BLOCK was negotiated
+    pub meta: Option<EntryMeta>,
+}
+
+// EntryMeta presence bits
+const META_UID: u8 = 0x01;
+const META_GID: u8 = 0x02;
+const META_BSD_FLAGS: u8 = 0x04;
+const META_ACLS: u8 = 0x08;
+const META_XATTRS: u8 = 0x10;
+
+/// Metadata beyond mode and mtime, carried per entry in FILE_LIST
+///
+/// Encoded as a length-prefixed block: a presence byte, then each present
+/// field in bit order (uid u32, gid u32, bsd_flags u32, acls as bytes,
+/// xattrs as u16 count + name/value pairs). Decoders skip trailing bytes,
+/// so fields can be appended later.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EntryMeta {
+    pub uid: Option<u32>,
+    pub gid: Option<u32>,
+    pub bsd_flags: Option<u32>,
+    /// POSIX ACL entries in text form, one per line
+    pub acls: Option<String>,
+    pub xattrs: Vec<(String, Vec<u8>)>,
+}
+
+impl EntryMeta {
+    pub fn is_empty(&self) -> bool {
+        self.uid.is_none()
+            && self.gid.is_none()
+            && self.bsd_flags.is_none()
+            && self.acls.is_none()
+            && self.xattrs.is_empty()
+    }
+
+    async fn write<W: AsyncWrite + Unpin>(&self, w: &mut W) -> Result<()> {
+        let mut block = Vec::new();
+        let mut present = 0u8;
+        for (bit, set) in [
+            (META_UID, self.uid.is_some()),
+            (META_GID, self.gid.is_some()),
+            (META_BSD_FLAGS, self.bsd_flags.is_some()),
+            (META_ACLS, self.acls.is_some()),
+            (META_XATTRS, !self.xattrs.is_empty()),
+        ] {
+            if set {
+                present |= bit;
+            }
+        }
+        block.write_u8(present).await?;
+        for id in [self.uid, self.gid, self.bsd_flags].into_iter().flatten() {
+            block.write_u32(id).await?;
+        }
+        if let Some(ref acls) = self.acls {
+            write_bytes(&mut block, acls.as_bytes()).await?;
+        }
+        if !self.xattrs.is_empty() {
+            block.write_u16(self.xattrs.len() as u16).await?;
+            for (name, value) in &self.xattrs {
+                write_string(&mut block, name).await?;
+                write_bytes(&mut block, value).await?;
+            }
+        }
+        write_bytes(w, &block).await
+    }
+
+    async fn from_reader<R: AsyncRead + Unpin>(r: &mut R) -> Result<Self, ProtocolError> {
+        let block = read_bytes(r, "metadata block", MAX_META_LEN).await?;
+        let mut r = std::io::Cursor::new(block);
+        let present = r.read_u8().await?;
+        let mut meta = EntryMeta::default();
+        if present & META_UID != 0 {
+            meta.uid = Some(r.read_u32().await?);
+        }
+        if present & META_GID != 0 {
+            meta.gid = Some(r.read_u32().await?);
+        }
+        if present & META_BSD_FLAGS != 0 {
+            meta.bsd_flags = Some(r.read_u32().await?);
+        }
+        if present & META_ACLS != 0 {
+            let acls = read_bytes(&mut r, "ACLs", MAX_META_LEN).await?;
+            meta.acls =
+                Some(String::from_utf8(acls).map_err(|_| ProtocolError::InvalidUtf8("ACLs"))?);
+        }
+        if present & META_XATTRS != 0 {
+            let count = r.read_u16().await?;
+            for _ in 0..count {
+                let name = read_string(&mut r, "xattr name").await?;
+                let value = read_bytes(&mut r, "xattr value", MAX_META_LEN).await?;
+                meta.xattrs.push((name, value));
+            }
+        }
+        Ok(meta)
+    }
 }
 
 impl FileListEntry {
@@ -174,9 +639,18 @@ impl FileListEntry {
     pub fn is_symlink(&self) -> bool {
         self.flags & FLAG_IS_SYMLINK != 0
     }
+
+    pub fn is_hardlink(&self) -> bool {
+        self.hardlink_to.is_some()
+    }
+
+    /// Whether the sender accepts a basis file for this entry (FLAG_FUZZY)
+    pub fn wants_basis(&self) -> bool {
+        self.flags & FLAG_FUZZY != 0
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 pub struct FileList {
     pub entries: Vec<FileListEntry>,
 }
@@ -193,7 +667,14 @@ impl FileList {
             payload.write_u64(entry.size).await?;
             payload.write_i64(entry.mtime).await?;
             payload.write_u32(entry.mode).await?;
-            payload.write_u8(entry.flags).await?;
+            let mut flags = entry.flags & !(FLAG_HAS_META | FLAG_IS_HARDLINK);
+            if entry.meta.is_some() {
+                flags |= FLAG_HAS_META;
+            }
+            if entry.hardlink_to.is_some() {
+                flags |= FLAG_IS_HARDLINK;
+            }
+            payload.write_u8(flags).await?;
 
             // Write symlink target if present
             if let Some(ref target) = entry.symlink_target {
@@ -204,6 +685,13 @@ impl FileList {
                 // Empty target for broken symlinks
                 payload.write_u16(0).await?;
             }
+
+            if let Some(leader) = entry.hardlink_to {
+                payload.write_u32(leader).await?;
+            }
+            if let Some(ref meta) = entry.meta {
+                meta.write(&mut payload).await?;
+            }
         }
 
         w.write_u32(payload.len() as u32).await?;
@@ -213,18 +701,22 @@ impl FileList {
     }
 
     pub async fn read<R: AsyncRead + Unpin>(r: &mut R) -> Result<Self> {
-        let count = r.read_u32().await? as usize;
-        let mut entries = Vec::with_capacity(count);
+        Ok(Self::from_reader(r).await?)
+    }
+
+    pub async fn from_reader<R: AsyncRead + Unpin>(r: &mut R) -> Result<Self, ProtocolError> {
+        let count = read_count(r, "file list", MAX_ENTRIES).await?;
+        let mut entries = Vec::with_capacity(count.min(PREALLOC_ITEMS));
 
         for _ in 0..count {
-            let path = read_string(r).await?;
+            let path = read_string(r, "path").await?;
             let size = r.read_u64().await?;
             let mtime = r.read_i64().await?;
             let mode = r.read_u32().await?;
             let flags = r.read_u8().await?;
 
             let symlink_target = if flags & FLAG_IS_SYMLINK != 0 {
-                let target = read_string(r).await?;
+                let target = read_string(r, "symlink target").await?;
                 if target.is_empty() {
                     None
                 } else {
@@ -234,6 +726,18 @@ impl FileList {
                 None
             };
 
+            let hardlink_to = if flags & FLAG_IS_HARDLINK != 0 {
+                Some(r.read_u32().await?)
+            } else {
+                None
+            };
+
+            let meta = if flags & FLAG_HAS_META != 0 {
+                Some(EntryMeta::from_reader(r).await?)
+            } else {
+                None
+            };
+
             entries.push(FileListEntry {
                 path,
                 size,
@@ -241,6 +745,8 @@ impl FileList {
                 mode,
                 flags,
                 symlink_target,
+                hardlink_to,
+                meta,
             });
         }
 
@@ -273,38 +779,102 @@ impl Action {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 pub struct Decision {
     pub index: u32,
     pub action: Action,
+    /// Length of a kept partial file the sender may continue from (CAP_RESUME)
+    pub resume_from: u64,
+    /// Digest the receiver wants sent with the data (CAP_FILE_CHECKSUM)
+    pub digest: ChecksumType,
+    /// Similar existing file a new entry may be delta-encoded against (CAP_FUZZY)
+    pub basis: Option<String>,
+}
+
+impl Decision {
+    fn flags(&self) -> u8 {
+        let mut flags = 0;
+        if self.resume_from > 0 {
+            flags |= ACTION_FLAG_RESUME;
+        }
+        match self.digest {
+            ChecksumType::None => {}
+            ChecksumType::Fast => flags |= ACTION_FLAG_DIGEST_FAST,
+            ChecksumType::Cryptographic => flags |= ACTION_FLAG_DIGEST_CRYPTO,
+        }
+        if self.basis.is_some() {
+            flags |= ACTION_FLAG_BASIS;
+        }
+        flags
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 pub struct FileListAck {
     pub decisions: Vec<Decision>,
 }
 
 impl FileListAck {
     pub async fn write<W: AsyncWrite + Unpin>(&self, w: &mut W) -> Result<()> {
-        let len = 4 + self.decisions.len() as u32 * 5;
+        let resumes = self.decisions.iter().filter(|d| d.resume_from > 0).count();
+        let bases: usize = self
+            .decisions
+            .iter()
+            .filter_map(|d| d.basis.as_ref())
+            .map(|b| 2 + b.len())
+            .sum();
+        let len = 4 + self.decisions.len() as u32 * 5 + resumes as u32 * 8 + bases as u32;
         w.write_u32(len).await?;
         w.write_u8(MessageType::FileListAck as u8).await?;
         w.write_u32(self.decisions.len() as u32).await?;
         for d in &self.decisions {
             w.write_u32(d.index).await?;
-            w.write_u8(d.action as u8).await?;
+            w.write_u8(d.action as u8 | d.flags()).await?;
+            if d.resume_from > 0 {
+                w.write_u64(d.resume_from).await?;
+            }
+            if let Some(basis) = &d.basis {
+                write_string(w, basis).await?;
+            }
         }
         Ok(())
     }
 
     pub async fn read<R: AsyncRead + Unpin>(r: &mut R) -> Result<Self> {
-        let count = r.read_u32().await? as usize;
-        let mut decisions = Vec::with_capacity(count);
+        Ok(Self::from_reader(r).await?)
+    }
+
+    pub async fn from_reader<R: AsyncRead + Unpin>(r: &mut R) -> Result<Self, ProtocolError> {
+        let count = read_count(r, "file list ack", MAX_ENTRIES).await?;
+        let mut decisions = Vec::with_capacity(count.min(PREALLOC_ITEMS));
         for _ in 0..count {
             let index = r.read_u32().await?;
             let action_byte = r.read_u8().await?;
-            let action = Action::from_u8(action_byte).unwrap_or(Action::Skip);
-            decisions.push(Decision { index, action });
+            let resume_from = if action_byte & ACTION_FLAG_RESUME != 0 {
+                r.read_u64().await?
+            } else {
+                0
+            };
+            let basis = if action_byte & ACTION_FLAG_BASIS != 0 {
+                Some(read_string(r, "basis path").await?)
+            } else {
+                None
+            };
+            let digest = if action_byte & ACTION_FLAG_DIGEST_CRYPTO != 0 {
+                ChecksumType::Cryptographic
+            } else if action_byte & ACTION_FLAG_DIGEST_FAST != 0 {
+                ChecksumType::Fast
+            } else {
+                ChecksumType::None
+            };
+            let action = Action::from_u8(action_byte & !ACTION_FLAGS).unwrap_or(Action::Skip);
+            decisions.push(Decision {
+                index,
+                action,
+                resume_from,
+                digest,
+                basis,
+            });
         }
         Ok(FileListAck { decisions })
     }
@@ -314,11 +884,11 @@ impl FileListAck {
 // FILE_DATA (0x04)
 // ============================================================================
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 pub struct FileData {
     pub index: u32,
     pub offset: u64,
-    pub flags: u8, // DATA_FLAG_COMPRESSED, DATA_FLAG_FINAL
+    pub flags: u8, // DATA_FLAG_*
     pub data: Vec<u8>,
 }
 
@@ -335,10 +905,14 @@ impl FileData {
     }
 
     pub async fn read<R: AsyncRead + Unpin>(r: &mut R) -> Result<Self> {
+        Ok(Self::from_reader(r).await?)
+    }
+
+    pub async fn from_reader<R: AsyncRead + Unpin>(r: &mut R) -> Result<Self, ProtocolError> {
         let index = r.read_u32().await?;
         let offset = r.read_u64().await?;
         let flags = r.read_u8().await?;
-        let data = read_bytes(r).await?;
+        let data = read_bytes(r, "file data", MAX_DATA_LEN).await?;
         Ok(FileData {
             index,
             offset,
@@ -352,7 +926,7 @@ impl FileData {
 // FILE_DONE (0x05)
 // ============================================================================
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 pub struct FileDone {
     pub index: u32,
     pub status: u8,
@@ -371,9 +945,13 @@ impl FileDone {
     }
 
     pub async fn read<R: AsyncRead + Unpin>(r: &mut R) -> Result<Self> {
+        Ok(Self::from_reader(r).await?)
+    }
+
+    pub async fn from_reader<R: AsyncRead + Unpin>(r: &mut R) -> Result<Self, ProtocolError> {
         let index = r.read_u32().await?;
         let status = r.read_u8().await?;
-        let checksum = read_bytes(r).await?;
+        let checksum = read_bytes(r, "checksum", MAX_DIGEST_LEN).await?;
         Ok(FileDone {
             index,
             status,
@@ -386,7 +964,7 @@ impl FileDone {
 // MKDIR_BATCH (0x06)
 // ============================================================================
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 pub struct MkdirBatch {
     pub paths: Vec<String>,
 }
@@ -407,10 +985,14 @@ impl MkdirBatch {
     }
 
     pub async fn read<R: AsyncRead + Unpin>(r: &mut R) -> Result<Self> {
-        let count = r.read_u32().await? as usize;
-        let mut paths = Vec::with_capacity(count);
+        Ok(Self::from_reader(r).await?)
+    }
+
+    pub async fn from_reader<R: AsyncRead + Unpin>(r: &mut R) -> Result<Self, ProtocolError> {
+        let count = read_count(r, "mkdir batch", MAX_ENTRIES).await?;
+        let mut paths = Vec::with_capacity(count.min(PREALLOC_ITEMS));
         for _ in 0..count {
-            paths.push(read_string(r).await?);
+            paths.push(read_string(r, "path").await?);
         }
         Ok(MkdirBatch { paths })
     }
@@ -420,7 +1002,7 @@ impl MkdirBatch {
 // MKDIR_BATCH_ACK (0x07)
 // ============================================================================
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 pub struct MkdirBatchAck {
     pub created: u32,
     pub failed: Vec<(String, String)>, // path, error message
@@ -446,12 +1028,16 @@ impl MkdirBatchAck {
     }
 
     pub async fn read<R: AsyncRead + Unpin>(r: &mut R) -> Result<Self> {
+        Ok(Self::from_reader(r).await?)
+    }
+
+    pub async fn from_reader<R: AsyncRead + Unpin>(r: &mut R) -> Result<Self, ProtocolError> {
         let created = r.read_u32().await?;
-        let failed_count = r.read_u32().await? as usize;
-        let mut failed = Vec::with_capacity(failed_count);
+        let failed_count = read_count(r, "failure list", MAX_ENTRIES).await?;
+        let mut failed = Vec::with_capacity(failed_count.min(PREALLOC_ITEMS));
         for _ in 0..failed_count {
-            let path = read_string(r).await?;
-            let err = read_string(r).await?;
+            let path = read_string(r, "path").await?;
+            let err = read_string(r, "error message").await?;
             failed.push((path, err));
         }
         Ok(MkdirBatchAck { created, failed })
@@ -462,13 +1048,13 @@ impl MkdirBatchAck {
 // SYMLINK_BATCH (0x08)
 // ============================================================================
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SymlinkEntry {
     pub path: String,
     pub target: String,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 pub struct SymlinkBatch {
     pub entries: Vec<SymlinkEntry>,
 }
@@ -492,11 +1078,15 @@ impl SymlinkBatch {
     }
 
     pub async fn read<R: AsyncRead + Unpin>(r: &mut R) -> Result<Self> {
-        let count = r.read_u32().await? as usize;
-        let mut entries = Vec::with_capacity(count);
+        Ok(Self::from_reader(r).await?)
+    }
+
+    pub async fn from_reader<R: AsyncRead + Unpin>(r: &mut R) -> Result<Self, ProtocolError> {
+        let count = read_count(r, "symlink batch", MAX_ENTRIES).await?;
+        let mut entries = Vec::with_capacity(count.min(PREALLOC_ITEMS));
         for _ in 0..count {
-            let path = read_string(r).await?;
-            let target = read_string(r).await?;
+            let path = read_string(r, "path").await?;
+            let target = read_string(r, "symlink target").await?;
             entries.push(SymlinkEntry { path, target });
         }
         Ok(SymlinkBatch { entries })
@@ -507,7 +1097,7 @@ impl SymlinkBatch {
 // SYMLINK_BATCH_ACK (0x09)
 // ============================================================================
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 pub struct SymlinkBatchAck {
     pub created: u32,
     pub failed: Vec<(String, String)>,
@@ -533,12 +1123,16 @@ impl SymlinkBatchAck {
     }
 
     pub async fn read<R: AsyncRead + Unpin>(r: &mut R) -> Result<Self> {
+        Ok(Self::from_reader(r).await?)
+    }
+
+    pub async fn from_reader<R: AsyncRead + Unpin>(r: &mut R) -> Result<Self, ProtocolError> {
         let created = r.read_u32().await?;
-        let failed_count = r.read_u32().await? as usize;
-        let mut failed = Vec::with_capacity(failed_count);
+        let failed_count = read_count(r, "failure list", MAX_ENTRIES).await?;
+        let mut failed = Vec::with_capacity(failed_count.min(PREALLOC_ITEMS));
         for _ in 0..failed_count {
-            let path = read_string(r).await?;
-            let err = read_string(r).await?;
+            let path = read_string(r, "path").await?;
+            let err = read_string(r, "error message").await?;
             failed.push((path, err));
         }
         Ok(SymlinkBatchAck { created, failed })
@@ -549,7 +1143,7 @@ impl SymlinkBatchAck {
 // ERROR (0xFF)
 // ============================================================================
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 pub struct ErrorMessage {
     pub code: u16,
     pub message: String,
@@ -567,8 +1161,12 @@ impl ErrorMessage {
     }
 
     pub async fn read<R: AsyncRead + Unpin>(r: &mut R) -> Result<Self> {
+        Ok(Self::from_reader(r).await?)
+    }
+
+    pub async fn from_reader<R: AsyncRead + Unpin>(r: &mut R) -> Result<Self, ProtocolError> {
         let code = r.read_u16().await?;
-        let message = read_string(r).await?;
+        let message = read_string(r, "error message").await?;
         Ok(ErrorMessage { code, message })
     }
 }
@@ -578,26 +1176,72 @@ impl ErrorMessage {
 // ============================================================================
 
 /// Request block checksums for a file (for delta sync)
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct ChecksumReq {
     pub index: u32,      // File index from FILE_LIST
-    pub block_size: u32, // Block size for checksums
+    pub block_size: u32, // Block size for checksums (average chunk size for CDC)
+    /// Fixed blocks, or content-defined chunks (CAP_DELTA_CDC)
+    pub algo: DeltaAlgo,
+    /// Whether the receiver may cache chunk lists (--checksum-db)
+    pub cached: bool,
 }
 
 impl ChecksumReq {
+    /// A fixed-block request
+    pub fn block(index: u32, block_size: u32) -> Self {
+        Self {
+            index,
+            block_size,
+            algo: DeltaAlgo::Block,
+            cached: false,
+        }
+    }
+
+    fn flags(&self) -> u32 {
+        let mut flags = 0;
+        if self.algo == DeltaAlgo::Cdc {
+            flags |= CHECKSUM_REQ_FLAG_CDC;
+        }
+        if self.cached {
+            flags |= CHECKSUM_REQ_FLAG_CACHE;
+        }
+        flags
+    }
+
     pub async fn write<W: AsyncWrite + Unpin>(&self, w: &mut W) -> Result<()> {
         let len = 4 + 4;
         w.write_u32(len).await?;
         w.write_u8(MessageType::ChecksumReq as u8).await?;
         w.write_u32(self.index).await?;
-        w.write_u32(self.block_size).await?;
+        w.write_u32(self.block_size | self.flags()).await?;
         Ok(())
     }
 
     pub async fn read<R: AsyncRead + Unpin>(r: &mut R) -> Result<Self> {
+        Ok(Self::from_reader(r).await?)
+    }
+
+    pub async fn from_reader<R: AsyncRead + Unpin>(r: &mut R) -> Result<Self, ProtocolError> {
         let index = r.read_u32().await?;
-        let block_size = r.read_u32().await?;
-        Ok(ChecksumReq { index, block_size })
+        let word = r.read_u32().await?;
+        let block_size = word & !CHECKSUM_REQ_FLAGS;
+        if block_size == 0 || block_size > MAX_BLOCK_SIZE {
+            return Err(ProtocolError::InvalidValue {
+                what: "block size",
+                value: block_size as u64,
+            });
+        }
+        let algo = if word & CHECKSUM_REQ_FLAG_CDC != 0 {
+            DeltaAlgo::Cdc
+        } else {
+            DeltaAlgo::Block
+        };
+        Ok(ChecksumReq {
+            index,
+            block_size,
+            algo,
+            cached: word & CHECKSUM_REQ_FLAG_CACHE != 0,
+        })
     }
 }
 
@@ -606,7 +1250,7 @@ impl ChecksumReq {
 // ============================================================================
 
 /// Block checksum for delta sync (weak Adler32 + strong xxHash3)
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct BlockChecksum {
     pub offset: u64, // Offset in file
     pub size: u32,   // Block size (may be smaller for last block)
@@ -615,7 +1259,7 @@ pub struct BlockChecksum {
 }
 
 /// Response with block checksums for delta sync
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ChecksumResp {
     pub index: u32,
     pub file_size: u64, // Total file size (for verification)
@@ -641,10 +1285,14 @@ impl ChecksumResp {
     }
 
     pub async fn read<R: AsyncRead + Unpin>(r: &mut R) -> Result<Self> {
+        Ok(Self::from_reader(r).await?)
+    }
+
+    pub async fn from_reader<R: AsyncRead + Unpin>(r: &mut R) -> Result<Self, ProtocolError> {
         let index = r.read_u32().await?;
         let file_size = r.read_u64().await?;
-        let count = r.read_u32().await? as usize;
-        let mut checksums = Vec::with_capacity(count);
+        let count = read_count(r, "block checksums", MAX_BLOCK_CHECKSUMS).await?;
+        let mut checksums = Vec::with_capacity(count.min(PREALLOC_ITEMS));
         for _ in 0..count {
             checksums.push(BlockChecksum {
                 offset: r.read_u64().await?,
@@ -666,7 +1314,7 @@ impl ChecksumResp {
 // ============================================================================
 
 /// Delta operation type
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum DeltaOp {
     /// Copy block from existing file (offset in dest, size)
     Copy { offset: u64, size: u32 },
@@ -675,19 +1323,26 @@ pub enum DeltaOp {
 }
 
 /// Delta data for updating a file
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 pub struct DeltaData {
     pub index: u32,
     pub flags: u8,         // DATA_FLAG_COMPRESSED applies to literal data
     pub ops: Vec<DeltaOp>, // Delta operations
+    /// Digest of the whole new file, sent (DATA_FLAG_DIGEST) when not empty
+    pub digest: Vec<u8>,
 }
 
 impl DeltaData {
     pub async fn write<W: AsyncWrite + Unpin>(&self, w: &mut W) -> Result<()> {
         // Serialize ops to payload first
         let mut payload = Vec::new();
+        let flags = if self.digest.is_empty() {
+            self.flags & !DATA_FLAG_DIGEST
+        } else {
+            self.flags | DATA_FLAG_DIGEST
+        };
         payload.write_u32(self.index).await?;
-        payload.write_u8(self.flags).await?;
+        payload.write_u8(flags).await?;
         payload.write_u32(self.ops.len() as u32).await?;
 
         for op in &self.ops {
@@ -704,6 +1359,9 @@ impl DeltaData {
                 }
             }
         }
+        if !self.digest.is_empty() {
+            write_bytes(&mut payload, &self.digest).await?;
+        }
 
         w.write_u32(payload.len() as u32).await?;
         w.write_u8(MessageType::DeltaData as u8).await?;
@@ -712,10 +1370,14 @@ impl DeltaData {
     }
 
     pub async fn read<R: AsyncRead + Unpin>(r: &mut R) -> Result<Self> {
+        Ok(Self::from_reader(r).await?)
+    }
+
+    pub async fn from_reader<R: AsyncRead + Unpin>(r: &mut R) -> Result<Self, ProtocolError> {
         let index = r.read_u32().await?;
         let flags = r.read_u8().await?;
-        let count = r.read_u32().await? as usize;
-        let mut ops = Vec::with_capacity(count);
+        let count = read_count(r, "delta ops", MAX_DELTA_OPS).await?;
+        let mut ops = Vec::with_capacity(count.min(PREALLOC_ITEMS));
 
         for _ in 0..count {
             let op_type = r.read_u8().await?;
@@ -728,18 +1390,26 @@ impl DeltaData {
                 }
                 1 => {
                     // Data
-                    let len = r.read_u32().await? as usize;
-                    let mut data = vec![0u8; len];
-                    r.read_exact(&mut data).await?;
-                    ops.push(DeltaOp::Data(data));
-                }
-                _ => {
-                    return Err(anyhow::anyhow!("Unknown delta op type: {}", op_type));
+                    ops.push(DeltaOp::Data(
+                        read_bytes(r, "delta literal", MAX_DATA_LEN).await?,
+                    ));
                 }
+                _ => return Err(ProtocolError::UnknownDeltaOp(op_type)),
             }
         }
 
-        Ok(DeltaData { index, flags, ops })
+        let digest = if flags & DATA_FLAG_DIGEST != 0 {
+            read_bytes(r, "digest", MAX_DIGEST_LEN).await?
+        } else {
+            Vec::new()
+        };
+
+        Ok(DeltaData {
+            index,
+            flags,
+            ops,
+            digest,
+        })
     }
 }
 
@@ -772,6 +1442,54 @@ mod tests {
         assert_eq!(decoded.capabilities, hello.capabilities);
     }
 
+    #[tokio::test]
+    async fn test_hello_capabilities_roundtrip() {
+        let mut buf = Vec::new();
+        Hello::new(HELLO_FLAG_PULL).write(&mut buf).await.unwrap();
+
+        let decoded = Hello::read(&mut Cursor::new(&buf[5..])).await.unwrap();
+        assert_eq!(decoded.flags, HELLO_FLAG_PULL);
+        assert_eq!(decoded.peer_capabilities(), Capabilities::local());
+
+        let negotiated = decoded.negotiate().unwrap();
+        assert_eq!(negotiated.version, PROTOCOL_VERSION_MAX);
+        assert!(negotiated.has(CAP_KEEPALIVE));
+    }
+
+    #[test]
+    fn test_negotiate_with_legacy_peer() {
+        // Pre-negotiation builds send version 1 and an empty block
+        let old = Hello {
+            version: 1,
+            flags: 0,
+            capabilities: vec![],
+        };
+        let negotiated = old.negotiate().unwrap();
+        assert_eq!(negotiated, Negotiated::default());
+        assert!(negotiated.has(CAP_DELTA_BLOCK | CAP_COMPRESS_ZSTD));
+        assert!(!negotiated.has(CAP_KEEPALIVE));
+    }
+
+    #[test]
+    fn test_negotiate_intersects_features_and_versions() {
+        let ours = Capabilities::local();
+        let peer = Capabilities {
+            min_version: 1,
+            max_version: 7,
+            features: CAP_DELTA_BLOCK | CAP_DELETE | (1 << 63),
+        };
+        let negotiated = ours.negotiate(&peer).unwrap();
+        assert_eq!(negotiated.version, PROTOCOL_VERSION_MAX);
+        assert_eq!(negotiated.features, CAP_DELTA_BLOCK);
+
+        let future = Capabilities {
+            min_version: PROTOCOL_VERSION_MAX + 1,
+            max_version: PROTOCOL_VERSION_MAX + 3,
+            features: CAPS_LOCAL,
+        };
+        assert!(ours.negotiate(&future).is_err());
+    }
+
     #[tokio::test]
     async fn test_file_list_with_symlink() {
         let list = FileList {
@@ -783,6 +1501,8 @@ mod tests {
                     mode: 0o644,
                     flags: 0,
                     symlink_target: None,
+                    hardlink_to: None,
+                    meta: None,
                 },
                 FileListEntry {
                     path: "link".to_string(),
@@ -791,6 +1511,8 @@ mod tests {
                     mode: 0o777,
                     flags: FLAG_IS_SYMLINK,
                     symlink_target: Some("file.txt".to_string()),
+                    hardlink_to: None,
+                    meta: None,
                 },
             ],
         };
@@ -810,6 +1532,56 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_file_list_with_meta() {
+        let meta = EntryMeta {
+            uid: Some(1000),
+            gid: None,
+            bsd_flags: Some(0x8000),
+            acls: Some("user::rw-\ngroup::r--\nother::r--".to_string()),
+            xattrs: vec![
+                ("user.a".to_string(), b"1".to_vec()),
+                ("user.b".to_string(), vec![0, 255]),
+            ],
+        };
+        let list = FileList {
+            entries: vec![
+                FileListEntry {
+                    path: "plain".to_string(),
+                    size: 1,
+                    mtime: 1234567890,
+                    mode: 0o600,
+                    flags: 0,
+                    symlink_target: None,
+                    hardlink_to: None,
+                    meta: None,
+                },
+                FileListEntry {
+                    path: "tagged".to_string(),
+                    size: 2,
+                    mtime: 1234567890,
+                    mode: 0o4755,
+                    flags: 0,
+                    symlink_target: None,
+                    hardlink_to: None,
+                    meta: Some(meta.clone()),
+                },
+            ],
+        };
+
+        let mut buf = Vec::new();
+        list.write(&mut buf).await.unwrap();
+
+        let mut cursor = Cursor::new(&buf[5..]);
+        let decoded = FileList::read(&mut cursor).await.unwrap();
+
+        assert_eq!(decoded.entries[0].meta, None);
+        assert_eq!(decoded.entries[0].flags & FLAG_HAS_META, 0);
+        assert_eq!(decoded.entries[1].meta, Some(meta));
+        assert_eq!(decoded.entries[1].mode, 0o4755);
+        assert_eq!(decoded.entries[1].size, 2);
+    }
+
     #[tokio::test]
     async fn test_mkdir_batch_roundtrip() {
         let batch = MkdirBatch {
@@ -853,10 +1625,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_checksum_req_roundtrip() {
-        let req = ChecksumReq {
-            index: 42,
-            block_size: 4096,
-        };
+        let req = ChecksumReq::block(42, 4096);
 
         let mut buf = Vec::new();
         req.write(&mut buf).await.unwrap();
@@ -866,6 +1635,21 @@ mod tests {
 
         assert_eq!(decoded.index, 42);
         assert_eq!(decoded.block_size, 4096);
+        assert_eq!(decoded.algo, DeltaAlgo::Block);
+
+        // CDC and caching ride in the top bits; old peers never see them
+        let req = ChecksumReq {
+            algo: DeltaAlgo::Cdc,
+            cached: true,
+            ..ChecksumReq::block(7, MAX_BLOCK_SIZE)
+        };
+        let mut buf = Vec::new();
+        req.write(&mut buf).await.unwrap();
+        assert_eq!(buf[9] & 0xC0, 0xC0);
+        let decoded = ChecksumReq::read(&mut Cursor::new(&buf[5..]))
+            .await
+            .unwrap();
+        assert_eq!(decoded, req);
     }
 
     #[tokio::test]
@@ -903,6 +1687,52 @@ mod tests {
         assert_eq!(decoded.checksums[1].strong, 0x0FEDCBA987654321);
     }
 
+    #[tokio::test]
+    async fn test_file_list_ack_flags_roundtrip() {
+        let ack = FileListAck {
+            decisions: vec![
+                Decision {
+                    index: 0,
+                    action: Action::Create,
+                    resume_from: 0,
+                    digest: ChecksumType::None,
+                    basis: None,
+                },
+                Decision {
+                    index: 1,
+                    action: Action::Update,
+                    resume_from: 4096,
+                    digest: ChecksumType::Fast,
+                    basis: None,
+                },
+                Decision {
+                    index: 2,
+                    action: Action::Create,
+                    resume_from: 0,
+                    digest: ChecksumType::Cryptographic,
+                    basis: Some("dist/app-1.2.tar".to_string()),
+                },
+            ],
+        };
+
+        let mut buf = Vec::new();
+        ack.write(&mut buf).await.unwrap();
+        assert_eq!(
+            u32::from_be_bytes(buf[..4].try_into().unwrap()) as usize,
+            buf.len() - 5
+        );
+
+        let mut cursor = Cursor::new(&buf[5..]);
+        let decoded = FileListAck::read(&mut cursor).await.unwrap();
+        for (sent, got) in ack.decisions.iter().zip(&decoded.decisions) {
+            assert_eq!(sent.index, got.index);
+            assert_eq!(sent.action, got.action);
+            assert_eq!(sent.resume_from, got.resume_from);
+            assert_eq!(sent.digest, got.digest);
+            assert_eq!(sent.basis, got.basis);
+        }
+    }
+
     #[tokio::test]
     async fn test_delta_data_roundtrip() {
         let delta = DeltaData {
@@ -919,6 +1749,7 @@ mod tests {
                     size: 2048,
                 },
             ],
+            digest: vec![7; 8],
         };
 
         let mut buf = Vec::new();
@@ -928,8 +1759,9 @@ mod tests {
         let decoded = DeltaData::read(&mut cursor).await.unwrap();
 
         assert_eq!(decoded.index, 3);
-        assert_eq!(decoded.flags, DATA_FLAG_COMPRESSED);
+        assert_eq!(decoded.flags, DATA_FLAG_COMPRESSED | DATA_FLAG_DIGEST);
         assert_eq!(decoded.ops.len(), 3);
+        assert_eq!(decoded.digest, vec![7; 8]);
 
         match &decoded.ops[0] {
             DeltaOp::Copy { offset, size } => {
@@ -967,4 +1799,87 @@ mod tests {
         assert_eq!(decoded.flags, DATA_FLAG_COMPRESSED);
         assert_eq!(decoded.data.len(), 100);
     }
+
+    #[tokio::test]
+    async fn test_wire_lengths_are_bounded() {
+        // A 4 GiB payload claimed by a 17-byte message
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&7u32.to_be_bytes());
+        buf.extend_from_slice(&0u64.to_be_bytes());
+        buf.push(0);
+        buf.extend_from_slice(&u32::MAX.to_be_bytes());
+        let err = FileData::from_reader(&mut Cursor::new(&buf))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            ProtocolError::TooLarge {
+                what: "file data",
+                ..
+            }
+        ));
+
+        // Counts within the limit only cost what actually arrives
+        let count = (MAX_ENTRIES as u32).to_be_bytes();
+        let err = FileList::from_reader(&mut Cursor::new(&count))
+            .await
+            .unwrap_err();
+        assert!(err.is_truncated());
+        let count = (MAX_ENTRIES as u32 + 1).to_be_bytes();
+        let err = MkdirBatch::from_reader(&mut Cursor::new(&count))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ProtocolError::TooLarge { .. }));
+
+        let done = FileDone {
+            index: 0,
+            status: STATUS_OK,
+            checksum: vec![0; MAX_DIGEST_LEN + 1],
+        };
+        let mut buf = Vec::new();
+        done.write(&mut buf).await.unwrap();
+        let err = FileDone::from_reader(&mut Cursor::new(&buf[5..]))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            ProtocolError::TooLarge {
+                what: "checksum",
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_values_are_rejected() {
+        for block_size in [0, MAX_BLOCK_SIZE + 1] {
+            let mut buf = Vec::new();
+            ChecksumReq::block(1, block_size)
+                .write(&mut buf)
+                .await
+                .unwrap();
+            let err = ChecksumReq::from_reader(&mut Cursor::new(&buf[5..]))
+                .await
+                .unwrap_err();
+            assert!(matches!(err, ProtocolError::InvalidValue { .. }));
+        }
+
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&1u32.to_be_bytes());
+        buf.extend_from_slice(&[0, 2, 0xFF, 0xFE]);
+        let err = MkdirBatch::from_reader(&mut Cursor::new(&buf))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ProtocolError::InvalidUtf8("path")));
+
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&0u32.to_be_bytes());
+        buf.push(0);
+        buf.extend_from_slice(&1u32.to_be_bytes());
+        buf.push(9);
+        let err = DeltaData::from_reader(&mut Cursor::new(&buf))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ProtocolError::UnknownDeltaOp(9)));
+    }
 }
@@ -0,0 +1,373 @@
+//! Stream-level zstd compression for the server protocol
+//!
+//! When both sides negotiate `CAP_STREAM_ZSTD`, every byte after the
+//! handshake goes through one zstd stream per direction. Each `flush()` on the
+//! writer ends a zstd block, so a message can be decoded as soon as its sender
+//! flushes; the protocol code already flushes wherever it waits for a reply.
+//!
+//! Both wrappers pass bytes straight through until `start_zstd()` is called,
+//! so a connection can switch over in place once the handshake is done.
+
+use std::io;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use zstd::stream::raw::{Decoder, Encoder, InBuffer, Operation, OutBuffer};
+
+/// Zstd level for the stream (same as per-chunk compression)
+const STREAM_LEVEL: i32 = 3;
+
+/// Size of the compressed-side buffers
+const BUF_SIZE: usize = 64 * 1024;
+
+/// Byte counts on both sides of the compressor
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StreamStats {
+    /// Protocol bytes as written or read by the application
+    pub raw_bytes: u64,
+    /// Bytes that actually crossed the connection
+    pub wire_bytes: u64,
+}
+
+impl StreamStats {
+    /// raw / wire, or None if nothing went through the compressor
+    pub fn ratio(&self) -> Option<f64> {
+        (self.wire_bytes > 0).then(|| self.raw_bytes as f64 / self.wire_bytes as f64)
+    }
+
+    /// Counts accumulated since `earlier`
+    pub fn since(&self, earlier: StreamStats) -> StreamStats {
+        StreamStats {
+            raw_bytes: self.raw_bytes.saturating_sub(earlier.raw_bytes),
+            wire_bytes: self.wire_bytes.saturating_sub(earlier.wire_bytes),
+        }
+    }
+}
+
+impl std::ops::Add for StreamStats {
+    type Output = StreamStats;
+
+    fn add(self, other: StreamStats) -> StreamStats {
+        StreamStats {
+            raw_bytes: self.raw_bytes + other.raw_bytes,
+            wire_bytes: self.wire_bytes + other.wire_bytes,
+        }
+    }
+}
+
+/// Writer that can switch to zstd-compressing everything it is given
+pub struct StreamWriter<W> {
+    inner: W,
+    encoder: Option<Encoder<'static>>,
+    /// Compressed bytes not yet accepted by `inner`
+    pending: Vec<u8>,
+    written: usize,
+    scratch: Vec<u8>,
+    /// Input was accepted since the last zstd flush
+    dirty: bool,
+    finished: bool,
+    stats: StreamStats,
+}
+
+impl<W: AsyncWrite + Unpin> StreamWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            encoder: None,
+            pending: Vec::new(),
+            written: 0,
+            scratch: Vec::new(),
+            dirty: false,
+            finished: false,
+            stats: StreamStats::default(),
+        }
+    }
+
+    /// Compress everything written from now on
+    pub fn start_zstd(&mut self) -> io::Result<()> {
+        if self.encoder.is_none() {
+            self.encoder = Some(Encoder::new(STREAM_LEVEL)?);
+            self.scratch = vec![0u8; BUF_SIZE];
+        }
+        Ok(())
+    }
+
+    pub fn is_compressed(&self) -> bool {
+        self.encoder.is_some()
+    }
+
+    pub fn stats(&self) -> StreamStats {
+        self.stats
+    }
+
+    /// Write out compressed bytes that `inner` hasn't taken yet
+    fn poll_drain(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while self.written < self.pending.len() {
+            let n =
+                ready!(Pin::new(&mut self.inner).poll_write(cx, &self.pending[self.written..]))?;
+            if n == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+            self.written += n;
+            self.stats.wire_bytes += n as u64;
+        }
+        self.pending.clear();
+        self.written = 0;
+        Poll::Ready(Ok(()))
+    }
+
+    /// Run one encoder step (flush or finish) into `pending`; true when done
+    fn end_block(&mut self, finish: bool) -> io::Result<bool> {
+        let Some(encoder) = self.encoder.as_mut() else {
+            return Ok(true);
+        };
+        let mut out = OutBuffer::around(&mut self.scratch[..]);
+        let remaining = if finish {
+            encoder.finish(&mut out, true)?
+        } else {
+            encoder.flush(&mut out)?
+        };
+        let n = out.pos();
+        self.pending.extend_from_slice(&self.scratch[..n]);
+        Ok(remaining == 0)
+    }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for StreamWriter<W> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if this.encoder.is_none() {
+            return Pin::new(&mut this.inner).poll_write(cx, buf);
+        }
+        if this.finished {
+            return Poll::Ready(Err(io::ErrorKind::BrokenPipe.into()));
+        }
+
+        // Keep at most one buffer of compressed output queued
+        if this.pending.len() - this.written >= BUF_SIZE {
+            ready!(this.poll_drain(cx))?;
+        }
+
+        let encoder = this.encoder.as_mut().expect("checked above");
+        let mut input = InBuffer::around(buf);
+        while input.pos() < buf.len() {
+            let mut out = OutBuffer::around(&mut this.scratch[..]);
+            encoder.run(&mut input, &mut out)?;
+            let n = out.pos();
+            this.pending.extend_from_slice(&this.scratch[..n]);
+        }
+        this.stats.raw_bytes += buf.len() as u64;
+        this.dirty = true;
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        loop {
+            ready!(this.poll_drain(cx))?;
+            if !this.dirty {
+                break;
+            }
+            if this.end_block(false)? {
+                this.dirty = false;
+            }
+        }
+        Pin::new(&mut this.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        loop {
+            ready!(this.poll_drain(cx))?;
+            if this.finished || this.encoder.is_none() {
+                break;
+            }
+            if this.end_block(true)? {
+                this.finished = true;
+                this.dirty = false;
+            }
+        }
+        Pin::new(&mut this.inner).poll_shutdown(cx)
+    }
+}
+
+/// Reader that can switch to zstd-decompressing everything it reads
+pub struct StreamReader<R> {
+    inner: R,
+    decoder: Option<Decoder<'static>>,
+    input: Vec<u8>,
+    in_pos: usize,
+    in_len: usize,
+    output: Vec<u8>,
+    out_pos: usize,
+    out_len: usize,
+    /// The decoder filled `output` and may be holding more
+    more_buffered: bool,
+    eof: bool,
+    stats: StreamStats,
+}
+
+impl<R: AsyncRead + Unpin> StreamReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            decoder: None,
+            input: Vec::new(),
+            in_pos: 0,
+            in_len: 0,
+            output: Vec::new(),
+            out_pos: 0,
+            out_len: 0,
+            more_buffered: false,
+            eof: false,
+            stats: StreamStats::default(),
+        }
+    }
+
+    /// Decompress everything read from now on
+    ///
+    /// Plain reads never read ahead, so no compressed bytes can have been
+    /// consumed before this is called.
+    pub fn start_zstd(&mut self) -> io::Result<()> {
+        if self.decoder.is_none() {
+            self.decoder = Some(Decoder::new()?);
+            self.input = vec![0u8; BUF_SIZE];
+            self.output = vec![0u8; BUF_SIZE];
+        }
+        Ok(())
+    }
+
+    pub fn is_compressed(&self) -> bool {
+        self.decoder.is_some()
+    }
+
+    pub fn stats(&self) -> StreamStats {
+        self.stats
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for StreamReader<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let Some(decoder) = this.decoder.as_mut() else {
+            return Pin::new(&mut this.inner).poll_read(cx, buf);
+        };
+        if buf.remaining() == 0 {
+            return Poll::Ready(Ok(()));
+        }
+
+        loop {
+            // Hand out decoded bytes first
+            if this.out_pos < this.out_len {
+                let n = buf.remaining().min(this.out_len - this.out_pos);
+                buf.put_slice(&this.output[this.out_pos..this.out_pos + n]);
+                this.out_pos += n;
+                this.stats.raw_bytes += n as u64;
+                return Poll::Ready(Ok(()));
+            }
+
+            // Decode what we have (or what the decoder still holds)
+            if this.in_pos < this.in_len || this.more_buffered {
+                let mut input = InBuffer::around(&this.input[this.in_pos..this.in_len]);
+                let mut out = OutBuffer::around(&mut this.output[..]);
+                decoder.run(&mut input, &mut out)?;
+                let (consumed, produced) = (input.pos(), out.pos());
+                this.in_pos += consumed;
+                this.out_pos = 0;
+                this.out_len = produced;
+                this.more_buffered = produced == this.output.len();
+                if consumed > 0 || produced > 0 {
+                    continue;
+                }
+            }
+
+            if this.eof {
+                // Clean EOF between messages, or a truncated stream the
+                // protocol reader will report as unexpected EOF
+                return Poll::Ready(Ok(()));
+            }
+
+            // Need more compressed input: keep any unconsumed tail
+            this.input.copy_within(this.in_pos..this.in_len, 0);
+            this.in_len -= this.in_pos;
+            this.in_pos = 0;
+            let mut read_buf = ReadBuf::new(&mut this.input[this.in_len..]);
+            ready!(Pin::new(&mut this.inner).poll_read(cx, &mut read_buf))?;
+            let n = read_buf.filled().len();
+            if n == 0 {
+                this.eof = true;
+            }
+            this.in_len += n;
+            this.stats.wire_bytes += n as u64;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[tokio::test]
+    async fn test_stream_roundtrip_with_flushes() {
+        let (client, server) = tokio::io::duplex(4096);
+        let mut writer = StreamWriter::new(client);
+        let mut reader = StreamReader::new(server);
+
+        // The handshake goes through uncompressed, then both sides switch
+        writer.write_all(b"HELLO").await.unwrap();
+        writer.flush().await.unwrap();
+        let mut hello = [0u8; 5];
+        reader.read_exact(&mut hello).await.unwrap();
+        assert_eq!(&hello, b"HELLO");
+        writer.start_zstd().unwrap();
+        reader.start_zstd().unwrap();
+
+        let big: Vec<u8> = (0..300_000u32).map(|i| (i % 251) as u8).collect();
+        let expected = big.clone();
+        let send = tokio::spawn(async move {
+            for chunk in big.chunks(10_000) {
+                writer.write_all(chunk).await.unwrap();
+                writer.flush().await.unwrap();
+            }
+            writer.shutdown().await.unwrap();
+            writer.stats()
+        });
+
+        let mut received = Vec::new();
+        reader.read_to_end(&mut received).await.unwrap();
+        let sent = send.await.unwrap();
+
+        assert_eq!(received, expected);
+        assert_eq!(sent.raw_bytes, expected.len() as u64);
+        assert_eq!(reader.stats().raw_bytes, expected.len() as u64);
+        assert_eq!(reader.stats().wire_bytes, sent.wire_bytes);
+        assert!(sent.ratio().unwrap() > 5.0, "{:?}", sent);
+    }
+
+    #[tokio::test]
+    async fn test_flushed_message_is_readable_before_more_data() {
+        let (client, server) = tokio::io::duplex(64 * 1024);
+        let mut writer = StreamWriter::new(client);
+        let mut reader = StreamReader::new(server);
+        writer.start_zstd().unwrap();
+        reader.start_zstd().unwrap();
+
+        // A request/response protocol must not stall on a half-sent message
+        writer.write_u32(42).await.unwrap();
+        writer.flush().await.unwrap();
+        let value = tokio::time::timeout(std::time::Duration::from_secs(5), reader.read_u32())
+            .await
+            .expect("flushed data should be decodable")
+            .unwrap();
+        assert_eq!(value, 42);
+    }
+}
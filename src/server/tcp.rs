@@ -0,0 +1,380 @@
+//! TCP listener support for the daemon: token authentication and TLS
+//!
+//! A Unix socket is protected by filesystem permissions (and reached through
+//! SSH forwarding), but a TCP port is reachable by anyone on the network, so
+//! every TCP connection must prove it holds a shared token before SET_ROOT:
+//!
+//! 1. Server → AUTH_CHALLENGE: 32 random bytes (nonce)
+//! 2. Client → AUTH_RESPONSE: token name + BLAKE3 keyed MAC over nonce and name
+//! 3. Server → AUTH_ACK: 0 = accepted, 1 = rejected (connection is closed)
+//!
+//! The secret itself never crosses the wire. TLS (`--tls-cert`/`--tls-key`)
+//! additionally encrypts the stream; it needs the `tls` feature.
+//!
+//! # Token file
+//!
+//! One `name:secret` pair per line; blank lines and `#` comments are ignored.
+//! Clients pass the same pair in `SY_DAEMON_TOKEN`.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use super::protocol::{ErrorMessage, Frame, MessageType, ProtocolError};
+
+/// Default port for `sy://` URLs
+pub const DEFAULT_PORT: u16 = 8730;
+
+/// Default time a client gets to authenticate and pick a root
+pub const DEFAULT_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Message types for token authentication (TCP daemon only)
+pub const MSG_AUTH_CHALLENGE: u8 = 0x32;
+pub const MSG_AUTH_RESPONSE: u8 = 0x33;
+pub const MSG_AUTH_ACK: u8 = 0x34;
+
+/// Environment variable holding the client's `name:secret` token
+pub const TOKEN_ENV: &str = "SY_DAEMON_TOKEN";
+
+const NONCE_LEN: usize = 32;
+const MAC_LEN: usize = 32;
+
+/// Domain separation for deriving MAC keys from token secrets
+const KEY_CONTEXT: &str = "sy daemon token v1";
+
+/// Options for `sy --daemon --listen`
+#[derive(Debug, Clone)]
+pub struct TcpListenConfig {
+    /// Address to bind, e.g. `0.0.0.0:8730`
+    pub listen: String,
+    /// Token file (required: TCP connections are always authenticated)
+    pub auth_tokens: PathBuf,
+    /// PEM certificate chain and private key; both or neither
+    pub tls_cert: Option<PathBuf>,
+    pub tls_key: Option<PathBuf>,
+    /// Time a client gets for TLS, HELLO, authentication and SET_ROOT
+    pub handshake_timeout: Duration,
+}
+
+/// Client-side connection details for a `sy://host:port/path` daemon
+#[derive(Debug, Clone)]
+pub struct TcpEndpoint {
+    pub host: String,
+    pub port: u16,
+    /// CA (or self-signed certificate) to verify the daemon with; enables TLS
+    pub tls_ca: Option<PathBuf>,
+    pub token: AuthToken,
+}
+
+/// A client's token: the name sent in the clear and a key derived from the secret
+#[derive(Clone)]
+pub struct AuthToken {
+    pub name: String,
+    key: [u8; 32],
+}
+
+impl std::fmt::Debug for AuthToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuthToken")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+impl AuthToken {
+    /// Parse a `name:secret` pair
+    pub fn parse(s: &str) -> Result<Self> {
+        let (name, secret) = s
+            .trim()
+            .split_once(':')
+            .context("Token must be in the form name:secret")?;
+        if name.is_empty() || secret.is_empty() {
+            anyhow::bail!("Token must be in the form name:secret");
+        }
+        if name.len() > u16::MAX as usize {
+            anyhow::bail!("Token name is too long");
+        }
+        Ok(Self {
+            name: name.to_string(),
+            key: derive_key(secret),
+        })
+    }
+
+    /// Read the token from `SY_DAEMON_TOKEN`
+    pub fn from_env() -> Result<Self> {
+        let value = std::env::var(TOKEN_ENV)
+            .with_context(|| format!("{} must be set to name:secret for sy:// paths", TOKEN_ENV))?;
+        Self::parse(&value).with_context(|| format!("Invalid {}", TOKEN_ENV))
+    }
+}
+
+/// Tokens accepted by a TCP daemon, keyed by name
+#[derive(Debug, Default)]
+pub struct TokenStore {
+    keys: HashMap<String, [u8; 32]>,
+}
+
+impl TokenStore {
+    /// Load a token file
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read token file: {}", path.display()))?;
+        let store = Self::parse(&contents)
+            .with_context(|| format!("Invalid token file: {}", path.display()))?;
+        if store.is_empty() {
+            anyhow::bail!("Token file {} contains no tokens", path.display());
+        }
+        Ok(store)
+    }
+
+    /// Parse token file contents
+    pub fn parse(contents: &str) -> Result<Self> {
+        let mut keys = HashMap::new();
+        for (lineno, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let token = AuthToken::parse(line).with_context(|| format!("line {}", lineno + 1))?;
+            if keys.insert(token.name.clone(), token.key).is_some() {
+                anyhow::bail!("line {}: duplicate token name '{}'", lineno + 1, token.name);
+            }
+        }
+        Ok(Self { keys })
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Check a response to `nonce`; constant-time in the MAC comparison
+    fn verify(&self, nonce: &[u8; NONCE_LEN], name: &str, mac: &[u8; MAC_LEN]) -> bool {
+        match self.keys.get(name) {
+            Some(key) => compute_mac(key, nonce, name) == blake3::Hash::from(*mac),
+            None => false,
+        }
+    }
+}
+
+fn derive_key(secret: &str) -> [u8; 32] {
+    blake3::derive_key(KEY_CONTEXT, secret.as_bytes())
+}
+
+fn compute_mac(key: &[u8; 32], nonce: &[u8; NONCE_LEN], name: &str) -> blake3::Hash {
+    let mut hasher = blake3::Hasher::new_keyed(key);
+    hasher.update(nonce);
+    hasher.update(name.as_bytes());
+    hasher.finalize()
+}
+
+/// Server side: challenge the client and verify its response
+///
+/// Returns the token name on success. On failure the rejection has already
+/// been sent and the caller should drop the connection.
+pub async fn authenticate_client<R, W>(
+    reader: &mut R,
+    writer: &mut W,
+    tokens: &TokenStore,
+) -> Result<Option<String>>
+where
+    R: AsyncReadExt + Unpin,
+    W: AsyncWriteExt + Unpin,
+{
+    let mut nonce = [0u8; NONCE_LEN];
+    getrandom::getrandom(&mut nonce).context("Failed to generate auth nonce")?;
+
+    writer.write_u32(NONCE_LEN as u32).await?;
+    writer.write_u8(MSG_AUTH_CHALLENGE).await?;
+    writer.write_all(&nonce).await?;
+    writer.flush().await?;
+
+    let frame = Frame::read(reader).await?;
+    let type_byte = frame.msg_type;
+    if type_byte != MSG_AUTH_RESPONSE {
+        anyhow::bail!("Expected AUTH_RESPONSE (0x33), got 0x{:02X}", type_byte);
+    }
+
+    let mut payload = frame.payload(reader, (2 + u16::MAX as usize + MAC_LEN) as u64)?;
+    let response = async {
+        let name_len = payload.read_u16().await? as usize;
+        let mut name = vec![0u8; name_len];
+        payload.read_exact(&mut name).await?;
+        let mut mac = [0u8; MAC_LEN];
+        payload.read_exact(&mut mac).await?;
+        Ok::<_, ProtocolError>((name, mac))
+    }
+    .await;
+    let (name, mac) = frame.finish(&payload, response)?;
+
+    let name = String::from_utf8_lossy(&name).into_owned();
+    let accepted = tokens.verify(&nonce, &name, &mac);
+
+    writer.write_u32(1).await?;
+    writer.write_u8(MSG_AUTH_ACK).await?;
+    writer.write_u8(if accepted { 0 } else { 1 }).await?;
+    writer.flush().await?;
+
+    Ok(accepted.then_some(name))
+}
+
+/// Client side: answer the daemon's challenge with `token`
+pub async fn answer_challenge<R, W>(reader: &mut R, writer: &mut W, token: &AuthToken) -> Result<()>
+where
+    R: AsyncReadExt + Unpin,
+    W: AsyncWriteExt + Unpin,
+{
+    let frame = Frame::read(reader).await?;
+    let type_byte = frame.msg_type;
+    if type_byte == MessageType::Error as u8 {
+        let err = ErrorMessage::read_frame(reader, &frame).await?;
+        anyhow::bail!("Daemon error: {}", err.message);
+    }
+    if type_byte != MSG_AUTH_CHALLENGE {
+        anyhow::bail!("Expected AUTH_CHALLENGE (0x32), got 0x{:02X}", type_byte);
+    }
+
+    let mut nonce = [0u8; NONCE_LEN];
+    let mut payload = frame.payload(reader, NONCE_LEN as u64)?;
+    let read = payload
+        .read_exact(&mut nonce)
+        .await
+        .map_err(ProtocolError::from);
+    frame.finish(&payload, read)?;
+    let mac = compute_mac(&token.key, &nonce, &token.name);
+
+    let name = token.name.as_bytes();
+    writer.write_u32((2 + name.len() + MAC_LEN) as u32).await?;
+    writer.write_u8(MSG_AUTH_RESPONSE).await?;
+    writer.write_u16(name.len() as u16).await?;
+    writer.write_all(name).await?;
+    writer.write_all(mac.as_bytes()).await?;
+    writer.flush().await?;
+
+    let frame = Frame::read(reader).await?;
+    let type_byte = frame.msg_type;
+    if type_byte != MSG_AUTH_ACK {
+        anyhow::bail!("Expected AUTH_ACK (0x34), got 0x{:02X}", type_byte);
+    }
+    let mut payload = frame.payload(reader, 1)?;
+    let status = payload.read_u8().await.map_err(ProtocolError::from);
+    if frame.finish(&payload, status)? != 0 {
+        anyhow::bail!("Daemon rejected token '{}'", token.name);
+    }
+
+    Ok(())
+}
+
+/// Build a TLS acceptor from PEM certificate chain and key files
+#[cfg(feature = "tls")]
+pub fn tls_acceptor(cert_path: &Path, key_path: &Path) -> Result<tokio_rustls::TlsAcceptor> {
+    use rustls_pki_types::pem::PemObject;
+    use rustls_pki_types::{CertificateDer, PrivateKeyDer};
+    use std::sync::Arc;
+    use tokio_rustls::rustls;
+
+    let certs = CertificateDer::pem_file_iter(cert_path)
+        .and_then(|certs| certs.collect::<std::result::Result<Vec<_>, _>>())
+        .with_context(|| format!("Failed to read certificate: {}", cert_path.display()))?;
+    let key = PrivateKeyDer::from_pem_file(key_path)
+        .with_context(|| format!("Failed to read private key: {}", key_path.display()))?;
+
+    let config = rustls::ServerConfig::builder_with_provider(Arc::new(
+        rustls::crypto::ring::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()?
+    .with_no_client_auth()
+    .with_single_cert(certs, key)
+    .context("Invalid TLS certificate or key")?;
+
+    Ok(tokio_rustls::TlsAcceptor::from(Arc::new(config)))
+}
+
+/// Build a TLS connector that trusts the certificates in `ca_path`
+#[cfg(feature = "tls")]
+pub fn tls_connector(ca_path: &Path) -> Result<tokio_rustls::TlsConnector> {
+    use rustls_pki_types::pem::PemObject;
+    use rustls_pki_types::CertificateDer;
+    use std::sync::Arc;
+    use tokio_rustls::rustls;
+
+    let mut roots = rustls::RootCertStore::empty();
+    for cert in CertificateDer::pem_file_iter(ca_path)
+        .with_context(|| format!("Failed to read CA file: {}", ca_path.display()))?
+    {
+        let cert = cert.with_context(|| format!("Invalid CA file: {}", ca_path.display()))?;
+        roots
+            .add(cert)
+            .with_context(|| format!("Invalid CA certificate in {}", ca_path.display()))?;
+    }
+
+    let config = rustls::ClientConfig::builder_with_provider(Arc::new(
+        rustls::crypto::ring::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()?
+    .with_root_certificates(roots)
+    .with_no_client_auth();
+
+    Ok(tokio_rustls::TlsConnector::from(Arc::new(config)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOKENS: &str = "# CI runners\nci:s3cret\n\nbackup:another-secret\n";
+
+    #[test]
+    fn test_token_store_parse() {
+        let store = TokenStore::parse(TOKENS).unwrap();
+        assert_eq!(store.len(), 2);
+
+        assert!(TokenStore::parse("no-separator").is_err());
+        assert!(TokenStore::parse("ci:a\nci:b").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_challenge_response_accepts_valid_token() {
+        let store = TokenStore::parse(TOKENS).unwrap();
+        let token = AuthToken::parse("ci:s3cret").unwrap();
+
+        let (client, server) = tokio::io::duplex(1024);
+        let (mut server_reader, mut server_writer) = tokio::io::split(server);
+        let (mut client_reader, mut client_writer) = tokio::io::split(client);
+
+        let server = tokio::spawn(async move {
+            authenticate_client(&mut server_reader, &mut server_writer, &store).await
+        });
+        answer_challenge(&mut client_reader, &mut client_writer, &token)
+            .await
+            .unwrap();
+
+        assert_eq!(server.await.unwrap().unwrap(), Some("ci".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_challenge_response_rejects_wrong_secret() {
+        let store = TokenStore::parse(TOKENS).unwrap();
+        let token = AuthToken::parse("ci:guessed").unwrap();
+
+        let (client, server) = tokio::io::duplex(1024);
+        let (mut server_reader, mut server_writer) = tokio::io::split(server);
+        let (mut client_reader, mut client_writer) = tokio::io::split(client);
+
+        let server = tokio::spawn(async move {
+            authenticate_client(&mut server_reader, &mut server_writer, &store).await
+        });
+        let err = answer_challenge(&mut client_reader, &mut client_writer, &token)
+            .await
+            .unwrap_err();
+
+        assert!(err.to_string().contains("rejected"));
+        assert_eq!(server.await.unwrap().unwrap(), None);
+    }
+}
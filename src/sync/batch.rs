@@ -0,0 +1,945 @@
+//! Batch files for offline replication (--write-batch / --read-batch)
+//!
+//! A batch records one sync's changes so the same update can be replayed on
+//! mirrors that can't be reached while syncing. Updated files are stored as
+//! delta ops against the destination file they were computed from (their
+//! basis); new files are stored whole unless --fuzzy found a basis for them.
+//!
+//! Layout: the magic `SYBATCH1`, then length-prefixed (u32 big-endian)
+//! bincode records. The first record is a header describing the batch, the
+//! last a trailer holding the record count and a BLAKE3 digest of every
+//! byte before it. Each file carries the digest of its basis and of the
+//! finished file, so replaying onto a destination that doesn't match the
+//! original fails before anything is written.
+
+use super::strategy::{SyncAction, SyncTask};
+use crate::delta::{compute_checksums, generate_delta_streaming, BlockSizePolicy, DeltaOp};
+use crate::integrity::Blake3Hasher;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Component, Path, PathBuf};
+
+const MAGIC: &[u8; 8] = b"SYBATCH1";
+
+/// BLAKE3 digest of a file or of the batch itself
+type Digest = [u8; 32];
+
+/// Batch format version (bumped on incompatible record changes)
+pub const BATCH_VERSION: u32 = 1;
+
+/// Literal data is split into records of at most this size
+const DATA_CHUNK: usize = 1024 * 1024;
+
+/// Largest record a reader accepts
+const MAX_RECORD: u32 = 16 * 1024 * 1024;
+
+/// Describes a batch: where it came from and what it changes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchHeader {
+    pub version: u32,
+    /// sy version that wrote the batch
+    pub writer: String,
+    /// Unix time the batch was written
+    pub created: u64,
+    pub source: String,
+    pub destination: String,
+    pub files: u64,
+    pub dirs: u64,
+    pub symlinks: u64,
+    pub deletions: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct FileRecord {
+    path: String,
+    /// Destination file the Copy ops read from, with its digest
+    basis: Option<(String, Digest)>,
+    /// Permission bits (Unix only)
+    mode: Option<u32>,
+    /// Modification time as (seconds, nanoseconds) since the Unix epoch
+    mtime: (i64, u32),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+enum Record {
+    Header(BatchHeader),
+    Dir {
+        path: String,
+        mode: Option<u32>,
+    },
+    Symlink {
+        path: String,
+        target: String,
+    },
+    /// Starts a file; its Op records follow, then FileEnd
+    File(FileRecord),
+    Op(DeltaOp),
+    FileEnd {
+        digest: Digest,
+    },
+    Delete {
+        path: String,
+    },
+    Trailer {
+        records: u64,
+        digest: Digest,
+    },
+}
+
+/// What writing or applying a batch did
+#[derive(Debug, Default, Clone, Copy)]
+pub struct BatchStats {
+    pub files: u64,
+    /// Files the destination already had in their final form
+    pub files_current: u64,
+    pub dirs: u64,
+    pub symlinks: u64,
+    pub deletions: u64,
+    /// File data carried in the batch
+    pub literal_bytes: u64,
+    /// File data reconstructed from destination files
+    pub matched_bytes: u64,
+}
+
+struct FrameWriter<W: Write> {
+    out: W,
+    hasher: blake3::Hasher,
+    records: u64,
+}
+
+impl<W: Write> FrameWriter<W> {
+    fn new(mut out: W) -> Result<Self> {
+        out.write_all(MAGIC)?;
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(MAGIC);
+        Ok(Self {
+            out,
+            hasher,
+            records: 0,
+        })
+    }
+
+    fn frame(&mut self, record: &Record) -> Result<Vec<u8>> {
+        let body = bincode::serialize(record)?;
+        let len = u32::try_from(body.len())
+            .ok()
+            .filter(|&len| len <= MAX_RECORD)
+            .context("batch record too large")?;
+        let mut frame = Vec::with_capacity(4 + body.len());
+        frame.extend_from_slice(&len.to_be_bytes());
+        frame.extend_from_slice(&body);
+        Ok(frame)
+    }
+
+    fn record(&mut self, record: &Record) -> Result<()> {
+        let frame = self.frame(record)?;
+        self.out.write_all(&frame)?;
+        self.hasher.update(&frame);
+        self.records += 1;
+        Ok(())
+    }
+
+    fn data(&mut self, data: &[u8]) -> Result<()> {
+        for chunk in data.chunks(DATA_CHUNK) {
+            self.record(&Record::Op(DeltaOp::Data(chunk.to_vec())))?;
+        }
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<()> {
+        let trailer = Record::Trailer {
+            records: self.records,
+            digest: *self.hasher.finalize().as_bytes(),
+        };
+        let frame = self.frame(&trailer)?;
+        self.out.write_all(&frame)?;
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+struct FrameReader<R: Read> {
+    input: R,
+    hasher: blake3::Hasher,
+    records: u64,
+}
+
+impl FrameReader<BufReader<File>> {
+    fn open(path: &Path) -> Result<Self> {
+        let file = File::open(path)
+            .with_context(|| format!("Failed to open batch file {}", path.display()))?;
+        let mut input = BufReader::new(file);
+        let mut magic = [0u8; 8];
+        input
+            .read_exact(&mut magic)
+            .ok()
+            .filter(|_| &magic == MAGIC)
+            .with_context(|| format!("{} is not a sy batch file", path.display()))?;
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(MAGIC);
+        Ok(Self {
+            input,
+            hasher,
+            records: 0,
+        })
+    }
+}
+
+impl<R: Read> FrameReader<R> {
+    /// Read the next record; the trailer is returned but not hashed
+    fn next(&mut self) -> Result<Record> {
+        let mut len = [0u8; 4];
+        self.input
+            .read_exact(&mut len)
+            .context("Batch file is corrupt (truncated)")?;
+        let len = u32::from_be_bytes(len);
+        if len > MAX_RECORD {
+            bail!("Batch file is corrupt (record of {} bytes)", len);
+        }
+        let mut body = vec![0u8; len as usize];
+        self.input
+            .read_exact(&mut body)
+            .context("Batch file is corrupt (truncated)")?;
+        let record: Record =
+            bincode::deserialize(&body).context("Batch file is corrupt (bad record)")?;
+        if !matches!(record, Record::Trailer { .. }) {
+            self.hasher.update(&len.to_be_bytes());
+            self.hasher.update(&body);
+            self.records += 1;
+        }
+        Ok(record)
+    }
+
+    /// Whether a trailer matches the records read before it
+    fn trailer_matches(&self, records: u64, digest: &Digest) -> bool {
+        records == self.records && digest == self.hasher.finalize().as_bytes()
+    }
+}
+
+/// Write the changes in `tasks` (from `SyncEngine::plan`) to a batch file
+///
+/// Creates whose fuzzy basis is a destination file come first, before any
+/// update can change that basis, and deletions come last.
+pub fn write_batch(
+    batch_path: &Path,
+    source_root: &Path,
+    dest_root: &Path,
+    tasks: &[SyncTask],
+    block_sizes: &BlockSizePolicy,
+) -> Result<BatchStats> {
+    let file = File::create(batch_path)
+        .with_context(|| format!("Failed to create batch file {}", batch_path.display()))?;
+    let result = write_records(
+        BufWriter::new(file),
+        source_root,
+        dest_root,
+        tasks,
+        block_sizes,
+    );
+    if result.is_err() {
+        let _ = fs::remove_file(batch_path);
+    }
+    result
+}
+
+fn write_records<W: Write>(
+    out: W,
+    source_root: &Path,
+    dest_root: &Path,
+    tasks: &[SyncTask],
+    block_sizes: &BlockSizePolicy,
+) -> Result<BatchStats> {
+    let mut ordered: Vec<&SyncTask> = tasks.iter().filter(|t| t.basis.is_some()).collect();
+    ordered.extend(tasks.iter().filter(|t| {
+        t.basis.is_none() && matches!(t.action, SyncAction::Create | SyncAction::Update)
+    }));
+    ordered.extend(tasks.iter().filter(|t| t.action == SyncAction::Delete));
+
+    let mut header = BatchHeader {
+        version: BATCH_VERSION,
+        writer: env!("CARGO_PKG_VERSION").to_string(),
+        created: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        source: source_root.display().to_string(),
+        destination: dest_root.display().to_string(),
+        files: 0,
+        dirs: 0,
+        symlinks: 0,
+        deletions: 0,
+    };
+    for task in &ordered {
+        match &task.source {
+            None => header.deletions += 1,
+            Some(entry) if entry.is_dir => header.dirs += 1,
+            Some(entry) if entry.is_symlink => header.symlinks += 1,
+            Some(_) => header.files += 1,
+        }
+    }
+
+    let mut writer = FrameWriter::new(out)?;
+    writer.record(&Record::Header(header))?;
+
+    let mut stats = BatchStats::default();
+    for task in ordered {
+        let path = batch_path_string(dest_root, &task.dest_path)?;
+        let Some(entry) = &task.source else {
+            writer.record(&Record::Delete { path })?;
+            stats.deletions += 1;
+            continue;
+        };
+
+        if entry.is_dir {
+            writer.record(&Record::Dir {
+                path,
+                mode: file_mode(&entry.path),
+            })?;
+            stats.dirs += 1;
+        } else if entry.is_symlink {
+            let target = entry
+                .symlink_target
+                .as_deref()
+                .with_context(|| format!("Symlink without a target: {}", entry.path.display()))?;
+            let target = target
+                .to_str()
+                .with_context(|| format!("Symlink target is not UTF-8: {}", entry.path.display()))?
+                .to_string();
+            writer.record(&Record::Symlink { path, target })?;
+            stats.symlinks += 1;
+        } else {
+            let basis_path = match task.action {
+                SyncAction::Update => Some(task.dest_path.clone()),
+                _ => task.basis.clone(),
+            };
+            let basis = match &basis_path {
+                Some(basis) => Some((
+                    batch_path_string(dest_root, basis)?,
+                    *Blake3Hasher::hash_file(basis)?.as_bytes(),
+                )),
+                None => None,
+            };
+            let mtime = filetime::FileTime::from_system_time(entry.modified);
+            writer.record(&Record::File(FileRecord {
+                path,
+                basis,
+                mode: file_mode(&entry.path),
+                mtime: (mtime.unix_seconds(), mtime.nanoseconds()),
+            }))?;
+
+            match &basis_path {
+                Some(basis) => {
+                    let basis_size = fs::metadata(basis)?.len();
+                    let block_size = block_sizes.block_size(&entry.path, basis_size);
+                    let checksums = compute_checksums(basis, block_size)?;
+                    let delta = generate_delta_streaming(&entry.path, &checksums, block_size)?;
+                    if task.action == SyncAction::Update {
+                        let literal: usize = delta
+                            .ops
+                            .iter()
+                            .map(|op| match op {
+                                DeltaOp::Data(data) => data.len(),
+                                DeltaOp::Copy { .. } => 0,
+                            })
+                            .sum();
+                        block_sizes.record_delta(&entry.path, literal as u64, entry.size);
+                    }
+                    for op in delta.ops {
+                        match op {
+                            DeltaOp::Data(data) => {
+                                stats.literal_bytes += data.len() as u64;
+                                writer.data(&data)?;
+                            }
+                            op @ DeltaOp::Copy { size, .. } => {
+                                stats.matched_bytes += size as u64;
+                                writer.record(&Record::Op(op))?;
+                            }
+                        }
+                    }
+                }
+                None => {
+                    let mut file = File::open(&*entry.path)?;
+                    let mut buffer = vec![0u8; DATA_CHUNK];
+                    loop {
+                        let n = file.read(&mut buffer)?;
+                        if n == 0 {
+                            break;
+                        }
+                        stats.literal_bytes += n as u64;
+                        writer.data(&buffer[..n])?;
+                    }
+                }
+            }
+
+            // Hashed separately so a file changing mid-write is caught on replay
+            let digest = *Blake3Hasher::hash_file(&entry.path)?.as_bytes();
+            writer.record(&Record::FileEnd { digest })?;
+            stats.files += 1;
+        }
+    }
+
+    writer.finish()?;
+    tracing::debug!(
+        "Wrote batch for {} (source {})",
+        dest_root.display(),
+        source_root.display()
+    );
+    Ok(stats)
+}
+
+/// Check the trailer digest and collect the files the batch writes
+fn verify(batch_path: &Path) -> Result<(BatchHeader, Vec<(FileRecord, Digest)>)> {
+    let mut reader = FrameReader::open(batch_path)?;
+    let header = match reader.next()? {
+        Record::Header(header) => header,
+        _ => bail!("Batch file is corrupt (missing header)"),
+    };
+    if header.version != BATCH_VERSION {
+        bail!(
+            "Batch file version {} is not supported (expected {})",
+            header.version,
+            BATCH_VERSION
+        );
+    }
+
+    let mut files = Vec::new();
+    let mut open: Option<FileRecord> = None;
+    loop {
+        match reader.next()? {
+            Record::File(file) => open = Some(file),
+            Record::FileEnd { digest } => {
+                let file = open
+                    .take()
+                    .context("Batch file is corrupt (stray file end)")?;
+                files.push((file, digest));
+            }
+            Record::Trailer { records, digest } => {
+                if !reader.trailer_matches(records, &digest) {
+                    bail!(
+                        "Batch file {} is corrupt (checksum mismatch)",
+                        batch_path.display()
+                    );
+                }
+                return Ok((header, files));
+            }
+            Record::Header(_) => bail!("Batch file is corrupt (second header)"),
+            _ => {}
+        }
+    }
+}
+
+/// Apply a batch to `dest_root`
+///
+/// Every basis is checked against the destination before anything is
+/// written. Files the destination already has in their final form are left
+/// alone, so replaying a batch is harmless.
+pub fn read_batch(batch_path: &Path, dest_root: &Path) -> Result<BatchStats> {
+    let (header, files) = verify(batch_path)?;
+    tracing::debug!(
+        "Applying batch written by sy {} from {}",
+        header.writer,
+        header.source
+    );
+
+    let mut current = HashSet::new();
+    for (file, digest) in &files {
+        let dest = resolve(dest_root, &file.path)?;
+        if dest.is_file() && Blake3Hasher::hash_file(&dest)?.as_bytes() == digest {
+            current.insert(file.path.clone());
+            continue;
+        }
+        match &file.basis {
+            Some((basis, expected)) => {
+                let basis_path = resolve(dest_root, basis)?;
+                if !basis_path.is_file() {
+                    bail!(
+                        "Destination does not match the batch: basis file {} is missing",
+                        basis_path.display()
+                    );
+                }
+                if Blake3Hasher::hash_file(&basis_path)?.as_bytes() != expected {
+                    bail!(
+                        "Destination does not match the batch: basis checksum of {} differs \
+                         from the one the batch was written against",
+                        basis_path.display()
+                    );
+                }
+            }
+            None => {
+                if dest.symlink_metadata().is_ok() {
+                    bail!(
+                        "Destination does not match the batch: {} exists but the batch creates it",
+                        dest.display()
+                    );
+                }
+            }
+        }
+    }
+
+    // The second pass reads the file again, so it must be the one verified:
+    // every file must match the record and digest seen in the first pass,
+    // and directories, symlinks and deletions wait until the trailer checks out
+    let changed = || {
+        format!(
+            "Batch file {} changed while it was applied",
+            batch_path.display()
+        )
+    };
+    let mut expected = files.iter();
+    let mut expected_digest = None;
+    let mut deferred = Vec::new();
+    let mut stats = BatchStats::default();
+    let mut reader = FrameReader::open(batch_path)?;
+    let mut pending: Option<PendingFile> = None;
+    loop {
+        match reader.next()? {
+            Record::Header(_) => {}
+            record @ (Record::Dir { .. } | Record::Symlink { .. } | Record::Delete { .. }) => {
+                deferred.push(record)
+            }
+            Record::File(file) => {
+                let (_, digest) = expected
+                    .next()
+                    .filter(|(verified, _)| *verified == file)
+                    .with_context(changed)?;
+                expected_digest = Some(digest);
+                let dest = resolve(dest_root, &file.path)?;
+                pending = Some(if current.contains(&file.path) {
+                    PendingFile::current(file, dest)
+                } else {
+                    PendingFile::start(file, dest, dest_root)?
+                });
+            }
+            Record::Op(op) => {
+                let file = pending
+                    .as_mut()
+                    .context("Batch file is corrupt (op outside a file)")?;
+                file.apply(&op, &mut stats)?;
+            }
+            Record::FileEnd { digest } => {
+                let file = pending
+                    .take()
+                    .context("Batch file is corrupt (stray file end)")?;
+                if expected_digest.take() != Some(&digest) {
+                    bail!(changed());
+                }
+                if file.finish(digest)? {
+                    stats.files += 1;
+                } else {
+                    stats.files_current += 1;
+                }
+            }
+            Record::Trailer { records, digest } => {
+                if !reader.trailer_matches(records, &digest) || expected.next().is_some() {
+                    bail!(changed());
+                }
+                break;
+            }
+        }
+    }
+
+    for record in deferred {
+        match record {
+            Record::Dir { path, mode } => {
+                let dest = resolve(dest_root, &path)?;
+                fs::create_dir_all(&dest)
+                    .with_context(|| format!("Failed to create {}", dest.display()))?;
+                set_mode(&dest, mode)?;
+                stats.dirs += 1;
+            }
+            Record::Symlink { path, target } => {
+                let dest = resolve(dest_root, &path)?;
+                if let Some(parent) = dest.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                if dest.symlink_metadata().is_ok() {
+                    fs::remove_file(&dest)
+                        .with_context(|| format!("Failed to replace {}", dest.display()))?;
+                }
+                make_symlink(&target, &dest)?;
+                stats.symlinks += 1;
+            }
+            Record::Delete { path } => {
+                let dest = resolve(dest_root, &path)?;
+                let removed = match dest.symlink_metadata() {
+                    Ok(meta) if meta.is_dir() => fs::remove_dir_all(&dest),
+                    Ok(_) => fs::remove_file(&dest),
+                    Err(e) => Err(e),
+                };
+                match removed {
+                    Ok(()) => stats.deletions += 1,
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                    Err(e) => {
+                        return Err(e)
+                            .with_context(|| format!("Failed to delete {}", dest.display()))
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(stats)
+}
+
+/// An open basis file and its length
+type Basis = (File, u64);
+
+/// A file being rebuilt from its ops
+struct PendingFile {
+    record: FileRecord,
+    dest: PathBuf,
+    /// None when the destination is already current and ops are skipped
+    output: Option<(tempfile::NamedTempFile, blake3::Hasher, Option<Basis>)>,
+}
+
+impl PendingFile {
+    fn current(record: FileRecord, dest: PathBuf) -> Self {
+        Self {
+            record,
+            dest,
+            output: None,
+        }
+    }
+
+    fn start(record: FileRecord, dest: PathBuf, dest_root: &Path) -> Result<Self> {
+        let parent = dest.parent().unwrap_or(dest_root);
+        fs::create_dir_all(parent)?;
+        let temp = tempfile::Builder::new()
+            .prefix(".sy-batch")
+            .tempfile_in(parent)?;
+        let basis = match &record.basis {
+            Some((basis, _)) => {
+                let file = File::open(resolve(dest_root, basis)?)?;
+                let len = file.metadata()?.len();
+                Some((file, len))
+            }
+            None => None,
+        };
+        Ok(Self {
+            record,
+            dest,
+            output: Some((temp, blake3::Hasher::new(), basis)),
+        })
+    }
+
+    fn apply(&mut self, op: &DeltaOp, stats: &mut BatchStats) -> Result<()> {
+        let Some((temp, hasher, basis)) = self.output.as_mut() else {
+            return Ok(());
+        };
+        match op {
+            DeltaOp::Data(data) => {
+                temp.write_all(data)?;
+                hasher.update(data);
+                stats.literal_bytes += data.len() as u64;
+            }
+            DeltaOp::Copy { offset, size } => {
+                let (basis, basis_len) = basis
+                    .as_mut()
+                    .context("Batch file is corrupt (copy without a basis)")?;
+                // The size comes from the batch; never trust it beyond the basis
+                if offset
+                    .checked_add(*size as u64)
+                    .is_none_or(|end| end > *basis_len)
+                {
+                    bail!(
+                        "Batch file is corrupt (copy past the end of the basis for {})",
+                        self.dest.display()
+                    );
+                }
+                basis.seek(SeekFrom::Start(*offset))?;
+                let mut block = vec![0u8; (*size).min(DATA_CHUNK)];
+                let mut remaining = *size;
+                while remaining > 0 {
+                    let chunk = &mut block[..remaining.min(DATA_CHUNK)];
+                    basis.read_exact(chunk).with_context(|| {
+                        format!("Basis for {} is shorter than expected", self.dest.display())
+                    })?;
+                    temp.write_all(chunk)?;
+                    hasher.update(chunk);
+                    remaining -= chunk.len();
+                }
+                stats.matched_bytes += *size as u64;
+            }
+        }
+        Ok(())
+    }
+
+    /// Verify and install the file; returns false if it was already current
+    fn finish(self, digest: Digest) -> Result<bool> {
+        let written = match self.output {
+            Some((temp, hasher, _)) => {
+                if hasher.finalize().as_bytes() != &digest {
+                    bail!(
+                        "Rebuilt {} does not match the checksum recorded in the batch",
+                        self.dest.display()
+                    );
+                }
+                temp.as_file().sync_all()?;
+                temp.persist(&self.dest)
+                    .with_context(|| format!("Failed to write {}", self.dest.display()))?;
+                true
+            }
+            None => false,
+        };
+        set_mode(&self.dest, self.record.mode)?;
+        let (secs, nanos) = self.record.mtime;
+        filetime::set_file_mtime(&self.dest, filetime::FileTime::from_unix_time(secs, nanos))?;
+        Ok(written)
+    }
+}
+
+/// Batch paths are '/'-separated and relative to the destination root
+fn batch_path_string(dest_root: &Path, path: &Path) -> Result<String> {
+    let relative = path
+        .strip_prefix(dest_root)
+        .with_context(|| format!("{} is outside {}", path.display(), dest_root.display()))?;
+    let mut parts = Vec::new();
+    for component in relative.components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_str().with_context(|| {
+                format!("Path is not UTF-8 and can't be batched: {}", path.display())
+            })?),
+            Component::CurDir => {}
+            _ => bail!("Unexpected path in batch: {}", path.display()),
+        }
+    }
+    Ok(parts.join("/"))
+}
+
+/// Map a batch path onto `dest_root`, refusing anything that escapes it
+fn resolve(dest_root: &Path, path: &str) -> Result<PathBuf> {
+    let mut resolved = dest_root.to_path_buf();
+    for part in path.split('/') {
+        if part.is_empty() || part == "." || part == ".." || part.contains('\0') {
+            bail!("Batch file contains an unsafe path: {:?}", path);
+        }
+        resolved.push(part);
+    }
+    Ok(resolved)
+}
+
+#[cfg(unix)]
+fn file_mode(path: &Path) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::symlink_metadata(path)
+        .ok()
+        .map(|meta| meta.permissions().mode() & 0o7777)
+}
+
+#[cfg(not(unix))]
+fn file_mode(_path: &Path) -> Option<u32> {
+    None
+}
+
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: Option<u32>) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    if let Some(mode) = mode {
+        fs::set_permissions(path, fs::Permissions::from_mode(mode))?;
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn set_mode(_path: &Path, _mode: Option<u32>) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(unix)]
+fn make_symlink(target: &str, link: &Path) -> Result<()> {
+    std::os::unix::fs::symlink(target, link)
+        .with_context(|| format!("Failed to create symlink {}", link.display()))
+}
+
+#[cfg(not(unix))]
+fn make_symlink(_target: &str, link: &Path) -> Result<()> {
+    tracing::warn!("Skipping symlink {} (not supported here)", link.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sync::scanner::Scanner;
+    use crate::sync::strategy::StrategyPlanner;
+    use std::collections::HashMap;
+    use tempfile::TempDir;
+
+    fn plan(source: &Path, dest: &Path) -> Vec<SyncTask> {
+        let source_files = Scanner::new(source).scan().unwrap();
+        let dest_map: HashMap<PathBuf, _> = Scanner::new(dest)
+            .scan()
+            .unwrap()
+            .into_iter()
+            .map(|f| ((*f.relative_path).clone(), f))
+            .collect();
+        let planner = StrategyPlanner::new();
+        let mut tasks: Vec<SyncTask> = source_files
+            .iter()
+            .map(|f| planner.plan_file_with_dest_map(f, dest, &dest_map))
+            .filter(|t| t.action != SyncAction::Skip)
+            .collect();
+        tasks.extend(planner.plan_deletions(&source_files, dest));
+        tasks
+    }
+
+    /// Source with an edited large file, a new file and a stale file in dest
+    fn setup() -> (TempDir, TempDir, Vec<u8>) {
+        let source = TempDir::new().unwrap();
+        let dest = TempDir::new().unwrap();
+        let old: Vec<u8> = (0..300_000u32).map(|i| (i * 31 % 251) as u8).collect();
+        let mut new = old.clone();
+        new[150_000..150_008].copy_from_slice(b"modified");
+
+        fs::create_dir(source.path().join("data")).unwrap();
+        fs::create_dir(dest.path().join("data")).unwrap();
+        fs::write(source.path().join("data/big.bin"), &new).unwrap();
+        fs::write(dest.path().join("data/big.bin"), &old).unwrap();
+        filetime::set_file_mtime(
+            dest.path().join("data/big.bin"),
+            filetime::FileTime::from_unix_time(1_600_000_000, 0),
+        )
+        .unwrap();
+        fs::write(source.path().join("data/new.txt"), b"fresh").unwrap();
+        fs::write(dest.path().join("stale.txt"), b"old").unwrap();
+        (source, dest, new)
+    }
+
+    fn copy_tree(from: &Path, to: &Path) {
+        for entry in Scanner::new(from).scan().unwrap() {
+            let target = to.join(&*entry.relative_path);
+            if entry.is_dir {
+                fs::create_dir_all(&target).unwrap();
+            } else {
+                fs::create_dir_all(target.parent().unwrap()).unwrap();
+                fs::copy(&*entry.path, &target).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn test_batch_replays_onto_matching_mirror() {
+        let (source, dest, new) = setup();
+        let mirror = TempDir::new().unwrap();
+        copy_tree(dest.path(), mirror.path());
+
+        let batch = TempDir::new().unwrap();
+        let batch = batch.path().join("update.batch");
+        let tasks = plan(source.path(), dest.path());
+        let written = write_batch(
+            &batch,
+            source.path(),
+            dest.path(),
+            &tasks,
+            &BlockSizePolicy::default(),
+        )
+        .unwrap();
+        assert_eq!(written.files, 2);
+        assert_eq!(written.deletions, 1);
+        assert!(written.matched_bytes > written.literal_bytes);
+
+        let applied = read_batch(&batch, mirror.path()).unwrap();
+        assert_eq!(applied.files, 2);
+        assert_eq!(fs::read(mirror.path().join("data/big.bin")).unwrap(), new);
+        assert_eq!(
+            fs::read(mirror.path().join("data/new.txt")).unwrap(),
+            b"fresh"
+        );
+        assert!(!mirror.path().join("stale.txt").exists());
+
+        // Replaying finds everything already current
+        let again = read_batch(&batch, mirror.path()).unwrap();
+        assert_eq!(again.files, 0);
+        assert_eq!(again.files_current, 2);
+    }
+
+    #[test]
+    fn test_batch_rejects_mismatched_basis() {
+        let (source, dest, _) = setup();
+        let mirror = TempDir::new().unwrap();
+        copy_tree(dest.path(), mirror.path());
+        fs::write(mirror.path().join("data/big.bin"), b"diverged").unwrap();
+
+        let batch = TempDir::new().unwrap();
+        let batch = batch.path().join("update.batch");
+        let tasks = plan(source.path(), dest.path());
+        write_batch(
+            &batch,
+            source.path(),
+            dest.path(),
+            &tasks,
+            &BlockSizePolicy::default(),
+        )
+        .unwrap();
+
+        let err = read_batch(&batch, mirror.path()).unwrap_err();
+        assert!(err.to_string().contains("basis checksum"), "{}", err);
+        // Nothing was applied
+        assert!(!mirror.path().join("data/new.txt").exists());
+        assert!(mirror.path().join("stale.txt").exists());
+    }
+
+    #[test]
+    fn test_batch_detects_corruption() {
+        let (source, dest, _) = setup();
+        let batch = TempDir::new().unwrap();
+        let batch = batch.path().join("update.batch");
+        let tasks = plan(source.path(), dest.path());
+        write_batch(
+            &batch,
+            source.path(),
+            dest.path(),
+            &tasks,
+            &BlockSizePolicy::default(),
+        )
+        .unwrap();
+
+        let mut bytes = fs::read(&batch).unwrap();
+        let middle = bytes.len() / 2;
+        bytes[middle] ^= 0xff;
+        fs::write(&batch, &bytes).unwrap();
+
+        let err = read_batch(&batch, dest.path()).unwrap_err();
+        assert!(err.to_string().contains("corrupt"), "{}", err);
+        assert!(dest.path().join("stale.txt").exists());
+    }
+
+    #[test]
+    fn test_copy_past_basis_is_refused() {
+        let dest = TempDir::new().unwrap();
+        fs::write(dest.path().join("basis.bin"), b"0123456789").unwrap();
+        let record = FileRecord {
+            path: "out.bin".to_string(),
+            basis: Some(("basis.bin".to_string(), [0; 32])),
+            mode: None,
+            mtime: (0, 0),
+        };
+        let mut file =
+            PendingFile::start(record, dest.path().join("out.bin"), dest.path()).unwrap();
+        let mut stats = BatchStats::default();
+
+        file.apply(&DeltaOp::Copy { offset: 2, size: 8 }, &mut stats)
+            .unwrap();
+        assert_eq!(stats.matched_bytes, 8);
+
+        // A crafted size must fail before anything is allocated for it
+        for (offset, size) in [(0, usize::MAX / 2), (4, 7), (u64::MAX, 1)] {
+            let err = file
+                .apply(&DeltaOp::Copy { offset, size }, &mut stats)
+                .unwrap_err();
+            assert!(err.to_string().contains("past the end"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_resolve_refuses_escapes() {
+        let root = Path::new("/mirror");
+        assert_eq!(resolve(root, "a/b").unwrap(), root.join("a/b"));
+        assert!(resolve(root, "../etc/passwd").is_err());
+        assert!(resolve(root, "a//b").is_err());
+        assert!(resolve(root, "").is_err());
+    }
+}
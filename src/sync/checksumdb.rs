@@ -1,3 +1,4 @@
+use crate::delta::cdc::Chunk;
 use crate::error::Result;
 use crate::integrity::Checksum;
 use fjall::{Config, Keyspace, PartitionHandle};
@@ -17,9 +18,21 @@ struct ChecksumEntry {
     updated_at: i64,
 }
 
+/// Content-defined chunk list stored in the checksum database
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ChunkEntry {
+    mtime_secs: i64,
+    mtime_nanos: i32,
+    size: u64,
+    avg_size: u32,
+    chunks: Vec<Chunk>,
+    updated_at: i64,
+}
+
 /// Persistent checksum database for fast re-verification
 ///
-/// Stores file checksums with metadata to avoid recomputing on every sync.
+/// Stores file checksums with metadata to avoid recomputing on every sync,
+/// and the chunk lists CDC delta sync (`--delta-algo=cdc`) matches against.
 /// Uses fjall LSM-tree for efficient key-value storage.
 #[allow(dead_code)] // Integration with SyncEngine pending
 pub struct ChecksumDatabase {
@@ -28,6 +41,7 @@ pub struct ChecksumDatabase {
     /// invalidates the partition. Rust's ownership rules (keyspace field) ensure this never happens.
     keyspace: Keyspace,
     partition: PartitionHandle,
+    chunk_partition: PartitionHandle,
 }
 
 #[allow(dead_code)] // Integration with SyncEngine pending
@@ -38,19 +52,28 @@ impl ChecksumDatabase {
     /// Partition name for checksums
     const PARTITION_NAME: &'static str = "checksums";
 
+    /// Partition name for content-defined chunk lists
+    const CHUNK_PARTITION_NAME: &'static str = "chunks";
+
     /// Open or create checksum database in destination directory
     pub fn open(dest_path: &Path) -> Result<Self> {
-        let db_path = dest_path.join(Self::DB_DIR);
+        Self::open_at(&dest_path.join(Self::DB_DIR))
+    }
 
+    /// Open or create checksum database at an explicit directory
+    pub fn open_at(db_path: &Path) -> Result<Self> {
         // Create keyspace with default config
-        let keyspace = Config::new(&db_path).open()?;
+        let keyspace = Config::new(db_path).open()?;
 
         // Open or create partition for checksums
         let partition = keyspace.open_partition(Self::PARTITION_NAME, Default::default())?;
+        let chunk_partition =
+            keyspace.open_partition(Self::CHUNK_PARTITION_NAME, Default::default())?;
 
         Ok(Self {
             keyspace,
             partition,
+            chunk_partition,
         })
     }
 
@@ -170,18 +193,80 @@ impl ChecksumDatabase {
         Ok(())
     }
 
-    /// Clear all cached checksums
+    /// Get a cached chunk list if the file is unchanged and was chunked
+    /// with the same average size
+    pub fn get_chunks(
+        &self,
+        path: &Path,
+        mtime: SystemTime,
+        size: u64,
+        avg_size: u32,
+    ) -> Result<Option<Vec<Chunk>>> {
+        let Some(value) = self.chunk_partition.get(Self::path_to_key(path))? else {
+            return Ok(None);
+        };
+        let entry: ChunkEntry = bincode::deserialize(&value).map_err(|e| {
+            crate::error::SyncError::Database(format!(
+                "Failed to deserialize chunk list for {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+
+        let (mtime_secs, mtime_nanos) = system_time_to_parts(mtime);
+        if entry.mtime_secs != mtime_secs
+            || entry.mtime_nanos != mtime_nanos
+            || entry.size != size
+            || entry.avg_size != avg_size
+        {
+            tracing::debug!("Stale chunk list for {}", path.display());
+            return Ok(None);
+        }
+
+        tracing::debug!("Chunk list cache hit for {}", path.display());
+        Ok(Some(entry.chunks))
+    }
+
+    /// Store a file's chunk list
+    pub fn store_chunks(
+        &self,
+        path: &Path,
+        mtime: SystemTime,
+        size: u64,
+        avg_size: u32,
+        chunks: &[Chunk],
+    ) -> Result<()> {
+        let (mtime_secs, mtime_nanos) = system_time_to_parts(mtime);
+        let entry = ChunkEntry {
+            mtime_secs,
+            mtime_nanos,
+            size,
+            avg_size,
+            chunks: chunks.to_vec(),
+            updated_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs() as i64,
+        };
+        let value = bincode::serialize(&entry)?;
+        self.chunk_partition
+            .insert(Self::path_to_key(path), &value)?;
+        Ok(())
+    }
+
+    /// Clear all cached checksums and chunk lists
     pub fn clear(&self) -> Result<()> {
-        // Collect all keys first (can't delete while iterating)
-        let keys: Vec<_> = self
-            .partition
-            .iter()
-            .map(|item| item.map(|(k, _)| k.to_vec()))
-            .collect::<std::result::Result<_, _>>()?;
-
-        // Delete all entries
-        for key in keys {
-            self.partition.remove(&key)?;
+        for partition in [&self.partition, &self.chunk_partition] {
+            // Collect all keys first (can't delete while iterating)
+            let keys: Vec<_> = partition
+                .iter()
+                .map(|item| item.map(|(k, _)| k.to_vec()))
+                .collect::<std::result::Result<_, _>>()?;
+
+            // Delete all entries
+            for key in keys {
+                partition.remove(&key)?;
+            }
         }
 
         tracing::info!("Cleared checksum database");
@@ -197,23 +282,27 @@ impl ChecksumDatabase {
     /// Paths are matched against the set after round-tripping through to_string_lossy().
     pub fn prune(&self, existing_files: &HashSet<PathBuf>) -> Result<usize> {
         // Collect paths to delete
-        let mut to_delete = Vec::new();
+        let mut deleted_count = 0;
 
-        for item in self.partition.iter() {
-            let (key, _) = item?;
-            // Use same lossy conversion as path_to_key() for consistent matching
-            let path_str = String::from_utf8_lossy(&key);
-            let path = PathBuf::from(path_str.as_ref());
+        for partition in [&self.partition, &self.chunk_partition] {
+            let mut to_delete = Vec::new();
+
+            for item in partition.iter() {
+                let (key, _) = item?;
+                // Use same lossy conversion as path_to_key() for consistent matching
+                let path_str = String::from_utf8_lossy(&key);
+                let path = PathBuf::from(path_str.as_ref());
 
-            if !existing_files.contains(&path) {
-                to_delete.push(key.to_vec());
+                if !existing_files.contains(&path) {
+                    to_delete.push(key.to_vec());
+                }
             }
-        }
 
-        // Delete stale entries
-        let deleted_count = to_delete.len();
-        for key in to_delete {
-            self.partition.remove(&key)?;
+            // Delete stale entries
+            deleted_count += to_delete.len();
+            for key in to_delete {
+                partition.remove(&key)?;
+            }
         }
 
         if deleted_count > 0 {
@@ -459,4 +548,40 @@ mod tests {
         let retrieved = db.get_checksum(&path, mtime, size, "fast").unwrap();
         assert_eq!(retrieved.unwrap(), checksum2);
     }
+
+    #[test]
+    fn test_chunk_list_storage() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = ChecksumDatabase::open(temp_dir.path()).unwrap();
+
+        let path = PathBuf::from("archive.tar");
+        let mtime = SystemTime::now();
+        let chunks = vec![
+            Chunk {
+                offset: 0,
+                size: 4000,
+                strong: 1,
+                tag: 2,
+            },
+            Chunk {
+                offset: 4000,
+                size: 96,
+                strong: 3,
+                tag: 4,
+            },
+        ];
+        db.store_chunks(&path, mtime, 4096, 8192, &chunks).unwrap();
+
+        assert_eq!(
+            db.get_chunks(&path, mtime, 4096, 8192).unwrap(),
+            Some(chunks)
+        );
+        // Changed file or different chunking: recompute
+        assert!(db.get_chunks(&path, mtime, 4097, 8192).unwrap().is_none());
+        assert!(db.get_chunks(&path, mtime, 4096, 16384).unwrap().is_none());
+
+        // Chunk lists go with their checksums on prune and clear
+        assert_eq!(db.prune(&HashSet::new()).unwrap(), 1);
+        assert!(db.get_chunks(&path, mtime, 4096, 8192).unwrap().is_none());
+    }
 }
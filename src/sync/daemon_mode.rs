@@ -23,15 +23,26 @@ use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Instant;
 
-use crate::compress::{compress, is_compressed_extension, Compression};
-use crate::delta::{generate_delta_streaming, BlockChecksum as DeltaBlockChecksum};
+use crate::compress::is_compressed_extension;
+use crate::delta::cdc;
+use crate::delta::{BlockSizePolicy, DeltaAlgo};
+use crate::integrity::ChecksumType;
+use crate::server::confine::{self, Confinement};
+use crate::server::digest::{self, Verdict};
+use crate::server::layout::{self, Chunk, ChunkPlan, HardlinkGroups};
+use crate::server::meta::{self, MetaOptions};
+use crate::server::partial;
 use crate::server::protocol::{
-    delta_block_size, Action, Decision, DeltaOp, FileListEntry, SymlinkEntry, DATA_FLAG_COMPRESSED,
-    DELTA_MIN_SIZE,
+    Action, ChecksumReq, ChecksumResp, Decision, DeltaOp, EntryMeta, FileListEntry, SymlinkEntry,
+    CAP_COMPRESS_ZSTD, CAP_DELTA_CDC, CAP_FILE_CHECKSUM, CAP_FUZZY, CAP_HARDLINKS, CAP_META_BLOCK,
+    CAP_RESUME, CAP_SPARSE, CAP_STREAM_ZSTD, DATA_FLAG_FINAL, DELTA_MIN_SIZE, FLAG_FUZZY,
+    STATUS_CHECKSUM_MISMATCH, STATUS_OK, VERIFY_RETRIES,
 };
+use crate::server::tcp::TcpEndpoint;
 use crate::sync::scanner::{self, ScanOptions};
+use crate::sync::server_mode::{delta_from_checksums, TransferOptions};
 use crate::sync::SyncStats;
-use crate::transport::server::DaemonSession;
+use crate::transport::server::{DaemonSession, PullSession};
 
 /// Minimum size for compression (1MB)
 const COMPRESS_MIN_SIZE: u64 = 1024 * 1024;
@@ -39,6 +50,9 @@ const COMPRESS_MIN_SIZE: u64 = 1024 * 1024;
 /// Number of delta checksum requests to pipeline before reading responses
 const PIPELINE_DEPTH: usize = 8;
 
+/// A computed delta: (index, ops, literal bytes, digest of the source)
+type FileDelta = (u32, Vec<DeltaOp>, u64, Vec<u8>);
+
 /// Source entry with all info needed for transfer
 struct SourceEntry {
     rel_path: String,
@@ -49,6 +63,11 @@ struct SourceEntry {
     is_dir: bool,
     is_symlink: bool,
     symlink_target: Option<String>,
+    meta: Option<EntryMeta>,
+    /// Inode and link count, only recorded with -H
+    inode: Option<u64>,
+    nlink: u64,
+    sparse: bool,
 }
 
 /// Sync from local source to daemon destination (PUSH mode)
@@ -57,20 +76,84 @@ struct SourceEntry {
 /// * `source` - Local source directory
 /// * `socket_path` - Path to Unix socket (local or forwarded from remote)
 /// * `remote_path` - Destination path on daemon side
+/// * `meta` - Metadata to send with each file
+/// * `transfer` - Delta, verification and session options
 pub async fn sync_daemon_mode(
     source: &Path,
     socket_path: &str,
     remote_path: &Path,
+    meta: MetaOptions,
+    transfer: TransferOptions,
 ) -> Result<SyncStats> {
     let start = Instant::now();
 
     // Connect to daemon
-    let mut session = DaemonSession::connect(socket_path, remote_path).await?;
+    let session = DaemonSession::connect(socket_path, remote_path, transfer.session).await?;
     tracing::debug!("Connected to daemon at {}", socket_path);
 
+    push_to_daemon(session, source, meta, transfer, start).await
+}
+
+/// Sync from local source to a daemon's TCP listener (PUSH mode)
+///
+/// # Arguments
+/// * `source` - Local source directory
+/// * `endpoint` - Daemon host, port, token and optional TLS CA
+/// * `remote_path` - Destination path on daemon side
+/// * `meta` - Metadata to send with each file
+/// * `transfer` - Delta, verification and session options
+pub async fn sync_tcp_daemon_mode(
+    source: &Path,
+    endpoint: &TcpEndpoint,
+    remote_path: &Path,
+    meta: MetaOptions,
+    transfer: TransferOptions,
+) -> Result<SyncStats> {
+    let start = Instant::now();
+
+    let session = DaemonSession::connect_tcp(endpoint, remote_path, transfer.session).await?;
+    tracing::debug!("Connected to daemon at {}:{}", endpoint.host, endpoint.port);
+
+    push_to_daemon(session, source, meta, transfer, start).await
+}
+
+/// Push the local source tree over an established daemon session
+async fn push_to_daemon(
+    mut session: DaemonSession,
+    source: &Path,
+    meta: MetaOptions,
+    transfer: TransferOptions,
+    start: Instant,
+) -> Result<SyncStats> {
     // Scan source
     tracing::debug!("Scanning source...");
-    let source_entries = scan_source(source).await?;
+    let source_entries = scan_source(source, meta, transfer).await?;
+
+    // Per-file compression is redundant when the whole stream is compressed
+    let features = session.negotiated();
+    let can_compress = features.has(CAP_COMPRESS_ZSTD) && !features.has(CAP_STREAM_ZSTD);
+    let can_meta = features.has(CAP_META_BLOCK);
+    let can_sparse = features.has(CAP_SPARSE);
+    let can_resume = features.has(CAP_RESUME);
+    let block_sizes = BlockSizePolicy::new(transfer.block_size);
+    let cdc = transfer.delta == DeltaAlgo::Cdc && features.has(CAP_DELTA_CDC);
+    if transfer.delta == DeltaAlgo::Cdc && !cdc {
+        tracing::debug!("Daemon lacks CDC delta, using fixed blocks");
+    }
+    // New files may be delta-encoded against a similar file (--fuzzy)
+    let fuzzy = transfer.fuzzy && features.has(CAP_FUZZY);
+    // Whole-file digests ride along with the data when asked for (--verify)
+    let verify = if features.has(CAP_FILE_CHECKSUM) {
+        transfer.verify
+    } else {
+        ChecksumType::None
+    };
+    let plan = ChunkPlan {
+        sparse: can_sparse,
+        split: layout::final_chunks(features),
+        digest: verify,
+        ..Default::default()
+    };
 
     // Separate entries by type
     let mut directories: Vec<String> = Vec::new();
@@ -87,16 +170,32 @@ pub async fn sync_daemon_mode(
         }
     }
 
+    // Later links to an inode already in the list are sent as links, not data
+    let mut groups = HardlinkGroups::new();
+    let can_link = features.has(CAP_HARDLINKS);
+    let links: Vec<Option<u32>> = files
+        .iter()
+        .enumerate()
+        .map(|(idx, e)| {
+            can_link
+                .then(|| groups.leader(e.inode, e.nlink, idx as u32))
+                .flatten()
+        })
+        .collect();
+
     // Build protocol entries (files only for FILE_LIST comparison)
     let proto_entries: Vec<FileListEntry> = files
         .iter()
-        .map(|e| FileListEntry {
+        .zip(&links)
+        .map(|(e, link)| FileListEntry {
             path: e.rel_path.clone(),
             size: e.size,
             mtime: e.mtime,
             mode: e.mode,
-            flags: 0,
+            flags: if fuzzy { FLAG_FUZZY } else { 0 },
             symlink_target: None,
+            hardlink_to: *link,
+            meta: e.meta.clone().filter(|_| can_meta),
         })
         .collect();
 
@@ -132,6 +231,14 @@ pub async fn sync_daemon_mode(
     tracing::debug!("Waiting for daemon decisions...");
     let ack = session.read_ack().await?;
 
+    // Partials the receiver kept from an interrupted run (CAP_RESUME)
+    let resumes: HashMap<u32, u64> = ack
+        .decisions
+        .iter()
+        .filter(|d| can_resume && d.resume_from > 0)
+        .map(|d| (d.index, d.resume_from))
+        .collect();
+
     // Count files needing transfer
     let files_to_transfer = ack
         .decisions
@@ -144,13 +251,36 @@ pub async fn sync_daemon_mode(
     let mut bytes_transferred = 0u64;
     let mut files_created = 0u64;
     let mut files_updated = 0u64;
+    // Files the daemon failed to verify, to send again whole
+    let mut mismatched: Vec<(u32, Action)> = Vec::new();
+
+    // Holes cost nothing as sparse chunks, so sparse files skip delta;
+    // so do files continuing from a partial
+    let delta_ok = |idx: &u32, e: &SourceEntry| {
+        e.size >= DELTA_MIN_SIZE && !(can_sparse && e.sparse) && !resumes.contains_key(idx)
+    };
+
+    // Categorize by action type; new files with a basis (--fuzzy) are
+    // delta-encoded along with the updates
+    let mut creates: Vec<(u32, &SourceEntry)> = Vec::new();
+    let mut based: Vec<(u32, &SourceEntry)> = Vec::new();
+    for d in &ack.decisions {
+        if d.action != Action::Create || links[d.index as usize].is_some() {
+            continue;
+        }
+        let entry = &files[d.index as usize];
+        if d.basis.is_some() && delta_ok(&d.index, entry) {
+            based.push((d.index, entry));
+        } else {
+            creates.push((d.index, entry));
+        }
+    }
 
-    // Categorize by action type
-    let creates: Vec<(u32, &SourceEntry)> = ack
+    let updates: Vec<(u32, &SourceEntry)> = ack
         .decisions
         .iter()
         .filter_map(|d| {
-            if d.action == Action::Create {
+            if d.action == Action::Update && links[d.index as usize].is_none() {
                 Some((d.index, &files[d.index as usize]))
             } else {
                 None
@@ -158,76 +288,52 @@ pub async fn sync_daemon_mode(
         })
         .collect();
 
-    let updates: Vec<(u32, &SourceEntry)> = ack
+    let hardlinks: Vec<(u32, Action)> = ack
         .decisions
         .iter()
-        .filter_map(|d| {
-            if d.action == Action::Update {
-                Some((d.index, &files[d.index as usize]))
-            } else {
-                None
-            }
-        })
+        .filter(|d| d.action != Action::Skip && links[d.index as usize].is_some())
+        .map(|d| (d.index, d.action))
         .collect();
 
     // Step 3a: Handle CREATES with full file transfer (+ compression)
     if !creates.is_empty() {
         tracing::debug!("Transferring {} new files...", creates.len());
 
-        let paths: Vec<(u32, Arc<PathBuf>, String, u64)> = creates
-            .iter()
-            .map(|(idx, e)| (*idx, e.abs_path.clone(), e.rel_path.clone(), e.size))
-            .collect();
-
-        // Read and optionally compress files
-        let files_data: Vec<(u32, Vec<u8>, u8)> = tokio::task::spawn_blocking(move || {
-            paths
-                .into_iter()
-                .filter_map(|(idx, path, rel_path, size)| {
-                    std::fs::read(&*path).ok().map(|data| {
-                        // Compress if file is large enough and not already compressed
-                        let (send_data, flags) =
-                            if size >= COMPRESS_MIN_SIZE && !is_compressed_extension(&rel_path) {
-                                match compress(&data, Compression::Zstd) {
-                                    Ok(compressed) if compressed.len() < data.len() => {
-                                        (compressed, DATA_FLAG_COMPRESSED)
-                                    }
-                                    _ => (data, 0),
-                                }
-                            } else {
-                                (data, 0)
-                            };
-                        (idx, send_data, flags)
-                    })
-                })
-                .collect()
-        })
-        .await?;
+        // Read (hole-aware) and optionally compress files
+        let files_data = read_chunks(&creates, plan, can_compress, &resumes).await?;
 
         // Send all creates
-        for (idx, data, flags) in &files_data {
-            bytes_transferred += data.len() as u64;
-            session
-                .send_file_data_with_flags(*idx, 0, *flags, data.clone())
-                .await?;
+        for (idx, chunks) in &files_data {
+            bytes_transferred += send_chunks(&mut session, *idx, chunks).await?;
         }
         session.flush().await?;
 
         // Read confirmations
-        for _ in &files_data {
+        for (idx, chunks) in &files_data {
             let done = session.read_file_done().await?;
-            if done.status != 0 {
-                tracing::error!("Create failed: index {} status {}", done.index, done.status);
-            } else {
-                files_created += 1;
+            match digest::verdict(&done, layout::sent_digest(chunks)) {
+                Verdict::Ok => files_created += 1,
+                Verdict::Mismatch => mismatched.push((*idx, Action::Create)),
+                Verdict::Failed => {
+                    tracing::error!("Create failed: index {} status {}", done.index, done.status);
+                }
             }
         }
     }
 
-    // Step 3b: Handle UPDATES - use delta sync for large files
-    if !updates.is_empty() {
-        let (delta_candidates, full_updates): (Vec<_>, Vec<_>) =
-            updates.iter().partition(|(_, e)| e.size >= DELTA_MIN_SIZE);
+    // Step 3b: Handle UPDATES (and creates with a basis) - use delta sync for large files
+    if !updates.is_empty() || !based.is_empty() {
+        let (delta_updates, full_updates): (Vec<_>, Vec<_>) =
+            updates.iter().partition(|(idx, e)| delta_ok(idx, e));
+        let delta_candidates: Vec<(u32, &SourceEntry, Action)> = based
+            .iter()
+            .map(|(idx, e)| (*idx, *e, Action::Create))
+            .chain(
+                delta_updates
+                    .iter()
+                    .map(|(idx, e)| (*idx, *e, Action::Update)),
+            )
+            .collect();
 
         // Process delta candidates with pipelined checksum requests
         if !delta_candidates.is_empty() {
@@ -239,23 +345,40 @@ pub async fn sync_daemon_mode(
             );
 
             // Collect pending requests for batching
-            let mut pending: Vec<(u32, &SourceEntry, u32)> = Vec::with_capacity(PIPELINE_DEPTH);
-
-            for (idx, entry) in &delta_candidates {
-                let file_idx = *idx;
-                let block_size = delta_block_size(entry.size);
+            let mut pending: Vec<(u32, &SourceEntry, Action, ChecksumReq)> =
+                Vec::with_capacity(PIPELINE_DEPTH);
+
+            for (idx, entry, action) in &delta_candidates {
+                let req = if cdc {
+                    ChecksumReq {
+                        index: *idx,
+                        block_size: cdc::avg_chunk_size(entry.size),
+                        algo: DeltaAlgo::Cdc,
+                        cached: transfer.chunk_cache,
+                    }
+                } else {
+                    ChecksumReq::block(
+                        *idx,
+                        block_sizes.block_size(&entry.abs_path, entry.size) as u32,
+                    )
+                };
 
                 // Send checksum request without waiting (no flush)
-                session
-                    .send_checksum_req_no_flush(file_idx, block_size)
-                    .await?;
-                pending.push((file_idx, *entry, block_size));
+                session.send_checksum_req_no_flush(req).await?;
+                pending.push((*idx, *entry, *action, req));
 
                 // Process batch when full
                 if pending.len() >= PIPELINE_DEPTH {
                     session.flush().await?;
-                    let (updated, transferred) =
-                        process_delta_batch(&mut session, &pending).await?;
+                    let (created, updated, transferred) = process_delta_batch(
+                        &mut session,
+                        &pending,
+                        verify,
+                        &block_sizes,
+                        &mut mismatched,
+                    )
+                    .await?;
+                    files_created += created;
                     files_updated += updated;
                     bytes_transferred += transferred;
                     pending.clear();
@@ -265,7 +388,15 @@ pub async fn sync_daemon_mode(
             // Process remaining files
             if !pending.is_empty() {
                 session.flush().await?;
-                let (updated, transferred) = process_delta_batch(&mut session, &pending).await?;
+                let (created, updated, transferred) = process_delta_batch(
+                    &mut session,
+                    &pending,
+                    verify,
+                    &block_sizes,
+                    &mut mismatched,
+                )
+                .await?;
+                files_created += created;
                 files_updated += updated;
                 bytes_transferred += transferred;
             }
@@ -278,54 +409,61 @@ pub async fn sync_daemon_mode(
                 full_updates.len()
             );
 
-            let paths: Vec<(u32, Arc<PathBuf>, String, u64)> = full_updates
-                .iter()
-                .map(|(idx, e)| (*idx, e.abs_path.clone(), e.rel_path.clone(), e.size))
-                .collect();
-
-            let files_data: Vec<(u32, Vec<u8>, u8)> = tokio::task::spawn_blocking(move || {
-                paths
-                    .into_iter()
-                    .filter_map(|(idx, path, rel_path, size)| {
-                        std::fs::read(&*path).ok().map(|data| {
-                            let (send_data, flags) = if size >= COMPRESS_MIN_SIZE
-                                && !is_compressed_extension(&rel_path)
-                            {
-                                match compress(&data, Compression::Zstd) {
-                                    Ok(compressed) if compressed.len() < data.len() => {
-                                        (compressed, DATA_FLAG_COMPRESSED)
-                                    }
-                                    _ => (data, 0),
-                                }
-                            } else {
-                                (data, 0)
-                            };
-                            (idx, send_data, flags)
-                        })
-                    })
-                    .collect()
-            })
-            .await?;
+            let files_data = read_chunks(&full_updates, plan, can_compress, &resumes).await?;
 
-            for (idx, data, flags) in &files_data {
-                bytes_transferred += data.len() as u64;
-                session
-                    .send_file_data_with_flags(*idx, 0, *flags, data.clone())
-                    .await?;
+            for (idx, chunks) in &files_data {
+                bytes_transferred += send_chunks(&mut session, *idx, chunks).await?;
             }
             session.flush().await?;
 
-            for _ in &files_data {
+            for (idx, chunks) in &files_data {
                 let done = session.read_file_done().await?;
-                if done.status != 0 {
-                    tracing::error!("Update failed: index {} status {}", done.index, done.status);
-                } else {
-                    files_updated += 1;
+                match digest::verdict(&done, layout::sent_digest(chunks)) {
+                    Verdict::Ok => files_updated += 1,
+                    Verdict::Mismatch => mismatched.push((*idx, Action::Update)),
+                    Verdict::Failed => {
+                        tracing::error!(
+                            "Update failed: index {} status {}",
+                            done.index,
+                            done.status
+                        );
+                    }
                 }
             }
         }
     }
 
+    // Step 3c: Send files that failed verification again, whole
+    if !mismatched.is_empty() {
+        let (created, updated, transferred) =
+            resend_mismatched(&mut session, &files, mismatched, plan, can_compress).await?;
+        files_created += created;
+        files_updated += updated;
+        bytes_transferred += transferred;
+    }
+
+    // Step 3d: Hard links, once every file they point at has been written
+    if !hardlinks.is_empty() {
+        tracing::debug!("Linking {} hard links...", hardlinks.len());
+        for (idx, _) in &hardlinks {
+            session
+                .send_file_data_with_flags(*idx, 0, DATA_FLAG_FINAL, Vec::new())
+                .await?;
+        }
+        session.flush().await?;
+
+        for (_, action) in &hardlinks {
+            let done = session.read_file_done().await?;
+            if done.status != 0 {
+                tracing::error!("Link failed: index {} status {}", done.index, done.status);
+            } else if *action == Action::Create {
+                files_created += 1;
+            } else {
+                files_updated += 1;
+            }
+        }
+    }
+
     // Step 4: Create symlinks (if any)
     let mut symlinks_created = 0u64;
     if !symlinks.is_empty() {
@@ -369,6 +507,7 @@ pub async fn sync_daemon_mode(
     );
 
     // Close session
+    let stream = session.stream_stats();
     session.close().await?;
 
     Ok(SyncStats {
@@ -376,12 +515,15 @@ pub async fn sync_daemon_mode(
         files_created,
         files_updated,
         files_deleted: 0,
+        files_moved: 0,
         files_skipped,
         bytes_transferred,
         files_delta_synced: 0,
         delta_bytes_saved: 0,
         files_compressed: 0,
         compression_bytes_saved: 0,
+        stream_bytes_raw: stream.raw_bytes,
+        stream_bytes_wire: stream.wire_bytes,
         files_verified: 0,
         verification_failures: 0,
         duration,
@@ -401,21 +543,65 @@ pub async fn sync_daemon_mode(
 /// * `socket_path` - Path to Unix socket
 /// * `remote_path` - Source path on daemon side
 /// * `dest` - Local destination directory
+/// * `meta` - Which of the daemon's metadata to apply
+/// * `transfer` - Verification and session options
 pub async fn sync_pull_daemon_mode(
     socket_path: &str,
     remote_path: &Path,
     dest: &Path,
+    meta: MetaOptions,
+    transfer: TransferOptions,
 ) -> Result<SyncStats> {
     let start = Instant::now();
 
     // Connect to daemon in PULL mode
-    let mut session = DaemonSession::connect_pull(socket_path, remote_path).await?;
+    let session = DaemonSession::connect_pull(socket_path, remote_path, transfer.session).await?;
     tracing::debug!("Connected to daemon (PULL mode)");
 
+    pull_from_daemon(session, dest, meta, transfer, start).await
+}
+
+/// Sync from a daemon's TCP listener to local destination (PULL mode)
+///
+/// # Arguments
+/// * `endpoint` - Daemon host, port, token and optional TLS CA
+/// * `remote_path` - Source path on daemon side
+/// * `dest` - Local destination directory
+/// * `meta` - Which of the daemon's metadata to apply
+/// * `transfer` - Verification and session options
+pub async fn sync_pull_tcp_daemon_mode(
+    endpoint: &TcpEndpoint,
+    remote_path: &Path,
+    dest: &Path,
+    meta: MetaOptions,
+    transfer: TransferOptions,
+) -> Result<SyncStats> {
+    let start = Instant::now();
+
+    let session = DaemonSession::connect_tcp_pull(endpoint, remote_path, transfer.session).await?;
+    tracing::debug!(
+        "Connected to daemon at {}:{} (PULL mode)",
+        endpoint.host,
+        endpoint.port
+    );
+
+    pull_from_daemon(session, dest, meta, transfer, start).await
+}
+
+/// Receive the daemon's tree over an established PULL session
+async fn pull_from_daemon(
+    mut session: DaemonSession,
+    dest: &Path,
+    meta: MetaOptions,
+    transfer: TransferOptions,
+    start: Instant,
+) -> Result<SyncStats> {
     // Ensure local destination exists
     if !dest.exists() {
         std::fs::create_dir_all(dest)?;
     }
+    // Paths come from the daemon; keep every write beneath dest
+    let confine = Confinement::new(dest);
 
     // Scan local destination for comparison
     let local_entries = scan_local_dest(dest).await?;
@@ -437,7 +623,14 @@ pub async fn sync_pull_daemon_mode(
     let mut failed: Vec<(String, String)> = Vec::new();
 
     for dir_path in &mkdir_batch.paths {
-        let full_path = dest.join(dir_path);
+        let full_path = match confine.resolve(dir_path) {
+            Ok(p) => p,
+            Err(e) => {
+                tracing::warn!("Refusing directory {}: {}", dir_path, e);
+                failed.push((dir_path.clone(), e.to_string()));
+                continue;
+            }
+        };
         match std::fs::create_dir_all(&full_path) {
             Ok(_) => dirs_created += 1,
             Err(e) => failed.push((dir_path.clone(), e.to_string())),
@@ -453,9 +646,18 @@ pub async fn sync_pull_daemon_mode(
 
     let mut decisions: Vec<Decision> = Vec::with_capacity(file_list.entries.len());
     let mut files_to_receive: Vec<(u32, String)> = Vec::new();
+    // Hard links are never sent as data; they are made locally afterwards
+    let mut hardlinks: Vec<(u32, u32)> = Vec::new();
+    // Set once read_file_data has consumed the SYMLINK_BATCH header
+    let mut symlinks_next = false;
 
+    let can_resume = session.negotiated().has(CAP_RESUME);
+    let can_verify = session.negotiated().has(CAP_FILE_CHECKSUM);
     for (idx, entry) in file_list.entries.iter().enumerate() {
-        let action = if let Some((local_size, local_mtime)) = local_map.get(&entry.path) {
+        let action = if let Err(e) = confine.resolve(&entry.path) {
+            tracing::warn!("Refusing file {}: {}", entry.path, e);
+            Action::Skip
+        } else if let Some((local_size, local_mtime)) = local_map.get(&entry.path) {
             if *local_size == entry.size && *local_mtime >= entry.mtime {
                 Action::Skip
             } else {
@@ -465,57 +667,142 @@ pub async fn sync_pull_daemon_mode(
             Action::Create
         };
 
+        if let Some(leader) = entry.hardlink_to {
+            hardlinks.push((idx as u32, leader));
+            decisions.push(Decision {
+                index: idx as u32,
+                action: Action::Skip,
+                resume_from: 0,
+                digest: ChecksumType::None,
+                basis: None,
+            });
+            continue;
+        }
+
         if action != Action::Skip {
             files_to_receive.push((idx as u32, entry.path.clone()));
         } else {
             files_skipped += 1;
         }
 
+        // Offer what an interrupted run left behind
+        let resume_from = if can_resume && action != Action::Skip {
+            confine
+                .resolve(&partial::partial_rel(&entry.path, entry.size, entry.mtime))
+                .map_or(0, |partial| partial::resume_offset(&partial, entry.size))
+        } else {
+            0
+        };
+        // Ask for the whole-file digest to check before each rename
+        let digest = if can_verify && action != Action::Skip {
+            transfer.verify
+        } else {
+            ChecksumType::None
+        };
         decisions.push(Decision {
             index: idx as u32,
             action,
+            resume_from,
+            digest,
+            basis: None,
         });
     }
 
     session.send_file_list_ack(decisions).await?;
     tracing::info!("{} files to receive", files_to_receive.len());
 
-    // Step 3: Receive files
-    for (idx, rel_path) in &files_to_receive {
-        let file_data = match session.read_file_data().await? {
-            Some(data) => data,
-            None => break, // Got SYMLINK_BATCH instead
-        };
-
-        let full_path = dest.join(rel_path);
-
-        // Ensure parent directory exists
-        if let Some(parent) = full_path.parent() {
-            std::fs::create_dir_all(parent)?;
+    // Step 3: Receive files; with CAP_FILE_CHECKSUM the daemon sends
+    // mismatched files again
+    let mut pending = files_to_receive;
+    for round in 0..=VERIFY_RETRIES {
+        if round > 0 {
+            if !can_verify || pending.is_empty() {
+                break;
+            }
+            tracing::warn!(
+                "Receiving {} files again after failed verification",
+                pending.len()
+            );
         }
 
-        // Write file
-        std::fs::write(&full_path, &file_data.data)?;
-        bytes_transferred += file_data.data.len() as u64;
+        let mut mismatched = Vec::new();
+        for (idx, rel_path) in pending {
+            let file_data = match session.read_file_data().await? {
+                Some(data) => data,
+                None => {
+                    // Got SYMLINK_BATCH instead
+                    symlinks_next = true;
+                    break;
+                }
+            };
+
+            // Write file (sparse and split files arrive as several chunks)
+            let entry = &file_list.entries[idx as usize];
+            let received = match session
+                .receive_file(&confine, &rel_path, entry, file_data)
+                .await?
+            {
+                Ok(received) => received,
+                Err(e) if digest::is_mismatch(&e) => {
+                    tracing::warn!("{}", e);
+                    session
+                        .send_file_done(idx, STATUS_CHECKSUM_MISMATCH, Vec::new())
+                        .await?;
+                    mismatched.push((idx, rel_path));
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
+            meta::apply_file(&received.path, entry, &meta);
+            bytes_transferred += received.bytes;
+
+            // Update stats
+            if local_map.contains_key(&rel_path) {
+                files_updated += 1;
+            } else {
+                files_created += 1;
+            }
 
-        // Update stats
-        if local_map.contains_key(rel_path) {
-            files_updated += 1;
-        } else {
-            files_created += 1;
+            // Send FILE_DONE
+            session
+                .send_file_done(idx, STATUS_OK, received.checksum)
+                .await?;
         }
-
-        // Send FILE_DONE
-        session.send_file_done(*idx, 0).await?;
+        pending = mismatched;
+    }
+    for (_, rel_path) in &pending {
+        tracing::error!("{} failed verification", rel_path);
     }
 
-    // Step 4: Handle symlinks
-    if let Ok(symlink_batch) = session.read_symlink_batch_body().await {
+    // Step 4: Handle symlinks (a daemon without any ends the session)
+    let symlink_batch = if symlinks_next {
+        session.read_symlink_batch_body().await
+    } else {
+        session.read_symlink_batch().await
+    };
+    if let Ok(symlink_batch) = symlink_batch {
         let mut created = 0u32;
         let mut failed: Vec<(String, String)> = Vec::new();
 
         for entry in &symlink_batch.entries {
-            let link_path = dest.join(&entry.path);
+            let link_path = match confine.resolve(&entry.path) {
+                Ok(p) => p,
+                Err(e) => {
+                    tracing::warn!("Refusing symlink {}: {}", entry.path, e);
+                    failed.push((entry.path.clone(), e.to_string()));
+                    continue;
+                }
+            };
+            if transfer.session.safe_links
+                && !confine::symlink_is_safe(Path::new(&entry.path), &entry.target)
+            {
+                tracing::warn!("Refusing unsafe symlink {} -> {}", entry.path, entry.target);
+                failed.push((
+                    entry.path.clone(),
+                    format!("unsafe symlink target: {}", entry.target),
+                ));
+                continue;
+            }
             let target = PathBuf::from(&entry.target);
 
             // Remove existing symlink if present
@@ -535,6 +822,33 @@ pub async fn sync_pull_daemon_mode(
         session.send_symlink_batch_ack(created, failed).await?;
     }
 
+    // Step 5: Hard links to files received (or already present) above
+    for (idx, leader) in &hardlinks {
+        let (Some(entry), Some(leader)) = (
+            file_list.entries.get(*idx as usize),
+            file_list.entries.get(*leader as usize),
+        ) else {
+            continue;
+        };
+        let (leader, path) = match (confine.resolve(&leader.path), confine.resolve(&entry.path)) {
+            (Ok(leader), Ok(path)) => (leader, path),
+            (Err(e), _) | (_, Err(e)) => {
+                tracing::warn!("Refusing link {}: {}", entry.path, e);
+                continue;
+            }
+        };
+        if transfer.hardlinks && layout::same_file(&leader, &path) {
+            files_skipped += 1;
+            continue;
+        }
+        let existed = path.symlink_metadata().is_ok();
+        match layout::link_file(&leader, &path, transfer.hardlinks) {
+            Ok(()) if existed => files_updated += 1,
+            Ok(()) => files_created += 1,
+            Err(e) => tracing::warn!("Failed to link {}: {}", path.display(), e),
+        }
+    }
+
     let duration = start.elapsed();
 
     tracing::info!(
@@ -545,6 +859,7 @@ pub async fn sync_pull_daemon_mode(
         duration
     );
 
+    let stream = session.stream_stats();
     session.close().await?;
 
     Ok(SyncStats {
@@ -552,12 +867,15 @@ pub async fn sync_pull_daemon_mode(
         files_created,
         files_updated,
         files_deleted: 0,
+        files_moved: 0,
         files_skipped,
         bytes_transferred,
         files_delta_synced: 0,
         delta_bytes_saved: 0,
         files_compressed: 0,
         compression_bytes_saved: 0,
+        stream_bytes_raw: stream.raw_bytes,
+        stream_bytes_wire: stream.wire_bytes,
         files_verified: 0,
         verification_failures: 0,
         duration,
@@ -571,17 +889,122 @@ pub async fn sync_pull_daemon_mode(
     })
 }
 
+/// Send files the daemon failed to verify again whole, for up to
+/// `VERIFY_RETRIES` rounds; returns (created, updated, bytes sent)
+async fn resend_mismatched(
+    session: &mut DaemonSession,
+    files: &[SourceEntry],
+    mut mismatched: Vec<(u32, Action)>,
+    plan: ChunkPlan,
+    can_compress: bool,
+) -> Result<(u64, u64, u64)> {
+    let (mut created, mut updated, mut transferred) = (0u64, 0u64, 0u64);
+    for _ in 0..VERIFY_RETRIES {
+        if mismatched.is_empty() {
+            break;
+        }
+        let actions: HashMap<u32, Action> = mismatched.iter().copied().collect();
+        let entries: Vec<(u32, &SourceEntry)> = mismatched
+            .iter()
+            .map(|(idx, _)| (*idx, &files[*idx as usize]))
+            .collect();
+        let files_data = read_chunks(&entries, plan, can_compress, &HashMap::new()).await?;
+        for (idx, chunks) in &files_data {
+            transferred += send_chunks(session, *idx, chunks).await?;
+        }
+        session.flush().await?;
+
+        mismatched.clear();
+        for (idx, chunks) in &files_data {
+            let done = session.read_file_done().await?;
+            match digest::verdict(&done, layout::sent_digest(chunks)) {
+                Verdict::Ok if actions[idx] == Action::Create => created += 1,
+                Verdict::Ok => updated += 1,
+                Verdict::Mismatch => mismatched.push((*idx, actions[idx])),
+                Verdict::Failed => {
+                    tracing::error!("Resend failed: index {} status {}", done.index, done.status);
+                }
+            }
+        }
+    }
+    for (idx, _) in &mismatched {
+        tracing::error!("{} failed verification", files[*idx as usize].rel_path);
+    }
+    Ok((created, updated, transferred))
+}
+
+/// Read files as FILE_DATA chunks off the runtime, skipping unreadable ones
+async fn read_chunks(
+    entries: &[(u32, &SourceEntry)],
+    base: ChunkPlan,
+    can_compress: bool,
+    resumes: &HashMap<u32, u64>,
+) -> Result<Vec<(u32, Vec<Chunk>)>> {
+    let paths: Vec<(u32, Arc<PathBuf>, ChunkPlan)> = entries
+        .iter()
+        .map(|(idx, e)| {
+            let sparse = base.sparse && e.sparse;
+            let plan = ChunkPlan {
+                sparse,
+                zstd: can_compress
+                    && e.size >= COMPRESS_MIN_SIZE
+                    && !is_compressed_extension(&e.rel_path),
+                // A sparse partial is already full length; send it again
+                resume_from: if sparse {
+                    0
+                } else {
+                    resumes.get(idx).copied().unwrap_or(0)
+                },
+                ..base
+            };
+            (*idx, e.abs_path.clone(), plan)
+        })
+        .collect();
+
+    Ok(tokio::task::spawn_blocking(move || {
+        paths
+            .into_iter()
+            .filter_map(|(idx, path, plan)| {
+                layout::read_chunks(&path, plan)
+                    .ok()
+                    .map(|chunks| (idx, chunks))
+            })
+            .collect()
+    })
+    .await?)
+}
+
+/// Send a file's chunks (no flush); returns the bytes sent
+async fn send_chunks(session: &mut DaemonSession, idx: u32, chunks: &[Chunk]) -> Result<u64> {
+    let mut sent = 0u64;
+    for chunk in chunks {
+        sent += chunk.data.len() as u64;
+        session
+            .send_file_data_with_flags(idx, chunk.offset, chunk.flags, chunk.data.clone())
+            .await?;
+    }
+    Ok(sent)
+}
+
 /// Process a batch of delta sync requests
+///
+/// With `verify` each DELTA_DATA carries the source's digest; files the
+/// daemon fails to verify are added to `mismatched`.
+///
+/// Returns (files_created, files_updated, bytes_transferred).
 async fn process_delta_batch(
     session: &mut DaemonSession,
-    pending: &[(u32, &SourceEntry, u32)],
-) -> Result<(u64, u64)> {
+    pending: &[(u32, &SourceEntry, Action, ChecksumReq)],
+    verify: ChecksumType,
+    block_sizes: &BlockSizePolicy,
+    mismatched: &mut Vec<(u32, Action)>,
+) -> Result<(u64, u64, u64)> {
+    let mut files_created = 0u64;
     let mut files_updated = 0u64;
     let mut bytes_transferred = 0u64;
 
     // Step 1: Read all checksum responses (may arrive out of order)
-    let mut responses: HashMap<u32, crate::server::protocol::ChecksumResp> =
-        HashMap::with_capacity(pending.len());
+    let mut responses: HashMap<u32, ChecksumResp> = HashMap::with_capacity(pending.len());
     for _ in 0..pending.len() {
         let resp = session.read_checksum_resp().await?;
         responses.insert(resp.index, resp);
@@ -590,10 +1013,10 @@ async fn process_delta_batch(
     // Step 2: Compute all deltas in parallel
     let delta_futures: Vec<_> = pending
         .iter()
-        .map(|(file_idx, entry, block_size)| {
+        .map(|(file_idx, entry, _, req)| {
             let resp = responses.get(file_idx).cloned();
             let path = entry.abs_path.clone();
-            let bs = *block_size as usize;
+            let req = *req;
             let idx = *file_idx;
 
             async move {
@@ -601,23 +1024,10 @@ async fn process_delta_batch(
                     anyhow::anyhow!("Missing checksum response for index {}", idx)
                 })?;
 
-                // Convert protocol checksums to delta checksums
-                let dest_checksums: Vec<DeltaBlockChecksum> = resp
-                    .checksums
-                    .iter()
-                    .enumerate()
-                    .map(|(i, c)| DeltaBlockChecksum {
-                        index: i as u64,
-                        offset: c.offset,
-                        size: c.size as usize,
-                        weak: c.weak,
-                        strong: c.strong,
-                    })
-                    .collect();
-
-                // Generate delta in blocking task
-                let delta = tokio::task::spawn_blocking(move || {
-                    generate_delta_streaming(&path, &dest_checksums, bs)
+                // Generate delta (and the digest it should produce) in blocking task
+                let (delta, digest) = tokio::task::spawn_blocking(move || {
+                    let delta = delta_from_checksums(&path, req, &resp)?;
+                    Ok::<_, std::io::Error>((delta, digest::file_digest(&path, verify)?))
                 })
                 .await??;
 
@@ -640,37 +1050,48 @@ async fn process_delta_batch(
                     }
                 }
 
-                Ok::<_, anyhow::Error>((idx, ops, delta_bytes))
+                Ok::<_, anyhow::Error>((idx, ops, delta_bytes, digest))
             }
         })
         .collect();
 
-    let deltas: Vec<Result<(u32, Vec<DeltaOp>, u64)>> =
-        futures::future::join_all(delta_futures).await;
+    let deltas: Vec<Result<FileDelta>> = futures::future::join_all(delta_futures).await;
 
     // Step 3: Send all DELTA_DATA without waiting for confirmations
-    for result in deltas {
-        let (idx, ops, delta_bytes) = result?;
+    let mut sent: Vec<(u32, Action, Vec<u8>)> = Vec::with_capacity(pending.len());
+    for (result, (_, entry, action, _)) in deltas.into_iter().zip(pending) {
+        let (idx, ops, delta_bytes, digest) = result?;
         bytes_transferred += delta_bytes;
+        if *action == Action::Update {
+            block_sizes.record_delta(&entry.abs_path, delta_bytes, entry.size);
+        }
 
-        session.send_delta_data(idx, 0, ops).await?;
+        session.send_delta_data(idx, 0, ops, digest.clone()).await?;
+        sent.push((idx, *action, digest));
     }
 
     session.flush().await?;
 
     // Step 4: Read confirmations
-    for _ in pending {
+    for (idx, action, digest) in sent {
         let done = session.read_file_done().await?;
-        if done.status == 0 {
-            files_updated += 1;
+        match digest::verdict(&done, &digest) {
+            Verdict::Ok if action == Action::Create => files_created += 1,
+            Verdict::Ok => files_updated += 1,
+            Verdict::Mismatch => mismatched.push((idx, action)),
+            Verdict::Failed => {}
         }
     }
 
-    Ok((files_updated, bytes_transferred))
+    Ok((files_created, files_updated, bytes_transferred))
 }
 
-/// Scan source directory
-async fn scan_source(source: &Path) -> Result<Vec<SourceEntry>> {
+/// Scan source directory, with the metadata `meta` asks for on regular files
+async fn scan_source(
+    source: &Path,
+    meta: MetaOptions,
+    transfer: TransferOptions,
+) -> Result<Vec<SourceEntry>> {
     let scan_opts = ScanOptions::default();
     let src = source.to_path_buf();
 
@@ -693,15 +1114,23 @@ async fn scan_source(source: &Path) -> Result<Vec<SourceEntry>> {
                         .unwrap_or_default()
                         .as_secs() as i64;
 
+                    let is_file = !e.is_dir && !e.is_symlink;
                     SourceEntry {
                         rel_path: s.to_string(),
                         abs_path: Arc::new(e.path.to_path_buf()),
                         size: e.size,
                         mtime,
-                        mode: 0o644,
+                        mode: meta::file_mode(&e.path),
                         is_dir: e.is_dir,
                         is_symlink: e.is_symlink,
-                        symlink_target: e.symlink_target.and_then(|t| t.to_str().map(String::from)),
+                        meta: is_file.then(|| meta::collect(&e, &meta)).flatten(),
+                        inode: e.inode.filter(|_| transfer.hardlinks && is_file),
+                        nlink: e.nlink,
+                        sparse: e.is_sparse,
+                        symlink_target: e
+                            .symlink_target
+                            .as_ref()
+                            .and_then(|t| t.to_str().map(String::from)),
                     }
                 })
             })
@@ -744,6 +1173,10 @@ async fn scan_local_dest(dest: &Path) -> Result<Vec<SourceEntry>> {
                         is_dir: e.is_dir,
                         is_symlink: e.is_symlink,
                         symlink_target: None,
+                        meta: None,
+                        inode: None,
+                        nlink: e.nlink,
+                        sparse: false,
                     }
                 })
             })
@@ -768,6 +1201,10 @@ mod tests {
             is_dir: false,
             is_symlink: false,
             symlink_target: None,
+            meta: None,
+            inode: None,
+            nlink: 1,
+            sparse: false,
         };
 
         assert_eq!(entry.rel_path, "test.txt");
@@ -1,4 +1,5 @@
 use crate::error::{Result, SyncError};
+use crate::sync::incremental::ChangeSet;
 use crate::sync::scanner::FileEntry;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -266,6 +267,79 @@ impl DirectoryCache {
         self.dir_entries.is_empty() && self.file_entries.is_empty()
     }
 
+    /// Snapshot a local tree for the polling watcher
+    ///
+    /// Records every directory's mtime and the metadata of its entries,
+    /// keyed the same way as the incremental scan cache.
+    #[allow(dead_code)] // Used by watch mode
+    pub fn snapshot(root: &Path) -> Result<Self> {
+        Ok(Self::new().refresh(root)?.0)
+    }
+
+    /// Take a new snapshot of `root` and report what changed since this one
+    ///
+    /// Only directories whose mtime changed are listed again. In the others
+    /// the known entries are just stat'ed, which still catches files edited
+    /// in place (that doesn't touch the directory mtime).
+    #[allow(dead_code)] // Used by watch mode
+    pub fn refresh(&self, root: &Path) -> Result<(Self, ChangeSet)> {
+        let mut next = Self::new();
+        let mut changes = ChangeSet::new();
+        let mut pending = vec![PathBuf::new()];
+
+        while let Some(dir) = pending.pop() {
+            let key = if dir.as_os_str().is_empty() {
+                PathBuf::from(".")
+            } else {
+                dir.clone()
+            };
+            let mtime = match std::fs::symlink_metadata(root.join(&dir)).and_then(|m| m.modified())
+            {
+                Ok(mtime) => mtime,
+                Err(e) if dir.as_os_str().is_empty() => {
+                    return Err(SyncError::ReadDirError {
+                        path: root.to_path_buf(),
+                        source: e,
+                    })
+                }
+                Err(_) => continue, // Removed since its parent was read
+            };
+
+            let known = self.file_entries.get(&key);
+            let unchanged = known.is_some() && self.dir_entries.get(&key) == Some(&mtime);
+            let children = match known {
+                Some(files) if unchanged => restat_entries(root, files, &mut changes),
+                _ => match list_entries(root, &dir) {
+                    Ok(listed) => {
+                        if let Some(files) = known {
+                            diff_entries(files, &listed, &mut changes);
+                        }
+                        listed
+                    }
+                    Err(e) => {
+                        // Keep what we knew rather than report everything as deleted
+                        tracing::warn!("Failed to list {}: {}", root.join(&dir).display(), e);
+                        match known {
+                            Some(files) => restat_entries(root, files, &mut changes),
+                            None => Vec::new(),
+                        }
+                    }
+                },
+            };
+
+            pending.extend(
+                children
+                    .iter()
+                    .filter(|child| child.is_dir)
+                    .map(|child| child.path.clone()),
+            );
+            next.dir_entries.insert(key.clone(), mtime);
+            next.file_entries.insert(key, children);
+        }
+
+        Ok((next, changes))
+    }
+
     /// Get cache file path for a destination
     #[allow(dead_code)] // Will be used for incremental scanning
     pub fn cache_path(dest_root: &Path) -> PathBuf {
@@ -279,6 +353,76 @@ impl Default for DirectoryCache {
     }
 }
 
+/// Whether two snapshots of an entry differ in a way worth syncing
+///
+/// Directory mtimes only reflect their listing, which is compared separately.
+fn entry_changed(old: &CachedFile, new: &CachedFile) -> bool {
+    old.is_dir != new.is_dir
+        || (!new.is_dir && (old.size != new.size || old.modified != new.modified))
+}
+
+fn cached_entry(path: PathBuf, metadata: &std::fs::Metadata) -> Option<CachedFile> {
+    Some(CachedFile {
+        path,
+        size: metadata.len(),
+        modified: metadata.modified().ok()?,
+        is_dir: metadata.is_dir(),
+    })
+}
+
+/// Read the entries of a directory (relative to `root`)
+fn list_entries(root: &Path, dir: &Path) -> std::io::Result<Vec<CachedFile>> {
+    let mut entries = Vec::new();
+    for entry in std::fs::read_dir(root.join(dir))? {
+        let Ok(entry) = entry else { continue };
+        // Entries removed mid-listing show up in the next snapshot
+        if let Ok(metadata) = std::fs::symlink_metadata(entry.path()) {
+            entries.extend(cached_entry(dir.join(entry.file_name()), &metadata));
+        }
+    }
+    Ok(entries)
+}
+
+/// Stat the known entries of a directory whose listing hasn't changed
+fn restat_entries(root: &Path, known: &[CachedFile], changes: &mut ChangeSet) -> Vec<CachedFile> {
+    let mut entries = Vec::with_capacity(known.len());
+    for old in known {
+        let current = std::fs::symlink_metadata(root.join(&old.path))
+            .ok()
+            .and_then(|metadata| cached_entry(old.path.clone(), &metadata));
+        match current {
+            Some(new) => {
+                if entry_changed(old, &new) {
+                    changes.insert(new.path.clone());
+                }
+                entries.push(new);
+            }
+            None => changes.insert(old.path.clone()),
+        }
+    }
+    entries
+}
+
+/// Record added, removed and modified entries between two listings
+fn diff_entries(old: &[CachedFile], new: &[CachedFile], changes: &mut ChangeSet) {
+    let old_by_path: HashMap<&Path, &CachedFile> =
+        old.iter().map(|f| (f.path.as_path(), f)).collect();
+    let new_paths: std::collections::HashSet<&Path> =
+        new.iter().map(|f| f.path.as_path()).collect();
+
+    for entry in new {
+        match old_by_path.get(entry.path.as_path()) {
+            Some(previous) if !entry_changed(previous, entry) => {}
+            _ => changes.insert(entry.path.clone()),
+        }
+    }
+    for entry in old {
+        if !new_paths.contains(entry.path.as_path()) {
+            changes.insert(entry.path.clone());
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -441,4 +585,40 @@ mod tests {
 
         assert_eq!(cache_path, temp.path().join(DirectoryCache::CACHE_FILENAME));
     }
+
+    #[test]
+    fn test_refresh_reports_changes() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        std::fs::create_dir_all(root.join("sub")).unwrap();
+        std::fs::write(root.join("sub/edited.txt"), "one").unwrap();
+        std::fs::write(root.join("removed.txt"), "bye").unwrap();
+
+        let snapshot = DirectoryCache::snapshot(root).unwrap();
+        let (snapshot, changes) = snapshot.refresh(root).unwrap();
+        assert!(changes.is_empty());
+
+        // An in-place edit doesn't touch the directory mtime
+        let dir_mtime = std::fs::metadata(root.join("sub"))
+            .unwrap()
+            .modified()
+            .unwrap();
+        std::fs::write(root.join("sub/edited.txt"), "two!").unwrap();
+        assert_eq!(
+            std::fs::metadata(root.join("sub"))
+                .unwrap()
+                .modified()
+                .unwrap(),
+            dir_mtime
+        );
+        std::fs::remove_file(root.join("removed.txt")).unwrap();
+        std::fs::create_dir_all(root.join("new/deep")).unwrap();
+        std::fs::write(root.join("new/deep/file.txt"), "x").unwrap();
+
+        let (_, changes) = snapshot.refresh(root).unwrap();
+        assert!(changes.contains(Path::new("sub/edited.txt")));
+        assert!(changes.contains(Path::new("removed.txt")));
+        assert!(changes.contains(Path::new("new")));
+        assert_eq!(changes.len(), 3);
+    }
 }
@@ -0,0 +1,150 @@
+//! Fuzzy basis selection for new files (--fuzzy)
+//!
+//! A new file often has an older relative next to it in the destination:
+//! `app-1.3.tar` arrives where `app-1.2.tar` already is. Delta-encoding the
+//! new file against that relative sends only what changed instead of the
+//! whole file.
+//!
+//! Only files in the new file's own directory are considered. A candidate
+//! must share the extension and most of the name (edit distance at most
+//! half the longer name); the closest name wins, then the closest size.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Smallest file worth delta-encoding against a basis
+pub const MIN_BASIS_SIZE: u64 = 4096;
+
+/// Destination files that may serve as a basis, grouped by directory
+#[derive(Debug, Default)]
+pub struct BasisIndex {
+    by_dir: HashMap<PathBuf, Vec<(String, u64)>>,
+}
+
+impl BasisIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a regular destination file (path relative to the root)
+    pub fn insert(&mut self, path: &Path, size: u64) {
+        if size < MIN_BASIS_SIZE {
+            return;
+        }
+        let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+            return;
+        };
+        self.by_dir
+            .entry(dir.to_path_buf())
+            .or_default()
+            .push((name.to_string_lossy().into_owned(), size));
+    }
+
+    /// Best basis for a new file at `path` of `size` bytes, if any is close enough
+    pub fn find(&self, path: &Path, size: u64) -> Option<PathBuf> {
+        if size < MIN_BASIS_SIZE {
+            return None;
+        }
+        let dir = path.parent()?;
+        let name = path.file_name()?.to_string_lossy();
+        let candidates = self.by_dir.get(dir)?;
+        candidates
+            .iter()
+            .filter_map(|(cand, cand_size)| {
+                let distance = name_distance(&name, cand)?;
+                Some(((distance, size.abs_diff(*cand_size), cand), cand))
+            })
+            .min_by(|a, b| a.0.cmp(&b.0))
+            .map(|(_, cand)| dir.join(cand))
+    }
+}
+
+/// Edit distance between two file names, or None if they are too different
+/// to be versions of the same file
+fn name_distance(name: &str, candidate: &str) -> Option<usize> {
+    if name == candidate || Path::new(name).extension() != Path::new(candidate).extension() {
+        return None;
+    }
+    let a: Vec<char> = name.chars().collect();
+    let b: Vec<char> = candidate.chars().collect();
+    let distance = levenshtein(&a, &b);
+    (distance * 2 <= a.len().max(b.len())).then_some(distance)
+}
+
+fn levenshtein(a: &[char], b: &[char]) -> usize {
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut cur = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        cur[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitute = prev[j] + usize::from(ca != cb);
+            cur[j + 1] = substitute.min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut cur);
+    }
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MB: u64 = 1024 * 1024;
+
+    fn index(files: &[(&str, u64)]) -> BasisIndex {
+        let mut index = BasisIndex::new();
+        for (path, size) in files {
+            index.insert(Path::new(path), *size);
+        }
+        index
+    }
+
+    #[test]
+    fn test_finds_previous_version() {
+        let index = index(&[
+            ("dist/app-1.2.tar", 10 * MB),
+            ("dist/notes.txt", 10 * MB),
+            ("other/app-1.2.tar", 10 * MB),
+        ]);
+        assert_eq!(
+            index.find(Path::new("dist/app-1.3.tar"), 11 * MB),
+            Some(PathBuf::from("dist/app-1.2.tar"))
+        );
+        // Only the new file's own directory is searched
+        assert_eq!(index.find(Path::new("app-1.3.tar"), 11 * MB), None);
+    }
+
+    #[test]
+    fn test_rejects_unrelated_names() {
+        let index = index(&[("a/app-1.2.tar", MB), ("a/app-1.3.zip", MB)]);
+        // Different extension
+        assert_eq!(index.find(Path::new("a/app-1.3.zip.tmp"), MB), None);
+        assert_eq!(
+            index.find(Path::new("a/app-1.4.zip"), MB),
+            Some(PathBuf::from("a/app-1.3.zip"))
+        );
+        // Mostly different name
+        assert_eq!(index.find(Path::new("a/database.tar"), MB), None);
+    }
+
+    #[test]
+    fn test_prefers_closer_name_then_size() {
+        let index = index(&[
+            ("v/log-2024-01.csv", 5 * MB),
+            ("v/log-2024-02.csv", 9 * MB),
+            ("v/log-2023-12.csv", 9 * MB),
+        ]);
+        assert_eq!(
+            index.find(Path::new("v/log-2024-03.csv"), 8 * MB),
+            Some(PathBuf::from("v/log-2024-02.csv"))
+        );
+    }
+
+    #[test]
+    fn test_small_files_are_never_used() {
+        let small = index(&[("x/small-1.bin", 100)]);
+        assert_eq!(small.find(Path::new("x/small-2.bin"), MB), None);
+        let big = index(&[("x/big-1.bin", MB)]);
+        assert_eq!(big.find(Path::new("x/big-2.bin"), 100), None);
+    }
+}
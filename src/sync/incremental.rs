@@ -0,0 +1,510 @@
+//! Incremental syncs of individually changed paths
+//!
+//! Watch mode already knows which paths changed since the last pass, so
+//! instead of rescanning the whole tree it re-examines just those paths
+//! (and their parent directories) and plans them with the same
+//! `StrategyPlanner` rules as a full sync.
+
+use super::scanner::{self, FileEntry};
+use super::strategy::{StrategyPlanner, SyncAction, SyncTask};
+use super::transfer::Transferrer;
+use super::{output::SyncEvent, SyncEngine, SyncError, SyncStats};
+use crate::error::Result;
+use crate::transport::{TransferResult, Transport};
+use futures::stream::StreamExt;
+use std::collections::{BTreeSet, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Paths that changed under the source root since the last pass
+///
+/// All paths are relative to the source root.
+#[derive(Debug, Default, Clone)]
+pub struct ChangeSet {
+    paths: BTreeSet<PathBuf>,
+    renames: Vec<(PathBuf, PathBuf)>,
+}
+
+#[allow(dead_code)] // Only used by watch mode
+impl ChangeSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a created, modified or removed path
+    pub fn insert(&mut self, path: PathBuf) {
+        // The root itself changing (e.g. its mtime) carries no work
+        if !path.as_os_str().is_empty() {
+            self.paths.insert(path);
+        }
+    }
+
+    /// Record a rename observed as a single event
+    pub fn insert_rename(&mut self, from: PathBuf, to: PathBuf) {
+        if from.as_os_str().is_empty() || to.as_os_str().is_empty() {
+            return;
+        }
+        self.insert(from.clone());
+        self.insert(to.clone());
+        self.renames.push((from, to));
+    }
+
+    /// Add all changes of another set
+    pub fn merge(&mut self, other: ChangeSet) {
+        self.paths.extend(other.paths);
+        self.renames.extend(other.renames);
+    }
+
+    pub fn contains(&self, path: &Path) -> bool {
+        self.paths.contains(path)
+    }
+
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.paths.clear();
+        self.renames.clear();
+    }
+
+    /// Changed paths, without those already covered by a changed ancestor
+    ///
+    /// A changed directory is synced with its whole subtree, so its
+    /// descendants don't need to be looked at separately.
+    pub(crate) fn roots(&self) -> Vec<&Path> {
+        let mut roots: Vec<&Path> = Vec::new();
+        // BTreeSet orders a directory directly before its descendants
+        for path in &self.paths {
+            if roots.last().is_some_and(|root| path.starts_with(root)) {
+                continue;
+            }
+            roots.push(path);
+        }
+        roots
+    }
+}
+
+impl<T: Transport + 'static> SyncEngine<T> {
+    /// Sync only the paths in `changes` from `source` to `destination`
+    ///
+    /// Paths that still exist in the source are planned like in a full sync,
+    /// together with their parent directories; directories bring their whole
+    /// subtree. Paths gone from the source are removed from the destination
+    /// when deletion is enabled, and renames are replayed on the destination
+    /// so the data isn't transferred again.
+    #[allow(dead_code)] // Only used by watch mode
+    pub async fn sync_paths(
+        &self,
+        source: &Path,
+        destination: &Path,
+        changes: &ChangeSet,
+    ) -> Result<SyncStats> {
+        let start_time = std::time::Instant::now();
+        let mut stats = SyncStats::default();
+
+        tracing::info!(
+            "Starting incremental sync of {} paths: {} → {}",
+            changes.len(),
+            source.display(),
+            destination.display()
+        );
+
+        let hardlink_map = Arc::new(Mutex::new(std::collections::HashMap::new()));
+        let transferrer = Transferrer::new(
+            self.transport.as_ref(),
+            self.dry_run,
+            self.diff_mode,
+            self.symlink_mode,
+            self.preserve_xattrs,
+            self.preserve_hardlinks,
+            self.preserve_acls,
+            self.preserve_flags,
+            self.per_file_progress && !self.quiet,
+            hardlink_map,
+        );
+
+        // Renames first, so the paths below plan as up to date
+        if self.delete {
+            for (from, to) in &changes.renames {
+                if self.is_excluded_path(from, false) || self.is_excluded_path(to, false) {
+                    continue;
+                }
+                if source.join(from).symlink_metadata().is_ok() {
+                    continue; // Old name is back, nothing to replay
+                }
+                let dest_from = destination.join(from);
+                if !self.transport.exists(&dest_from).await.unwrap_or(false) {
+                    continue;
+                }
+                let dest_to = destination.join(to);
+                match transferrer.rename(&dest_from, &dest_to).await {
+                    Ok(()) => {
+                        stats.files_moved += 1;
+                        if self.json {
+                            SyncEvent::Move {
+                                from: dest_from,
+                                to: dest_to,
+                                size: 0,
+                            }
+                            .emit();
+                        }
+                    }
+                    Err(e) => record_error(&mut stats, dest_to, "move", &e),
+                }
+            }
+        }
+
+        let mut entries: Vec<FileEntry> = Vec::new();
+        let mut seen: HashSet<PathBuf> = HashSet::new();
+
+        for rel in changes.roots() {
+            let source_path = source.join(rel);
+            let metadata = match std::fs::symlink_metadata(&source_path) {
+                Ok(metadata) => metadata,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    if self.delete && !self.is_excluded_path(rel, false) {
+                        self.delete_path(&transferrer, &destination.join(rel), &mut stats)
+                            .await;
+                    }
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
+
+            if self.is_excluded_path(rel, metadata.is_dir()) {
+                continue;
+            }
+
+            // Parent directories, outermost first, so missing ones get created
+            let mut parents: Vec<&Path> = rel
+                .ancestors()
+                .skip(1)
+                .take_while(|p| !p.as_os_str().is_empty())
+                .collect();
+            parents.reverse();
+            for parent in parents {
+                if seen.insert(parent.to_path_buf()) {
+                    entries.push(scanner::scan_entry(source, &source.join(parent))?);
+                }
+            }
+
+            if seen.insert(rel.to_path_buf()) {
+                entries.push(scanner::scan_entry(source, &source_path)?);
+            }
+
+            if metadata.is_dir() {
+                for mut entry in self.transport.scan(&source_path).await? {
+                    entry.relative_path = Arc::new(rel.join(&*entry.relative_path));
+                    if seen.insert((*entry.relative_path).clone()) {
+                        entries.push(entry);
+                    }
+                }
+            }
+        }
+
+        let entries: Vec<FileEntry> = entries
+            .into_iter()
+            .filter(|entry| {
+                !self.is_excluded_path(&entry.relative_path, entry.is_dir)
+                    && (entry.is_dir || !self.should_filter_by_size(entry.size))
+            })
+            .collect();
+        stats.files_scanned = entries.len() as u64;
+
+        let planner = StrategyPlanner::with_comparison_flags(
+            self.ignore_times,
+            self.size_only,
+            self.checksum,
+            self.update_only,
+            self.ignore_existing,
+        );
+        let mut tasks: Vec<SyncTask> = Vec::with_capacity(entries.len());
+        for entry in &entries {
+            tasks.push(
+                planner
+                    .plan_file_async(entry, destination, self.transport.as_ref(), None)
+                    .await?,
+            );
+        }
+
+        let transferrer = &transferrer;
+        let mut results = futures::stream::iter(tasks.into_iter().map(|task| async move {
+            let result = match (&task.action, &task.source) {
+                (SyncAction::Create, Some(entry)) => {
+                    transferrer.create(entry, &task.dest_path).await
+                }
+                (SyncAction::Update, Some(entry)) => {
+                    transferrer.update(entry, &task.dest_path).await
+                }
+                _ => Ok(None),
+            };
+            (task, result)
+        }))
+        .buffer_unordered(self.max_concurrent.max(1));
+
+        while let Some((task, result)) = results.next().await {
+            self.record_task(&mut stats, &task, result);
+        }
+
+        stats.duration = start_time.elapsed();
+        Ok(stats)
+    }
+
+    /// Whether a path, or any directory above it, is excluded by the filters
+    fn is_excluded_path(&self, relative_path: &Path, is_dir: bool) -> bool {
+        if self.should_exclude(relative_path, is_dir) {
+            return true;
+        }
+        relative_path
+            .ancestors()
+            .skip(1)
+            .take_while(|p| !p.as_os_str().is_empty())
+            .any(|parent| self.should_exclude(parent, true))
+    }
+
+    /// Remove a path that disappeared from the source
+    async fn delete_path(
+        &self,
+        transferrer: &Transferrer<'_, T>,
+        dest_path: &Path,
+        stats: &mut SyncStats,
+    ) {
+        if !self.transport.exists(dest_path).await.unwrap_or(false) {
+            return;
+        }
+
+        // The source is gone, so we can't tell whether it was a directory;
+        // try it as a file first and fall back to removing a tree
+        let result = match transferrer.delete(dest_path, false).await {
+            Ok(()) => Ok(()),
+            Err(_) => transferrer.delete(dest_path, true).await,
+        };
+
+        match result {
+            Ok(()) => {
+                stats.files_deleted += 1;
+                if self.json {
+                    SyncEvent::Delete {
+                        path: dest_path.to_path_buf(),
+                    }
+                    .emit();
+                }
+            }
+            Err(e) => record_error(stats, dest_path.to_path_buf(), "delete", &e),
+        }
+    }
+
+    fn record_task(
+        &self,
+        stats: &mut SyncStats,
+        task: &SyncTask,
+        result: Result<Option<TransferResult>>,
+    ) {
+        let size = task.source.as_ref().map(|s| s.size).unwrap_or(0);
+        let transfer = match result {
+            Ok(transfer) => transfer,
+            Err(e) => {
+                let action = match task.action {
+                    SyncAction::Update => "update",
+                    _ => "create",
+                };
+                record_error(stats, task.dest_path.clone(), action, &e);
+                return;
+            }
+        };
+        let bytes_written = transfer.as_ref().map(|t| t.bytes_written).unwrap_or(0);
+        stats.bytes_transferred += bytes_written;
+
+        match task.action {
+            SyncAction::Create => {
+                stats.files_created += 1;
+                if self.dry_run {
+                    stats.bytes_would_add += size;
+                }
+                if self.json {
+                    SyncEvent::Create {
+                        path: task.dest_path.clone(),
+                        size,
+                        bytes_transferred: bytes_written,
+                    }
+                    .emit();
+                }
+            }
+            SyncAction::Update => {
+                stats.files_updated += 1;
+                let delta_used = transfer.as_ref().is_some_and(|t| t.used_delta());
+                if delta_used {
+                    stats.files_delta_synced += 1;
+                    if let Some(literal_bytes) = transfer.as_ref().and_then(|t| t.literal_bytes) {
+                        stats.delta_bytes_saved += bytes_written.saturating_sub(literal_bytes);
+                    }
+                }
+                if self.dry_run {
+                    stats.bytes_would_change += size;
+                }
+                if self.json {
+                    SyncEvent::Update {
+                        path: task.dest_path.clone(),
+                        size,
+                        bytes_transferred: bytes_written,
+                        delta_used,
+                        delta_stats: transfer
+                            .as_ref()
+                            .filter(|_| self.delta_stats)
+                            .and_then(super::output::DeltaReport::from_transfer),
+                    }
+                    .emit();
+                }
+            }
+            _ => {
+                stats.files_skipped += 1;
+            }
+        }
+
+        if let Some(transfer) = transfer {
+            if transfer.compression_used {
+                stats.files_compressed += 1;
+                if let Some(transferred) = transfer.transferred_bytes {
+                    stats.compression_bytes_saved += bytes_written.saturating_sub(transferred);
+                }
+            }
+        }
+    }
+}
+
+fn record_error(
+    stats: &mut SyncStats,
+    path: PathBuf,
+    action: &str,
+    error: &crate::error::SyncError,
+) {
+    tracing::error!("Sync error for {}: {}", path.display(), error);
+    stats.errors.push(SyncError {
+        path,
+        error: error.to_string(),
+        action: action.to_string(),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::SymlinkMode;
+    use crate::integrity::ChecksumType;
+    use crate::transport::local::LocalTransport;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn engine(delete: bool) -> SyncEngine<LocalTransport> {
+        SyncEngine::new(
+            LocalTransport::new(),
+            false,
+            false,
+            delete,
+            50,
+            false,
+            false,
+            true,
+            4,
+            100,
+            None,
+            None,
+            crate::filter::FilterEngine::new(),
+            None,
+            false,
+            10,
+            100,
+            false,
+            ChecksumType::None,
+            false,
+            SymlinkMode::Preserve,
+            false,
+            false,
+            false,
+            false,
+            false,
+            false,
+            false,
+            false,
+            false,
+            false,
+            false,
+            false,
+            false,
+            false,
+            false,
+            false,
+            false,
+        )
+    }
+
+    #[test]
+    fn test_change_set_roots_skip_descendants() {
+        let mut changes = ChangeSet::new();
+        changes.insert(PathBuf::from("a/b/c.txt"));
+        changes.insert(PathBuf::from("a/b"));
+        changes.insert(PathBuf::from("ab.txt"));
+        changes.insert(PathBuf::new());
+
+        assert_eq!(changes.len(), 3);
+        assert_eq!(changes.roots(), vec![Path::new("a/b"), Path::new("ab.txt")]);
+    }
+
+    #[tokio::test]
+    async fn test_sync_paths_touches_only_changed_paths() {
+        let temp = TempDir::new().unwrap();
+        let source = temp.path().join("src");
+        let dest = temp.path().join("dst");
+        fs::create_dir_all(source.join("sub")).unwrap();
+        fs::create_dir_all(&dest).unwrap();
+        fs::write(source.join("sub/changed.txt"), "new").unwrap();
+        fs::write(source.join("untouched.txt"), "left alone").unwrap();
+
+        let mut changes = ChangeSet::new();
+        changes.insert(PathBuf::from("sub/changed.txt"));
+
+        let stats = engine(false)
+            .sync_paths(&source, &dest, &changes)
+            .await
+            .unwrap();
+
+        assert!(stats.errors.is_empty());
+        assert_eq!(
+            fs::read_to_string(dest.join("sub/changed.txt")).unwrap(),
+            "new"
+        );
+        assert!(!dest.join("untouched.txt").exists());
+    }
+
+    #[tokio::test]
+    async fn test_sync_paths_replays_renames_and_deletes() {
+        let temp = TempDir::new().unwrap();
+        let source = temp.path().join("src");
+        let dest = temp.path().join("dst");
+        fs::create_dir_all(&source).unwrap();
+        fs::create_dir_all(&dest).unwrap();
+        fs::write(source.join("new.txt"), "content").unwrap();
+        fs::write(dest.join("old.txt"), "content").unwrap();
+        fs::write(dest.join("gone.txt"), "stale").unwrap();
+
+        let mut changes = ChangeSet::new();
+        changes.insert_rename(PathBuf::from("old.txt"), PathBuf::from("new.txt"));
+        changes.insert(PathBuf::from("gone.txt"));
+
+        let stats = engine(true)
+            .sync_paths(&source, &dest, &changes)
+            .await
+            .unwrap();
+
+        assert_eq!(stats.files_moved, 1);
+        assert_eq!(stats.files_deleted, 1);
+        assert!(!dest.join("old.txt").exists());
+        assert!(!dest.join("gone.txt").exists());
+        assert_eq!(fs::read_to_string(dest.join("new.txt")).unwrap(), "content");
+    }
+}
@@ -1,10 +1,14 @@
+pub mod batch;
 pub mod checksumdb;
 #[cfg(unix)]
 pub mod daemon_auto;
 #[cfg(unix)]
 pub mod daemon_mode;
 pub mod dircache;
+pub mod fuzzy;
+pub mod incremental;
 pub mod live_progress;
+pub mod moves;
 pub mod output;
 pub mod progress;
 pub mod ratelimit;
@@ -82,6 +86,7 @@ pub enum ChangeAction {
     Update,
     Delete,
     Skip,
+    Move,
 }
 
 /// Comprehensive dry-run details with file-level information
@@ -103,11 +108,15 @@ pub struct SyncStats {
     pub files_updated: u64,
     pub files_skipped: usize,
     pub files_deleted: usize,
+    pub files_moved: usize,
     pub bytes_transferred: u64,
     pub files_delta_synced: usize,
     pub delta_bytes_saved: u64,
     pub files_compressed: usize,
     pub compression_bytes_saved: u64,
+    // Stream-level compression on server/daemon connections (0 when off)
+    pub stream_bytes_raw: u64,
+    pub stream_bytes_wire: u64,
     pub files_verified: usize,
     pub verification_failures: usize,
     pub duration: Duration,
@@ -179,6 +188,12 @@ pub struct SyncEngine<T: Transport> {
     perf_monitor: Option<Arc<Mutex<PerformanceMonitor>>>,
     /// Optional live progress state for real-time progress reporting
     live_progress: Option<Arc<live_progress::ProgressState>>,
+    /// Turn matching create + delete pairs into destination renames
+    detect_moves: bool,
+    /// Delta-encode new files against similar destination files
+    fuzzy: bool,
+    /// Report matched and literal bytes per delta-synced file in JSON output
+    delta_stats: bool,
 }
 
 impl<T: Transport + 'static> SyncEngine<T> {
@@ -269,9 +284,36 @@ impl<T: Transport + 'static> SyncEngine<T> {
             dest_is_remote,
             perf_monitor,
             live_progress: None,
+            detect_moves: false,
+            fuzzy: false,
+            delta_stats: false,
         }
     }
 
+    /// Enable rename/move detection (requires --delete)
+    ///
+    /// Files that vanished from one destination path and appear at another in
+    /// the source are renamed on the destination instead of copied again.
+    pub fn with_move_detection(mut self, detect_moves: bool) -> Self {
+        self.detect_moves = detect_moves;
+        self
+    }
+
+    /// Enable fuzzy basis selection for new files (--fuzzy)
+    ///
+    /// A new file with a similarly named file in its destination directory
+    /// is delta-encoded against that file instead of copied whole.
+    pub fn with_fuzzy(mut self, fuzzy: bool) -> Self {
+        self.fuzzy = fuzzy;
+        self
+    }
+
+    /// Include matched/literal byte counts in JSON update events (--delta-stats)
+    pub fn with_delta_stats(mut self, delta_stats: bool) -> Self {
+        self.delta_stats = delta_stats;
+        self
+    }
+
     /// Set the live progress state for real-time progress reporting
     ///
     /// When set, the sync engine will update this state during sync operations,
@@ -306,6 +348,381 @@ impl<T: Transport + 'static> SyncEngine<T> {
         self.filter_engine.should_exclude(relative_path, is_dir)
     }
 
+    /// Drop excluded and out-of-size-range entries from a source scan
+    ///
+    /// Children of an excluded directory are dropped with it (rsync behavior).
+    fn filter_source_files(&self, all_files: Vec<FileEntry>) -> Vec<FileEntry> {
+        let mut excluded_dirs: Vec<PathBuf> = Vec::new();
+
+        all_files
+            .into_iter()
+            .filter(|file| {
+                // Check if this file is inside an excluded directory
+                for excluded_dir in &excluded_dirs {
+                    if file.relative_path.starts_with(excluded_dir) {
+                        tracing::debug!(
+                            "Filtering out (parent excluded): {}",
+                            file.relative_path.display()
+                        );
+                        return false;
+                    }
+                }
+
+                // Apply exclude patterns
+                if self.should_exclude(&file.relative_path, file.is_dir) {
+                    tracing::debug!("Filtering out (excluded): {}", file.relative_path.display());
+
+                    // If this is a directory, track it to exclude its children
+                    if file.is_dir {
+                        excluded_dirs.push((*file.relative_path).clone());
+                    }
+
+                    return false;
+                }
+
+                // Don't filter directories (but only after checking exclude patterns)
+                if file.is_dir {
+                    return true;
+                }
+                // Apply size filter
+                if self.should_filter_by_size(file.size) {
+                    tracing::debug!("Filtering out (size): {}", file.relative_path.display());
+                    return false;
+                }
+                true
+            })
+            .collect()
+    }
+
+    /// Refuse (or ask before) deleting `deletions` destination files
+    ///
+    /// Enforces --delete-threshold unless --force-delete, and asks for
+    /// confirmation before large deletions.
+    fn check_deletions(&self, deletions: usize, destination: &Path) -> Result<()> {
+        if deletions == 0 {
+            return Ok(());
+        }
+        let dest_file_count = scanner::Scanner::new(destination)
+            .scan()
+            .map(|files| files.len())
+            .unwrap_or(0);
+
+        // Check threshold: prevent mass deletion
+        if dest_file_count > 0 && !self.force_delete {
+            let delete_percentage = (deletions as f64 / dest_file_count as f64) * 100.0;
+
+            if delete_percentage > self.delete_threshold as f64 {
+                tracing::error!(
+                    "Refusing to delete {:.1}% of destination files ({} files). Threshold: {}%. Use --force-delete to override.",
+                    delete_percentage,
+                    deletions,
+                    self.delete_threshold
+                );
+
+                if !self.quiet {
+                    eprintln!(
+                        "⚠️  ERROR: Would delete {:.1}% of files ({}/{}), exceeding threshold of {}%",
+                        delete_percentage,
+                        deletions,
+                        dest_file_count,
+                        self.delete_threshold
+                    );
+                    eprintln!("Use --force-delete to skip safety checks (dangerous!)");
+                }
+
+                return Err(crate::error::SyncError::Io(std::io::Error::other(format!(
+                    "Deletion threshold exceeded: {:.1}% > {}%",
+                    delete_percentage, self.delete_threshold
+                ))));
+            }
+        }
+
+        // CRITICAL SAFETY NET: Even with --force-delete, require confirmation for catastrophic deletions
+        // This prevents accidental destruction of large amounts of data
+        const CATASTROPHIC_THRESHOLD: usize = 10000;
+        if deletions > CATASTROPHIC_THRESHOLD && !self.quiet && !self.json && !self.dry_run {
+            let warning_msg = if self.force_delete {
+                format!(
+                    "🚨 CRITICAL WARNING: About to delete {} files with --force-delete!\n\
+                     This will PERMANENTLY DELETE a large amount of data.\n\
+                     Type 'DELETE {}' to confirm (case-sensitive): ",
+                    deletions, deletions
+                )
+            } else {
+                format!(
+                    "⚠️  WARNING: About to delete {} files. Continue? [y/N] ",
+                    deletions
+                )
+            };
+
+            eprintln!("{}", warning_msg);
+
+            // Check if stdin is a TTY before prompting to avoid hanging on non-interactive input
+            use std::io::IsTerminal;
+            if !std::io::stdin().is_terminal() {
+                return Err(crate::error::SyncError::Io(std::io::Error::other(
+                    "Cannot prompt for deletion confirmation: stdin is not a terminal",
+                )));
+            }
+
+            let mut input = String::new();
+            std::io::stdin().read_line(&mut input)?;
+
+            let confirmed = if self.force_delete {
+                // Require exact confirmation string for catastrophic deletions
+                input.trim() == format!("DELETE {}", deletions)
+            } else {
+                input.trim().eq_ignore_ascii_case("y")
+            };
+
+            if !confirmed {
+                tracing::info!("Deletion cancelled by user");
+                return Err(crate::error::SyncError::Io(std::io::Error::other(
+                    "Deletion cancelled by user",
+                )));
+            }
+        } else if deletions > 1000
+            && !self.force_delete
+            && !self.quiet
+            && !self.json
+            && !self.dry_run
+        {
+            // Standard confirmation for large deletions (without --force-delete)
+            eprintln!(
+                "⚠️  WARNING: About to delete {} files. Continue? [y/N] ",
+                deletions
+            );
+
+            // Check if stdin is a TTY before prompting to avoid hanging on non-interactive input
+            use std::io::IsTerminal;
+            if !std::io::stdin().is_terminal() {
+                return Err(crate::error::SyncError::Io(std::io::Error::other(
+                    "Cannot prompt for deletion confirmation: stdin is not a terminal",
+                )));
+            }
+
+            let mut input = String::new();
+            std::io::stdin().read_line(&mut input)?;
+
+            if !input.trim().eq_ignore_ascii_case("y") {
+                tracing::info!("Deletion cancelled by user");
+                return Err(crate::error::SyncError::Io(std::io::Error::other(
+                    "Deletion cancelled by user",
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Replace matching Create/Delete task pairs with Move tasks
+    ///
+    /// Candidates are paired on size + mtime and then confirmed by checksum.
+    /// The destination checksum comes from the checksum database when it has
+    /// an entry for the old path, so remote files don't have to be re-hashed.
+    /// Returns the number of moves planned.
+    async fn plan_moves(
+        &self,
+        tasks: &mut [strategy::SyncTask],
+        deletions: &mut Vec<strategy::SyncTask>,
+        source: &Path,
+        destination: &Path,
+        dest_map: &std::collections::HashMap<PathBuf, FileEntry>,
+        checksum_db: Option<&checksumdb::ChecksumDatabase>,
+    ) -> usize {
+        use moves::MoveCandidate;
+
+        let mut removed_indices = Vec::new();
+        let mut removed = Vec::new();
+        for (i, deletion) in deletions.iter().enumerate() {
+            let Ok(rel) = deletion.dest_path.strip_prefix(destination) else {
+                continue;
+            };
+            if let Some(entry) = dest_map.get(rel) {
+                if !entry.is_dir && !entry.is_symlink {
+                    removed_indices.push(i);
+                    removed.push(MoveCandidate::from_entry(entry));
+                }
+            }
+        }
+
+        let mut added_indices = Vec::new();
+        let mut added = Vec::new();
+        for (i, task) in tasks.iter().enumerate() {
+            if task.action != SyncAction::Create {
+                continue;
+            }
+            if let Some(src) = &task.source {
+                if !src.is_dir && !src.is_symlink {
+                    added_indices.push(i);
+                    added.push(MoveCandidate::from_entry(src));
+                }
+            }
+        }
+
+        if removed.is_empty() || added.is_empty() {
+            return 0;
+        }
+
+        let matches = moves::match_candidates(&removed, &added, Duration::from_secs(1));
+        let verifier = IntegrityVerifier::new(ChecksumType::Fast, false);
+        let mut moved_deletions = std::collections::HashSet::new();
+
+        for m in matches {
+            let old = &removed[m.removed];
+            let deletion_index = removed_indices[m.removed];
+            let task_index = added_indices[m.added];
+            let old_path = deletions[deletion_index].dest_path.clone();
+
+            if !m.by_inode {
+                let Some(src) = tasks[task_index].source.clone() else {
+                    continue;
+                };
+                let source_checksum =
+                    match self.transport.compute_checksum(&src.path, &verifier).await {
+                        Ok(c) => c,
+                        Err(e) => {
+                            tracing::debug!("Move candidate {}: {}", src.path.display(), e);
+                            continue;
+                        }
+                    };
+
+                // sy preserves mtimes, so the source checksum recorded for the
+                // old path still describes the destination copy
+                let cached = checksum_db.and_then(|db| {
+                    db.get_checksum(&source.join(&old.path), old.modified, old.size, "fast")
+                        .ok()
+                        .flatten()
+                });
+                let dest_checksum = match cached {
+                    Some(c) => c,
+                    None => match self.transport.compute_checksum(&old_path, &verifier).await {
+                        Ok(c) => c,
+                        Err(e) => {
+                            tracing::debug!("Move candidate {}: {}", old_path.display(), e);
+                            continue;
+                        }
+                    },
+                };
+
+                if source_checksum != dest_checksum {
+                    continue;
+                }
+            }
+
+            tracing::debug!(
+                "Detected move: {} -> {}",
+                old_path.display(),
+                tasks[task_index].dest_path.display()
+            );
+            tasks[task_index].action = SyncAction::Move { from: old_path };
+            moved_deletions.insert(deletion_index);
+        }
+
+        let moved = moved_deletions.len();
+        if moved > 0 {
+            let mut index = 0;
+            deletions.retain(|_| {
+                let keep = !moved_deletions.contains(&index);
+                index += 1;
+                keep
+            });
+        }
+        moved
+    }
+
+    /// Give new files a similar destination file as their delta basis
+    ///
+    /// Files that are about to be moved away aren't offered. Returns the
+    /// number of files given a basis.
+    fn plan_fuzzy_bases(
+        &self,
+        tasks: &mut [strategy::SyncTask],
+        destination: &Path,
+        dest_map: &std::collections::HashMap<PathBuf, FileEntry>,
+    ) -> usize {
+        let moved: std::collections::HashSet<&Path> = tasks
+            .iter()
+            .filter_map(|t| match &t.action {
+                SyncAction::Move { from } => from.strip_prefix(destination).ok(),
+                _ => None,
+            })
+            .collect();
+
+        let mut index = fuzzy::BasisIndex::new();
+        for (rel, entry) in dest_map {
+            if !entry.is_dir && !entry.is_symlink && !moved.contains(rel.as_path()) {
+                index.insert(rel, entry.size);
+            }
+        }
+
+        let mut planned = 0;
+        for task in tasks.iter_mut() {
+            if task.action != SyncAction::Create {
+                continue;
+            }
+            let Some(src) = &task.source else {
+                continue;
+            };
+            // Hard-linked files are linked to their first copy, not rebuilt
+            if src.is_dir || src.is_symlink || (self.preserve_hardlinks && src.nlink > 1) {
+                continue;
+            }
+            if let Some(basis) = index.find(&src.relative_path, src.size) {
+                tracing::debug!(
+                    "Fuzzy basis for {}: {}",
+                    src.relative_path.display(),
+                    basis.display()
+                );
+                task.basis = Some(destination.join(basis));
+                planned += 1;
+            }
+        }
+        planned
+    }
+
+    /// Plan a sync without executing it (--write-batch)
+    ///
+    /// Scans, filters and compares like `sync`, but only returns the tasks
+    /// that change the destination: creates, updates and (with --delete)
+    /// deletions, which come last. Deletions pass the same safety checks as
+    /// in `sync`.
+    pub async fn plan(&self, source: &Path, destination: &Path) -> Result<Vec<strategy::SyncTask>> {
+        let source_files = self.filter_source_files(self.transport.scan(source).await?);
+
+        let dest_map: std::collections::HashMap<PathBuf, FileEntry> = self
+            .transport
+            .scan_destination(destination)
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .map(|f| ((*f.relative_path).clone(), f))
+            .collect();
+
+        let planner = StrategyPlanner::with_comparison_flags(
+            self.ignore_times,
+            self.size_only,
+            self.checksum,
+            self.update_only,
+            self.ignore_existing,
+        );
+        let mut tasks: Vec<strategy::SyncTask> = source_files
+            .iter()
+            .map(|file| planner.plan_file_with_dest_map(file, destination, &dest_map))
+            .filter(|task| task.action != SyncAction::Skip)
+            .collect();
+
+        if self.fuzzy {
+            self.plan_fuzzy_bases(&mut tasks, destination, &dest_map);
+        }
+        if self.delete {
+            let deletions = planner.plan_deletions(&source_files, destination);
+            self.check_deletions(deletions.len(), destination)?;
+            tasks.extend(deletions);
+        }
+
+        Ok(tasks)
+    }
+
     pub async fn sync(&self, source: &Path, destination: &Path) -> Result<SyncStats> {
         let start_time = std::time::Instant::now();
 
@@ -484,47 +901,7 @@ impl<T: Transport + 'static> SyncEngine<T> {
         }
 
         // Filter files by size and exclude patterns
-        // Also track excluded directories to filter their children (rsync behavior)
-        let mut excluded_dirs: Vec<PathBuf> = Vec::new();
-
-        let source_files: Vec<_> = all_files
-            .into_iter()
-            .filter(|file| {
-                // Check if this file is inside an excluded directory
-                for excluded_dir in &excluded_dirs {
-                    if file.relative_path.starts_with(excluded_dir) {
-                        tracing::debug!(
-                            "Filtering out (parent excluded): {}",
-                            file.relative_path.display()
-                        );
-                        return false;
-                    }
-                }
-
-                // Apply exclude patterns
-                if self.should_exclude(&file.relative_path, file.is_dir) {
-                    tracing::debug!("Filtering out (excluded): {}", file.relative_path.display());
-
-                    // If this is a directory, track it to exclude its children
-                    if file.is_dir {
-                        excluded_dirs.push((*file.relative_path).clone());
-                    }
-
-                    return false;
-                }
-
-                // Don't filter directories (but only after checking exclude patterns)
-                if file.is_dir {
-                    return true;
-                }
-                // Apply size filter
-                if self.should_filter_by_size(file.size) {
-                    tracing::debug!("Filtering out (size): {}", file.relative_path.display());
-                    return false;
-                }
-                true
-            })
-            .collect();
+        let source_files = self.filter_source_files(all_files);
 
         if source_files.len() < total_scanned {
             let filtered_count = total_scanned - source_files.len();
@@ -721,128 +1098,37 @@ impl<T: Transport + 'static> SyncEngine<T> {
 
         // Plan deletions if requested
         if self.delete {
-            let deletions = planner.plan_deletions(&source_files, destination);
-
-            // Apply deletion safety checks
-            if !deletions.is_empty() {
-                let dest_file_count = scanner::Scanner::new(destination)
-                    .scan()
-                    .map(|files| files.len())
-                    .unwrap_or(0);
-
-                // Check threshold: prevent mass deletion
-                if dest_file_count > 0 && !self.force_delete {
-                    let delete_percentage =
-                        (deletions.len() as f64 / dest_file_count as f64) * 100.0;
-
-                    if delete_percentage > self.delete_threshold as f64 {
-                        tracing::error!(
-                            "Refusing to delete {:.1}% of destination files ({} files). Threshold: {}%. Use --force-delete to override.",
-                            delete_percentage,
-                            deletions.len(),
-                            self.delete_threshold
-                        );
-
-                        if !self.quiet {
-                            eprintln!(
-                                "⚠️  ERROR: Would delete {:.1}% of files ({}/{}), exceeding threshold of {}%",
-                                delete_percentage,
-                                deletions.len(),
-                                dest_file_count,
-                                self.delete_threshold
-                            );
-                            eprintln!("Use --force-delete to skip safety checks (dangerous!)");
-                        }
-
-                        return Err(crate::error::SyncError::Io(std::io::Error::other(format!(
-                            "Deletion threshold exceeded: {:.1}% > {}%",
-                            delete_percentage, self.delete_threshold
-                        ))));
-                    }
+            let mut deletions = planner.plan_deletions(&source_files, destination);
+
+            // Turn create + delete pairs into renames before the safety checks,
+            // so renaming a large directory doesn't look like a mass deletion
+            if self.detect_moves && !deletions.is_empty() {
+                let moved = self
+                    .plan_moves(
+                        &mut tasks,
+                        &mut deletions,
+                        source,
+                        destination,
+                        &dest_map,
+                        checksum_db.as_ref(),
+                    )
+                    .await;
+                if moved > 0 {
+                    tracing::info!("Detected {} moved files", moved);
                 }
+            }
 
-                // CRITICAL SAFETY NET: Even with --force-delete, require confirmation for catastrophic deletions
-                // This prevents accidental destruction of large amounts of data
-                const CATASTROPHIC_THRESHOLD: usize = 10000;
-                if deletions.len() > CATASTROPHIC_THRESHOLD
-                    && !self.quiet
-                    && !self.json
-                    && !self.dry_run
-                {
-                    let warning_msg = if self.force_delete {
-                        format!(
-                            "🚨 CRITICAL WARNING: About to delete {} files with --force-delete!\n\
-                             This will PERMANENTLY DELETE a large amount of data.\n\
-                             Type 'DELETE {}' to confirm (case-sensitive): ",
-                            deletions.len(),
-                            deletions.len()
-                        )
-                    } else {
-                        format!(
-                            "⚠️  WARNING: About to delete {} files. Continue? [y/N] ",
-                            deletions.len()
-                        )
-                    };
-
-                    eprintln!("{}", warning_msg);
-
-                    // Check if stdin is a TTY before prompting to avoid hanging on non-interactive input
-                    use std::io::IsTerminal;
-                    if !std::io::stdin().is_terminal() {
-                        return Err(crate::error::SyncError::Io(std::io::Error::other(
-                            "Cannot prompt for deletion confirmation: stdin is not a terminal",
-                        )));
-                    }
-
-                    let mut input = String::new();
-                    std::io::stdin().read_line(&mut input)?;
-
-                    let confirmed = if self.force_delete {
-                        // Require exact confirmation string for catastrophic deletions
-                        input.trim() == format!("DELETE {}", deletions.len())
-                    } else {
-                        input.trim().eq_ignore_ascii_case("y")
-                    };
-
-                    if !confirmed {
-                        tracing::info!("Deletion cancelled by user");
-                        return Err(crate::error::SyncError::Io(std::io::Error::other(
-                            "Deletion cancelled by user",
-                        )));
-                    }
-                } else if deletions.len() > 1000
-                    && !self.force_delete
-                    && !self.quiet
-                    && !self.json
-                    && !self.dry_run
-                {
-                    // Standard confirmation for large deletions (without --force-delete)
-                    eprintln!(
-                        "⚠️  WARNING: About to delete {} files. Continue? [y/N] ",
-                        deletions.len()
-                    );
-
-                    // Check if stdin is a TTY before prompting to avoid hanging on non-interactive input
-                    use std::io::IsTerminal;
-                    if !std::io::stdin().is_terminal() {
-                        return Err(crate::error::SyncError::Io(std::io::Error::other(
-                            "Cannot prompt for deletion confirmation: stdin is not a terminal",
-                        )));
-                    }
+            // Apply deletion safety checks
+            self.check_deletions(deletions.len(), destination)?;
 
-                    let mut input = String::new();
-                    std::io::stdin().read_line(&mut input)?;
+            tasks.extend(deletions);
+        }
 
-                    if !input.trim().eq_ignore_ascii_case("y") {
-                        tracing::info!("Deletion cancelled by user");
-                        return Err(crate::error::SyncError::Io(std::io::Error::other(
-                            "Deletion cancelled by user",
-                        )));
-                    }
-                }
+        if self.fuzzy {
+            let based = self.plan_fuzzy_bases(&mut tasks, destination, &dest_map);
+            if based > 0 {
+                tracing::info!("Found a fuzzy basis for {} new files", based);
             }
-
-            tasks.extend(deletions);
         }
 
         // End plan timing
@@ -873,11 +1159,14 @@ impl<T: Transport + 'static> SyncEngine<T> {
             files_updated: 0,
             files_skipped: 0,
             files_deleted: 0,
+            files_moved: 0,
             bytes_transferred: 0,
             files_delta_synced: 0,
             delta_bytes_saved: 0,
             files_compressed: 0,
             compression_bytes_saved: 0,
+            stream_bytes_raw: 0,
+            stream_bytes_wire: 0,
             files_verified: 0,
             verification_failures: 0,
             duration: Duration::ZERO,
@@ -900,7 +1189,12 @@ impl<T: Transport + 'static> SyncEngine<T> {
         // Calculate total bytes to transfer (for accurate progress/ETA)
         let total_bytes: u64 = tasks
             .iter()
-            .filter(|t| !matches!(t.action, SyncAction::Skip | SyncAction::Delete))
+            .filter(|t| {
+                !matches!(
+                    t.action,
+                    SyncAction::Skip | SyncAction::Delete | SyncAction::Move { .. }
+                )
+            })
             .map(|t| {
                 t.source
                     .as_ref()
@@ -912,7 +1206,12 @@ impl<T: Transport + 'static> SyncEngine<T> {
         // Count total transfers (non-skip, non-delete tasks with source files)
         let total_transfers = tasks
             .iter()
-            .filter(|t| !matches!(t.action, SyncAction::Skip | SyncAction::Delete))
+            .filter(|t| {
+                !matches!(
+                    t.action,
+                    SyncAction::Skip | SyncAction::Delete | SyncAction::Move { .. }
+                )
+            })
             .filter(|t| t.source.as_ref().map(|s| !s.is_dir).unwrap_or(false))
             .count();
 
@@ -956,7 +1255,12 @@ impl<T: Transport + 'static> SyncEngine<T> {
         if !self.dry_run {
             let unique_dirs: std::collections::HashSet<_> = tasks
                 .iter()
-                .filter(|t| matches!(t.action, SyncAction::Create | SyncAction::Update))
+                .filter(|t| {
+                    matches!(
+                        t.action,
+                        SyncAction::Create | SyncAction::Update | SyncAction::Move { .. }
+                    )
+                })
                 .filter_map(|t| t.dest_path.parent())
                 .filter(|p| !p.as_os_str().is_empty())
                 .collect();
@@ -980,7 +1284,7 @@ impl<T: Transport + 'static> SyncEngine<T> {
 
         // Use stream-based execution (buffer_unordered) instead of join_all
         // This allows processing results as they complete and enabling periodic checkpointing
-        let transfer_futures = tasks.into_iter().map(|task| {
+        let make_transfer_future = |task: strategy::SyncTask| {
             let transport = Arc::clone(&self.transport);
             let dry_run = self.dry_run;
             let diff_mode = self.diff_mode;
@@ -1043,6 +1347,7 @@ impl<T: Transport + 'static> SyncEngine<T> {
                     SyncAction::Update => format!("Updating: {}", filename),
                     SyncAction::Skip => format!("Skipping: {}", filename),
                     SyncAction::Delete => format!("Deleting: {}", filename),
+                    SyncAction::Move { .. } => format!("Moving: {}", filename),
                 };
 
                 if !matches!(task.action, SyncAction::Skip) {
@@ -1062,7 +1367,15 @@ impl<T: Transport + 'static> SyncEngine<T> {
                 let result = match task.action {
                     SyncAction::Create => {
                         if let Some(source) = &task.source {
-                            match transferrer.create(source, &task.dest_path).await {
+                            let created = match &task.basis {
+                                Some(basis) => {
+                                    transferrer
+                                        .create_from_basis(source, basis, &task.dest_path)
+                                        .await
+                                }
+                                None => transferrer.create(source, &task.dest_path).await,
+                            };
+                            match created {
                                 Ok(transfer_result) => {
                                     let bytes_written = transfer_result
                                         .as_ref()
@@ -1230,6 +1543,18 @@ impl<T: Transport + 'static> SyncEngine<T> {
                         _error: None,
                         verified: true,
                     }),
+                    SyncAction::Move { ref from } => {
+                        match transferrer.rename(from, &task.dest_path).await {
+                            Ok(_) => Ok(TaskResult {
+                                task: task.clone(),
+                                bytes_written: 0,
+                                transfer_result: None,
+                                _error: None,
+                                verified: true,
+                            }),
+                            Err(e) => Err((task.clone(), e)),
+                        }
+                    }
                 };
 
                 // Update progress bar
@@ -1250,11 +1575,22 @@ impl<T: Transport + 'static> SyncEngine<T> {
 
                 result
             }
-        });
+        };
+
+        // Renames run to completion before anything else, so deleting an old
+        // directory can't race with moving files out of it; so do creates
+        // reading a fuzzy basis, which may be updated or deleted later
+        let (move_tasks, tasks): (Vec<_>, Vec<_>) = tasks
+            .into_iter()
+            .partition(|t| matches!(t.action, SyncAction::Move { .. }) || t.basis.is_some());
 
         // Process results as they stream in
-        let mut stream =
-            futures::stream::iter(transfer_futures).buffer_unordered(self.max_concurrent);
+        let mut stream = futures::stream::iter(move_tasks.into_iter().map(&make_transfer_future))
+            .buffer_unordered(self.max_concurrent)
+            .chain(
+                futures::stream::iter(tasks.into_iter().map(&make_transfer_future))
+                    .buffer_unordered(self.max_concurrent),
+            );
 
         while let Some(result) = stream.next().await {
             match result {
@@ -1283,7 +1619,7 @@ impl<T: Transport + 'static> SyncEngine<T> {
                                                     .transfer_result
                                                     .as_ref()
                                                     .map(|tr| tr.used_delta())
-                                                    .unwrap_or(false),
+                                                    .unwrap_or(task.basis.is_some()),
                                                 would_compress: res
                                                     .transfer_result
                                                     .as_ref()
@@ -1409,6 +1745,11 @@ impl<T: Transport + 'static> SyncEngine<T> {
                                     size: task.source.as_ref().map(|s| s.size).unwrap_or(0),
                                     bytes_transferred: res.bytes_written,
                                     delta_used,
+                                    delta_stats: res
+                                        .transfer_result
+                                        .as_ref()
+                                        .filter(|_| self.delta_stats)
+                                        .and_then(output::DeltaReport::from_transfer),
                                 }
                                 .emit();
                             }
@@ -1485,6 +1826,33 @@ impl<T: Transport + 'static> SyncEngine<T> {
                                 .emit();
                             }
                         }
+                        SyncAction::Move { ref from } => {
+                            s.files_moved += 1;
+                            let size = task.source.as_ref().map(|s| s.size).unwrap_or(0);
+
+                            if self.dry_run {
+                                if let Ok(mut changes) = dry_run_file_changes.lock() {
+                                    changes.push(FileChange {
+                                        path: task.dest_path.clone(),
+                                        action: ChangeAction::Move,
+                                        size,
+                                        transfer_bytes: 0,
+                                        would_use_delta: false,
+                                        would_compress: false,
+                                        skip_reason: None,
+                                    });
+                                }
+                            }
+
+                            if self.json {
+                                SyncEvent::Move {
+                                    from: from.clone(),
+                                    to: task.dest_path.clone(),
+                                    size,
+                                }
+                                .emit();
+                            }
+                        }
                     }
 
                     // Verification stats
@@ -1565,6 +1933,7 @@ impl<T: Transport + 'static> SyncEngine<T> {
                             SyncAction::Update => "update".to_string(),
                             SyncAction::Delete => "delete".to_string(),
                             SyncAction::Skip => "skip".to_string(),
+                            SyncAction::Move { .. } => "move".to_string(),
                         },
                     });
                     tracing::error!("Sync error for {}: {}", task.dest_path.display(), e);
@@ -1673,6 +2042,7 @@ impl<T: Transport + 'static> SyncEngine<T> {
                 files_updated: final_stats.files_updated as usize,
                 files_skipped: final_stats.files_skipped,
                 files_deleted: final_stats.files_deleted,
+                files_moved: final_stats.files_moved,
                 bytes_transferred: final_stats.bytes_transferred,
                 duration_secs: final_stats.duration.as_secs_f64(),
                 files_verified: final_stats.files_verified,
@@ -1856,11 +2226,14 @@ impl<T: Transport + 'static> SyncEngine<T> {
             files_updated: 0,
             files_skipped: 0,
             files_deleted: 0,
+            files_moved: 0,
             bytes_transferred: 0,
             files_delta_synced: 0,
             delta_bytes_saved: 0,
             files_compressed: 0,
             compression_bytes_saved: 0,
+            stream_bytes_raw: 0,
+            stream_bytes_wire: 0,
             files_verified: 0,
             verification_failures: 0,
             duration: Duration::ZERO,
@@ -1967,6 +2340,7 @@ impl<T: Transport + 'static> SyncEngine<T> {
                 let dry_run = self.dry_run;
                 let diff_mode = self.diff_mode;
                 let json = self.json;
+                let delta_stats = self.delta_stats;
                 // Clone other config fields...
                 let verification_mode = self.verification_mode;
                 let verify_on_write = self.verify_on_write;
@@ -2027,6 +2401,7 @@ impl<T: Transport + 'static> SyncEngine<T> {
                         SyncAction::Update => format!("Updating: {}", filename),
                         SyncAction::Skip => format!("Skipping: {}", filename),
                         SyncAction::Delete => format!("Deleting: {}", filename),
+                        SyncAction::Move { .. } => format!("Moving: {}", filename),
                     };
 
                     if !matches!(task.action, SyncAction::Skip) {
@@ -2171,6 +2546,10 @@ impl<T: Transport + 'static> SyncEngine<T> {
                                                 size: source.size,
                                                 bytes_transferred: bytes_written,
                                                 delta_used,
+                                                delta_stats: transfer_result
+                                                    .as_ref()
+                                                    .filter(|_| delta_stats)
+                                                    .and_then(output::DeltaReport::from_transfer),
                                             }
                                             .emit();
                                         }
@@ -2492,11 +2871,14 @@ impl<T: Transport + 'static> SyncEngine<T> {
             files_updated: 0,
             files_skipped: 0,
             files_deleted: 0,
+            files_moved: 0,
             bytes_transferred: 0,
             files_delta_synced: 0,
             delta_bytes_saved: 0,
             files_compressed: 0,
             compression_bytes_saved: 0,
+            stream_bytes_raw: 0,
+            stream_bytes_wire: 0,
             files_verified: 0,
             verification_failures: 0,
             duration: Duration::ZERO,
@@ -0,0 +1,223 @@
+//! Rename and move detection
+//!
+//! Pairs entries that disappeared from one path with entries that appeared at
+//! another, so a renamed directory becomes a handful of renames instead of a
+//! full re-copy followed by a delete.
+//!
+//! Matching is deliberately conservative:
+//! - Inode equality is authoritative (only set when both sides come from the
+//!   same local filesystem, e.g. bisync state history)
+//! - Otherwise size + mtime must match, and the pairing must be unambiguous
+//! - Callers confirm size+mtime matches by checksum before acting on them
+
+use crate::sync::scanner::FileEntry;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+/// Minimal identity of a file used for move matching
+#[derive(Debug, Clone)]
+pub struct MoveCandidate {
+    /// Path relative to the sync root
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: SystemTime,
+    /// Inode number, only when comparable with the other side
+    pub inode: Option<u64>,
+}
+
+impl MoveCandidate {
+    /// Build a candidate from a scanned entry (inode omitted)
+    pub fn from_entry(entry: &FileEntry) -> Self {
+        Self {
+            path: (*entry.relative_path).clone(),
+            size: entry.size,
+            modified: entry.modified,
+            inode: None,
+        }
+    }
+
+    /// Build a candidate from a scanned entry, keeping its inode
+    pub fn from_entry_with_inode(entry: &FileEntry) -> Self {
+        Self {
+            inode: entry.inode,
+            ..Self::from_entry(entry)
+        }
+    }
+}
+
+/// A pairing of a removed entry with an added entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MoveMatch {
+    /// Index into the `removed` slice
+    pub removed: usize,
+    /// Index into the `added` slice
+    pub added: usize,
+    /// Matched by inode (no content confirmation needed)
+    pub by_inode: bool,
+}
+
+/// Find likely moves between `removed` and `added` entries
+///
+/// Each entry is used at most once. Empty files never match (every empty
+/// file looks identical), and size+mtime matches with more than one
+/// possible partner are skipped rather than guessed.
+pub fn match_candidates(
+    removed: &[MoveCandidate],
+    added: &[MoveCandidate],
+    mtime_tolerance: Duration,
+) -> Vec<MoveMatch> {
+    let mut matches = Vec::new();
+    let mut removed_used = vec![false; removed.len()];
+    let mut added_used = vec![false; added.len()];
+
+    // Pass 1: inode matches are exact
+    let by_inode: HashMap<u64, usize> = removed
+        .iter()
+        .enumerate()
+        .filter_map(|(i, r)| r.inode.map(|ino| (ino, i)))
+        .collect();
+    for (ai, a) in added.iter().enumerate() {
+        if let Some(&ri) = a.inode.and_then(|ino| by_inode.get(&ino)) {
+            if !removed_used[ri] && removed[ri].size == a.size {
+                removed_used[ri] = true;
+                added_used[ai] = true;
+                matches.push(MoveMatch {
+                    removed: ri,
+                    added: ai,
+                    by_inode: true,
+                });
+            }
+        }
+    }
+
+    // Pass 2: size + mtime, unambiguous in both directions
+    let mut removed_by_size: HashMap<u64, Vec<usize>> = HashMap::new();
+    for (i, r) in removed.iter().enumerate() {
+        if !removed_used[i] && r.size > 0 {
+            removed_by_size.entry(r.size).or_default().push(i);
+        }
+    }
+
+    let mut proposals: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (ai, a) in added.iter().enumerate() {
+        if added_used[ai] || a.size == 0 {
+            continue;
+        }
+        let Some(same_size) = removed_by_size.get(&a.size) else {
+            continue;
+        };
+        let partners: Vec<usize> = same_size
+            .iter()
+            .copied()
+            .filter(|&ri| mtime_within(removed[ri].modified, a.modified, mtime_tolerance))
+            .collect();
+        if partners.len() == 1 {
+            proposals.entry(partners[0]).or_default().push(ai);
+        }
+    }
+
+    for (ri, added_indices) in proposals {
+        if added_indices.len() == 1 {
+            matches.push(MoveMatch {
+                removed: ri,
+                added: added_indices[0],
+                by_inode: false,
+            });
+        }
+    }
+
+    matches.sort_by_key(|m| m.added);
+    matches
+}
+
+fn mtime_within(a: SystemTime, b: SystemTime, tolerance: Duration) -> bool {
+    match a.duration_since(b) {
+        Ok(d) => d <= tolerance,
+        Err(e) => e.duration() <= tolerance,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(path: &str, size: u64, mtime_secs: u64, inode: Option<u64>) -> MoveCandidate {
+        MoveCandidate {
+            path: PathBuf::from(path),
+            size,
+            modified: SystemTime::UNIX_EPOCH + Duration::from_secs(mtime_secs),
+            inode,
+        }
+    }
+
+    #[test]
+    fn test_matches_by_size_and_mtime() {
+        let removed = vec![candidate("old/a.bin", 100, 1000, None)];
+        let added = vec![candidate("new/a.bin", 100, 1000, None)];
+
+        let matches = match_candidates(&removed, &added, Duration::from_secs(1));
+        assert_eq!(
+            matches,
+            vec![MoveMatch {
+                removed: 0,
+                added: 0,
+                by_inode: false
+            }]
+        );
+    }
+
+    #[test]
+    fn test_mtime_outside_tolerance_does_not_match() {
+        let removed = vec![candidate("old.bin", 100, 1000, None)];
+        let added = vec![candidate("new.bin", 100, 1005, None)];
+
+        assert!(match_candidates(&removed, &added, Duration::from_secs(1)).is_empty());
+    }
+
+    #[test]
+    fn test_ambiguous_matches_are_skipped() {
+        let removed = vec![
+            candidate("a.bin", 100, 1000, None),
+            candidate("b.bin", 100, 1000, None),
+        ];
+        let added = vec![candidate("c.bin", 100, 1000, None)];
+
+        assert!(match_candidates(&removed, &added, Duration::from_secs(1)).is_empty());
+
+        let removed = vec![candidate("a.bin", 100, 1000, None)];
+        let added = vec![
+            candidate("b.bin", 100, 1000, None),
+            candidate("c.bin", 100, 1000, None),
+        ];
+
+        assert!(match_candidates(&removed, &added, Duration::from_secs(1)).is_empty());
+    }
+
+    #[test]
+    fn test_empty_files_never_match() {
+        let removed = vec![candidate("a.txt", 0, 1000, None)];
+        let added = vec![candidate("b.txt", 0, 1000, None)];
+
+        assert!(match_candidates(&removed, &added, Duration::from_secs(1)).is_empty());
+    }
+
+    #[test]
+    fn test_inode_match_resolves_ambiguity() {
+        let removed = vec![
+            candidate("a.bin", 100, 1000, Some(7)),
+            candidate("b.bin", 100, 1000, Some(8)),
+        ];
+        let added = vec![candidate("c.bin", 100, 1000, Some(8))];
+
+        let matches = match_candidates(&removed, &added, Duration::from_secs(1));
+        assert_eq!(
+            matches,
+            vec![MoveMatch {
+                removed: 1,
+                added: 0,
+                by_inode: true
+            }]
+        );
+    }
+}
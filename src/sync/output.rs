@@ -1,6 +1,27 @@
+use crate::transport::TransferResult;
 use serde::Serialize;
 use std::path::PathBuf;
 
+/// How a delta-synced file was rebuilt (--delta-stats)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct DeltaReport {
+    /// Bytes reused from the destination's copy
+    pub matched_bytes: u64,
+    /// Bytes sent as literal data
+    pub literal_bytes: u64,
+}
+
+impl DeltaReport {
+    /// Report for a transfer, if it used delta sync
+    pub fn from_transfer(result: &TransferResult) -> Option<Self> {
+        let literal_bytes = result.literal_bytes.filter(|_| result.used_delta())?;
+        Some(Self {
+            matched_bytes: result.bytes_written.saturating_sub(literal_bytes),
+            literal_bytes,
+        })
+    }
+}
+
 /// JSON output mode for machine-readable sync events
 /// Uses NDJSON format (newline-delimited JSON)
 #[derive(Debug, Serialize)]
@@ -21,6 +42,9 @@ pub enum SyncEvent {
         size: u64,
         bytes_transferred: u64,
         delta_used: bool,
+        /// Matched and literal bytes of the delta (--delta-stats)
+        #[serde(skip_serializing_if = "Option::is_none")]
+        delta_stats: Option<DeltaReport>,
     },
     Skip {
         path: PathBuf,
@@ -29,6 +53,11 @@ pub enum SyncEvent {
     Delete {
         path: PathBuf,
     },
+    Move {
+        from: PathBuf,
+        to: PathBuf,
+        size: u64,
+    },
     #[allow(dead_code)] // Event for error reporting
     Error {
         path: PathBuf,
@@ -39,6 +68,7 @@ pub enum SyncEvent {
         files_updated: usize,
         files_skipped: usize,
         files_deleted: usize,
+        files_moved: usize,
         bytes_transferred: u64,
         duration_secs: f64,
         files_verified: usize,
@@ -126,6 +156,7 @@ mod tests {
             size: 5678,
             bytes_transferred: 234,
             delta_used: true,
+            delta_stats: None,
         };
 
         let json = serde_json::to_string(&event).unwrap();
@@ -133,6 +164,36 @@ mod tests {
         assert!(json.contains(r#""delta_used":true"#));
     }
 
+    #[test]
+    fn test_serialize_update_event_with_delta_stats() {
+        let transfer = TransferResult::with_delta(5678, 3, 234);
+        let event = SyncEvent::Update {
+            path: PathBuf::from("file.txt"),
+            size: 5678,
+            bytes_transferred: 5678,
+            delta_used: true,
+            delta_stats: DeltaReport::from_transfer(&transfer),
+        };
+
+        let json = serde_json::to_string(&event).unwrap();
+        assert!(json.contains(r#""delta_stats":{"matched_bytes":5444,"literal_bytes":234}"#));
+        assert_eq!(DeltaReport::from_transfer(&TransferResult::new(5678)), None);
+    }
+
+    #[test]
+    fn test_serialize_move_event() {
+        let event = SyncEvent::Move {
+            from: PathBuf::from("old/file.txt"),
+            to: PathBuf::from("new/file.txt"),
+            size: 42,
+        };
+
+        let json = serde_json::to_string(&event).unwrap();
+        assert!(json.contains(r#""type":"move"#));
+        assert!(json.contains(r#""from":"old/file.txt"#));
+        assert!(json.contains(r#""to":"new/file.txt"#));
+    }
+
     #[test]
     fn test_serialize_summary_event() {
         let event = SyncEvent::Summary {
@@ -140,6 +201,7 @@ mod tests {
             files_updated: 5,
             files_skipped: 20,
             files_deleted: 2,
+            files_moved: 1,
             bytes_transferred: 123456,
             duration_secs: 12.5,
             files_verified: 15,
@@ -210,7 +210,15 @@ fn should_use_parallel(root: &Path) -> bool {
 /// Process a directory entry into a FileEntry
 /// Extracted to share between sequential and parallel scanners
 fn process_dir_entry(root: &Path, entry: ignore::DirEntry) -> Result<FileEntry> {
-    let path = entry.path().to_path_buf();
+    scan_entry(root, entry.path())
+}
+
+/// Build the FileEntry for a single path below `root`
+///
+/// Used by incremental syncs that re-examine individual changed paths
+/// instead of walking the whole tree.
+pub fn scan_entry(root: &Path, path: &Path) -> Result<FileEntry> {
+    let path = path.to_path_buf();
 
     // Use symlink_metadata to properly detect symlinks
     // entry.metadata() follows symlinks by default, making is_symlink() always false
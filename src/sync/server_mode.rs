@@ -4,20 +4,37 @@ use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Instant;
 
-use crate::compress::{compress, is_compressed_extension, Compression};
-use crate::delta::{generate_delta_streaming, BlockChecksum as DeltaBlockChecksum};
+use crate::compress::is_compressed_extension;
+use crate::delta::cdc::{self, ChunkParams};
+use crate::delta::{
+    generate_delta_streaming, BlockChecksum as DeltaBlockChecksum, BlockSize, BlockSizePolicy,
+    Delta, DeltaAlgo,
+};
+use crate::integrity::ChecksumType;
 use crate::path::SyncPath;
+use crate::server::confine::{self, Confinement};
+use crate::server::digest::{self, Verdict};
+use crate::server::layout::{self, Chunk, ChunkPlan, HardlinkGroups};
+use crate::server::meta::{self, MetaOptions};
+use crate::server::partial;
 use crate::server::protocol::{
-    delta_block_size, Action, Decision, DeltaOp, FileListEntry, SymlinkEntry, DATA_FLAG_COMPRESSED,
-    DELTA_MIN_SIZE,
+    Action, ChecksumReq, ChecksumResp, Decision, DeltaOp, EntryMeta, FileListEntry, SymlinkEntry,
+    CAP_COMPRESS_ZSTD, CAP_DELTA_BLOCK, CAP_DELTA_CDC, CAP_FILE_CHECKSUM, CAP_FUZZY, CAP_HARDLINKS,
+    CAP_META_BLOCK, CAP_RESUME, CAP_SPARSE, CAP_STREAM_ZSTD, CAP_SYMLINKS, DATA_FLAG_FINAL,
+    DELTA_MIN_SIZE, FLAG_FUZZY, STATUS_CHECKSUM_MISMATCH, STATUS_OK, VERIFY_RETRIES,
 };
 use crate::ssh::config::SshConfig;
+use crate::sync::incremental::ChangeSet;
 use crate::sync::live_progress::ProgressState;
 use crate::sync::scanner::{self, ScanOptions};
 use crate::sync::{
     ChangeAction, DirectoryChange, DryRunDetails, FileChange, SymlinkChange, SyncStats,
 };
-use crate::transport::server::ServerSession;
+#[cfg(unix)]
+use crate::transport::server::DaemonSession;
+use crate::transport::server::{
+    PullSession, PushSession, RemoteShell, ServerSession, SessionOptions,
+};
 
 /// Minimum size for compression (1MB)
 const COMPRESS_MIN_SIZE: u64 = 1024 * 1024;
@@ -25,7 +42,31 @@ const COMPRESS_MIN_SIZE: u64 = 1024 * 1024;
 /// Number of delta checksum requests to pipeline before reading responses
 const PIPELINE_DEPTH: usize = 8;
 
+/// A computed delta: (index, ops, literal bytes, digest of the source)
+type FileDelta = (u32, Vec<DeltaOp>, u64, Vec<u8>);
+
+/// How files are encoded and verified over a server or daemon session, and
+/// what each session is asked for (-H, --verify, --delta-algo, --block-size,
+/// --checksum-db, --fuzzy, -z, --safe-links)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TransferOptions {
+    /// Send hard links as links to their first path (-H)
+    pub hardlinks: bool,
+    /// Whole-file digest each transferred file is verified with
+    pub verify: ChecksumType,
+    pub delta: DeltaAlgo,
+    /// Block size policy for fixed-block deltas
+    pub block_size: BlockSize,
+    /// Let the receiver cache CDC chunk lists in its checksum database
+    pub chunk_cache: bool,
+    /// Let the receiver pick a similar file as the basis for new files
+    pub fuzzy: bool,
+    /// Stream compression and symlink policy asked of each session
+    pub session: SessionOptions,
+}
+
 /// Source entry with all info needed for transfer
+#[derive(Clone)]
 struct SourceEntry {
     rel_path: String,
     abs_path: Arc<PathBuf>,
@@ -35,6 +76,11 @@ struct SourceEntry {
     is_dir: bool,
     is_symlink: bool,
     symlink_target: Option<String>,
+    meta: Option<EntryMeta>,
+    /// Inode and link count, only recorded with -H
+    inode: Option<u64>,
+    nlink: u64,
+    sparse: bool,
 }
 
 /// Sync from local source to remote destination using server protocol
@@ -42,9 +88,11 @@ pub async fn sync_server_mode(
     source: &Path,
     dest: &SyncPath,
     dry_run: bool,
+    meta: MetaOptions,
+    transfer: TransferOptions,
     progress: Option<Arc<ProgressState>>,
 ) -> Result<SyncStats> {
-    sync_server_mode_with_config(source, dest, dry_run, progress, None).await
+    sync_server_mode_with_config(source, dest, dry_run, meta, transfer, progress, None).await
 }
 
 /// Sync from local source to remote destination using server protocol with optional SSH config
@@ -52,18 +100,79 @@ pub async fn sync_server_mode_with_config(
     source: &Path,
     dest: &SyncPath,
     dry_run: bool,
+    meta: MetaOptions,
+    transfer: TransferOptions,
     progress: Option<Arc<ProgressState>>,
     ssh_config: Option<&SshConfig>,
 ) -> Result<SyncStats> {
     let start = Instant::now();
 
     // Connect to server
-    let mut session = connect_with_config(dest, ssh_config).await?;
+    let mut session = connect_with_config(dest, ssh_config, transfer.session).await?;
     tracing::debug!("Connected to server (dry_run: {})", dry_run);
 
     // Scan source
     tracing::debug!("Scanning source...");
-    let source_entries = scan_source(source).await?;
+    let source_entries = scan_source(source, meta, transfer).await?;
+
+    push_entries(
+        &mut session,
+        source_entries,
+        dry_run,
+        transfer,
+        progress,
+        start,
+    )
+    .await
+}
+
+/// Push scanned source entries over an open session
+///
+/// Directories go out as one MKDIR_BATCH, files as a FILE_LIST whose
+/// decisions drive full (creates, small updates) or delta transfers, and
+/// symlinks as a final SYMLINK_BATCH.
+///
+/// With `transfer.verify` and CAP_FILE_CHECKSUM each file carries its digest,
+/// checked by the receiver before the rename; files that fail are sent again
+/// whole. `transfer.delta` picks fixed blocks or CDC chunks (CAP_DELTA_CDC) for
+/// delta updates. With `transfer.fuzzy` (CAP_FUZZY) new files the receiver finds
+/// a similar basis for are delta-encoded against it like updates.
+async fn push_entries<S: PushSession + ?Sized>(
+    session: &mut S,
+    source_entries: Vec<SourceEntry>,
+    dry_run: bool,
+    transfer: TransferOptions,
+    progress: Option<Arc<ProgressState>>,
+    start: Instant,
+) -> Result<SyncStats> {
+    // Older servers may lack some features; fall back to what both sides speak
+    let features = session.negotiated();
+    let can_compress = features.has(CAP_COMPRESS_ZSTD) && !features.has(CAP_STREAM_ZSTD);
+    let can_delta = features.has(CAP_DELTA_BLOCK);
+    let can_meta = features.has(CAP_META_BLOCK);
+    let can_sparse = features.has(CAP_SPARSE);
+    let can_resume = features.has(CAP_RESUME);
+    let block_sizes = BlockSizePolicy::new(transfer.block_size);
+    let cdc = transfer.delta == DeltaAlgo::Cdc && features.has(CAP_DELTA_CDC);
+    if transfer.delta == DeltaAlgo::Cdc && !cdc {
+        tracing::debug!("Server lacks CDC delta, using fixed blocks");
+    }
+    let fuzzy = transfer.fuzzy && features.has(CAP_FUZZY);
+    if transfer.fuzzy && !fuzzy {
+        tracing::debug!("Server lacks fuzzy basis selection, sending new files whole");
+    }
+    let verify = if features.has(CAP_FILE_CHECKSUM) {
+        transfer.verify
+    } else {
+        ChecksumType::None
+    };
+    let plan = ChunkPlan {
+        sparse: can_sparse,
+        split: layout::final_chunks(features),
+        digest: verify,
+        ..Default::default()
+    };
+    let stream_before = session.stream_stats();
 
     // Separate entries by type
     let mut directories: Vec<String> = Vec::new();
@@ -80,16 +189,22 @@ pub async fn sync_server_mode_with_config(
         }
     }
 
+    // Later links to an inode already in the list are sent as links, not data
+    let links = hardlink_leaders(&files, features.has(CAP_HARDLINKS));
+
     // Build protocol entries (files only for FILE_LIST comparison)
     let proto_entries: Vec<FileListEntry> = files
         .iter()
-        .map(|e| FileListEntry {
+        .zip(&links)
+        .map(|(e, link)| FileListEntry {
             path: e.rel_path.clone(),
             size: e.size,
             mtime: e.mtime,
             mode: e.mode,
-            flags: 0,
+            flags: if fuzzy { FLAG_FUZZY } else { 0 },
             symlink_target: None,
+            hardlink_to: *link,
+            meta: e.meta.clone().filter(|_| can_meta),
         })
         .collect();
 
@@ -142,6 +257,14 @@ pub async fn sync_server_mode_with_config(
     tracing::debug!("Waiting for server decisions...");
     let ack = session.read_ack().await?;
 
+    // Partials the receiver kept from an interrupted run (CAP_RESUME)
+    let resumes: HashMap<u32, u64> = ack
+        .decisions
+        .iter()
+        .filter(|d| can_resume && d.resume_from > 0)
+        .map(|d| (d.index, d.resume_from))
+        .collect();
+
     // Count files needing transfer
     let files_to_transfer = ack
         .decisions
@@ -175,12 +298,39 @@ pub async fn sync_server_mode_with_config(
     let mut bytes_would_add = 0u64;
     let mut bytes_would_change = 0u64;
 
-    // Categorize by action type
-    let creates: Vec<(u32, &SourceEntry)> = ack
+    // Files the receiver failed to verify, to send again whole
+    let mut mismatched: Vec<(u32, Action)> = Vec::new();
+
+    // Holes cost nothing as sparse chunks, so sparse files skip delta;
+    // so do files continuing from a partial
+    let delta_ok = |idx: &u32, e: &SourceEntry| {
+        can_delta
+            && e.size >= DELTA_MIN_SIZE
+            && !(can_sparse && e.sparse)
+            && !resumes.contains_key(idx)
+    };
+
+    // Categorize by action type; new files with a basis (--fuzzy) are
+    // delta-encoded along with the updates
+    let mut creates: Vec<(u32, &SourceEntry)> = Vec::new();
+    let mut based: Vec<(u32, &SourceEntry)> = Vec::new();
+    for d in &ack.decisions {
+        if d.action != Action::Create || links[d.index as usize].is_some() {
+            continue;
+        }
+        let entry = &files[d.index as usize];
+        if d.basis.is_some() && delta_ok(&d.index, entry) {
+            based.push((d.index, entry));
+        } else {
+            creates.push((d.index, entry));
+        }
+    }
+
+    let updates: Vec<(u32, &SourceEntry)> = ack
         .decisions
         .iter()
         .filter_map(|d| {
-            if d.action == Action::Create {
+            if d.action == Action::Update && links[d.index as usize].is_none() {
                 Some((d.index, &files[d.index as usize]))
             } else {
                 None
@@ -188,23 +338,22 @@ pub async fn sync_server_mode_with_config(
         })
         .collect();
 
-    let updates: Vec<(u32, &SourceEntry)> = ack
+    let hardlinks: Vec<(u32, Action, &SourceEntry)> = ack
         .decisions
         .iter()
-        .filter_map(|d| {
-            if d.action == Action::Update {
-                Some((d.index, &files[d.index as usize]))
-            } else {
-                None
-            }
-        })
+        .filter(|d| d.action != Action::Skip && links[d.index as usize].is_some())
+        .map(|d| (d.index, d.action, &files[d.index as usize]))
         .collect();
 
     // Initialize progress tracking if provided
     if let Some(ref progress) = progress {
-        let total_bytes: u64 = creates.iter().map(|(_, e)| e.size).sum::<u64>()
-            + updates.iter().map(|(_, e)| e.size).sum::<u64>();
-        let total_transfers = creates.len() + updates.len();
+        let total_bytes: u64 = creates
+            .iter()
+            .chain(&based)
+            .chain(&updates)
+            .map(|(_, e)| e.size)
+            .sum();
+        let total_transfers = creates.len() + based.len() + updates.len();
         progress.set_totals(total_bytes, total_transfers);
     }
 
@@ -217,8 +366,9 @@ pub async fn sync_server_mode_with_config(
                 bytes_would_add += entry.size;
 
                 // Track detailed file change
-                let would_compress =
-                    entry.size >= COMPRESS_MIN_SIZE && !is_compressed_extension(&entry.rel_path);
+                let would_compress = can_compress
+                    && entry.size >= COMPRESS_MIN_SIZE
+                    && !is_compressed_extension(&entry.rel_path);
                 file_changes.push(FileChange {
                     path: PathBuf::from(&entry.rel_path),
                     action: ChangeAction::Create,
@@ -232,43 +382,15 @@ pub async fn sync_server_mode_with_config(
         } else {
             tracing::debug!("Transferring {} new files...", creates.len());
 
-            let paths: Vec<(u32, Arc<PathBuf>, String, u64)> = creates
-                .iter()
-                .map(|(idx, e)| (*idx, e.abs_path.clone(), e.rel_path.clone(), e.size))
-                .collect();
-
-            // Read and optionally compress files
-            let files_data: Vec<(u32, Vec<u8>, u8)> = tokio::task::spawn_blocking(move || {
-                paths
-                    .into_iter()
-                    .filter_map(|(idx, path, rel_path, size)| {
-                        std::fs::read(&*path).ok().map(|data| {
-                            // Compress if file is large enough and not already compressed
-                            let (send_data, flags) = if size >= COMPRESS_MIN_SIZE
-                                && !is_compressed_extension(&rel_path)
-                            {
-                                match compress(&data, Compression::Zstd) {
-                                    Ok(compressed) if compressed.len() < data.len() => {
-                                        (compressed, DATA_FLAG_COMPRESSED)
-                                    }
-                                    _ => (data, 0),
-                                }
-                            } else {
-                                (data, 0)
-                            };
-                            (idx, send_data, flags)
-                        })
-                    })
-                    .collect()
-            })
-            .await?;
+            // Read (hole-aware) and optionally compress files
+            let files_data = read_chunks(&creates, plan, can_compress, &resumes).await?;
 
             // Send all creates - track progress for each file
             // We need to map idx back to original entry for progress tracking
             let idx_to_entry: HashMap<u32, &SourceEntry> =
                 creates.iter().map(|(idx, e)| (*idx, *e)).collect();
 
-            for (idx, data, flags) in &files_data {
+            for (idx, chunks) in files_data.iter() {
                 // Start transfer progress
                 if let Some(ref progress) = progress {
                     if let Some(entry) = idx_to_entry.get(idx) {
@@ -276,43 +398,63 @@ pub async fn sync_server_mode_with_config(
                     }
                 }
 
-                bytes_transferred += data.len() as u64;
-                session
-                    .send_file_data_with_flags(*idx, 0, *flags, data.clone())
-                    .await?;
+                bytes_transferred += send_chunks(session, *idx, chunks).await?;
             }
             session.flush().await?;
 
             // Read confirmations
-            for (idx, _, _) in &files_data {
+            for (idx, chunks) in &files_data {
                 let done = session.read_file_done().await?;
-                if done.status != 0 {
-                    tracing::error!("Create failed: index {} status {}", done.index, done.status);
-                } else {
-                    files_created += 1;
-                    // Finish transfer progress
-                    if let Some(ref progress) = progress {
-                        if let Some(entry) = idx_to_entry.get(idx) {
-                            progress.finish_transfer(&PathBuf::from(&entry.rel_path), entry.size);
+                match digest::verdict(&done, layout::sent_digest(chunks)) {
+                    Verdict::Ok => {
+                        files_created += 1;
+                        // Finish transfer progress
+                        if let Some(ref progress) = progress {
+                            if let Some(entry) = idx_to_entry.get(idx) {
+                                progress
+                                    .finish_transfer(&PathBuf::from(&entry.rel_path), entry.size);
+                            }
                         }
                     }
+                    Verdict::Mismatch => mismatched.push((*idx, Action::Create)),
+                    Verdict::Failed => {
+                        tracing::error!(
+                            "Create failed: index {} status {}",
+                            done.index,
+                            done.status
+                        );
+                    }
                 }
             }
         }
     }
 
-    // Step 3b: Handle UPDATES - use delta sync for large files
-    if !updates.is_empty() {
+    // Step 3b: Handle UPDATES (and creates with a basis) - use delta sync for large files
+    if !updates.is_empty() || !based.is_empty() {
         if dry_run {
+            for (_, entry) in &based {
+                files_created += 1;
+                bytes_would_add += entry.size;
+                file_changes.push(FileChange {
+                    path: PathBuf::from(&entry.rel_path),
+                    action: ChangeAction::Create,
+                    size: entry.size,
+                    transfer_bytes: entry.size / 2,
+                    would_use_delta: true,
+                    would_compress: false,
+                    skip_reason: None,
+                });
+            }
             tracing::debug!("[DRY-RUN] Would update {} files", updates.len());
             for (_, entry) in &updates {
                 files_updated += 1;
                 bytes_would_change += entry.size;
 
                 // Track detailed file change
-                let would_use_delta = entry.size >= DELTA_MIN_SIZE;
-                let would_compress =
-                    entry.size >= COMPRESS_MIN_SIZE && !is_compressed_extension(&entry.rel_path);
+                let would_use_delta = can_delta && entry.size >= DELTA_MIN_SIZE;
+                let would_compress = can_compress
+                    && entry.size >= COMPRESS_MIN_SIZE
+                    && !is_compressed_extension(&entry.rel_path);
                 // For dry-run, estimate transfer bytes (conservative: assume 50% for delta)
                 let transfer_bytes = if would_use_delta {
                     entry.size / 2
@@ -330,8 +472,17 @@ pub async fn sync_server_mode_with_config(
                 });
             }
         } else {
-            let (delta_candidates, full_updates): (Vec<_>, Vec<_>) =
-                updates.iter().partition(|(_, e)| e.size >= DELTA_MIN_SIZE);
+            let (delta_updates, full_updates): (Vec<_>, Vec<_>) =
+                updates.iter().partition(|(idx, e)| delta_ok(idx, e));
+            let delta_candidates: Vec<(u32, &SourceEntry, Action)> = based
+                .iter()
+                .map(|(idx, e)| (*idx, *e, Action::Create))
+                .chain(
+                    delta_updates
+                        .iter()
+                        .map(|(idx, e)| (*idx, *e, Action::Update)),
+                )
+                .collect();
 
             // Process delta candidates with pipelined checksum requests
             if !delta_candidates.is_empty() {
@@ -343,23 +494,41 @@ pub async fn sync_server_mode_with_config(
                 );
 
                 // Collect pending requests for batching
-                let mut pending: Vec<(u32, &SourceEntry, u32)> = Vec::with_capacity(PIPELINE_DEPTH);
-
-                for (idx, entry) in &delta_candidates {
-                    let file_idx = *idx;
-                    let block_size = delta_block_size(entry.size);
+                let mut pending: Vec<(u32, &SourceEntry, Action, ChecksumReq)> =
+                    Vec::with_capacity(PIPELINE_DEPTH);
+
+                for (idx, entry, action) in &delta_candidates {
+                    let req = if cdc {
+                        ChecksumReq {
+                            index: *idx,
+                            block_size: cdc::avg_chunk_size(entry.size),
+                            algo: DeltaAlgo::Cdc,
+                            cached: transfer.chunk_cache,
+                        }
+                    } else {
+                        ChecksumReq::block(
+                            *idx,
+                            block_sizes.block_size(&entry.abs_path, entry.size) as u32,
+                        )
+                    };
 
                     // Send checksum request without waiting (no flush)
-                    session
-                        .send_checksum_req_no_flush(file_idx, block_size)
-                        .await?;
-                    pending.push((file_idx, *entry, block_size));
+                    session.send_checksum_req_no_flush(req).await?;
+                    pending.push((*idx, *entry, *action, req));
 
                     // Process batch when full
                     if pending.len() >= PIPELINE_DEPTH {
                         session.flush().await?;
-                        let (updated, transferred) =
-                            process_delta_batch(&mut session, &pending, progress.as_ref()).await?;
+                        let (created, updated, transferred) = process_delta_batch(
+                            session,
+                            &pending,
+                            verify,
+                            &block_sizes,
+                            &mut mismatched,
+                            progress.as_ref(),
+                        )
+                        .await?;
+                        files_created += created;
                         files_updated += updated;
                         bytes_transferred += transferred;
                         pending.clear();
@@ -369,8 +538,16 @@ pub async fn sync_server_mode_with_config(
                 // Process remaining files
                 if !pending.is_empty() {
                     session.flush().await?;
-                    let (updated, transferred) =
-                        process_delta_batch(&mut session, &pending, progress.as_ref()).await?;
+                    let (created, updated, transferred) = process_delta_batch(
+                        session,
+                        &pending,
+                        verify,
+                        &block_sizes,
+                        &mut mismatched,
+                        progress.as_ref(),
+                    )
+                    .await?;
+                    files_created += created;
                     files_updated += updated;
                     bytes_transferred += transferred;
                 }
@@ -383,40 +560,13 @@ pub async fn sync_server_mode_with_config(
                     full_updates.len()
                 );
 
-                let paths: Vec<(u32, Arc<PathBuf>, String, u64)> = full_updates
-                    .iter()
-                    .map(|(idx, e)| (*idx, e.abs_path.clone(), e.rel_path.clone(), e.size))
-                    .collect();
-
-                let files_data: Vec<(u32, Vec<u8>, u8)> = tokio::task::spawn_blocking(move || {
-                    paths
-                        .into_iter()
-                        .filter_map(|(idx, path, rel_path, size)| {
-                            std::fs::read(&*path).ok().map(|data| {
-                                let (send_data, flags) = if size >= COMPRESS_MIN_SIZE
-                                    && !is_compressed_extension(&rel_path)
-                                {
-                                    match compress(&data, Compression::Zstd) {
-                                        Ok(compressed) if compressed.len() < data.len() => {
-                                            (compressed, DATA_FLAG_COMPRESSED)
-                                        }
-                                        _ => (data, 0),
-                                    }
-                                } else {
-                                    (data, 0)
-                                };
-                                (idx, send_data, flags)
-                            })
-                        })
-                        .collect()
-                })
-                .await?;
+                let files_data = read_chunks(&full_updates, plan, can_compress, &resumes).await?;
 
                 // Map idx to entry for progress tracking
                 let idx_to_entry: HashMap<u32, &SourceEntry> =
                     full_updates.iter().map(|(idx, e)| (*idx, *e)).collect();
 
-                for (idx, data, flags) in &files_data {
+                for (idx, chunks) in files_data.iter() {
                     // Start transfer progress
                     if let Some(ref progress) = progress {
                         if let Some(entry) = idx_to_entry.get(idx) {
@@ -424,39 +574,99 @@ pub async fn sync_server_mode_with_config(
                         }
                     }
 
-                    bytes_transferred += data.len() as u64;
-                    session
-                        .send_file_data_with_flags(*idx, 0, *flags, data.clone())
-                        .await?;
+                    bytes_transferred += send_chunks(session, *idx, chunks).await?;
                 }
                 session.flush().await?;
 
-                for (idx, _, _) in &files_data {
+                for (idx, chunks) in &files_data {
                     let done = session.read_file_done().await?;
-                    if done.status != 0 {
-                        tracing::error!(
-                            "Update failed: index {} status {}",
-                            done.index,
-                            done.status
-                        );
-                    } else {
-                        files_updated += 1;
-                        // Finish transfer progress
-                        if let Some(ref progress) = progress {
-                            if let Some(entry) = idx_to_entry.get(idx) {
-                                progress
-                                    .finish_transfer(&PathBuf::from(&entry.rel_path), entry.size);
+                    match digest::verdict(&done, layout::sent_digest(chunks)) {
+                        Verdict::Ok => {
+                            files_updated += 1;
+                            // Finish transfer progress
+                            if let Some(ref progress) = progress {
+                                if let Some(entry) = idx_to_entry.get(idx) {
+                                    progress.finish_transfer(
+                                        &PathBuf::from(&entry.rel_path),
+                                        entry.size,
+                                    );
+                                }
                             }
                         }
+                        Verdict::Mismatch => mismatched.push((*idx, Action::Update)),
+                        Verdict::Failed => {
+                            tracing::error!(
+                                "Update failed: index {} status {}",
+                                done.index,
+                                done.status
+                            );
+                        }
                     }
                 }
             }
         }
     }
 
+    // Step 3c: Send files that failed verification again, whole
+    if !mismatched.is_empty() {
+        let (created, updated, transferred) =
+            resend_mismatched(session, &files, mismatched, plan, can_compress).await?;
+        files_created += created;
+        files_updated += updated;
+        bytes_transferred += transferred;
+    }
+
+    // Step 3d: Hard links, once every file they point at has been written
+    if !hardlinks.is_empty() {
+        if dry_run {
+            for (_, action, entry) in &hardlinks {
+                let action = if *action == Action::Create {
+                    files_created += 1;
+                    ChangeAction::Create
+                } else {
+                    files_updated += 1;
+                    ChangeAction::Update
+                };
+                file_changes.push(FileChange {
+                    path: PathBuf::from(&entry.rel_path),
+                    action,
+                    size: entry.size,
+                    transfer_bytes: 0,
+                    would_use_delta: false,
+                    would_compress: false,
+                    skip_reason: None,
+                });
+            }
+        } else {
+            tracing::debug!("Linking {} hard links...", hardlinks.len());
+            for (idx, _, _) in &hardlinks {
+                session
+                    .send_file_data_with_flags(*idx, 0, DATA_FLAG_FINAL, Vec::new())
+                    .await?;
+            }
+            session.flush().await?;
+
+            for (_, action, _) in &hardlinks {
+                let done = session.read_file_done().await?;
+                if done.status != 0 {
+                    tracing::error!("Link failed: index {} status {}", done.index, done.status);
+                } else if *action == Action::Create {
+                    files_created += 1;
+                } else {
+                    files_updated += 1;
+                }
+            }
+        }
+    }
+
     // Step 4: Create symlinks (if any)
     let mut symlinks_created = 0u64;
-    if !symlinks.is_empty() {
+    if !symlinks.is_empty() && !features.has(CAP_SYMLINKS) {
+        tracing::warn!(
+            "Server does not support symlinks; skipping {}",
+            symlinks.len()
+        );
+    } else if !symlinks.is_empty() {
         if dry_run {
             tracing::debug!("[DRY-RUN] Would create {} symlinks", symlinks.len());
             symlinks_created = symlinks.len() as u64;
@@ -537,11 +747,13 @@ pub async fn sync_server_mode_with_config(
         None
     };
 
+    let stream = session.stream_stats().since(stream_before);
     Ok(SyncStats {
         files_scanned: total_files as u64,
         files_created,
         files_updated,
         files_deleted: 0,
+        files_moved: 0,
         files_skipped: (total_files - files_to_transfer) as usize,
         bytes_transferred,
         duration,
@@ -552,22 +764,188 @@ pub async fn sync_server_mode_with_config(
         bytes_would_change,
         bytes_would_delete: 0,
         dry_run_details,
+        stream_bytes_raw: stream.raw_bytes,
+        stream_bytes_wire: stream.wire_bytes,
         ..Default::default()
     })
 }
 
+/// For each file, the index of an earlier file it is a hard link to
+fn hardlink_leaders(files: &[SourceEntry], enabled: bool) -> Vec<Option<u32>> {
+    let mut groups = HardlinkGroups::new();
+    files
+        .iter()
+        .enumerate()
+        .map(|(idx, e)| {
+            enabled
+                .then(|| groups.leader(e.inode, e.nlink, idx as u32))
+                .flatten()
+        })
+        .collect()
+}
+
+/// Send files the receiver failed to verify again whole (no delta, no
+/// partial), for up to `VERIFY_RETRIES` rounds
+///
+/// Returns the files created, files updated and bytes sent for the retries.
+async fn resend_mismatched<S: PushSession + ?Sized>(
+    session: &mut S,
+    files: &[SourceEntry],
+    mut mismatched: Vec<(u32, Action)>,
+    plan: ChunkPlan,
+    can_compress: bool,
+) -> Result<(u64, u64, u64)> {
+    let (mut created, mut updated, mut transferred) = (0u64, 0u64, 0u64);
+    for round in 1..=VERIFY_RETRIES {
+        if mismatched.is_empty() {
+            break;
+        }
+        tracing::warn!(
+            "Resending {} files that failed verification (round {})",
+            mismatched.len(),
+            round
+        );
+        let actions: HashMap<u32, Action> = mismatched.iter().copied().collect();
+        let entries: Vec<(u32, &SourceEntry)> = mismatched
+            .iter()
+            .map(|(idx, _)| (*idx, &files[*idx as usize]))
+            .collect();
+        let files_data = read_chunks(&entries, plan, can_compress, &HashMap::new()).await?;
+        for (idx, chunks) in &files_data {
+            transferred += send_chunks(session, *idx, chunks).await?;
+        }
+        session.flush().await?;
+
+        mismatched.clear();
+        for (idx, chunks) in &files_data {
+            let done = session.read_file_done().await?;
+            match digest::verdict(&done, layout::sent_digest(chunks)) {
+                Verdict::Ok if actions[idx] == Action::Create => created += 1,
+                Verdict::Ok => updated += 1,
+                Verdict::Mismatch => mismatched.push((*idx, actions[idx])),
+                Verdict::Failed => {
+                    tracing::error!("Resend failed: index {} status {}", done.index, done.status);
+                }
+            }
+        }
+    }
+    for (idx, _) in &mismatched {
+        tracing::error!(
+            "{} failed verification after {} retries",
+            files[*idx as usize].rel_path,
+            VERIFY_RETRIES
+        );
+    }
+    Ok((created, updated, transferred))
+}
+
+/// Read files as FILE_DATA chunks off the runtime, skipping unreadable ones
+///
+/// Large files without a compressed extension are zstd-compressed when the
+/// peer takes compressed chunks; sparse files are read hole-aware when `base`
+/// allows it. Files in `resumes` continue from the receiver's partial.
+async fn read_chunks(
+    entries: &[(u32, &SourceEntry)],
+    base: ChunkPlan,
+    can_compress: bool,
+    resumes: &HashMap<u32, u64>,
+) -> Result<Vec<(u32, Vec<Chunk>)>> {
+    let paths: Vec<(u32, Arc<PathBuf>, ChunkPlan)> = entries
+        .iter()
+        .map(|(idx, e)| {
+            let sparse = base.sparse && e.sparse;
+            let plan = ChunkPlan {
+                sparse,
+                zstd: can_compress
+                    && e.size >= COMPRESS_MIN_SIZE
+                    && !is_compressed_extension(&e.rel_path),
+                // A sparse partial is already full length; send it again
+                resume_from: if sparse {
+                    0
+                } else {
+                    resumes.get(idx).copied().unwrap_or(0)
+                },
+                ..base
+            };
+            (*idx, e.abs_path.clone(), plan)
+        })
+        .collect();
+
+    Ok(tokio::task::spawn_blocking(move || {
+        paths
+            .into_iter()
+            .filter_map(|(idx, path, plan)| {
+                layout::read_chunks(&path, plan)
+                    .map_err(|e| tracing::warn!("Failed to read {}: {}", path.display(), e))
+                    .ok()
+                    .map(|chunks| (idx, chunks))
+            })
+            .collect()
+    })
+    .await?)
+}
+
+/// Send a file's chunks (no flush); returns the bytes sent
+async fn send_chunks<S: PushSession + ?Sized>(
+    session: &mut S,
+    idx: u32,
+    chunks: &[Chunk],
+) -> Result<u64> {
+    let mut sent = 0u64;
+    for chunk in chunks {
+        sent += chunk.data.len() as u64;
+        session
+            .send_file_data_with_flags(idx, chunk.offset, chunk.flags, chunk.data.clone())
+            .await?;
+    }
+    Ok(sent)
+}
+
+/// Sync from local source to remote destination, starting the server through
+/// a user-supplied remote shell (`-e/--rsh`)
+pub async fn sync_server_mode_with_rsh(
+    source: &Path,
+    dest: &SyncPath,
+    dry_run: bool,
+    meta: MetaOptions,
+    transfer: TransferOptions,
+    progress: Option<Arc<ProgressState>>,
+    rsh: &RemoteShell,
+) -> Result<SyncStats> {
+    let start = Instant::now();
+
+    let mut session = connect_rsh(dest, rsh, transfer.session).await?;
+    tracing::debug!(
+        "Connected to server via remote shell (dry_run: {})",
+        dry_run
+    );
+
+    let source_entries = scan_source(source, meta, transfer).await?;
+
+    push_entries(
+        &mut session,
+        source_entries,
+        dry_run,
+        transfer,
+        progress,
+        start,
+    )
+    .await
+}
+
 /// Connect to remote server
 async fn connect(dest: &SyncPath) -> Result<ServerSession> {
-    connect_with_config(dest, None).await
+    connect_with_config(dest, None, SessionOptions::default()).await
 }
 
 /// Connect to remote server with optional SSH config override
 async fn connect_with_config(
     dest: &SyncPath,
     ssh_config_override: Option<&SshConfig>,
+    options: SessionOptions,
 ) -> Result<ServerSession> {
     match dest {
-        SyncPath::Local { path, .. } => ServerSession::connect_local(path).await,
+        SyncPath::Local { path, .. } => ServerSession::connect_local(path, options).await,
         SyncPath::Remote {
             host, user, path, ..
         } => {
@@ -585,58 +963,277 @@ async fn connect_with_config(
                     c
                 })
             };
-            ServerSession::connect_ssh(&config, path).await
+            ServerSession::connect_ssh(&config, path, options).await
         }
         _ => anyhow::bail!("Unsupported destination for server mode"),
     }
 }
 
-/// Scan source directory and return entries
-async fn scan_source(source: &Path) -> Result<Vec<SourceEntry>> {
+/// Connect to a remote server started through a user-supplied remote shell
+async fn connect_rsh(
+    dest: &SyncPath,
+    rsh: &RemoteShell,
+    options: SessionOptions,
+) -> Result<ServerSession> {
+    match dest {
+        SyncPath::Remote {
+            host, user, path, ..
+        } => ServerSession::connect_rsh(rsh, host, user.as_deref(), path, options).await,
+        _ => anyhow::bail!("--rsh requires a remote (host:path) destination"),
+    }
+}
+
+/// Scan source directory and return entries, with the metadata `meta` asks for
+async fn scan_source(
+    source: &Path,
+    meta: MetaOptions,
+    transfer: TransferOptions,
+) -> Result<Vec<SourceEntry>> {
+    scan_subtree(source, source, meta, transfer).await
+}
+
+/// Scan `dir` (the source root or a directory below it), with entries
+/// relative to `source`
+async fn scan_subtree(
+    source: &Path,
+    dir: &Path,
+    meta: MetaOptions,
+    transfer: TransferOptions,
+) -> Result<Vec<SourceEntry>> {
     let scan_opts = ScanOptions::default();
-    let src = source.to_path_buf();
+    let src = dir.to_path_buf();
 
     let entries = tokio::task::spawn_blocking(move || {
         scanner::Scanner::new(&src).with_options(scan_opts).scan()
     })
     .await??;
 
-    let mut result = Vec::with_capacity(entries.len());
+    Ok(entries
+        .into_iter()
+        .filter_map(|entry| source_entry(source, entry, &meta, transfer.hardlinks))
+        .collect())
+}
 
-    for entry in entries {
-        if let Ok(rel_path) = entry.path.strip_prefix(source) {
-            // Skip root directory
-            if rel_path.as_os_str().is_empty() {
-                continue;
-            }
+/// Scan only the paths in `changes`, with the subtrees of changed directories
+///
+/// Paths gone from the source are skipped: server mode doesn't delete.
+async fn scan_changes(
+    source: &Path,
+    changes: &ChangeSet,
+    meta: MetaOptions,
+    transfer: TransferOptions,
+) -> Result<Vec<SourceEntry>> {
+    let mut result = Vec::new();
+
+    for rel_path in changes.roots() {
+        let abs_path = source.join(rel_path);
+        if abs_path.symlink_metadata().is_err() {
+            tracing::debug!("{} was removed, nothing to push", rel_path.display());
+            continue;
+        }
 
-            if let Some(path_str) = rel_path.to_str() {
-                let mtime = entry
-                    .modified
-                    .duration_since(std::time::SystemTime::UNIX_EPOCH)
-                    .unwrap_or_default()
-                    .as_secs() as i64;
+        let entry = scanner::scan_entry(source, &abs_path)?;
+        let is_dir = entry.is_dir;
+        result.extend(source_entry(source, entry, &meta, transfer.hardlinks));
+        if is_dir {
+            result.extend(
+                scan_subtree(source, &abs_path, meta, transfer)
+                    .await?
+                    .into_iter()
+                    .filter(|e| Path::new(&e.rel_path) != rel_path),
+            );
+        }
+    }
 
-                let symlink_target = entry
-                    .symlink_target
-                    .as_ref()
-                    .and_then(|t| t.to_str().map(String::from));
+    Ok(result)
+}
 
-                result.push(SourceEntry {
-                    rel_path: path_str.to_string(),
-                    abs_path: entry.path.clone(),
-                    size: entry.size,
-                    mtime,
-                    mode: 0o644, // TODO: Get real mode from entry
-                    is_dir: entry.is_dir,
-                    is_symlink: entry.is_symlink,
-                    symlink_target,
-                });
+/// Convert a scanned entry below `source`, skipping the root itself
+fn source_entry(
+    source: &Path,
+    entry: scanner::FileEntry,
+    meta: &MetaOptions,
+    hardlinks: bool,
+) -> Option<SourceEntry> {
+    let rel_path = entry.path.strip_prefix(source).ok()?;
+    if rel_path.as_os_str().is_empty() {
+        return None;
+    }
+    let path_str = rel_path.to_str()?;
+
+    let mtime = entry
+        .modified
+        .duration_since(std::time::SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64;
+
+    let symlink_target = entry
+        .symlink_target
+        .as_ref()
+        .and_then(|t| t.to_str().map(String::from));
+
+    // Only regular files carry a metadata block, as in SshTransport
+    let is_file = !entry.is_dir && !entry.is_symlink;
+    let entry_meta = is_file.then(|| meta::collect(&entry, meta)).flatten();
+
+    Some(SourceEntry {
+        rel_path: path_str.to_string(),
+        abs_path: entry.path.clone(),
+        size: entry.size,
+        mtime,
+        mode: meta::file_mode(&entry.path),
+        is_dir: entry.is_dir,
+        is_symlink: entry.is_symlink,
+        symlink_target,
+        meta: entry_meta,
+        inode: entry.inode.filter(|_| hardlinks && is_file),
+        nlink: entry.nlink,
+        sparse: entry.is_sparse,
+    })
+}
+
+/// Where a [`PersistentSession`] connects to
+#[allow(dead_code)] // Used by watch mode
+#[allow(clippy::large_enum_variant)] // Built once per watch
+pub enum SessionTarget {
+    /// `sy --server` over SSH (a local subprocess for local paths)
+    Server {
+        dest: SyncPath,
+        ssh_config: Option<SshConfig>,
+    },
+    /// `sy --server` through a user-supplied remote shell (`-e/--rsh`)
+    Rsh { dest: SyncPath, rsh: RemoteShell },
+    /// A running `sy --daemon`, via its Unix socket
+    #[cfg(unix)]
+    Daemon {
+        socket_path: String,
+        remote_path: PathBuf,
+    },
+}
+
+/// A push session that stays open across syncs (used by watch mode)
+///
+/// Each sync streams just its entries over the same connection. The server
+/// keeps its view of the destination from the first scan and updates it
+/// from the files it acknowledges, so later syncs skip the remote scan.
+/// A broken connection is dropped and re-established on the next use.
+#[allow(dead_code)] // Used by watch mode
+pub struct PersistentSession {
+    target: SessionTarget,
+    dry_run: bool,
+    meta: MetaOptions,
+    transfer: TransferOptions,
+    session: Option<Box<dyn PushSession>>,
+}
+
+#[allow(dead_code)] // Used by watch mode
+impl PersistentSession {
+    pub fn new(target: SessionTarget, dry_run: bool) -> Self {
+        Self {
+            target,
+            dry_run,
+            meta: MetaOptions::default(),
+            transfer: TransferOptions::default(),
+            session: None,
+        }
+    }
+
+    /// Metadata to send along with file data (-o, -g, -X, -A, -F)
+    pub fn with_meta(mut self, meta: MetaOptions) -> Self {
+        self.meta = meta;
+        self
+    }
+
+    /// How files are encoded and verified, and what the session is asked for
+    pub fn with_transfer(mut self, transfer: TransferOptions) -> Self {
+        self.transfer = transfer;
+        self
+    }
+
+    /// Push the whole source tree
+    pub async fn sync_all(&mut self, source: &Path) -> Result<SyncStats> {
+        let entries = scan_source(source, self.meta, self.transfer).await?;
+        self.push(entries).await
+    }
+
+    /// Push only the paths in `changes` (relative to `source`)
+    pub async fn sync_paths(&mut self, source: &Path, changes: &ChangeSet) -> Result<SyncStats> {
+        let entries = scan_changes(source, changes, self.meta, self.transfer).await?;
+        self.push(entries).await
+    }
+
+    /// Ping the server, reconnecting if the connection has gone away
+    pub async fn keepalive(&mut self) -> Result<()> {
+        if let Some(session) = self.session.as_mut() {
+            match session.ping().await {
+                Ok(()) => return Ok(()),
+                Err(e) => {
+                    tracing::warn!("Keepalive failed ({}), reconnecting", e);
+                    self.session = None;
+                }
             }
         }
+        self.session = Some(self.connect().await?);
+        Ok(())
     }
 
-    Ok(result)
+    /// Push entries, retrying once on a fresh connection if the session fails
+    async fn push(&mut self, entries: Vec<SourceEntry>) -> Result<SyncStats> {
+        let start = Instant::now();
+
+        if let Some(session) = self.session.as_mut() {
+            match push_entries(
+                session.as_mut(),
+                entries.clone(),
+                self.dry_run,
+                self.transfer,
+                None,
+                start,
+            )
+            .await
+            {
+                Ok(stats) => return Ok(stats),
+                Err(e) => {
+                    // The stream is out of step now; only a new session can recover
+                    tracing::warn!("Session failed ({}), reconnecting", e);
+                    self.session = None;
+                }
+            }
+        }
+
+        let mut session = self.connect().await?;
+        let stats = push_entries(
+            session.as_mut(),
+            entries,
+            self.dry_run,
+            self.transfer,
+            None,
+            start,
+        )
+        .await?;
+        self.session = Some(session);
+        Ok(stats)
+    }
+
+    async fn connect(&self) -> Result<Box<dyn PushSession>> {
+        let session: Box<dyn PushSession> = match &self.target {
+            SessionTarget::Server { dest, ssh_config } => Box::new(
+                connect_with_config(dest, ssh_config.as_ref(), self.transfer.session).await?,
+            ),
+            SessionTarget::Rsh { dest, rsh } => {
+                Box::new(connect_rsh(dest, rsh, self.transfer.session).await?)
+            }
+            #[cfg(unix)]
+            SessionTarget::Daemon {
+                socket_path,
+                remote_path,
+            } => Box::new(
+                DaemonSession::connect(socket_path, remote_path, self.transfer.session).await?,
+            ),
+        };
+        tracing::debug!("Connected persistent session");
+        Ok(session)
+    }
 }
 
 /// Sync from remote source to local destination using server protocol (PULL mode)
@@ -644,9 +1241,11 @@ pub async fn sync_pull_server_mode(
     source: &SyncPath,
     dest: &Path,
     dry_run: bool,
+    meta: MetaOptions,
+    transfer: TransferOptions,
     progress: Option<Arc<ProgressState>>,
 ) -> Result<SyncStats> {
-    sync_pull_server_mode_with_config(source, dest, dry_run, progress, None).await
+    sync_pull_server_mode_with_config(source, dest, dry_run, meta, transfer, progress, None).await
 }
 
 /// Sync from remote source to local destination using server protocol (PULL mode) with optional SSH config
@@ -654,19 +1253,63 @@ pub async fn sync_pull_server_mode_with_config(
     source: &SyncPath,
     dest: &Path,
     dry_run: bool,
+    meta: MetaOptions,
+    transfer: TransferOptions,
     progress: Option<Arc<ProgressState>>,
     ssh_config: Option<&SshConfig>,
 ) -> Result<SyncStats> {
     let start = Instant::now();
 
     // Connect to server in PULL mode
-    let mut session = connect_pull_with_config(source, ssh_config).await?;
+    let session = connect_pull_with_config(source, ssh_config, transfer.session).await?;
     tracing::debug!("Connected to server (PULL mode, dry_run: {})", dry_run);
 
+    pull_entries(session, dest, dry_run, meta, transfer, progress, start).await
+}
+
+/// Sync from remote source to local destination, starting the server through
+/// a user-supplied remote shell (`-e/--rsh`)
+pub async fn sync_pull_server_mode_with_rsh(
+    source: &SyncPath,
+    dest: &Path,
+    dry_run: bool,
+    meta: MetaOptions,
+    transfer: TransferOptions,
+    progress: Option<Arc<ProgressState>>,
+    rsh: &RemoteShell,
+) -> Result<SyncStats> {
+    let start = Instant::now();
+
+    let session = match source {
+        SyncPath::Remote {
+            host, user, path, ..
+        } => {
+            ServerSession::connect_rsh_pull(rsh, host, user.as_deref(), path, transfer.session)
+                .await?
+        }
+        _ => anyhow::bail!("--rsh requires a remote (host:path) source"),
+    };
+    tracing::debug!("Connected to server via remote shell (PULL mode)");
+
+    pull_entries(session, dest, dry_run, meta, transfer, progress, start).await
+}
+
+/// Receive the server's tree into a local destination over an open PULL session
+async fn pull_entries(
+    mut session: ServerSession,
+    dest: &Path,
+    dry_run: bool,
+    meta: MetaOptions,
+    transfer: TransferOptions,
+    progress: Option<Arc<ProgressState>>,
+    start: Instant,
+) -> Result<SyncStats> {
     // Ensure local destination exists
     if !dest.exists() {
         std::fs::create_dir_all(dest)?;
     }
+    // Paths come from the server; keep every write beneath dest
+    let confine = Confinement::new(dest);
 
     // Scan local destination for comparison
     let local_entries = scan_local_dest(dest).await?;
@@ -713,7 +1356,14 @@ pub async fn sync_pull_server_mode_with_config(
             .await?;
     } else {
         for dir_path in &mkdir_batch.paths {
-            let full_path = dest.join(dir_path);
+            let full_path = match confine.resolve(dir_path) {
+                Ok(p) => p,
+                Err(e) => {
+                    tracing::warn!("Refusing directory {}: {}", dir_path, e);
+                    failed.push((dir_path.clone(), e.to_string()));
+                    continue;
+                }
+            };
             match std::fs::create_dir_all(&full_path) {
                 Ok(_) => dirs_created += 1,
                 Err(e) => failed.push((dir_path.clone(), e.to_string())),
@@ -730,6 +1380,10 @@ pub async fn sync_pull_server_mode_with_config(
 
     let mut decisions: Vec<Decision> = Vec::with_capacity(file_list.entries.len());
     let mut files_to_receive: Vec<(u32, String)> = Vec::new();
+    // Hard links are never sent as data; they are made locally afterwards
+    let mut hardlinks: Vec<(u32, u32)> = Vec::new();
+    // Set once read_file_data has consumed the SYMLINK_BATCH header
+    let mut symlinks_next = false;
 
     // Track what WOULD happen in dry-run using metadata (before making decisions)
     if dry_run {
@@ -793,13 +1447,21 @@ pub async fn sync_pull_server_mode_with_config(
             .map(|idx| Decision {
                 index: idx as u32,
                 action: Action::Skip,
+                resume_from: 0,
+                digest: ChecksumType::None,
+                basis: None,
             })
             .collect();
         session.send_file_list_ack(skip_decisions).await?;
     } else {
         // Normal mode: make real decisions
+        let can_resume = session.negotiated().has(CAP_RESUME);
+        let can_verify = session.negotiated().has(CAP_FILE_CHECKSUM);
         for (idx, entry) in file_list.entries.iter().enumerate() {
-            let action = if let Some((local_size, local_mtime)) = local_map.get(&entry.path) {
+            let action = if let Err(e) = confine.resolve(&entry.path) {
+                tracing::warn!("Refusing file {}: {}", entry.path, e);
+                Action::Skip
+            } else if let Some((local_size, local_mtime)) = local_map.get(&entry.path) {
                 // File exists locally - compare
                 if *local_size == entry.size && *local_mtime >= entry.mtime {
                     Action::Skip
@@ -810,15 +1472,44 @@ pub async fn sync_pull_server_mode_with_config(
                 Action::Create
             };
 
+            if let Some(leader) = entry.hardlink_to {
+                hardlinks.push((idx as u32, leader));
+                decisions.push(Decision {
+                    index: idx as u32,
+                    action: Action::Skip,
+                    resume_from: 0,
+                    digest: ChecksumType::None,
+                    basis: None,
+                });
+                continue;
+            }
+
             if action != Action::Skip {
                 files_to_receive.push((idx as u32, entry.path.clone()));
             } else {
                 files_skipped += 1;
             }
 
+            // Offer what an interrupted run left behind
+            let resume_from = if can_resume && action != Action::Skip {
+                confine
+                    .resolve(&partial::partial_rel(&entry.path, entry.size, entry.mtime))
+                    .map_or(0, |partial| partial::resume_offset(&partial, entry.size))
+            } else {
+                0
+            };
+            // Ask for the whole-file digest to check before each rename
+            let digest = if can_verify && action != Action::Skip {
+                transfer.verify
+            } else {
+                ChecksumType::None
+            };
             decisions.push(Decision {
                 index: idx as u32,
                 action,
+                resume_from,
+                digest,
+                basis: None,
             });
         }
 
@@ -841,69 +1532,114 @@ pub async fn sync_pull_server_mode_with_config(
 
         session.send_file_list_ack(decisions).await?;
 
-        // Step 3: Receive files (pipelined - receive all, then send all ACKs)
-        let mut files_received: Vec<(u32, u8)> = Vec::new(); // (idx, status)
-
-        for (idx, rel_path) in &files_to_receive {
-            // Start transfer progress
-            if let Some(ref progress) = progress {
-                progress.start_transfer(PathBuf::from(rel_path));
+        // Step 3: Receive files (pipelined - receive all, then send all ACKs);
+        // with CAP_FILE_CHECKSUM the server sends mismatched files again
+        let mut pending = files_to_receive.clone();
+        for round in 0..=VERIFY_RETRIES {
+            if round > 0 {
+                if !can_verify || pending.is_empty() {
+                    break;
+                }
+                tracing::warn!(
+                    "Receiving {} files again after failed verification",
+                    pending.len()
+                );
             }
-            let file_data = match session.read_file_data().await? {
-                Some(data) => data,
-                None => break, // Server sent symlinks instead
-            };
 
-            let full_path = dest.join(rel_path);
-
-            // Ensure parent directory exists
-            if let Some(parent) = full_path.parent() {
-                std::fs::create_dir_all(parent)?;
-            }
+            let mut files_received: Vec<(u32, u8, Vec<u8>)> = Vec::new(); // (idx, status, digest)
+            let mut mismatched = Vec::new();
+            for (idx, rel_path) in pending {
+                // Start transfer progress
+                if let Some(ref progress) = progress {
+                    progress.start_transfer(PathBuf::from(&rel_path));
+                }
+                let file_data = match session.read_file_data().await? {
+                    Some(data) => data,
+                    None => {
+                        // Server sent symlinks instead
+                        symlinks_next = true;
+                        break;
+                    }
+                };
 
-            // Write file
-            let file_size = file_data.data.len() as u64;
-            match std::fs::write(&full_path, &file_data.data) {
-                Ok(_) => {
-                    bytes_transferred += file_size;
-                    if local_map.contains_key(rel_path) {
-                        files_updated += 1;
-                    } else {
-                        files_created += 1;
+                // Write file (sparse and split files arrive as several chunks)
+                let entry = &file_list.entries[idx as usize];
+                match session
+                    .receive_file(&confine, &rel_path, entry, file_data)
+                    .await?
+                {
+                    Ok(received) => {
+                        meta::apply_file(&received.path, entry, &meta);
+                        bytes_transferred += received.bytes;
+                        if local_map.contains_key(&rel_path) {
+                            files_updated += 1;
+                        } else {
+                            files_created += 1;
+                        }
+                        // Finish transfer progress
+                        if let Some(ref progress) = progress {
+                            progress.finish_transfer(&PathBuf::from(&rel_path), received.bytes);
+                        }
+                        files_received.push((idx, STATUS_OK, received.checksum));
                     }
-                    // Finish transfer progress
-                    if let Some(ref progress) = progress {
-                        progress.finish_transfer(&PathBuf::from(rel_path), file_size);
+                    Err(e) => {
+                        tracing::warn!("Failed to write {}: {}", rel_path, e);
+                        let status = digest::failure_status(&e);
+                        if status == STATUS_CHECKSUM_MISMATCH {
+                            mismatched.push((idx, rel_path));
+                        }
+                        files_received.push((idx, status, Vec::new()));
                     }
-                    files_received.push((*idx, 0)); // Status OK
-                }
-                Err(e) => {
-                    tracing::warn!("Failed to write {}: {}", full_path.display(), e);
-                    files_received.push((*idx, 2)); // STATUS_WRITE_ERROR
                 }
             }
-        }
 
-        // Send all FILE_DONE responses at once (pipelined)
-        for (idx, status) in &files_received {
-            session.send_file_done(*idx, *status).await?;
+            // Send all FILE_DONE responses at once (pipelined)
+            for (idx, status, checksum) in files_received {
+                session.send_file_done(idx, status, checksum).await?;
+            }
+            pending = mismatched;
+        }
+        for (_, rel_path) in &pending {
+            tracing::error!("{} failed verification", rel_path);
         }
     }
 
-    // Step 4: Receive and create symlinks (if any)
-    // Note: In dry-run mode, we've told server to skip everything, so no symlinks sent
-    if !dry_run
-        && (files_to_receive.is_empty()
-            || files_created + files_updated < files_to_receive.len() as u64)
-    {
-        // Try to read symlink batch (may have been signaled by read_file_data returning None)
-        match session.read_symlink_batch_body().await {
+    // Step 4: Receive and create symlinks (if any; a server without them
+    // just exits). In dry-run mode they are not read.
+    if !dry_run {
+        let symlink_batch = if symlinks_next {
+            session.read_symlink_batch_body().await
+        } else {
+            session.read_symlink_batch().await
+        };
+        match symlink_batch {
             Ok(symlink_batch) => {
                 tracing::debug!("Received {} symlinks", symlink_batch.entries.len());
                 let mut created = 0u32;
                 let mut failed: Vec<(String, String)> = Vec::new();
                 for entry in &symlink_batch.entries {
-                    let link_path = dest.join(&entry.path);
+                    let link_path = match confine.resolve(&entry.path) {
+                        Ok(p) => p,
+                        Err(e) => {
+                            tracing::warn!("Refusing symlink {}: {}", entry.path, e);
+                            failed.push((entry.path.clone(), e.to_string()));
+                            continue;
+                        }
+                    };
+                    if transfer.session.safe_links
+                        && !confine::symlink_is_safe(Path::new(&entry.path), &entry.target)
+                    {
+                        tracing::warn!(
+                            "Refusing unsafe symlink {} -> {}",
+                            entry.path,
+                            entry.target
+                        );
+                        failed.push((
+                            entry.path.clone(),
+                            format!("unsafe symlink target: {}", entry.target),
+                        ));
+                        continue;
+                    }
 
                     // Remove existing if present
                     if link_path.exists() || link_path.symlink_metadata().is_ok() {
@@ -937,6 +1673,33 @@ pub async fn sync_pull_server_mode_with_config(
         }
     }
 
+    // Step 5: Hard links to files received (or already present) above
+    for (idx, leader) in &hardlinks {
+        let (Some(entry), Some(leader)) = (
+            file_list.entries.get(*idx as usize),
+            file_list.entries.get(*leader as usize),
+        ) else {
+            continue;
+        };
+        let (leader, path) = match (confine.resolve(&leader.path), confine.resolve(&entry.path)) {
+            (Ok(leader), Ok(path)) => (leader, path),
+            (Err(e), _) | (_, Err(e)) => {
+                tracing::warn!("Refusing link {}: {}", entry.path, e);
+                continue;
+            }
+        };
+        if transfer.hardlinks && layout::same_file(&leader, &path) {
+            files_skipped += 1;
+            continue;
+        }
+        let existed = path.symlink_metadata().is_ok();
+        match layout::link_file(&leader, &path, transfer.hardlinks) {
+            Ok(()) if existed => files_updated += 1,
+            Ok(()) => files_created += 1,
+            Err(e) => tracing::warn!("Failed to link {}: {}", path.display(), e),
+        }
+    }
+
     let duration = start.elapsed();
     tracing::info!(
         "Pull sync complete: {} created, {} updated, {} skipped, {} bytes in {:?}",
@@ -977,11 +1740,13 @@ pub async fn sync_pull_server_mode_with_config(
         None
     };
 
+    let stream = session.stream_stats();
     Ok(SyncStats {
         files_scanned: file_list.entries.len() as u64,
         files_created,
         files_updated,
         files_deleted: 0,
+        files_moved: 0,
         files_skipped,
         bytes_transferred,
         duration,
@@ -992,18 +1757,21 @@ pub async fn sync_pull_server_mode_with_config(
         bytes_would_change,
         bytes_would_delete: 0,
         dry_run_details,
+        stream_bytes_raw: stream.raw_bytes,
+        stream_bytes_wire: stream.wire_bytes,
         ..Default::default()
     })
 }
 
 /// Connect to remote server in PULL mode
 async fn connect_pull(source: &SyncPath) -> Result<ServerSession> {
-    connect_pull_with_config(source, None).await
+    connect_pull_with_config(source, None, SessionOptions::default()).await
 }
 
 async fn connect_pull_with_config(
     source: &SyncPath,
     ssh_config_override: Option<&SshConfig>,
+    options: SessionOptions,
 ) -> Result<ServerSession> {
     match source {
         SyncPath::Local { path, .. } => ServerSession::connect_local_pull(path).await,
@@ -1024,7 +1792,7 @@ async fn connect_pull_with_config(
                     c
                 })
             };
-            ServerSession::connect_ssh_pull(&config, path).await
+            ServerSession::connect_ssh_pull(&config, path, options).await
         }
         _ => anyhow::bail!("Unsupported source for pull mode"),
     }
@@ -1070,6 +1838,10 @@ async fn scan_local_dest(dest: &Path) -> Result<Vec<SourceEntry>> {
                     is_dir: entry.is_dir,
                     is_symlink: entry.is_symlink,
                     symlink_target: None,
+                    meta: None,
+                    inode: None,
+                    nlink: entry.nlink,
+                    sparse: false,
                 });
             }
         }
@@ -1078,20 +1850,67 @@ async fn scan_local_dest(dest: &Path) -> Result<Vec<SourceEntry>> {
     Ok(result)
 }
 
+/// Generate a delta against the receiver's CHECKSUM_RESP
+///
+/// Fixed-block responses feed the rolling-checksum delta; CDC responses
+/// carry chunks, with the chunk tag in the `weak` field.
+pub(crate) fn delta_from_checksums(
+    path: &Path,
+    req: ChecksumReq,
+    resp: &ChecksumResp,
+) -> std::io::Result<Delta> {
+    match req.algo {
+        DeltaAlgo::Block => {
+            let dest_checksums: Vec<DeltaBlockChecksum> = resp
+                .checksums
+                .iter()
+                .enumerate()
+                .map(|(i, c)| DeltaBlockChecksum {
+                    index: i as u64,
+                    offset: c.offset,
+                    size: c.size as usize,
+                    weak: c.weak,
+                    strong: c.strong,
+                })
+                .collect();
+            generate_delta_streaming(path, &dest_checksums, req.block_size as usize)
+        }
+        DeltaAlgo::Cdc => {
+            let chunks: Vec<cdc::Chunk> = resp
+                .checksums
+                .iter()
+                .map(|c| cdc::Chunk {
+                    offset: c.offset,
+                    size: c.size,
+                    strong: c.strong,
+                    tag: c.weak,
+                })
+                .collect();
+            cdc::generate_delta(path, &chunks, ChunkParams::new(req.block_size))
+        }
+    }
+}
+
 /// Process a batch of delta sync candidates with full pipelining.
 /// - Reads all checksum responses
 /// - Computes all deltas in parallel
 /// - Sends all DELTA_DATA without waiting
 /// - Reads all FILE_DONE responses at the end
 ///
-/// Returns (files_updated, bytes_transferred)
-async fn process_delta_batch(
-    session: &mut ServerSession,
-    pending: &[(u32, &SourceEntry, u32)],
+/// With `verify` each DELTA_DATA carries the source's digest; files the
+/// receiver fails to verify are added to `mismatched`.
+///
+/// Returns (files_created, files_updated, bytes_transferred); creates are
+/// new files delta-encoded against a fuzzy basis.
+async fn process_delta_batch<S: PushSession + ?Sized>(
+    session: &mut S,
+    pending: &[(u32, &SourceEntry, Action, ChecksumReq)],
+    verify: ChecksumType,
+    block_sizes: &BlockSizePolicy,
+    mismatched: &mut Vec<(u32, Action)>,
     progress: Option<&Arc<ProgressState>>,
-) -> Result<(u64, u64)> {
-    use crate::server::protocol::ChecksumResp;
-
+) -> Result<(u64, u64, u64)> {
+    let mut files_created = 0u64;
     let mut files_updated = 0u64;
     let mut bytes_transferred = 0u64;
 
@@ -1105,10 +1924,10 @@ async fn process_delta_batch(
     // Step 2: Compute all deltas in parallel
     let delta_futures: Vec<_> = pending
         .iter()
-        .map(|(file_idx, entry, block_size)| {
+        .map(|(file_idx, entry, _, req)| {
             let resp = responses.get(file_idx).cloned();
             let path = entry.abs_path.clone();
-            let bs = *block_size as usize;
+            let req = *req;
             let idx = *file_idx;
 
             async move {
@@ -1116,23 +1935,10 @@ async fn process_delta_batch(
                     anyhow::anyhow!("Missing checksum response for index {}", idx)
                 })?;
 
-                // Convert protocol checksums to delta checksums
-                let dest_checksums: Vec<DeltaBlockChecksum> = resp
-                    .checksums
-                    .iter()
-                    .enumerate()
-                    .map(|(i, c)| DeltaBlockChecksum {
-                        index: i as u64,
-                        offset: c.offset,
-                        size: c.size as usize,
-                        weak: c.weak,
-                        strong: c.strong,
-                    })
-                    .collect();
-
-                // Generate delta in blocking task
-                let delta = tokio::task::spawn_blocking(move || {
-                    generate_delta_streaming(&path, &dest_checksums, bs)
+                // Generate delta (and the digest it should produce) in blocking task
+                let (delta, digest) = tokio::task::spawn_blocking(move || {
+                    let delta = delta_from_checksums(&path, req, &resp)?;
+                    Ok::<_, std::io::Error>((delta, digest::file_digest(&path, verify)?))
                 })
                 .await??;
 
@@ -1155,19 +1961,22 @@ async fn process_delta_batch(
                     }
                 }
 
-                Ok::<_, anyhow::Error>((idx, ops, delta_bytes))
+                Ok::<_, anyhow::Error>((idx, ops, delta_bytes, digest))
             }
         })
         .collect();
 
-    let deltas: Vec<Result<(u32, Vec<DeltaOp>, u64)>> =
-        futures::future::join_all(delta_futures).await;
+    let deltas: Vec<Result<FileDelta>> = futures::future::join_all(delta_futures).await;
 
     // Step 3: Send all DELTA_DATA without waiting for confirmations
-    let mut sent_indices: Vec<(u32, String, u64, u64)> = Vec::with_capacity(pending.len());
+    let mut sent_indices: Vec<(u32, Action, String, u64, u64, Vec<u8>)> =
+        Vec::with_capacity(pending.len());
     for (i, result) in deltas.into_iter().enumerate() {
-        let (idx, ops, delta_bytes) = result?;
-        let entry = pending[i].1;
+        let (idx, ops, delta_bytes, digest) = result?;
+        let (_, entry, action, _) = pending[i];
+        if action == Action::Update {
+            block_sizes.record_delta(&entry.abs_path, delta_bytes, entry.size);
+        }
 
         // Start transfer progress
         if let Some(progress) = progress {
@@ -1175,25 +1984,42 @@ async fn process_delta_batch(
         }
 
         bytes_transferred += delta_bytes;
-        sent_indices.push((idx, entry.rel_path.clone(), delta_bytes, entry.size));
+        sent_indices.push((
+            idx,
+            action,
+            entry.rel_path.clone(),
+            delta_bytes,
+            entry.size,
+            digest.clone(),
+        ));
 
-        session.send_delta_data_no_flush(idx, 0, ops).await?;
+        session
+            .send_delta_data_no_flush(idx, 0, ops, digest)
+            .await?;
     }
     session.flush().await?;
 
     // Step 4: Read all FILE_DONE responses
-    for (idx, rel_path, delta_bytes, size) in sent_indices {
+    for (idx, action, rel_path, delta_bytes, size, digest) in sent_indices {
         let done = session.read_file_done().await?;
 
-        if done.status != 0 {
+        let verdict = digest::verdict(&done, &digest);
+        if verdict == Verdict::Mismatch {
+            tracing::warn!("Delta transfer of {} failed verification", rel_path);
+            mismatched.push((idx, action));
+        } else if verdict == Verdict::Failed {
             tracing::error!(
-                "Delta update failed for {}: index {} status {}",
+                "Delta transfer failed for {}: index {} status {}",
                 rel_path,
                 done.index,
                 done.status
             );
         } else {
-            files_updated += 1;
+            if action == Action::Create {
+                files_created += 1;
+            } else {
+                files_updated += 1;
+            }
             // Finish transfer progress
             if let Some(progress) = progress {
                 progress.finish_transfer(&PathBuf::from(&rel_path), size);
@@ -1215,5 +2041,5 @@ async fn process_delta_batch(
         }
     }
 
-    Ok((files_updated, bytes_transferred))
+    Ok((files_created, files_updated, bytes_transferred))
 }
@@ -17,6 +17,11 @@ pub enum SyncAction {
     Update,
     /// Delete - file exists in destination but not source
     Delete,
+    /// Move - rename an existing destination file instead of copy + delete
+    Move {
+        /// Destination path the file currently lives at
+        from: std::path::PathBuf,
+    },
 }
 
 #[derive(Debug, Clone)]
@@ -30,6 +35,8 @@ pub struct SyncTask {
     /// Pre-computed destination checksum (for --checksum mode)
     #[allow(dead_code)] // Will be used for checksum database storage (Phase 5b)
     pub dest_checksum: Option<Checksum>,
+    /// Similar destination file to delta-encode a Create against (--fuzzy)
+    pub basis: Option<std::path::PathBuf>,
 }
 
 #[derive(Clone)]
@@ -125,6 +132,7 @@ impl StrategyPlanner {
                             action: SyncAction::Skip,
                             source_checksum: None,
                             dest_checksum: None,
+                            basis: None,
                         });
                     }
 
@@ -140,6 +148,7 @@ impl StrategyPlanner {
                             action: SyncAction::Skip,
                             source_checksum: None,
                             dest_checksum: None,
+                            basis: None,
                         });
                     }
 
@@ -189,6 +198,7 @@ impl StrategyPlanner {
             action,
             source_checksum,
             dest_checksum,
+            basis: None,
         })
     }
 
@@ -381,6 +391,7 @@ impl StrategyPlanner {
             action,
             source_checksum,
             dest_checksum,
+            basis: None,
         }
     }
 
@@ -435,6 +446,7 @@ impl StrategyPlanner {
                             action: SyncAction::Skip,
                             source_checksum: None,
                             dest_checksum: None,
+                            basis: None,
                         };
                     }
 
@@ -454,6 +466,7 @@ impl StrategyPlanner {
                             action: SyncAction::Skip,
                             source_checksum: None,
                             dest_checksum: None,
+                            basis: None,
                         };
                     }
 
@@ -474,6 +487,7 @@ impl StrategyPlanner {
             action,
             source_checksum: None,
             dest_checksum: None,
+            basis: None,
         }
     }
 
@@ -579,6 +593,7 @@ impl StrategyPlanner {
                             action: SyncAction::Delete,
                             source_checksum: None,
                             dest_checksum: None,
+                            basis: None,
                         });
                     } else {
                         // Bloom says "might exist" - verify with HashMap to handle false positives
@@ -589,6 +604,7 @@ impl StrategyPlanner {
                                 action: SyncAction::Delete,
                                 source_checksum: None,
                                 dest_checksum: None,
+                                basis: None,
                             });
                         }
                     }
@@ -612,6 +628,7 @@ impl StrategyPlanner {
                             action: SyncAction::Delete,
                             source_checksum: None,
                             dest_checksum: None,
+                            basis: None,
                         });
                     }
                 }
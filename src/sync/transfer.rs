@@ -196,6 +196,50 @@ impl<'a, T: Transport> Transferrer<'a, T> {
         }
     }
 
+    /// Create a new file by delta-encoding it against a similar destination
+    /// file (--fuzzy); the basis itself is left untouched
+    pub async fn create_from_basis(
+        &self,
+        source: &FileEntry,
+        basis: &Path,
+        dest_path: &Path,
+    ) -> Result<Option<TransferResult>> {
+        if self.dry_run {
+            tracing::info!(
+                "Would create: {} (delta from {})",
+                dest_path.display(),
+                basis.display()
+            );
+            return Ok(None);
+        }
+
+        if let Some(parent) = dest_path.parent() {
+            self.transport.create_dir_all(parent).await?;
+        }
+
+        let result = self
+            .transport
+            .sync_file_with_basis(&source.path, basis, dest_path)
+            .await?;
+
+        // Write extended attributes if present
+        self.write_xattrs(source, dest_path).await?;
+
+        // Write ACLs if present
+        self.write_acls(source, dest_path).await?;
+
+        // Write BSD flags if present (macOS only)
+        self.write_bsd_flags(source, dest_path).await?;
+
+        tracing::info!(
+            "Created: {} -> {} (delta from {})",
+            source.path.display(),
+            dest_path.display(),
+            basis.display()
+        );
+        Ok(Some(result))
+    }
+
     /// Update an existing file
     /// Returns Some(TransferResult) for files, None for directories
     pub async fn update(
@@ -255,6 +299,18 @@ impl<'a, T: Transport> Transferrer<'a, T> {
         Ok(())
     }
 
+    /// Rename a destination file to its new location (move detection)
+    pub async fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        if self.dry_run {
+            tracing::info!("Would move: {} -> {}", from.display(), to.display());
+            return Ok(());
+        }
+
+        self.transport.rename(from, to).await?;
+        tracing::info!("Moved: {} -> {}", from.display(), to.display());
+        Ok(())
+    }
+
     async fn create_directory(&self, path: &Path) -> Result<()> {
         self.transport.create_dir_all(path).await?;
         tracing::debug!("Created directory: {}", path.display());
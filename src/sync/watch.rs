@@ -1,8 +1,12 @@
-use crate::sync::SyncEngine;
+use crate::bisync::{BisyncSession, TRASH_DIR};
+use crate::sync::dircache::DirectoryCache;
+use crate::sync::incremental::ChangeSet;
+use crate::sync::server_mode::PersistentSession;
+use crate::sync::{SyncEngine, SyncStats};
 use crate::transport::Transport;
 use anyhow::Result;
 use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::mpsc::{channel, RecvTimeoutError};
 use std::time::{Duration, Instant};
 use tokio::signal;
@@ -12,11 +16,29 @@ use crate::cli::SymlinkMode;
 #[cfg(test)]
 use crate::integrity::ChecksumType;
 
+/// Above this many changed paths a full rescan is cheaper than per-path syncs
+const MAX_INCREMENTAL_PATHS: usize = 10_000;
+
+/// Poll interval used when a network filesystem source is detected
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How long a persistent session may sit idle before it is pinged
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// What each watch pass syncs through
+enum WatchBackend<T: Transport> {
+    Engine(SyncEngine<T>),
+    /// One server/daemon session held open for the whole watch
+    Session(Box<tokio::sync::Mutex<PersistentSession>>),
+}
+
 pub struct WatchMode<T: Transport> {
-    engine: SyncEngine<T>,
+    backend: WatchBackend<T>,
     source: PathBuf,
     destination: PathBuf,
     debounce: Duration,
+    /// Poll the source instead of using file system events
+    poll_interval: Option<Duration>,
 }
 
 impl<T: Transport + 'static> WatchMode<T> {
@@ -27,22 +49,69 @@ impl<T: Transport + 'static> WatchMode<T> {
         debounce: Duration,
     ) -> Self {
         Self {
-            engine,
+            backend: WatchBackend::Engine(engine),
+            source,
+            destination,
+            debounce,
+            poll_interval: None,
+        }
+    }
+
+    /// Push changes over one long-lived server or daemon session instead
+    /// of an engine, so passes skip the connection setup and remote scan
+    ///
+    /// `destination` is only used for display; the session knows its target.
+    pub fn with_session(
+        session: PersistentSession,
+        source: PathBuf,
+        destination: PathBuf,
+        debounce: Duration,
+    ) -> Self {
+        Self {
+            backend: WatchBackend::Session(Box::new(tokio::sync::Mutex::new(session))),
             source,
             destination,
             debounce,
+            poll_interval: None,
         }
     }
 
+    /// Poll the source every `interval` instead of relying on file system
+    /// events (`--watch-poll`)
+    pub fn with_poll_interval(mut self, interval: Option<Duration>) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
     pub async fn watch(&self) -> Result<()> {
         // Initial sync
         tracing::info!("Running initial sync...");
-        self.engine.sync(&self.source, &self.destination).await?;
+        self.sync_full().await?;
+
+        // Use the canonical root, so event paths can be made relative to it
+        let root = self
+            .source
+            .canonicalize()
+            .unwrap_or_else(|_| self.source.clone());
+
+        // Events from other NFS/SMB clients never reach the local kernel
+        let poll_interval = self.poll_interval.or_else(|| {
+            crate::fs_util::is_network_filesystem(&root).then(|| {
+                println!(
+                    "ℹ️  {} is on a network filesystem, polling for changes instead of watching",
+                    self.source.display()
+                );
+                DEFAULT_POLL_INTERVAL
+            })
+        });
+        if let Some(interval) = poll_interval {
+            return self.watch_polling(&root, interval).await;
+        }
 
         // Set up file watcher
         let (tx, rx) = channel();
         let mut watcher: RecommendedWatcher = notify::recommended_watcher(tx)?;
-        watcher.watch(&self.source, RecursiveMode::Recursive)?;
+        watcher.watch(&root, RecursiveMode::Recursive)?;
 
         println!(
             "\n🔍 Watching {} for changes (Ctrl+C to stop)...\n",
@@ -50,8 +119,11 @@ impl<T: Transport + 'static> WatchMode<T> {
         );
 
         // Event loop with debouncing
-        let mut pending_changes = Vec::new();
+        let mut pending_events = 0usize;
+        let mut changes = ChangeSet::new();
+        let mut full_rescan = false;
         let mut last_sync = Instant::now();
+        let mut last_keepalive = Instant::now();
 
         // Set up Ctrl+C handler
         let ctrl_c = signal::ctrl_c();
@@ -74,31 +146,32 @@ impl<T: Transport + 'static> WatchMode<T> {
                 Ok(Ok(event)) => {
                     // Filter out events we don't care about
                     if self.should_sync_event(&event) {
-                        pending_changes.push(event);
+                        pending_events += 1;
+                        if !record_event(&root, &event, &mut changes) {
+                            full_rescan = true;
+                        }
                     }
                 }
                 Ok(Err(e)) => {
                     tracing::error!("Watch error: {}", e);
-                    // Force sync on error to ensure consistency
-                    pending_changes.push(Event::new(notify::EventKind::Other));
+                    // Events may have been lost; rescan to ensure consistency
+                    pending_events += 1;
+                    full_rescan = true;
                 }
                 Err(RecvTimeoutError::Timeout) => {
                     // Check if we should sync (debounce timeout reached)
-                    if !pending_changes.is_empty() && last_sync.elapsed() >= self.debounce {
-                        tracing::info!("Detected {} changes, syncing...", pending_changes.len());
+                    if pending_events > 0 && last_sync.elapsed() >= self.debounce {
+                        tracing::info!("Detected {} changes, syncing...", pending_events);
                         println!("📝 Changes detected, syncing...");
 
-                        match self.engine.sync(&self.source, &self.destination).await {
-                            Ok(_) => {
-                                println!("✓ Sync complete\n");
-                            }
-                            Err(e) => {
-                                eprintln!("✗ Sync failed: {}\n", e);
-                            }
-                        }
+                        self.sync_changes(&changes, full_rescan).await;
 
-                        pending_changes.clear();
+                        pending_events = 0;
+                        changes.clear();
+                        full_rescan = false;
                         last_sync = Instant::now();
+                    } else if pending_events == 0 {
+                        self.keepalive(&mut last_keepalive, last_sync).await;
                     }
                 }
                 Err(RecvTimeoutError::Disconnected) => {
@@ -112,19 +185,325 @@ impl<T: Transport + 'static> WatchMode<T> {
         Ok(())
     }
 
+    /// Watch by comparing `DirectoryCache` snapshots every `interval`
+    async fn watch_polling(&self, root: &Path, interval: Duration) -> Result<()> {
+        let mut snapshot = DirectoryCache::snapshot(root)?;
+
+        println!(
+            "\n🔍 Polling {} every {}s for changes (Ctrl+C to stop)...\n",
+            self.source.display(),
+            interval.as_secs_f64()
+        );
+
+        let mut changes = ChangeSet::new();
+        let mut last_sync = Instant::now();
+        let mut last_keepalive = Instant::now();
+
+        let ctrl_c = signal::ctrl_c();
+        tokio::pin!(ctrl_c);
+
+        loop {
+            tokio::select! {
+                _ = &mut ctrl_c => {
+                    println!("\n⏹️  Stopping watch mode...");
+                    break;
+                }
+                _ = tokio::time::sleep(interval) => {}
+            }
+
+            if changes.is_empty() {
+                self.keepalive(&mut last_keepalive, last_sync).await;
+            }
+
+            // Walking the tree is blocking I/O
+            let previous = snapshot.clone();
+            let poll_root = root.to_path_buf();
+            match tokio::task::spawn_blocking(move || previous.refresh(&poll_root)).await? {
+                Ok((next, found)) => {
+                    snapshot = next;
+                    changes.merge(found);
+                }
+                Err(e) => {
+                    // E.g. the mount went away; keep the old snapshot and retry
+                    tracing::warn!("Failed to poll {}: {}", root.display(), e);
+                    continue;
+                }
+            }
+
+            if !changes.is_empty() && last_sync.elapsed() >= self.debounce {
+                tracing::info!("Detected {} changed paths, syncing...", changes.len());
+                println!("📝 Changes detected, syncing...");
+
+                self.sync_changes(&changes, false).await;
+
+                changes.clear();
+                last_sync = Instant::now();
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sync the whole tree
+    async fn sync_full(&self) -> Result<SyncStats> {
+        match &self.backend {
+            WatchBackend::Engine(engine) => {
+                Ok(engine.sync(&self.source, &self.destination).await?)
+            }
+            WatchBackend::Session(session) => session.lock().await.sync_all(&self.source).await,
+        }
+    }
+
+    /// Sync a batch of changes, reporting instead of failing so watching continues
+    async fn sync_changes(&self, changes: &ChangeSet, full_rescan: bool) {
+        let result = if full_rescan || changes.len() > MAX_INCREMENTAL_PATHS {
+            tracing::info!("Running full rescan");
+            self.sync_full().await
+        } else {
+            match &self.backend {
+                WatchBackend::Engine(engine) => engine
+                    .sync_paths(&self.source, &self.destination, changes)
+                    .await
+                    .map_err(Into::into),
+                WatchBackend::Session(session) => {
+                    session.lock().await.sync_paths(&self.source, changes).await
+                }
+            }
+        };
+        match result {
+            Ok(_) => {
+                println!("✓ Sync complete\n");
+            }
+            Err(e) => {
+                eprintln!("✗ Sync failed: {}\n", e);
+            }
+        }
+    }
+
+    /// Ping an idle session so neither side (nor a NAT) drops it
+    async fn keepalive(&self, last_keepalive: &mut Instant, last_sync: Instant) {
+        let WatchBackend::Session(session) = &self.backend else {
+            return;
+        };
+        if last_keepalive.elapsed() < KEEPALIVE_INTERVAL || last_sync.elapsed() < KEEPALIVE_INTERVAL
+        {
+            return;
+        }
+        *last_keepalive = Instant::now();
+        if let Err(e) = session.lock().await.keepalive().await {
+            // The next sync retries the connection
+            tracing::warn!("Session keepalive failed: {}", e);
+        }
+    }
+
     fn should_sync_event(&self, event: &Event) -> bool {
-        use notify::EventKind;
+        is_sync_event(event)
+    }
+}
+
+/// Add the paths of an event to `changes`
+///
+/// Returns false when the event can't be mapped to individual paths
+/// (a queue overflow, or the root itself going away), meaning the next
+/// pass has to rescan the whole tree.
+fn record_event(root: &Path, event: &Event, changes: &mut ChangeSet) -> bool {
+    use notify::event::{ModifyKind, RenameMode};
+    use notify::EventKind;
+
+    if event.need_rescan() || event.paths.is_empty() {
+        return false;
+    }
+
+    let relative = |path: &PathBuf| path.strip_prefix(root).ok().map(Path::to_path_buf);
+
+    if let EventKind::Modify(ModifyKind::Name(RenameMode::Both)) = event.kind {
+        if let [from, to] = event.paths.as_slice() {
+            match (relative(from), relative(to)) {
+                (Some(from), Some(to)) => changes.insert_rename(from, to),
+                // Moved across the root boundary: one side is a plain change
+                (Some(path), None) | (None, Some(path)) => changes.insert(path),
+                (None, None) => {}
+            }
+            return true;
+        }
+    }
+
+    for path in &event.paths {
+        match relative(path) {
+            Some(rel) if rel.as_os_str().is_empty() => {
+                if matches!(event.kind, EventKind::Remove(_)) {
+                    return false;
+                }
+            }
+            Some(rel) => changes.insert(rel),
+            None => {}
+        }
+    }
+    true
+}
+
+fn is_sync_event(event: &Event) -> bool {
+    use notify::EventKind;
+
+    match event.kind {
+        // File created, modified, or removed
+        EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_) | EventKind::Other => {
+            true
+        }
+        // Ignore metadata-only changes (access time, etc.)
+        _ => false,
+    }
+}
+
+/// Continuous bidirectional sync (`--watch --bidirectional`)
+///
+/// Local roots are watched with notify; remote roots, network filesystems and
+/// roots under `--watch-poll` cannot be, so a pass also runs every
+/// `poll_interval` when any side is left unwatched. Every pass goes
+/// through the same `BisyncSession`, which holds the pair lock and the
+/// loaded state for the whole run.
+pub struct BisyncWatchMode {
+    session: BisyncSession,
+    watched_roots: Vec<PathBuf>,
+    poll_interval: Option<Duration>,
+    debounce: Duration,
+}
+
+impl BisyncWatchMode {
+    pub fn new(
+        session: BisyncSession,
+        watched_roots: Vec<PathBuf>,
+        poll_interval: Option<Duration>,
+        debounce: Duration,
+    ) -> Self {
+        Self {
+            session,
+            watched_roots,
+            poll_interval,
+            debounce,
+        }
+    }
+
+    pub async fn watch(&mut self) -> Result<()> {
+        // Set up file watchers for local sides first, so edits made during
+        // the initial pass are seen
+        let (tx, rx) = channel();
+        let mut watcher: RecommendedWatcher = notify::recommended_watcher(tx)?;
+        for root in &self.watched_roots {
+            watcher.watch(root, RecursiveMode::Recursive)?;
+        }
+
+        // Initial sync
+        tracing::info!("Running initial bidirectional sync...");
+        self.run_pass().await;
+
+        println!(
+            "\n🔍 Watching {} ↔ {} for changes (Ctrl+C to stop)...\n",
+            self.session.source().display(),
+            self.session.dest().display()
+        );
+        if let Some(interval) = self.poll_interval {
+            println!("   Polling for changes every {}s\n", interval.as_secs());
+        }
+
+        let mut pending_changes = 0usize;
+        let mut last_sync = Instant::now();
+
+        let ctrl_c = signal::ctrl_c();
+        tokio::pin!(ctrl_c);
+
+        loop {
+            tokio::select! {
+                _ = &mut ctrl_c => {
+                    println!("\n⏹️  Stopping watch mode...");
+                    break;
+                }
+                _ = tokio::time::sleep(Duration::from_millis(10)) => {}
+            }
+
+            match rx.recv_timeout(Duration::from_millis(100)) {
+                Ok(Ok(event)) => {
+                    if is_sync_event(&event) && !is_trash_event(&event) {
+                        pending_changes += 1;
+                    }
+                }
+                Ok(Err(e)) => {
+                    tracing::error!("Watch error: {}", e);
+                    // Force a pass on error to ensure consistency
+                    pending_changes += 1;
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    let changed = pending_changes > 0 && last_sync.elapsed() >= self.debounce;
+                    let poll_due = self
+                        .poll_interval
+                        .is_some_and(|interval| last_sync.elapsed() >= interval);
 
-        match event.kind {
-            // File created, modified, or removed
-            EventKind::Create(_)
-            | EventKind::Modify(_)
-            | EventKind::Remove(_)
-            | EventKind::Other => true,
-            // Ignore metadata-only changes (access time, etc.)
-            _ => false,
+                    if changed || poll_due {
+                        if changed {
+                            tracing::info!("Detected {} changes, syncing...", pending_changes);
+                            println!("📝 Changes detected, syncing...");
+                        }
+                        // Events queued so far are covered by this pass. Events
+                        // arriving while it runs stay queued for the next one:
+                        // they may be edits the pass missed, and the pass's own
+                        // writes only cost one pass that finds nothing to do.
+                        while rx.try_recv().is_ok() {}
+                        self.run_pass().await;
+                        pending_changes = 0;
+                        last_sync = Instant::now();
+                    }
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    tracing::error!("File watcher disconnected unexpectedly");
+                    eprintln!("❌ File watcher stopped. Exiting.");
+                    break;
+                }
+            }
         }
+
+        Ok(())
     }
+
+    /// Run one pass, reporting instead of failing so watching continues
+    async fn run_pass(&mut self) {
+        match self.session.sync_once().await {
+            Ok(result) => {
+                let stats = &result.stats;
+                let changed = stats.files_synced_to_dest
+                    + stats.files_synced_to_source
+                    + stats.files_deleted_from_source
+                    + stats.files_deleted_from_dest
+                    + stats.files_moved;
+                if changed > 0 {
+                    println!(
+                        "✓ Synced: {} → dest, {} → source, {} deleted, {} moved\n",
+                        stats.files_synced_to_dest,
+                        stats.files_synced_to_source,
+                        stats.files_deleted_from_source + stats.files_deleted_from_dest,
+                        stats.files_moved
+                    );
+                }
+                for conflict in &result.conflicts {
+                    println!("  ⚠ {} - {}", conflict.path.display(), conflict.action);
+                }
+                for error in &result.errors {
+                    eprintln!("  ✗ {}", error);
+                }
+            }
+            Err(e) => {
+                eprintln!("✗ Sync failed: {}\n", e);
+            }
+        }
+    }
+}
+
+/// Whether every path of an event is inside a trash directory
+fn is_trash_event(event: &Event) -> bool {
+    !event.paths.is_empty()
+        && event
+            .paths
+            .iter()
+            .all(|p| p.components().any(|c| c.as_os_str() == TRASH_DIR))
 }
 
 #[cfg(test)]
@@ -266,4 +645,48 @@ mod tests {
         let access_event = Event::new(EventKind::Access(notify::event::AccessKind::Read));
         assert!(!watch_mode.should_sync_event(&access_event));
     }
+
+    #[test]
+    fn test_trash_events_are_ignored() {
+        use notify::{Event, EventKind};
+
+        let trashed = Event::new(EventKind::Create(notify::event::CreateKind::File))
+            .add_path(PathBuf::from("/src/.sy-trash/1700000000/a.txt"));
+        assert!(is_trash_event(&trashed));
+
+        let regular = Event::new(EventKind::Create(notify::event::CreateKind::File))
+            .add_path(PathBuf::from("/src/a.txt"));
+        assert!(!is_trash_event(&regular));
+
+        // A rename out of the trash (a restore) must still trigger a pass
+        let restored = Event::new(EventKind::Modify(notify::event::ModifyKind::Any))
+            .add_path(PathBuf::from("/src/.sy-trash/1700000000/a.txt"))
+            .add_path(PathBuf::from("/src/a.txt"));
+        assert!(!is_trash_event(&restored));
+    }
+
+    #[test]
+    fn test_record_event_maps_paths() {
+        use notify::event::{CreateKind, Flag, ModifyKind, RemoveKind, RenameMode};
+        use notify::EventKind;
+
+        let root = PathBuf::from("/src");
+        let mut changes = ChangeSet::new();
+
+        let created =
+            Event::new(EventKind::Create(CreateKind::File)).add_path(root.join("dir/a.txt"));
+        assert!(record_event(&root, &created, &mut changes));
+
+        let renamed = Event::new(EventKind::Modify(ModifyKind::Name(RenameMode::Both)))
+            .add_path(root.join("old.txt"))
+            .add_path(root.join("new.txt"));
+        assert!(record_event(&root, &renamed, &mut changes));
+        assert_eq!(changes.len(), 3);
+
+        // Losing events or the root itself forces a full rescan
+        let overflow = Event::new(EventKind::Other).set_flag(Flag::Rescan);
+        assert!(!record_event(&root, &overflow, &mut changes));
+        let root_removed = Event::new(EventKind::Remove(RemoveKind::Folder)).add_path(root.clone());
+        assert!(!record_event(&root, &root_removed, &mut changes));
+    }
 }
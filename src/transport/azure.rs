@@ -0,0 +1,680 @@
+use super::cloud::CloudClientOptions;
+use super::{FileInfo, TransferResult, Transport};
+use crate::error::{Result, SyncError};
+use crate::sync::scanner::{FileEntry, ScanOptions};
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::stream::BoxStream;
+use object_store::azure::MicrosoftAzureBuilder;
+use object_store::path::Path as ObjectPath;
+use object_store::ObjectStore;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+/// Azure Blob Storage configuration for explicit credential and client management
+///
+/// This struct allows configuring Azure credentials and HTTP client behavior
+/// explicitly from code, rather than relying solely on environment variables.
+#[derive(Debug, Clone, Default)]
+pub struct AzureConfig {
+    // === Credentials ===
+    /// Storage account name (overrides `?account=` and AZURE_STORAGE_ACCOUNT_NAME)
+    pub account: Option<String>,
+    /// Storage account access key
+    pub access_key: Option<String>,
+    /// Custom blob endpoint URL (e.g., for sovereign clouds)
+    pub endpoint: Option<String>,
+    /// Talk to a local Azurite emulator instead of Azure
+    pub use_emulator: bool,
+
+    // === Client Options ===
+    /// HTTP client and retry configuration
+    pub client_options: Option<CloudClientOptions>,
+}
+
+/// Azure transport for Azure Blob Storage operations
+///
+/// Authentication is handled via:
+/// - AZURE_STORAGE_ACCOUNT_NAME / AZURE_STORAGE_ACCOUNT_KEY environment variables
+/// - AZURE_STORAGE_SAS_KEY, or service principal variables (AZURE_CLIENT_ID, ...)
+/// - Managed identity (when running on Azure)
+/// - AZURE_STORAGE_USE_EMULATOR=true for the Azurite emulator
+pub struct AzureTransport {
+    store: Arc<dyn ObjectStore>,
+    prefix: String, // Key prefix for all operations
+}
+
+impl AzureTransport {
+    /// Create a new Azure transport
+    ///
+    /// # Arguments
+    /// * `container` - Blob container name
+    /// * `prefix` - Key prefix (e.g., "backups/")
+    /// * `account` - Optional storage account name (otherwise from the environment)
+    pub async fn new(container: String, prefix: String, account: Option<String>) -> Result<Self> {
+        Self::with_config(container, prefix, account, None, 10).await
+    }
+
+    /// Create a new Azure transport with explicit configuration
+    ///
+    /// # Arguments
+    /// * `container` - Blob container name
+    /// * `prefix` - Key prefix (e.g., "backups/")
+    /// * `account` - Optional storage account name
+    /// * `config` - Optional AzureConfig with explicit credentials and client options
+    /// * `max_connections` - Maximum number of concurrent HTTP connections
+    ///
+    /// # Client Options
+    /// If `config.client_options` is provided, those settings are used.
+    /// Otherwise, sensible defaults optimized for sync operations are applied.
+    pub async fn with_config(
+        container: String,
+        prefix: String,
+        account: Option<String>,
+        config: Option<AzureConfig>,
+        max_connections: usize,
+    ) -> Result<Self> {
+        // Get client options from config or use defaults
+        let cloud_options = config
+            .as_ref()
+            .and_then(|c| c.client_options.clone())
+            .unwrap_or_default();
+
+        // Build object_store client options
+        let client_options = cloud_options.to_client_options(max_connections);
+        let retry_config = cloud_options.to_retry_config();
+
+        // Build Azure store with object_store
+        let mut builder = MicrosoftAzureBuilder::from_env()
+            .with_container_name(&container)
+            .with_client_options(client_options)
+            .with_retry(retry_config);
+
+        // Account from the path (?account=...)
+        if let Some(account) = account {
+            builder = builder.with_account(account);
+        }
+
+        // Apply explicit config if provided
+        if let Some(cfg) = &config {
+            if let Some(account) = &cfg.account {
+                builder = builder.with_account(account);
+            }
+            if let Some(key) = &cfg.access_key {
+                builder = builder.with_access_key(key);
+            }
+            if let Some(endpoint) = &cfg.endpoint {
+                builder = builder.with_endpoint(endpoint.clone());
+            }
+            if cfg.use_emulator {
+                builder = builder.with_use_emulator(true);
+            }
+        }
+
+        let pool_size = max_connections.max(cloud_options.pool_max_idle_per_host);
+        let store = Arc::new(builder.build().map_err(|e| {
+            SyncError::Io(std::io::Error::other(format!(
+                "Failed to create Azure client: {}",
+                e
+            )))
+        })?);
+
+        tracing::info!(
+            "Azure transport initialized: pool_size={}, timeout={}s, retries={}",
+            pool_size,
+            cloud_options.request_timeout_secs,
+            cloud_options.max_retries
+        );
+
+        Ok(Self { store, prefix })
+    }
+
+    /// Create a transport over an existing store (e.g. `InMemory` in tests)
+    #[cfg(test)]
+    fn from_store(store: Arc<dyn ObjectStore>, prefix: String) -> Self {
+        Self { store, prefix }
+    }
+
+    /// Convert a local path to an object store path
+    ///
+    /// If the path already starts with the prefix, use it as-is.
+    /// Otherwise, prepend the prefix.
+    fn path_to_object_path(&self, path: &Path) -> ObjectPath {
+        let path_str = path.to_string_lossy();
+        let path_str = path_str.trim_start_matches('/');
+
+        let key = if self.prefix.is_empty() {
+            path_str.to_string()
+        } else {
+            // Don't double-prefix: if path already starts with prefix, use it as-is
+            let prefix_trimmed = self.prefix.trim_end_matches('/');
+            if path_str.starts_with(prefix_trimmed) {
+                path_str.to_string()
+            } else {
+                format!("{}/{}", prefix_trimmed, path_str)
+            }
+        };
+
+        ObjectPath::from(key)
+    }
+
+    /// Convert an object store path to a local path
+    fn object_path_to_path(&self, object_path: &ObjectPath) -> PathBuf {
+        let key = object_path.as_ref();
+        let key = if !self.prefix.is_empty() {
+            key.strip_prefix(&self.prefix)
+                .unwrap_or(key)
+                .trim_start_matches('/')
+        } else {
+            key
+        };
+        PathBuf::from(key)
+    }
+}
+
+#[async_trait]
+impl Transport for AzureTransport {
+    fn set_scan_options(&mut self, _options: ScanOptions) {
+        // Azure transport currently ignores scan options
+    }
+
+    async fn scan(&self, _path: &Path) -> Result<Vec<FileEntry>> {
+        use futures::stream::StreamExt;
+
+        let prefix = if self.prefix.is_empty() {
+            None
+        } else {
+            Some(ObjectPath::from(self.prefix.clone()))
+        };
+
+        let mut entries = Vec::new();
+        let mut list_stream = self.store.list(prefix.as_ref());
+
+        while let Some(meta) = list_stream.next().await {
+            let meta = meta.map_err(|e| {
+                SyncError::Io(std::io::Error::other(format!(
+                    "Failed to retrieve object metadata: {}",
+                    e
+                )))
+            })?;
+
+            let key = meta.location.as_ref();
+            let size = meta.size;
+            let modified = meta.last_modified.into();
+
+            // Check if this is a directory marker:
+            // 1. Objects ending with / are always directory markers
+            // 2. 0-byte objects without file extension are likely directory markers
+            //    (created by cloud consoles or other tools)
+            let is_dir =
+                key.ends_with('/') || (size == 0 && !key.contains('.') && !key.ends_with('/'));
+
+            entries.push(FileEntry {
+                path: Arc::new(PathBuf::from(key)),
+                relative_path: Arc::new(self.object_path_to_path(&meta.location)),
+                size,
+                modified,
+                is_dir,
+                is_symlink: false, // Azure doesn't have symlinks
+                symlink_target: None,
+                is_sparse: false,
+                allocated_size: size,
+                xattrs: None,
+                inode: None,
+                nlink: 1,
+                acls: None,
+                bsd_flags: None,
+            });
+        }
+
+        Ok(entries)
+    }
+
+    async fn scan_flat(&self, _path: &Path) -> Result<Vec<FileEntry>> {
+        use object_store::path::Path as ObjectPath;
+
+        let prefix = if self.prefix.is_empty() {
+            None
+        } else {
+            Some(ObjectPath::from(self.prefix.clone()))
+        };
+
+        tracing::debug!(
+            "Starting Azure flat listing with prefix: {:?} (using delimiter)",
+            prefix
+        );
+
+        // Use list_with_delimiter for efficient non-recursive listing
+        let list_result = self
+            .store
+            .list_with_delimiter(prefix.as_ref())
+            .await
+            .map_err(|e| {
+                SyncError::Io(std::io::Error::other(format!(
+                    "Failed to list Azure objects: {}",
+                    e
+                )))
+            })?;
+
+        let mut entries = Vec::new();
+
+        let object_count = list_result.objects.len();
+        let dir_count = list_result.common_prefixes.len();
+
+        // Process regular objects (files at this level)
+        for meta in list_result.objects {
+            let key = meta.location.as_ref();
+            let size = meta.size;
+            let modified = meta.last_modified.into();
+
+            entries.push(FileEntry {
+                path: Arc::new(PathBuf::from(key)),
+                relative_path: Arc::new(self.object_path_to_path(&meta.location)),
+                size,
+                modified,
+                is_dir: false,
+                is_symlink: false,
+                symlink_target: None,
+                is_sparse: false,
+                allocated_size: size,
+                xattrs: None,
+                inode: None,
+                nlink: 1,
+                acls: None,
+                bsd_flags: None,
+            });
+        }
+
+        // Process common prefixes (directories at this level)
+        for prefix_path in list_result.common_prefixes {
+            let key = prefix_path.as_ref();
+
+            entries.push(FileEntry {
+                path: Arc::new(PathBuf::from(key)),
+                relative_path: Arc::new(self.object_path_to_path(&prefix_path)),
+                size: 0,
+                modified: std::time::SystemTime::UNIX_EPOCH,
+                is_dir: true,
+                is_symlink: false,
+                symlink_target: None,
+                is_sparse: false,
+                allocated_size: 0,
+                xattrs: None,
+                inode: None,
+                nlink: 1,
+                acls: None,
+                bsd_flags: None,
+            });
+        }
+
+        tracing::info!(
+            "Azure flat listing complete: {} entries ({} files, {} dirs)",
+            entries.len(),
+            object_count,
+            dir_count
+        );
+        Ok(entries)
+    }
+
+    async fn scan_streaming(&self, _path: &Path) -> Result<BoxStream<'static, Result<FileEntry>>> {
+        use futures::stream::StreamExt;
+
+        let prefix = if self.prefix.is_empty() {
+            None
+        } else {
+            Some(ObjectPath::from(self.prefix.clone()))
+        };
+
+        // Clone for closure
+        let prefix_str = self.prefix.clone();
+
+        let stream = self.store.list(prefix.as_ref());
+
+        let mapped = stream.map(move |meta_res| {
+            let meta = meta_res.map_err(|e| {
+                SyncError::Io(std::io::Error::other(format!(
+                    "Failed to retrieve object metadata: {}",
+                    e
+                )))
+            })?;
+
+            let key = meta.location.as_ref();
+            let size = meta.size;
+            let modified = meta.last_modified.into();
+
+            // Check if this is a directory marker:
+            // 1. Objects ending with / are always directory markers
+            // 2. 0-byte objects without file extension are likely directory markers
+            let is_dir =
+                key.ends_with('/') || (size == 0 && !key.contains('.') && !key.ends_with('/'));
+
+            // Replicate object_path_to_path logic locally to avoid self capture
+            let relative_key = if !prefix_str.is_empty() {
+                key.strip_prefix(&prefix_str)
+                    .unwrap_or(key)
+                    .trim_start_matches('/')
+            } else {
+                key
+            };
+            let relative_path = PathBuf::from(relative_key);
+
+            Ok(FileEntry {
+                path: Arc::new(PathBuf::from(key)),
+                relative_path: Arc::new(relative_path),
+                size,
+                modified,
+                is_dir,
+                is_symlink: false, // Azure doesn't have symlinks
+                symlink_target: None,
+                is_sparse: false,
+                allocated_size: size,
+                xattrs: None,
+                inode: None,
+                nlink: 1,
+                acls: None,
+                bsd_flags: None,
+            })
+        });
+
+        Ok(mapped.boxed())
+    }
+
+    async fn exists(&self, path: &Path) -> Result<bool> {
+        let object_path = self.path_to_object_path(path);
+        let result = self.store.head(&object_path).await;
+        Ok(result.is_ok())
+    }
+
+    async fn metadata(&self, _path: &Path) -> Result<std::fs::Metadata> {
+        // Azure doesn't have std::fs::Metadata, this method shouldn't be used
+        Err(SyncError::Io(std::io::Error::other(
+            "metadata() not supported for Azure, use file_info() instead",
+        )))
+    }
+
+    async fn file_info(&self, path: &Path) -> Result<FileInfo> {
+        let object_path = self.path_to_object_path(path);
+
+        let meta = self.store.head(&object_path).await.map_err(|e| {
+            SyncError::Io(std::io::Error::other(format!(
+                "Failed to get Azure object metadata: {}",
+                e
+            )))
+        })?;
+
+        Ok(FileInfo {
+            size: meta.size,
+            modified: meta.last_modified.into(),
+        })
+    }
+
+    async fn create_dir_all(&self, path: &Path) -> Result<()> {
+        // Azure doesn't have directories in the traditional sense
+        // We can create a directory marker object (key ending with /)
+        let mut key_str = self.path_to_object_path(path).to_string();
+        if !key_str.ends_with('/') {
+            key_str.push('/');
+        }
+        let object_path = ObjectPath::from(key_str);
+
+        self.store
+            .put(&object_path, Bytes::new().into())
+            .await
+            .map_err(|e| {
+                SyncError::Io(std::io::Error::other(format!(
+                    "Failed to create Azure directory marker: {}",
+                    e
+                )))
+            })?;
+
+        Ok(())
+    }
+
+    async fn copy_file(&self, source: &Path, dest: &Path) -> Result<TransferResult> {
+        use tokio::io::AsyncReadExt;
+
+        let metadata = tokio::fs::metadata(source).await?;
+        let size = metadata.len();
+        let object_path = self.path_to_object_path(dest);
+
+        // Use streaming multipart upload for large files to avoid loading into memory
+        // For small files (<5MB), use simple put for efficiency
+        const MULTIPART_THRESHOLD: u64 = 5 * 1024 * 1024; // 5MB
+
+        if size < MULTIPART_THRESHOLD {
+            // Small file: use simple put (one API call)
+            let data = tokio::fs::read(source).await?;
+            self.store
+                .put(&object_path, Bytes::from(data).into())
+                .await
+                .map_err(|e| {
+                    SyncError::Io(std::io::Error::other(format!(
+                        "Failed to upload to Azure: {}",
+                        e
+                    )))
+                })?;
+        } else {
+            // Large file: use multipart upload (streaming, no memory buffering)
+            use object_store::WriteMultipart;
+
+            let mut file = tokio::fs::File::open(source).await?;
+            let upload = self.store.put_multipart(&object_path).await.map_err(|e| {
+                SyncError::Io(std::io::Error::other(format!(
+                    "Failed to initiate multipart upload: {}",
+                    e
+                )))
+            })?;
+
+            // WriteMultipart handles chunking automatically (5MB chunks)
+            let mut writer = WriteMultipart::new(upload);
+
+            // Stream file in chunks
+            const BUFFER_SIZE: usize = 5 * 1024 * 1024;
+            let mut buffer = vec![0u8; BUFFER_SIZE];
+
+            loop {
+                let bytes_read = file.read(&mut buffer).await?;
+                if bytes_read == 0 {
+                    break;
+                }
+
+                // write() is synchronous by design - it buffers data and starts uploads automatically
+                // Errors are reported via finish()
+                writer.write(&buffer[..bytes_read]);
+            }
+
+            // finish() waits for all uploads to complete and reports any errors
+            writer.finish().await.map_err(|e| {
+                SyncError::Io(std::io::Error::other(format!(
+                    "Failed to complete multipart upload: {}",
+                    e
+                )))
+            })?;
+        }
+
+        Ok(TransferResult::new(size))
+    }
+
+    async fn remove(&self, path: &Path, _is_dir: bool) -> Result<()> {
+        let object_path = self.path_to_object_path(path);
+
+        self.store.delete(&object_path).await.map_err(|e| {
+            SyncError::Io(std::io::Error::other(format!(
+                "Failed to delete Azure object: {}",
+                e
+            )))
+        })?;
+
+        Ok(())
+    }
+
+    async fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        let from_path = self.path_to_object_path(from);
+        let to_path = self.path_to_object_path(to);
+
+        // Object stores implement rename as server-side copy + delete
+        self.store.rename(&from_path, &to_path).await.map_err(|e| {
+            SyncError::Io(std::io::Error::other(format!(
+                "Failed to rename Azure object: {}",
+                e
+            )))
+        })?;
+
+        Ok(())
+    }
+
+    async fn create_hardlink(&self, _source: &Path, _dest: &Path) -> Result<()> {
+        Err(SyncError::Io(std::io::Error::other(
+            "Hardlinks not supported on Azure",
+        )))
+    }
+
+    async fn create_symlink(&self, _target: &Path, _dest: &Path) -> Result<()> {
+        Err(SyncError::Io(std::io::Error::other(
+            "Symlinks not supported on Azure",
+        )))
+    }
+
+    async fn read_file(&self, path: &Path) -> Result<Vec<u8>> {
+        let object_path = self.path_to_object_path(path);
+
+        let result = self.store.get(&object_path).await.map_err(|e| {
+            SyncError::Io(std::io::Error::other(format!(
+                "Failed to download from Azure: {}",
+                e
+            )))
+        })?;
+
+        let bytes = result.bytes().await.map_err(|e| {
+            SyncError::Io(std::io::Error::other(format!(
+                "Failed to read Azure object body: {}",
+                e
+            )))
+        })?;
+
+        Ok(bytes.to_vec())
+    }
+
+    async fn write_file(&self, path: &Path, data: &[u8], _mtime: SystemTime) -> Result<()> {
+        let object_path = self.path_to_object_path(path);
+
+        self.store
+            .put(&object_path, Bytes::copy_from_slice(data).into())
+            .await
+            .map_err(|e| {
+                SyncError::Io(std::io::Error::other(format!(
+                    "Failed to upload to Azure: {}",
+                    e
+                )))
+            })?;
+
+        Ok(())
+    }
+
+    async fn get_mtime(&self, path: &Path) -> Result<SystemTime> {
+        let info = self.file_info(path).await?;
+        Ok(info.modified)
+    }
+
+    async fn check_disk_space(&self, _path: &Path, _bytes_needed: u64) -> Result<()> {
+        // Azure has virtually unlimited storage, so no disk space check needed
+        Ok(())
+    }
+
+    async fn set_bsd_flags(&self, _path: &Path, _flags: u32) -> Result<()> {
+        // BSD flags are not supported on Azure - silently ignore
+        Ok(())
+    }
+
+    async fn set_xattrs(&self, _path: &Path, _xattrs: &[(String, Vec<u8>)]) -> Result<()> {
+        // Extended attributes are not supported on Azure - silently ignore
+        Ok(())
+    }
+
+    async fn set_acls(&self, _path: &Path, _acls_text: &str) -> Result<()> {
+        // ACLs are not supported on Azure - silently ignore
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use object_store::memory::InMemory;
+    use tempfile::TempDir;
+
+    fn in_memory(prefix: &str) -> AzureTransport {
+        AzureTransport::from_store(Arc::new(InMemory::new()), prefix.to_string())
+    }
+
+    #[test]
+    fn test_path_to_object_path() {
+        let transport = in_memory("backups/");
+        assert_eq!(
+            transport
+                .path_to_object_path(Path::new("docs/a.txt"))
+                .as_ref(),
+            "backups/docs/a.txt"
+        );
+        // Already-prefixed paths are not prefixed twice
+        assert_eq!(
+            transport
+                .path_to_object_path(Path::new("backups/docs/a.txt"))
+                .as_ref(),
+            "backups/docs/a.txt"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_upload_scan_and_read() {
+        let tmp = TempDir::new().unwrap();
+        let local = tmp.path().join("a.txt");
+        std::fs::write(&local, "hello").unwrap();
+
+        let transport = in_memory("backups");
+        let result = transport
+            .copy_file(&local, Path::new("docs/a.txt"))
+            .await
+            .unwrap();
+        assert_eq!(result.bytes_written, 5);
+
+        assert!(transport.exists(Path::new("docs/a.txt")).await.unwrap());
+        assert_eq!(
+            transport
+                .file_info(Path::new("docs/a.txt"))
+                .await
+                .unwrap()
+                .size,
+            5
+        );
+        assert_eq!(
+            transport.read_file(Path::new("docs/a.txt")).await.unwrap(),
+            b"hello"
+        );
+
+        let entries = transport.scan(Path::new("")).await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(*entries[0].relative_path, PathBuf::from("docs/a.txt"));
+        assert!(!entries[0].is_dir);
+    }
+
+    #[tokio::test]
+    async fn test_rename_and_remove() {
+        let transport = in_memory("");
+        transport
+            .write_file(Path::new("old.txt"), b"data", SystemTime::now())
+            .await
+            .unwrap();
+
+        transport
+            .rename(Path::new("old.txt"), Path::new("new.txt"))
+            .await
+            .unwrap();
+        assert!(!transport.exists(Path::new("old.txt")).await.unwrap());
+        assert!(transport.exists(Path::new("new.txt")).await.unwrap());
+
+        transport.remove(Path::new("new.txt"), false).await.unwrap();
+        assert!(!transport.exists(Path::new("new.txt")).await.unwrap());
+    }
+}
@@ -1,4 +1,4 @@
-//! Shared types and utilities for cloud storage transports (S3, GCS)
+//! Shared types and utilities for cloud storage transports (S3, GCS, Azure)
 //!
 //! This module contains configuration that is common to all cloud storage
 //! backends, avoiding duplication and ensuring consistent behavior.
@@ -6,11 +6,11 @@
 use object_store::{ClientOptions, RetryConfig};
 use std::time::Duration;
 
-/// HTTP client options for cloud storage transports (S3, GCS)
+/// HTTP client options for cloud storage transports (S3, GCS, Azure)
 ///
 /// These options control connection pooling, timeouts, and retry behavior
-/// for HTTP requests to cloud storage services. They apply to both S3
-/// and GCS transports.
+/// for HTTP requests to cloud storage services. They apply to the S3,
+/// GCS and Azure transports.
 ///
 /// # Performance Tuning
 ///
@@ -29,6 +29,12 @@ impl Transport for DualTransport {
         self.source.set_scan_options(options);
     }
 
+    fn set_block_size(&mut self, policy: crate::delta::BlockSizePolicy) {
+        // Either side may end up computing the delta (see sync_file_with_delta)
+        self.source.set_block_size(policy.clone());
+        self.dest.set_block_size(policy);
+    }
+
     async fn prepare_for_transfer(&self, file_count: usize) -> Result<()> {
         // Prepare both source and destination transports
         // (both might be SSH and need pool expansion)
@@ -37,6 +43,11 @@ impl Transport for DualTransport {
         Ok(())
     }
 
+    fn is_sftp_only(&self) -> bool {
+        // Either side being SFTP-only rules out the server protocol
+        self.source.is_sftp_only() || self.dest.is_sftp_only()
+    }
+
     async fn scan(&self, path: &Path) -> Result<Vec<FileEntry>> {
         // Always scan from source
         self.source.scan(path).await
@@ -179,11 +190,36 @@ impl Transport for DualTransport {
         }
     }
 
+    async fn sync_file_with_basis(
+        &self,
+        source: &Path,
+        basis: &Path,
+        dest: &Path,
+    ) -> Result<TransferResult> {
+        // The basis lives on the destination, so only the destination
+        // transport can delta against it (local→remote)
+        match self.dest.sync_file_with_basis(source, basis, dest).await {
+            Ok(result) => Ok(result),
+            Err(e) => {
+                tracing::debug!(
+                    "DualTransport: basis delta failed ({}), falling back to full copy",
+                    e
+                );
+                self.copy_file(source, dest).await
+            }
+        }
+    }
+
     async fn remove(&self, path: &Path, is_dir: bool) -> Result<()> {
         // Remove from destination
         self.dest.remove(path, is_dir).await
     }
 
+    async fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        // Rename on destination
+        self.dest.rename(from, to).await
+    }
+
     async fn create_hardlink(&self, source: &Path, dest: &Path) -> Result<()> {
         // Create hardlink on destination
         self.dest.create_hardlink(source, dest).await
@@ -514,6 +514,21 @@ impl Transport for GcsTransport {
         Ok(())
     }
 
+    async fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        let from_path = self.path_to_object_path(from);
+        let to_path = self.path_to_object_path(to);
+
+        // Object stores implement rename as server-side copy + delete
+        self.store.rename(&from_path, &to_path).await.map_err(|e| {
+            SyncError::Io(std::io::Error::other(format!(
+                "Failed to rename GCS object: {}",
+                e
+            )))
+        })?;
+
+        Ok(())
+    }
+
     async fn create_hardlink(&self, _source: &Path, _dest: &Path) -> Result<()> {
         Err(SyncError::Io(std::io::Error::other(
             "Hardlinks not supported on GCS",
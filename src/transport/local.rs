@@ -1,4 +1,5 @@
 use super::{TransferResult, Transport};
+use crate::delta::{BlockSize, BlockSizePolicy};
 use crate::error::{format_bytes, Result, SyncError};
 use crate::fs_util::{has_hard_links, same_filesystem, supports_cow_reflinks};
 use crate::integrity::{ChecksumType, IntegrityVerifier};
@@ -200,6 +201,7 @@ fn copy_sparse_file(source: &Path, dest: &Path) -> std::io::Result<u64> {
 pub struct LocalTransport {
     verifier: IntegrityVerifier,
     scan_options: ScanOptions,
+    block_sizes: BlockSizePolicy,
 }
 
 impl LocalTransport {
@@ -208,6 +210,7 @@ impl LocalTransport {
         Self {
             verifier: IntegrityVerifier::new(ChecksumType::None, false),
             scan_options: ScanOptions::default(),
+            block_sizes: BlockSizePolicy::default(),
         }
     }
 
@@ -215,6 +218,7 @@ impl LocalTransport {
         Self {
             verifier,
             scan_options: ScanOptions::default(),
+            block_sizes: BlockSizePolicy::default(),
         }
     }
 
@@ -237,6 +241,10 @@ impl Transport for LocalTransport {
         self.scan_options = options;
     }
 
+    fn set_block_size(&mut self, policy: BlockSizePolicy) {
+        self.block_sizes = policy;
+    }
+
     async fn scan(&self, path: &Path) -> Result<Vec<FileEntry>> {
         // Use existing scanner (runs synchronously, wrapped in async)
         let path = path.to_path_buf();
@@ -525,13 +533,19 @@ impl Transport for LocalTransport {
         let source = source.to_path_buf();
         let dest = dest.to_path_buf();
         let verifier = self.verifier.clone();
+        let block_sizes = self.block_sizes.clone();
 
         tokio::task::spawn_blocking(move || {
             use crate::delta::estimate_change_ratio;
             use std::io::{BufReader, Read, Seek, SeekFrom, Write};
             use std::time::Instant;
 
-            let block_size = 64 * 1024; // 64KB blocks for good I/O performance
+            // Blocks are compared in place, not searched for, so they only need
+            // to be large enough for good I/O; a fixed --block-size still applies
+            let block_size = match block_sizes.mode() {
+                BlockSize::Fixed(size) => size,
+                BlockSize::Auto | BlockSize::Adaptive => 64 * 1024,
+            };
             let total_start = Instant::now();
 
             // Check if source file is sparse FIRST (before change ratio)
@@ -572,6 +586,7 @@ impl Transport for LocalTransport {
 
             match change_ratio_result {
                 Ok(ratio) => {
+                    block_sizes.record(&source, ratio.change_ratio);
                     tracing::info!(
                         "Change ratio: {} ({}/{} blocks changed)",
                         ratio.change_ratio_percent(),
@@ -959,6 +974,81 @@ impl Transport for LocalTransport {
         .map_err(|e| SyncError::Io(std::io::Error::other(e.to_string())))?
     }
 
+    async fn sync_file_with_basis(
+        &self,
+        source: &Path,
+        basis: &Path,
+        dest: &Path,
+    ) -> Result<TransferResult> {
+        let source = source.to_path_buf();
+        let basis = basis.to_path_buf();
+        let dest = dest.to_path_buf();
+        let block_sizes = self.block_sizes.clone();
+
+        tokio::task::spawn_blocking(move || {
+            use crate::delta::{apply_delta, compute_checksums, generate_delta_streaming};
+
+            let delta_error = |path: &Path, e: std::io::Error| SyncError::DeltaSyncError {
+                path: path.to_path_buf(),
+                strategy: "fuzzy basis".to_string(),
+                source: e,
+                hint: format!("Delta against basis {} failed.", basis.display()),
+            };
+
+            let source_meta = fs::metadata(&source).map_err(|e| SyncError::CopyError {
+                path: source.clone(),
+                source: e,
+            })?;
+            let basis_size = fs::metadata(&basis)
+                .map_err(|e| delta_error(&basis, e))?
+                .len();
+
+            let block_size = block_sizes.block_size(&source, basis_size);
+            let checksums =
+                compute_checksums(&basis, block_size).map_err(|e| delta_error(&basis, e))?;
+            let delta = generate_delta_streaming(&source, &checksums, block_size)
+                .map_err(|e| delta_error(&source, e))?;
+
+            let temp_dest = dest.with_extension("sy.tmp");
+            let temp_guard = TempFileGuard::new(&temp_dest);
+            let stats =
+                apply_delta(&basis, &delta, &temp_dest).map_err(|e| delta_error(&temp_dest, e))?;
+
+            fs::set_permissions(&temp_dest, source_meta.permissions()).map_err(|e| {
+                SyncError::CopyError {
+                    path: temp_dest.clone(),
+                    source: e,
+                }
+            })?;
+            if let Ok(mtime) = source_meta.modified() {
+                let _ = filetime::set_file_mtime(
+                    &temp_dest,
+                    filetime::FileTime::from_system_time(mtime),
+                );
+            }
+
+            fs::rename(&temp_dest, &dest).map_err(|e| SyncError::CopyError {
+                path: dest.clone(),
+                source: e,
+            })?;
+            temp_guard.defuse();
+
+            tracing::debug!(
+                "Fuzzy delta from {}: {} ops, {} literal bytes",
+                basis.display(),
+                stats.operations_count,
+                stats.literal_bytes
+            );
+            Ok(TransferResult::with_delta(
+                stats.bytes_written,
+                stats.operations_count,
+                stats.literal_bytes,
+            ))
+        })
+        .await
+        .map_err(|e| SyncError::Io(std::io::Error::other(e.to_string())))?
+    }
+
     async fn remove(&self, path: &Path, is_dir: bool) -> Result<()> {
         if is_dir {
             tokio::fs::remove_dir_all(path)
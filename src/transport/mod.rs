@@ -1,9 +1,11 @@
-// Shared cloud transport types (available when either S3 or GCS is enabled)
-#[cfg(any(feature = "s3", feature = "gcs"))]
+// Shared cloud transport types (available when any cloud backend is enabled)
+#[cfg(any(feature = "s3", feature = "gcs", feature = "azure"))]
 pub mod cloud;
-#[cfg(any(feature = "s3", feature = "gcs"))]
+#[cfg(any(feature = "s3", feature = "gcs", feature = "azure"))]
 pub use cloud::CloudClientOptions;
 
+#[cfg(feature = "azure")]
+pub mod azure;
 pub mod dual;
 #[cfg(feature = "gcs")]
 pub mod gcs;
@@ -13,6 +15,8 @@ pub mod router;
 pub mod s3;
 
 // Re-export config types for convenience
+#[cfg(feature = "azure")]
+pub use azure::AzureConfig;
 #[cfg(feature = "gcs")]
 pub use gcs::GcsConfig;
 #[cfg(feature = "s3")]
@@ -20,7 +24,11 @@ pub use s3::S3Config;
 
 pub mod server;
 #[cfg(feature = "ssh")]
+pub mod sftp;
+#[cfg(feature = "ssh")]
 pub mod ssh;
+#[cfg(feature = "webdav")]
+pub mod webdav;
 
 use crate::error::Result;
 use crate::sync::scanner::FileEntry;
@@ -119,6 +127,11 @@ pub trait Transport: Send + Sync {
         // Default: no-op for transports that don't support scan options
     }
 
+    /// Set the block size policy for delta sync (--block-size)
+    ///
+    /// Default implementation does nothing (for transports without delta sync).
+    fn set_block_size(&mut self, _policy: crate::delta::BlockSizePolicy) {}
+
     /// Prepare the transport for transferring a known number of files
     ///
     /// Called after scanning to allow transports to optimize for the workload.
@@ -129,6 +142,14 @@ pub trait Transport: Send + Sync {
         Ok(())
     }
 
+    /// Whether this transport reaches a remote host over pure SFTP
+    ///
+    /// SFTP-only hosts can't run `sy --server`, so callers must skip the
+    /// server-protocol fast path and go through the transport instead.
+    fn is_sftp_only(&self) -> bool {
+        false
+    }
+
     /// Scan a directory and return all entries (recursive)
     ///
     /// This recursively scans the directory. Behavior is controlled by scan options:
@@ -231,9 +252,45 @@ pub trait Transport: Send + Sync {
         self.copy_file(source, dest).await
     }
 
+    /// Create `dest` by delta-encoding `source` against a different, similar
+    /// destination file (`basis`, used by --fuzzy)
+    ///
+    /// `basis` is only read. Falls back to a full copy when the transport
+    /// can't delta against another file.
+    async fn sync_file_with_basis(
+        &self,
+        source: &Path,
+        _basis: &Path,
+        dest: &Path,
+    ) -> Result<TransferResult> {
+        self.copy_file(source, dest).await
+    }
+
     /// Remove a file or directory
     async fn remove(&self, path: &Path, is_dir: bool) -> Result<()>;
 
+    /// Rename (move) a file within the transport
+    ///
+    /// Used by move detection to turn a copy + delete pair into a single
+    /// rename. Parent directories of `to` are created as needed.
+    /// Default implementation renames on the local filesystem.
+    async fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        if let Some(parent) = to.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::rename(from, to).await.map_err(|e| {
+            crate::error::SyncError::Io(std::io::Error::new(
+                e.kind(),
+                format!(
+                    "Failed to rename {} to {}: {}",
+                    from.display(),
+                    to.display(),
+                    e
+                ),
+            ))
+        })
+    }
+
     /// Create a hard link
     ///
     /// Creates a hard link at `dest` pointing to `source`.
@@ -516,6 +573,10 @@ pub trait Transport: Send + Sync {
 // This allows sharing transports across tasks in parallel execution
 #[async_trait]
 impl<T: Transport + ?Sized> Transport for std::sync::Arc<T> {
+    fn is_sftp_only(&self) -> bool {
+        (**self).is_sftp_only()
+    }
+
     async fn scan(&self, path: &Path) -> Result<Vec<FileEntry>> {
         (**self).scan(path).await
     }
@@ -556,10 +617,23 @@ impl<T: Transport + ?Sized> Transport for std::sync::Arc<T> {
         (**self).sync_file_with_delta(source, dest).await
     }
 
+    async fn sync_file_with_basis(
+        &self,
+        source: &Path,
+        basis: &Path,
+        dest: &Path,
+    ) -> Result<TransferResult> {
+        (**self).sync_file_with_basis(source, basis, dest).await
+    }
+
     async fn remove(&self, path: &Path, is_dir: bool) -> Result<()> {
         (**self).remove(path, is_dir).await
     }
 
+    async fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        (**self).rename(from, to).await
+    }
+
     async fn create_hardlink(&self, source: &Path, dest: &Path) -> Result<()> {
         (**self).create_hardlink(source, dest).await
     }
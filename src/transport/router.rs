@@ -1,10 +1,17 @@
+#[cfg(feature = "azure")]
+use super::azure::AzureTransport;
 #[cfg(feature = "gcs")]
 use super::gcs::GcsTransport;
 #[cfg(feature = "s3")]
 use super::s3::S3Transport;
 #[cfg(feature = "ssh")]
-use super::ssh::SshTransport;
+use super::sftp::SftpTransport;
+#[cfg(feature = "ssh")]
+use super::ssh::{ExecProbeCache, SshTransport};
+#[cfg(feature = "webdav")]
+use super::webdav::WebDavTransport;
 use super::{dual::DualTransport, local::LocalTransport, TransferResult, Transport};
+use crate::delta::BlockSizePolicy;
 use crate::error::Result;
 use crate::integrity::{ChecksumType, IntegrityVerifier};
 use crate::path::SyncPath;
@@ -17,7 +24,7 @@ use std::path::Path;
 
 /// Router that dispatches to the appropriate transport based on path types
 ///
-/// This allows SyncEngine to work with both local, remote, S3, and GCS paths seamlessly.
+/// This allows SyncEngine to work with local, remote, S3, GCS, Azure, and WebDAV paths seamlessly.
 pub enum TransportRouter {
     Local(LocalTransport),
     Dual(DualTransport),
@@ -34,6 +41,10 @@ impl TransportRouter {
     /// - Remote → Local: Use DualTransport (SSH for source, Local for dest)
     /// - Local → Remote: Use DualTransport (Local for source, SSH for dest)
     /// - Remote → Remote: Use DualTransport (SSH for source, SSH for dest)
+    /// - Local ↔ SFTP: Use DualTransport with the SFTP-only transport
+    ///
+    /// SSH hosts that refuse command execution fall back to the SFTP-only
+    /// transport automatically.
     ///
     /// `pool_size` controls the number of SSH connections in the pool for parallel transfers.
     /// Should typically match the number of parallel workers.
@@ -70,10 +81,8 @@ impl TransportRouter {
                 };
 
                 let source_transport = Box::new(LocalTransport::with_verifier(verifier.clone()));
-                let dest_transport = Box::new(
-                    SshTransport::with_retry_config(&config, pool_size, retry_config.clone())
-                        .await?,
-                );
+                let dest_transport =
+                    connect_ssh(&config, pool_size, retry_config.clone(), false).await?;
                 let dual = DualTransport::new(source_transport, dest_transport);
                 Ok(TransportRouter::Dual(dual))
             }
@@ -90,10 +99,8 @@ impl TransportRouter {
                     parse_ssh_config(host)?
                 };
 
-                let source_transport = Box::new(
-                    SshTransport::with_retry_config(&config, pool_size, retry_config.clone())
-                        .await?,
-                );
+                let source_transport =
+                    connect_ssh(&config, pool_size, retry_config.clone(), false).await?;
                 let dest_transport = Box::new(LocalTransport::with_verifier(verifier));
                 let dual = DualTransport::new(source_transport, dest_transport);
                 Ok(TransportRouter::Dual(dual))
@@ -132,18 +139,10 @@ impl TransportRouter {
                     parse_ssh_config(dest_host)?
                 };
 
-                let source_transport = Box::new(
-                    SshTransport::with_retry_config(
-                        &source_config,
-                        pool_size,
-                        retry_config.clone(),
-                    )
-                    .await?,
-                );
-                let dest_transport = Box::new(
-                    SshTransport::with_retry_config(&dest_config, pool_size, retry_config.clone())
-                        .await?,
-                );
+                let source_transport =
+                    connect_ssh(&source_config, pool_size, retry_config.clone(), false).await?;
+                let dest_transport =
+                    connect_ssh(&dest_config, pool_size, retry_config.clone(), false).await?;
                 let dual = DualTransport::new(source_transport, dest_transport);
                 Ok(TransportRouter::Dual(dual))
             }
@@ -154,6 +153,47 @@ impl TransportRouter {
                     "SSH support is disabled. Install with: cargo install sy --features ssh",
                 )))
             }
+            #[cfg(feature = "ssh")]
+            (
+                SyncPath::Local { .. },
+                SyncPath::Sftp {
+                    host, user, port, ..
+                },
+            ) => {
+                // Local → SFTP: use DualTransport (Local for source, SFTP-only for dest)
+                let config = sftp_config(host, user.as_deref(), *port);
+                let source_transport = Box::new(LocalTransport::with_verifier(verifier));
+                let dest_transport = connect_ssh(&config, pool_size, retry_config, true).await?;
+                let dual = DualTransport::new(source_transport, dest_transport);
+                Ok(TransportRouter::Dual(dual))
+            }
+            #[cfg(feature = "ssh")]
+            (
+                SyncPath::Sftp {
+                    host, user, port, ..
+                },
+                SyncPath::Local { .. },
+            ) => {
+                // SFTP → Local: use DualTransport (SFTP-only for source, Local for dest)
+                let config = sftp_config(host, user.as_deref(), *port);
+                let source_transport = connect_ssh(&config, pool_size, retry_config, true).await?;
+                let dest_transport = Box::new(LocalTransport::with_verifier(verifier));
+                let dual = DualTransport::new(source_transport, dest_transport);
+                Ok(TransportRouter::Dual(dual))
+            }
+            #[cfg(feature = "ssh")]
+            (SyncPath::Sftp { .. }, _) | (_, SyncPath::Sftp { .. }) => {
+                Err(crate::error::SyncError::Io(std::io::Error::other(
+                    "SFTP sync is only supported to or from a local path",
+                )))
+            }
+            #[cfg(not(feature = "ssh"))]
+            (SyncPath::Sftp { .. }, _) | (_, SyncPath::Sftp { .. }) => {
+                Err(crate::error::SyncError::Io(std::io::Error::new(
+                    std::io::ErrorKind::Unsupported,
+                    "SSH support is disabled. Install with: cargo install sy --features ssh",
+                )))
+            }
             #[cfg(feature = "s3")]
             (
                 SyncPath::Local { .. },
@@ -316,6 +356,111 @@ impl TransportRouter {
                     "GCS support not enabled. Reinstall with: cargo install sy --features gcs",
                 )))
             }
+            #[cfg(feature = "azure")]
+            (
+                SyncPath::Local { .. },
+                SyncPath::Azure {
+                    container,
+                    key,
+                    account,
+                    ..
+                },
+            ) => {
+                // Local → Azure: use DualTransport (Local for source, Azure for dest)
+                // Use pool_size for Azure connection pool (matches parallelism setting)
+                let source_transport = Box::new(LocalTransport::with_verifier(verifier));
+                let dest_transport = Box::new(
+                    AzureTransport::with_config(
+                        container.clone(),
+                        key.clone(),
+                        account.clone(),
+                        None,
+                        pool_size,
+                    )
+                    .await?,
+                );
+                let dual = DualTransport::new(source_transport, dest_transport);
+                Ok(TransportRouter::Dual(dual))
+            }
+            #[cfg(feature = "azure")]
+            (
+                SyncPath::Azure {
+                    container,
+                    key,
+                    account,
+                    ..
+                },
+                SyncPath::Local { .. },
+            ) => {
+                // Azure → Local: use DualTransport (Azure for source, Local for dest)
+                // Use pool_size for Azure connection pool (matches parallelism setting)
+                let source_transport = Box::new(
+                    AzureTransport::with_config(
+                        container.clone(),
+                        key.clone(),
+                        account.clone(),
+                        None,
+                        pool_size,
+                    )
+                    .await?,
+                );
+                let dest_transport = Box::new(LocalTransport::with_verifier(verifier));
+                let dual = DualTransport::new(source_transport, dest_transport);
+                Ok(TransportRouter::Dual(dual))
+            }
+            #[cfg(feature = "azure")]
+            (SyncPath::Azure { .. }, _) | (_, SyncPath::Azure { .. }) => {
+                // Azure ↔ Azure/SSH/S3/GCS: not yet supported
+                Err(crate::error::SyncError::Io(std::io::Error::other(
+                    "Azure sync is only supported to or from a local path",
+                )))
+            }
+            #[cfg(not(feature = "azure"))]
+            (SyncPath::Azure { .. }, _) | (_, SyncPath::Azure { .. }) => {
+                Err(crate::error::SyncError::Io(std::io::Error::other(
+                    "Azure support not enabled. Reinstall with: cargo install sy --features azure",
+                )))
+            }
+            #[cfg(feature = "webdav")]
+            (
+                SyncPath::Local { .. },
+                SyncPath::WebDav {
+                    host, user, secure, ..
+                },
+            ) => {
+                // Local → WebDAV: use DualTransport (Local for source, WebDAV for dest)
+                let source_transport = Box::new(LocalTransport::with_verifier(verifier));
+                let dest_transport =
+                    Box::new(WebDavTransport::new(host.clone(), user.clone(), *secure)?);
+                let dual = DualTransport::new(source_transport, dest_transport);
+                Ok(TransportRouter::Dual(dual))
+            }
+            #[cfg(feature = "webdav")]
+            (
+                SyncPath::WebDav {
+                    host, user, secure, ..
+                },
+                SyncPath::Local { .. },
+            ) => {
+                // WebDAV → Local: use DualTransport (WebDAV for source, Local for dest)
+                let source_transport =
+                    Box::new(WebDavTransport::new(host.clone(), user.clone(), *secure)?);
+                let dest_transport = Box::new(LocalTransport::with_verifier(verifier));
+                let dual = DualTransport::new(source_transport, dest_transport);
+                Ok(TransportRouter::Dual(dual))
+            }
+            #[cfg(feature = "webdav")]
+            (SyncPath::WebDav { .. }, _) | (_, SyncPath::WebDav { .. }) => {
+                Err(crate::error::SyncError::Io(std::io::Error::other(
+                    "WebDAV sync is only supported to or from a local path",
+                )))
+            }
+            #[cfg(not(feature = "webdav"))]
+            (SyncPath::WebDav { .. }, _) | (_, SyncPath::WebDav { .. }) => {
+                Err(crate::error::SyncError::Io(std::io::Error::other(
+                    "WebDAV support not enabled. Reinstall with: cargo install sy --features webdav",
+                )))
+            }
             // Daemon paths require --use-daemon socket to be specified
             (SyncPath::Daemon { .. }, _) | (_, SyncPath::Daemon { .. }) => {
                 Err(crate::error::SyncError::Io(std::io::Error::other(
@@ -328,6 +473,12 @@ impl TransportRouter {
                      3. Sync using daemon: sy --use-daemon /tmp/sy.sock /local daemon:/remote",
                 )))
             }
+            // TCP daemon paths are handled by the daemon client before routing
+            (SyncPath::TcpDaemon { .. }, _) | (_, SyncPath::TcpDaemon { .. }) => {
+                Err(crate::error::SyncError::Io(std::io::Error::other(
+                    "TCP daemon paths (sy://host/path) are only supported between a local directory and the daemon",
+                )))
+            }
         }
     }
 
@@ -349,6 +500,60 @@ impl TransportRouter {
             }
         }
     }
+
+    /// Apply a delta block size policy to the underlying transport
+    pub fn with_block_size(mut self, policy: BlockSizePolicy) -> Self {
+        self.set_block_size(policy);
+        self
+    }
+}
+
+/// SSH config for an `sftp://` path: ssh_config(5) settings plus URL overrides
+#[cfg(feature = "ssh")]
+fn sftp_config(host: &str, user: Option<&str>, port: Option<u16>) -> SshConfig {
+    let mut config = parse_ssh_config(host).unwrap_or_else(|_| SshConfig::new(host));
+    if let Some(user) = user {
+        config.user = user.to_string();
+    }
+    if let Some(port) = port {
+        config.port = port;
+    }
+    config
+}
+
+/// Connect over SSH, falling back to pure SFTP when the host refuses exec
+///
+/// `sftp_only` skips the probe for explicit `sftp://` paths. Probe results
+/// are remembered per host, see `ExecProbeCache`.
+#[cfg(feature = "ssh")]
+async fn connect_ssh(
+    config: &SshConfig,
+    pool_size: usize,
+    retry_config: RetryConfig,
+    sftp_only: bool,
+) -> Result<Box<dyn Transport>> {
+    let ssh = SshTransport::with_retry_config(config, pool_size, retry_config).await?;
+    if sftp_only {
+        return Ok(Box::new(SftpTransport::new(ssh)));
+    }
+    let host = format!("{}@{}:{}", config.user, config.hostname, config.port);
+    let probes = ExecProbeCache::load();
+    let exec = match probes.get(&host) {
+        Some(exec) => exec,
+        None => {
+            let exec = ssh.exec_available().await?;
+            probes.record(&host, exec);
+            exec
+        }
+    };
+    if !exec {
+        tracing::warn!(
+            "{} refuses command execution, falling back to SFTP-only transfers",
+            config.hostname
+        );
+        return Ok(Box::new(SftpTransport::new(ssh)));
+    }
+    Ok(Box::new(ssh))
 }
 
 #[async_trait]
@@ -362,6 +567,15 @@ impl Transport for TransportRouter {
         }
     }
 
+    fn set_block_size(&mut self, policy: BlockSizePolicy) {
+        match self {
+            TransportRouter::Local(t) => t.set_block_size(policy),
+            TransportRouter::Dual(t) => t.set_block_size(policy),
+            #[cfg(feature = "s3")]
+            TransportRouter::S3(t) => t.set_block_size(policy),
+        }
+    }
+
     async fn prepare_for_transfer(&self, file_count: usize) -> Result<()> {
         match self {
             TransportRouter::Local(t) => t.prepare_for_transfer(file_count).await,
@@ -371,6 +585,15 @@ impl Transport for TransportRouter {
         }
     }
 
+    fn is_sftp_only(&self) -> bool {
+        match self {
+            TransportRouter::Local(t) => t.is_sftp_only(),
+            TransportRouter::Dual(t) => t.is_sftp_only(),
+            #[cfg(feature = "s3")]
+            TransportRouter::S3(t) => t.is_sftp_only(),
+        }
+    }
+
     async fn scan(&self, path: &Path) -> Result<Vec<crate::sync::scanner::FileEntry>> {
         match self {
             TransportRouter::Local(t) => t.scan(path).await,
@@ -464,6 +687,20 @@ impl Transport for TransportRouter {
         }
     }
 
+    async fn sync_file_with_basis(
+        &self,
+        source: &Path,
+        basis: &Path,
+        dest: &Path,
+    ) -> Result<TransferResult> {
+        match self {
+            TransportRouter::Local(t) => t.sync_file_with_basis(source, basis, dest).await,
+            TransportRouter::Dual(t) => t.sync_file_with_basis(source, basis, dest).await,
+            #[cfg(feature = "s3")]
+            TransportRouter::S3(t) => t.sync_file_with_basis(source, basis, dest).await,
+        }
+    }
+
     async fn remove(&self, path: &Path, is_dir: bool) -> Result<()> {
         match self {
             TransportRouter::Local(t) => t.remove(path, is_dir).await,
@@ -473,6 +710,15 @@ impl Transport for TransportRouter {
         }
     }
 
+    async fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        match self {
+            TransportRouter::Local(t) => t.rename(from, to).await,
+            TransportRouter::Dual(t) => t.rename(from, to).await,
+            #[cfg(feature = "s3")]
+            TransportRouter::S3(t) => t.rename(from, to).await,
+        }
+    }
+
     async fn create_hardlink(&self, source: &Path, dest: &Path) -> Result<()> {
         match self {
             TransportRouter::Local(t) => t.create_hardlink(source, dest).await,
@@ -3,32 +3,144 @@
 #![allow(dead_code)]
 
 use anyhow::{Context, Result};
-use std::path::Path;
+use async_trait::async_trait;
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
 use tokio::process::{Child, Command};
 
 #[cfg(unix)]
-use tokio::net::UnixStream;
+use tokio::net::{TcpStream, UnixStream};
 
-#[cfg(unix)]
-use crate::server::daemon::{read_set_root_ack, write_set_root, MSG_PING, MSG_PONG};
+use crate::server::confine::Confinement;
+use crate::server::daemon::{read_set_root_ack, write_set_root};
+use crate::server::partial::StalePartials;
 use crate::server::protocol::{
-    self, ChecksumReq, ChecksumResp, Decision, DeltaData, DeltaOp, FileData, FileDone, FileList,
-    FileListAck, FileListEntry, Hello, MessageType, MkdirBatch, MkdirBatchAck, SymlinkBatch,
-    SymlinkBatchAck, SymlinkEntry, HELLO_FLAG_PULL, PROTOCOL_VERSION,
+    self, Capabilities, ChecksumReq, ChecksumResp, Decision, DeltaData, DeltaOp, FileData,
+    FileDone, FileList, FileListAck, FileListEntry, Frame, Hello, MessageType, MkdirBatch,
+    MkdirBatchAck, Negotiated, SymlinkBatch, SymlinkBatchAck, SymlinkEntry, CAPS_LOCAL,
+    CAP_KEEPALIVE, CAP_STREAM_ZSTD, DATA_FLAG_DIGEST, HELLO_FLAG_PULL, MSG_PING, MSG_PONG,
 };
+use crate::server::stream::{StreamReader, StreamStats, StreamWriter};
+#[cfg(unix)]
+use crate::server::tcp::{answer_challenge, AuthToken, TcpEndpoint};
+use crate::server::{layout, partial};
 use crate::ssh::config::SshConfig;
 
+/// Features offered over local pipes, which gain nothing from compressing the stream
+const LOCAL_PIPE_FEATURES: u64 = CAPS_LOCAL & !CAP_STREAM_ZSTD;
+
+/// What a client asks of the sessions it opens
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SessionOptions {
+    /// Offer zstd for the whole stream after the handshake (`-z`)
+    pub compress: bool,
+    /// Refuse unsafe symlinks: pushes start `sy --server` with
+    /// `--safe-links`, pulls check what they receive
+    pub safe_links: bool,
+}
+
+impl SessionOptions {
+    /// Features to offer in HELLO
+    fn features(&self) -> u64 {
+        if self.compress {
+            CAPS_LOCAL
+        } else {
+            CAPS_LOCAL & !CAP_STREAM_ZSTD
+        }
+    }
+}
+
+/// Context for TCP handshake failures, which usually mean a bad token or TLS mismatch
+#[cfg(unix)]
+const TCP_HANDSHAKE_HINT: &str =
+    "Daemon handshake failed (check SY_DAEMON_TOKEN, and pass --tls-ca if the daemon uses TLS)";
+
+/// A user-supplied remote shell (`-e/--rsh`) used instead of the built-in ssh command
+///
+/// Follows rsync's `--rsh` semantics: the command is split shell-style and
+/// run as `<command> [-l user] <host> sy --server <path>`, so
+/// `-e "ssh -p 2222 -o Compression=no"` or `-e "docker exec -i"` work as-is.
+/// Commands that need the host elsewhere can place it with `%h`, e.g.
+/// `-e "kubectl exec -i %h --"`; the host is then not appended.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemoteShell {
+    args: Vec<String>,
+}
+
+impl RemoteShell {
+    /// Parse a remote shell command line
+    pub fn parse(command: &str) -> Result<Self> {
+        let args = shlex::split(command)
+            .with_context(|| format!("Invalid remote shell command: {}", command))?;
+        if args.is_empty() {
+            anyhow::bail!("Remote shell command is empty");
+        }
+        Ok(Self { args })
+    }
+
+    /// Full argument list (program first) that starts `sy --server` for `host`
+    pub fn server_args(
+        &self,
+        host: &str,
+        user: Option<&str>,
+        remote_path: &Path,
+        safe_links: bool,
+    ) -> Vec<String> {
+        let mut args: Vec<String> = self.args.iter().map(|a| a.replace("%h", host)).collect();
+
+        if !self.args.iter().any(|a| a.contains("%h")) {
+            if let Some(user) = user {
+                args.push("-l".to_string());
+                args.push(user.to_string());
+            }
+            args.push(host.to_string());
+        }
+
+        args.push("sy".to_string());
+        args.push("--server".to_string());
+        if safe_links {
+            args.push("--safe-links".to_string());
+        }
+        args.push(remote_path.to_string_lossy().into_owned());
+        args
+    }
+
+    fn command(
+        &self,
+        host: &str,
+        user: Option<&str>,
+        remote_path: &Path,
+        safe_links: bool,
+    ) -> Command {
+        let args = self.server_args(host, user, remote_path, safe_links);
+        let mut cmd = Command::new(&args[0]);
+        cmd.args(&args[1..]);
+        cmd.stdin(Stdio::piped());
+        cmd.stdout(Stdio::piped());
+        cmd.stderr(Stdio::inherit());
+        cmd
+    }
+}
+
 /// Manages the client-side connection to a remote sy --server instance
 pub struct ServerSession {
     child: Child,
-    stdin: tokio::process::ChildStdin,
-    stdout: tokio::process::ChildStdout,
+    stdin: StreamWriter<tokio::process::ChildStdin>,
+    stdout: StreamReader<tokio::process::ChildStdout>,
+    negotiated: Negotiated,
+    /// Header of the SYMLINK_BATCH that ended the pulled files
+    symlink_frame: Option<Frame>,
+    /// Partials of older source versions, removed as pulled files are committed
+    stale_partials: StalePartials,
 }
 
 impl ServerSession {
-    pub async fn connect_ssh(config: &SshConfig, remote_path: &Path) -> Result<Self> {
+    pub async fn connect_ssh(
+        config: &SshConfig,
+        remote_path: &Path,
+        options: SessionOptions,
+    ) -> Result<Self> {
         let mut cmd = Command::new("ssh");
 
         cmd.arg(&config.hostname);
@@ -48,9 +160,12 @@ impl ServerSession {
         cmd.arg("-o").arg("StrictHostKeyChecking=no");
         cmd.arg("-o").arg("UserKnownHostsFile=/dev/null");
 
-        // Remote command: sy --server <remote_path>
+        // Remote command: sy --server [--safe-links] <remote_path>
         cmd.arg("sy");
         cmd.arg("--server");
+        if options.safe_links {
+            cmd.arg("--safe-links");
+        }
         cmd.arg(remote_path);
 
         cmd.stdin(Stdio::piped());
@@ -64,19 +179,25 @@ impl ServerSession {
 
         let mut session = Self {
             child,
-            stdin,
-            stdout,
+            stdin: StreamWriter::new(stdin),
+            stdout: StreamReader::new(stdout),
+            negotiated: Negotiated::default(),
+            symlink_frame: None,
+            stale_partials: StalePartials::default(),
         };
 
-        session.handshake().await?;
+        session.handshake(0, options.features()).await?;
 
         Ok(session)
     }
 
-    pub async fn connect_local(remote_path: &Path) -> Result<Self> {
+    pub async fn connect_local(remote_path: &Path, options: SessionOptions) -> Result<Self> {
         let exe = std::env::current_exe()?;
         let mut cmd = Command::new(exe);
         cmd.arg("--server");
+        if options.safe_links {
+            cmd.arg("--safe-links");
+        }
         cmd.arg(remote_path);
 
         cmd.stdin(Stdio::piped());
@@ -90,30 +211,68 @@ impl ServerSession {
 
         let mut session = Self {
             child,
-            stdin,
-            stdout,
+            stdin: StreamWriter::new(stdin),
+            stdout: StreamReader::new(stdout),
+            negotiated: Negotiated::default(),
+            symlink_frame: None,
+            stale_partials: StalePartials::default(),
         };
 
-        session.handshake().await?;
+        session.handshake(0, LOCAL_PIPE_FEATURES).await?;
 
         Ok(session)
     }
 
-    async fn handshake(&mut self) -> Result<()> {
-        let hello = Hello {
-            version: PROTOCOL_VERSION,
-            flags: 0,
-            capabilities: vec![],
-        };
+    /// Connect through a user-supplied remote shell (`-e/--rsh`)
+    pub async fn connect_rsh(
+        rsh: &RemoteShell,
+        host: &str,
+        user: Option<&str>,
+        remote_path: &Path,
+        options: SessionOptions,
+    ) -> Result<Self> {
+        let mut session = Self::spawn_rsh(rsh, host, user, remote_path, options.safe_links)?;
+        session.handshake(0, options.features()).await?;
+        Ok(session)
+    }
+
+    fn spawn_rsh(
+        rsh: &RemoteShell,
+        host: &str,
+        user: Option<&str>,
+        remote_path: &Path,
+        safe_links: bool,
+    ) -> Result<Self> {
+        let mut child = rsh
+            .command(host, user, remote_path, safe_links)
+            .spawn()
+            .with_context(|| format!("Failed to spawn remote shell '{}'", rsh.args[0]))?;
+
+        let stdin = child.stdin.take().context("Failed to open stdin")?;
+        let stdout = child.stdout.take().context("Failed to open stdout")?;
 
-        hello.write(&mut self.stdin).await?;
+        Ok(Self {
+            child,
+            stdin: StreamWriter::new(stdin),
+            stdout: StreamReader::new(stdout),
+            negotiated: Negotiated::default(),
+            symlink_frame: None,
+            stale_partials: StalePartials::default(),
+        })
+    }
+
+    /// Exchange HELLOs and record the features both sides support
+    async fn handshake(&mut self, flags: u32, features: u64) -> Result<()> {
+        Hello::with_features(flags, features)
+            .write(&mut self.stdin)
+            .await?;
         self.stdin.flush().await?;
 
-        let _len = self.stdout.read_u32().await?;
-        let type_byte = self.stdout.read_u8().await?;
+        let frame = Frame::read(&mut self.stdout).await?;
+        let type_byte = frame.msg_type;
 
         if type_byte == MessageType::Error as u8 {
-            let err = protocol::ErrorMessage::read(&mut self.stdout).await?;
+            let err = protocol::ErrorMessage::read_frame(&mut self.stdout, &frame).await?;
             return Err(anyhow::anyhow!("Server handshake error: {}", err.message));
         }
 
@@ -121,11 +280,62 @@ impl ServerSession {
             return Err(anyhow::anyhow!("Expected HELLO, got 0x{:02X}", type_byte));
         }
 
-        let resp = Hello::read(&mut self.stdout).await?;
+        let resp = Hello::read_frame(&mut self.stdout, &frame).await?;
+        let ours = Capabilities {
+            features,
+            ..Capabilities::local()
+        };
+        self.negotiated = ours
+            .negotiate(&resp.peer_capabilities())
+            .context("Server protocol version mismatch")?;
+        tracing::debug!(
+            "Negotiated protocol v{} (features {:#x})",
+            self.negotiated.version,
+            self.negotiated.features
+        );
+
+        // Everything after the handshake travels as one zstd stream
+        if self.negotiated.has(CAP_STREAM_ZSTD) {
+            self.stdin.start_zstd()?;
+            self.stdout.start_zstd()?;
+        }
+
+        Ok(())
+    }
+
+    /// Protocol version and features agreed with the server
+    pub fn negotiated(&self) -> Negotiated {
+        self.negotiated
+    }
+
+    /// Bytes through the stream compressor so far, both directions
+    pub fn stream_stats(&self) -> StreamStats {
+        self.stdin.stats() + self.stdout.stats()
+    }
 
-        if resp.version != PROTOCOL_VERSION {
-            return Err(anyhow::anyhow!("Version mismatch: server {}", resp.version));
+    // =========================================================================
+    // Keepalive
+    // =========================================================================
+
+    /// Send a PING to check if the server is alive
+    ///
+    /// Servers that predate keepalive don't answer PING; for them this is a
+    /// no-op and a dead link shows up on the next real message instead.
+    pub async fn ping(&mut self) -> Result<()> {
+        if !self.negotiated.has(CAP_KEEPALIVE) {
+            return Ok(());
         }
+        self.stdin.write_u32(0).await?;
+        self.stdin.write_u8(MSG_PING).await?;
+        self.stdin.flush().await?;
+
+        let frame = Frame::read(&mut self.stdout).await?;
+        let type_byte = frame.msg_type;
+
+        if type_byte != MSG_PONG {
+            return Err(anyhow::anyhow!("Expected PONG, got 0x{:02X}", type_byte));
+        }
+        frame.expect_empty()?;
 
         Ok(())
     }
@@ -142,11 +352,11 @@ impl ServerSession {
     }
 
     pub async fn read_ack(&mut self) -> Result<FileListAck> {
-        let _len = self.stdout.read_u32().await?;
-        let type_byte = self.stdout.read_u8().await?;
+        let frame = Frame::read(&mut self.stdout).await?;
+        let type_byte = frame.msg_type;
 
         if type_byte == MessageType::Error as u8 {
-            let err = protocol::ErrorMessage::read(&mut self.stdout).await?;
+            let err = protocol::ErrorMessage::read_frame(&mut self.stdout, &frame).await?;
             return Err(anyhow::anyhow!("Server error: {}", err.message));
         }
 
@@ -157,7 +367,7 @@ impl ServerSession {
             ));
         }
 
-        FileListAck::read(&mut self.stdout).await
+        FileListAck::read_frame(&mut self.stdout, &frame).await
     }
 
     // =========================================================================
@@ -172,11 +382,11 @@ impl ServerSession {
     }
 
     pub async fn read_mkdir_ack(&mut self) -> Result<MkdirBatchAck> {
-        let _len = self.stdout.read_u32().await?;
-        let type_byte = self.stdout.read_u8().await?;
+        let frame = Frame::read(&mut self.stdout).await?;
+        let type_byte = frame.msg_type;
 
         if type_byte == MessageType::Error as u8 {
-            let err = protocol::ErrorMessage::read(&mut self.stdout).await?;
+            let err = protocol::ErrorMessage::read_frame(&mut self.stdout, &frame).await?;
             return Err(anyhow::anyhow!("Server error: {}", err.message));
         }
 
@@ -187,7 +397,7 @@ impl ServerSession {
             ));
         }
 
-        MkdirBatchAck::read(&mut self.stdout).await
+        MkdirBatchAck::read_frame(&mut self.stdout, &frame).await
     }
 
     // =========================================================================
@@ -202,11 +412,11 @@ impl ServerSession {
     }
 
     pub async fn read_symlink_ack(&mut self) -> Result<SymlinkBatchAck> {
-        let _len = self.stdout.read_u32().await?;
-        let type_byte = self.stdout.read_u8().await?;
+        let frame = Frame::read(&mut self.stdout).await?;
+        let type_byte = frame.msg_type;
 
         if type_byte == MessageType::Error as u8 {
-            let err = protocol::ErrorMessage::read(&mut self.stdout).await?;
+            let err = protocol::ErrorMessage::read_frame(&mut self.stdout, &frame).await?;
             return Err(anyhow::anyhow!("Server error: {}", err.message));
         }
 
@@ -217,7 +427,7 @@ impl ServerSession {
             ));
         }
 
-        SymlinkBatchAck::read(&mut self.stdout).await
+        SymlinkBatchAck::read_frame(&mut self.stdout, &frame).await
     }
 
     // =========================================================================
@@ -278,11 +488,11 @@ impl ServerSession {
     }
 
     pub async fn read_file_done(&mut self) -> Result<FileDone> {
-        let _len = self.stdout.read_u32().await?;
-        let type_byte = self.stdout.read_u8().await?;
+        let frame = Frame::read(&mut self.stdout).await?;
+        let type_byte = frame.msg_type;
 
         if type_byte == MessageType::Error as u8 {
-            let err = protocol::ErrorMessage::read(&mut self.stdout).await?;
+            let err = protocol::ErrorMessage::read_frame(&mut self.stdout, &frame).await?;
             return Err(anyhow::anyhow!("Server error: {}", err.message));
         }
 
@@ -293,7 +503,7 @@ impl ServerSession {
             ));
         }
 
-        FileDone::read(&mut self.stdout).await
+        FileDone::read_frame(&mut self.stdout, &frame).await
     }
 
     // =========================================================================
@@ -301,27 +511,25 @@ impl ServerSession {
     // =========================================================================
 
     /// Request block checksums for a file (for delta sync)
-    pub async fn send_checksum_req(&mut self, index: u32, block_size: u32) -> Result<()> {
-        let req = ChecksumReq { index, block_size };
+    pub async fn send_checksum_req(&mut self, req: ChecksumReq) -> Result<()> {
         req.write(&mut self.stdin).await?;
         self.stdin.flush().await?;
         Ok(())
     }
 
     /// Request block checksums without flushing - use flush() after batch
-    pub async fn send_checksum_req_no_flush(&mut self, index: u32, block_size: u32) -> Result<()> {
-        let req = ChecksumReq { index, block_size };
+    pub async fn send_checksum_req_no_flush(&mut self, req: ChecksumReq) -> Result<()> {
         req.write(&mut self.stdin).await?;
         Ok(())
     }
 
     /// Read checksum response
     pub async fn read_checksum_resp(&mut self) -> Result<ChecksumResp> {
-        let _len = self.stdout.read_u32().await?;
-        let type_byte = self.stdout.read_u8().await?;
+        let frame = Frame::read(&mut self.stdout).await?;
+        let type_byte = frame.msg_type;
 
         if type_byte == MessageType::Error as u8 {
-            let err = protocol::ErrorMessage::read(&mut self.stdout).await?;
+            let err = protocol::ErrorMessage::read_frame(&mut self.stdout, &frame).await?;
             return Err(anyhow::anyhow!("Server error: {}", err.message));
         }
 
@@ -332,7 +540,7 @@ impl ServerSession {
             ));
         }
 
-        ChecksumResp::read(&mut self.stdout).await
+        ChecksumResp::read_frame(&mut self.stdout, &frame).await
     }
 
     /// Send delta data (for updating existing file)
@@ -341,8 +549,14 @@ impl ServerSession {
         index: u32,
         flags: u8,
         ops: Vec<DeltaOp>,
+        digest: Vec<u8>,
     ) -> Result<()> {
-        let delta = DeltaData { index, flags, ops };
+        let delta = DeltaData {
+            index,
+            flags,
+            ops,
+            digest,
+        };
         delta.write(&mut self.stdin).await?;
         self.stdin.flush().await?;
         Ok(())
@@ -354,8 +568,14 @@ impl ServerSession {
         index: u32,
         flags: u8,
         ops: Vec<DeltaOp>,
+        digest: Vec<u8>,
     ) -> Result<()> {
-        let delta = DeltaData { index, flags, ops };
+        let delta = DeltaData {
+            index,
+            flags,
+            ops,
+            digest,
+        };
         delta.write(&mut self.stdin).await?;
         Ok(())
     }
@@ -365,7 +585,11 @@ impl ServerSession {
     // =========================================================================
 
     /// Connect to SSH server in PULL mode (server sends files to client)
-    pub async fn connect_ssh_pull(config: &SshConfig, remote_path: &Path) -> Result<Self> {
+    pub async fn connect_ssh_pull(
+        config: &SshConfig,
+        remote_path: &Path,
+        options: SessionOptions,
+    ) -> Result<Self> {
         let mut cmd = Command::new("ssh");
 
         cmd.arg(&config.hostname);
@@ -400,11 +624,16 @@ impl ServerSession {
 
         let mut session = Self {
             child,
-            stdin,
-            stdout,
+            stdin: StreamWriter::new(stdin),
+            stdout: StreamReader::new(stdout),
+            negotiated: Negotiated::default(),
+            symlink_frame: None,
+            stale_partials: StalePartials::default(),
         };
 
-        session.handshake_pull().await?;
+        session
+            .handshake(HELLO_FLAG_PULL, options.features())
+            .await?;
 
         Ok(session)
     }
@@ -427,54 +656,42 @@ impl ServerSession {
 
         let mut session = Self {
             child,
-            stdin,
-            stdout,
+            stdin: StreamWriter::new(stdin),
+            stdout: StreamReader::new(stdout),
+            negotiated: Negotiated::default(),
+            symlink_frame: None,
+            stale_partials: StalePartials::default(),
         };
 
-        session.handshake_pull().await?;
+        session
+            .handshake(HELLO_FLAG_PULL, LOCAL_PIPE_FEATURES)
+            .await?;
 
         Ok(session)
     }
 
-    /// Handshake with PULL flag set
-    async fn handshake_pull(&mut self) -> Result<()> {
-        let hello = Hello {
-            version: PROTOCOL_VERSION,
-            flags: HELLO_FLAG_PULL,
-            capabilities: vec![],
-        };
-
-        hello.write(&mut self.stdin).await?;
-        self.stdin.flush().await?;
-
-        let _len = self.stdout.read_u32().await?;
-        let type_byte = self.stdout.read_u8().await?;
-
-        if type_byte == MessageType::Error as u8 {
-            let err = protocol::ErrorMessage::read(&mut self.stdout).await?;
-            return Err(anyhow::anyhow!("Server handshake error: {}", err.message));
-        }
-
-        if type_byte != MessageType::Hello as u8 {
-            return Err(anyhow::anyhow!("Expected HELLO, got 0x{:02X}", type_byte));
-        }
-
-        let resp = Hello::read(&mut self.stdout).await?;
-
-        if resp.version != PROTOCOL_VERSION {
-            return Err(anyhow::anyhow!("Version mismatch: server {}", resp.version));
-        }
-
-        Ok(())
+    /// Connect through a user-supplied remote shell in PULL mode
+    pub async fn connect_rsh_pull(
+        rsh: &RemoteShell,
+        host: &str,
+        user: Option<&str>,
+        remote_path: &Path,
+        options: SessionOptions,
+    ) -> Result<Self> {
+        let mut session = Self::spawn_rsh(rsh, host, user, remote_path, false)?;
+        session
+            .handshake(HELLO_FLAG_PULL, options.features())
+            .await?;
+        Ok(session)
     }
 
     /// Read MKDIR_BATCH from server (PULL mode) - server always sends this
     pub async fn read_mkdir_batch(&mut self) -> Result<MkdirBatch> {
-        let _len = self.stdout.read_u32().await?;
-        let type_byte = self.stdout.read_u8().await?;
+        let frame = Frame::read(&mut self.stdout).await?;
+        let type_byte = frame.msg_type;
 
         if type_byte == MessageType::Error as u8 {
-            let err = protocol::ErrorMessage::read(&mut self.stdout).await?;
+            let err = protocol::ErrorMessage::read_frame(&mut self.stdout, &frame).await?;
             return Err(anyhow::anyhow!("Server error: {}", err.message));
         }
 
@@ -485,7 +702,7 @@ impl ServerSession {
             ));
         }
 
-        let batch = MkdirBatch::read(&mut self.stdout).await?;
+        let batch = MkdirBatch::read_frame(&mut self.stdout, &frame).await?;
         Ok(batch)
     }
 
@@ -503,11 +720,11 @@ impl ServerSession {
 
     /// Read FILE_LIST from server (PULL mode)
     pub async fn read_file_list(&mut self) -> Result<FileList> {
-        let _len = self.stdout.read_u32().await?;
-        let type_byte = self.stdout.read_u8().await?;
+        let frame = Frame::read(&mut self.stdout).await?;
+        let type_byte = frame.msg_type;
 
         if type_byte == MessageType::Error as u8 {
-            let err = protocol::ErrorMessage::read(&mut self.stdout).await?;
+            let err = protocol::ErrorMessage::read_frame(&mut self.stdout, &frame).await?;
             return Err(anyhow::anyhow!("Server error: {}", err.message));
         }
 
@@ -518,7 +735,7 @@ impl ServerSession {
             ));
         }
 
-        FileList::read(&mut self.stdout).await
+        FileList::read_frame(&mut self.stdout, &frame).await
     }
 
     /// Send FILE_LIST_ACK to server (PULL mode)
@@ -531,16 +748,17 @@ impl ServerSession {
 
     /// Read FILE_DATA from server (PULL mode)
     pub async fn read_file_data(&mut self) -> Result<Option<FileData>> {
-        let _len = self.stdout.read_u32().await?;
-        let type_byte = self.stdout.read_u8().await?;
+        let frame = Frame::read(&mut self.stdout).await?;
+        let type_byte = frame.msg_type;
 
         // Server might send SYMLINK_BATCH if done with files
         if type_byte == MessageType::SymlinkBatch as u8 {
+            self.symlink_frame = Some(frame);
             return Ok(None);
         }
 
         if type_byte == MessageType::Error as u8 {
-            let err = protocol::ErrorMessage::read(&mut self.stdout).await?;
+            let err = protocol::ErrorMessage::read_frame(&mut self.stdout, &frame).await?;
             return Err(anyhow::anyhow!("Server error: {}", err.message));
         }
 
@@ -551,16 +769,21 @@ impl ServerSession {
             ));
         }
 
-        let data = FileData::read(&mut self.stdout).await?;
+        let data = FileData::read_frame(&mut self.stdout, &frame).await?;
         Ok(Some(data))
     }
 
-    /// Send FILE_DONE to server (PULL mode)
-    pub async fn send_file_done(&mut self, index: u32, status: u8) -> Result<()> {
+    /// Send FILE_DONE to server (PULL mode), echoing the written file's digest
+    pub async fn send_file_done(
+        &mut self,
+        index: u32,
+        status: u8,
+        checksum: Vec<u8>,
+    ) -> Result<()> {
         let done = FileDone {
             index,
             status,
-            checksum: vec![],
+            checksum,
         };
         done.write(&mut self.stdin).await?;
         self.stdin.flush().await?;
@@ -569,7 +792,23 @@ impl ServerSession {
 
     /// Read SYMLINK_BATCH from server (PULL mode) - assumes type byte already read
     pub async fn read_symlink_batch_body(&mut self) -> Result<SymlinkBatch> {
-        SymlinkBatch::read(&mut self.stdout).await
+        let frame = self
+            .symlink_frame
+            .take()
+            .context("No SYMLINK_BATCH header has been read")?;
+        SymlinkBatch::read_frame(&mut self.stdout, &frame).await
+    }
+
+    /// Read the SYMLINK_BATCH that follows the last file (PULL mode)
+    ///
+    /// Fails at end of stream: a server without symlinks just exits.
+    pub async fn read_symlink_batch(&mut self) -> Result<SymlinkBatch> {
+        let frame = Frame::read(&mut self.stdout).await?;
+        let type_byte = frame.msg_type;
+        if type_byte != MessageType::SymlinkBatch as u8 {
+            anyhow::bail!("Expected SYMLINK_BATCH, got 0x{:02X}", type_byte);
+        }
+        SymlinkBatch::read_frame(&mut self.stdout, &frame).await
     }
 
     /// Send SYMLINK_BATCH_ACK to server (PULL mode)
@@ -590,6 +829,8 @@ impl ServerSession {
 
     /// Close the session gracefully
     pub async fn close(mut self) -> Result<()> {
+        // Ends the zstd frame (if any) before closing the pipe
+        let _ = self.stdin.shutdown().await;
         drop(self.stdin);
         let _ = self.child.wait().await;
         Ok(())
@@ -618,49 +859,141 @@ impl ServerSession {
 /// ```
 #[cfg(unix)]
 pub struct DaemonSession {
-    reader: tokio::net::unix::OwnedReadHalf,
-    writer: tokio::net::unix::OwnedWriteHalf,
+    reader: StreamReader<Box<dyn AsyncRead + Unpin + Send>>,
+    writer: StreamWriter<Box<dyn AsyncWrite + Unpin + Send>>,
+    negotiated: Negotiated,
+    /// Header of the SYMLINK_BATCH that ended the pulled files
+    symlink_frame: Option<Frame>,
+    /// Partials of older source versions, removed as pulled files are committed
+    stale_partials: StalePartials,
 }
 
 #[cfg(unix)]
 impl DaemonSession {
     /// Connect to a daemon via Unix socket and set the root path
-    pub async fn connect(socket_path: &str, remote_path: &Path) -> Result<Self> {
+    pub async fn connect(
+        socket_path: &str,
+        remote_path: &Path,
+        options: SessionOptions,
+    ) -> Result<Self> {
         let stream = UnixStream::connect(socket_path)
             .await
             .with_context(|| format!("Failed to connect to daemon at {}", socket_path))?;
 
         let (reader, writer) = stream.into_split();
-        let mut session = Self { reader, writer };
-
-        // Handshake
-        session.handshake().await?;
-
-        // Set root path
-        let path_str = remote_path.to_string_lossy();
-        write_set_root(&mut session.writer, &path_str).await?;
-
-        if !read_set_root_ack(&mut session.reader).await? {
-            return Err(anyhow::anyhow!(
-                "Daemon rejected root path: {}",
-                remote_path.display()
-            ));
-        }
-
-        Ok(session)
+        let (reader, writer) = (Box::new(reader), Box::new(writer));
+        Self::establish(reader, writer, remote_path, 0, options, None).await
     }
 
     /// Connect to a daemon in PULL mode (daemon sends files to client)
-    pub async fn connect_pull(socket_path: &str, remote_path: &Path) -> Result<Self> {
+    pub async fn connect_pull(
+        socket_path: &str,
+        remote_path: &Path,
+        options: SessionOptions,
+    ) -> Result<Self> {
         let stream = UnixStream::connect(socket_path)
             .await
             .with_context(|| format!("Failed to connect to daemon at {}", socket_path))?;
 
         let (reader, writer) = stream.into_split();
-        let mut session = Self { reader, writer };
+        let (reader, writer) = (Box::new(reader), Box::new(writer));
+        Self::establish(reader, writer, remote_path, HELLO_FLAG_PULL, options, None).await
+    }
 
-        // Handshake with PULL flag
-        session.handshake_pull().await?;
+    /// Connect to a daemon's TCP listener (`sy://host:port/path`)
+    pub async fn connect_tcp(
+        endpoint: &TcpEndpoint,
+        remote_path: &Path,
+        options: SessionOptions,
+    ) -> Result<Self> {
+        let (reader, writer) = Self::open_tcp(endpoint).await?;
+        Self::establish(
+            reader,
+            writer,
+            remote_path,
+            0,
+            options,
+            Some(&endpoint.token),
+        )
+        .await
+        .context(TCP_HANDSHAKE_HINT)
+    }
+
+    /// Connect to a daemon's TCP listener in PULL mode
+    pub async fn connect_tcp_pull(
+        endpoint: &TcpEndpoint,
+        remote_path: &Path,
+        options: SessionOptions,
+    ) -> Result<Self> {
+        let (reader, writer) = Self::open_tcp(endpoint).await?;
+        let token = Some(&endpoint.token);
+        Self::establish(reader, writer, remote_path, HELLO_FLAG_PULL, options, token)
+            .await
+            .context(TCP_HANDSHAKE_HINT)
+    }
+
+    /// Open the TCP stream, wrapped in TLS when the endpoint has a CA
+    async fn open_tcp(
+        endpoint: &TcpEndpoint,
+    ) -> Result<(
+        Box<dyn AsyncRead + Unpin + Send>,
+        Box<dyn AsyncWrite + Unpin + Send>,
+    )> {
+        let stream = TcpStream::connect((endpoint.host.as_str(), endpoint.port))
+            .await
+            .with_context(|| {
+                format!(
+                    "Failed to connect to daemon at {}:{}",
+                    endpoint.host, endpoint.port
+                )
+            })?;
+        stream.set_nodelay(true)?;
+
+        match &endpoint.tls_ca {
+            #[cfg(feature = "tls")]
+            Some(ca) => {
+                let connector = crate::server::tcp::tls_connector(ca)?;
+                let server_name = rustls_pki_types::ServerName::try_from(endpoint.host.clone())
+                    .with_context(|| format!("Invalid TLS server name: {}", endpoint.host))?;
+                let stream = connector
+                    .connect(server_name, stream)
+                    .await
+                    .context("TLS handshake with daemon failed")?;
+                let (reader, writer) = tokio::io::split(stream);
+                Ok((Box::new(reader), Box::new(writer)))
+            }
+            #[cfg(not(feature = "tls"))]
+            Some(_) => anyhow::bail!(
+                "TLS support not enabled. Reinstall with: cargo install sy --features tls"
+            ),
+            None => {
+                let (reader, writer) = stream.into_split();
+                Ok((Box::new(reader), Box::new(writer)))
+            }
+        }
+    }
+
+    /// Handshake, authenticate (TCP only) and set the root path
+    async fn establish(
+        reader: Box<dyn AsyncRead + Unpin + Send>,
+        writer: Box<dyn AsyncWrite + Unpin + Send>,
+        remote_path: &Path,
+        flags: u32,
+        options: SessionOptions,
+        token: Option<&AuthToken>,
+    ) -> Result<Self> {
+        let mut session = Self {
+            reader: StreamReader::new(reader),
+            writer: StreamWriter::new(writer),
+            negotiated: Negotiated::default(),
+            symlink_frame: None,
+            stale_partials: StalePartials::default(),
+        };
+        session.handshake(flags, options.features()).await?;
+
+        if let Some(token) = token {
+            answer_challenge(&mut session.reader, &mut session.writer, token).await?;
+        }
 
         // Set root path
         let path_str = remote_path.to_string_lossy();
@@ -673,24 +1006,27 @@ impl DaemonSession {
             ));
         }
 
+        // The daemon switches once it has acknowledged the root
+        if session.negotiated.has(CAP_STREAM_ZSTD) {
+            session.reader.start_zstd()?;
+            session.writer.start_zstd()?;
+        }
+
         Ok(session)
     }
 
-    async fn handshake(&mut self) -> Result<()> {
-        let hello = Hello {
-            version: PROTOCOL_VERSION,
-            flags: 0,
-            capabilities: vec![],
-        };
-
-        hello.write(&mut self.writer).await?;
+    /// Exchange HELLOs and record the features both sides support
+    async fn handshake(&mut self, flags: u32, features: u64) -> Result<()> {
+        Hello::with_features(flags, features)
+            .write(&mut self.writer)
+            .await?;
         self.writer.flush().await?;
 
-        let _len = self.reader.read_u32().await?;
-        let type_byte = self.reader.read_u8().await?;
+        let frame = Frame::read(&mut self.reader).await?;
+        let type_byte = frame.msg_type;
 
         if type_byte == MessageType::Error as u8 {
-            let err = protocol::ErrorMessage::read(&mut self.reader).await?;
+            let err = protocol::ErrorMessage::read_frame(&mut self.reader, &frame).await?;
             return Err(anyhow::anyhow!("Daemon handshake error: {}", err.message));
         }
 
@@ -698,44 +1034,31 @@ impl DaemonSession {
             return Err(anyhow::anyhow!("Expected HELLO, got 0x{:02X}", type_byte));
         }
 
-        let resp = Hello::read(&mut self.reader).await?;
-
-        if resp.version != PROTOCOL_VERSION {
-            return Err(anyhow::anyhow!("Version mismatch: daemon {}", resp.version));
-        }
+        let resp = Hello::read_frame(&mut self.reader, &frame).await?;
+        let ours = Capabilities {
+            features,
+            ..Capabilities::local()
+        };
+        self.negotiated = ours
+            .negotiate(&resp.peer_capabilities())
+            .context("Daemon protocol version mismatch")?;
+        tracing::debug!(
+            "Negotiated protocol v{} (features {:#x})",
+            self.negotiated.version,
+            self.negotiated.features
+        );
 
         Ok(())
     }
 
-    async fn handshake_pull(&mut self) -> Result<()> {
-        let hello = Hello {
-            version: PROTOCOL_VERSION,
-            flags: HELLO_FLAG_PULL,
-            capabilities: vec![],
-        };
-
-        hello.write(&mut self.writer).await?;
-        self.writer.flush().await?;
-
-        let _len = self.reader.read_u32().await?;
-        let type_byte = self.reader.read_u8().await?;
-
-        if type_byte == MessageType::Error as u8 {
-            let err = protocol::ErrorMessage::read(&mut self.reader).await?;
-            return Err(anyhow::anyhow!("Daemon handshake error: {}", err.message));
-        }
-
-        if type_byte != MessageType::Hello as u8 {
-            return Err(anyhow::anyhow!("Expected HELLO, got 0x{:02X}", type_byte));
-        }
-
-        let resp = Hello::read(&mut self.reader).await?;
-
-        if resp.version != PROTOCOL_VERSION {
-            return Err(anyhow::anyhow!("Version mismatch: daemon {}", resp.version));
-        }
+    /// Protocol version and features agreed with the daemon
+    pub fn negotiated(&self) -> Negotiated {
+        self.negotiated
+    }
 
-        Ok(())
+    /// Bytes through the stream compressor so far, both directions
+    pub fn stream_stats(&self) -> StreamStats {
+        self.writer.stats() + self.reader.stats()
     }
 
     // =========================================================================
@@ -743,17 +1066,23 @@ impl DaemonSession {
     // =========================================================================
 
     /// Send a PING to check if daemon is alive
+    ///
+    /// A no-op for daemons that don't advertise keepalive.
     pub async fn ping(&mut self) -> Result<()> {
+        if !self.negotiated.has(CAP_KEEPALIVE) {
+            return Ok(());
+        }
         self.writer.write_u32(0).await?;
         self.writer.write_u8(MSG_PING).await?;
         self.writer.flush().await?;
 
-        let _len = self.reader.read_u32().await?;
-        let type_byte = self.reader.read_u8().await?;
+        let frame = Frame::read(&mut self.reader).await?;
+        let type_byte = frame.msg_type;
 
         if type_byte != MSG_PONG {
             return Err(anyhow::anyhow!("Expected PONG, got 0x{:02X}", type_byte));
         }
+        frame.expect_empty()?;
 
         Ok(())
     }
@@ -770,11 +1099,11 @@ impl DaemonSession {
     }
 
     pub async fn read_ack(&mut self) -> Result<FileListAck> {
-        let _len = self.reader.read_u32().await?;
-        let type_byte = self.reader.read_u8().await?;
+        let frame = Frame::read(&mut self.reader).await?;
+        let type_byte = frame.msg_type;
 
         if type_byte == MessageType::Error as u8 {
-            let err = protocol::ErrorMessage::read(&mut self.reader).await?;
+            let err = protocol::ErrorMessage::read_frame(&mut self.reader, &frame).await?;
             return Err(anyhow::anyhow!("Daemon error: {}", err.message));
         }
 
@@ -785,7 +1114,7 @@ impl DaemonSession {
             ));
         }
 
-        FileListAck::read(&mut self.reader).await
+        FileListAck::read_frame(&mut self.reader, &frame).await
     }
 
     // =========================================================================
@@ -800,11 +1129,11 @@ impl DaemonSession {
     }
 
     pub async fn read_mkdir_ack(&mut self) -> Result<MkdirBatchAck> {
-        let _len = self.reader.read_u32().await?;
-        let type_byte = self.reader.read_u8().await?;
+        let frame = Frame::read(&mut self.reader).await?;
+        let type_byte = frame.msg_type;
 
         if type_byte == MessageType::Error as u8 {
-            let err = protocol::ErrorMessage::read(&mut self.reader).await?;
+            let err = protocol::ErrorMessage::read_frame(&mut self.reader, &frame).await?;
             return Err(anyhow::anyhow!("Daemon error: {}", err.message));
         }
 
@@ -815,7 +1144,7 @@ impl DaemonSession {
             ));
         }
 
-        MkdirBatchAck::read(&mut self.reader).await
+        MkdirBatchAck::read_frame(&mut self.reader, &frame).await
     }
 
     // =========================================================================
@@ -830,11 +1159,11 @@ impl DaemonSession {
     }
 
     pub async fn read_symlink_ack(&mut self) -> Result<SymlinkBatchAck> {
-        let _len = self.reader.read_u32().await?;
-        let type_byte = self.reader.read_u8().await?;
+        let frame = Frame::read(&mut self.reader).await?;
+        let type_byte = frame.msg_type;
 
         if type_byte == MessageType::Error as u8 {
-            let err = protocol::ErrorMessage::read(&mut self.reader).await?;
+            let err = protocol::ErrorMessage::read_frame(&mut self.reader, &frame).await?;
             return Err(anyhow::anyhow!("Daemon error: {}", err.message));
         }
 
@@ -845,7 +1174,7 @@ impl DaemonSession {
             ));
         }
 
-        SymlinkBatchAck::read(&mut self.reader).await
+        SymlinkBatchAck::read_frame(&mut self.reader, &frame).await
     }
 
     // =========================================================================
@@ -903,11 +1232,11 @@ impl DaemonSession {
     }
 
     pub async fn read_file_done(&mut self) -> Result<FileDone> {
-        let _len = self.reader.read_u32().await?;
-        let type_byte = self.reader.read_u8().await?;
+        let frame = Frame::read(&mut self.reader).await?;
+        let type_byte = frame.msg_type;
 
         if type_byte == MessageType::Error as u8 {
-            let err = protocol::ErrorMessage::read(&mut self.reader).await?;
+            let err = protocol::ErrorMessage::read_frame(&mut self.reader, &frame).await?;
             return Err(anyhow::anyhow!("Daemon error: {}", err.message));
         }
 
@@ -918,32 +1247,30 @@ impl DaemonSession {
             ));
         }
 
-        FileDone::read(&mut self.reader).await
+        FileDone::read_frame(&mut self.reader, &frame).await
     }
 
     // =========================================================================
     // DELTA SYNC
     // =========================================================================
 
-    pub async fn send_checksum_req(&mut self, index: u32, block_size: u32) -> Result<()> {
-        let req = ChecksumReq { index, block_size };
+    pub async fn send_checksum_req(&mut self, req: ChecksumReq) -> Result<()> {
         req.write(&mut self.writer).await?;
         self.writer.flush().await?;
         Ok(())
     }
 
-    pub async fn send_checksum_req_no_flush(&mut self, index: u32, block_size: u32) -> Result<()> {
-        let req = ChecksumReq { index, block_size };
+    pub async fn send_checksum_req_no_flush(&mut self, req: ChecksumReq) -> Result<()> {
         req.write(&mut self.writer).await?;
         Ok(())
     }
 
     pub async fn read_checksum_resp(&mut self) -> Result<ChecksumResp> {
-        let _len = self.reader.read_u32().await?;
-        let type_byte = self.reader.read_u8().await?;
+        let frame = Frame::read(&mut self.reader).await?;
+        let type_byte = frame.msg_type;
 
         if type_byte == MessageType::Error as u8 {
-            let err = protocol::ErrorMessage::read(&mut self.reader).await?;
+            let err = protocol::ErrorMessage::read_frame(&mut self.reader, &frame).await?;
             return Err(anyhow::anyhow!("Daemon error: {}", err.message));
         }
 
@@ -954,7 +1281,7 @@ impl DaemonSession {
             ));
         }
 
-        ChecksumResp::read(&mut self.reader).await
+        ChecksumResp::read_frame(&mut self.reader, &frame).await
     }
 
     pub async fn send_delta_data(
@@ -962,8 +1289,14 @@ impl DaemonSession {
         index: u32,
         flags: u8,
         ops: Vec<DeltaOp>,
+        digest: Vec<u8>,
     ) -> Result<()> {
-        let delta = DeltaData { index, flags, ops };
+        let delta = DeltaData {
+            index,
+            flags,
+            ops,
+            digest,
+        };
         delta.write(&mut self.writer).await?;
         self.writer.flush().await?;
         Ok(())
@@ -974,8 +1307,14 @@ impl DaemonSession {
         index: u32,
         flags: u8,
         ops: Vec<DeltaOp>,
+        digest: Vec<u8>,
     ) -> Result<()> {
-        let delta = DeltaData { index, flags, ops };
+        let delta = DeltaData {
+            index,
+            flags,
+            ops,
+            digest,
+        };
         delta.write(&mut self.writer).await?;
         Ok(())
     }
@@ -985,11 +1324,11 @@ impl DaemonSession {
     // =========================================================================
 
     pub async fn read_mkdir_batch(&mut self) -> Result<MkdirBatch> {
-        let _len = self.reader.read_u32().await?;
-        let type_byte = self.reader.read_u8().await?;
+        let frame = Frame::read(&mut self.reader).await?;
+        let type_byte = frame.msg_type;
 
         if type_byte == MessageType::Error as u8 {
-            let err = protocol::ErrorMessage::read(&mut self.reader).await?;
+            let err = protocol::ErrorMessage::read_frame(&mut self.reader, &frame).await?;
             return Err(anyhow::anyhow!("Daemon error: {}", err.message));
         }
 
@@ -1000,7 +1339,7 @@ impl DaemonSession {
             ));
         }
 
-        MkdirBatch::read(&mut self.reader).await
+        MkdirBatch::read_frame(&mut self.reader, &frame).await
     }
 
     pub async fn send_mkdir_batch_ack(
@@ -1015,11 +1354,11 @@ impl DaemonSession {
     }
 
     pub async fn read_file_list(&mut self) -> Result<FileList> {
-        let _len = self.reader.read_u32().await?;
-        let type_byte = self.reader.read_u8().await?;
+        let frame = Frame::read(&mut self.reader).await?;
+        let type_byte = frame.msg_type;
 
         if type_byte == MessageType::Error as u8 {
-            let err = protocol::ErrorMessage::read(&mut self.reader).await?;
+            let err = protocol::ErrorMessage::read_frame(&mut self.reader, &frame).await?;
             return Err(anyhow::anyhow!("Daemon error: {}", err.message));
         }
 
@@ -1030,7 +1369,7 @@ impl DaemonSession {
             ));
         }
 
-        FileList::read(&mut self.reader).await
+        FileList::read_frame(&mut self.reader, &frame).await
     }
 
     pub async fn send_file_list_ack(&mut self, decisions: Vec<Decision>) -> Result<()> {
@@ -1041,15 +1380,16 @@ impl DaemonSession {
     }
 
     pub async fn read_file_data(&mut self) -> Result<Option<FileData>> {
-        let _len = self.reader.read_u32().await?;
-        let type_byte = self.reader.read_u8().await?;
+        let frame = Frame::read(&mut self.reader).await?;
+        let type_byte = frame.msg_type;
 
         if type_byte == MessageType::SymlinkBatch as u8 {
+            self.symlink_frame = Some(frame);
             return Ok(None);
         }
 
         if type_byte == MessageType::Error as u8 {
-            let err = protocol::ErrorMessage::read(&mut self.reader).await?;
+            let err = protocol::ErrorMessage::read_frame(&mut self.reader, &frame).await?;
             return Err(anyhow::anyhow!("Daemon error: {}", err.message));
         }
 
@@ -1060,15 +1400,20 @@ impl DaemonSession {
             ));
         }
 
-        let data = FileData::read(&mut self.reader).await?;
+        let data = FileData::read_frame(&mut self.reader, &frame).await?;
         Ok(Some(data))
     }
 
-    pub async fn send_file_done(&mut self, index: u32, status: u8) -> Result<()> {
+    pub async fn send_file_done(
+        &mut self,
+        index: u32,
+        status: u8,
+        checksum: Vec<u8>,
+    ) -> Result<()> {
         let done = FileDone {
             index,
             status,
-            checksum: vec![],
+            checksum,
         };
         done.write(&mut self.writer).await?;
         self.writer.flush().await?;
@@ -1076,7 +1421,22 @@ impl DaemonSession {
     }
 
     pub async fn read_symlink_batch_body(&mut self) -> Result<SymlinkBatch> {
-        SymlinkBatch::read(&mut self.reader).await
+        let frame = self
+            .symlink_frame
+            .take()
+            .context("No SYMLINK_BATCH header has been read")?;
+        SymlinkBatch::read_frame(&mut self.reader, &frame).await
+    }
+
+    /// Read the SYMLINK_BATCH that follows the last file; fails at end of
+    /// stream, which is how a daemon without symlinks ends the session
+    pub async fn read_symlink_batch(&mut self) -> Result<SymlinkBatch> {
+        let frame = Frame::read(&mut self.reader).await?;
+        let type_byte = frame.msg_type;
+        if type_byte != MessageType::SymlinkBatch as u8 {
+            anyhow::bail!("Expected SYMLINK_BATCH, got 0x{:02X}", type_byte);
+        }
+        SymlinkBatch::read_frame(&mut self.reader, &frame).await
     }
 
     pub async fn send_symlink_batch_ack(
@@ -1095,10 +1455,300 @@ impl DaemonSession {
     // =========================================================================
 
     /// Close the session gracefully
-    pub async fn close(self) -> Result<()> {
+    pub async fn close(mut self) -> Result<()> {
         // Dropping the reader/writer closes the connection
+        let _ = self.writer.shutdown().await;
         drop(self.reader);
         drop(self.writer);
         Ok(())
     }
 }
+
+// =============================================================================
+// PushSession - operations shared by both session types
+// =============================================================================
+
+/// The PUSH-mode operations of [`ServerSession`] and [`DaemonSession`]
+///
+/// Lets the push pipeline in `server_mode` run over either connection, e.g.
+/// for watch mode, which keeps one session open for its whole lifetime.
+#[async_trait]
+pub trait PushSession: Send {
+    fn negotiated(&self) -> Negotiated;
+    fn stream_stats(&self) -> StreamStats;
+    async fn ping(&mut self) -> Result<()>;
+    async fn send_file_list(&mut self, entries: Vec<FileListEntry>) -> Result<()>;
+    async fn read_ack(&mut self) -> Result<FileListAck>;
+    async fn send_mkdir_batch(&mut self, paths: Vec<String>) -> Result<()>;
+    async fn read_mkdir_ack(&mut self) -> Result<MkdirBatchAck>;
+    async fn send_symlink_batch(&mut self, entries: Vec<SymlinkEntry>) -> Result<()>;
+    async fn read_symlink_ack(&mut self) -> Result<SymlinkBatchAck>;
+    async fn send_file_data_with_flags(
+        &mut self,
+        index: u32,
+        offset: u64,
+        flags: u8,
+        data: Vec<u8>,
+    ) -> Result<()>;
+    async fn flush(&mut self) -> Result<()>;
+    async fn read_file_done(&mut self) -> Result<FileDone>;
+    async fn send_checksum_req_no_flush(&mut self, req: ChecksumReq) -> Result<()>;
+    async fn read_checksum_resp(&mut self) -> Result<ChecksumResp>;
+    async fn send_delta_data_no_flush(
+        &mut self,
+        index: u32,
+        flags: u8,
+        ops: Vec<DeltaOp>,
+        digest: Vec<u8>,
+    ) -> Result<()>;
+}
+
+/// Forward `PushSession` to the inherent methods of a session type
+macro_rules! impl_push_session {
+    ($session:ty) => {
+        #[async_trait]
+        impl PushSession for $session {
+            fn negotiated(&self) -> Negotiated {
+                <$session>::negotiated(self)
+            }
+            fn stream_stats(&self) -> StreamStats {
+                <$session>::stream_stats(self)
+            }
+            async fn ping(&mut self) -> Result<()> {
+                <$session>::ping(self).await
+            }
+            async fn send_file_list(&mut self, entries: Vec<FileListEntry>) -> Result<()> {
+                <$session>::send_file_list(self, entries).await
+            }
+            async fn read_ack(&mut self) -> Result<FileListAck> {
+                <$session>::read_ack(self).await
+            }
+            async fn send_mkdir_batch(&mut self, paths: Vec<String>) -> Result<()> {
+                <$session>::send_mkdir_batch(self, paths).await
+            }
+            async fn read_mkdir_ack(&mut self) -> Result<MkdirBatchAck> {
+                <$session>::read_mkdir_ack(self).await
+            }
+            async fn send_symlink_batch(&mut self, entries: Vec<SymlinkEntry>) -> Result<()> {
+                <$session>::send_symlink_batch(self, entries).await
+            }
+            async fn read_symlink_ack(&mut self) -> Result<SymlinkBatchAck> {
+                <$session>::read_symlink_ack(self).await
+            }
+            async fn send_file_data_with_flags(
+                &mut self,
+                index: u32,
+                offset: u64,
+                flags: u8,
+                data: Vec<u8>,
+            ) -> Result<()> {
+                <$session>::send_file_data_with_flags(self, index, offset, flags, data).await
+            }
+            async fn flush(&mut self) -> Result<()> {
+                <$session>::flush(self).await
+            }
+            async fn read_file_done(&mut self) -> Result<FileDone> {
+                <$session>::read_file_done(self).await
+            }
+            async fn send_checksum_req_no_flush(&mut self, req: ChecksumReq) -> Result<()> {
+                <$session>::send_checksum_req_no_flush(self, req).await
+            }
+            async fn read_checksum_resp(&mut self) -> Result<ChecksumResp> {
+                <$session>::read_checksum_resp(self).await
+            }
+            async fn send_delta_data_no_flush(
+                &mut self,
+                index: u32,
+                flags: u8,
+                ops: Vec<DeltaOp>,
+                digest: Vec<u8>,
+            ) -> Result<()> {
+                <$session>::send_delta_data_no_flush(self, index, flags, ops, digest).await
+            }
+        }
+    };
+}
+
+impl_push_session!(ServerSession);
+#[cfg(unix)]
+impl_push_session!(DaemonSession);
+
+// =============================================================================
+// PullSession - receiving files on either session type
+// =============================================================================
+
+/// A file [`PullSession::receive_file`] wrote and renamed into place
+#[derive(Debug, Clone, Default)]
+pub struct Received {
+    /// Where the file was written
+    pub path: PathBuf,
+    pub bytes: u64,
+    /// Digest of the written file, to echo in FILE_DONE (empty if none came)
+    pub checksum: Vec<u8>,
+}
+
+/// The PULL-mode file receiving of [`ServerSession`] and [`DaemonSession`]
+#[async_trait]
+pub trait PullSession: Send {
+    async fn read_file_data(&mut self) -> Result<Option<FileData>>;
+    fn negotiated(&self) -> Negotiated;
+    fn stale_partials(&mut self) -> &mut StalePartials;
+
+    /// Receive one file's FILE_DATA into `rel_path` beneath `confine`'s
+    /// root, starting with its first chunk
+    ///
+    /// `rel_path` usually is `entry.path`, which comes from the peer: a path
+    /// that is absolute, climbs out with `..` or leads through a symlink
+    /// outside the root fails like a write error.
+    ///
+    /// Chunks are written to the file's partial (see [`partial`]), which is
+    /// renamed into place once complete. Sparse and split (CAP_RESUME or
+    /// CAP_FILE_CHECKSUM) files arrive as several chunks ending in
+    /// `DATA_FLAG_FINAL`; everything else is one chunk. A resumed or verified
+    /// file ends with a digest chunk, checked before the rename. Returns what
+    /// was written, or the error (a [`digest::mismatch`] for a failed check)
+    /// once the file's remaining chunks have been drained, so the stream stays
+    /// in step.
+    ///
+    /// [`digest::mismatch`]: crate::server::digest::mismatch
+    async fn receive_file(
+        &mut self,
+        confine: &Confinement,
+        rel_path: &str,
+        entry: &FileListEntry,
+        first: FileData,
+    ) -> Result<std::io::Result<Received>> {
+        let chunked = layout::final_chunks(self.negotiated());
+        let partial_rel = partial::partial_rel(rel_path, entry.size, entry.mtime);
+        let mut written = confine
+            .resolve(rel_path)
+            .map_err(std::io::Error::other)
+            .and_then(|path| {
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                Ok(Received {
+                    path,
+                    ..Default::default()
+                })
+            });
+        let mut chunk = first;
+        loop {
+            if let Ok(received) = written {
+                written = if chunk.flags & DATA_FLAG_DIGEST != 0 {
+                    confine
+                        .resolve(&partial_rel)
+                        .map_err(std::io::Error::other)
+                        .and_then(|partial_path| partial::verify(&partial_path, &chunk.data))
+                        .map(|checksum| Received {
+                            checksum,
+                            ..received
+                        })
+                } else {
+                    confine
+                        .create_file(&partial_rel, chunk.offset == 0)
+                        .map_err(std::io::Error::other)
+                        .and_then(|mut file| {
+                            layout::write_chunk(
+                                &mut file,
+                                entry.size,
+                                chunk.offset,
+                                &chunk.data,
+                                chunk.flags,
+                            )
+                        })
+                        .map(|_| Received {
+                            bytes: received.bytes + chunk.data.len() as u64,
+                            ..received
+                        })
+                };
+            }
+            if layout::is_last_chunk(chunk.flags, chunked) {
+                return Ok(written.and_then(|received| {
+                    let partial_path = confine
+                        .resolve(&partial_rel)
+                        .map_err(std::io::Error::other)?;
+                    std::fs::rename(&partial_path, &received.path)?;
+                    self.stale_partials().remove(&received.path);
+                    Ok(received)
+                }));
+            }
+            chunk = self
+                .read_file_data()
+                .await?
+                .ok_or_else(|| anyhow::anyhow!("File {} ended before its last chunk", rel_path))?;
+        }
+    }
+}
+
+macro_rules! impl_pull_session {
+    ($session:ty) => {
+        #[async_trait]
+        impl PullSession for $session {
+            async fn read_file_data(&mut self) -> Result<Option<FileData>> {
+                <$session>::read_file_data(self).await
+            }
+            fn negotiated(&self) -> Negotiated {
+                <$session>::negotiated(self)
+            }
+            fn stale_partials(&mut self) -> &mut StalePartials {
+                &mut self.stale_partials
+            }
+        }
+    };
+}
+
+impl_pull_session!(ServerSession);
+#[cfg(unix)]
+impl_pull_session!(DaemonSession);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rsh_appends_host_and_server_command() {
+        let rsh = RemoteShell::parse("ssh -p 2222 -o 'Compression no'").unwrap();
+        let args = rsh.server_args(
+            "backup.example.com",
+            Some("alice"),
+            Path::new("/srv/data"),
+            false,
+        );
+        assert_eq!(
+            args,
+            [
+                "ssh",
+                "-p",
+                "2222",
+                "-o",
+                "Compression no",
+                "-l",
+                "alice",
+                "backup.example.com",
+                "sy",
+                "--server",
+                "/srv/data"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rsh_host_placeholder() {
+        let rsh = RemoteShell::parse("kubectl exec -i %h --").unwrap();
+        let args = rsh.server_args("web-0", None, Path::new("/app"), false);
+        assert_eq!(
+            args,
+            ["kubectl", "exec", "-i", "web-0", "--", "sy", "--server", "/app"]
+        );
+
+        let args = rsh.server_args("web-0", None, Path::new("/app"), true);
+        assert_eq!(args[5..], ["sy", "--server", "--safe-links", "/app"]);
+    }
+
+    #[test]
+    fn test_rsh_rejects_invalid_commands() {
+        assert!(RemoteShell::parse("").is_err());
+        assert!(RemoteShell::parse("ssh -o 'unterminated").is_err());
+    }
+}
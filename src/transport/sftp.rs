@@ -0,0 +1,420 @@
+use super::ssh::SshTransport;
+use super::{FileInfo, TransferResult, Transport};
+use crate::delta::BlockSizePolicy;
+use crate::error::{Result, SyncError};
+use crate::sync::scanner::{FileEntry, ScanOptions};
+use async_trait::async_trait;
+use ssh2::{ErrorCode, FileStat, RenameFlags, Sftp};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// LIBSSH2_FX_NO_SUCH_FILE
+const FX_NO_SUCH_FILE: i32 = 2;
+
+/// Pure-SFTP transport for hosts where commands can't be executed
+///
+/// Locked-down appliances and chrooted `ForceCommand internal-sftp` accounts
+/// accept SFTP but refuse (or hijack) exec requests, so neither `sy --server`
+/// nor `sy-remote` can run. This transport reuses the SSH connection pool but
+/// only speaks SFTP:
+/// - Scanning via SFTP readdir (`scan_sftp_recursive`)
+/// - Whole-file transfers (no delta sync)
+/// - Permissions and mtimes via setstat
+///
+/// Hardlinks, xattrs, ACLs and BSD flags are not available over plain SFTP.
+pub struct SftpTransport {
+    ssh: SshTransport,
+}
+
+impl SftpTransport {
+    /// Wrap an already-connected SSH transport
+    pub fn new(ssh: SshTransport) -> Self {
+        Self { ssh }
+    }
+
+    /// Run a blocking closure against a fresh SFTP channel
+    async fn with_sftp<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Sftp) -> Result<T> + Send + 'static,
+    {
+        let session_arc = self.ssh.session();
+
+        tokio::task::spawn_blocking(move || {
+            let session = session_arc.lock().map_err(|e| {
+                SyncError::Io(std::io::Error::other(format!(
+                    "Failed to lock session: {}",
+                    e
+                )))
+            })?;
+
+            let sftp = session.sftp().map_err(|e| {
+                SyncError::Io(std::io::Error::other(format!(
+                    "Failed to create SFTP session: {}",
+                    e
+                )))
+            })?;
+
+            f(&sftp)
+        })
+        .await
+        .map_err(|e| SyncError::Io(std::io::Error::other(e.to_string())))?
+    }
+}
+
+/// Whether an SFTP error means the path doesn't exist
+fn is_not_found(e: &ssh2::Error) -> bool {
+    matches!(e.code(), ErrorCode::SFTP(FX_NO_SUCH_FILE))
+}
+
+fn sftp_error(action: &str, path: &Path, e: ssh2::Error) -> SyncError {
+    let kind = if is_not_found(&e) {
+        std::io::ErrorKind::NotFound
+    } else {
+        std::io::ErrorKind::Other
+    };
+    SyncError::Io(std::io::Error::new(
+        kind,
+        format!("Failed to {} {}: {}", action, path.display(), e),
+    ))
+}
+
+/// Attributes applied after an upload: permission bits and mtime
+fn upload_stat(mode: Option<u32>, mtime: SystemTime) -> FileStat {
+    let mtime_secs = mtime
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    FileStat {
+        size: None,
+        uid: None,
+        gid: None,
+        perm: mode.map(|m| m & 0o7777),
+        atime: Some(mtime_secs),
+        mtime: Some(mtime_secs),
+    }
+}
+
+/// Create `path` and any missing ancestors with SFTP mkdir
+fn mkdir_all(sftp: &Sftp, path: &Path) -> Result<()> {
+    let mut current = PathBuf::new();
+    for component in path.components() {
+        current.push(component);
+        if !matches!(component, Component::Normal(_)) {
+            continue;
+        }
+        match sftp.stat(&current) {
+            Ok(stat) if stat.is_dir() => {}
+            Ok(_) => {
+                return Err(SyncError::Io(std::io::Error::other(format!(
+                    "Remote path {} exists and is not a directory",
+                    current.display()
+                ))))
+            }
+            Err(_) => sftp
+                .mkdir(&current, 0o755)
+                .map_err(|e| sftp_error("create remote directory", &current, e))?,
+        }
+    }
+    Ok(())
+}
+
+/// Remove a directory tree depth-first (SFTP rmdir only removes empty directories)
+fn remove_tree(sftp: &Sftp, path: &Path) -> Result<()> {
+    let entries = sftp
+        .readdir(path)
+        .map_err(|e| sftp_error("read remote directory", path, e))?;
+
+    for (entry_path, stat) in entries {
+        if matches!(
+            entry_path.file_name().and_then(|n| n.to_str()),
+            Some(".") | Some("..")
+        ) {
+            continue;
+        }
+        if stat.is_dir() {
+            remove_tree(sftp, &entry_path)?;
+        } else {
+            sftp.unlink(&entry_path)
+                .map_err(|e| sftp_error("remove remote file", &entry_path, e))?;
+        }
+    }
+
+    sftp.rmdir(path)
+        .map_err(|e| sftp_error("remove remote directory", path, e))
+}
+
+#[async_trait]
+impl Transport for SftpTransport {
+    fn set_scan_options(&mut self, options: ScanOptions) {
+        self.ssh.set_scan_options(options);
+    }
+
+    fn set_block_size(&mut self, policy: BlockSizePolicy) {
+        self.ssh.set_block_size(policy);
+    }
+
+    async fn prepare_for_transfer(&self, file_count: usize) -> Result<()> {
+        self.ssh.prepare_for_transfer(file_count).await
+    }
+
+    fn is_sftp_only(&self) -> bool {
+        true
+    }
+
+    async fn scan(&self, path: &Path) -> Result<Vec<FileEntry>> {
+        let mut entries = self.ssh.scan_sftp_recursive(path).await?;
+
+        // readdir doesn't report symlink targets; resolve them in one pass
+        let links: Vec<(usize, PathBuf)> = entries
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| e.is_symlink)
+            .map(|(i, e)| (i, (*e.path).clone()))
+            .collect();
+        if !links.is_empty() {
+            let targets = self
+                .with_sftp(move |sftp| {
+                    Ok(links
+                        .into_iter()
+                        .filter_map(|(i, link)| sftp.readlink(&link).ok().map(|t| (i, t)))
+                        .collect::<Vec<_>>())
+                })
+                .await?;
+            for (i, target) in targets {
+                entries[i].symlink_target = Some(Arc::new(target));
+            }
+        }
+
+        Ok(entries)
+    }
+
+    async fn scan_flat(&self, path: &Path) -> Result<Vec<FileEntry>> {
+        // SshTransport's flat scan is already pure SFTP
+        self.ssh.scan_flat(path).await
+    }
+
+    async fn exists(&self, path: &Path) -> Result<bool> {
+        let path_buf = path.to_path_buf();
+        self.with_sftp(move |sftp| match sftp.lstat(&path_buf) {
+            Ok(_) => Ok(true),
+            Err(e) if is_not_found(&e) => Ok(false),
+            Err(e) => Err(sftp_error("stat", &path_buf, e)),
+        })
+        .await
+    }
+
+    async fn metadata(&self, _path: &Path) -> Result<std::fs::Metadata> {
+        Err(SyncError::Io(std::io::Error::other(
+            "metadata() not supported for SFTP, use file_info() instead",
+        )))
+    }
+
+    async fn file_info(&self, path: &Path) -> Result<FileInfo> {
+        self.ssh.file_info(path).await
+    }
+
+    async fn create_dir_all(&self, path: &Path) -> Result<()> {
+        let path_buf = path.to_path_buf();
+        self.with_sftp(move |sftp| mkdir_all(sftp, &path_buf)).await
+    }
+
+    async fn copy_file(&self, source: &Path, dest: &Path) -> Result<TransferResult> {
+        let source_buf = source.to_path_buf();
+        let dest_buf = dest.to_path_buf();
+
+        self.with_sftp(move |sftp| {
+            let metadata = std::fs::metadata(&source_buf)?;
+            let mtime = metadata.modified()?;
+            #[cfg(unix)]
+            let mode = {
+                use std::os::unix::fs::PermissionsExt;
+                Some(metadata.permissions().mode())
+            };
+            #[cfg(not(unix))]
+            let mode = None;
+
+            let mut local = std::fs::File::open(&source_buf)?;
+            let mut remote = match sftp.create(&dest_buf) {
+                Ok(file) => file,
+                Err(e) if is_not_found(&e) => {
+                    // Parent directory missing: create it and retry once
+                    if let Some(parent) = dest_buf.parent() {
+                        mkdir_all(sftp, parent)?;
+                    }
+                    sftp.create(&dest_buf)
+                        .map_err(|e| sftp_error("create remote file", &dest_buf, e))?
+                }
+                Err(e) => return Err(sftp_error("create remote file", &dest_buf, e)),
+            };
+
+            let bytes_written = std::io::copy(&mut local, &mut remote).map_err(|e| {
+                SyncError::Io(std::io::Error::new(
+                    e.kind(),
+                    format!("Failed to upload to {}: {}", dest_buf.display(), e),
+                ))
+            })?;
+            drop(remote);
+
+            if let Err(e) = sftp.setstat(&dest_buf, upload_stat(mode, mtime)) {
+                // Some servers refuse setstat; the data still made it
+                tracing::warn!("Failed to set attributes on {}: {}", dest_buf.display(), e);
+            }
+
+            Ok(TransferResult::new(bytes_written))
+        })
+        .await
+    }
+
+    async fn remove(&self, path: &Path, is_dir: bool) -> Result<()> {
+        let path_buf = path.to_path_buf();
+        self.with_sftp(move |sftp| {
+            let result = if is_dir {
+                remove_tree(sftp, &path_buf)
+            } else {
+                sftp.unlink(&path_buf)
+                    .map_err(|e| sftp_error("remove remote file", &path_buf, e))
+            };
+            match result {
+                // Already gone counts as removed (matches `rm -f`)
+                Err(SyncError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+                other => other,
+            }
+        })
+        .await
+    }
+
+    async fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        let from_buf = from.to_path_buf();
+        let to_buf = to.to_path_buf();
+        self.with_sftp(move |sftp| {
+            if let Some(parent) = to_buf.parent() {
+                mkdir_all(sftp, parent)?;
+            }
+            let flags = Some(RenameFlags::OVERWRITE | RenameFlags::ATOMIC | RenameFlags::NATIVE);
+            if sftp.rename(&from_buf, &to_buf, flags).is_ok() {
+                return Ok(());
+            }
+            // SFTPv3 servers refuse to rename over an existing file
+            sftp.unlink(&to_buf).ok();
+            sftp.rename(&from_buf, &to_buf, None)
+                .map_err(|e| sftp_error("rename", &from_buf, e))
+        })
+        .await
+    }
+
+    async fn create_hardlink(&self, _source: &Path, _dest: &Path) -> Result<()> {
+        Err(SyncError::Io(std::io::Error::other(
+            "Hardlinks not supported over SFTP-only connections",
+        )))
+    }
+
+    async fn create_symlink(&self, target: &Path, dest: &Path) -> Result<()> {
+        let target_buf = target.to_path_buf();
+        let dest_buf = dest.to_path_buf();
+        self.with_sftp(move |sftp| {
+            if let Some(parent) = dest_buf.parent() {
+                mkdir_all(sftp, parent)?;
+            }
+            // Replace an existing link, like `ln -sf`
+            sftp.unlink(&dest_buf).ok();
+            sftp.symlink(&target_buf, &dest_buf)
+                .map_err(|e| sftp_error("create symlink", &dest_buf, e))
+        })
+        .await
+    }
+
+    async fn read_file(&self, path: &Path) -> Result<Vec<u8>> {
+        self.ssh.read_file(path).await
+    }
+
+    async fn compute_checksum(
+        &self,
+        path: &Path,
+        verifier: &crate::integrity::IntegrityVerifier,
+    ) -> Result<crate::integrity::Checksum> {
+        // No remote hashing without exec: stream the file through the hasher
+        let path_buf = path.to_path_buf();
+        let verifier = verifier.clone();
+        self.with_sftp(move |sftp| {
+            let mut file = sftp
+                .open(&path_buf)
+                .map_err(|e| sftp_error("open", &path_buf, e))?;
+            verifier.compute_reader_checksum(&mut file)
+        })
+        .await
+    }
+
+    async fn write_file(&self, path: &Path, data: &[u8], mtime: SystemTime) -> Result<()> {
+        self.ssh.write_file(path, data, mtime).await
+    }
+
+    async fn get_mtime(&self, path: &Path) -> Result<SystemTime> {
+        let path_buf = path.to_path_buf();
+        self.with_sftp(move |sftp| {
+            let stat = sftp
+                .stat(&path_buf)
+                .map_err(|e| sftp_error("stat", &path_buf, e))?;
+            Ok(UNIX_EPOCH + Duration::from_secs(stat.mtime.unwrap_or(0)))
+        })
+        .await
+    }
+
+    async fn copy_file_streaming(
+        &self,
+        source: &Path,
+        dest: &Path,
+        progress_callback: Option<std::sync::Arc<dyn Fn(u64, u64) + Send + Sync>>,
+    ) -> Result<TransferResult> {
+        // SshTransport streams remote → local over SFTP already
+        self.ssh
+            .copy_file_streaming(source, dest, progress_callback)
+            .await
+    }
+
+    async fn check_disk_space(&self, _path: &Path, _bytes_needed: u64) -> Result<()> {
+        // Plain SFTPv3 has no statvfs; let the server report a full disk on write
+        Ok(())
+    }
+
+    async fn set_xattrs(&self, _path: &Path, _xattrs: &[(String, Vec<u8>)]) -> Result<()> {
+        // Extended attributes are not supported over SFTP - silently ignore
+        Ok(())
+    }
+
+    async fn set_acls(&self, _path: &Path, _acls_text: &str) -> Result<()> {
+        // ACLs are not supported over SFTP - silently ignore
+        Ok(())
+    }
+
+    async fn set_bsd_flags(&self, _path: &Path, _flags: u32) -> Result<()> {
+        // BSD flags are not supported over SFTP - silently ignore
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upload_stat_preserves_mode_and_mtime() {
+        let mtime = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let stat = upload_stat(Some(0o100644), mtime);
+
+        // File type bits are stripped, permission bits kept
+        assert_eq!(stat.perm, Some(0o644));
+        assert_eq!(stat.mtime, Some(1_700_000_000));
+        assert_eq!(stat.atime, Some(1_700_000_000));
+        assert_eq!(stat.size, None);
+        assert_eq!(stat.uid, None);
+    }
+
+    #[test]
+    fn test_upload_stat_without_mode() {
+        let stat = upload_stat(None, UNIX_EPOCH);
+        assert_eq!(stat.perm, None);
+        assert_eq!(stat.mtime, Some(0));
+    }
+}
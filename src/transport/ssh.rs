@@ -1,7 +1,7 @@
 use super::{TransferResult, Transport};
 use crate::binary;
 use crate::compress::{compress, should_compress_smart, Compression, CompressionDetection};
-use crate::delta::{calculate_block_size, generate_delta_streaming, BlockChecksum, DeltaOp};
+use crate::delta::{generate_delta_streaming, BlockChecksum, BlockSizePolicy, DeltaOp};
 use crate::error::{Result, SyncError};
 use crate::resume::{TransferState, DEFAULT_CHUNK_SIZE};
 use crate::retry::{retry_with_backoff, RetryConfig};
@@ -21,6 +21,93 @@ use std::time::{Duration, Instant, UNIX_EPOCH};
 #[cfg(unix)]
 use std::os::unix::io::AsRawFd;
 
+/// LIBSSH2_ERROR_CHANNEL_REQUEST_DENIED
+const CHANNEL_REQUEST_DENIED: i32 = -22;
+
+/// Whether an exec request failed because the server refused it
+fn is_exec_refusal(e: &ssh2::Error) -> bool {
+    matches!(e.code(), ssh2::ErrorCode::Session(CHANNEL_REQUEST_DENIED))
+}
+
+/// How long a remembered exec probe result is trusted
+const EXEC_PROBE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct ExecProbe {
+    exec: bool,
+    /// Unix time of the probe
+    checked: u64,
+}
+
+/// Exec probe results per host
+///
+/// Kept in `~/.cache/sy/exec-probe.json`, so connecting to a host doesn't
+/// cost a probe round trip every time. Whether an account may run commands
+/// rarely changes; results are probed again after a day.
+#[derive(Debug, Clone)]
+pub struct ExecProbeCache {
+    path: Option<PathBuf>,
+}
+
+impl ExecProbeCache {
+    /// The cache in the user's cache directory
+    pub fn load() -> Self {
+        Self {
+            path: dirs::cache_dir().map(|dir| dir.join("sy").join("exec-probe.json")),
+        }
+    }
+
+    /// A cache kept at `path`
+    #[cfg(test)]
+    fn at(path: PathBuf) -> Self {
+        Self { path: Some(path) }
+    }
+
+    /// Whether `host` allowed exec when last probed, if that was recent
+    pub fn get(&self, host: &str) -> Option<bool> {
+        let probe = *self.read().get(host)?;
+        let age = unix_now().saturating_sub(probe.checked);
+        (age < EXEC_PROBE_TTL.as_secs()).then_some(probe.exec)
+    }
+
+    /// Remember a probe result (best effort)
+    pub fn record(&self, host: &str, exec: bool) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let mut probes = self.read();
+        let now = unix_now();
+        probes.retain(|_, p| now.saturating_sub(p.checked) < EXEC_PROBE_TTL.as_secs());
+        probes.insert(host.to_string(), ExecProbe { exec, checked: now });
+        let save = || -> std::io::Result<()> {
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            let temp = path.with_extension("json.tmp");
+            std::fs::write(&temp, serde_json::to_vec(&probes)?)?;
+            std::fs::rename(&temp, path)
+        };
+        if let Err(e) = save() {
+            tracing::debug!("Failed to save exec probe cache: {}", e);
+        }
+    }
+
+    fn read(&self) -> std::collections::HashMap<String, ExecProbe> {
+        self.path
+            .as_ref()
+            .and_then(|path| std::fs::read(path).ok())
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .unwrap_or_default()
+    }
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
 /// Represents a contiguous region of data in a sparse file
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 struct DataRegion {
@@ -309,6 +396,7 @@ pub struct SshTransport {
     retry_config: RetryConfig,
     speedometer: Arc<Speedometer>,
     scan_options: ScanOptions,
+    block_sizes: BlockSizePolicy,
 }
 
 impl SshTransport {
@@ -341,6 +429,7 @@ impl SshTransport {
             retry_config,
             speedometer: Arc::new(Speedometer::new()),
             scan_options: ScanOptions::default(),
+            block_sizes: BlockSizePolicy::default(),
         })
     }
 
@@ -356,6 +445,59 @@ impl SshTransport {
         self.connection_pool.size()
     }
 
+    /// Get a session from the pool (for transports layered on this one)
+    pub(crate) fn session(&self) -> Arc<Mutex<Session>> {
+        self.connection_pool.get_session()
+    }
+
+    /// Check whether the remote host lets us execute commands
+    ///
+    /// SFTP-only accounts (`ForceCommand internal-sftp`, chrooted appliances)
+    /// either refuse the exec request or run sftp-server in place of the
+    /// command, so the probe's marker never comes back. Any other failure
+    /// (channel errors, a dropped connection) is returned, not read as a
+    /// refusal.
+    pub async fn exec_available(&self) -> Result<bool> {
+        const MARKER: &str = "sy-exec-ok";
+        let session_arc = self.connection_pool.get_session();
+
+        tokio::task::spawn_blocking(move || {
+            let probe_error = |context: &str, e: ssh2::Error| {
+                SyncError::from_ssh_io_error(std::io::Error::other(e.to_string()), context)
+            };
+
+            let session = session_arc.lock().map_err(|e| {
+                let io_err = std::io::Error::other(format!("Failed to lock session: {}", e));
+                SyncError::from_ssh_io_error(io_err, "SSH session lock")
+            })?;
+            let mut channel = session
+                .channel_session()
+                .map_err(|e| probe_error("SSH channel creation", e))?;
+            match channel.exec(&format!("echo {}", MARKER)) {
+                Ok(()) => {}
+                Err(e) if is_exec_refusal(&e) => return Ok(false),
+                Err(e) => return Err(probe_error("SSH exec probe", e)),
+            }
+            // EOF makes a hijacking sftp-server exit instead of waiting for INIT
+            channel
+                .send_eof()
+                .map_err(|e| probe_error("SSH exec probe", e))?;
+
+            let mut output = Vec::new();
+            channel
+                .read_to_end(&mut output)
+                .map_err(|e| SyncError::from_ssh_io_error(e, "SSH exec probe"))?;
+            channel
+                .wait_close()
+                .map_err(|e| probe_error("SSH exec probe", e))?;
+
+            let output = String::from_utf8_lossy(&output);
+            Ok(output.lines().any(|line| line.trim() == MARKER))
+        })
+        .await
+        .map_err(|e| SyncError::Io(std::io::Error::other(e.to_string())))?
+    }
+
     /// Deploy sy-remote binary to remote server at ~/.sy/bin/sy-remote
     /// Takes a locked session guard and deploys the binary
     /// Returns the full path to the deployed binary
@@ -1196,6 +1338,213 @@ impl SshTransport {
 
         Ok(entries)
     }
+
+    /// Delta-encode local `source` against remote `basis` and write the
+    /// result to remote `dest` (`basis` and `dest` may be the same file)
+    async fn delta_from_basis(
+        &self,
+        source: &Path,
+        basis: &Path,
+        dest: &Path,
+    ) -> Result<TransferResult> {
+        // Get source size
+        let source_meta = std::fs::metadata(source).map_err(|e| {
+            SyncError::Io(std::io::Error::new(
+                e.kind(),
+                format!("Failed to get source metadata: {}", e),
+            ))
+        })?;
+        let source_size = source_meta.len();
+
+        let source_path = source.to_path_buf();
+        let basis_path = basis.to_path_buf();
+        let dest_path = dest.to_path_buf();
+        let remote_binary = self.remote_binary_path.clone();
+        let session_clone = self.connection_pool.get_session();
+        let block_sizes = self.block_sizes.clone();
+
+        retry_with_backoff(&self.retry_config, || {
+            let source_path = source_path.clone();
+            let basis_path = basis_path.clone();
+            let dest_path = dest_path.clone();
+            let remote_binary = remote_binary.clone();
+            let session_arc = session_clone.clone();
+            let block_sizes = block_sizes.clone();
+            async move {
+                tokio::task::spawn_blocking(move || {
+                    let session = session_arc.lock().map_err(|e| {
+                        SyncError::Io(std::io::Error::other(format!(
+                            "Failed to lock session: {}",
+                            e
+                        )))
+                    })?;
+
+                    let sftp = session.sftp().map_err(|e| {
+                        SyncError::Io(std::io::Error::other(format!(
+                            "Failed to create SFTP session: {}",
+                            e
+                        )))
+                    })?;
+
+                    // Get remote file size
+                    let remote_stat = sftp.stat(&basis_path).map_err(|e| {
+                        SyncError::Io(std::io::Error::other(format!(
+                            "Failed to stat remote file {}: {}",
+                            basis_path.display(),
+                            e
+                        )))
+                    })?;
+
+                    let dest_size = remote_stat.size.unwrap_or(0);
+
+                    // Skip delta if destination is too small
+                    if dest_size < 4096 {
+                        tracing::debug!(
+                            "Remote destination too small for delta sync, using full copy"
+                        );
+                        drop(session);
+                        return Err(SyncError::Io(std::io::Error::other(
+                            "Destination too small, caller should use copy_file",
+                        )));
+                    }
+
+                    let block_size = block_sizes.block_size(&source_path, dest_size);
+
+                    // Compute checksums on remote side (avoid downloading entire file!)
+                    tracing::debug!("Computing remote checksums via sy-remote...");
+                    drop(session); // Unlock session before remote command
+
+                    let basis_path_str = basis_path.to_string_lossy();
+                    let dest_path_str = dest_path.to_string_lossy();
+                    let command = format!(
+                        "{} checksums {} --block-size {}",
+                        remote_binary, basis_path_str, block_size
+                    );
+
+                    let output = tokio::task::block_in_place(|| {
+                        Self::execute_command(Arc::clone(&session_arc), &command)
+                    })?;
+
+                    let dest_checksums: Vec<BlockChecksum> = serde_json::from_str(&output)
+                        .map_err(|e| {
+                            SyncError::Io(std::io::Error::other(format!(
+                                "Failed to parse remote checksums: {}",
+                                e
+                            )))
+                        })?;
+
+                    // Generate delta with streaming (constant memory)
+                    tracing::debug!("Generating delta with streaming...");
+                    let delta = generate_delta_streaming(&source_path, &dest_checksums, block_size)
+                        .map_err(|e| SyncError::CopyError {
+                            path: source_path.clone(),
+                            source: e,
+                        })?;
+
+                    // Calculate compression ratio
+                    let literal_bytes: u64 = delta
+                        .ops
+                        .iter()
+                        .filter_map(|op| {
+                            if let DeltaOp::Data(data) = op {
+                                Some(data.len() as u64)
+                            } else {
+                                None
+                            }
+                        })
+                        .sum();
+                    if basis_path == dest_path {
+                        block_sizes.record_delta(&source_path, literal_bytes, source_size);
+                    }
+
+                    let compression_ratio = if source_size > 0 {
+                        (literal_bytes as f64 / source_size as f64) * 100.0
+                    } else {
+                        0.0
+                    };
+
+                    // Serialize delta to JSON
+                    let delta_json = serde_json::to_string(&delta).map_err(|e| {
+                        SyncError::Io(std::io::Error::other(format!(
+                            "Failed to serialize delta: {}",
+                            e
+                        )))
+                    })?;
+
+                    // Compress delta JSON (typically 5-10x reduction for JSON data)
+                    let uncompressed_size = delta_json.len();
+                    let compressed_delta = compress(delta_json.as_bytes(), Compression::Zstd)
+                        .map_err(|e| {
+                            SyncError::Io(std::io::Error::other(format!(
+                                "Failed to compress delta: {}",
+                                e
+                            )))
+                        })?;
+                    let compressed_size = compressed_delta.len();
+
+                    tracing::debug!(
+                        "Delta: {} ops, {} bytes JSON, {} bytes compressed ({:.1}x)",
+                        delta.ops.len(),
+                        uncompressed_size,
+                        compressed_size,
+                        uncompressed_size as f64 / compressed_size as f64
+                    );
+
+                    // Apply delta on remote side (avoids uploading full file!)
+                    // Send compressed delta via stdin to avoid command line length limits
+                    tracing::debug!("Sending compressed delta to remote for application...");
+                    let temp_remote_path = format!("{}.sy-tmp", dest_path.display());
+                    let command = format!(
+                        "{} apply-delta {} {}",
+                        remote_binary, basis_path_str, temp_remote_path
+                    );
+
+                    let output = tokio::task::block_in_place(|| {
+                        Self::execute_command_with_stdin(
+                            Arc::clone(&session_arc),
+                            &command,
+                            &compressed_delta,
+                        )
+                    })?;
+
+                    #[derive(Deserialize)]
+                    struct ApplyStats {
+                        operations_count: usize,
+                        literal_bytes: u64,
+                    }
+
+                    let stats: ApplyStats = serde_json::from_str(&output).map_err(|e| {
+                        SyncError::Io(std::io::Error::other(format!(
+                            "Failed to parse apply-delta output: {}",
+                            e
+                        )))
+                    })?;
+
+                    // Rename temp file to final destination (atomic)
+                    let rename_command = format!("mv '{}' '{}'", temp_remote_path, dest_path_str);
+                    tokio::task::block_in_place(|| {
+                        Self::execute_command(Arc::clone(&session_arc), &rename_command)
+                    })?;
+
+                    tracing::info!(
+                    "Delta sync: {} ops, {:.1}% literal data, transferred ~{} bytes (delta only)",
+                    stats.operations_count,
+                    compression_ratio,
+                    literal_bytes
+                );
+
+                    Ok::<TransferResult, SyncError>(TransferResult::with_delta(
+                        source_size, // Full file size
+                        stats.operations_count,
+                        stats.literal_bytes,
+                    ))
+                })
+                .await
+                .map_err(|e| SyncError::Io(std::io::Error::other(e.to_string())))?
+            }
+        })
+        .await
+    }
 }
 
 #[async_trait]
@@ -1204,6 +1553,10 @@ impl Transport for SshTransport {
         self.scan_options = options;
     }
 
+    fn set_block_size(&mut self, policy: BlockSizePolicy) {
+        self.block_sizes = policy;
+    }
+
     async fn prepare_for_transfer(&self, file_count: usize) -> Result<()> {
         // Expand connection pool based on actual workload
         // For small syncs (1-5 files), keep 1 connection
@@ -2006,196 +2359,68 @@ impl Transport for SshTransport {
             return self.copy_file(source, dest).await;
         }
 
-        // Get source size
-        let source_meta = std::fs::metadata(source).map_err(|e| {
-            SyncError::Io(std::io::Error::new(
-                e.kind(),
-                format!("Failed to get source metadata: {}", e),
-            ))
-        })?;
-        let source_size = source_meta.len();
-
-        let source_path = source.to_path_buf();
-        let dest_path = dest.to_path_buf();
-        let remote_binary = self.remote_binary_path.clone();
-        let session_clone = self.connection_pool.get_session();
-
-        retry_with_backoff(&self.retry_config, || {
-            let source_path = source_path.clone();
-            let dest_path = dest_path.clone();
-            let remote_binary = remote_binary.clone();
-            let session_arc = session_clone.clone();
-            async move {
-                tokio::task::spawn_blocking(move || {
-                    let session = session_arc.lock().map_err(|e| {
-                        SyncError::Io(std::io::Error::other(format!(
-                            "Failed to lock session: {}",
-                            e
-                        )))
-                    })?;
-
-                    let sftp = session.sftp().map_err(|e| {
-                        SyncError::Io(std::io::Error::other(format!(
-                            "Failed to create SFTP session: {}",
-                            e
-                        )))
-                    })?;
-
-                    // Get remote file size
-                    let remote_stat = sftp.stat(&dest_path).map_err(|e| {
-                        SyncError::Io(std::io::Error::other(format!(
-                            "Failed to stat remote file {}: {}",
-                            dest_path.display(),
-                            e
-                        )))
-                    })?;
-
-                    let dest_size = remote_stat.size.unwrap_or(0);
-
-                    // Skip delta if destination is too small
-                    if dest_size < 4096 {
-                        tracing::debug!(
-                            "Remote destination too small for delta sync, using full copy"
-                        );
-                        drop(session);
-                        return Err(SyncError::Io(std::io::Error::other(
-                            "Destination too small, caller should use copy_file",
-                        )));
-                    }
-
-                    // Calculate block size
-                    let block_size = calculate_block_size(dest_size);
-
-                    // Compute checksums on remote side (avoid downloading entire file!)
-                    tracing::debug!("Computing remote checksums via sy-remote...");
-                    drop(session); // Unlock session before remote command
-
-                    let dest_path_str = dest_path.to_string_lossy();
-                    let command = format!(
-                        "{} checksums {} --block-size {}",
-                        remote_binary, dest_path_str, block_size
-                    );
-
-                    let output = tokio::task::block_in_place(|| {
-                        Self::execute_command(Arc::clone(&session_arc), &command)
-                    })?;
-
-                    let dest_checksums: Vec<BlockChecksum> = serde_json::from_str(&output)
-                        .map_err(|e| {
-                            SyncError::Io(std::io::Error::other(format!(
-                                "Failed to parse remote checksums: {}",
-                                e
-                            )))
-                        })?;
-
-                    // Generate delta with streaming (constant memory)
-                    tracing::debug!("Generating delta with streaming...");
-                    let delta = generate_delta_streaming(&source_path, &dest_checksums, block_size)
-                        .map_err(|e| SyncError::CopyError {
-                            path: source_path.clone(),
-                            source: e,
-                        })?;
-
-                    // Calculate compression ratio
-                    let literal_bytes: u64 = delta
-                        .ops
-                        .iter()
-                        .filter_map(|op| {
-                            if let DeltaOp::Data(data) = op {
-                                Some(data.len() as u64)
-                            } else {
-                                None
-                            }
-                        })
-                        .sum();
-
-                    let compression_ratio = if source_size > 0 {
-                        (literal_bytes as f64 / source_size as f64) * 100.0
-                    } else {
-                        0.0
-                    };
-
-                    // Serialize delta to JSON
-                    let delta_json = serde_json::to_string(&delta).map_err(|e| {
-                        SyncError::Io(std::io::Error::other(format!(
-                            "Failed to serialize delta: {}",
-                            e
-                        )))
-                    })?;
-
-                    // Compress delta JSON (typically 5-10x reduction for JSON data)
-                    let uncompressed_size = delta_json.len();
-                    let compressed_delta = compress(delta_json.as_bytes(), Compression::Zstd)
-                        .map_err(|e| {
-                            SyncError::Io(std::io::Error::other(format!(
-                                "Failed to compress delta: {}",
-                                e
-                            )))
-                        })?;
-                    let compressed_size = compressed_delta.len();
-
-                    tracing::debug!(
-                        "Delta: {} ops, {} bytes JSON, {} bytes compressed ({:.1}x)",
-                        delta.ops.len(),
-                        uncompressed_size,
-                        compressed_size,
-                        uncompressed_size as f64 / compressed_size as f64
-                    );
-
-                    // Apply delta on remote side (avoids uploading full file!)
-                    // Send compressed delta via stdin to avoid command line length limits
-                    tracing::debug!("Sending compressed delta to remote for application...");
-                    let temp_remote_path = format!("{}.sy-tmp", dest_path.display());
-                    let command = format!(
-                        "{} apply-delta {} {}",
-                        remote_binary, dest_path_str, temp_remote_path
-                    );
-
-                    let output = tokio::task::block_in_place(|| {
-                        Self::execute_command_with_stdin(
-                            Arc::clone(&session_arc),
-                            &command,
-                            &compressed_delta,
-                        )
-                    })?;
-
-                    #[derive(Deserialize)]
-                    struct ApplyStats {
-                        operations_count: usize,
-                        literal_bytes: u64,
-                    }
-
-                    let stats: ApplyStats = serde_json::from_str(&output).map_err(|e| {
-                        SyncError::Io(std::io::Error::other(format!(
-                            "Failed to parse apply-delta output: {}",
-                            e
-                        )))
-                    })?;
-
-                    // Rename temp file to final destination (atomic)
-                    let rename_command = format!("mv '{}' '{}'", temp_remote_path, dest_path_str);
-                    tokio::task::block_in_place(|| {
-                        Self::execute_command(Arc::clone(&session_arc), &rename_command)
-                    })?;
-
-                    tracing::info!(
-                    "Delta sync: {} ops, {:.1}% literal data, transferred ~{} bytes (delta only)",
-                    stats.operations_count,
-                    compression_ratio,
-                    literal_bytes
-                );
+        self.delta_from_basis(source, dest, dest).await
+    }
 
-                    Ok::<TransferResult, SyncError>(TransferResult::with_delta(
-                        source_size, // Full file size
-                        stats.operations_count,
-                        stats.literal_bytes,
-                    ))
-                })
-                .await
-                .map_err(|e| SyncError::Io(std::io::Error::other(e.to_string())))?
+    async fn sync_file_with_basis(
+        &self,
+        source: &Path,
+        basis: &Path,
+        dest: &Path,
+    ) -> Result<TransferResult> {
+        let result = match self.delta_from_basis(source, basis, dest).await {
+            Ok(result) => result,
+            Err(e) => {
+                tracing::debug!("Fuzzy basis delta failed ({}), using full copy", e);
+                return self.copy_file(source, dest).await;
             }
+        };
+
+        // apply-delta writes a fresh file; give it the source's mode and mtime
+        let metadata = std::fs::metadata(source)?;
+        let mtime = metadata
+            .modified()
+            .ok()
+            .and_then(|m| m.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs());
+        #[cfg(unix)]
+        let perm = Some(std::os::unix::fs::PermissionsExt::mode(
+            &metadata.permissions(),
+        ));
+        #[cfg(not(unix))]
+        let perm = None;
+        let session_arc = self.connection_pool.get_session();
+        let dest_path = dest.to_path_buf();
+        tokio::task::spawn_blocking(move || {
+            let session = session_arc.lock().map_err(|e| {
+                SyncError::Io(std::io::Error::other(format!(
+                    "Failed to lock session: {}",
+                    e
+                )))
+            })?;
+            let sftp = session.sftp().map_err(|e| {
+                SyncError::Io(std::io::Error::other(format!(
+                    "Failed to create SFTP session: {}",
+                    e
+                )))
+            })?;
+            let _ = sftp.setstat(
+                &dest_path,
+                ssh2::FileStat {
+                    size: None,
+                    uid: None,
+                    gid: None,
+                    perm,
+                    atime: mtime,
+                    mtime,
+                },
+            );
+            Ok::<(), SyncError>(())
         })
         .await
+        .map_err(|e| SyncError::Io(std::io::Error::other(e.to_string())))??;
+
+        Ok(result)
     }
 
     async fn remove(&self, path: &Path, is_dir: bool) -> Result<()> {
@@ -2212,6 +2437,27 @@ impl Transport for SshTransport {
         Ok(())
     }
 
+    async fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        let from_str = from.to_string_lossy();
+        let to_str = to.to_string_lossy();
+
+        // Ensure parent directory exists, then move in a single round trip
+        let command = match to.parent() {
+            Some(parent) => format!(
+                "mkdir -p '{}' && mv -f '{}' '{}'",
+                parent.to_string_lossy(),
+                from_str,
+                to_str
+            ),
+            None => format!("mv -f '{}' '{}'", from_str, to_str),
+        };
+
+        self.execute_command_with_retry(self.connection_pool.get_session(), &command)
+            .await?;
+
+        Ok(())
+    }
+
     async fn create_hardlink(&self, source: &Path, dest: &Path) -> Result<()> {
         let source_str = source.to_string_lossy();
         let dest_str = dest.to_string_lossy();
@@ -3081,6 +3327,48 @@ mod tests {
     use super::*;
     use std::sync::atomic::Ordering;
 
+    #[test]
+    fn test_only_denied_exec_is_a_refusal() {
+        let denied = ssh2::Error::new(
+            ssh2::ErrorCode::Session(CHANNEL_REQUEST_DENIED),
+            "Unable to complete request for channel-process-startup",
+        );
+        assert!(is_exec_refusal(&denied));
+
+        // A failed channel or a dropped connection is not a refusal
+        let channel_failure = ssh2::Error::new(ssh2::ErrorCode::Session(-21), "channel failure");
+        let socket_send = ssh2::Error::new(ssh2::ErrorCode::Session(-7), "socket send");
+        assert!(!is_exec_refusal(&channel_failure));
+        assert!(!is_exec_refusal(&socket_send));
+    }
+
+    #[test]
+    fn test_exec_probe_cache() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let path = temp.path().join("sy").join("exec-probe.json");
+        let cache = ExecProbeCache::at(path.clone());
+        assert_eq!(cache.get("alice@example.com:22"), None);
+
+        cache.record("alice@example.com:22", false);
+        cache.record("bob@example.com:22", true);
+        let cache = ExecProbeCache::at(path.clone());
+        assert_eq!(cache.get("alice@example.com:22"), Some(false));
+        assert_eq!(cache.get("bob@example.com:22"), Some(true));
+        assert_eq!(cache.get("alice@example.com:2222"), None);
+
+        // Old results are probed again
+        let stale = format!(
+            r#"{{"alice@example.com:22":{{"exec":false,"checked":{}}}}}"#,
+            unix_now() - EXEC_PROBE_TTL.as_secs()
+        );
+        std::fs::write(&path, stale).unwrap();
+        assert_eq!(cache.get("alice@example.com:22"), None);
+
+        // Unreadable caches are empty
+        std::fs::write(&path, "not json").unwrap();
+        assert_eq!(cache.get("bob@example.com:22"), None);
+    }
+
     // Helper to create a dummy connection pool for testing logic
     // Uses empty sessions list for logic tests that don't need real sessions
     fn create_test_pool(max_size: usize) -> ConnectionPool {
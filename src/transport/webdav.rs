@@ -0,0 +1,881 @@
+use super::{FileInfo, TransferResult, Transport};
+use crate::error::{Result, SyncError};
+use crate::sync::scanner::{FileEntry, ScanOptions};
+use async_trait::async_trait;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, CONTROLS};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use reqwest::{Client, Method, RequestBuilder, StatusCode};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Characters that must be percent-encoded inside a single path segment
+const SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'/')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'[')
+    .add(b']')
+    .add(b'^')
+    .add(b'`')
+    .add(b'{')
+    .add(b'|')
+    .add(b'}');
+
+/// Properties requested from PROPFIND (everything a FileEntry needs)
+const PROPFIND_BODY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="DAV:">
+  <d:prop>
+    <d:resourcetype/>
+    <d:getcontentlength/>
+    <d:getlastmodified/>
+  </d:prop>
+</d:propfind>"#;
+
+/// One `<d:response>` from a PROPFIND multistatus reply
+#[derive(Debug, Clone, PartialEq)]
+struct DavEntry {
+    path: PathBuf,
+    is_dir: bool,
+    size: u64,
+    modified: SystemTime,
+}
+
+/// WebDAV transport for Nextcloud, ownCloud and other WebDAV servers
+///
+/// Paths handed to the transport are absolute server paths
+/// (e.g. `/remote.php/dav/files/alice/artifacts/build.tar`).
+///
+/// Authentication uses HTTP basic auth:
+/// - User from `dav://user@host/...`, or SY_WEBDAV_USER
+/// - Password (or Nextcloud app password) from SY_WEBDAV_PASSWORD
+///
+/// Modification times are preserved with the `X-OC-Mtime` header where the
+/// server supports it (Nextcloud, ownCloud); other servers keep upload time.
+pub struct WebDavTransport {
+    client: Client,
+    base_url: String, // scheme://host[:port], no trailing slash
+    user: Option<String>,
+    password: Option<String>,
+}
+
+impl WebDavTransport {
+    /// Create a new WebDAV transport
+    ///
+    /// # Arguments
+    /// * `host` - Server host, optionally with port (e.g., "cloud.example.com:8443")
+    /// * `user` - Optional user name (otherwise from SY_WEBDAV_USER)
+    /// * `secure` - Use HTTPS (`davs://`) instead of plain HTTP (`dav://`)
+    pub fn new(host: String, user: Option<String>, secure: bool) -> Result<Self> {
+        let scheme = if secure { "https" } else { "http" };
+        let user = user.or_else(|| std::env::var("SY_WEBDAV_USER").ok());
+        let password = std::env::var("SY_WEBDAV_PASSWORD").ok();
+        Self::with_base_url(format!("{}://{}", scheme, host), user, password)
+    }
+
+    /// Create a WebDAV transport for an explicit base URL and credentials
+    pub fn with_base_url(
+        base_url: String,
+        user: Option<String>,
+        password: Option<String>,
+    ) -> Result<Self> {
+        let client = Client::builder()
+            .connect_timeout(Duration::from_secs(10))
+            .pool_idle_timeout(Duration::from_secs(60))
+            .build()
+            .map_err(|e| {
+                SyncError::Io(std::io::Error::other(format!(
+                    "Failed to create WebDAV client: {}",
+                    e
+                )))
+            })?;
+
+        tracing::info!("WebDAV transport initialized: {}", base_url);
+
+        Ok(Self {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            user,
+            password,
+        })
+    }
+
+    /// Build the request URL for an absolute server path
+    fn url(&self, path: &Path, is_dir: bool) -> String {
+        let mut url = self.base_url.clone();
+        for segment in path_segments(path) {
+            url.push('/');
+            url.extend(utf8_percent_encode(&segment, SEGMENT));
+        }
+        if is_dir || url.len() == self.base_url.len() {
+            url.push('/');
+        }
+        url
+    }
+
+    fn request(&self, method: Method, path: &Path, is_dir: bool) -> RequestBuilder {
+        let builder = self.client.request(method, self.url(path, is_dir));
+        match &self.user {
+            Some(user) => builder.basic_auth(user, self.password.as_ref()),
+            None => builder,
+        }
+    }
+
+    /// Issue a PROPFIND and parse the multistatus reply
+    ///
+    /// Returns `Ok(None)` if the resource does not exist.
+    async fn propfind(&self, path: &Path, depth: u8) -> Result<Option<Vec<DavEntry>>> {
+        let response = self
+            .request(Method::from_bytes(b"PROPFIND").unwrap(), path, false)
+            .header("Depth", depth.to_string())
+            .header("Content-Type", "application/xml; charset=utf-8")
+            .body(PROPFIND_BODY)
+            .send()
+            .await
+            .map_err(|e| dav_error("PROPFIND", path, e))?;
+
+        match response.status() {
+            StatusCode::NOT_FOUND => return Ok(None),
+            StatusCode::MULTI_STATUS => {}
+            status => return Err(status_error("PROPFIND", path, status)),
+        }
+
+        let body = response
+            .text()
+            .await
+            .map_err(|e| dav_error("PROPFIND", path, e))?;
+        parse_multistatus(&body).map(Some)
+    }
+
+    fn to_file_entry(entry: DavEntry, root: &Path) -> FileEntry {
+        let relative = entry
+            .path
+            .strip_prefix(root)
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|_| entry.path.clone());
+
+        FileEntry {
+            path: Arc::new(entry.path),
+            relative_path: Arc::new(relative),
+            size: entry.size,
+            modified: entry.modified,
+            is_dir: entry.is_dir,
+            is_symlink: false, // WebDAV has no symlinks
+            symlink_target: None,
+            is_sparse: false,
+            allocated_size: entry.size,
+            xattrs: None,
+            inode: None,
+            nlink: 1,
+            acls: None,
+            bsd_flags: None,
+        }
+    }
+
+    /// PUT a body, creating missing parent collections on 409 Conflict
+    async fn put<F>(&self, path: &Path, len: u64, mtime: Option<SystemTime>, body: F) -> Result<()>
+    where
+        F: Fn() -> std::result::Result<reqwest::Body, std::io::Error>,
+    {
+        for attempt in 0..2 {
+            // Explicit length: some servers reject chunked uploads
+            let mut request = self
+                .request(Method::PUT, path, false)
+                .header("Content-Length", len)
+                .body(body()?);
+            if let Some(secs) = mtime.and_then(|t| t.duration_since(UNIX_EPOCH).ok()) {
+                request = request.header("X-OC-Mtime", secs.as_secs().to_string());
+            }
+
+            let response = request
+                .send()
+                .await
+                .map_err(|e| dav_error("PUT", path, e))?;
+            let status = response.status();
+
+            if status == StatusCode::CONFLICT && attempt == 0 {
+                // Parent collection missing: create it and retry once
+                if let Some(parent) = path.parent() {
+                    self.create_dir_all(parent).await?;
+                    continue;
+                }
+            }
+            if !status.is_success() {
+                return Err(status_error("PUT", path, status));
+            }
+            if mtime.is_some() && !response.headers().contains_key("x-oc-mtime") {
+                tracing::debug!(
+                    "WebDAV server ignored X-OC-Mtime for {}; mtime not preserved",
+                    path.display()
+                );
+            }
+            return Ok(());
+        }
+        Err(status_error("PUT", path, StatusCode::CONFLICT))
+    }
+}
+
+#[async_trait]
+impl Transport for WebDavTransport {
+    fn set_scan_options(&mut self, _options: ScanOptions) {
+        // WebDAV transport currently ignores scan options
+    }
+
+    async fn scan(&self, path: &Path) -> Result<Vec<FileEntry>> {
+        // Walk with Depth: 1 - Nextcloud and most servers reject Depth: infinity
+        let mut entries = Vec::new();
+        let mut pending = VecDeque::from([path.to_path_buf()]);
+
+        while let Some(dir) = pending.pop_front() {
+            let listing = self.propfind(&dir, 1).await?.ok_or_else(|| {
+                SyncError::Io(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("WebDAV path not found: {}", dir.display()),
+                ))
+            })?;
+
+            for entry in listing {
+                // The collection itself is listed alongside its members
+                if entry.path == dir {
+                    continue;
+                }
+                if entry.is_dir {
+                    pending.push_back(entry.path.clone());
+                }
+                entries.push(Self::to_file_entry(entry, path));
+            }
+        }
+
+        Ok(entries)
+    }
+
+    async fn scan_flat(&self, path: &Path) -> Result<Vec<FileEntry>> {
+        let listing = self.propfind(path, 1).await?.unwrap_or_default();
+        Ok(listing
+            .into_iter()
+            .filter(|entry| entry.path != path)
+            .map(|entry| Self::to_file_entry(entry, path))
+            .collect())
+    }
+
+    async fn exists(&self, path: &Path) -> Result<bool> {
+        Ok(self.propfind(path, 0).await?.is_some())
+    }
+
+    async fn metadata(&self, _path: &Path) -> Result<std::fs::Metadata> {
+        // WebDAV doesn't have std::fs::Metadata, this method shouldn't be used
+        Err(SyncError::Io(std::io::Error::other(
+            "metadata() not supported for WebDAV, use file_info() instead",
+        )))
+    }
+
+    async fn file_info(&self, path: &Path) -> Result<FileInfo> {
+        let entry = self
+            .propfind(path, 0)
+            .await?
+            .and_then(|entries| entries.into_iter().next())
+            .ok_or_else(|| {
+                SyncError::Io(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("WebDAV path not found: {}", path.display()),
+                ))
+            })?;
+
+        Ok(FileInfo {
+            size: entry.size,
+            modified: entry.modified,
+        })
+    }
+
+    async fn create_dir_all(&self, path: &Path) -> Result<()> {
+        // MKCOL only creates one level, so create each ancestor in turn
+        let mut current = PathBuf::from("/");
+        for segment in path_segments(path) {
+            current.push(segment);
+
+            let response = self
+                .request(Method::from_bytes(b"MKCOL").unwrap(), &current, true)
+                .send()
+                .await
+                .map_err(|e| dav_error("MKCOL", &current, e))?;
+
+            // 405 Method Not Allowed: collection already exists
+            let status = response.status();
+            if !status.is_success() && status != StatusCode::METHOD_NOT_ALLOWED {
+                return Err(status_error("MKCOL", &current, status));
+            }
+        }
+        Ok(())
+    }
+
+    async fn copy_file(&self, source: &Path, dest: &Path) -> Result<TransferResult> {
+        let metadata = tokio::fs::metadata(source).await?;
+        let size = metadata.len();
+        let mtime = metadata.modified().ok();
+
+        // Stream the file body; reopened if the PUT has to be retried
+        self.put(dest, size, mtime, || {
+            let file = std::fs::File::open(source)?;
+            Ok(reqwest::Body::from(tokio::fs::File::from_std(file)))
+        })
+        .await?;
+
+        Ok(TransferResult::new(size))
+    }
+
+    async fn remove(&self, path: &Path, is_dir: bool) -> Result<()> {
+        let response = self
+            .request(Method::DELETE, path, is_dir)
+            .send()
+            .await
+            .map_err(|e| dav_error("DELETE", path, e))?;
+
+        // Already gone counts as removed
+        let status = response.status();
+        if !status.is_success() && status != StatusCode::NOT_FOUND {
+            return Err(status_error("DELETE", path, status));
+        }
+        Ok(())
+    }
+
+    async fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        let response = self
+            .request(Method::from_bytes(b"MOVE").unwrap(), from, false)
+            .header("Destination", self.url(to, false))
+            .header("Overwrite", "T")
+            .send()
+            .await
+            .map_err(|e| dav_error("MOVE", from, e))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(status_error("MOVE", from, status));
+        }
+        Ok(())
+    }
+
+    async fn create_hardlink(&self, _source: &Path, _dest: &Path) -> Result<()> {
+        Err(SyncError::Io(std::io::Error::other(
+            "Hardlinks not supported on WebDAV",
+        )))
+    }
+
+    async fn create_symlink(&self, _target: &Path, _dest: &Path) -> Result<()> {
+        Err(SyncError::Io(std::io::Error::other(
+            "Symlinks not supported on WebDAV",
+        )))
+    }
+
+    async fn read_file(&self, path: &Path) -> Result<Vec<u8>> {
+        let response = self
+            .request(Method::GET, path, false)
+            .send()
+            .await
+            .map_err(|e| dav_error("GET", path, e))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(status_error("GET", path, status));
+        }
+
+        let bytes = response
+            .bytes()
+            .await
+            .map_err(|e| dav_error("GET", path, e))?;
+        Ok(bytes.to_vec())
+    }
+
+    async fn write_file(&self, path: &Path, data: &[u8], mtime: SystemTime) -> Result<()> {
+        let data = bytes::Bytes::copy_from_slice(data);
+        self.put(path, data.len() as u64, Some(mtime), || {
+            Ok(reqwest::Body::from(data.clone()))
+        })
+        .await
+    }
+
+    async fn get_mtime(&self, path: &Path) -> Result<SystemTime> {
+        let info = self.file_info(path).await?;
+        Ok(info.modified)
+    }
+
+    async fn check_disk_space(&self, _path: &Path, _bytes_needed: u64) -> Result<()> {
+        // Quota is enforced by the server (507 Insufficient Storage on PUT)
+        Ok(())
+    }
+
+    async fn set_bsd_flags(&self, _path: &Path, _flags: u32) -> Result<()> {
+        // BSD flags are not supported on WebDAV - silently ignore
+        Ok(())
+    }
+
+    async fn set_xattrs(&self, _path: &Path, _xattrs: &[(String, Vec<u8>)]) -> Result<()> {
+        // Extended attributes are not supported on WebDAV - silently ignore
+        Ok(())
+    }
+
+    async fn set_acls(&self, _path: &Path, _acls_text: &str) -> Result<()> {
+        // ACLs are not supported on WebDAV - silently ignore
+        Ok(())
+    }
+}
+
+/// Normal path components of a server path, as strings
+fn path_segments(path: &Path) -> impl Iterator<Item = String> + '_ {
+    path.components().filter_map(|c| match c {
+        std::path::Component::Normal(s) => Some(s.to_string_lossy().into_owned()),
+        _ => None,
+    })
+}
+
+fn dav_error(method: &str, path: &Path, e: reqwest::Error) -> SyncError {
+    SyncError::Io(std::io::Error::other(format!(
+        "WebDAV {} {} failed: {}",
+        method,
+        path.display(),
+        e
+    )))
+}
+
+fn status_error(method: &str, path: &Path, status: StatusCode) -> SyncError {
+    let kind = match status {
+        StatusCode::NOT_FOUND => std::io::ErrorKind::NotFound,
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => std::io::ErrorKind::PermissionDenied,
+        _ => std::io::ErrorKind::Other,
+    };
+    SyncError::Io(std::io::Error::new(
+        kind,
+        format!("WebDAV {} {} returned {}", method, path.display(), status),
+    ))
+}
+
+/// Convert an `<d:href>` (absolute path or full URL) to a decoded server path
+fn href_to_path(href: &str) -> PathBuf {
+    let path = match href.split_once("://") {
+        Some((_, rest)) => rest.find('/').map_or("/", |i| &rest[i..]),
+        None => href,
+    };
+    let decoded = percent_decode_str(path).decode_utf8_lossy();
+    let trimmed = decoded.trim_end_matches('/');
+    PathBuf::from(if trimmed.is_empty() { "/" } else { trimmed })
+}
+
+/// Parse a PROPFIND `207 Multi-Status` body
+fn parse_multistatus(xml: &str) -> Result<Vec<DavEntry>> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+
+    let mut entries = Vec::new();
+    let mut current: Option<DavEntry> = None;
+    let mut href = String::new();
+    let mut element = Vec::new(); // local name of the innermost open element
+    let mut text = String::new();
+
+    loop {
+        let event = reader.read_event().map_err(|e| {
+            SyncError::Io(std::io::Error::other(format!(
+                "Invalid WebDAV PROPFIND response: {}",
+                e
+            )))
+        })?;
+
+        match event {
+            Event::Start(e) => {
+                let name = e.local_name().as_ref().to_vec();
+                if name == b"response" {
+                    href.clear();
+                    current = Some(DavEntry {
+                        path: PathBuf::new(),
+                        is_dir: false,
+                        size: 0,
+                        modified: UNIX_EPOCH,
+                    });
+                } else if name == b"collection" {
+                    if let Some(entry) = current.as_mut() {
+                        entry.is_dir = true;
+                    }
+                }
+                element = name;
+                text.clear();
+            }
+            Event::Empty(e) if e.local_name().as_ref() == b"collection" => {
+                if let Some(entry) = current.as_mut() {
+                    entry.is_dir = true;
+                }
+            }
+            Event::Text(e) => {
+                text.push_str(&e.xml_content().unwrap_or_default());
+            }
+            Event::GeneralRef(e) => {
+                if let Ok(Some(ch)) = e.resolve_char_ref() {
+                    text.push(ch);
+                } else if let Ok(name) = e.decode() {
+                    if let Some(value) = quick_xml::escape::resolve_predefined_entity(&name) {
+                        text.push_str(value);
+                    }
+                }
+            }
+            Event::End(e) => {
+                let name = e.local_name();
+                match name.as_ref() {
+                    b"href" if element == b"href" => href = text.trim().to_string(),
+                    b"getcontentlength" => {
+                        if let (Some(entry), Ok(size)) = (current.as_mut(), text.trim().parse()) {
+                            entry.size = size;
+                        }
+                    }
+                    b"getlastmodified" => {
+                        if let (Some(entry), Ok(date)) = (
+                            current.as_mut(),
+                            chrono::DateTime::parse_from_rfc2822(text.trim()),
+                        ) {
+                            entry.modified = date.into();
+                        }
+                    }
+                    b"response" => {
+                        if let Some(mut entry) = current.take() {
+                            entry.path = href_to_path(&href);
+                            if entry.is_dir {
+                                entry.size = 0;
+                            }
+                            entries.push(entry);
+                        }
+                    }
+                    _ => {}
+                }
+                element.clear();
+                text.clear();
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use tempfile::TempDir;
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+
+    /// Minimal WebDAV server over a local directory, for exercising the transport
+    async fn start_server(root: PathBuf) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let root = root.clone();
+                tokio::spawn(async move {
+                    let (read, mut write) = stream.into_split();
+                    let mut reader = BufReader::new(read);
+
+                    let mut request_line = String::new();
+                    reader.read_line(&mut request_line).await.unwrap();
+                    let mut parts = request_line.split_whitespace();
+                    let method = parts.next().unwrap_or_default().to_string();
+                    let target = parts.next().unwrap_or_default().to_string();
+
+                    let mut headers = HashMap::new();
+                    loop {
+                        let mut line = String::new();
+                        reader.read_line(&mut line).await.unwrap();
+                        let line = line.trim_end();
+                        if line.is_empty() {
+                            break;
+                        }
+                        if let Some((k, v)) = line.split_once(':') {
+                            headers.insert(k.trim().to_ascii_lowercase(), v.trim().to_string());
+                        }
+                    }
+                    let len: usize = headers
+                        .get("content-length")
+                        .and_then(|v| v.parse().ok())
+                        .unwrap_or(0);
+                    let mut body = vec![0u8; len];
+                    reader.read_exact(&mut body).await.unwrap();
+
+                    let (status, extra, body) = handle(&root, &method, &target, &headers, body);
+                    let head = format!(
+                        "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n{}\r\n",
+                        status,
+                        body.len(),
+                        extra
+                    );
+                    write.write_all(head.as_bytes()).await.unwrap();
+                    write.write_all(&body).await.unwrap();
+                    write.shutdown().await.ok();
+                });
+            }
+        });
+        format!("http://{}", addr)
+    }
+
+    fn handle(
+        root: &Path,
+        method: &str,
+        target: &str,
+        headers: &HashMap<String, String>,
+        body: Vec<u8>,
+    ) -> (&'static str, String, Vec<u8>) {
+        let local = |url_path: &str| root.join(href_to_path(url_path).strip_prefix("/").unwrap());
+        let path = local(target);
+
+        match method {
+            "PROPFIND" => {
+                if !path.exists() {
+                    return ("404 Not Found", String::new(), Vec::new());
+                }
+                let mut members = vec![path.clone()];
+                if headers.get("depth").map(String::as_str) == Some("1") && path.is_dir() {
+                    for entry in std::fs::read_dir(&path).unwrap() {
+                        members.push(entry.unwrap().path());
+                    }
+                }
+                let mut xml =
+                    String::from(r#"<?xml version="1.0"?><d:multistatus xmlns:d="DAV:">"#);
+                for member in members {
+                    let meta = std::fs::metadata(&member).unwrap();
+                    let rel = member.strip_prefix(root).unwrap();
+                    let mut href = String::new();
+                    for segment in path_segments(rel) {
+                        href.push('/');
+                        href.extend(utf8_percent_encode(&segment, SEGMENT));
+                    }
+                    let modified: chrono::DateTime<chrono::Utc> = meta.modified().unwrap().into();
+                    let (kind, len) = if meta.is_dir() {
+                        href.push('/');
+                        ("<d:collection/>", String::new())
+                    } else {
+                        (
+                            "",
+                            format!("<d:getcontentlength>{}</d:getcontentlength>", meta.len()),
+                        )
+                    };
+                    xml.push_str(&format!(
+                        "<d:response><d:href>{}</d:href><d:propstat><d:prop>\
+                         <d:resourcetype>{}</d:resourcetype>{}\
+                         <d:getlastmodified>{}</d:getlastmodified>\
+                         </d:prop><d:status>HTTP/1.1 200 OK</d:status></d:propstat></d:response>",
+                        if href.is_empty() { "/" } else { &href },
+                        kind,
+                        len,
+                        modified.format("%a, %d %b %Y %H:%M:%S GMT")
+                    ));
+                }
+                xml.push_str("</d:multistatus>");
+                ("207 Multi-Status", String::new(), xml.into_bytes())
+            }
+            "GET" => match std::fs::read(&path) {
+                Ok(data) => ("200 OK", String::new(), data),
+                Err(_) => ("404 Not Found", String::new(), Vec::new()),
+            },
+            "PUT" => {
+                if !path.parent().unwrap().is_dir() {
+                    return ("409 Conflict", String::new(), Vec::new());
+                }
+                std::fs::write(&path, body).unwrap();
+                let mut extra = String::new();
+                if let Some(secs) = headers.get("x-oc-mtime").and_then(|v| v.parse().ok()) {
+                    filetime::set_file_mtime(&path, filetime::FileTime::from_unix_time(secs, 0))
+                        .unwrap();
+                    extra.push_str("X-OC-MTime: accepted\r\n");
+                }
+                ("201 Created", extra, Vec::new())
+            }
+            "MKCOL" => {
+                if path.exists() {
+                    ("405 Method Not Allowed", String::new(), Vec::new())
+                } else if std::fs::create_dir(&path).is_ok() {
+                    ("201 Created", String::new(), Vec::new())
+                } else {
+                    ("409 Conflict", String::new(), Vec::new())
+                }
+            }
+            "DELETE" => {
+                let removed = if path.is_dir() {
+                    std::fs::remove_dir_all(&path)
+                } else {
+                    std::fs::remove_file(&path)
+                };
+                match removed {
+                    Ok(()) => ("204 No Content", String::new(), Vec::new()),
+                    Err(_) => ("404 Not Found", String::new(), Vec::new()),
+                }
+            }
+            "MOVE" => {
+                let dest = local(&headers["destination"]);
+                match std::fs::rename(&path, dest) {
+                    Ok(()) => ("201 Created", String::new(), Vec::new()),
+                    Err(_) => ("409 Conflict", String::new(), Vec::new()),
+                }
+            }
+            _ => ("405 Method Not Allowed", String::new(), Vec::new()),
+        }
+    }
+
+    async fn transport(root: &Path) -> WebDavTransport {
+        let base_url = start_server(root.to_path_buf()).await;
+        WebDavTransport::with_base_url(base_url, None, None).unwrap()
+    }
+
+    #[test]
+    fn test_parse_multistatus_nextcloud() {
+        let xml = r#"<?xml version="1.0"?>
+<d:multistatus xmlns:d="DAV:" xmlns:oc="http://owncloud.org/ns">
+  <d:response>
+    <d:href>/remote.php/dav/files/alice/builds/</d:href>
+    <d:propstat>
+      <d:prop>
+        <d:resourcetype><d:collection/></d:resourcetype>
+        <d:getlastmodified>Tue, 14 Oct 2025 09:30:00 GMT</d:getlastmodified>
+      </d:prop>
+      <d:status>HTTP/1.1 200 OK</d:status>
+    </d:propstat>
+    <d:propstat>
+      <d:prop><d:getcontentlength/></d:prop>
+      <d:status>HTTP/1.1 404 Not Found</d:status>
+    </d:propstat>
+  </d:response>
+  <d:response>
+    <d:href>https://cloud.example.com/remote.php/dav/files/alice/builds/app%20v1.tar.gz</d:href>
+    <d:propstat>
+      <d:prop>
+        <d:resourcetype/>
+        <d:getcontentlength>1024</d:getcontentlength>
+        <d:getlastmodified>Tue, 14 Oct 2025 09:31:40 GMT</d:getlastmodified>
+      </d:prop>
+      <d:status>HTTP/1.1 200 OK</d:status>
+    </d:propstat>
+  </d:response>
+</d:multistatus>"#;
+
+        let entries = parse_multistatus(xml).unwrap();
+        assert_eq!(entries.len(), 2);
+
+        assert_eq!(
+            entries[0].path,
+            PathBuf::from("/remote.php/dav/files/alice/builds")
+        );
+        assert!(entries[0].is_dir);
+
+        assert_eq!(
+            entries[1].path,
+            PathBuf::from("/remote.php/dav/files/alice/builds/app v1.tar.gz")
+        );
+        assert!(!entries[1].is_dir);
+        assert_eq!(entries[1].size, 1024);
+        assert_eq!(
+            entries[1].modified,
+            UNIX_EPOCH + Duration::from_secs(1_760_434_300)
+        );
+    }
+
+    #[test]
+    fn test_url_encoding() {
+        let transport =
+            WebDavTransport::with_base_url("https://cloud.example.com/".into(), None, None)
+                .unwrap();
+        assert_eq!(
+            transport.url(Path::new("/files/a b/c#1.txt"), false),
+            "https://cloud.example.com/files/a%20b/c%231.txt"
+        );
+        assert_eq!(
+            transport.url(Path::new("/files/dir"), true),
+            "https://cloud.example.com/files/dir/"
+        );
+        assert_eq!(
+            transport.url(Path::new("/"), false),
+            "https://cloud.example.com/"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_upload_scan_and_read() {
+        let server_root = TempDir::new().unwrap();
+        let local = TempDir::new().unwrap();
+        let source = local.path().join("report.txt");
+        std::fs::write(&source, "hello webdav").unwrap();
+        let mtime = filetime::FileTime::from_unix_time(1_700_000_000, 0);
+        filetime::set_file_mtime(&source, mtime).unwrap();
+
+        let transport = transport(server_root.path()).await;
+
+        // Parent collections are created on demand
+        let dest = Path::new("/builds/nightly/report.txt");
+        let result = transport.copy_file(&source, dest).await.unwrap();
+        assert_eq!(result.bytes_written, 12);
+
+        assert!(transport.exists(dest).await.unwrap());
+        assert!(!transport
+            .exists(Path::new("/builds/missing.txt"))
+            .await
+            .unwrap());
+        assert_eq!(transport.read_file(dest).await.unwrap(), b"hello webdav");
+
+        // X-OC-Mtime preserved the source mtime
+        let info = transport.file_info(dest).await.unwrap();
+        assert_eq!(info.size, 12);
+        assert_eq!(
+            info.modified,
+            UNIX_EPOCH + Duration::from_secs(1_700_000_000)
+        );
+
+        let mut entries = transport.scan(Path::new("/builds")).await.unwrap();
+        entries.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+        assert_eq!(entries.len(), 2);
+        assert_eq!(*entries[0].relative_path, PathBuf::from("nightly"));
+        assert!(entries[0].is_dir);
+        assert_eq!(
+            *entries[1].relative_path,
+            PathBuf::from("nightly/report.txt")
+        );
+        assert_eq!(*entries[1].path, dest.to_path_buf());
+        assert_eq!(entries[1].size, 12);
+    }
+
+    #[tokio::test]
+    async fn test_mkcol_move_and_delete() {
+        let server_root = TempDir::new().unwrap();
+        let transport = transport(server_root.path()).await;
+
+        transport.create_dir_all(Path::new("/a/b/c")).await.unwrap();
+        // Existing collections are fine
+        transport.create_dir_all(Path::new("/a/b")).await.unwrap();
+        assert!(server_root.path().join("a/b/c").is_dir());
+
+        transport
+            .write_file(Path::new("/a/old name.txt"), b"data", SystemTime::now())
+            .await
+            .unwrap();
+        transport
+            .rename(Path::new("/a/old name.txt"), Path::new("/a/b/new.txt"))
+            .await
+            .unwrap();
+        assert!(!server_root.path().join("a/old name.txt").exists());
+        assert_eq!(
+            std::fs::read(server_root.path().join("a/b/new.txt")).unwrap(),
+            b"data"
+        );
+
+        transport
+            .remove(Path::new("/a/b/new.txt"), false)
+            .await
+            .unwrap();
+        transport.remove(Path::new("/a/b"), true).await.unwrap();
+        assert!(!server_root.path().join("a/b").exists());
+
+        // Removing something already gone is not an error
+        transport.remove(Path::new("/a/b"), true).await.unwrap();
+    }
+}
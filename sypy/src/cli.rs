@@ -48,7 +48,7 @@ async fn run_server_with_path(path: &str) -> anyhow::Result<()> {
     use sy::server::handler::ServerHandler;
     use sy::server::protocol::{
         ChecksumReq, ChecksumResp, DeltaData, ErrorMessage, FileListEntry, Hello, MessageType,
-        MkdirBatch, SymlinkBatch, PROTOCOL_VERSION,
+        MkdirBatch, SymlinkBatch, CAPS_LOCAL, CAP_FILE_CHECKSUM, CAP_RESUME, CAP_STREAM_ZSTD,
     };
     use tokio::io::{self, AsyncReadExt, AsyncWriteExt};
     use tokio::sync::mpsc;
@@ -80,31 +80,32 @@ async fn run_server_with_path(path: &str) -> anyhow::Result<()> {
 
     let hello = Hello::read(&mut stdin).await?;
 
-    if hello.version != PROTOCOL_VERSION {
-        let err = ErrorMessage {
-            code: 1,
-            message: format!(
-                "Version mismatch: client {}, server {}",
-                hello.version, PROTOCOL_VERSION
-            ),
-        };
-        err.write(&mut stdout).await?;
-        return Ok(());
-    }
-
-    // Send HELLO response
-    let resp = Hello {
-        version: PROTOCOL_VERSION,
-        flags: 0,
-        capabilities: vec![],
+    let negotiated = match hello.negotiate() {
+        Ok(negotiated) => negotiated,
+        Err(e) => {
+            let err = ErrorMessage {
+                code: 1,
+                message: format!("Version mismatch: {}", e),
+            };
+            err.write(&mut stdout).await?;
+            return Ok(());
+        }
     };
-    resp.write(&mut stdout).await?;
+
+    // Send HELLO response (this loop speaks plain stdio, without stream compression)
+    Hello::with_features(0, CAPS_LOCAL & !CAP_STREAM_ZSTD)
+        .write(&mut stdout)
+        .await?;
     stdout.flush().await?;
 
+    handler = handler
+        .with_resume(negotiated.has(CAP_RESUME))
+        .with_checksums(negotiated.has(CAP_FILE_CHECKSUM));
+
     // Check if client requested PULL mode (server sends files to client)
     use sy::server::protocol::HELLO_FLAG_PULL;
     if hello.flags & HELLO_FLAG_PULL != 0 {
-        return sy::server::run_server_pull_mode(&root_path, &mut stdin, &mut stdout)
+        return sy::server::run_server_pull_mode(&root_path, negotiated, &mut stdin, &mut stdout)
             .await
             .map_err(|e| anyhow::anyhow!("Pull mode error: {}", e));
     }
@@ -171,13 +172,13 @@ async fn run_server_with_path(path: &str) -> anyhow::Result<()> {
                             use sy::server::handler::compute_checksum_response;
                             let fl = Arc::clone(fl);
                             let rp = Arc::clone(&root_path_arc);
-                            let index = req.index;
-                            let block_size = req.block_size as usize;
+                            let cache = handler.chunk_cache(&req);
+                            let basis = handler.fuzzy_basis(req.index);
                             let tx = checksum_tx.clone();
 
                             pending_checksum_count += 1;
                             tokio::spawn(async move {
-                                match compute_checksum_response(index, block_size, &fl, &rp).await {
+                                match compute_checksum_response(req, &fl, &rp, basis, cache).await {
                                     Ok(resp) => {
                                         let _ = tx.send(resp).await;
                                     }
@@ -258,7 +259,7 @@ pub fn run_daemon(py: Python<'_>, socket_path: &str, root_path: Option<&str>) ->
             .map(PathBuf::from)
             .unwrap_or_else(|| std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")));
 
-        rt.block_on(async { sy::server::daemon::run_daemon(socket_path, &root).await })
+        rt.block_on(async { sy::server::daemon::run_daemon(socket_path, &root, false).await })
             .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
     })
 }
@@ -10,6 +10,7 @@ pub enum PyChangeAction {
     Create,
     Update,
     Delete,
+    Move,
     Skip,
 }
 
@@ -19,6 +20,7 @@ impl From<ChangeAction> for PyChangeAction {
             ChangeAction::Create => PyChangeAction::Create,
             ChangeAction::Update => PyChangeAction::Update,
             ChangeAction::Delete => PyChangeAction::Delete,
+            ChangeAction::Move => PyChangeAction::Move,
             ChangeAction::Skip => PyChangeAction::Skip,
         }
     }
@@ -31,6 +33,7 @@ impl PyChangeAction {
             PyChangeAction::Create => "create".to_string(),
             PyChangeAction::Update => "update".to_string(),
             PyChangeAction::Delete => "delete".to_string(),
+            PyChangeAction::Move => "move".to_string(),
             PyChangeAction::Skip => "skip".to_string(),
         }
     }
@@ -48,7 +51,7 @@ pub struct PyFileChange {
     #[pyo3(get)]
     pub path: String,
 
-    /// Action type (create/update/delete/skip)
+    /// Action type (create/update/delete/move/skip)
     #[pyo3(get)]
     pub action: String,
 
@@ -109,6 +112,7 @@ impl From<&FileChange> for PyFileChange {
                 ChangeAction::Create => "create".to_string(),
                 ChangeAction::Update => "update".to_string(),
                 ChangeAction::Delete => "delete".to_string(),
+                ChangeAction::Move => "move".to_string(),
                 ChangeAction::Skip => "skip".to_string(),
             },
             size: fc.size,
@@ -162,6 +166,7 @@ impl From<&DirectoryChange> for PyDirectoryChange {
             action: match dc.action {
                 ChangeAction::Create => "create".to_string(),
                 ChangeAction::Delete => "delete".to_string(),
+                ChangeAction::Move => "move".to_string(),
                 _ => "unknown".to_string(),
             },
         }
@@ -215,6 +220,7 @@ impl From<&SymlinkChange> for PySymlinkChange {
             action: match sc.action {
                 ChangeAction::Create => "create".to_string(),
                 ChangeAction::Delete => "delete".to_string(),
+                ChangeAction::Move => "move".to_string(),
                 _ => "unknown".to_string(),
             },
             target: sc.target.clone(),
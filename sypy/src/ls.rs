@@ -11,13 +11,19 @@ use sy::transport::local::LocalTransport;
 #[cfg(feature = "ssh")]
 use sy::ssh::config::{parse_ssh_config, SshConfig};
 #[cfg(feature = "ssh")]
+use sy::transport::sftp::SftpTransport;
+#[cfg(feature = "ssh")]
 use sy::transport::ssh::SshTransport;
 
 #[cfg(feature = "s3")]
 use sy::transport::s3::S3Transport;
 
+#[cfg(feature = "azure")]
+use sy::transport::azure::AzureTransport;
 #[cfg(feature = "gcs")]
 use sy::transport::gcs::GcsTransport;
+#[cfg(feature = "webdav")]
+use sy::transport::webdav::WebDavTransport;
 
 /// Python class representing a directory listing entry
 #[pyclass(name = "ListEntry")]
@@ -206,6 +212,30 @@ async fn ls_async(
         SyncPath::Remote { .. } => {
             return Err("SSH support not enabled".into());
         }
+        #[cfg(feature = "ssh")]
+        SyncPath::Sftp {
+            host, user, port, ..
+        } => {
+            let mut config = parse_ssh_config(&host).unwrap_or_else(|_| SshConfig {
+                hostname: host.clone(),
+                ..Default::default()
+            });
+            if let Some(user) = user {
+                config.user = user.clone();
+            }
+            if let Some(port) = port {
+                config.port = port;
+            }
+
+            let retry_config = RetryConfig::default();
+            let ssh = SshTransport::with_retry_config(&config, 1, retry_config).await?;
+            let transport = SftpTransport::new(ssh);
+            list_directory(&transport, &path_buf, &list_opts).await?
+        }
+        #[cfg(not(feature = "ssh"))]
+        SyncPath::Sftp { .. } => {
+            return Err("SSH support not enabled".into());
+        }
         #[cfg(feature = "s3")]
         SyncPath::S3 {
             bucket,
@@ -293,7 +323,57 @@ async fn ls_async(
         SyncPath::Gcs { .. } => {
             return Err("GCS support not enabled".into());
         }
-        SyncPath::Daemon { .. } => {
+        #[cfg(feature = "azure")]
+        SyncPath::Azure {
+            container,
+            key,
+            account,
+            ..
+        } => {
+            use sy::transport::{AzureConfig, CloudClientOptions};
+
+            // Use optimized settings for listing
+            let client_options = CloudClientOptions {
+                pool_max_idle_per_host: 50,
+                pool_idle_timeout_secs: 60,
+                connect_timeout_secs: 5,
+                request_timeout_secs: 120,
+                max_retries: 1,
+                retry_timeout_secs: 30,
+                allow_http: false,
+            };
+
+            let config = AzureConfig {
+                client_options: Some(client_options),
+                ..Default::default()
+            };
+
+            let transport = AzureTransport::with_config(
+                container.clone(),
+                key.clone(),
+                account.clone(),
+                Some(config),
+                50,
+            )
+            .await?;
+            list_directory(&transport, &path_buf, &list_opts).await?
+        }
+        #[cfg(not(feature = "azure"))]
+        SyncPath::Azure { .. } => {
+            return Err("Azure support not enabled".into());
+        }
+        #[cfg(feature = "webdav")]
+        SyncPath::WebDav {
+            host, user, secure, ..
+        } => {
+            let transport = WebDavTransport::new(host.clone(), user.clone(), *secure)?;
+            list_directory(&transport, &path_buf, &list_opts).await?
+        }
+        #[cfg(not(feature = "webdav"))]
+        SyncPath::WebDav { .. } => {
+            return Err("WebDAV support not enabled".into());
+        }
+        SyncPath::Daemon { .. } | SyncPath::TcpDaemon { .. } => {
             return Err("Daemon paths are not supported for listing".into());
         }
     };
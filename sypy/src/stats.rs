@@ -76,6 +76,12 @@ pub struct PySyncStats {
     /// Bytes saved by compression
     #[pyo3(get)]
     pub compression_bytes_saved: u64,
+    /// Protocol bytes sent and received over a compressed server stream
+    #[pyo3(get)]
+    pub stream_bytes_raw: u64,
+    /// Bytes that crossed the wire for that stream
+    #[pyo3(get)]
+    pub stream_bytes_wire: u64,
     /// Number of files verified
     #[pyo3(get)]
     pub files_verified: usize,
@@ -208,6 +214,8 @@ impl From<sy::sync::SyncStats> for PySyncStats {
             delta_bytes_saved: stats.delta_bytes_saved,
             files_compressed: stats.files_compressed,
             compression_bytes_saved: stats.compression_bytes_saved,
+            stream_bytes_raw: stats.stream_bytes_raw,
+            stream_bytes_wire: stats.stream_bytes_wire,
             files_verified: stats.files_verified,
             verification_failures: stats.verification_failures,
             duration_secs: stats.duration.as_secs_f64(),
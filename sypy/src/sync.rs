@@ -13,8 +13,10 @@ use sy::filter::FilterEngine;
 use sy::integrity::ChecksumType;
 use sy::path::SyncPath;
 use sy::retry::RetryConfig;
+use sy::server::meta::MetaOptions;
 use sy::sync::live_progress::ProgressState;
 use sy::sync::scanner::ScanOptions;
+use sy::sync::server_mode::TransferOptions;
 use sy::sync::SyncEngine;
 use sy::transport::router::TransportRouter;
 
@@ -366,6 +368,17 @@ async fn do_sync(
         include_git_dir: !exclude_vcs,
     };
 
+    // Metadata the server and daemon protocols carry, and how files are verified
+    let meta = MetaOptions {
+        xattrs: preserve_xattrs,
+        ..Default::default()
+    };
+    let transfer = TransferOptions {
+        hardlinks: preserve_hardlinks,
+        verify: checksum_type,
+        ..Default::default()
+    };
+
     // Handle daemon auto mode for SSH destinations
     #[cfg(unix)]
     if daemon_auto && dest.is_remote() {
@@ -400,6 +413,8 @@ async fn do_sync(
                 source.path(),
                 &daemon_result.socket_path,
                 path,
+                meta,
+                transfer,
             )
             .await
             .map_err(anyhow_to_pyerr)?;
@@ -442,6 +457,8 @@ async fn do_sync(
                 source.path(),
                 &dest,
                 dry_run,
+                meta,
+                transfer,
                 live_progress.clone(),
                 Some(config),
             )
@@ -451,6 +468,8 @@ async fn do_sync(
                 source.path(),
                 &dest,
                 dry_run,
+                meta,
+                transfer,
                 live_progress.clone(),
             )
             .await
@@ -500,6 +519,8 @@ async fn do_sync(
                 &source,
                 dest.path(),
                 dry_run,
+                meta,
+                transfer,
                 live_progress.clone(),
                 Some(config),
             )
@@ -509,6 +530,8 @@ async fn do_sync(
                 &source,
                 dest.path(),
                 dry_run,
+                meta,
+                transfer,
                 live_progress.clone(),
             )
             .await
@@ -0,0 +1,187 @@
+// Batch file tests (--write-batch / --read-batch)
+//
+// A batch written while syncing one destination is replayed onto mirrors
+// that started from the same state.
+
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+use tempfile::TempDir;
+
+fn sy_bin() -> String {
+    env!("CARGO_BIN_EXE_sy").to_string()
+}
+
+fn sy(args: &[&str]) -> Output {
+    Command::new(sy_bin()).args(args).output().unwrap()
+}
+
+fn path(p: &Path) -> &str {
+    p.to_str().unwrap()
+}
+
+/// Source and two identical destinations, one edit apart from the source
+fn setup() -> (TempDir, TempDir, TempDir, Vec<u8>) {
+    let source = TempDir::new().unwrap();
+    let dest = TempDir::new().unwrap();
+    let mirror = TempDir::new().unwrap();
+
+    let old: Vec<u8> = (0..500_000u32).map(|i| (i * 13 % 241) as u8).collect();
+    let mut new = old.clone();
+    new[250_000..250_005].copy_from_slice(b"v2.0!");
+
+    fs::create_dir(source.path().join("lib")).unwrap();
+    fs::write(source.path().join("lib/core.so"), &new).unwrap();
+    fs::write(source.path().join("README"), b"release notes").unwrap();
+    for root in [dest.path(), mirror.path()] {
+        fs::create_dir(root.join("lib")).unwrap();
+        fs::write(root.join("lib/core.so"), &old).unwrap();
+        fs::write(root.join("obsolete.cfg"), b"old").unwrap();
+        filetime::set_file_mtime(
+            root.join("lib/core.so"),
+            filetime::FileTime::from_unix_time(1_600_000_000, 0),
+        )
+        .unwrap();
+    }
+    (source, dest, mirror, new)
+}
+
+#[test]
+fn test_write_batch_then_read_batch_on_mirror() {
+    let (source, dest, mirror, new) = setup();
+    let batch_dir = TempDir::new().unwrap();
+    let batch = batch_dir.path().join("release.batch");
+    let source_path = format!("{}/", source.path().display());
+
+    let output = sy(&[
+        &source_path,
+        path(dest.path()),
+        "--delete",
+        "--write-batch",
+        path(&batch),
+    ]);
+    assert!(
+        output.status.success(),
+        "write-batch failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    // The written destination is updated too
+    assert_eq!(fs::read(dest.path().join("lib/core.so")).unwrap(), new);
+    assert!(!dest.path().join("obsolete.cfg").exists());
+
+    // The batch carries the edit, not the whole file
+    assert!(fs::metadata(&batch).unwrap().len() < 100_000);
+
+    let output = sy(&["--read-batch", path(&batch), path(mirror.path())]);
+    assert!(
+        output.status.success(),
+        "read-batch failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(fs::read(mirror.path().join("lib/core.so")).unwrap(), new);
+    assert_eq!(
+        fs::read(mirror.path().join("README")).unwrap(),
+        b"release notes"
+    );
+    assert!(!mirror.path().join("obsolete.cfg").exists());
+}
+
+#[test]
+fn test_read_batch_refuses_different_basis() {
+    let (source, dest, mirror, _) = setup();
+    let batch_dir = TempDir::new().unwrap();
+    let batch = batch_dir.path().join("release.batch");
+    let source_path = format!("{}/", source.path().display());
+
+    // --dry-run only writes the batch
+    let output = sy(&[
+        &source_path,
+        path(dest.path()),
+        "--dry-run",
+        "--write-batch",
+        path(&batch),
+    ]);
+    assert!(output.status.success());
+    assert!(!dest.path().join("README").exists());
+
+    fs::write(mirror.path().join("lib/core.so"), b"locally patched").unwrap();
+    let output = sy(&["--read-batch", path(&batch), path(mirror.path())]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("basis checksum"), "stderr: {}", stderr);
+    assert!(!mirror.path().join("README").exists());
+}
+
+#[test]
+fn test_write_batch_honors_delete_threshold() {
+    let (source, dest, _, _) = setup();
+    for i in 0..10 {
+        fs::write(dest.path().join(format!("stale{i}.log")), b"x").unwrap();
+    }
+    let batch_dir = TempDir::new().unwrap();
+    let batch = batch_dir.path().join("release.batch");
+    let source_path = format!("{}/", source.path().display());
+
+    let output = sy(&[
+        &source_path,
+        path(dest.path()),
+        "--delete",
+        "--write-batch",
+        path(&batch),
+    ]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("threshold"), "stderr: {}", stderr);
+    assert!(!batch.exists());
+    assert!(dest.path().join("stale0.log").exists());
+}
+
+#[test]
+fn test_batch_refuses_metadata_it_cannot_record() {
+    let (source, dest, mirror, _) = setup();
+    let batch_dir = TempDir::new().unwrap();
+    let batch = batch_dir.path().join("release.batch");
+    let source_path = format!("{}/", source.path().display());
+
+    let output = sy(&[
+        &source_path,
+        path(dest.path()),
+        "-X",
+        "--write-batch",
+        path(&batch),
+    ]);
+    assert!(!output.status.success());
+    assert!(!batch.exists());
+
+    let output = sy(&["-a", "--read-batch", path(&batch), path(mirror.path())]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("ownership"), "stderr: {}", stderr);
+}
+
+#[test]
+fn test_read_batch_json_summary() {
+    let (source, dest, mirror, _) = setup();
+    let batch_dir = TempDir::new().unwrap();
+    let batch = batch_dir.path().join("release.batch");
+    let source_path = format!("{}/", source.path().display());
+
+    let output = sy(&[
+        &source_path,
+        path(dest.path()),
+        "--json",
+        "--write-batch",
+        path(&batch),
+    ]);
+    assert!(output.status.success());
+
+    let output = sy(&["--json", "--read-batch", path(&batch), path(mirror.path())]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let summary: serde_json::Value = stdout
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .find(|event: &serde_json::Value| event["type"] == "summary")
+        .unwrap_or_else(|| panic!("no summary in: {}", stdout));
+    assert_eq!(summary["files_created"], 2);
+}
@@ -6,8 +6,11 @@
 #![cfg(unix)]
 
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
+use sy::server::meta::MetaOptions;
+use sy::sync::server_mode::TransferOptions;
+use sy::transport::server::SessionOptions;
 use tempfile::TempDir;
 use tokio::time::timeout;
 
@@ -38,7 +41,9 @@ async fn test_daemon_lifecycle() {
     let root = root_path.clone();
 
     let daemon_handle =
-        tokio::spawn(async move { sy::server::daemon::run_daemon(&socket_str, &root).await });
+        tokio::spawn(
+            async move { sy::server::daemon::run_daemon(&socket_str, &root, false).await },
+        );
 
     // Give daemon time to start
     tokio::time::sleep(Duration::from_millis(100)).await;
@@ -66,7 +71,9 @@ async fn test_daemon_session_connect() {
     let root = root_path.clone();
 
     let daemon_handle =
-        tokio::spawn(async move { sy::server::daemon::run_daemon(&socket_str, &root).await });
+        tokio::spawn(
+            async move { sy::server::daemon::run_daemon(&socket_str, &root, false).await },
+        );
 
     // Give daemon time to start
     tokio::time::sleep(Duration::from_millis(100)).await;
@@ -77,7 +84,7 @@ async fn test_daemon_session_connect() {
 
     let session_result = timeout(
         Duration::from_secs(5),
-        DaemonSession::connect(&socket_str, &remote_path),
+        DaemonSession::connect(&socket_str, &remote_path, SessionOptions::default()),
     )
     .await;
 
@@ -109,7 +116,9 @@ async fn test_daemon_session_ping() {
     let root = root_path.clone();
 
     let daemon_handle =
-        tokio::spawn(async move { sy::server::daemon::run_daemon(&socket_str, &root).await });
+        tokio::spawn(
+            async move { sy::server::daemon::run_daemon(&socket_str, &root, false).await },
+        );
 
     // Give daemon time to start
     tokio::time::sleep(Duration::from_millis(100)).await;
@@ -118,10 +127,22 @@ async fn test_daemon_session_ping() {
     let socket_str = socket_path.to_string_lossy().to_string();
     let remote_path = PathBuf::from("/tmp/test");
 
-    let mut session = DaemonSession::connect(&socket_str, &remote_path)
+    let options = SessionOptions {
+        compress: true,
+        ..Default::default()
+    };
+    let mut session = DaemonSession::connect(&socket_str, &remote_path, options)
         .await
         .expect("Should connect to daemon");
 
+    // Both sides are this build, so everything offered is negotiated
+    let negotiated = session.negotiated();
+    assert_eq!(
+        negotiated.version,
+        sy::server::protocol::PROTOCOL_VERSION_MAX
+    );
+    assert!(negotiated.has(sy::server::protocol::CAPS_LOCAL));
+
     // Test ping
     let ping_result = session.ping().await;
     assert!(ping_result.is_ok(), "Ping should succeed");
@@ -134,6 +155,73 @@ async fn test_daemon_session_ping() {
     let _ = daemon_handle.await;
 }
 
+/// Old clients (no capability block) and clients needing a newer protocol
+#[tokio::test]
+async fn test_daemon_hello_negotiation() {
+    use sy::server::protocol::{
+        Capabilities, ErrorMessage, Hello, MessageType, CAPS_V1, PROTOCOL_VERSION_MAX,
+    };
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::UnixStream;
+
+    let temp = TempDir::new().expect("Failed to create temp dir");
+    let socket_path = temp.path().join("daemon.sock");
+    let socket_str = socket_path.to_string_lossy().to_string();
+    let root = temp.path().to_path_buf();
+    let daemon_handle =
+        tokio::spawn(
+            async move { sy::server::daemon::run_daemon(&socket_str, &root, false).await },
+        );
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // A pre-negotiation client sends version 1 and nothing else
+    let mut stream = UnixStream::connect(&socket_path).await.unwrap();
+    Hello {
+        version: 1,
+        flags: 0,
+        capabilities: vec![],
+    }
+    .write(&mut stream)
+    .await
+    .unwrap();
+    stream.flush().await.unwrap();
+    let _len = stream.read_u32().await.unwrap();
+    assert_eq!(stream.read_u8().await.unwrap(), MessageType::Hello as u8);
+    let reply = Hello::read(&mut stream).await.unwrap();
+    assert_eq!(reply.version, 1, "old clients require version 1 in HELLO");
+    let negotiated = reply.negotiate().unwrap();
+    assert_eq!(negotiated.version, PROTOCOL_VERSION_MAX);
+    drop(stream);
+
+    // The daemon only agrees on v1 features with that old client
+    let old = Capabilities::legacy(1);
+    assert_eq!(
+        reply.peer_capabilities().negotiate(&old).unwrap().features,
+        CAPS_V1
+    );
+
+    // A client that only speaks newer versions is refused with an error
+    let mut stream = UnixStream::connect(&socket_path).await.unwrap();
+    let mut capabilities = (PROTOCOL_VERSION_MAX + 5).to_be_bytes().to_vec();
+    capabilities.extend_from_slice(&u64::MAX.to_be_bytes());
+    Hello {
+        version: PROTOCOL_VERSION_MAX + 1,
+        flags: 0,
+        capabilities,
+    }
+    .write(&mut stream)
+    .await
+    .unwrap();
+    stream.flush().await.unwrap();
+    let _len = stream.read_u32().await.unwrap();
+    assert_eq!(stream.read_u8().await.unwrap(), MessageType::Error as u8);
+    let err = ErrorMessage::read(&mut stream).await.unwrap();
+    assert!(err.message.contains("Version mismatch"), "{}", err.message);
+
+    daemon_handle.abort();
+    let _ = daemon_handle.await;
+}
+
 /// Test full sync through daemon (local mode)
 #[tokio::test]
 async fn test_daemon_sync_push() {
@@ -150,7 +238,9 @@ async fn test_daemon_sync_push() {
     let root = root_path.clone();
 
     let daemon_handle =
-        tokio::spawn(async move { sy::server::daemon::run_daemon(&socket_str, &root).await });
+        tokio::spawn(
+            async move { sy::server::daemon::run_daemon(&socket_str, &root, false).await },
+        );
 
     // Give daemon time to start
     tokio::time::sleep(Duration::from_millis(200)).await;
@@ -161,6 +251,8 @@ async fn test_daemon_sync_push() {
         &source_path,
         &socket_str,
         &root_path, // Use absolute path
+        MetaOptions::default(),
+        TransferOptions::default(),
     )
     .await;
 
@@ -194,6 +286,651 @@ async fn test_daemon_sync_push() {
     drop(source_temp);
 }
 
+/// Test that modes and xattrs travel both ways through the daemon
+#[tokio::test]
+async fn test_daemon_metadata_push_pull() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = TempDir::new().expect("Failed to create temp dir");
+    let socket_path = temp.path().join("daemon.sock");
+    let root_path = temp.path().join("dest");
+    fs::create_dir_all(&root_path).unwrap();
+
+    let (source_temp, source_path) = create_test_source();
+    let tagged = source_path.join("file1.txt");
+    if xattr::set(&tagged, "user.sy.test", b"pushed").is_err() {
+        // Filesystem without user xattrs
+        return;
+    }
+    fs::set_permissions(&tagged, fs::Permissions::from_mode(0o600)).unwrap();
+
+    let socket_str = socket_path.to_string_lossy().to_string();
+    let root = root_path.clone();
+    let daemon_handle =
+        tokio::spawn(
+            async move { sy::server::daemon::run_daemon(&socket_str, &root, false).await },
+        );
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    let socket_str = socket_path.to_string_lossy().to_string();
+    let meta = MetaOptions {
+        xattrs: true,
+        ..Default::default()
+    };
+    sy::sync::daemon_mode::sync_daemon_mode(
+        &source_path,
+        &socket_str,
+        &root_path,
+        meta,
+        TransferOptions::default(),
+    )
+    .await
+    .expect("Push should succeed");
+
+    let pushed = root_path.join("file1.txt");
+    assert_eq!(
+        xattr::get(&pushed, "user.sy.test").unwrap(),
+        Some(b"pushed".to_vec())
+    );
+    assert_eq!(
+        fs::metadata(&pushed).unwrap().permissions().mode() & 0o777,
+        0o600
+    );
+
+    // Without -X the xattr is left behind, but the mode still arrives
+    let local_dest = temp.path().join("pulled");
+    sy::sync::daemon_mode::sync_pull_daemon_mode(
+        &socket_str,
+        &root_path,
+        &local_dest,
+        MetaOptions::default(),
+        TransferOptions::default(),
+    )
+    .await
+    .expect("Pull should succeed");
+    let pulled = local_dest.join("file1.txt");
+    assert_eq!(xattr::get(&pulled, "user.sy.test").unwrap_or(None), None);
+    assert_eq!(
+        fs::metadata(&pulled).unwrap().permissions().mode() & 0o777,
+        0o600
+    );
+
+    let local_dest = temp.path().join("pulled-x");
+    sy::sync::daemon_mode::sync_pull_daemon_mode(
+        &socket_str,
+        &root_path,
+        &local_dest,
+        meta,
+        TransferOptions::default(),
+    )
+    .await
+    .expect("Pull should succeed");
+    assert_eq!(
+        xattr::get(local_dest.join("file1.txt"), "user.sy.test").unwrap(),
+        Some(b"pushed".to_vec())
+    );
+
+    daemon_handle.abort();
+    let _ = daemon_handle.await;
+    drop(source_temp);
+}
+
+/// Test that hard links stay linked and sparse files keep their contents
+#[tokio::test]
+async fn test_daemon_hardlinks_and_sparse_push_pull() {
+    use std::os::unix::fs::MetadataExt;
+
+    let temp = TempDir::new().expect("Failed to create temp dir");
+    let socket_path = temp.path().join("daemon.sock");
+    let root_path = temp.path().join("dest");
+    fs::create_dir_all(&root_path).unwrap();
+
+    let (source_temp, source_path) = create_test_source();
+    fs::hard_link(source_path.join("file1.txt"), source_path.join("link1.txt")).unwrap();
+    let sparse = source_path.join("sparse.bin");
+    {
+        use std::io::{Seek, SeekFrom, Write};
+        let mut file = fs::File::create(&sparse).unwrap();
+        file.set_len(8 * 1024 * 1024).unwrap();
+        file.seek(SeekFrom::Start(4 * 1024 * 1024)).unwrap();
+        file.write_all(b"island").unwrap();
+    }
+
+    let socket_str = socket_path.to_string_lossy().to_string();
+    let root = root_path.clone();
+    let daemon_handle =
+        tokio::spawn(
+            async move { sy::server::daemon::run_daemon(&socket_str, &root, false).await },
+        );
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    let socket_str = socket_path.to_string_lossy().to_string();
+    let transfer = TransferOptions {
+        hardlinks: true,
+        ..Default::default()
+    };
+    sy::sync::daemon_mode::sync_daemon_mode(
+        &source_path,
+        &socket_str,
+        &root_path,
+        MetaOptions::default(),
+        transfer,
+    )
+    .await
+    .expect("Push should succeed");
+
+    let same_inode =
+        |a: &Path, b: &Path| fs::metadata(a).unwrap().ino() == fs::metadata(b).unwrap().ino();
+    assert!(same_inode(
+        &root_path.join("file1.txt"),
+        &root_path.join("link1.txt")
+    ));
+    assert_eq!(
+        fs::read(&sparse).unwrap(),
+        fs::read(root_path.join("sparse.bin")).unwrap()
+    );
+
+    // With -H the pulled pair is linked again; without it, two copies
+    let local_dest = temp.path().join("pulled");
+    sy::sync::daemon_mode::sync_pull_daemon_mode(
+        &socket_str,
+        &root_path,
+        &local_dest,
+        MetaOptions::default(),
+        transfer,
+    )
+    .await
+    .expect("Pull should succeed");
+    assert!(same_inode(
+        &local_dest.join("file1.txt"),
+        &local_dest.join("link1.txt")
+    ));
+    assert_eq!(
+        fs::read(&sparse).unwrap(),
+        fs::read(local_dest.join("sparse.bin")).unwrap()
+    );
+
+    let local_dest = temp.path().join("pulled-copies");
+    sy::sync::daemon_mode::sync_pull_daemon_mode(
+        &socket_str,
+        &root_path,
+        &local_dest,
+        MetaOptions::default(),
+        TransferOptions::default(),
+    )
+    .await
+    .expect("Pull should succeed");
+    assert!(!same_inode(
+        &local_dest.join("file1.txt"),
+        &local_dest.join("link1.txt")
+    ));
+    assert_eq!(
+        fs::read(local_dest.join("file1.txt")).unwrap(),
+        fs::read(local_dest.join("link1.txt")).unwrap()
+    );
+
+    daemon_handle.abort();
+    let _ = daemon_handle.await;
+    drop(source_temp);
+}
+
+/// Test that push and pull continue from partials an interrupted run left
+#[tokio::test]
+async fn test_daemon_resumes_partial_push_pull() {
+    use sy::server::partial::partial_path;
+
+    let temp = TempDir::new().expect("Failed to create temp dir");
+    let socket_path = temp.path().join("daemon.sock");
+    let root_path = temp.path().join("dest");
+    fs::create_dir_all(&root_path).unwrap();
+
+    let (source_temp, source_path) = create_test_source();
+    let data: Vec<u8> = (0..3 * 1024 * 1024u32).map(|i| (i % 253) as u8).collect();
+    let big = source_path.join("big.bin");
+    fs::write(&big, &data).unwrap();
+    let size = data.len() as u64;
+    let mtime_of = |path: &Path| {
+        fs::metadata(path)
+            .unwrap()
+            .modified()
+            .unwrap()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64
+    };
+    let half = data.len() / 2;
+
+    let socket_str = socket_path.to_string_lossy().to_string();
+    let root = root_path.clone();
+    let daemon_handle =
+        tokio::spawn(
+            async move { sy::server::daemon::run_daemon(&socket_str, &root, false).await },
+        );
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let socket_str = socket_path.to_string_lossy().to_string();
+
+    // Push: the daemon kept the first half from an earlier attempt
+    let partial = partial_path(&root_path.join("big.bin"), size, mtime_of(&big));
+    fs::write(&partial, &data[..half]).unwrap();
+    let stats = sy::sync::daemon_mode::sync_daemon_mode(
+        &source_path,
+        &socket_str,
+        &root_path,
+        MetaOptions::default(),
+        TransferOptions::default(),
+    )
+    .await
+    .expect("Push should succeed");
+    assert_eq!(fs::read(root_path.join("big.bin")).unwrap(), data);
+    assert!(!partial.exists());
+    assert!(stats.bytes_transferred < size);
+
+    // Pull: same from this side, and the partial is never listed
+    let local_dest = temp.path().join("pulled");
+    fs::create_dir_all(&local_dest).unwrap();
+    let mtime = mtime_of(&root_path.join("big.bin"));
+    let partial = partial_path(&local_dest.join("big.bin"), size, mtime);
+    fs::write(&partial, &data[..half]).unwrap();
+    fs::write(
+        partial_path(&root_path.join("stale.bin"), 1, 1),
+        b"leftover",
+    )
+    .unwrap();
+    let stats = sy::sync::daemon_mode::sync_pull_daemon_mode(
+        &socket_str,
+        &root_path,
+        &local_dest,
+        MetaOptions::default(),
+        TransferOptions::default(),
+    )
+    .await
+    .expect("Pull should succeed");
+    assert_eq!(fs::read(local_dest.join("big.bin")).unwrap(), data);
+    assert!(!partial.exists());
+    assert!(stats.bytes_transferred < size);
+    assert!(!fs::read_dir(&local_dest).unwrap().any(|e| e
+        .unwrap()
+        .file_name()
+        .to_string_lossy()
+        .ends_with(".sy-partial")));
+
+    daemon_handle.abort();
+    let _ = daemon_handle.await;
+    drop(source_temp);
+}
+
+/// Test that --verify digests travel with the data, and that a bad partial
+/// is caught by its digest and the file sent again whole
+#[tokio::test]
+async fn test_daemon_verifies_and_retries_push_pull() {
+    use sy::integrity::ChecksumType;
+    use sy::server::partial::partial_path;
+
+    let temp = TempDir::new().expect("Failed to create temp dir");
+    let socket_path = temp.path().join("daemon.sock");
+    let root_path = temp.path().join("dest");
+    fs::create_dir_all(&root_path).unwrap();
+
+    let (source_temp, source_path) = create_test_source();
+    let mut data: Vec<u8> = (0..2 * 1024 * 1024u32).map(|i| (i % 251) as u8).collect();
+    let big = source_path.join("big.bin");
+    fs::write(&big, &data).unwrap();
+    let mtime_of = |path: &Path| {
+        fs::metadata(path)
+            .unwrap()
+            .modified()
+            .unwrap()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64
+    };
+    let garbage = vec![0xAAu8; data.len() / 2];
+
+    let socket_str = socket_path.to_string_lossy().to_string();
+    let root = root_path.clone();
+    let daemon_handle =
+        tokio::spawn(
+            async move { sy::server::daemon::run_daemon(&socket_str, &root, false).await },
+        );
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let socket_str = socket_path.to_string_lossy().to_string();
+
+    // Push onto a corrupt partial: its digest fails, the retry sends it whole
+    let partial = partial_path(
+        &root_path.join("big.bin"),
+        data.len() as u64,
+        mtime_of(&big),
+    );
+    fs::write(&partial, &garbage).unwrap();
+    let transfer = TransferOptions {
+        verify: ChecksumType::Cryptographic,
+        ..Default::default()
+    };
+    let stats = sy::sync::daemon_mode::sync_daemon_mode(
+        &source_path,
+        &socket_str,
+        &root_path,
+        MetaOptions::default(),
+        transfer,
+    )
+    .await
+    .expect("Push should succeed");
+    assert_eq!(fs::read(root_path.join("big.bin")).unwrap(), data);
+    assert_eq!(
+        fs::read(root_path.join("file1.txt")).unwrap(),
+        b"hello world"
+    );
+    assert_eq!(stats.files_created, 4);
+    assert!(!partial.exists());
+
+    // A delta update carries the digest too
+    data.extend_from_slice(b"appended");
+    fs::write(&big, &data).unwrap();
+    let transfer = TransferOptions {
+        verify: ChecksumType::Fast,
+        ..Default::default()
+    };
+    let stats = sy::sync::daemon_mode::sync_daemon_mode(
+        &source_path,
+        &socket_str,
+        &root_path,
+        MetaOptions::default(),
+        transfer,
+    )
+    .await
+    .expect("Delta push should succeed");
+    assert_eq!(stats.files_updated, 1);
+    assert_eq!(fs::read(root_path.join("big.bin")).unwrap(), data);
+
+    // Pull onto a corrupt partial, likewise
+    let local_dest = temp.path().join("pulled");
+    fs::create_dir_all(&local_dest).unwrap();
+    let mtime = mtime_of(&root_path.join("big.bin"));
+    let partial = partial_path(&local_dest.join("big.bin"), data.len() as u64, mtime);
+    fs::write(&partial, &garbage).unwrap();
+    let stats = sy::sync::daemon_mode::sync_pull_daemon_mode(
+        &socket_str,
+        &root_path,
+        &local_dest,
+        MetaOptions::default(),
+        transfer,
+    )
+    .await
+    .expect("Pull should succeed");
+    assert_eq!(fs::read(local_dest.join("big.bin")).unwrap(), data);
+    assert_eq!(
+        fs::read(local_dest.join("subdir/nested.txt")).unwrap(),
+        b"nested content"
+    );
+    assert_eq!(stats.files_created, 4);
+    assert!(!partial.exists());
+
+    daemon_handle.abort();
+    let _ = daemon_handle.await;
+    drop(source_temp);
+}
+
+/// Test that CDC delta updates survive an insertion, with and without the
+/// daemon caching chunk lists
+#[tokio::test]
+async fn test_daemon_cdc_delta_push() {
+    use sy::delta::DeltaAlgo;
+
+    let temp = TempDir::new().expect("Failed to create temp dir");
+    let socket_path = temp.path().join("daemon.sock");
+    let root_path = temp.path().join("dest");
+    fs::create_dir_all(&root_path).unwrap();
+
+    let (source_temp, source_path) = create_test_source();
+    let mut state = 0x2545_F491_4F6C_DD1Du64;
+    let mut data: Vec<u8> = (0..2 * 1024 * 1024)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        })
+        .collect();
+    let big = source_path.join("big.bin");
+    fs::write(&big, &data).unwrap();
+
+    let socket_str = socket_path.to_string_lossy().to_string();
+    let root = root_path.clone();
+    let daemon_handle =
+        tokio::spawn(
+            async move { sy::server::daemon::run_daemon(&socket_str, &root, false).await },
+        );
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let socket_str = socket_path.to_string_lossy().to_string();
+
+    sy::sync::daemon_mode::sync_daemon_mode(
+        &source_path,
+        &socket_str,
+        &root_path,
+        MetaOptions::default(),
+        TransferOptions::default(),
+    )
+    .await
+    .expect("Initial push should succeed");
+
+    // Each insertion shifts everything after it; only nearby chunks resend
+    for (at, chunk_cache) in [(700_000, false), (1_500_000, true), (100, true)] {
+        data.splice(at..at, b"inserted bytes".iter().copied());
+        fs::write(&big, &data).unwrap();
+        let transfer = TransferOptions {
+            delta: DeltaAlgo::Cdc,
+            chunk_cache,
+            ..Default::default()
+        };
+        let stats = sy::sync::daemon_mode::sync_daemon_mode(
+            &source_path,
+            &socket_str,
+            &root_path,
+            MetaOptions::default(),
+            transfer,
+        )
+        .await
+        .expect("CDC push should succeed");
+        assert_eq!(stats.files_updated, 1);
+        assert!(
+            stats.bytes_transferred < 256 * 1024,
+            "{} bytes sent",
+            stats.bytes_transferred
+        );
+        assert_eq!(fs::read(root_path.join("big.bin")).unwrap(), data);
+    }
+
+    daemon_handle.abort();
+    let _ = daemon_handle.await;
+    drop(source_temp);
+}
+
+/// Test that a new file is delta-encoded against a similar one (--fuzzy)
+#[tokio::test]
+async fn test_daemon_fuzzy_basis_push() {
+    use sy::integrity::ChecksumType;
+
+    let temp = TempDir::new().expect("Failed to create temp dir");
+    let socket_path = temp.path().join("daemon.sock");
+    let root_path = temp.path().join("dest");
+    fs::create_dir_all(&root_path).unwrap();
+
+    let (source_temp, source_path) = create_test_source();
+    let mut state = 0x9E37_79B9_7F4A_7C15u64;
+    let old: Vec<u8> = (0..1024 * 1024)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        })
+        .collect();
+    fs::write(source_path.join("app-1.2.bin"), &old).unwrap();
+
+    let socket_str = socket_path.to_string_lossy().to_string();
+    let root = root_path.clone();
+    let daemon_handle =
+        tokio::spawn(
+            async move { sy::server::daemon::run_daemon(&socket_str, &root, false).await },
+        );
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let socket_str = socket_path.to_string_lossy().to_string();
+
+    sy::sync::daemon_mode::sync_daemon_mode(
+        &source_path,
+        &socket_str,
+        &root_path,
+        MetaOptions::default(),
+        TransferOptions::default(),
+    )
+    .await
+    .expect("Initial push should succeed");
+
+    let mut new = old.clone();
+    new[500_000..500_010].copy_from_slice(b"release!!!");
+    fs::write(source_path.join("app-1.3.bin"), &new).unwrap();
+    let transfer = TransferOptions {
+        fuzzy: true,
+        verify: ChecksumType::Fast,
+        ..Default::default()
+    };
+    let stats = sy::sync::daemon_mode::sync_daemon_mode(
+        &source_path,
+        &socket_str,
+        &root_path,
+        MetaOptions::default(),
+        transfer,
+    )
+    .await
+    .expect("Fuzzy push should succeed");
+    assert_eq!(stats.files_created, 1);
+    assert!(
+        stats.bytes_transferred < 64 * 1024,
+        "{} bytes sent",
+        stats.bytes_transferred
+    );
+    assert_eq!(fs::read(root_path.join("app-1.3.bin")).unwrap(), new);
+    assert_eq!(fs::read(root_path.join("app-1.2.bin")).unwrap(), old);
+
+    daemon_handle.abort();
+    let _ = daemon_handle.await;
+    drop(source_temp);
+}
+
+/// Test that daemon sessions compress the whole stream after SET_ROOT with -z
+#[tokio::test]
+async fn test_daemon_stream_compression() {
+    use sy::server::protocol::{CAP_COMPRESS_ZSTD, CAP_STREAM_ZSTD};
+
+    let temp = TempDir::new().expect("Failed to create temp dir");
+    let socket_path = temp.path().join("daemon.sock");
+    let root_path = temp.path().join("dest");
+    fs::create_dir_all(&root_path).unwrap();
+
+    // Many small compressible files: too small for per-file compression
+    let source_temp = TempDir::new().unwrap();
+    for i in 0..50 {
+        let line = format!("file {} has the same line over and over\n", i);
+        fs::write(
+            source_temp.path().join(format!("f{}.txt", i)),
+            line.repeat(50),
+        )
+        .unwrap();
+    }
+
+    let socket_str = socket_path.to_string_lossy().to_string();
+    let root = root_path.clone();
+    let daemon_handle =
+        tokio::spawn(
+            async move { sy::server::daemon::run_daemon(&socket_str, &root, false).await },
+        );
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // Only offered when asked for
+    let socket_str = socket_path.to_string_lossy().to_string();
+    let session = sy::transport::server::DaemonSession::connect(
+        &socket_str,
+        &root_path,
+        SessionOptions::default(),
+    )
+    .await
+    .unwrap();
+    assert!(!session.negotiated().has(CAP_STREAM_ZSTD));
+    session.close().await.unwrap();
+
+    let compress = SessionOptions {
+        compress: true,
+        ..Default::default()
+    };
+    let session = sy::transport::server::DaemonSession::connect(&socket_str, &root_path, compress)
+        .await
+        .unwrap();
+    let negotiated = session.negotiated();
+    assert!(negotiated.has(CAP_STREAM_ZSTD));
+    assert!(negotiated.has(CAP_COMPRESS_ZSTD));
+    session.close().await.unwrap();
+
+    let transfer = TransferOptions {
+        session: SessionOptions {
+            compress: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let stats = sy::sync::daemon_mode::sync_daemon_mode(
+        source_temp.path(),
+        &socket_str,
+        &root_path,
+        MetaOptions::default(),
+        transfer,
+    )
+    .await
+    .unwrap();
+    assert_eq!(stats.files_created, 50);
+    assert!(
+        stats.stream_bytes_raw > stats.stream_bytes_wire * 3,
+        "stream should compress: {} raw, {} wire",
+        stats.stream_bytes_raw,
+        stats.stream_bytes_wire
+    );
+
+    // And the other direction
+    let pull_dest = TempDir::new().unwrap();
+    let stats = sy::sync::daemon_mode::sync_pull_daemon_mode(
+        &socket_str,
+        &root_path,
+        pull_dest.path(),
+        MetaOptions::default(),
+        transfer,
+    )
+    .await
+    .unwrap();
+    assert_eq!(stats.files_created, 50);
+    assert!(stats.stream_bytes_raw > stats.stream_bytes_wire * 3);
+    assert_eq!(
+        fs::read(pull_dest.path().join("f7.txt")).unwrap(),
+        fs::read(source_temp.path().join("f7.txt")).unwrap()
+    );
+
+    // The CLI summary reports the ratio too
+    let mut command = std::process::Command::new(env!("CARGO_BIN_EXE_sy"));
+    command
+        .arg(format!("{}/", source_temp.path().display()))
+        .arg(root_path.join("cli"))
+        .args(["-z", "--use-daemon", &socket_str]);
+    let output = tokio::task::spawn_blocking(move || command.output())
+        .await
+        .unwrap()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "stdout: {}", stdout);
+    assert!(stdout.contains("Stream:"), "stdout: {}", stdout);
+
+    daemon_handle.abort();
+    let _ = daemon_handle.await;
+}
+
 /// Test incremental sync (skip unchanged files)
 #[tokio::test]
 async fn test_daemon_sync_incremental() {
@@ -210,7 +947,9 @@ async fn test_daemon_sync_incremental() {
     let root = root_path.clone();
 
     let daemon_handle =
-        tokio::spawn(async move { sy::server::daemon::run_daemon(&socket_str, &root).await });
+        tokio::spawn(
+            async move { sy::server::daemon::run_daemon(&socket_str, &root, false).await },
+        );
 
     // Give daemon time to start
     tokio::time::sleep(Duration::from_millis(200)).await;
@@ -222,6 +961,8 @@ async fn test_daemon_sync_incremental() {
         &source_path,
         &socket_str,
         &root_path, // Use absolute path
+        MetaOptions::default(),
+        TransferOptions::default(),
     )
     .await
     .expect("First sync should succeed");
@@ -233,6 +974,8 @@ async fn test_daemon_sync_incremental() {
         &source_path,
         &socket_str,
         &root_path, // Use absolute path
+        MetaOptions::default(),
+        TransferOptions::default(),
     )
     .await
     .expect("Second sync should succeed");
@@ -246,6 +989,103 @@ async fn test_daemon_sync_incremental() {
     drop(source_temp);
 }
 
+/// Test a persistent session pushing several batches over one connection
+#[tokio::test]
+async fn test_daemon_persistent_session() {
+    use sy::sync::incremental::ChangeSet;
+    use sy::sync::server_mode::{PersistentSession, SessionTarget};
+
+    let temp = TempDir::new().expect("Failed to create temp dir");
+    let socket_path = temp.path().join("daemon.sock");
+    let root_path = temp.path().join("dest");
+    fs::create_dir_all(&root_path).unwrap();
+
+    let (source_temp, source_path) = create_test_source();
+
+    let socket_str = socket_path.to_string_lossy().to_string();
+    let root = root_path.clone();
+
+    let daemon_handle =
+        tokio::spawn(
+            async move { sy::server::daemon::run_daemon(&socket_str, &root, false).await },
+        );
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    let mut session = PersistentSession::new(
+        SessionTarget::Daemon {
+            socket_path: socket_path.to_string_lossy().to_string(),
+            remote_path: root_path.clone(),
+        },
+        false,
+    );
+
+    let stats = session
+        .sync_all(&source_path)
+        .await
+        .expect("Initial sync should succeed");
+    assert_eq!(stats.files_created, 3);
+
+    session.keepalive().await.expect("Ping should succeed");
+
+    // Only the changed paths are sent on the same connection
+    fs::write(source_path.join("file3.txt"), "new file").unwrap();
+    fs::write(source_path.join("subdir/nested.txt"), "changed content").unwrap();
+    let mut changes = ChangeSet::new();
+    changes.insert(PathBuf::from("file3.txt"));
+    changes.insert(PathBuf::from("subdir/nested.txt"));
+
+    let stats = session
+        .sync_paths(&source_path, &changes)
+        .await
+        .expect("Incremental sync should succeed");
+    assert_eq!(stats.files_scanned, 2);
+    assert_eq!(stats.files_created, 1);
+    assert_eq!(stats.files_updated, 1);
+    assert_eq!(
+        fs::read_to_string(root_path.join("subdir/nested.txt")).unwrap(),
+        "changed content"
+    );
+
+    // Unchanged files are skipped from the cached destination listing
+    let stats = session
+        .sync_all(&source_path)
+        .await
+        .expect("Full sync should succeed");
+    assert_eq!(stats.files_created + stats.files_updated, 0);
+    assert_eq!(stats.files_skipped, 4);
+
+    daemon_handle.abort();
+    let _ = daemon_handle.await;
+    drop(source_temp);
+}
+
+/// The persistent watch session can't delete or filter, so it refuses
+/// flags that only the sync engine honors instead of ignoring them
+#[cfg(feature = "watch")]
+#[test]
+fn test_daemon_watch_rejects_engine_only_flags() {
+    let temp = TempDir::new().expect("Failed to create temp dir");
+    let (_source_temp, source_path) = create_test_source();
+    let socket_path = temp.path().join("daemon.sock");
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_sy"))
+        .arg(&source_path)
+        .arg(temp.path().join("dest"))
+        .args(["--watch", "--delete", "--use-daemon"])
+        .arg(&socket_path)
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("--delete is not supported with --watch"),
+        "stderr: {}",
+        stderr
+    );
+}
+
 /// Test pull mode (daemon sends files to local)
 #[tokio::test]
 async fn test_daemon_sync_pull() {
@@ -270,7 +1110,9 @@ async fn test_daemon_sync_pull() {
     let root = daemon_root.clone();
 
     let daemon_handle =
-        tokio::spawn(async move { sy::server::daemon::run_daemon(&socket_str, &root).await });
+        tokio::spawn(
+            async move { sy::server::daemon::run_daemon(&socket_str, &root, false).await },
+        );
 
     // Give daemon time to start
     tokio::time::sleep(Duration::from_millis(200)).await;
@@ -281,6 +1123,8 @@ async fn test_daemon_sync_pull() {
         &socket_str,
         &daemon_root, // Use absolute path
         &local_dest,
+        MetaOptions::default(),
+        TransferOptions::default(),
     )
     .await;
 
@@ -308,3 +1152,490 @@ async fn test_daemon_sync_pull() {
     daemon_handle.abort();
     let _ = daemon_handle.await;
 }
+
+/// --safe-links on the client applies to the symlinks it pulls
+#[tokio::test]
+async fn test_daemon_pull_safe_links() {
+    let temp = TempDir::new().expect("Failed to create temp dir");
+    let socket_path = temp.path().join("daemon.sock");
+    let local_dest = temp.path().join("local_dest");
+    let daemon_root = temp.path().join("daemon_root");
+    fs::create_dir_all(&daemon_root).unwrap();
+    fs::write(daemon_root.join("file.txt"), "content").unwrap();
+    std::os::unix::fs::symlink("file.txt", daemon_root.join("inside")).unwrap();
+    std::os::unix::fs::symlink("../../etc/passwd", daemon_root.join("escape")).unwrap();
+
+    let socket_str = socket_path.to_string_lossy().to_string();
+    let root = daemon_root.clone();
+    let daemon_handle =
+        tokio::spawn(
+            async move { sy::server::daemon::run_daemon(&socket_str, &root, false).await },
+        );
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    let socket_str = socket_path.to_string_lossy().to_string();
+    let transfer = TransferOptions {
+        session: SessionOptions {
+            safe_links: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    sy::sync::daemon_mode::sync_pull_daemon_mode(
+        &socket_str,
+        &daemon_root,
+        &local_dest,
+        MetaOptions::default(),
+        transfer,
+    )
+    .await
+    .expect("Pull should succeed");
+
+    assert_eq!(
+        fs::read_link(local_dest.join("inside")).unwrap(),
+        Path::new("file.txt")
+    );
+    assert!(fs::symlink_metadata(local_dest.join("escape")).is_err());
+
+    daemon_handle.abort();
+    let _ = daemon_handle.await;
+}
+
+/// A running daemon sets its own symlink policy, so pushing can't ask for one
+#[test]
+fn test_daemon_push_rejects_safe_links() {
+    let temp = TempDir::new().expect("Failed to create temp dir");
+    let (_source_temp, source_path) = create_test_source();
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_sy"))
+        .arg(&source_path)
+        .arg(temp.path().join("dest"))
+        .args(["--safe-links", "--use-daemon"])
+        .arg(temp.path().join("daemon.sock"))
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("--safe-links can't be passed to a running daemon"),
+        "stderr: {}",
+        stderr
+    );
+}
+
+/// Daemon sessions emit no per-file JSON events to attach delta stats to
+#[test]
+fn test_daemon_push_rejects_delta_stats() {
+    let temp = TempDir::new().expect("Failed to create temp dir");
+    let (_source_temp, source_path) = create_test_source();
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_sy"))
+        .arg(&source_path)
+        .arg(temp.path().join("dest"))
+        .args(["--json", "--delta-stats", "--use-daemon"])
+        .arg(temp.path().join("daemon.sock"))
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("not by server or daemon sessions"),
+        "stderr: {}",
+        stderr
+    );
+}
+
+/// Run `sy --daemon` with `args`, expecting it to refuse them instead of
+/// starting; returns its stderr
+fn refused_daemon(temp: &TempDir, args: &[&str]) -> String {
+    let mut child = std::process::Command::new(env!("CARGO_BIN_EXE_sy"))
+        .arg("--daemon")
+        .arg("--socket")
+        .arg(temp.path().join("daemon.sock"))
+        .args(args)
+        .arg(temp.path())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let deadline = std::time::Instant::now() + Duration::from_secs(10);
+    while child.try_wait().unwrap().is_none() {
+        if std::time::Instant::now() > deadline {
+            let _ = child.kill();
+            panic!("daemon started with {:?}", args);
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    let output = child.wait_with_output().unwrap();
+    assert!(!output.status.success());
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+/// A TCP listener without tokens must not fall back to a Unix socket
+#[test]
+fn test_daemon_listen_requires_auth_tokens() {
+    let temp = TempDir::new().expect("Failed to create temp dir");
+
+    let stderr = refused_daemon(&temp, &["--listen", "127.0.0.1:0"]);
+    assert!(
+        stderr.contains("--listen requires --auth-tokens"),
+        "stderr: {}",
+        stderr
+    );
+    assert!(!temp.path().join("daemon.sock").exists());
+
+    let cert = temp.path().join("cert.pem");
+    let key = temp.path().join("key.pem");
+    let stderr = refused_daemon(
+        &temp,
+        &[
+            "--tls-cert",
+            cert.to_str().unwrap(),
+            "--tls-key",
+            key.to_str().unwrap(),
+        ],
+    );
+    assert!(
+        stderr.contains("--tls-cert and --auth-tokens only apply with --listen"),
+        "stderr: {}",
+        stderr
+    );
+}
+
+/// Modules set their own symlink policy, so the command line can't
+#[test]
+fn test_daemon_config_rejects_safe_links() {
+    let temp = TempDir::new().expect("Failed to create temp dir");
+    let config = temp.path().join("modules.toml");
+    fs::write(&config, "").unwrap();
+
+    let stderr = refused_daemon(
+        &temp,
+        &["--safe-links", "--daemon-config", config.to_str().unwrap()],
+    );
+    assert!(
+        stderr.contains("--safe-links is set per module in --daemon-config"),
+        "stderr: {}",
+        stderr
+    );
+    assert!(!temp.path().join("daemon.sock").exists());
+}
+
+/// Start a TCP daemon with one token on a free local port
+async fn start_tcp_daemon(
+    temp: &TempDir,
+    root: PathBuf,
+) -> (u16, tokio::task::JoinHandle<anyhow::Result<()>>) {
+    start_tcp_daemon_with_timeout(temp, root, Duration::from_secs(30)).await
+}
+
+async fn start_tcp_daemon_with_timeout(
+    temp: &TempDir,
+    root: PathBuf,
+    handshake_timeout: Duration,
+) -> (u16, tokio::task::JoinHandle<anyhow::Result<()>>) {
+    let tokens_path = temp.path().join("tokens");
+    fs::write(&tokens_path, "# test tokens\nci:s3cret\n").unwrap();
+
+    // Reserve a free port, then hand it to the daemon
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let config = sy::server::tcp::TcpListenConfig {
+        listen: format!("127.0.0.1:{}", port),
+        auth_tokens: tokens_path,
+        tls_cert: None,
+        tls_key: None,
+        handshake_timeout,
+    };
+    let handle =
+        tokio::spawn(
+            async move { sy::server::daemon::run_tcp_daemon(&config, &root, false).await },
+        );
+
+    // Give daemon time to start
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    (port, handle)
+}
+
+fn tcp_endpoint(port: u16, token: &str) -> sy::server::tcp::TcpEndpoint {
+    sy::server::tcp::TcpEndpoint {
+        host: "127.0.0.1".to_string(),
+        port,
+        tls_ca: None,
+        token: sy::server::tcp::AuthToken::parse(token).unwrap(),
+    }
+}
+
+/// A client that never finishes the handshake is dropped at the deadline,
+/// freeing its connection slot
+#[tokio::test]
+async fn test_tcp_daemon_drops_stalled_handshake() {
+    use tokio::io::AsyncReadExt;
+
+    let temp = TempDir::new().expect("Failed to create temp dir");
+    let root_path = temp.path().join("dest");
+    fs::create_dir_all(&root_path).unwrap();
+    let (_source_temp, source_path) = create_test_source();
+
+    let (port, daemon_handle) =
+        start_tcp_daemon_with_timeout(&temp, root_path.clone(), Duration::from_millis(300)).await;
+
+    let mut idle = tokio::net::TcpStream::connect(("127.0.0.1", port))
+        .await
+        .unwrap();
+    let mut buf = [0u8; 1];
+    let read = timeout(Duration::from_secs(5), idle.read(&mut buf))
+        .await
+        .expect("Daemon should close a stalled connection");
+    assert_eq!(read.unwrap(), 0);
+
+    // Clients that authenticate in time are unaffected
+    let endpoint = tcp_endpoint(port, "ci:s3cret");
+    let stats = timeout(
+        Duration::from_secs(10),
+        sy::sync::daemon_mode::sync_tcp_daemon_mode(
+            &source_path,
+            &endpoint,
+            &root_path,
+            MetaOptions::default(),
+            TransferOptions::default(),
+        ),
+    )
+    .await
+    .expect("Push timed out")
+    .expect("Push should succeed");
+    assert_eq!(stats.files_created, 3);
+
+    daemon_handle.abort();
+    let _ = daemon_handle.await;
+}
+
+/// Test push and pull through the TCP listener with a valid token
+#[tokio::test]
+async fn test_tcp_daemon_sync_push_and_pull() {
+    let temp = TempDir::new().expect("Failed to create temp dir");
+    let root_path = temp.path().join("dest");
+    fs::create_dir_all(&root_path).unwrap();
+    let (source_temp, source_path) = create_test_source();
+
+    let (port, daemon_handle) = start_tcp_daemon(&temp, root_path.clone()).await;
+    let endpoint = tcp_endpoint(port, "ci:s3cret");
+
+    let stats = timeout(
+        Duration::from_secs(10),
+        sy::sync::daemon_mode::sync_tcp_daemon_mode(
+            &source_path,
+            &endpoint,
+            &root_path,
+            MetaOptions::default(),
+            TransferOptions::default(),
+        ),
+    )
+    .await
+    .expect("Push timed out")
+    .expect("Push should succeed");
+    assert_eq!(stats.files_created, 3, "Should create 3 files");
+    assert_eq!(
+        fs::read_to_string(root_path.join("subdir/nested.txt")).unwrap(),
+        "nested content"
+    );
+
+    let local_dest = temp.path().join("pulled");
+    let stats = timeout(
+        Duration::from_secs(10),
+        sy::sync::daemon_mode::sync_pull_tcp_daemon_mode(
+            &endpoint,
+            &root_path,
+            &local_dest,
+            MetaOptions::default(),
+            TransferOptions::default(),
+        ),
+    )
+    .await
+    .expect("Pull timed out")
+    .expect("Pull should succeed");
+    assert_eq!(stats.files_created, 3, "Should pull 3 files");
+    assert_eq!(
+        fs::read_to_string(local_dest.join("file1.txt")).unwrap(),
+        "hello world"
+    );
+
+    daemon_handle.abort();
+    let _ = daemon_handle.await;
+    drop(source_temp);
+}
+
+/// Test that a wrong secret or unknown token name is rejected
+#[tokio::test]
+async fn test_tcp_daemon_rejects_bad_token() {
+    let temp = TempDir::new().expect("Failed to create temp dir");
+    let root_path = temp.path().join("dest");
+    fs::create_dir_all(&root_path).unwrap();
+    let (source_temp, source_path) = create_test_source();
+
+    let (port, daemon_handle) = start_tcp_daemon(&temp, root_path.clone()).await;
+
+    for token in ["ci:wrong", "other:s3cret"] {
+        let result = timeout(
+            Duration::from_secs(10),
+            sy::sync::daemon_mode::sync_tcp_daemon_mode(
+                &source_path,
+                &tcp_endpoint(port, token),
+                &root_path,
+                MetaOptions::default(),
+                TransferOptions::default(),
+            ),
+        )
+        .await
+        .expect("Connection timed out");
+        assert!(result.is_err(), "Token {} should be rejected", token);
+    }
+    assert!(!root_path.join("file1.txt").exists());
+
+    daemon_handle.abort();
+    let _ = daemon_handle.await;
+    drop(source_temp);
+}
+
+/// Start a Unix-socket daemon serving the modules in `config`
+async fn start_module_daemon(
+    temp: &TempDir,
+    config: &str,
+) -> (String, tokio::task::JoinHandle<anyhow::Result<()>>) {
+    let modules = sy::server::modules::ModuleTable::parse(config).unwrap();
+    let socket_str = temp
+        .path()
+        .join("daemon.sock")
+        .to_string_lossy()
+        .to_string();
+    let socket = socket_str.clone();
+    let handle =
+        tokio::spawn(
+            async move { sy::server::daemon::run_daemon_with_modules(&socket, modules).await },
+        );
+
+    // Give daemon time to start
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    (socket_str, handle)
+}
+
+/// Test push and pull through a named module, with its filter applied
+#[tokio::test]
+async fn test_daemon_module_push_pull_filtered() {
+    let temp = TempDir::new().expect("Failed to create temp dir");
+    let module_root = temp.path().join("module");
+    fs::create_dir_all(&module_root).unwrap();
+    let (source_temp, source_path) = create_test_source();
+    fs::write(source_path.join("debug.log"), "noise").unwrap();
+
+    let config = format!(
+        "[modules.data]\npath = \"{}\"\nfilter = [\"- *.log\"]\n",
+        module_root.display()
+    );
+    let (socket, daemon_handle) = start_module_daemon(&temp, &config).await;
+
+    let stats = sy::sync::daemon_mode::sync_daemon_mode(
+        &source_path,
+        &socket,
+        std::path::Path::new("data/incoming"),
+        MetaOptions::default(),
+        TransferOptions::default(),
+    )
+    .await
+    .expect("Push to module should succeed");
+    assert_eq!(stats.files_created, 3, "Excluded file should be skipped");
+    assert!(module_root.join("incoming/subdir/nested.txt").exists());
+    assert!(!module_root.join("incoming/debug.log").exists());
+
+    // A .log file placed on the daemon side is never offered to pullers
+    fs::write(module_root.join("incoming/server.log"), "private").unwrap();
+    let local_dest = temp.path().join("pulled");
+    let stats = sy::sync::daemon_mode::sync_pull_daemon_mode(
+        &socket,
+        std::path::Path::new("data/incoming"),
+        &local_dest,
+        MetaOptions::default(),
+        TransferOptions::default(),
+    )
+    .await
+    .expect("Pull from module should succeed");
+    assert_eq!(stats.files_created, 3);
+    assert!(local_dest.join("file1.txt").exists());
+    assert!(!local_dest.join("server.log").exists());
+
+    daemon_handle.abort();
+    let _ = daemon_handle.await;
+    drop(source_temp);
+}
+
+/// Test that module access rules and path escapes are rejected
+#[tokio::test]
+async fn test_daemon_module_rejections() {
+    let temp = TempDir::new().expect("Failed to create temp dir");
+    let data_root = temp.path().join("data");
+    let ro_root = temp.path().join("ro");
+    let outside = temp.path().join("outside");
+    for dir in [&data_root, &ro_root, &outside] {
+        fs::create_dir_all(dir).unwrap();
+    }
+    std::os::unix::fs::symlink(&outside, data_root.join("escape")).unwrap();
+    let (source_temp, source_path) = create_test_source();
+
+    let config = format!(
+        "[modules.data]\npath = \"{}\"\n\n[modules.ro]\npath = \"{}\"\nread_only = true\n\n\
+         [modules.private]\npath = \"{}\"\nallow = [\"nobody-here\"]\n",
+        data_root.display(),
+        ro_root.display(),
+        outside.display()
+    );
+    let (socket, daemon_handle) = start_module_daemon(&temp, &config).await;
+
+    for (target, reason) in [
+        ("ro", "read-only"),
+        ("missing/dir", "Unknown module"),
+        ("private", "denied"),
+        ("data/../outside", "escapes"),
+        ("data/escape/sub", "escapes"),
+    ] {
+        let err = sy::sync::daemon_mode::sync_daemon_mode(
+            &source_path,
+            &socket,
+            std::path::Path::new(target),
+            MetaOptions::default(),
+            TransferOptions::default(),
+        )
+        .await
+        .expect_err(target);
+        assert!(
+            format!("{:#}", err).contains(reason),
+            "{}: unexpected error {:#}",
+            target,
+            err
+        );
+    }
+    assert_eq!(fs::read_dir(&outside).unwrap().count(), 0);
+
+    // Read-only modules still serve pulls
+    fs::write(ro_root.join("release.txt"), "v1").unwrap();
+    let local_dest = temp.path().join("pulled");
+    sy::sync::daemon_mode::sync_pull_daemon_mode(
+        &socket,
+        std::path::Path::new("ro"),
+        &local_dest,
+        MetaOptions::default(),
+        TransferOptions::default(),
+    )
+    .await
+    .expect("Pull from read-only module should succeed");
+    assert!(local_dest.join("release.txt").exists());
+
+    daemon_handle.abort();
+    let _ = daemon_handle.await;
+    drop(source_temp);
+}
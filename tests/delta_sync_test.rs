@@ -647,3 +647,109 @@ fn test_sparse_file_delta_sync_preserves_sparseness() {
         stdout
     );
 }
+
+#[test]
+fn test_fuzzy_basis_for_new_file() {
+    let source = TempDir::new().unwrap();
+    let dest = TempDir::new().unwrap();
+
+    // New release next to the old one it was built from
+    let old: Vec<u8> = (0..200_000u32).map(|i| (i * 7 % 253) as u8).collect();
+    let mut new = old.clone();
+    new[100_000..100_010].copy_from_slice(b"1.3 build ");
+    fs::create_dir(source.path().join("dist")).unwrap();
+    fs::create_dir(dest.path().join("dist")).unwrap();
+    fs::write(source.path().join("dist/app-1.3.tar"), &new).unwrap();
+    fs::write(dest.path().join("dist/app-1.2.tar"), &old).unwrap();
+
+    // With --delete the basis itself goes away, but only after it was used
+    let source_path = format!("{}/", source.path().display());
+    let output = Command::new(sy_bin())
+        .args([
+            &source_path,
+            dest.path().to_str().unwrap(),
+            "--fuzzy",
+            "--delete",
+        ])
+        .output()
+        .unwrap();
+
+    assert!(
+        output.status.success(),
+        "Sync should succeed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(fs::read(dest.path().join("dist/app-1.3.tar")).unwrap(), new);
+    assert!(!dest.path().join("dist/app-1.2.tar").exists());
+}
+
+#[test]
+fn test_delta_stats_in_json_output() {
+    let source = TempDir::new().unwrap();
+    let dest = TempDir::new().unwrap();
+
+    // Large enough for local delta sync, one small edit
+    let old: Vec<u8> = (0..12 * 1024 * 1024u32)
+        .map(|i| (i * 31 % 251) as u8)
+        .collect();
+    let mut new = old.clone();
+    new[6_000_000..6_000_016].copy_from_slice(b"page 1465 edited");
+    fs::write(source.path().join("app.db"), &new).unwrap();
+    fs::write(dest.path().join("app.db"), &old).unwrap();
+    filetime::set_file_mtime(
+        dest.path().join("app.db"),
+        filetime::FileTime::from_unix_time(1_600_000_000, 0),
+    )
+    .unwrap();
+
+    let source_path = format!("{}/", source.path().display());
+    let output = Command::new(sy_bin())
+        .args([
+            &source_path,
+            dest.path().to_str().unwrap(),
+            "--block-size",
+            "8K",
+            "--json",
+            "--delta-stats",
+        ])
+        .output()
+        .unwrap();
+
+    assert!(
+        output.status.success(),
+        "Sync should succeed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(fs::read(dest.path().join("app.db")).unwrap() == new);
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let update: serde_json::Value = stdout
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .find(|event| event["type"] == "update")
+        .expect("update event");
+    let stats = &update["delta_stats"];
+    let literal = stats["literal_bytes"].as_u64().unwrap();
+    let matched = stats["matched_bytes"].as_u64().unwrap();
+    // Only the 8K block holding the edit is literal
+    assert!(literal > 0 && literal <= 8192, "literal bytes: {}", literal);
+    assert_eq!(matched + literal, new.len() as u64);
+}
+
+#[test]
+fn test_delta_stats_requires_json() {
+    let source = TempDir::new().unwrap();
+    let dest = TempDir::new().unwrap();
+
+    let output = Command::new(sy_bin())
+        .args([
+            source.path().to_str().unwrap(),
+            dest.path().to_str().unwrap(),
+            "--delta-stats",
+        ])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--delta-stats requires --json"));
+}
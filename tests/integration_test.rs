@@ -253,6 +253,42 @@ fn test_error_source_not_exists() {
     assert!(stderr.contains("does not exist"));
 }
 
+#[test]
+fn test_detect_moves_needs_engine_deletions() {
+    let (source, dest) = setup_test_dir("moves");
+
+    // Without --delete one-way sync never deletes, so nothing could move
+    let output = Command::new(sy_bin())
+        .args([
+            source.path().to_str().unwrap(),
+            dest.path().to_str().unwrap(),
+            "--detect-moves",
+        ])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("requires --delete"), "stderr: {}", stderr);
+
+    // Server-mode pushes don't delete either; refused before connecting
+    let output = Command::new(sy_bin())
+        .args([
+            source.path().to_str().unwrap(),
+            "nohost.invalid:/tmp/dest",
+            "--detect-moves",
+            "--delete",
+        ])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("not supported by server or daemon sessions"),
+        "stderr: {}",
+        stderr
+    );
+}
+
 #[tokio::test]
 async fn test_single_file_sync() {
     let temp = TempDir::new().unwrap();
@@ -0,0 +1,335 @@
+//! Round-trip and robustness properties for the server wire protocol
+//!
+//! Every message type is encoded, its frame header checked, and decoded
+//! back with `from_reader` and within its frame with `read_frame`. Arbitrary
+//! and truncated payloads, and frames whose length doesn't match their
+//! payload, must come back as errors, never panics. The cargo-fuzz targets in `fuzz/` drive the same
+//! decoders with coverage guidance.
+
+use proptest::prelude::*;
+use std::io::Cursor;
+use sy::delta::DeltaAlgo;
+use sy::integrity::ChecksumType;
+use sy::server::protocol::*;
+
+fn block_on<F: std::future::Future>(f: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap()
+        .block_on(f)
+}
+
+fn frame_error<T: std::fmt::Debug>(result: anyhow::Result<T>) -> ProtocolError {
+    result
+        .unwrap_err()
+        .downcast::<ProtocolError>()
+        .expect("frame errors are ProtocolErrors")
+}
+
+/// Encode `$msg`, check the frame, and decode it back as `$ty`
+macro_rules! assert_roundtrip {
+    ($ty:ty, $kind:expr, $msg:expr) => {{
+        let msg: $ty = $msg;
+        let mut buf = Vec::new();
+        block_on(msg.write(&mut buf)).unwrap();
+        let len = u32::from_be_bytes(buf[..4].try_into().unwrap()) as usize;
+        prop_assert_eq!(len, buf.len() - 5, "frame length");
+        prop_assert_eq!(buf[4], $kind as u8);
+
+        let mut cursor = Cursor::new(&buf[5..]);
+        let decoded = block_on(<$ty>::from_reader(&mut cursor));
+        prop_assert!(decoded.is_ok(), "{:?}", decoded);
+        prop_assert_eq!(cursor.position() as usize, len, "payload consumed");
+        prop_assert_eq!(&decoded.unwrap(), &msg);
+
+        // Decoded within its frame, the payload must fill the length exactly
+        let read_framed = |bytes: &[u8]| {
+            block_on(async {
+                let mut cursor = Cursor::new(bytes);
+                let frame = Frame::read(&mut cursor).await?;
+                <$ty>::read_frame(&mut cursor, &frame).await
+            })
+        };
+        let framed = read_framed(&buf);
+        prop_assert!(framed.is_ok(), "{:?}", framed);
+        prop_assert_eq!(&framed.unwrap(), &msg);
+
+        // A frame announcing one byte more than the message is refused
+        let mut long = buf.clone();
+        long[..4].copy_from_slice(&(len as u32 + 1).to_be_bytes());
+        long.push(0);
+        let err = frame_error(read_framed(&long));
+        prop_assert!(
+            matches!(err, ProtocolError::TrailingBytes { extra: 1, .. } | ProtocolError::TooLarge { .. }),
+            "{:?}",
+            err
+        );
+
+        // So is one that ends before the message does
+        if len > 0 {
+            let mut short = buf.clone();
+            short[..4].copy_from_slice(&(len as u32 - 1).to_be_bytes());
+            let err = frame_error(read_framed(&short));
+            prop_assert!(matches!(err, ProtocolError::ShortFrame { .. }), "{:?}", err);
+        }
+
+        // Every strict prefix of the payload is a truncated message
+        for cut in 0..len {
+            let err = block_on(<$ty>::from_reader(&mut Cursor::new(&buf[5..5 + cut])));
+            prop_assert!(
+                matches!(err, Err(ref e) if e.is_truncated()),
+                "prefix of {} bytes: {:?}",
+                cut,
+                err
+            );
+        }
+    }};
+}
+
+fn name() -> impl Strategy<Value = String> {
+    "\\PC{0,24}"
+}
+
+fn digest() -> impl Strategy<Value = Vec<u8>> {
+    prop_oneof![
+        Just(Vec::new()),
+        Just(vec![0u8; 8]),
+        prop::collection::vec(any::<u8>(), 0..=MAX_DIGEST_LEN)
+    ]
+}
+
+fn entry_meta() -> impl Strategy<Value = EntryMeta> {
+    (
+        any::<Option<u32>>(),
+        any::<Option<u32>>(),
+        any::<Option<u32>>(),
+        prop::option::of("\\PC{0,64}"),
+        prop::collection::vec((name(), prop::collection::vec(any::<u8>(), 0..32)), 0..4),
+    )
+        .prop_map(|(uid, gid, bsd_flags, acls, xattrs)| EntryMeta {
+            uid,
+            gid,
+            bsd_flags,
+            acls,
+            xattrs,
+        })
+}
+
+fn file_list_entry() -> impl Strategy<Value = FileListEntry> {
+    (
+        name(),
+        any::<u64>(),
+        any::<i64>(),
+        any::<u32>(),
+        prop::sample::select(vec![0, FLAG_IS_DIR, FLAG_IS_SYMLINK, FLAG_HAS_XATTRS]),
+        prop::option::of("\\PC{1,24}"),
+        any::<Option<u32>>(),
+        prop::option::of(entry_meta()),
+    )
+        .prop_map(
+            |(path, size, mtime, mode, kind, target, hardlink_to, meta)| {
+                // The writer derives these bits from the optional fields
+                let mut flags = kind;
+                if meta.is_some() {
+                    flags |= FLAG_HAS_META;
+                }
+                if hardlink_to.is_some() {
+                    flags |= FLAG_IS_HARDLINK;
+                }
+                FileListEntry {
+                    path,
+                    size,
+                    mtime,
+                    mode,
+                    flags,
+                    symlink_target: target.filter(|_| kind == FLAG_IS_SYMLINK),
+                    hardlink_to,
+                    meta,
+                }
+            },
+        )
+}
+
+fn decision() -> impl Strategy<Value = Decision> {
+    (
+        any::<u32>(),
+        prop::sample::select(vec![
+            Action::Skip,
+            Action::Create,
+            Action::Update,
+            Action::Delete,
+        ]),
+        prop_oneof![Just(0u64), any::<u64>()],
+        prop::sample::select(vec![
+            ChecksumType::None,
+            ChecksumType::Fast,
+            ChecksumType::Cryptographic,
+        ]),
+        prop::option::of(name()),
+    )
+        .prop_map(|(index, action, resume_from, digest, basis)| Decision {
+            index,
+            action,
+            resume_from,
+            digest,
+            basis,
+        })
+}
+
+fn failures() -> impl Strategy<Value = Vec<(String, String)>> {
+    prop::collection::vec((name(), name()), 0..8)
+}
+
+fn delta_op() -> impl Strategy<Value = DeltaOp> {
+    prop_oneof![
+        (any::<u64>(), any::<u32>()).prop_map(|(offset, size)| DeltaOp::Copy { offset, size }),
+        prop::collection::vec(any::<u8>(), 0..64).prop_map(DeltaOp::Data),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn prop_hello_roundtrip(version: u16, flags: u32, caps in prop::collection::vec(any::<u8>(), 0..64)) {
+        assert_roundtrip!(Hello, MessageType::Hello, Hello { version, flags, capabilities: caps });
+    }
+
+    #[test]
+    fn prop_file_list_roundtrip(entries in prop::collection::vec(file_list_entry(), 0..8)) {
+        assert_roundtrip!(FileList, MessageType::FileList, FileList { entries });
+    }
+
+    #[test]
+    fn prop_file_list_ack_roundtrip(decisions in prop::collection::vec(decision(), 0..16)) {
+        assert_roundtrip!(FileListAck, MessageType::FileListAck, FileListAck { decisions });
+    }
+
+    #[test]
+    fn prop_file_data_roundtrip(index: u32, offset: u64, flags: u8, data in prop::collection::vec(any::<u8>(), 0..256)) {
+        assert_roundtrip!(FileData, MessageType::FileData, FileData { index, offset, flags, data });
+    }
+
+    #[test]
+    fn prop_file_done_roundtrip(index: u32, status: u8, checksum in digest()) {
+        assert_roundtrip!(FileDone, MessageType::FileDone, FileDone { index, status, checksum });
+    }
+
+    #[test]
+    fn prop_mkdir_batch_roundtrip(paths in prop::collection::vec(name(), 0..16)) {
+        assert_roundtrip!(MkdirBatch, MessageType::MkdirBatch, MkdirBatch { paths });
+    }
+
+    #[test]
+    fn prop_mkdir_batch_ack_roundtrip(created: u32, failed in failures()) {
+        assert_roundtrip!(MkdirBatchAck, MessageType::MkdirBatchAck, MkdirBatchAck { created, failed });
+    }
+
+    #[test]
+    fn prop_symlink_batch_roundtrip(pairs in prop::collection::vec((name(), name()), 0..8)) {
+        let entries = pairs
+            .into_iter()
+            .map(|(path, target)| SymlinkEntry { path, target })
+            .collect();
+        assert_roundtrip!(SymlinkBatch, MessageType::SymlinkBatch, SymlinkBatch { entries });
+    }
+
+    #[test]
+    fn prop_symlink_batch_ack_roundtrip(created: u32, failed in failures()) {
+        assert_roundtrip!(SymlinkBatchAck, MessageType::SymlinkBatchAck, SymlinkBatchAck { created, failed });
+    }
+
+    #[test]
+    fn prop_error_roundtrip(code: u16, message in name()) {
+        assert_roundtrip!(ErrorMessage, MessageType::Error, ErrorMessage { code, message });
+    }
+
+    #[test]
+    fn prop_checksum_req_roundtrip(index: u32, block_size in 1..=MAX_BLOCK_SIZE, cdc: bool, cached: bool) {
+        let algo = if cdc { DeltaAlgo::Cdc } else { DeltaAlgo::Block };
+        assert_roundtrip!(ChecksumReq, MessageType::ChecksumReq, ChecksumReq { index, block_size, algo, cached });
+    }
+
+    #[test]
+    fn prop_checksum_resp_roundtrip(
+        index: u32,
+        file_size: u64,
+        blocks in prop::collection::vec(any::<(u64, u32, u32, u64)>(), 0..16),
+    ) {
+        let checksums = blocks
+            .into_iter()
+            .map(|(offset, size, weak, strong)| BlockChecksum { offset, size, weak, strong })
+            .collect();
+        assert_roundtrip!(ChecksumResp, MessageType::ChecksumResp, ChecksumResp { index, file_size, checksums });
+    }
+
+    #[test]
+    fn prop_delta_data_roundtrip(
+        index: u32,
+        flags: u8,
+        ops in prop::collection::vec(delta_op(), 0..8),
+        digest in digest(),
+    ) {
+        // The writer sets DATA_FLAG_DIGEST exactly when a digest is sent
+        let flags = if digest.is_empty() {
+            flags & !DATA_FLAG_DIGEST
+        } else {
+            flags | DATA_FLAG_DIGEST
+        };
+        assert_roundtrip!(DeltaData, MessageType::DeltaData, DeltaData { index, flags, ops, digest });
+    }
+
+    #[test]
+    fn prop_arbitrary_payloads_never_panic(payload in prop::collection::vec(any::<u8>(), 0..512)) {
+        block_on(async {
+            let _ = Hello::from_reader(&mut Cursor::new(&payload)).await;
+            let _ = FileList::from_reader(&mut Cursor::new(&payload)).await;
+            let _ = FileListAck::from_reader(&mut Cursor::new(&payload)).await;
+            let _ = FileData::from_reader(&mut Cursor::new(&payload)).await;
+            let _ = FileDone::from_reader(&mut Cursor::new(&payload)).await;
+            let _ = MkdirBatch::from_reader(&mut Cursor::new(&payload)).await;
+            let _ = MkdirBatchAck::from_reader(&mut Cursor::new(&payload)).await;
+            let _ = SymlinkBatch::from_reader(&mut Cursor::new(&payload)).await;
+            let _ = SymlinkBatchAck::from_reader(&mut Cursor::new(&payload)).await;
+            let _ = ErrorMessage::from_reader(&mut Cursor::new(&payload)).await;
+            let _ = ChecksumReq::from_reader(&mut Cursor::new(&payload)).await;
+            let _ = ChecksumResp::from_reader(&mut Cursor::new(&payload)).await;
+            let _ = DeltaData::from_reader(&mut Cursor::new(&payload)).await;
+        });
+    }
+}
+
+#[test]
+fn test_oversized_frames_refused_before_reading() {
+    // Only the header arrives: the length alone must be enough to refuse
+    for (kind, len) in [
+        (MessageType::ChecksumReq, 9),
+        (MessageType::FileDone, 4 + 1 + 4 + MAX_DIGEST_LEN as u32 + 1),
+        (MessageType::Hello, u32::MAX),
+    ] {
+        let mut header = len.to_be_bytes().to_vec();
+        header.push(kind as u8);
+        let mut cursor = Cursor::new(&header);
+        let frame = block_on(Frame::read(&mut cursor)).unwrap();
+        let err = frame
+            .payload(&mut cursor, kind.max_len())
+            .map(|_| ())
+            .unwrap_err();
+        assert!(matches!(err, ProtocolError::TooLarge { .. }), "{:?}", err);
+    }
+}
+
+#[test]
+fn test_ping_must_be_empty() {
+    let frame = Frame {
+        len: 0,
+        msg_type: MSG_PING,
+    };
+    assert!(frame.expect_empty().is_ok());
+    let frame = Frame {
+        len: 4,
+        msg_type: MSG_PING,
+    };
+    assert!(matches!(
+        frame.expect_empty(),
+        Err(ProtocolError::TrailingBytes { extra: 4, .. })
+    ));
+}
@@ -0,0 +1,416 @@
+//! Hostile protocol streams replayed against the server-side receiver
+//!
+//! Each test encodes a sequence of client messages, feeds them through
+//! `ServerHandler` the way `sy --server` does, and checks that nothing was
+//! written outside the session root and that the session kept going. The
+//! pull side's receiver gets the same treatment with a queued stream.
+
+#![cfg(unix)]
+
+use std::fs;
+use std::os::unix::fs::symlink;
+use std::path::Path;
+use sy::server::confine::Confinement;
+use sy::server::handler::ServerHandler;
+use sy::server::partial::StalePartials;
+use sy::server::protocol::*;
+use sy::transport::server::PullSession;
+use tempfile::TempDir;
+
+/// A reply read back from the handler's output stream
+#[derive(Debug)]
+enum Reply {
+    FileListAck,
+    FileDone(FileDone),
+    MkdirAck(MkdirBatchAck),
+    SymlinkAck(SymlinkBatchAck),
+    Checksums(ChecksumResp),
+}
+
+/// Replay an encoded client stream through `handler` and decode its replies
+async fn replay(handler: &mut ServerHandler, stream: Vec<u8>) -> anyhow::Result<Vec<Reply>> {
+    use tokio::io::AsyncReadExt;
+
+    let mut input = std::io::Cursor::new(stream);
+    let mut output = Vec::new();
+    while let Ok(_len) = input.read_u32().await {
+        let type_byte = input.read_u8().await?;
+        match MessageType::from_u8(type_byte) {
+            Some(MessageType::FileList) => {
+                let list = FileList::read(&mut input).await?;
+                handler.handle_file_list(list, &mut output).await?;
+            }
+            Some(MessageType::MkdirBatch) => {
+                let batch = MkdirBatch::read(&mut input).await?;
+                handler.handle_mkdir_batch(batch, &mut output).await?;
+            }
+            Some(MessageType::SymlinkBatch) => {
+                let batch = SymlinkBatch::read(&mut input).await?;
+                handler.handle_symlink_batch(batch, &mut output).await?;
+            }
+            Some(MessageType::FileData) => {
+                let data = FileData::read(&mut input).await?;
+                handler.handle_file_data(data, &mut output).await?;
+            }
+            Some(MessageType::ChecksumReq) => {
+                let req = ChecksumReq::read(&mut input).await?;
+                handler.handle_checksum_req(req, &mut output).await?;
+            }
+            Some(MessageType::DeltaData) => {
+                let delta = DeltaData::read(&mut input).await?;
+                handler.handle_delta_data(delta, &mut output).await?;
+            }
+            other => anyhow::bail!("unexpected message {:?}", other),
+        }
+    }
+
+    let mut replies = Vec::new();
+    let mut cursor = std::io::Cursor::new(output);
+    while let Ok(_len) = cursor.read_u32().await {
+        let type_byte = cursor.read_u8().await?;
+        replies.push(match MessageType::from_u8(type_byte) {
+            Some(MessageType::FileListAck) => {
+                FileListAck::read(&mut cursor).await?;
+                Reply::FileListAck
+            }
+            Some(MessageType::FileDone) => Reply::FileDone(FileDone::read(&mut cursor).await?),
+            Some(MessageType::MkdirBatchAck) => {
+                Reply::MkdirAck(MkdirBatchAck::read(&mut cursor).await?)
+            }
+            Some(MessageType::SymlinkBatchAck) => {
+                Reply::SymlinkAck(SymlinkBatchAck::read(&mut cursor).await?)
+            }
+            Some(MessageType::ChecksumResp) => {
+                Reply::Checksums(ChecksumResp::read(&mut cursor).await?)
+            }
+            other => anyhow::bail!("unexpected reply {:?}", other),
+        });
+    }
+    Ok(replies)
+}
+
+fn file(path: &str, size: u64) -> FileListEntry {
+    FileListEntry {
+        path: path.to_string(),
+        size,
+        mtime: 1_700_000_000,
+        mode: 0o644,
+        flags: 0,
+        symlink_target: None,
+        hardlink_to: None,
+        meta: None,
+    }
+}
+
+fn data(index: u32, bytes: &[u8]) -> FileData {
+    FileData {
+        index,
+        offset: 0,
+        flags: DATA_FLAG_FINAL,
+        data: bytes.to_vec(),
+    }
+}
+
+/// Statuses of all FILE_DONE replies, in order
+fn statuses(replies: &[Reply]) -> Vec<u8> {
+    replies
+        .iter()
+        .filter_map(|r| match r {
+            Reply::FileDone(done) => Some(done.status),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Session root inside `temp`, next to an `outside` directory it must not touch
+fn layout(temp: &TempDir) -> (std::path::PathBuf, std::path::PathBuf) {
+    let root = temp.path().join("root");
+    let outside = temp.path().join("outside");
+    fs::create_dir(&root).unwrap();
+    fs::create_dir(&outside).unwrap();
+    (root, outside)
+}
+
+fn assert_untouched(outside: &Path, expected: &[&str]) {
+    let mut names: Vec<String> = fs::read_dir(outside)
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    assert_eq!(names, expected, "files appeared outside the root");
+}
+
+#[tokio::test]
+async fn test_parent_and_absolute_paths_are_refused() -> anyhow::Result<()> {
+    let temp = TempDir::new()?;
+    let (root, outside) = layout(&temp);
+    let absolute = outside.join("absolute.txt");
+
+    let mut stream = Vec::new();
+    FileList {
+        entries: vec![
+            file("../outside/dotdot.txt", 4),
+            file(absolute.to_str().unwrap(), 4),
+            file("sub/../../outside/sneaky.txt", 4),
+            file("ok.txt", 4),
+        ],
+    }
+    .write(&mut stream)
+    .await?;
+    MkdirBatch {
+        paths: vec![
+            "../outside/dir".to_string(),
+            outside.join("absdir").to_string_lossy().into_owned(),
+            "fine/dir".to_string(),
+        ],
+    }
+    .write(&mut stream)
+    .await?;
+    for index in 0..4 {
+        data(index, b"evil").write(&mut stream).await?;
+    }
+
+    let mut handler = ServerHandler::new(root.clone());
+    let replies = replay(&mut handler, stream).await?;
+
+    let mkdir = replies.iter().find_map(|r| match r {
+        Reply::MkdirAck(ack) => Some(ack),
+        _ => None,
+    });
+    let mkdir = mkdir.expect("mkdir ack");
+    assert_eq!(mkdir.created, 1);
+    assert_eq!(mkdir.failed.len(), 2);
+
+    assert_eq!(
+        statuses(&replies),
+        vec![
+            STATUS_WRITE_ERROR,
+            STATUS_WRITE_ERROR,
+            STATUS_WRITE_ERROR,
+            STATUS_OK
+        ]
+    );
+    assert_eq!(fs::read(root.join("ok.txt"))?, b"evil");
+    assert!(root.join("fine/dir").is_dir());
+    assert_untouched(&outside, &[]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_writes_do_not_follow_planted_symlinks() -> anyhow::Result<()> {
+    let temp = TempDir::new()?;
+    let (root, outside) = layout(&temp);
+    fs::write(outside.join("victim"), b"original")?;
+
+    // The stream plants the links itself, then writes through them
+    let mut stream = Vec::new();
+    SymlinkBatch {
+        entries: vec![
+            SymlinkEntry {
+                path: "dir_link".to_string(),
+                target: outside.to_string_lossy().into_owned(),
+            },
+            SymlinkEntry {
+                path: "file_link".to_string(),
+                target: outside.join("victim").to_string_lossy().into_owned(),
+            },
+        ],
+    }
+    .write(&mut stream)
+    .await?;
+    FileList {
+        entries: vec![file("dir_link/planted", 4), file("file_link", 4)],
+    }
+    .write(&mut stream)
+    .await?;
+    MkdirBatch {
+        paths: vec!["dir_link/newdir".to_string()],
+    }
+    .write(&mut stream)
+    .await?;
+    data(0, b"evil").write(&mut stream).await?;
+    data(1, b"evil").write(&mut stream).await?;
+
+    let mut handler = ServerHandler::new(root.clone());
+    let replies = replay(&mut handler, stream).await?;
+
+    assert_eq!(statuses(&replies), vec![STATUS_WRITE_ERROR, STATUS_OK]);
+    assert!(replies
+        .iter()
+        .any(|r| matches!(r, Reply::MkdirAck(ack) if ack.created == 0 && ack.failed.len() == 1)));
+
+    // The link at the final component was replaced, not written through
+    assert!(!fs::symlink_metadata(root.join("file_link"))?.is_symlink());
+    assert_eq!(fs::read(root.join("file_link"))?, b"evil");
+    assert_eq!(fs::read(outside.join("victim"))?, b"original");
+    assert_untouched(&outside, &["victim"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_delta_and_checksums_do_not_follow_symlinks() -> anyhow::Result<()> {
+    let temp = TempDir::new()?;
+    let (root, outside) = layout(&temp);
+    fs::write(outside.join("secret"), b"top secret contents")?;
+    symlink(outside.join("secret"), root.join("basis"))?;
+    symlink(&outside, root.join("dir_link"))?;
+
+    let mut stream = Vec::new();
+    FileList {
+        entries: vec![file("basis", 6), file("dir_link/secret", 6)],
+    }
+    .write(&mut stream)
+    .await?;
+    for index in 0..2 {
+        ChecksumReq::block(index, 4).write(&mut stream).await?;
+    }
+    // Copy ops would read the secret through the link if it were followed
+    DeltaData {
+        index: 0,
+        flags: 0,
+        ops: vec![DeltaOp::Copy { offset: 0, size: 6 }],
+        digest: Vec::new(),
+    }
+    .write(&mut stream)
+    .await?;
+    DeltaData {
+        index: 1,
+        flags: 0,
+        ops: vec![DeltaOp::Data(b"pwned!".to_vec())],
+        digest: Vec::new(),
+    }
+    .write(&mut stream)
+    .await?;
+    DeltaData {
+        index: 0,
+        flags: 0,
+        ops: vec![DeltaOp::Data(b"honest".to_vec())],
+        digest: Vec::new(),
+    }
+    .write(&mut stream)
+    .await?;
+
+    let mut handler = ServerHandler::new(root.clone());
+    let replies = replay(&mut handler, stream).await?;
+
+    for reply in &replies {
+        if let Reply::Checksums(resp) = reply {
+            assert!(
+                resp.checksums.is_empty(),
+                "checksums leaked a file outside the root"
+            );
+            assert_eq!(resp.file_size, 0);
+        }
+    }
+    assert_eq!(
+        statuses(&replies),
+        vec![STATUS_WRITE_ERROR, STATUS_WRITE_ERROR, STATUS_OK]
+    );
+    assert_eq!(fs::read(root.join("basis"))?, b"honest");
+    assert_eq!(fs::read(outside.join("secret"))?, b"top secret contents");
+    assert_untouched(&outside, &["secret"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_safe_links_refuses_escaping_targets() -> anyhow::Result<()> {
+    let temp = TempDir::new()?;
+    let (root, outside) = layout(&temp);
+
+    let mut stream = Vec::new();
+    SymlinkBatch {
+        entries: vec![
+            SymlinkEntry {
+                path: "absolute".to_string(),
+                target: "/etc/passwd".to_string(),
+            },
+            SymlinkEntry {
+                path: "climbing".to_string(),
+                target: "../outside".to_string(),
+            },
+            SymlinkEntry {
+                path: "nested/ok".to_string(),
+                target: "../sibling".to_string(),
+            },
+        ],
+    }
+    .write(&mut stream)
+    .await?;
+    // The legacy path (symlinks sent as FILE_DATA) gets the same policy
+    FileList {
+        entries: vec![FileListEntry {
+            flags: FLAG_IS_SYMLINK,
+            symlink_target: Some("../../etc".to_string()),
+            ..file("legacy", 0)
+        }],
+    }
+    .write(&mut stream)
+    .await?;
+    data(0, b"").write(&mut stream).await?;
+
+    let mut handler = ServerHandler::new(root.clone()).with_safe_links(true);
+    let replies = replay(&mut handler, stream).await?;
+
+    let ack = replies.iter().find_map(|r| match r {
+        Reply::SymlinkAck(ack) => Some(ack),
+        _ => None,
+    });
+    let ack = ack.expect("symlink ack");
+    assert_eq!(ack.created, 1);
+    let failed: Vec<&str> = ack.failed.iter().map(|(p, _)| p.as_str()).collect();
+    assert_eq!(failed, vec!["absolute", "climbing"]);
+    assert_eq!(statuses(&replies), vec![STATUS_WRITE_ERROR]);
+
+    assert!(fs::symlink_metadata(root.join("nested/ok"))?.is_symlink());
+    assert!(fs::symlink_metadata(root.join("absolute")).is_err());
+    assert!(fs::symlink_metadata(root.join("legacy")).is_err());
+    assert_untouched(&outside, &[]);
+    Ok(())
+}
+
+/// A pull stream whose FILE_DATA is queued up front
+#[derive(Default)]
+struct QueuedPull(std::collections::VecDeque<FileData>, StalePartials);
+
+#[async_trait::async_trait]
+impl PullSession for QueuedPull {
+    async fn read_file_data(&mut self) -> anyhow::Result<Option<FileData>> {
+        Ok(self.0.pop_front())
+    }
+
+    fn negotiated(&self) -> Negotiated {
+        Negotiated {
+            version: PROTOCOL_VERSION,
+            features: 0,
+        }
+    }
+
+    fn stale_partials(&mut self) -> &mut StalePartials {
+        &mut self.1
+    }
+}
+
+#[tokio::test]
+async fn test_pull_receiver_stays_in_destination() -> anyhow::Result<()> {
+    let temp = TempDir::new()?;
+    let (root, outside) = layout(&temp);
+    symlink(&outside, root.join("link"))?;
+    let confine = Confinement::new(&root);
+    let mut session = QueuedPull::default();
+
+    let absolute = outside.join("absolute").to_string_lossy().into_owned();
+    for path in ["../escape", absolute.as_str(), "link/planted"] {
+        let received = session
+            .receive_file(&confine, path, &file(path, 4), data(0, b"evil"))
+            .await?;
+        assert!(received.is_err(), "{} was written", path);
+    }
+    assert_untouched(&outside, &[]);
+
+    let received = session
+        .receive_file(&confine, "dir/ok", &file("dir/ok", 4), data(0, b"good"))
+        .await??;
+    assert_eq!(received.path, root.join("dir/ok"));
+    assert_eq!(fs::read(root.join("dir/ok"))?, b"good");
+    Ok(())
+}
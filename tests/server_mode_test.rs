@@ -2,7 +2,13 @@
 mod tests {
     use std::fs;
     use sy::path::SyncPath;
-    use sy::sync::server_mode::{sync_pull_server_mode, sync_server_mode};
+    use sy::server::meta::MetaOptions;
+    use sy::sync::server_mode::TransferOptions;
+    use sy::sync::server_mode::{
+        sync_pull_server_mode, sync_pull_server_mode_with_rsh, sync_server_mode,
+        sync_server_mode_with_rsh,
+    };
+    use sy::transport::server::RemoteShell;
     use tempfile::TempDir;
 
     #[tokio::test]
@@ -54,7 +60,15 @@ mod tests {
         let new_path = format!("{}:{}", sy_bin.parent().unwrap().display(), path_env);
         std::env::set_var("PATH", new_path);
 
-        sync_server_mode(&source, &dest_sync_path, false, None).await?;
+        sync_server_mode(
+            &source,
+            &dest_sync_path,
+            false,
+            MetaOptions::default(),
+            TransferOptions::default(),
+            None,
+        )
+        .await?;
 
         // Verify
         assert!(dest.join("file1.txt").exists());
@@ -107,7 +121,15 @@ mod tests {
             has_trailing_slash: false,
         };
 
-        sync_pull_server_mode(&source_sync_path, &dest, false, None).await?;
+        sync_pull_server_mode(
+            &source_sync_path,
+            &dest,
+            false,
+            MetaOptions::default(),
+            TransferOptions::default(),
+            None,
+        )
+        .await?;
 
         // Verify
         assert!(dest.join("file1.txt").exists());
@@ -128,4 +150,123 @@ mod tests {
 
         Ok(())
     }
+
+    /// Write a fake remote shell that runs the local sy binary, recording the
+    /// host it was given (args: <host> sy --server <path>)
+    #[cfg(unix)]
+    fn fake_rsh(dir: &std::path::Path) -> anyhow::Result<Option<std::path::PathBuf>> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let sy_bin = std::env::current_exe()?
+            .parent()
+            .unwrap()
+            .parent()
+            .unwrap()
+            .join("sy");
+        if !sy_bin.exists() {
+            eprintln!("Skipping test: sy binary not found at {}", sy_bin.display());
+            return Ok(None);
+        }
+
+        let script = dir.join("fake-rsh");
+        fs::write(
+            &script,
+            format!(
+                "#!/bin/sh\necho \"$1\" > \"{}\"\nshift 2\nexec \"{}\" \"$@\"\n",
+                dir.join("host").display(),
+                sy_bin.display()
+            ),
+        )?;
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755))?;
+        Ok(Some(script))
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_server_mode_push_via_rsh() -> anyhow::Result<()> {
+        let temp = TempDir::new()?;
+        let source = temp.path().join("src");
+        let dest = temp.path().join("dest");
+        fs::create_dir_all(source.join("subdir"))?;
+        fs::write(source.join("file1.txt"), "Over a remote shell")?;
+        fs::write(source.join("subdir/file2.txt"), "Nested")?;
+
+        let Some(script) = fake_rsh(temp.path())? else {
+            return Ok(());
+        };
+        let rsh = RemoteShell::parse(&format!("'{}'", script.display()))?;
+
+        let dest_sync_path = SyncPath::Remote {
+            host: "web-container".to_string(),
+            user: None,
+            path: dest.clone(),
+            has_trailing_slash: false,
+        };
+        let stats = sync_server_mode_with_rsh(
+            &source,
+            &dest_sync_path,
+            false,
+            MetaOptions::default(),
+            TransferOptions::default(),
+            None,
+            &rsh,
+        )
+        .await?;
+
+        assert_eq!(stats.files_created, 2);
+        assert_eq!(
+            fs::read_to_string(dest.join("file1.txt"))?,
+            "Over a remote shell"
+        );
+        assert_eq!(fs::read_to_string(dest.join("subdir/file2.txt"))?, "Nested");
+        assert_eq!(
+            fs::read_to_string(temp.path().join("host"))?.trim(),
+            "web-container"
+        );
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_server_mode_pull_via_rsh() -> anyhow::Result<()> {
+        let temp = TempDir::new()?;
+        let source = temp.path().join("src");
+        let dest = temp.path().join("dest");
+        fs::create_dir_all(&source)?;
+        fs::write(source.join("file1.txt"), "Pulled over a remote shell")?;
+
+        let Some(script) = fake_rsh(temp.path())? else {
+            return Ok(());
+        };
+        let rsh = RemoteShell::parse(&format!("'{}'", script.display()))?;
+
+        let source_sync_path = SyncPath::Remote {
+            host: "db-pod".to_string(),
+            user: None,
+            path: source.clone(),
+            has_trailing_slash: false,
+        };
+        sync_pull_server_mode_with_rsh(
+            &source_sync_path,
+            &dest,
+            false,
+            MetaOptions::default(),
+            TransferOptions::default(),
+            None,
+            &rsh,
+        )
+        .await?;
+
+        assert_eq!(
+            fs::read_to_string(dest.join("file1.txt"))?,
+            "Pulled over a remote shell"
+        );
+        assert_eq!(
+            fs::read_to_string(temp.path().join("host"))?.trim(),
+            "db-pod"
+        );
+
+        Ok(())
+    }
 }